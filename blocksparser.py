@@ -16,6 +16,8 @@ with open(".etc/blocks.json") as f:
                 if "id" in state:
                     block_id = state["id"]
                     out[block_id] = {"name": block, "properties": props}
+                    if state.get("default"):
+                        out[block_id]["default"] = True
             else:
                 block_id = state["id"]
                 out[block_id] = {"name": block, "default": True}
@@ -0,0 +1,79 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use tokio::runtime::Runtime;
+
+use ferrumc::ecs::component::ComponentStorage;
+use ferrumc::utils::encoding::position::Position;
+use ferrumc::utils::encoding::velocity::Velocity;
+
+/// A storage with `entities` positions, and velocities for every other one. Roughly what the
+/// movement systems see, where only some entities have what they look for.
+fn populate(entities: usize) -> ComponentStorage {
+    let storage = ComponentStorage::new();
+    for entity_id in 0..entities {
+        storage.insert(entity_id, Position { x: 1, y: 2, z: 3 });
+        if entity_id % 2 == 0 {
+            storage.insert(entity_id, Velocity { x: 1, y: 0, z: 0 });
+        }
+    }
+    storage
+}
+
+fn benchmark_queries(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("ecs_query");
+
+    for entities in [100, 1_000, 10_000] {
+        let storage = populate(entities);
+
+        group.bench_with_input(BenchmarkId::new("query", entities), &entities, |b, _| {
+            b.iter(|| {
+                runtime.block_on(async {
+                    for (_, (position, velocity)) in storage.query::<(&Position, &Velocity)>().await
+                    {
+                        black_box((position.x, velocity.x));
+                    }
+                })
+            })
+        });
+
+        // What systems did before, asking for every entity id there could be.
+        group.bench_with_input(
+            BenchmarkId::new("naive_get", entities),
+            &entities,
+            |b, &entities| {
+                b.iter(|| {
+                    runtime.block_on(async {
+                        for entity_id in 0..entities {
+                            let Ok(position) = storage.get::<Position>(entity_id).await else {
+                                continue;
+                            };
+                            let Ok(velocity) = storage.get::<Velocity>(entity_id).await else {
+                                continue;
+                            };
+                            black_box((position.x, velocity.x));
+                        }
+                    })
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("query_mut", entities),
+            &entities,
+            |b, _| {
+                b.iter(|| {
+                    runtime.block_on(async {
+                        for (_, mut position) in storage.query_mut::<Position>().await {
+                            position.y = black_box(position.y);
+                        }
+                    })
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_queries);
+criterion_main!(benches);
@@ -0,0 +1,31 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use ferrumc::world::generation::terrain::NoiseGenerator;
+use ferrumc::world::generation::ChunkGenerator;
+
+/// Generating terrain, one chunk at a time and for the whole spawn area at once.
+fn benchmark_terrain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("terrain");
+    let generator = NoiseGenerator::new(0);
+
+    group.bench_function("chunk", |b| {
+        b.iter(|| black_box(generator.generate(black_box(3), black_box(-2))))
+    });
+
+    // A 10 chunk radius around spawn, like the spawn area is loaded at.
+    group.sample_size(10);
+    group.bench_function("spawn_area", |b| {
+        b.iter(|| {
+            for x in -10..=10 {
+                for z in -10..=10 {
+                    black_box(generator.generate(x, z));
+                }
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_terrain);
+criterion_main!(benches);
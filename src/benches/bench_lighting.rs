@@ -0,0 +1,45 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+use ferrumc::world::conversions::default_block_state;
+use ferrumc::world::generation::superflat::SuperflatGenerator;
+use ferrumc::world::generation::terrain::NoiseGenerator;
+use ferrumc::world::generation::ChunkGenerator;
+
+/// Lighting freshly generated chunks from scratch, and relighting them after a block that lets a
+/// different amount of light through is placed.
+fn benchmark_sky_light(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sky_light");
+    let stone = default_block_state("minecraft:stone").unwrap();
+
+    let chunks = [
+        (
+            "superflat",
+            SuperflatGenerator::from_layers("bedrock,2xdirt,grass_block")
+                .unwrap()
+                .generate(0, 0),
+        ),
+        ("terrain", NoiseGenerator::new(0).generate(3, -2)),
+    ];
+    for (name, chunk) in chunks {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || chunk.clone(),
+                |mut chunk| black_box(chunk.compute_sky_light().unwrap()),
+                BatchSize::SmallInput,
+            )
+        });
+        // A roof over the ground, which shades the column under it.
+        group.bench_function(format!("{}/block", name), |b| {
+            b.iter_batched(
+                || chunk.clone(),
+                |mut chunk| black_box(chunk.set_block(8, 100, 8, stone.clone()).unwrap()),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_sky_light);
+criterion_main!(benches);
@@ -0,0 +1,93 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use ferrumc::utils::encoding::position::Position;
+use ferrumc::utils::spatial_index::SpatialIndex;
+
+const ENTITIES: usize = 500;
+const VIEW_DISTANCE: i32 = 10;
+
+/// Players spread over a 1024 block square around spawn, the same ones in the index and in a
+/// list for scanning.
+fn populate() -> (SpatialIndex, Vec<(usize, Position)>) {
+    let index = SpatialIndex::new();
+    let mut players = Vec::with_capacity(ENTITIES);
+    for id in 0..ENTITIES {
+        let position = Position::new(
+            (id * 7_919 % 1_024) as i32 - 512,
+            64,
+            (id * 6_151 % 1_024) as i32 - 512,
+        );
+        index.insert(id, &position, VIEW_DISTANCE);
+        players.push((id, position));
+    }
+    (index, players)
+}
+
+fn benchmark_spatial_index(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spatial_index");
+    let (index, players) = populate();
+    let center = Position::new(10, 64, -20);
+
+    // What a sound at full volume needs.
+    group.bench_function("radius/index", |b| {
+        b.iter(|| black_box(index.players_in_radius(black_box(&center), 16.0)))
+    });
+    group.bench_function("radius/scan", |b| {
+        b.iter(|| {
+            let center = black_box(&center);
+            black_box(
+                players
+                    .iter()
+                    .filter(|(_, position)| {
+                        let (dx, dy, dz) = (
+                            (position.x - center.x) as f64,
+                            (position.y - center.y) as f64,
+                            (position.z - center.z) as f64,
+                        );
+                        dx * dx + dy * dy + dz * dz <= 16.0 * 16.0
+                    })
+                    .map(|(id, position)| (*id, position.clone()))
+                    .collect::<Vec<_>>(),
+            )
+        })
+    });
+
+    // What every packet sent to the players near something needs.
+    group.bench_function("viewing_chunk/index", |b| {
+        b.iter(|| black_box(index.players_viewing_chunk(black_box(0), black_box(-2))))
+    });
+    group.bench_function("viewing_chunk/scan", |b| {
+        b.iter(|| {
+            let (chunk_x, chunk_z) = (black_box(0), black_box(-2));
+            black_box(
+                players
+                    .iter()
+                    .filter(|(_, position)| {
+                        let distance = ((position.x >> 4) - chunk_x)
+                            .abs()
+                            .max(((position.z >> 4) - chunk_z).abs());
+                        distance <= VIEW_DISTANCE
+                    })
+                    .map(|(id, _)| *id)
+                    .collect::<Vec<_>>(),
+            )
+        })
+    });
+
+    // Players moving about, which the index has to keep up with.
+    group.bench_function("update", |b| {
+        let mut tick = 0;
+        b.iter(|| {
+            tick += 1;
+            for (id, position) in &players {
+                let moved = Position::new(position.x + tick % 32, position.y, position.z);
+                index.update(*id, black_box(&moved));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_spatial_index);
+criterion_main!(benches);
@@ -0,0 +1,167 @@
+use crate::commands::graph::{ArgumentParser, CommandArgument};
+use crate::commands::{CommandContext, CommandDispatcher, CommandSender};
+use crate::utils::components::keep_alive::KeepAlive;
+use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
+
+/// Registers the commands that come with the server.
+pub fn register_builtin_commands(dispatcher: &CommandDispatcher) {
+    dispatcher.register("help", "Lists the commands", help);
+    dispatcher.register("list", "Lists the players that are online", list);
+    dispatcher.register("ping", "Shows your latency to the server", ping);
+    dispatcher.register("tps", "Shows how fast the server is ticking", tps);
+    dispatcher.register_with_arguments(
+        "xp",
+        "Gives a player experience, or takes it away",
+        vec![
+            CommandArgument::required(
+                "amount",
+                ArgumentParser::Integer {
+                    min: None,
+                    max: None,
+                },
+            ),
+            CommandArgument::required("player", ArgumentParser::Player),
+        ],
+        xp,
+    );
+}
+
+async fn help(ctx: CommandContext) -> Result<String> {
+    let lines = ctx
+        .state
+        .commands
+        .commands()
+        .into_iter()
+        .map(|(name, description)| format!("/{} - {}", name, description))
+        .collect::<Vec<_>>();
+    Ok(lines.join("\n"))
+}
+
+async fn list(ctx: CommandContext) -> Result<String> {
+    let players = ctx
+        .state
+        .world
+        .query::<&Player>()
+        .iter()
+        .await
+        .map(|(_, player)| player.username.clone())
+        .collect::<Vec<_>>();
+    Ok(format!(
+        "There are {} players online: {}",
+        players.len(),
+        players.join(", ")
+    ))
+}
+
+async fn ping(ctx: CommandContext) -> Result<String> {
+    let CommandSender::Player(entity) = ctx.sender else {
+        return Ok("Only players have a ping".to_string());
+    };
+    let keep_alive = ctx.state.world.get_component::<KeepAlive>(entity).await?;
+    Ok(format!("Your ping is {}ms", keep_alive.ping_ms))
+}
+
+/// How many of the slowest systems /tps lists.
+const TPS_SYSTEMS_LISTED: usize = 5;
+
+async fn tps(ctx: CommandContext) -> Result<String> {
+    let tick_rate = &ctx.state.tick_rate;
+    let mut lines = vec![format!(
+        "TPS: {:.1} of {:.0}, MSPT: {:.2}ms",
+        tick_rate.ticks_per_second(),
+        ctx.state.scheduler.target_ticks_per_second(),
+        tick_rate.milliseconds_per_tick()
+    )];
+    for (name, timings) in ctx
+        .state
+        .scheduler
+        .timings()
+        .into_iter()
+        .take(TPS_SYSTEMS_LISTED)
+    {
+        lines.push(format!(
+            "{}: {:.2}ms on average, {:.2}ms at most",
+            name,
+            timings.average().as_secs_f64() * 1000.0,
+            timings.max.as_secs_f64() * 1000.0
+        ));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Only for admins, so players can't run it.
+async fn xp(ctx: CommandContext) -> Result<String> {
+    if ctx.sender != CommandSender::Console {
+        return Ok("You don't have permission to use /xp".to_string());
+    }
+    let (Some(Ok(amount)), Some(username)) = (
+        ctx.args.first().map(|amount| amount.parse::<i32>()),
+        ctx.args.get(1),
+    ) else {
+        return Ok("Usage: /xp <amount> <player>".to_string());
+    };
+    let player = ctx
+        .state
+        .world
+        .query::<&Player>()
+        .iter()
+        .await
+        .find(|(_, player)| player.username.eq_ignore_ascii_case(username))
+        .map(|(entity, _)| entity);
+    let Some(entity) = player else {
+        return Ok(format!("{} isn't online", username));
+    };
+    ctx.state.give_experience(entity, amount).await?;
+    Ok(format!("Gave {} experience", amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use crate::commands::CommandSender;
+    use crate::create_state;
+
+    #[tokio::test]
+    async fn test_help_lists_commands() {
+        let state = create_state(vec![TcpListener::bind("127.0.0.1:0").await.unwrap()])
+            .await
+            .unwrap();
+        let help = state.execute_command("help").await;
+        assert!(help.contains("/help - Lists the commands"));
+        assert!(help.contains("/ping - "));
+        assert_eq!(
+            state.execute_command("ping").await,
+            "Only players have a ping"
+        );
+        // Nothing's ticked yet.
+        assert!(state
+            .execute_command("tps")
+            .await
+            .starts_with("TPS: 0.0 of 20, MSPT: 0.00ms"));
+    }
+
+    #[tokio::test]
+    async fn test_only_admins_can_give_experience() {
+        let state = create_state(vec![TcpListener::bind("127.0.0.1:0").await.unwrap()])
+            .await
+            .unwrap();
+        let output = state
+            .commands
+            .dispatch(state.clone(), CommandSender::Player(1), "xp 100")
+            .await;
+        assert_eq!(
+            output.unwrap().unwrap(),
+            "You don't have permission to use /xp"
+        );
+        assert_eq!(
+            state.execute_command("xp 100").await,
+            "Usage: /xp <amount> <player>"
+        );
+        assert_eq!(
+            state.execute_command("xp 100 Notch").await,
+            "Notch isn't online"
+        );
+    }
+}
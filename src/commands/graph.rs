@@ -0,0 +1,494 @@
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::utils::error::Error;
+use crate::utils::impls::packet_impls::NetDecode;
+
+const NODE_ROOT: u8 = 0;
+const NODE_LITERAL: u8 = 1;
+const NODE_ARGUMENT: u8 = 2;
+const NODE_TYPE_MASK: u8 = 0x03;
+const FLAG_EXECUTABLE: u8 = 0x04;
+const FLAG_HAS_REDIRECT: u8 = 0x08;
+const FLAG_HAS_SUGGESTIONS: u8 = 0x10;
+
+// Ids in the argument type registry.
+const PARSER_INTEGER: i32 = 3;
+const PARSER_STRING: i32 = 5;
+const PARSER_ENTITY: i32 = 6;
+
+const INTEGER_HAS_MIN: u8 = 0x01;
+const INTEGER_HAS_MAX: u8 = 0x02;
+const STRING_SINGLE_WORD: i32 = 0;
+const STRING_GREEDY_PHRASE: i32 = 2;
+const ENTITY_SINGLE: u8 = 0x01;
+const ENTITY_PLAYERS_ONLY: u8 = 0x02;
+
+/// Makes the client send a Command Suggestions Request while the argument is being typed.
+pub const ASK_SERVER: &str = "minecraft:ask_server";
+
+/// How the client parses and checks an argument, and what it suggests for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgumentParser {
+    /// One word, without spaces.
+    Word,
+    /// Everything left of the command, spaces and all.
+    GreedyString,
+    Integer {
+        min: Option<i32>,
+        max: Option<i32>,
+    },
+    /// One player, by name. Online players are suggested by the server.
+    Player,
+}
+
+impl ArgumentParser {
+    fn id(&self) -> i32 {
+        match self {
+            ArgumentParser::Word | ArgumentParser::GreedyString => PARSER_STRING,
+            ArgumentParser::Integer { .. } => PARSER_INTEGER,
+            ArgumentParser::Player => PARSER_ENTITY,
+        }
+    }
+
+    /// The suggestions type the client is told to use for the argument.
+    fn suggestions(&self) -> Option<String> {
+        match self {
+            ArgumentParser::Player => Some(ASK_SERVER.to_string()),
+            _ => None,
+        }
+    }
+
+    async fn encode<T: AsyncWrite + Unpin>(
+        &self,
+        bytes: &mut T,
+    ) -> Result<(), ferrumc_codec::CodecError> {
+        VarInt::from(self.id()).net_encode(bytes).await?;
+        match self {
+            ArgumentParser::Word => VarInt::from(STRING_SINGLE_WORD).net_encode(bytes).await?,
+            ArgumentParser::GreedyString => {
+                VarInt::from(STRING_GREEDY_PHRASE).net_encode(bytes).await?
+            }
+            ArgumentParser::Integer { min, max } => {
+                let mut flags = 0;
+                if min.is_some() {
+                    flags |= INTEGER_HAS_MIN;
+                }
+                if max.is_some() {
+                    flags |= INTEGER_HAS_MAX;
+                }
+                bytes.write_u8(flags).await?;
+                min.net_encode(bytes).await?;
+                max.net_encode(bytes).await?;
+            }
+            ArgumentParser::Player => bytes.write_u8(ENTITY_SINGLE | ENTITY_PLAYERS_ONLY).await?,
+        }
+        Ok(())
+    }
+
+    async fn decode<T: AsyncRead + Unpin>(bytes: &mut T) -> Result<Self, Error> {
+        let id = VarInt::read(bytes).await?.get_val();
+        Ok(match id {
+            PARSER_STRING => match VarInt::read(bytes).await?.get_val() {
+                STRING_SINGLE_WORD => ArgumentParser::Word,
+                STRING_GREEDY_PHRASE => ArgumentParser::GreedyString,
+                behavior => {
+                    return Err(Error::DeserializationError(format!(
+                        "Unsupported string argument behavior {}",
+                        behavior
+                    )))
+                }
+            },
+            PARSER_INTEGER => {
+                let flags = bytes.read_u8().await?;
+                let min = match flags & INTEGER_HAS_MIN {
+                    0 => None,
+                    _ => Some(bytes.read_i32().await?),
+                };
+                let max = match flags & INTEGER_HAS_MAX {
+                    0 => None,
+                    _ => Some(bytes.read_i32().await?),
+                };
+                ArgumentParser::Integer { min, max }
+            }
+            PARSER_ENTITY => {
+                bytes.read_u8().await?;
+                ArgumentParser::Player
+            }
+            _ => {
+                return Err(Error::DeserializationError(format!(
+                    "Unsupported argument parser {}",
+                    id
+                )))
+            }
+        })
+    }
+}
+
+/// An argument a command takes, declared so clients can check and complete it as it's typed.
+///
+/// - `required`: Whether the command can't be run without it. Only the last arguments of a
+///   command can be optional.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandArgument {
+    pub name: String,
+    pub parser: ArgumentParser,
+    pub required: bool,
+}
+
+impl CommandArgument {
+    pub fn required(name: &str, parser: ArgumentParser) -> Self {
+        Self {
+            name: name.to_string(),
+            parser,
+            required: true,
+        }
+    }
+
+    pub fn optional(name: &str, parser: ArgumentParser) -> Self {
+        Self {
+            required: false,
+            ..Self::required(name, parser)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeKind {
+    Root,
+    /// A word that has to be typed as it is, like a command's name.
+    Literal(String),
+    Argument {
+        name: String,
+        parser: ArgumentParser,
+        suggestions: Option<String>,
+    },
+}
+
+/// One node of a [CommandGraph]. Nodes refer to each other by their index in the graph.
+///
+/// - `executable`: Whether the command can be run if it ends at this node.
+/// - `redirect`: The node to carry on from after this one, e.g. for aliases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandNode {
+    pub kind: NodeKind,
+    pub executable: bool,
+    pub children: Vec<i32>,
+    pub redirect: Option<i32>,
+}
+
+impl CommandNode {
+    fn new(kind: NodeKind, executable: bool) -> Self {
+        Self {
+            kind,
+            executable,
+            children: Vec::new(),
+            redirect: None,
+        }
+    }
+}
+
+/// Every command the client can run, as the tree it uses to check and complete commands. The
+/// nodes are sent as a flat list, with the root's index at the end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandGraph {
+    pub nodes: Vec<CommandNode>,
+    pub root: i32,
+}
+
+impl Default for CommandGraph {
+    fn default() -> Self {
+        Self {
+            nodes: vec![CommandNode::new(NodeKind::Root, false)],
+            root: 0,
+        }
+    }
+}
+
+impl CommandGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a node under `parent`, and returns its index.
+    pub fn add_child(&mut self, parent: i32, node: CommandNode) -> i32 {
+        let index = self.nodes.len() as i32;
+        self.nodes.push(node);
+        self.nodes[parent as usize].children.push(index);
+        index
+    }
+
+    /// Adds a command with its arguments, one after the other. The command can be run from the
+    /// last required argument onwards.
+    pub fn add_command(&mut self, name: &str, arguments: &[CommandArgument]) {
+        // Runnable once everything after it is optional.
+        let runnable_from = arguments
+            .iter()
+            .rposition(|argument| argument.required)
+            .map_or(0, |index| index + 1);
+
+        let literal = CommandNode::new(NodeKind::Literal(name.to_string()), runnable_from == 0);
+        let mut parent = self.add_child(self.root, literal);
+        for (index, argument) in arguments.iter().enumerate() {
+            let kind = NodeKind::Argument {
+                name: argument.name.clone(),
+                parser: argument.parser.clone(),
+                suggestions: argument.parser.suggestions(),
+            };
+            let node = CommandNode::new(kind, index + 1 >= runnable_from);
+            parent = self.add_child(parent, node);
+        }
+    }
+}
+
+impl NetEncode for CommandNode {
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        let mut flags = match &self.kind {
+            NodeKind::Root => NODE_ROOT,
+            NodeKind::Literal(_) => NODE_LITERAL,
+            NodeKind::Argument { .. } => NODE_ARGUMENT,
+        };
+        if self.executable {
+            flags |= FLAG_EXECUTABLE;
+        }
+        if self.redirect.is_some() {
+            flags |= FLAG_HAS_REDIRECT;
+        }
+        if let NodeKind::Argument {
+            suggestions: Some(_),
+            ..
+        } = &self.kind
+        {
+            flags |= FLAG_HAS_SUGGESTIONS;
+        }
+        bytes.write_u8(flags).await?;
+
+        VarInt::from(self.children.len() as i32)
+            .net_encode(bytes)
+            .await?;
+        for &child in &self.children {
+            VarInt::from(child).net_encode(bytes).await?;
+        }
+        if let Some(redirect) = self.redirect {
+            VarInt::from(redirect).net_encode(bytes).await?;
+        }
+        match &self.kind {
+            NodeKind::Root => {}
+            NodeKind::Literal(name) => name.net_encode(bytes).await?,
+            NodeKind::Argument {
+                name,
+                parser,
+                suggestions,
+            } => {
+                name.net_encode(bytes).await?;
+                parser.encode(bytes).await?;
+                if let Some(suggestions) = suggestions {
+                    suggestions.net_encode(bytes).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl NetDecode for CommandNode {
+    async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>, Error>
+    where
+        T: AsyncRead + Unpin,
+    {
+        let flags = bytes.read_u8().await?;
+        let child_count = VarInt::read(bytes).await?.get_val();
+        let mut children = Vec::new();
+        for _ in 0..child_count {
+            children.push(VarInt::read(bytes).await?.get_val());
+        }
+        let redirect = match flags & FLAG_HAS_REDIRECT {
+            0 => None,
+            _ => Some(VarInt::read(bytes).await?.get_val()),
+        };
+        let kind = match flags & NODE_TYPE_MASK {
+            NODE_ROOT => NodeKind::Root,
+            NODE_LITERAL => NodeKind::Literal(*String::net_decode(bytes).await?),
+            NODE_ARGUMENT => {
+                let name = *String::net_decode(bytes).await?;
+                let parser = ArgumentParser::decode(bytes).await?;
+                let suggestions = match flags & FLAG_HAS_SUGGESTIONS {
+                    0 => None,
+                    _ => Some(*String::net_decode(bytes).await?),
+                };
+                NodeKind::Argument {
+                    name,
+                    parser,
+                    suggestions,
+                }
+            }
+            node_type => {
+                return Err(Error::DeserializationError(format!(
+                    "Unknown command node type {}",
+                    node_type
+                )))
+            }
+        };
+        Ok(Box::new(CommandNode {
+            kind,
+            executable: flags & FLAG_EXECUTABLE != 0,
+            children,
+            redirect,
+        }))
+    }
+}
+
+impl NetEncode for CommandGraph {
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        VarInt::from(self.nodes.len() as i32)
+            .net_encode(bytes)
+            .await?;
+        for node in &self.nodes {
+            node.net_encode(bytes).await?;
+        }
+        VarInt::from(self.root).net_encode(bytes).await
+    }
+}
+
+impl NetDecode for CommandGraph {
+    /// Checks that every index points at a node, so a graph that decodes can be walked safely.
+    async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>, Error>
+    where
+        T: AsyncRead + Unpin,
+    {
+        let count = VarInt::read(bytes).await?.get_val();
+        let mut nodes = Vec::new();
+        for _ in 0..count {
+            nodes.push(*CommandNode::net_decode(bytes).await?);
+        }
+        let root = VarInt::read(bytes).await?.get_val();
+
+        let is_valid = |index: i32| (0..nodes.len() as i32).contains(&index);
+        let indices_valid = is_valid(root)
+            && nodes.iter().all(|node| {
+                node.children.iter().all(|&child| is_valid(child))
+                    && node.redirect.is_none_or(is_valid)
+            });
+        if !indices_valid {
+            return Err(Error::DeserializationError(
+                "Command node index out of range".to_string(),
+            ));
+        }
+        Ok(Box::new(CommandGraph { nodes, root }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    async fn round_trip(graph: &CommandGraph) -> CommandGraph {
+        let mut bytes = Vec::new();
+        graph.net_encode(&mut bytes).await.unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let decoded = *CommandGraph::net_decode(&mut cursor).await.unwrap();
+        // Nothing left over.
+        assert_eq!(cursor.position() as usize, cursor.get_ref().len());
+        decoded
+    }
+
+    #[tokio::test]
+    async fn test_encode_command_graph() {
+        let mut graph = CommandGraph::new();
+        graph.add_command(
+            "kick",
+            &[CommandArgument::required("player", ArgumentParser::Player)],
+        );
+        let mut bytes = Vec::new();
+        graph.net_encode(&mut bytes).await.unwrap();
+
+        let mut expected = vec![
+            0x03, // 3 nodes
+            0x00, 0x01, 0x01, // The root, with the literal as its child
+            0x01, 0x01, 0x02, 0x04, b'k', b'i', b'c', b'k', // The literal
+            0x16, 0x00, 0x06, b'p', b'l', b'a', b'y', b'e', b'r', 0x06, 0x03, // The argument
+        ];
+        expected.push(ASK_SERVER.len() as u8);
+        expected.extend_from_slice(ASK_SERVER.as_bytes());
+        expected.push(0x00); // The root's index
+        assert_eq!(bytes, expected);
+    }
+
+    #[tokio::test]
+    async fn test_command_graphs_round_trip() {
+        let mut graph = CommandGraph::new();
+        graph.add_command("list", &[]);
+        graph.add_command(
+            "give",
+            &[
+                CommandArgument::required("player", ArgumentParser::Player),
+                CommandArgument::required("item", ArgumentParser::Word),
+                CommandArgument::optional(
+                    "count",
+                    ArgumentParser::Integer {
+                        min: Some(1),
+                        max: None,
+                    },
+                ),
+            ],
+        );
+        graph.add_command(
+            "say",
+            &[CommandArgument::required(
+                "message",
+                ArgumentParser::GreedyString,
+            )],
+        );
+        let alias = CommandNode {
+            redirect: Some(1),
+            ..CommandNode::new(NodeKind::Literal("ls".to_string()), true)
+        };
+        graph.add_child(0, alias);
+
+        assert_eq!(round_trip(&graph).await, graph);
+    }
+
+    #[test]
+    fn test_commands_run_from_the_last_required_argument() {
+        let mut graph = CommandGraph::new();
+        graph.add_command(
+            "tp",
+            &[
+                CommandArgument::required("player", ArgumentParser::Player),
+                CommandArgument::optional("target", ArgumentParser::Player),
+            ],
+        );
+        graph.add_command(
+            "help",
+            &[CommandArgument::optional("page", ArgumentParser::Word)],
+        );
+        let executable = graph
+            .nodes
+            .iter()
+            .map(|node| node.executable)
+            .collect::<Vec<_>>();
+        // The root, then tp and its arguments, then help and its argument.
+        assert_eq!(executable, vec![false, false, true, true, true, true]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_indices_are_rejected() {
+        let mut graph = CommandGraph::new();
+        graph.add_command("list", &[]);
+        graph.nodes[0].children.push(7);
+        let mut bytes = Vec::new();
+        graph.net_encode(&mut bytes).await.unwrap();
+        assert!(CommandGraph::net_decode(&mut Cursor::new(bytes))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+
+use crate::commands::graph::{ArgumentParser, CommandArgument, CommandGraph};
+use crate::net::packets::outgoing::commands::Commands;
+use crate::net::packets::ConnectionId;
+use crate::state::{GlobalState, ServerState};
+use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
+
+pub mod builtin;
+pub mod graph;
+
+pub type CommandFuture = Pin<Box<dyn Future<Output = Result<String>> + Send>>;
+type CommandHandler = Arc<dyn Fn(CommandContext) -> CommandFuture + Send + Sync>;
+
+/// Who ran a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandSender {
+    /// The console, or an admin over RCON.
+    Console,
+    /// A player, by their entity id.
+    Player(ConnectionId),
+}
+
+/// Everything a command handler gets.
+///
+/// - `sender`: Who ran the command, and who the output goes to.
+/// - `args`: The arguments after the command's name, see [parse_arguments].
+pub struct CommandContext {
+    pub state: GlobalState,
+    pub sender: CommandSender,
+    pub args: Vec<String>,
+}
+
+struct Command {
+    description: String,
+    arguments: Vec<CommandArgument>,
+    handler: CommandHandler,
+}
+
+/// The commands players and the console can run, by name.
+///
+/// Handlers return the output of the command, which is sent back to whoever ran it. Commands
+/// from anywhere in the server can register themselves, see [CommandDispatcher::register].
+#[derive(Default)]
+pub struct CommandDispatcher {
+    commands: RwLock<BTreeMap<String, Command>>,
+}
+
+impl CommandDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A dispatcher with the commands in [builtin] already registered.
+    pub fn with_builtin_commands() -> Self {
+        let dispatcher = Self::new();
+        builtin::register_builtin_commands(&dispatcher);
+        dispatcher
+    }
+
+    /// Registers a command, replacing any other command with the same name. Names aren't case
+    /// sensitive.
+    ///
+    /// ```ignore
+    /// state.commands.register("hello", "Says hello", |ctx| async move {
+    ///     Ok(format!("Hello, {}!", ctx.args.join(" ")))
+    /// });
+    /// ```
+    pub fn register<F, Fut>(&self, name: &str, description: &str, handler: F)
+    where
+        F: Fn(CommandContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        self.register_with_arguments(name, description, Vec::new(), handler);
+    }
+
+    /// Registers a command along with the arguments it takes, which clients use to check and
+    /// complete the command as it's typed. The handler still gets the arguments as they were
+    /// typed, and has to check them itself.
+    ///
+    /// ```ignore
+    /// state.commands.register_with_arguments(
+    ///     "kick",
+    ///     "Kicks a player",
+    ///     vec![CommandArgument::required("player", ArgumentParser::Player)],
+    ///     kick,
+    /// );
+    /// ```
+    pub fn register_with_arguments<F, Fut>(
+        &self,
+        name: &str,
+        description: &str,
+        arguments: Vec<CommandArgument>,
+        handler: F,
+    ) where
+        F: Fn(CommandContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        let handler: CommandHandler = Arc::new(move |ctx| Box::pin(handler(ctx)));
+        let command = Command {
+            description: description.to_string(),
+            arguments,
+            handler,
+        };
+        self.commands
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(name.to_lowercase(), command);
+    }
+
+    /// The name and description of every command, sorted by name.
+    pub fn commands(&self) -> Vec<(String, String)> {
+        self.commands
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(|(name, command)| (name.clone(), command.description.clone()))
+            .collect()
+    }
+
+    /// Every registered command as the graph clients are sent.
+    pub fn graph(&self) -> CommandGraph {
+        let mut graph = CommandGraph::new();
+        for (name, command) in self
+            .commands
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+        {
+            graph.add_command(name, &command.arguments);
+        }
+        graph
+    }
+
+    /// The parser of a command's argument, by its position after the command's name.
+    pub fn argument_parser(&self, name: &str, index: usize) -> Option<ArgumentParser> {
+        self.commands
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&name.to_lowercase())?
+            .arguments
+            .get(index)
+            .map(|argument| argument.parser.clone())
+    }
+
+    /// Runs `input`, a command's name followed by its arguments, with or without a leading
+    /// slash. Returns `None` if there's no command with that name.
+    pub async fn dispatch(
+        &self,
+        state: GlobalState,
+        sender: CommandSender,
+        input: &str,
+    ) -> Option<Result<String>> {
+        let mut args = parse_arguments(input.trim().trim_start_matches('/'));
+        if args.is_empty() {
+            return None;
+        }
+        let name = args.remove(0).to_lowercase();
+
+        // Cloned out, so the lock isn't held while the command runs.
+        let handler = self
+            .commands
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&name)?
+            .handler
+            .clone();
+
+        let ctx = CommandContext {
+            state,
+            sender,
+            args,
+        };
+        Some(handler(ctx).await)
+    }
+}
+
+impl ServerState {
+    /// Sends a player every registered command, so their client can complete them.
+    pub async fn send_commands(&self, conn_id: ConnectionId) -> Result<()> {
+        let conn = self.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(Commands::new(self.commands.graph())).await
+    }
+
+    /// Runs a command typed by an admin, e.g. over RCON, and returns its output.
+    pub async fn execute_command(self: &Arc<Self>, command: &str) -> String {
+        match self
+            .commands
+            .dispatch(self.clone(), CommandSender::Console, command)
+            .await
+        {
+            Some(Ok(output)) => output,
+            Some(Err(e)) => format!("Error: {}", e),
+            None => format!("Unknown command: {}", command_name(command)),
+        }
+    }
+}
+
+/// What suggestions are being asked for, see [completion_target].
+///
+/// - `argument`: The index of the argument being typed, after the command's name.
+/// - `start`: Where the argument starts in the text the client sent.
+/// - `prefix`: What's been typed of the argument so far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionTarget {
+    pub command: String,
+    pub argument: usize,
+    pub start: usize,
+    pub prefix: String,
+}
+
+/// Works out which argument is being typed in `text`, everything before the cursor including the
+/// leading slash. `None` while the command's name is still being typed, since the client
+/// completes that from the command graph.
+pub fn completion_target(text: &str) -> Option<CompletionTarget> {
+    let offset = text.len() - text.trim_start_matches('/').len();
+    let body = &text[offset..];
+    let start = body
+        .rfind(char::is_whitespace)
+        .map(|index| index + body[index..].chars().next().map_or(1, char::len_utf8))?;
+    let mut words = body[..start].split_whitespace();
+    let command = words.next()?.to_string();
+    Some(CompletionTarget {
+        command,
+        argument: words.count(),
+        start: offset + start,
+        prefix: body[start..].to_string(),
+    })
+}
+
+impl ServerState {
+    /// The suggestions for the argument being typed in `text`, along with where it starts and how
+    /// long it is. Only players have suggestions for now, the names of everyone online that start
+    /// with what's been typed.
+    pub async fn command_suggestions(&self, text: &str) -> Option<(CompletionTarget, Vec<String>)> {
+        let target = completion_target(text)?;
+        let parser = self
+            .commands
+            .argument_parser(&target.command, target.argument)?;
+        let suggestions = match parser {
+            ArgumentParser::Player => {
+                let prefix = target.prefix.to_lowercase();
+                let mut names = self
+                    .world
+                    .query::<&Player>()
+                    .iter()
+                    .await
+                    .map(|(_, player)| player.username.clone())
+                    .filter(|name| name.to_lowercase().starts_with(&prefix))
+                    .collect::<Vec<_>>();
+                names.sort_by_key(|name| name.to_lowercase());
+                names
+            }
+            _ => return None,
+        };
+        Some((target, suggestions))
+    }
+}
+
+/// The name a command was run with, for error messages.
+pub fn command_name(input: &str) -> &str {
+    input
+        .trim()
+        .trim_start_matches('/')
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+}
+
+/// Splits a command into its words. Anything in double quotes is kept together, so
+/// `kick Steve "being rude"` gives `["kick", "Steve", "being rude"]`.
+pub fn parse_arguments(input: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    // Tells `""` apart from no argument at all.
+    let mut quoted = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                quoted = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() || quoted {
+                    args.push(std::mem::take(&mut current));
+                }
+                quoted = false;
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() || quoted {
+        args.push(current);
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::create_state;
+
+    #[test]
+    fn test_parse_arguments() {
+        assert_eq!(parse_arguments("list"), vec!["list"]);
+        assert_eq!(parse_arguments("  tp  1 2   3 "), vec!["tp", "1", "2", "3"]);
+        assert_eq!(
+            parse_arguments(r#"kick Steve "being rude""#),
+            vec!["kick", "Steve", "being rude"]
+        );
+        assert_eq!(parse_arguments(r#"say """#), vec!["say", ""]);
+        assert!(parse_arguments("   ").is_empty());
+    }
+
+    #[test]
+    fn test_completion_target() {
+        assert_eq!(completion_target("/kic"), None);
+        assert_eq!(
+            completion_target("/kick St"),
+            Some(CompletionTarget {
+                command: "kick".to_string(),
+                argument: 0,
+                start: 6,
+                prefix: "St".to_string(),
+            })
+        );
+        let target = completion_target("/tp  Steve ").unwrap();
+        assert_eq!((target.argument, target.start), (1, 11));
+        assert!(target.prefix.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_registered_commands_are_dispatched() {
+        let state = create_state(vec![TcpListener::bind("127.0.0.1:0").await.unwrap()])
+            .await
+            .unwrap();
+        state
+            .commands
+            .register("echo", "Repeats you", |ctx| async move {
+                Ok(ctx.args.join(" "))
+            });
+
+        let output = state
+            .commands
+            .dispatch(state.clone(), CommandSender::Console, "/ECHO hello there")
+            .await;
+        assert_eq!(output.unwrap().unwrap(), "hello there");
+
+        let output = state
+            .commands
+            .dispatch(state.clone(), CommandSender::Console, "nope")
+            .await;
+        assert!(output.is_none());
+        assert_eq!(
+            state.execute_command("nope 1").await,
+            "Unknown command: nope"
+        );
+    }
+}
@@ -204,6 +204,8 @@ fn generate_modified_constructor(
 
     quote! {
         impl #impl_generics #name #ty_generics #where_clause {
+            // Packets can have any number of fields.
+            #[allow(clippy::too_many_arguments)]
             pub fn new_auto(#(#non_default_fields_params)*) -> Self {
                 Self {
                     #(#non_default_fields_names)*
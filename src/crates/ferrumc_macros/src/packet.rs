@@ -50,6 +50,8 @@ pub fn bake(input: TokenStream) -> TokenStream {
     }
 
     let mut match_arms = Vec::new();
+    let mut registered = Vec::new();
+    let mut debug_arms = Vec::new();
 
     let start = std::time::Instant::now();
 
@@ -135,6 +137,10 @@ pub fn bake(input: TokenStream) -> TokenStream {
 
             let struct_path = syn::parse_str::<syn::Path>(&struct_path).expect("parse_str failed");
 
+            registered.push(quote! {
+                (#packet_id, #state) => true,
+            });
+
             match_arms.push(quote! {
                 (#packet_id, #state) => {
                     let packet= #struct_path::net_decode(cursor).await?;
@@ -142,6 +148,14 @@ pub fn bake(input: TokenStream) -> TokenStream {
                 },
             });
 
+            debug_arms.push(quote! {
+                (#packet_id, #state) => Some(
+                    #struct_path::net_decode(cursor)
+                        .await
+                        .map(|packet| format!("{:#?}", packet))
+                ),
+            });
+
             /*match_arms.push(quote! {
                 (#packet_id, #state) => {
                     let packet= #path::#struct_name::decode(cursor).await?;
@@ -164,11 +178,29 @@ pub fn bake(input: TokenStream) -> TokenStream {
         pub async fn handle_packet(packet_id: u8, conn_id: usize, conn_state: &crate::net::State, cursor: &mut std::io::Cursor<Vec<u8>>, state: crate::state::GlobalState) -> crate::utils::prelude::Result<()> {
             match (packet_id, conn_state.as_str()) {
                 #(#match_arms)*
-                _ => tracing::warn!("No packet found for ID: 0x{:02X} in state: {}", packet_id, conn_state.as_str()),
+                _ => tracing::trace!("No packet found for ID: 0x{:02X} in state: {}", packet_id, conn_state.as_str()),
             }
 
             Ok(())
         }
+
+        /// Whether there's a handler for `packet_id` in `conn_state`. Packets without one can be
+        /// skipped without decoding them.
+        pub fn is_packet_registered(packet_id: u8, conn_state: &crate::net::State) -> bool {
+            match (packet_id, conn_state.as_str()) {
+                #(#registered)*
+                _ => false,
+            }
+        }
+
+        /// Decodes a packet without handling it, and formats it for debugging. Returns `None` if
+        /// there's no decoder for `packet_id` in `conn_state`.
+        pub async fn debug_decode_packet(packet_id: u8, conn_state: &crate::net::State, cursor: &mut std::io::Cursor<Vec<u8>>) -> Option<crate::utils::prelude::Result<String>> {
+            match (packet_id, conn_state.as_str()) {
+                #(#debug_arms)*
+                _ => None,
+            }
+        }
     };
 
     TokenStream::from(output)
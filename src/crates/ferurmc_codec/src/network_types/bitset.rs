@@ -1,55 +1,13 @@
-/*#![allow(dead_code)]
-
-use ferrumc_codec::network_types::varint::VarInt;
-use crate::utils::error::Error;
-use ferrumc_codec::enc::Encode;
-use tokio::io::{AsyncSeek, AsyncWrite};
-
-pub struct BitSet {
-    len: VarInt,
-    data: Vec<u64>,
-}
-
-impl BitSet {
-    pub(crate) fn new() -> Self {
-        BitSet {
-            data: vec![0; 1],
-            len: VarInt::from(0),
-        }
-    }
-
-    pub(crate) fn set(&mut self, index: u32) {
-        let word_index = (index / 64) as usize;
-        let bit_index = index % 64;
-        if word_index >= self.data.len() {
-            self.data.resize(word_index + 1, 0);
-        }
-        self.data[word_index] |= 1 << bit_index;
-    }
-
-    fn to_vec(&self) -> Vec<i64> {
-        self.data.iter().map(|&x| x as i64).collect()
-    }
-}
-
-impl Encode for BitSet {
-    async fn encode<T>(&self, bytes: &mut T) -> Result<(), Error>
-    where
-        T: AsyncWrite + Unpin,
-    {
-        self.len.encode(bytes).await?;
-        self.data.encode(bytes).await?;
-        Ok(())
-    }
-}
-*/
-
 use std::ops::Index;
 
-use ferrumc_codec::enc::NetEncode;
-use ferrumc_codec::network_types::varint::VarInt;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
+use crate::enc::NetEncode;
+use crate::network_types::varint::VarInt;
+use crate::prelude::*;
+
+/// A fixed size set of bits, sent as the number of longs it takes up followed by the longs.
+/// Bit `i` is bit `i % 64` of long `i / 64`.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct BitSet {
     data: Vec<u64>,
@@ -161,7 +119,7 @@ impl Index<usize> for BitSet {
 }
 
 impl NetEncode for BitSet {
-    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<()>
     where
         T: AsyncWrite + Unpin,
     {
@@ -217,4 +175,21 @@ mod tests {
         assert!(bs[50]);
         assert!(!bs[51]);
     }
+
+    #[tokio::test]
+    async fn test_encode_bitset() {
+        let mut bs = BitSet::new(70);
+        bs.set(0);
+        bs.set(65);
+        let mut bytes = Vec::new();
+        bs.net_encode(&mut bytes).await.unwrap();
+        assert_eq!(
+            bytes,
+            vec![0x02, 0, 0, 0, 0, 0, 0, 0, 0x01, 0, 0, 0, 0, 0, 0, 0, 0x02]
+        );
+
+        let mut bytes = Vec::new();
+        BitSet::empty().net_encode(&mut bytes).await.unwrap();
+        assert_eq!(bytes, vec![0x00]);
+    }
 }
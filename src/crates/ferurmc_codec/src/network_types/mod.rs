@@ -1,2 +1,3 @@
+pub mod bitset;
 pub mod varint;
 pub mod varlong;
\ No newline at end of file
@@ -1,3 +1,5 @@
+use crate::enc::NetEncode;
+
 #[tokio::test]
 async fn test_encode_bool() {
     let mut buf = Vec::new();
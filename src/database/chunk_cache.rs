@@ -0,0 +1,419 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::world::chunk_format::Chunk;
+
+/// Roughly how much memory a chunk takes besides its sections, in bytes.
+const CHUNK_OVERHEAD: usize = 1024;
+/// Roughly how much memory a section takes: its blocks at up to 16 bits each, plus its biomes
+/// and its block and sky light.
+const SECTION_SIZE: usize = 4096 * 2 + 2048 * 2;
+
+struct CachedChunk {
+    chunk: Chunk,
+    /// Changed since it was last written to the database.
+    dirty: bool,
+    /// The cache's clock when it was last changed, so a save only marks it clean if it hasn't
+    /// changed again since the save took it.
+    changed: u64,
+    /// From [estimate_size], kept so it doesn't have to be worked out again on eviction.
+    size: usize,
+    /// The cache's clock when it was last used, so the least recently used chunks go first.
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    chunks: HashMap<u64, CachedChunk>,
+    /// Every chunk's key by when it was last used, least recently used first, so eviction
+    /// doesn't have to sort them.
+    by_last_used: BTreeMap<u64, u64>,
+    /// How much memory all the chunks take, roughly.
+    size: usize,
+    clock: u64,
+    /// Chunks some player can see, which are kept however long ago they were used.
+    in_view: HashSet<u64>,
+    /// Chunks being loaded, and how many loads each has going.
+    pinned: HashMap<u64, usize>,
+}
+
+impl Entries {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn remove(&mut self, key: u64) {
+        if let Some(cached) = self.chunks.remove(&key) {
+            self.size -= cached.size;
+            self.by_last_used.remove(&cached.last_used);
+        }
+    }
+
+    /// Marks a chunk as just used, returning it if it's cached.
+    fn touch(&mut self, key: u64) -> Option<&CachedChunk> {
+        let now = self.tick();
+        let cached = self.chunks.get_mut(&key)?;
+        self.by_last_used.remove(&cached.last_used);
+        self.by_last_used.insert(now, key);
+        cached.last_used = now;
+        Some(cached)
+    }
+
+    fn evictable(&self, key: u64, cached: &CachedChunk) -> bool {
+        !cached.dirty && !self.in_view.contains(&key) && !self.pinned.contains_key(&key)
+    }
+}
+
+/// How the chunk cache has been doing since the server started.
+///
+/// - `hits`: How many times a chunk was asked for and was in memory.
+/// - `misses`: How many times a chunk was asked for and had to be read from the database.
+/// - `evictions`: How many chunks were dropped to stay under `database.cache_size_mb`.
+/// - `chunks`: How many chunks are in memory now.
+/// - `size`: Roughly how much memory they take, in bytes.
+/// - `dirty`: How many of them have changes that haven't been saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub chunks: usize,
+    pub size: usize,
+    pub dirty: usize,
+}
+
+/// The chunks in memory, by their database key.
+///
+/// Changes to chunks are only made here, and marked dirty until [Database::save_chunks] has
+/// written them to the database. Dirty chunks are never evicted, so nothing is lost before it's
+/// saved.
+///
+/// Once the chunks take more than `capacity` bytes, the least recently used ones are evicted,
+/// except ones that are dirty, in a player's view, or being loaded. Those can take the cache
+/// over its capacity for a while.
+///
+/// [Database::save_chunks]: crate::database::Database::save_chunks
+pub struct ChunkCache {
+    entries: Mutex<Entries>,
+    capacity: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl ChunkCache {
+    /// A cache that holds about `capacity` bytes of chunks.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(Entries::default()),
+            capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Entries> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn get(&self, key: u64) -> Option<Chunk> {
+        match self.entries().touch(key) {
+            Some(cached) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(cached.chunk.clone())
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Runs `read` on a chunk where it is in the cache, instead of copying it out like
+    /// [ChunkCache::get]. The cache is locked while it runs, so it should only look at a few
+    /// blocks.
+    pub fn read<R>(&self, key: u64, read: impl FnOnce(&Chunk) -> R) -> Option<R> {
+        match self.entries().touch(key) {
+            Some(cached) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(read(&cached.chunk))
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub fn contains(&self, key: u64) -> bool {
+        self.entries().chunks.contains_key(&key)
+    }
+
+    /// Caches a chunk that's the same as the one in the database. A cached chunk with changes
+    /// that haven't been saved yet is kept instead.
+    pub fn insert_saved(&self, key: u64, chunk: Chunk) {
+        let mut entries = self.entries();
+        if entries.chunks.get(&key).is_some_and(|cached| cached.dirty) {
+            return;
+        }
+        self.insert(&mut entries, key, chunk, false);
+    }
+
+    /// Replaces a chunk with a changed one, which is saved with the next
+    /// [ChunkCache::dirty_chunks].
+    pub fn update(&self, key: u64, chunk: Chunk) {
+        let mut entries = self.entries();
+        self.insert(&mut entries, key, chunk, true);
+    }
+
+    fn insert(&self, entries: &mut Entries, key: u64, chunk: Chunk, dirty: bool) {
+        entries.remove(key);
+        let size = estimate_size(&chunk);
+        let last_used = entries.tick();
+        entries.size += size;
+        entries.by_last_used.insert(last_used, key);
+        entries.chunks.insert(
+            key,
+            CachedChunk {
+                chunk,
+                dirty,
+                changed: last_used,
+                size,
+                last_used,
+            },
+        );
+        self.trim(entries);
+    }
+
+    /// Removes a chunk, unless it has changes that haven't been saved. Returns whether it was
+    /// removed.
+    pub fn evict(&self, key: u64) -> bool {
+        let mut entries = self.entries();
+        match entries.chunks.get(&key) {
+            Some(cached) if cached.dirty => false,
+            Some(_) => {
+                entries.remove(key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Evicts the least recently used chunks that can be, until the cache is under its
+    /// capacity or nothing else can go.
+    fn trim(&self, entries: &mut Entries) {
+        if entries.size <= self.capacity {
+            return;
+        }
+        let mut evicted = Vec::new();
+        let mut size = entries.size;
+        for &key in entries.by_last_used.values() {
+            if size <= self.capacity {
+                break;
+            }
+            let cached = &entries.chunks[&key];
+            if entries.evictable(key, cached) {
+                size -= cached.size;
+                evicted.push(key);
+            }
+        }
+        self.evictions
+            .fetch_add(evicted.len() as u64, Ordering::Relaxed);
+        for key in evicted {
+            entries.remove(key);
+        }
+    }
+
+    /// Sets which chunks players can see, which aren't evicted until they can't anymore.
+    pub fn set_in_view(&self, keys: HashSet<u64>) {
+        let mut entries = self.entries();
+        entries.in_view = keys;
+        self.trim(&mut entries);
+    }
+
+    /// Keeps a chunk from being evicted while it's loaded and handed to whoever asked for it.
+    /// Every call needs a matching [ChunkCache::unpin].
+    pub fn pin(&self, key: u64) {
+        *self.entries().pinned.entry(key).or_default() += 1;
+    }
+
+    pub fn unpin(&self, key: u64) {
+        let mut entries = self.entries();
+        if let Some(count) = entries.pinned.get_mut(&key) {
+            *count -= 1;
+            if *count == 0 {
+                entries.pinned.remove(&key);
+                self.trim(&mut entries);
+            }
+        }
+    }
+
+    /// The chunks that need saving, and the cache's clock when they were taken. They stay dirty,
+    /// so they can't be evicted, until [ChunkCache::mark_saved] is called with the clock once
+    /// they're in the database.
+    pub fn dirty_chunks(&self) -> (u64, Vec<(u64, Chunk)>) {
+        let mut entries = self.entries();
+        let taken = entries.tick();
+        let dirty = entries
+            .chunks
+            .iter()
+            .filter(|(_, cached)| cached.dirty)
+            .map(|(&key, cached)| (key, cached.chunk.clone()))
+            .collect();
+        (taken, dirty)
+    }
+
+    /// Marks the chunks taken with [ChunkCache::dirty_chunks] at `taken` as saved. Ones that
+    /// changed again since are left dirty, for the next save.
+    pub fn mark_saved(&self, keys: impl IntoIterator<Item = u64>, taken: u64) {
+        let mut entries = self.entries();
+        for key in keys {
+            if let Some(cached) = entries.chunks.get_mut(&key) {
+                cached.dirty &= cached.changed > taken;
+            }
+        }
+        self.trim(&mut entries);
+    }
+
+    /// How many chunks have changes that haven't been saved.
+    pub fn dirty_count(&self) -> usize {
+        self.entries()
+            .chunks
+            .values()
+            .filter(|cached| cached.dirty)
+            .count()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries().chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries().chunks.is_empty()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.entries();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            chunks: entries.chunks.len(),
+            size: entries.size,
+            dirty: entries
+                .chunks
+                .values()
+                .filter(|cached| cached.dirty)
+                .count(),
+        }
+    }
+}
+
+/// Roughly how much memory `chunk` takes, from how many sections it has.
+pub fn estimate_size(chunk: &Chunk) -> usize {
+    let sections = chunk.sections.as_ref().map_or(0, Vec::len);
+    CHUNK_OVERHEAD + sections * SECTION_SIZE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::generation::superflat::SuperflatGenerator;
+    use crate::world::generation::ChunkGenerator;
+
+    fn chunk(x: i32) -> Chunk {
+        SuperflatGenerator::from_layers("bedrock")
+            .unwrap()
+            .generate(x, 0)
+    }
+
+    /// A cache with room for `chunks` chunks.
+    fn cache_for(chunks: usize) -> ChunkCache {
+        ChunkCache::new(estimate_size(&chunk(0)) * chunks)
+    }
+
+    #[test]
+    fn test_dirty_chunks() {
+        let cache = ChunkCache::new(usize::MAX);
+        cache.insert_saved(1, chunk(1));
+        cache.update(2, chunk(2));
+        assert_eq!(cache.dirty_count(), 1);
+
+        // Changes aren't thrown away, by evicting or by loading the chunk again.
+        assert!(!cache.evict(2));
+        cache.insert_saved(2, chunk(20));
+        assert_eq!(cache.get(2).unwrap().x_pos, 2);
+
+        let (taken, dirty) = cache.dirty_chunks();
+        assert_eq!(dirty.len(), 1);
+        assert_eq!(dirty[0].0, 2);
+        // Until it's in the database, it can't go anywhere.
+        assert_eq!(cache.dirty_count(), 1);
+        assert!(!cache.evict(2));
+        cache.mark_saved([2], taken);
+        assert_eq!(cache.dirty_count(), 0);
+        assert!(cache.dirty_chunks().1.is_empty());
+
+        // Changed again while it was being saved, so that save doesn't count.
+        cache.update(2, chunk(2));
+        let (taken, _) = cache.dirty_chunks();
+        cache.update(2, chunk(2));
+        cache.mark_saved([2], taken);
+        assert_eq!(cache.dirty_count(), 1);
+        let (taken, _) = cache.dirty_chunks();
+        cache.mark_saved([2], taken);
+        assert!(cache.evict(2));
+        assert!(cache.evict(1));
+        assert!(!cache.evict(1));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let cache = cache_for(3);
+        for key in 0..3 {
+            cache.insert_saved(key, chunk(key as i32));
+        }
+        // Using the oldest makes the next oldest the one to go.
+        assert!(cache.get(0).is_some());
+        cache.insert_saved(3, chunk(3));
+        assert_eq!(cache.len(), 3);
+        assert!(cache.contains(0));
+        assert!(!cache.contains(1));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 0, 1));
+        assert!(cache.get(1).is_none());
+        assert_eq!(cache.stats().misses, 1);
+        assert!(stats.size <= estimate_size(&chunk(0)) * 3);
+    }
+
+    #[test]
+    fn test_protected_chunks_are_kept() {
+        let cache = cache_for(2);
+        cache.update(0, chunk(0));
+        cache.insert_saved(1, chunk(1));
+        cache.set_in_view(HashSet::from([1]));
+        cache.pin(2);
+        cache.insert_saved(2, chunk(2));
+
+        // Dirty, in view and being loaded, so nothing can go, even over capacity.
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.stats().evictions, 0);
+
+        // Once the load's done, it's fair game again.
+        cache.unpin(2);
+        assert!(!cache.contains(2));
+        cache.set_in_view(HashSet::new());
+        let (taken, _) = cache.dirty_chunks();
+        cache.mark_saved([0], taken);
+        cache.insert_saved(3, chunk(3));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains(3));
+    }
+}
@@ -1,11 +1,9 @@
 use byteorder::LE;
 use heed::types::Bytes;
 use heed::{types::U64, Env};
-use moka::future::Cache;
-use std::sync::Arc;
-use tokio::runtime::Handle;
-use tracing::{trace, warn};
+use tokio::sync::MutexGuard;
 
+use super::chunk_cache::CacheStats;
 use super::spawn_blocking_db;
 use crate::database::encoding::ZstdCodec;
 use crate::world::importing::SerializedChunk;
@@ -13,6 +11,9 @@ use crate::{
     database::Database, utils::error::Error, utils::hash::hash, world::chunk_format::Chunk,
 };
 
+/// How many locks the chunks that are being changed are spread over, see [Database::lock_chunk].
+pub(super) const CHUNK_LOCKS: usize = 64;
+
 impl Database {
     // Close the database
     pub fn close(self) {
@@ -20,6 +21,17 @@ impl Database {
         token.wait();
     }
 
+    /// Flush everything written so far to disk. The database is opened with `NO_SYNC`, so recent
+    /// writes can be lost if the server exits without calling this.
+    pub async fn sync(&self) -> Result<(), Error> {
+        let db = self.db.clone();
+        let tsk_db = self.db.clone();
+        spawn_blocking_db(tsk_db, move || db.force_sync())
+            .await
+            .map_err(|e| Error::DatabaseError(e.to_string()))??;
+        Ok(())
+    }
+
     /// Fetch chunk from database
     async fn get_chunk_from_database(db: &Env, key: &u64) -> Result<Option<Chunk>, heed::Error> {
         let data = {
@@ -47,25 +59,16 @@ impl Database {
     }
 
     /// Insert a single chunk into database
-    fn insert_chunk_into_database(db: &Env, chunk: &Chunk) -> Result<(), heed::Error> {
+    /// `chunk` is already compressed, since this runs on the database pool, outside the runtime
+    fn insert_chunk_into_database(db: &Env, chunk: &SerializedChunk) -> Result<(), heed::Error> {
         // Initialize write transaction and open chunks table
         let mut rw_tx = db.write_txn()?;
         let database = db
             .open_database::<U64<LE>, Bytes>(&rw_tx, Some("chunks"))?
             .expect("No table \"chunks\" found. The database should have been initialized");
 
-        // Calculate key
-        let key = hash((chunk.dimension.as_ref().unwrap(), chunk.x_pos, chunk.z_pos));
-
-        let chunk = chunk.clone();
-        let chunk = Handle::current().block_on(async {
-            ZstdCodec::compress_data(chunk)
-                .await
-                .expect("Failed to compress chunk")
-        });
-
         // Insert chunk
-        let res = database.put(&mut rw_tx, &key, chunk.as_slice());
+        let res = database.put(&mut rw_tx, &chunk.hash(), chunk.data());
         rw_tx.commit()?;
 
         res
@@ -96,47 +99,6 @@ impl Database {
         Ok(())
     }
 
-    #[allow(dead_code)]
-    async fn load_into_cache(&self, key: u64) -> Result<(), Error> {
-        Database::load_into_cache_standalone(self.db.clone(), self.cache.clone(), key).await
-    }
-
-    async fn load_into_cache_standalone(
-        db: Env,
-        cache: Arc<Cache<u64, Chunk>>,
-        key: u64,
-    ) -> Result<(), Error> {
-        // let tsk_db = db.clone();
-
-        let db = db.clone();
-        tokio::task::spawn(async move {
-            // Check cache
-            if cache.contains_key(&key) {
-                trace!("Chunk already exists in cache: {:X}", key);
-            }
-            // If not in cache then search in database
-            else if let Ok(chunk) = Self::get_chunk_from_database(&db, &key).await
-            /*spawn_blocking_db(tsk_db, move || Self::get_chunk_from_database(&db, &key))
-            .await
-            .unwrap()*/
-            {
-                if let Some(chunk) = chunk {
-                    cache.insert(key, chunk).await;
-                } else {
-                    warn!(
-                        "Chunk does not exist in db, can't load into cache: {:X}",
-                        key,
-                    );
-                }
-            }
-            // The chunk don't exist
-            else {
-                warn!("Error getting chunk: {:X}", key,);
-            }
-        })
-        .await?;
-        Ok(())
-    }
     /// Insert a chunk into the database <br>
     /// This will also insert the chunk into the cache <br>
     /// If the chunk already exists, it will return an error
@@ -161,7 +123,7 @@ impl Database {
         let key = hash((value.dimension.as_ref().unwrap(), value.x_pos, value.z_pos));
 
         // Insert chunk into persistent database
-        let chunk = value.clone();
+        let chunk = SerializedChunk::new(key, ZstdCodec::compress_data(value.clone()).await?);
         let db = self.db.clone();
         let tsk_db = self.db.clone();
         spawn_blocking_db(tsk_db, move || {
@@ -171,7 +133,7 @@ impl Database {
         .unwrap()?;
 
         // Insert into cache
-        self.cache.insert(key, value).await;
+        self.cache.insert_saved(key, value);
         Ok(())
     }
 
@@ -205,28 +167,38 @@ impl Database {
         let key = hash((dimension, x, z));
         let db = self.db.clone();
 
-        let res = Self::get_chunk_from_database(&db, &key).await?;
-
-        Ok(res)
-
-        /* // First check cache
-        if self.cache.contains_key(&key) {
-            Ok(self.cache.get(&key).await)
+        // First check cache, which has any changes that haven't been saved yet
+        if let Some(chunk) = self.cache.get(key) {
+            return Ok(Some(chunk));
         }
         // Attempt to get chunk from persistent database
-        else if let Some(chunk) = Self::get_chunk_from_database(&db, &key).await?
-            /*
-            spawn_blocking_db(tsk_db, move || Self::get_chunk_from_database(&db, &key))
-                .await
-                .unwrap()?*/
-        {
-            // self.cache.insert(key, chunk.clone()).await;
-            Ok(Some(chunk))
+        let Some(chunk) = Self::get_chunk_from_database(&db, &key).await? else {
+            return Ok(None);
+        };
+        self.cache.insert_saved(key, chunk.clone());
+        Ok(Some(chunk))
+    }
+
+    /// Runs `read` on a chunk without copying it, for looking at a few of its blocks, see
+    /// [ChunkCache::read](super::chunk_cache::ChunkCache::read). It's loaded into the cache
+    /// first if it isn't there. `None` if the chunk doesn't exist.
+    pub async fn read_chunk<R>(
+        &self,
+        x: i32,
+        z: i32,
+        dimension: &str,
+        read: impl Fn(&Chunk) -> R,
+    ) -> Result<Option<R>, Error> {
+        let key = hash((dimension, x, z));
+        if let Some(result) = self.cache.read(key, &read) {
+            return Ok(Some(result));
         }
-        // Chunk do not exist
-        else {
-            Ok(None)
-        }*/
+        let Some(chunk) = Self::get_chunk_from_database(&self.db, &key).await? else {
+            return Ok(None);
+        };
+        let result = read(&chunk);
+        self.cache.insert_saved(key, chunk);
+        Ok(Some(result))
     }
 
     /// Check if a chunk exists in the database
@@ -253,7 +225,7 @@ impl Database {
         let db = self.db.clone();
 
         // Check first cache
-        if self.cache.contains_key(&key) {
+        if self.cache.contains(key) {
             Ok(true)
         // Else check persistent database and load it into cache
         } else {
@@ -268,7 +240,7 @@ impl Database {
             // This has been replaced by directly loading the queried chunk into cache
 
             // Load chunk into cache
-            self.cache.insert(key, res.clone()).await;
+            self.cache.insert_saved(key, res);
             Ok(true)
 
             /* match res {
@@ -284,9 +256,9 @@ impl Database {
         }
     }
 
-    /// Update a chunk in the database <br>
-    /// This will also update the chunk in the cache <br>
-    /// If the chunk does not exist, it will return an error
+    /// Update a chunk in the cache <br>
+    /// The chunk is marked dirty, and written to the persistent database by the next
+    /// [Database::save_chunks]
     /// # Arguments
     /// * `value` - The chunk to update
     /// # Returns
@@ -307,21 +279,113 @@ impl Database {
         // WARNING: This key wasn't supposed to include value.dimension in the tuple, but it was different from the key used in persistent database most likely a bug.
         let key = hash((value.dimension.as_ref().unwrap(), value.x_pos, value.z_pos));
 
-        // Insert new chunk state into persistent database
-        let chunk = value.clone();
-        let db = self.db.clone();
-        let tsk_db = self.db.clone();
-        spawn_blocking_db(tsk_db, move || {
-            Self::insert_chunk_into_database(&db, &chunk)
-        })
-        .await
-        .unwrap()?;
-
-        // Insert new chunk state into cache
-        self.cache.insert(key, value).await;
+        // Insert new chunk state into cache, until it's saved
+        self.cache.update(key, value);
         Ok(())
     }
 
+    /// Locks a chunk for a change that reads it and writes it back, e.g. setting a block, so two
+    /// changes at once don't undo each other. Hold it from before [Database::get_chunk] until
+    /// after [Database::update_chunk].
+    ///
+    /// Chunks share a fixed number of locks, so this can also wait for a change to another
+    /// chunk. Never lock a second chunk while holding one.
+    pub async fn lock_chunk(&self, x: i32, z: i32, dimension: &str) -> MutexGuard<'_, ()> {
+        let key = hash((dimension, x, z));
+        self.chunk_locks[key as usize % self.chunk_locks.len()]
+            .lock()
+            .await
+    }
+
+    /// Write every chunk changed since the last save to the persistent database <br>
+    /// They're all written in one transaction, so a crash part way through leaves the world as
+    /// it was at the last save rather than half saved
+    /// # Returns
+    /// * `Result<usize, Error>` - How many chunks were saved
+    pub async fn save_chunks(&self) -> Result<usize, Error> {
+        let (taken, dirty) = self.cache.dirty_chunks();
+        if dirty.is_empty() {
+            return Ok(0);
+        }
+        let keys = dirty.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+
+        async {
+            let mut serialized = Vec::with_capacity(dirty.len());
+            for (key, chunk) in dirty {
+                serialized.push(SerializedChunk::new(
+                    key,
+                    ZstdCodec::compress_data(chunk).await?,
+                ));
+            }
+            let db = self.db.clone();
+            let tsk_db = self.db.clone();
+            spawn_blocking_db(tsk_db, move || {
+                Self::insert_chunks_into_database(&db, &serialized)
+            })
+            .await
+            .map_err(|e| Error::DatabaseError(e.to_string()))??;
+            Ok::<_, Error>(())
+        }
+        .await?;
+
+        // Only once they're committed, so they're never evicted before then. If the save
+        // failed, they're still dirty, and the next one tries again
+        let saved = keys.len();
+        self.cache.mark_saved(keys, taken);
+        Ok(saved)
+    }
+
+    /// How many chunks have been changed since the last [Database::save_chunks]
+    pub fn unsaved_chunks(&self) -> usize {
+        self.cache.dirty_count()
+    }
+
+    /// Remove a chunk from the cache <br>
+    /// The chunk stays in the persistent database, so this is only for chunks nobody is using <br>
+    /// Chunks with changes that haven't been saved yet are kept
+    /// # Arguments
+    /// * `x` - The x position of the chunk
+    /// * `z` - The z position of the chunk
+    /// * `dimension` - The dimension of the chunk
+    pub async fn evict_chunk_from_cache(&self, x: i32, z: i32, dimension: String) {
+        let key = hash((dimension, x, z));
+        self.cache.evict(key);
+    }
+
+    /// Keeps a chunk in the cache while it's being loaded, until [Database::unpin_chunk] is
+    /// called, so it isn't evicted before whoever asked for it gets it
+    /// # Arguments
+    /// * `x` - The x position of the chunk
+    /// * `z` - The z position of the chunk
+    /// * `dimension` - The dimension of the chunk
+    pub fn pin_chunk(&self, x: i32, z: i32, dimension: &str) {
+        self.cache.pin(hash((dimension, x, z)));
+    }
+
+    /// Lets a chunk pinned with [Database::pin_chunk] be evicted again
+    pub fn unpin_chunk(&self, x: i32, z: i32, dimension: &str) {
+        self.cache.unpin(hash((dimension, x, z)));
+    }
+
+    /// Sets which chunks players can see <br>
+    /// These are kept in the cache however long ago they were used, and the rest are evicted
+    /// least recently used first once the cache is over `database.cache_size_mb`
+    /// # Arguments
+    /// * `chunks` - The x, z and dimension of every chunk in view
+    pub fn set_chunks_in_view<'a>(&self, chunks: impl IntoIterator<Item = (i32, i32, &'a str)>) {
+        self.cache.set_in_view(
+            chunks
+                .into_iter()
+                .map(|(x, z, dimension)| hash((dimension, x, z)))
+                .collect(),
+        );
+    }
+
+    /// The chunk cache's hit, miss and eviction counts, and how full it is
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
     /// Batch insert chunks into the database <br>
     /// This will also insert the chunks into the cache <br>
     /// If any of the chunks already exist, it will return an error
@@ -390,7 +454,7 @@ async fn dump_chunk() {
     use nbt_lib::NBTSerialize;
     use tokio::net::TcpListener;
     setup_logger().unwrap();
-    let state = crate::create_state(TcpListener::bind("0.0.0.0:0").await.unwrap())
+    let state = crate::create_state(vec![TcpListener::bind("0.0.0.0:0").await.unwrap()])
         .await
         .unwrap();
     let chunk = state
@@ -1,29 +1,33 @@
 use byteorder::LE;
-use deepsize::DeepSizeOf;
-use futures::FutureExt;
-use heed::types::{Bytes, U64};
+use heed::types::{Bytes, Str, U64};
 use heed::{Env as LMDBDatabase, Env, EnvFlags, EnvOpenOptions, MdbError};
-use moka::notification::{ListenerFuture, RemovalCause};
 use rayon::{ThreadPool, ThreadPoolBuilder};
 use std::env;
 use std::future::Future;
 use std::path::PathBuf;
 use std::sync::{Arc, LazyLock, Mutex, OnceLock, RwLock};
-use std::time::Duration;
 use tokio::fs;
 use tokio::sync::oneshot;
-use tracing::{debug, info, trace, warn};
+use tracing::{debug, info, warn};
 
 use crate::utils::config::get_global_config;
 use crate::utils::error::Error;
 
-use crate::world::chunk_format::Chunk;
+use chunk_cache::ChunkCache;
+use player_data::PLAYER_DATA_TABLE;
+use world_info::WORLD_INFO_TABLE;
+pub mod chunk_cache;
 pub mod chunks;
 pub(crate) mod encoding;
+pub mod player_data;
+pub mod world_info;
 
 const LMDB_MIN_PAGE_SIZE: usize = 1800 * 1024usize.pow(2); // 1800MB
 const LMDB_PAGE_SIZE_INCREMENT: usize = 250 * 1024usize.pow(2); // 250MB
 const LMDB_MAX_DBS: u32 = 10;
+// Chunks are read on the runtime's threads and the chunk workers as well as the database pool,
+// and each thread keeps its reader slot, so one per core runs out. Same as LMDB's default
+const LMDB_MAX_READERS: u32 = 126;
 
 // Database threadpool
 static LMDB_THREADPOOL: OnceLock<ThreadPool> = OnceLock::new();
@@ -39,20 +43,9 @@ static LMDB_READER_SYNC: LazyLock<Arc<RwLock<()>>> = LazyLock::new(|| Arc::new(R
 /// cache for all in-memory updates
 pub struct Database {
     db: LMDBDatabase,
-    cache: Arc<moka::future::Cache<u64, Chunk>>,
-}
-
-fn evict_chunk(_key: Arc<u64>, value: Chunk, cause: RemovalCause) -> ListenerFuture {
-    async move {
-        if cause == RemovalCause::Expired {
-            trace!(
-                "Evicting chunk from cache: {}, {}",
-                value.x_pos,
-                value.z_pos
-            );
-        }
-    }
-    .boxed()
+    cache: ChunkCache,
+    /// See [Database::lock_chunk].
+    chunk_locks: Box<[tokio::sync::Mutex<()>]>,
 }
 
 /// Start database
@@ -81,7 +74,7 @@ pub async fn start_database() -> Result<Database, Error> {
 
     // Database Options
     let mut opts = EnvOpenOptions::new();
-    opts.max_readers(num_cpus::get() as u32)
+    opts.max_readers(LMDB_MAX_READERS)
         .map_size(LMDB_MIN_PAGE_SIZE)
         .max_dbs(LMDB_MAX_DBS);
 
@@ -110,27 +103,32 @@ pub async fn start_database() -> Result<Database, Error> {
         lmdb.create_database::<U64<LE>, Bytes>(&mut rw_tx, Some("chunks"))
             .expect("Unable to create database");
     }
+    if lmdb
+        .open_database::<Str, Bytes>(&rw_tx, Some(WORLD_INFO_TABLE))?
+        .is_none()
+    {
+        lmdb.create_database::<Str, Bytes>(&mut rw_tx, Some(WORLD_INFO_TABLE))
+            .expect("Unable to create database");
+    }
+    if lmdb
+        .open_database::<Str, Bytes>(&rw_tx, Some(PLAYER_DATA_TABLE))?
+        .is_none()
+    {
+        lmdb.create_database::<Str, Bytes>(&mut rw_tx, Some(PLAYER_DATA_TABLE))
+            .expect("Unable to create database");
+    }
     // `entities` table to be added, but needs the type to do so
 
     rw_tx.commit()?;
 
     info!("Database started");
 
-    info!("Initializing cache");
-
-    // Initializing moka cache
-    let cache = moka::future::Cache::builder()
-        .async_eviction_listener(evict_chunk)
-        .weigher(|_, v| v.deep_size_of() as u32)
-        .eviction_policy(moka::policy::EvictionPolicy::tiny_lfu())
-        /*.max_capacity(get_global_config().database.cache_size as u64 * 1024)
-        .initial_capacity(1000)*/
-        .time_to_live(Duration::from_millis(1000))
-        .build();
-
     Ok(Database {
         db: lmdb,
-        cache: Arc::new(cache),
+        cache: ChunkCache::new(get_global_config().database.cache_size_mb as usize * 1024 * 1024),
+        chunk_locks: (0..chunks::CHUNK_LOCKS)
+            .map(|_| tokio::sync::Mutex::new(()))
+            .collect(),
     })
 }
 
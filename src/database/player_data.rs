@@ -0,0 +1,52 @@
+use heed::types::{Bytes, Str};
+
+use super::spawn_blocking_db;
+use crate::database::Database;
+use crate::utils::error::Error;
+
+/// The table for things saved about each player, keyed by their uuid.
+pub(super) const PLAYER_DATA_TABLE: &str = "player_data";
+
+impl Database {
+    /// Reads what was saved about a player with [Database::set_player_data]. `None` if they've
+    /// never played here.
+    pub async fn get_player_data(&self, uuid: u128) -> Result<Option<Vec<u8>>, Error> {
+        let db = self.db.clone();
+        let tsk_db = self.db.clone();
+        let value = spawn_blocking_db(tsk_db, move || {
+            let ro_tx = db.read_txn()?;
+            let table = db
+                .open_database::<Str, Bytes>(&ro_tx, Some(PLAYER_DATA_TABLE))?
+                .expect(
+                    "No table \"player_data\" found. The database should have been initialized",
+                );
+            Ok(table.get(&ro_tx, &player_key(uuid))?.map(<[u8]>::to_vec))
+        })
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))??;
+        Ok(value)
+    }
+
+    /// Saves something about a player, replacing what was saved for them before.
+    pub async fn set_player_data(&self, uuid: u128, value: Vec<u8>) -> Result<(), Error> {
+        let db = self.db.clone();
+        let tsk_db = self.db.clone();
+        spawn_blocking_db(tsk_db, move || {
+            let mut rw_tx = db.write_txn()?;
+            let table = db
+                .open_database::<Str, Bytes>(&rw_tx, Some(PLAYER_DATA_TABLE))?
+                .expect(
+                    "No table \"player_data\" found. The database should have been initialized",
+                );
+            table.put(&mut rw_tx, &player_key(uuid), &value)?;
+            rw_tx.commit()
+        })
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))??;
+        Ok(())
+    }
+}
+
+fn player_key(uuid: u128) -> String {
+    format!("{:032x}", uuid)
+}
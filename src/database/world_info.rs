@@ -0,0 +1,46 @@
+use heed::types::{Bytes, Str};
+
+use super::spawn_blocking_db;
+use crate::database::Database;
+use crate::utils::error::Error;
+
+/// The table for things saved about the world as a whole, like its time.
+pub(super) const WORLD_INFO_TABLE: &str = "world_info";
+
+impl Database {
+    /// Reads something saved about the world with [Database::set_world_info]. `None` if it was
+    /// never saved.
+    pub async fn get_world_info(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        let key = key.to_string();
+        let db = self.db.clone();
+        let tsk_db = self.db.clone();
+        let value = spawn_blocking_db(tsk_db, move || {
+            let ro_tx = db.read_txn()?;
+            let table = db
+                .open_database::<Str, Bytes>(&ro_tx, Some(WORLD_INFO_TABLE))?
+                .expect("No table \"world_info\" found. The database should have been initialized");
+            Ok(table.get(&ro_tx, &key)?.map(<[u8]>::to_vec))
+        })
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))??;
+        Ok(value)
+    }
+
+    /// Saves something about the world, replacing what was saved under `key` before.
+    pub async fn set_world_info(&self, key: &str, value: Vec<u8>) -> Result<(), Error> {
+        let key = key.to_string();
+        let db = self.db.clone();
+        let tsk_db = self.db.clone();
+        spawn_blocking_db(tsk_db, move || {
+            let mut rw_tx = db.write_txn()?;
+            let table = db
+                .open_database::<Str, Bytes>(&rw_tx, Some(WORLD_INFO_TABLE))?
+                .expect("No table \"world_info\" found. The database should have been initialized");
+            table.put(&mut rw_tx, &key, &value)?;
+            rw_tx.commit()
+        })
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))??;
+        Ok(())
+    }
+}
@@ -2,11 +2,12 @@ use crate::utils::prelude::*;
 use std::any::TypeId;
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::sync::Arc;
 
 use crate::ecs::error::Error;
 use crate::ecs::helpers::sparse_set::SparseSet;
 use dashmap::DashMap;
-use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tokio::sync::{OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock};
 
 /// A trait for components in the ECS.
 pub trait Component: 'static + Send + Sync + Debug {}
@@ -20,8 +21,8 @@ pub trait Component: 'static + Send + Sync + Debug {}
 /// ```
 #[derive(Debug)]
 pub struct ComponentRef<'a, T: Component + 'a> {
-    read_guard: RwLockReadGuard<'a, Box<dyn Component>>,
-    _phantom: PhantomData<T>,
+    read_guard: OwnedRwLockReadGuard<Box<dyn Component>>,
+    _phantom: PhantomData<&'a T>,
 }
 
 /// A mutable reference to a component.
@@ -33,8 +34,8 @@ pub struct ComponentRef<'a, T: Component + 'a> {
 /// ```
 #[derive(Debug)]
 pub struct ComponentRefMut<'a, T: Component> {
-    write_guard: RwLockWriteGuard<'a, Box<dyn Component>>,
-    _phantom: PhantomData<T>,
+    write_guard: OwnedRwLockWriteGuard<Box<dyn Component>>,
+    _phantom: PhantomData<&'a mut T>,
 }
 
 impl<'a, T: Component> std::ops::Deref for ComponentRef<'a, T> {
@@ -58,9 +59,13 @@ impl<'id, T: Component> std::ops::DerefMut for ComponentRefMut<'id, T> {
     }
 }
 
+/// A single component. Each one has its own lock, which is shared out of the storage so it
+/// can be waited on without holding the storage's shard locked.
+type ComponentCell = Arc<RwLock<Box<dyn Component>>>;
+
 /// A storage structure for components in the ECS.
 pub struct ComponentStorage {
-    storages: DashMap<TypeId, SparseSet<RwLock<Box<dyn Component>>>>,
+    storages: DashMap<TypeId, SparseSet<ComponentCell>>,
 }
 
 // New + Insert
@@ -86,7 +91,7 @@ impl ComponentStorage {
             .unwrap();
         let type_id = TypeId::of::<T>();
         let mut storage = self.storages.entry(type_id).or_insert_with(SparseSet::new);
-        storage.insert(entity_id, RwLock::new(Box::new(component)));
+        storage.insert(entity_id, Arc::new(RwLock::new(Box::new(component))));
         self
     }
 }
@@ -110,23 +115,11 @@ impl ComponentStorage {
         &self,
         entity_id: impl TryInto<usize>,
     ) -> Result<ComponentRef<'a, T>> {
-        let type_id = TypeId::of::<T>();
         let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
-        let storage = self
-            .storages
-            .get(&type_id)
-            .ok_or(Error::ComponentNotFound)?;
-        let component = storage.get(entity_id).ok_or(Error::ComponentNotFound)?;
-
-        let read_guard = unsafe {
-            std::mem::transmute::<
-                RwLockReadGuard<'_, Box<dyn Component>>,
-                RwLockReadGuard<'_, Box<dyn Component>>,
-            >(component.read().await)
-        };
+        let component = self.cell::<T>(entity_id)?;
 
         Ok(ComponentRef {
-            read_guard,
+            read_guard: component.read_owned().await,
             _phantom: PhantomData,
         })
     }
@@ -142,27 +135,52 @@ impl ComponentStorage {
         &self,
         entity_id: impl TryInto<usize>,
     ) -> Result<ComponentRefMut<T>> {
-        let type_id = TypeId::of::<T>();
         let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
+        let component = self.cell::<T>(entity_id)?;
+
+        Ok(ComponentRefMut {
+            write_guard: component.write_owned().await,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// The cell holding the entity's `T`. The storage is only locked while it's looked up, so
+    /// nobody else has to wait while the component's lock is awaited.
+    fn cell<T: Component>(&self, entity_id: usize) -> Result<ComponentCell> {
         let storage = self
             .storages
-            .get(&type_id)
+            .get(&TypeId::of::<T>())
             .ok_or(Error::ComponentNotFound)?;
         let component = storage.get(entity_id).ok_or(Error::ComponentNotFound)?;
+        Ok(component.clone())
+    }
+}
 
-        let write = component.write().await;
-
-        let write_guard = unsafe {
-            std::mem::transmute::<
-                RwLockWriteGuard<'_, Box<dyn Component>>,
-                RwLockWriteGuard<Box<dyn Component>>,
-            >(write)
-        };
+// Entities
+impl ComponentStorage {
+    /// The entities that have a `T`, as they are right now.
+    pub fn entities_with<T: Component>(&self) -> Vec<usize> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .map(|storage| storage.iter().map(|(&entity_id, _)| entity_id).collect())
+            .unwrap_or_default()
+    }
 
-        Ok(ComponentRefMut {
-            write_guard,
-            _phantom: PhantomData,
-        })
+    /// Every entity that has any component, as they are right now.
+    pub fn entities(&self) -> Vec<usize> {
+        let mut entities = self
+            .storages
+            .iter()
+            .flat_map(|storage| {
+                storage
+                    .iter()
+                    .map(|(&entity_id, _)| entity_id)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        entities.sort_unstable();
+        entities.dedup();
+        entities
     }
 }
 
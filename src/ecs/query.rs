@@ -12,12 +12,20 @@ pub trait QueryItem {
         entity_id: impl Into<usize>,
         storage: &'a ComponentStorage,
     ) -> Result<Self::Item<'a>>;
+
+    /// The only entities that can match, or `None` if any entity can, e.g. for optional
+    /// components. Lets queries skip looking at every entity there is.
+    fn candidates(storage: &ComponentStorage) -> Option<Vec<usize>>;
 }
 
 // Implement QueryItem for immutable references
 impl<T: Component> QueryItem for &T {
     type Item<'a> = ComponentRef<'a, T>;
 
+    fn candidates(storage: &ComponentStorage) -> Option<Vec<usize>> {
+        Some(storage.entities_with::<T>())
+    }
+
     async fn fetch<'a>(
         entity_id: impl Into<usize>,
         storage: &'a ComponentStorage,
@@ -30,6 +38,10 @@ impl<T: Component> QueryItem for &T {
 impl<T: Component> QueryItem for &mut T {
     type Item<'a> = ComponentRefMut<'a, T>;
 
+    fn candidates(storage: &ComponentStorage) -> Option<Vec<usize>> {
+        Some(storage.entities_with::<T>())
+    }
+
     async fn fetch<'a>(
         entity_id: impl Into<usize>,
         storage: &'a ComponentStorage,
@@ -83,16 +95,7 @@ impl<'a, Q: QueryItem> Query<'a, Q> {
     /// }
     /// ```
     pub async fn iter(&'a self) -> impl Iterator<Item = (usize, Q::Item<'a>)> + 'a {
-        let max_entity_id = self.entity_manager.len().await;
-        let mut results = vec![];
-
-        for entity_id in 0..=max_entity_id {
-            if let Ok(item) = Q::fetch(entity_id, self.component_storage).await {
-                results.push((entity_id, item));
-            }
-        }
-
-        results.into_iter()
+        self.component_storage.query::<Q>().await.into_iter()
     }
 
     /// Returns the next query result.
@@ -128,6 +131,47 @@ impl<'a, Q: QueryItem> Query<'a, Q> {
     }
 }
 
+impl ComponentStorage {
+    /// Every entity that has all of `Q`'s components, along with them.
+    ///
+    /// The entities are looked up first, and each one's components are fetched after, so the
+    /// storage itself is never locked while waiting on a component. The results hold their
+    /// components' locks until they're dropped though, so don't keep them around across awaits
+    /// that could need the same components.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// for (entity_id, (position, player)) in storage.query::<(&Position, &Player)>().await {
+    ///     println!("{} is at {:?}", player.username, *position);
+    /// }
+    /// ```
+    pub async fn query<Q: QueryItem>(&self) -> Vec<(usize, Q::Item<'_>)> {
+        let candidates = Q::candidates(self).unwrap_or_else(|| self.entities());
+        let mut results = Vec::with_capacity(candidates.len());
+        for entity_id in candidates {
+            // Entities can lose components while the query runs, and are skipped if they do.
+            if let Ok(item) = Q::fetch(entity_id, self).await {
+                results.push((entity_id, item));
+            }
+        }
+        results
+    }
+
+    /// Every entity's `T`, to be changed in place.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// for (_, mut health) in storage.query_mut::<Health>().await {
+    ///     health.heal(1.0);
+    /// }
+    /// ```
+    pub async fn query_mut<T: Component>(&self) -> Vec<(usize, ComponentRefMut<'_, T>)> {
+        self.query::<&mut T>().await
+    }
+}
+
 // Macro to automatically generate tuples
 macro_rules! impl_query_item_tuple {
     ($($T: ident), *) => {
@@ -145,6 +189,14 @@ macro_rules! impl_query_item_tuple {
                     )*
                 ))
             }
+
+            // The fewest entities any of the components has.
+            fn candidates(storage: &ComponentStorage) -> Option<Vec<usize>> {
+                [$($T::candidates(storage),)*]
+                    .into_iter()
+                    .flatten()
+                    .min_by_key(|candidates| candidates.len())
+            }
         }
     };
 }
@@ -171,6 +223,10 @@ mod helpers {
             let component = T::fetch(entity_id, storage).await;
             Ok(component.ok())
         }
+
+        fn candidates(_: &ComponentStorage) -> Option<Vec<usize>> {
+            None
+        }
     }
 
     impl<T: Component> Component for Option<T> {}
@@ -193,6 +249,48 @@ mod tests {
         assert_eq!(component.unwrap().x, 0);
     }
 
+    #[tokio::test]
+    async fn test_storage_query() {
+        let storage = ComponentStorage::new();
+        storage.insert(3usize, Position { x: 3, y: 0, z: 0 });
+        storage.insert(3usize, Velocity { x: 1, y: 0, z: 0 });
+        storage.insert(7usize, Position { x: 7, y: 0, z: 0 });
+        storage.insert(9usize, Velocity { x: 1, y: 0, z: 0 });
+
+        let mut both = storage
+            .query::<(&Position, &Velocity)>()
+            .await
+            .into_iter()
+            .map(|(entity_id, (position, _))| (entity_id, position.x))
+            .collect::<Vec<_>>();
+        both.sort();
+        assert_eq!(both, vec![(3, 3)]);
+
+        let mut optional = storage
+            .query::<(&Position, Option<&Velocity>)>()
+            .await
+            .into_iter()
+            .map(|(entity_id, (_, velocity))| (entity_id, velocity.is_some()))
+            .collect::<Vec<_>>();
+        optional.sort();
+        assert_eq!(optional, vec![(3, true), (7, false)]);
+
+        assert_eq!(storage.query::<Option<&Velocity>>().await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_query_mut() {
+        let storage = ComponentStorage::new();
+        storage.insert(0usize, Position { x: 0, y: 0, z: 0 });
+        storage.insert(5usize, Position { x: 5, y: 0, z: 0 });
+
+        for (_, mut position) in storage.query_mut::<Position>().await {
+            position.y += 1;
+        }
+        assert_eq!(storage.get::<Position>(5usize).await.unwrap().y, 1);
+        assert_eq!(storage.get::<Position>(0usize).await.unwrap().y, 1);
+    }
+
     #[tokio::test]
     async fn test_iter() {
         let storage = ComponentStorage::new();
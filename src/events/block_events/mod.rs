@@ -0,0 +1,68 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::utils::encoding::position::Position;
+use crate::world::chunk_format::Palette;
+
+/// Dispatched when a player breaks a block, before it's removed from the world. Handlers can
+/// cancel it to keep the block, e.g. to protect an area.
+///
+/// Dispatch it with [crate::events::creation::dispatcher::EventDispatcherExt::dispatch_shared_event]
+/// to see whether it was cancelled.
+pub struct BlockBreakEvent {
+    pub entity_id: usize,
+    pub position: Position,
+    pub block: Palette,
+    cancelled: AtomicBool,
+}
+
+impl BlockBreakEvent {
+    pub fn new(entity_id: usize, position: Position, block: Palette) -> Self {
+        Self {
+            entity_id,
+            position,
+            block,
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    /// Keeps the block where it is. Handlers that run later still see the event.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Dispatched when a player places a block, before it's put in the world. Handlers can cancel it,
+/// or look at which block it is.
+///
+/// Dispatch it with [crate::events::creation::dispatcher::EventDispatcherExt::dispatch_shared_event]
+/// to see whether it was cancelled.
+pub struct BlockPlaceEvent {
+    pub entity_id: usize,
+    pub position: Position,
+    pub block: Palette,
+    cancelled: AtomicBool,
+}
+
+impl BlockPlaceEvent {
+    pub fn new(entity_id: usize, position: Position, block: Palette) -> Self {
+        Self {
+            entity_id,
+            position,
+            block,
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    /// Stops the block from being placed. Handlers that run later still see the event.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Dispatched when a player sends a chat message, before it's broadcast. Handlers can change the
+/// message, or cancel it so nobody sees it.
+///
+/// Dispatch it with [crate::events::creation::dispatcher::EventDispatcherExt::dispatch_shared_event]
+/// to see what the handlers did with it.
+pub struct PlayerChatEvent {
+    pub entity_id: usize,
+    pub username: String,
+    message: Mutex<String>,
+    cancelled: AtomicBool,
+}
+
+impl PlayerChatEvent {
+    pub fn new(entity_id: usize, username: String, message: String) -> Self {
+        Self {
+            entity_id,
+            username,
+            message: Mutex::new(message),
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    pub fn message(&self) -> String {
+        self.message
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn set_message(&self, message: impl Into<String>) {
+        *self
+            .message
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = message.into();
+    }
+
+    /// Stops the message from being broadcast. Handlers that run later still see it.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
@@ -14,15 +14,27 @@ impl EventDispatcher {
         let event = Arc::new(event);
         dispatch_event::<T>(event, state).await;
     }
+
+    /// Like [EventDispatcher::dispatch_event], but the caller keeps a handle to the event, so it
+    /// can see what the handlers changed once they've all run.
+    pub async fn dispatch_shared_event<T: 'static + Any + Send + Sync>(&self, event: Arc<T>, state: GlobalState) {
+        dispatch_event::<T>(event, state).await;
+    }
 }
 
 pub trait EventDispatcherExt {
     #[allow(async_fn_in_trait)]
     async fn dispatch_event<T: 'static + Any + Send + Sync>(&self, event: T);
+    #[allow(async_fn_in_trait)]
+    async fn dispatch_shared_event<T: 'static + Any + Send + Sync>(&self, event: Arc<T>);
 }
 
 impl EventDispatcherExt for GlobalState {
     async fn dispatch_event<T: 'static + Any + Send + Sync>(&self, event: T) {
         self.event_dispatcher.dispatch_event(event, self.clone()).await;
     }
+
+    async fn dispatch_shared_event<T: 'static + Any + Send + Sync>(&self, event: Arc<T>) {
+        self.event_dispatcher.dispatch_shared_event(event, self.clone()).await;
+    }
 }
\ No newline at end of file
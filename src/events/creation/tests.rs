@@ -55,7 +55,7 @@ async fn handler2(event: Arc<parking_lot::RwLock<TestEvent>>, _state: GlobalStat
 
 #[tokio::test]
 async fn test_if_this_even_compiles() -> anyhow::Result<()> {
-    let state = create_state(TcpListener::bind("0.0.0.0:9009").await?).await?;
+    let state = create_state(vec![TcpListener::bind("0.0.0.0:9009").await?]).await?;
     let some_event = TestEvent {
         value: 0,
     };
@@ -1,2 +1,6 @@
+pub mod block_events;
+pub mod chat_events;
 pub mod creation;
+pub mod player_events;
+pub mod server_events;
 pub mod world_events;
\ No newline at end of file
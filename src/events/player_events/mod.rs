@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::net::utils::damage::DamageSource;
+
+/// Dispatched when a player right-clicks another entity.
+///
+/// - `hand`: 0 for the main hand, 1 for the off hand.
+/// - `target_position`: Where on the entity it was clicked, relative to its position. Only sent
+///   for some clicks.
+pub struct PlayerInteractEntityEvent {
+    pub entity_id: usize,
+    pub target_id: usize,
+    pub hand: i32,
+    pub target_position: Option<(f32, f32, f32)>,
+    pub sneaking: bool,
+}
+
+/// Dispatched when a player attacks another entity, before it's hurt. Handlers can cancel it,
+/// e.g. to stop players fighting near spawn.
+///
+/// Dispatch it with [crate::events::creation::dispatcher::EventDispatcherExt::dispatch_shared_event]
+/// to see whether it was cancelled.
+pub struct PlayerAttackEntityEvent {
+    pub entity_id: usize,
+    pub target_id: usize,
+    pub damage: f32,
+    cancelled: AtomicBool,
+}
+
+impl PlayerAttackEntityEvent {
+    pub fn new(entity_id: usize, target_id: usize, damage: f32) -> Self {
+        Self {
+            entity_id,
+            target_id,
+            damage,
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    /// Stops the target from being hurt. Handlers that run later still see the event.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Dispatched when a player's health runs out, before they're shown the death screen.
+pub struct PlayerDeathEvent {
+    pub entity_id: usize,
+    pub source: DamageSource,
+}
+
+impl PlayerDeathEvent {
+    pub fn new(entity_id: usize, source: DamageSource) -> Self {
+        Self { entity_id, source }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::state::GlobalState;
+use ferrumc_macros::event_handler;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// Dispatched once every player has been disconnected during shutdown. Anything that keeps state
+/// in memory should save it here.
+pub struct ServerShutdownEvent;
+
+#[event_handler]
+async fn save_chunks(_event: Arc<ServerShutdownEvent>, state: GlobalState) {
+    match state.database.save_chunks().await {
+        Ok(saved) => info!("Saved {} changed chunks", saved),
+        Err(e) => error!("Failed to save chunks: {:?}", e),
+    }
+}
+
+#[event_handler]
+async fn save_time(_event: Arc<ServerShutdownEvent>, state: GlobalState) {
+    if let Err(e) = state.save_time().await {
+        error!("Failed to save the world time: {:?}", e);
+    }
+}
+
+#[event_handler]
+async fn save_weather(_event: Arc<ServerShutdownEvent>, state: GlobalState) {
+    if let Err(e) = state.save_weather().await {
+        error!("Failed to save the weather: {:?}", e);
+    }
+}
+
+#[event_handler]
+async fn save_border(_event: Arc<ServerShutdownEvent>, state: GlobalState) {
+    if let Err(e) = state.save_border().await {
+        error!("Failed to save the world border: {:?}", e);
+    }
+}
+
+/// Runs last, so everything saved by the other handlers makes it to disk.
+#[event_handler(priority = "slowest")]
+async fn on_server_shutdown(_event: Arc<ServerShutdownEvent>, state: GlobalState) {
+    if let Err(e) = state.database.sync().await {
+        error!("Failed to flush the database: {:?}", e);
+        return;
+    }
+
+    info!("Database flushed to disk");
+}
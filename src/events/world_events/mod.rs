@@ -1,5 +1,19 @@
 use crate::state::GlobalState;
+use crate::net::utils::player_list::add_to_player_list;
+use crate::net::utils::tab_list::refresh_tab_list;
+use crate::net::utils::visibility::update_visible_players;
+use crate::net::ConnectionExt;
+use crate::utils::components::exact_position::ExactPosition;
+use crate::utils::components::last_sent_movement::LastSentMovement;
 use crate::utils::components::player::{Player};
+use crate::utils::components::rotation::Rotation;
+use crate::utils::components::visible_entities::VisibleEntities;
+use crate::utils::config::get_global_config;
+use crate::utils::placeholders::{fill_placeholders, Placeholders};
+use crate::utils::text_component::TextComponent;
+use crate::utils::title::Title;
+use crate::world::dimensions::Dimension;
+use crate::world::weather::Weather;
 use ferrumc_macros::{event_handler, Constructor};
 use std::sync::Arc;
 use tracing::{error, info};
@@ -9,11 +23,91 @@ pub struct PlayerJoinWorldEvent {
     entity_id: usize,
 }
 
+/// Dispatched when a player that joined the world disconnects or is kicked, before any of their
+/// components are removed.
+#[derive(Constructor)]
+pub struct PlayerLeaveWorldEvent {
+    pub entity_id: usize,
+}
+
+/// Dispatched when the weather in `dimension` runs out and turns into the next, or is set with
+/// [crate::state::ServerState::set_weather]. It's already changed by then.
+///
+/// - `from`: What the weather was.
+/// - `to`: What it is now, which may be the same when it was set.
+/// - `duration`: How many ticks it lasts.
+#[derive(Constructor)]
+pub struct WeatherChangeEvent {
+    pub dimension: Arc<Dimension>,
+    pub from: Weather,
+    pub to: Weather,
+    pub duration: u32,
+}
+
 #[event_handler(priority = "slow")]
 async fn on_player_join_world(event: Arc<PlayerJoinWorldEvent>, state: GlobalState) {
-    if let Err(e) = send_join_message(event.entity_id, state).await {
+    if let Err(e) = send_join_message(event.entity_id, state.clone()).await {
         error!("Failed to send join message: {:?}", e);
     }
+
+    if let Err(e) = start_tracking_movement(event.entity_id, &state).await {
+        error!("Failed to start tracking movement: {:?}", e);
+    }
+    state
+        .world
+        .get_component_storage()
+        .insert(event.entity_id, VisibleEntities::default());
+    // Players have to be in the tab list before they can be spawned.
+    if let Err(e) = add_to_player_list(event.entity_id, &state).await {
+        error!("Failed to add the player to the player list: {:?}", e);
+    }
+    if let Err(e) = update_visible_players(event.entity_id, &state).await {
+        error!("Failed to show players to each other: {:?}", e);
+    }
+    // Everyone's player count just went up.
+    refresh_tab_list(&state).await;
+    if let Err(e) = state.send_time(event.entity_id).await {
+        error!("Failed to send the time: {:?}", e);
+    }
+    if let Err(e) = state.send_weather(event.entity_id).await {
+        error!("Failed to send the weather: {:?}", e);
+    }
+    if let Err(e) = state.send_commands(event.entity_id).await {
+        error!("Failed to send the commands: {:?}", e);
+    }
+    if let Err(e) = state.send_health(event.entity_id).await {
+        error!("Failed to send the health: {:?}", e);
+    }
+    if let Err(e) = state.send_experience(event.entity_id).await {
+        error!("Failed to send the experience: {:?}", e);
+    }
+    if let Err(e) = show_welcome_title(event.entity_id, &state).await {
+        error!("Failed to show the welcome title: {:?}", e);
+    }
+}
+
+/// Shows the player the `welcome_title` from the config, if there is one.
+async fn show_welcome_title(entity_id: usize, state: &GlobalState) -> crate::Result<()> {
+    let config = &get_global_config().welcome_title;
+    if config.title.is_empty() {
+        return Ok(());
+    }
+    let username = state.world.get_component::<Player>(entity_id).await?.username.clone();
+    let values = Placeholders {
+        player: Some(&username),
+        ..Placeholders::current(state).await
+    };
+    let text = |template: &str| TextComponent::from_legacy(&fill_placeholders(template, &values));
+    let mut title = Title::new(text(&config.title));
+    if !config.subtitle.is_empty() {
+        title = title.subtitle(text(&config.subtitle));
+    }
+    title.fade_in = config.fade_in;
+    title.stay = config.stay;
+    title.fade_out = config.fade_out;
+
+    let conn = state.connections.get_connection(entity_id)?;
+    conn.show_title(&title).await
 }
 
 async fn send_join_message(entity_id: usize, state: GlobalState) -> crate::Result<()> {
@@ -22,4 +116,27 @@ async fn send_join_message(entity_id: usize, state: GlobalState) -> crate::Resul
     info!("{} joined the world!", player.get_username());
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Players are spawned for others where they are when they join, so their moves are sent from
+/// there.
+async fn start_tracking_movement(entity_id: usize, state: &GlobalState) -> crate::Result<()> {
+    let component_storage = state.world.get_component_storage();
+    let movement = {
+        let position = component_storage.get::<ExactPosition>(entity_id).await?;
+        let rotation = component_storage.get::<Rotation>(entity_id).await?;
+        LastSentMovement::exact(position.0, &rotation)
+    };
+    component_storage.insert(entity_id, movement);
+    Ok(())
+}
+
+#[event_handler(priority = "slow")]
+async fn on_player_leave_world(event: Arc<PlayerLeaveWorldEvent>, state: GlobalState) {
+    if let Ok(player) = state.world.get_component::<Player>(event.entity_id).await {
+        info!("{} left the world!", player.get_username());
+    }
+    if let Err(e) = state.save_player_data(event.entity_id).await {
+        error!("Failed to save the player's data: {:?}", e);
+    }
+}
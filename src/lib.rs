@@ -1,22 +1,41 @@
 #![feature(box_into_inner)]
 
-use std::sync::{atomic::AtomicU32, Arc};
+use std::sync::{atomic::AtomicBool, atomic::AtomicU32, Arc};
 
 use dashmap::DashMap;
 use ecs::world::World;
 use net::ConnectionList;
 use state::{GlobalState, ServerState};
 use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
 use utils::prelude::*;
+use crate::commands::CommandDispatcher;
 use crate::events::creation::dispatcher::EventDispatcher;
+use crate::net::utils::entity_ids::EntityIdAllocator;
+use crate::net::utils::network_stats::NetworkCounters;
+use crate::net::utils::ping::PendingPings;
+use crate::utils::ban_list::BanList;
+use crate::utils::config::get_global_config;
+use crate::utils::plugin_channels::PluginChannels;
+use crate::utils::scheduler::Scheduler;
+use crate::utils::constants::{DEFAULT_BANS_FILE, DEFAULT_SKIN_CACHE_FILE, DEFAULT_WHITELIST_FILE};
+use crate::utils::skin_cache::SkinCache;
+use crate::utils::whitelist::PlayerWhitelist;
+use crate::world::chunk_service::ChunkService;
+use crate::utils::tick_rate::TickRate;
+use crate::world::border::{load_border, SharedWorldBorder};
+use crate::world::dimensions::Dimensions;
 
 extern crate core;
 #[macro_use]
 extern crate macro_rules_attribute;
 
+pub mod commands;
 pub mod ecs;
 pub mod net;
 pub mod setup;
+pub mod shutdown;
 #[cfg(test)]
 mod tests;
 pub mod utils;
@@ -26,15 +45,37 @@ pub mod state;
 pub mod world;
 pub mod events;
 
-pub async fn create_state(tcp_listener: TcpListener) -> Result<GlobalState> {
+pub async fn create_state(tcp_listeners: Vec<TcpListener>) -> Result<GlobalState> {
+    let database = database::start_database().await?;
+    let dimensions = Dimensions::load(get_global_config(), &database).await?;
+    let border = load_border(&database).await?;
     Ok(Arc::new(ServerState {
         world: Arc::new(World::new()),
         connections: ConnectionList {
             connections: DashMap::new(),
             connection_count: AtomicU32::new(0),
+            player_count: AtomicU32::new(0),
+            sessions: Default::default(),
         },
-        database: database::start_database().await?,
-        server_stream: tcp_listener,
+        database,
+        dimensions,
+        chunks: ChunkService::new(num_cpus::get()),
+        server_streams: tcp_listeners,
         event_dispatcher: Arc::new(EventDispatcher::new()),
+        entity_ids: EntityIdAllocator::new(),
+        bans: BanList::load(DEFAULT_BANS_FILE)?,
+        whitelist: PlayerWhitelist::load(DEFAULT_WHITELIST_FILE)?,
+        skins: SkinCache::load(DEFAULT_SKIN_CACHE_FILE)?,
+        commands: CommandDispatcher::with_builtin_commands(),
+        plugin_channels: PluginChannels::with_builtin_channels(),
+        pending_pings: Arc::new(PendingPings::new()),
+        scheduler: Scheduler::with_builtin_systems(get_global_config().tick_rate),
+        tick_rate: TickRate::new(),
+        border: SharedWorldBorder::new(border),
+        allow_flight: AtomicBool::new(get_global_config().abilities.allow_flight),
+        saving: AtomicBool::new(false),
+        shutdown: CancellationToken::new(),
+        connection_tasks: TaskTracker::new(),
+        network_totals: Arc::new(NetworkCounters::default()),
     }))
 }
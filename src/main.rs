@@ -1,9 +1,14 @@
 use std::env;
 use std::process::exit;
 
+use ferrumc::net::utils::packet_dump;
+use ferrumc::net::utils::socket_options::bind_listener;
+use ferrumc::shutdown::shutdown;
+use ferrumc::state::GlobalState;
 use ferrumc::{create_state, setup, utils, world};
-use tokio::net::TcpListener;
 use tokio::select;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
 use tokio::task::JoinHandle;
 use tracing::{error, info, trace};
 
@@ -25,6 +30,15 @@ async fn main() {
 async fn entry() -> Result<()> {
     utils::setup_logger()?;
 
+    // Reading a packet dump doesn't need a config or a running server.
+    if env::args().any(|arg| arg == "dump-replay") {
+        match env::args().skip_while(|arg| arg != "dump-replay").nth(1) {
+            Some(path) => packet_dump::replay(path).await?,
+            None => error!("Usage: ferrumc dump-replay <file>"),
+        }
+        return Ok(());
+    }
+
     if setup::handle_setup().await? {
         return Ok(());
     }
@@ -36,7 +50,15 @@ async fn entry() -> Result<()> {
         let _ = ServerConfig::new()?;
     }
 
-    let server_handle = start_server().await?;
+    // Load the favicon up front so a bad icon is reported at startup rather than on the first ping.
+    utils::config::get_favicon();
+
+    // Generating the RSA keypair takes a moment, so don't make the first player wait for it.
+    if get_global_config().online_mode {
+        ferrumc::net::utils::authentication::get_server_key()?;
+    }
+
+    let (server_handle, state) = start_server().await?;
 
     let need_to_kill = select! {
         server_result = server_handle => {
@@ -51,13 +73,14 @@ async fn entry() -> Result<()> {
             }
             false
         },
-        _ = tokio::signal::ctrl_c() => {
-            info!("Received ctrl+c.. Shutting down..");
+        _ = shutdown_signal() => {
+            info!("Received shutdown signal.. Shutting down..");
             true
         }
     };
 
     if need_to_kill {
+        shutdown(state).await?;
         kill_all_systems().await?;
     }
 
@@ -66,25 +89,61 @@ async fn entry() -> Result<()> {
     Ok(())
 }
 
+/// Waits for ctrl+c, or SIGTERM on unix.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let Ok(mut sigterm) = signal(SignalKind::terminate()) else {
+            error!("Failed to listen for SIGTERM");
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        };
+        select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = sigterm.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
 /// Starts the server. Sets up the sockets and listens for incoming connections
 ///
 /// The actual management of connections tx/rx is handled by [net::systems::connection_handler]
-async fn start_server() -> Result<JoinHandle<Result<()>>> {
+async fn start_server() -> Result<(JoinHandle<Result<()>>, GlobalState)> {
     let config = get_global_config();
-    trace!("Starting server on {}:{}", config.host, config.port);
-
-    let tcp_addr = format!("{}:{}", config.host, config.port);
+    let tcp_addrs = config.bind_addresses();
+    trace!("Starting server on {:?}", tcp_addrs);
 
-    let Ok(listener) = TcpListener::bind(tcp_addr.clone()).await else {
-        error!("Failed to bind to address: {}", &tcp_addr);
-        error!("Perhaps the port {} is already in use?", &config.port);
+    if tcp_addrs.is_empty() {
+        error!("There are no addresses to bind to, check the host in the config");
+        return Err(Error::TcpError("No addresses to bind to".to_string()));
+    }
 
-        return Err(Error::TcpError("Failed to bind to address".to_string()));
-    };
+    let mut listeners = Vec::with_capacity(tcp_addrs.len());
+    for tcp_addr in &tcp_addrs {
+        match bind_listener(tcp_addr, &config.network).await {
+            Ok(listener) => listeners.push(listener),
+            Err(e) => {
+                error!("Failed to bind to address {}: {}", tcp_addr, e);
+                error!("Perhaps the port is already in use?");
+
+                return Err(Error::TcpError(format!(
+                    "Failed to bind to address {}",
+                    tcp_addr
+                )));
+            }
+        }
+    }
 
-    let addr = listener.local_addr()?;
+    let addrs = listeners
+        .iter()
+        .map(|listener| listener.local_addr())
+        .collect::<std::io::Result<Vec<_>>>()?;
 
-    let state = create_state(listener).await?;
+    let state = create_state(listeners).await?;
 
     if env::args().any(|arg| arg == "--import") {
         // world::importing::import_regions(state.clone()).await?;
@@ -96,11 +155,12 @@ async fn start_server() -> Result<JoinHandle<Result<()>>> {
         exit(0);
     }
 
-    info!("Server started on {}", addr);
+    info!("Server started on {:?}", addrs);
 
     // Start all systems (separate task)
+    let systems_state = state.clone();
     let handle = tokio::task::spawn(async {
-        let all_systems = tokio::task::spawn(start_all_systems(state));
+        let all_systems = tokio::task::spawn(start_all_systems(systems_state));
 
         // Wait for all systems to finish
         all_systems.await??;
@@ -110,5 +170,5 @@ async fn start_server() -> Result<JoinHandle<Result<()>>> {
         Ok(())
     });
 
-    Ok(handle)
+    Ok((handle, state))
 }
@@ -1,6 +1,8 @@
 use std::cmp::PartialEq;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display};
 use std::io::Cursor;
+use std::net::SocketAddr;
 use std::sync::atomic::AtomicU32;
 use std::sync::{atomic, Arc};
 use std::time::Duration;
@@ -8,16 +10,45 @@ use std::time::Duration;
 use dashmap::DashMap;
 use ferrumc_codec::enc::NetEncode;
 use ferrumc_codec::network_types::varint::VarInt;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard};
-use tracing::{debug, error, trace};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::sync::{Mutex, MutexGuard, RwLock};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, trace, warn};
 
 use ferrumc_macros::Component;
 
-use crate::net::packets::{handle_packet, ConnectionId};
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::world_events::PlayerLeaveWorldEvent;
+use crate::net::packets::outgoing::clear_titles::ClearTitles;
+use crate::net::packets::outgoing::disconnect_login::DisconnectLogin;
+use crate::net::packets::outgoing::disconnect_play::DisconnectPlay;
+use crate::net::packets::outgoing::open_screen::OpenScreen;
+use crate::net::packets::outgoing::set_action_bar_text::SetActionBarText;
+use crate::net::packets::outgoing::set_container_content::SetContainerContent;
+use crate::net::packets::outgoing::system_chat_message::SystemChatMessage;
+use crate::net::packets::{handle_packet, is_packet_registered, ConnectionId};
+use crate::net::utils::compression::{compress_packets, decompress_packet};
+use crate::net::utils::encryption::{create_ciphers, Decryptor, EncryptedReader, EncryptedWriter};
+use crate::net::utils::legacy_ping::{is_legacy_ping, respond_to_legacy_ping};
+use crate::net::utils::network_stats::{count_packets, NetworkCounters};
+use crate::net::utils::packet_dump::PacketDump;
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::net::utils::packet_writer::{spawn_packet_writer, OutgoingMessage, PacketSender};
+use crate::net::utils::proxy::ForwardedPlayer;
+use crate::net::utils::rate_limiter::RateLimiter;
+use crate::net::utils::player_list::remove_from_player_list;
+use crate::net::utils::visibility::hide_from_everyone;
 use crate::state::GlobalState;
+use crate::utils::components::player::Player;
+use crate::utils::components::visible_entities::VisibleEntities;
+use crate::utils::constants::MAX_PACKET_LENGTH;
+use crate::utils::encoding::slot::Slot;
+use crate::utils::text_component::TextComponent;
+use crate::utils::title::Title;
+use crate::utils::window::{Window, WindowIds, MAX_ROWS};
 
-use super::utils::config::get_global_config;
+use super::utils::config::{get_global_config, DuplicateLoginPolicy};
 use super::utils::prelude::*;
 pub mod utils;
 // To allow implementing the `Component` trait for `Connection`. Since we can't implement a trait for a type defined in another crate.
@@ -75,6 +106,11 @@ pub struct ConnectionList {
     pub connections: DashMap<ConnectionId, Arc<RwLock<Connection>>>,
     // The number of connections.
     pub connection_count: AtomicU32,
+    // The number of connections taking up a player slot, so ones that are logging in or playing.
+    pub player_count: AtomicU32,
+    // The UUID and lowercase username of every connection that's logged in, see
+    // [ConnectionList::claim_session].
+    pub sessions: std::sync::Mutex<HashMap<ConnectionId, (u128, String)>>,
 }
 
 impl ConnectionList {
@@ -87,12 +123,71 @@ impl ConnectionList {
 
         Ok(conn.clone())
     }
+
+    /// Takes up one of the `max_players` player slots. Returns `false` if they're all taken.
+    ///
+    /// The check and the increment happen in one atomic step, so players logging in at the same
+    /// time can't both take the last slot.
+    pub fn try_reserve_player_slot(&self, max_players: u32) -> bool {
+        self.player_count
+            .fetch_update(atomic::Ordering::SeqCst, atomic::Ordering::SeqCst, |count| {
+                (count < max_players).then_some(count + 1)
+            })
+            .is_ok()
+    }
+
+    pub fn release_player_slot(&self) {
+        self.player_count.fetch_sub(1, atomic::Ordering::SeqCst);
+    }
+
+    /// Logs `conn_id` in as the player with `uuid` and `username`, following `policy` if they're
+    /// already online under either. Returns the connections they were already online on, which
+    /// have been logged out and need kicking, or `None` if the new login was turned away.
+    ///
+    /// The check and the login happen under one lock, so two logins at the same time can't both
+    /// get in as the same player.
+    pub fn claim_session(
+        &self,
+        conn_id: ConnectionId,
+        uuid: u128,
+        username: &str,
+        policy: DuplicateLoginPolicy,
+    ) -> Option<Vec<ConnectionId>> {
+        let username = username.to_lowercase();
+        let mut sessions = self
+            .sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let existing = sessions
+            .iter()
+            .filter(|&(&entity, (other_uuid, other_name))| {
+                entity != conn_id && (*other_uuid == uuid || *other_name == username)
+            })
+            .map(|(&entity, _)| entity)
+            .collect::<Vec<_>>();
+        if !existing.is_empty() && policy == DuplicateLoginPolicy::RejectNew {
+            return None;
+        }
+        for entity in &existing {
+            sessions.remove(entity);
+        }
+        sessions.insert(conn_id, (uuid, username));
+        Some(existing)
+    }
+
+    /// Logs a connection out once it's dropped, so the player can log in again.
+    pub fn end_session(&self, conn_id: ConnectionId) {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&conn_id);
+    }
 }
 
 /// A connection to a client.
 ///
 /// - `id`: The numerical ID for the connection. Is also the key for it's [ConnectionList] entry.
-/// - `socket`: The TCP socket for the connection ([tokio::net::TcpStream]).
+/// - `stream`: The read and write halves of the TCP socket ([NetStream]).
 /// - `player_uuid`: The UUID of the player, if the connection is authenticated ([uuid::Uuid]).
 /// - `state`: The current state of the connection ([State]).
 /// - `metadata`: Metadata for the connection ([ConnectionMetadata]).
@@ -100,22 +195,86 @@ impl ConnectionList {
 pub struct Connection {
     pub id: usize,
     // pub socket: tokio::net::TcpStream,
-    pub stream: NetStream,
+    pub stream: Arc<NetStream>,
     pub player_uuid: Option<uuid::Uuid>,
     pub state: State,
     pub metadata: ConnectionMetadata,
     pub drop: bool,
 }
 
+/// The two halves of a connection's socket. Both are encrypted once online mode authentication
+/// has finished, see [Connection::enable_encryption].
+///
+/// Packets aren't written to `out_stream` directly, but queued for the connection's writer task.
 pub struct NetStream {
-    pub in_stream: Mutex<tokio::net::tcp::OwnedReadHalf>,
-    pub out_stream: Mutex<tokio::net::tcp::OwnedWriteHalf>,
+    pub in_stream: Mutex<EncryptedReader<OwnedReadHalf>>,
+    pub out_stream: Arc<Mutex<EncryptedWriter<OwnedWriteHalf>>>,
+    // Kept outside the `in_stream` lock, since that one is held while waiting for packets.
+    decryptor: Arc<std::sync::Mutex<Option<Decryptor>>>,
+    outgoing: PacketSender,
+    /// Cancelled once the connection is closed, which stops its read loop and writer task.
+    pub closed: CancellationToken,
+}
+
+impl NetStream {
+    /// Splits `socket`, and spawns the writer task for the outgoing half.
+    pub fn new(socket: tokio::net::TcpStream) -> Self {
+        let (in_stream, out_stream) = socket.into_split();
+        let in_stream = EncryptedReader::new(in_stream);
+        let decryptor = in_stream.cipher();
+        let out_stream = Arc::new(Mutex::new(EncryptedWriter::new(out_stream)));
+
+        let network_config = &get_global_config().network;
+        let closed = CancellationToken::new();
+        let outgoing = spawn_packet_writer(
+            out_stream.clone(),
+            network_config.outgoing_queue_size,
+            Duration::from_secs(network_config.outgoing_queue_timeout),
+            closed.clone(),
+        );
+
+        Self {
+            in_stream: Mutex::new(in_stream),
+            out_stream,
+            decryptor,
+            outgoing,
+            closed,
+        }
+    }
 }
 
+/// - `username`: The username from the login start packet, kept while the client authenticates.
+/// - `verify_token`: The token sent in the encryption request, if one is pending.
+/// - `compression_threshold`: Set once compression has been turned on for the connection.
+/// - `has_player_slot`: Whether the connection took a player slot, which is given back on drop.
+/// - `forwarded`: The player info forwarded by a proxy, if the server is behind one.
+/// - `velocity_message_id`: The id of the pending Velocity player info request.
+/// - `remote_addr`: The client's address. Behind a PROXY protocol load balancer, this is the
+///   address from the PROXY header rather than the balancer's.
+/// - `skipped_packets`: How many packets the client sent that we have no handler for, by packet id.
+/// - `client_brand`: The brand the client announced, e.g. "vanilla" or "fabric".
+/// - `registered_channels`: The plugin channels the client said it can receive on.
+/// - `network`: How much traffic went over the connection, which also counts towards the server's
+///   totals.
+/// - `packet_dump`: Where every packet is captured, if `debug.packet_dump` is set.
+/// - `windows`: The ids of the windows opened for the player, and which one is open.
 #[derive(Debug, Default)]
 pub struct ConnectionMetadata {
     pub protocol_version: i32,
     pub entity: usize,
+    pub username: Option<String>,
+    pub verify_token: Option<Vec<u8>>,
+    pub compression_threshold: Option<usize>,
+    pub has_player_slot: bool,
+    pub forwarded: Option<ForwardedPlayer>,
+    pub velocity_message_id: Option<i32>,
+    pub remote_addr: Option<SocketAddr>,
+    pub skipped_packets: HashMap<u8, u32>,
+    pub client_brand: Option<String>,
+    pub registered_channels: HashSet<String>,
+    pub network: NetworkCounters,
+    pub packet_dump: Option<PacketDump>,
+    pub windows: WindowIds,
 }
 
 pub fn setup_tracer() {
@@ -125,22 +284,41 @@ pub fn setup_tracer() {
 /// Handles a connection. This is the main entry point for a connection.
 ///
 /// - `socket`: The TCP socket for the connection ([tokio::net::TcpStream]).
+/// - `remote_addr`: The client's address, which is the socket's peer address unless a load
+///   balancer told us otherwise.
 ///
 /// Creates a new [Connection] and adds it to the [ConnectionList]. Passes the connection to [manage_conn].
-pub async fn init_connection(socket: tokio::net::TcpStream, state: GlobalState) -> Result<()> {
+pub async fn init_connection(
+    socket: tokio::net::TcpStream,
+    remote_addr: SocketAddr,
+    state: GlobalState,
+) -> Result<()> {
     let entity_id = state.world.create_entity().await.build();
 
-    let (in_stream, out_stream) = socket.into_split();
+    let dump_directory = &get_global_config().debug.packet_dump;
+    let packet_dump = if dump_directory.is_empty() {
+        None
+    } else {
+        match PacketDump::create(dump_directory, entity_id).await {
+            Ok(dump) => Some(dump),
+            Err(e) => {
+                warn!("Failed to start a packet dump for {}: {}", entity_id, e);
+                None
+            }
+        }
+    };
 
     let conn = Connection {
         id: entity_id,
-        stream: NetStream {
-            in_stream: Mutex::new(in_stream),
-            out_stream: Mutex::new(out_stream),
-        },
+        stream: Arc::new(NetStream::new(socket)),
         player_uuid: None,
         state: State::Handshake,
-        metadata: ConnectionMetadata::default(),
+        metadata: ConnectionMetadata {
+            remote_addr: Some(remote_addr),
+            network: NetworkCounters::with_total(state.network_totals.clone()),
+            packet_dump,
+            ..Default::default()
+        },
         drop: false,
     };
 
@@ -171,9 +349,25 @@ pub async fn init_connection(socket: tokio::net::TcpStream, state: GlobalState)
         entity_id, current_amount
     );
 
-    let res = manage_conn(conn.clone(), state.clone()).await;
+    let closed = conn.read().await.stream.closed.clone();
+    let res = tokio::select! {
+        res = manage_conn(conn.clone(), state.clone()) => res,
+        _ = closed.cancelled() => Ok(()),
+    };
+
+    // The connection was closed from the outside, e.g. because its outgoing queue filled up.
+    if res.is_ok() && state.connections.connections.contains_key(&entity_id) {
+        debug!("Connection {} closed", entity_id);
+        return drop_conn(entity_id, state).await;
+    }
 
     if let Err(e) = res {
+        // The connection was already closed on purpose (e.g. after the status pong), so the
+        // read loop erroring out is expected.
+        if !state.connections.connections.contains_key(&entity_id) {
+            debug!("Connection {} closed", entity_id);
+            return Ok(());
+        }
         error!(
             "Error occurred in {:?}: {:?}, dropping connection",
             entity_id, e
@@ -192,29 +386,80 @@ pub async fn init_connection(socket: tokio::net::TcpStream, state: GlobalState)
 /// is generated at compile time by [ferrumc_macros::bake_packet_registry].
 pub async fn manage_conn(conn: Arc<RwLock<Connection>>, state: GlobalState) -> Result<()> {
     {
-        let local_addr = conn
-            .read()
-            .await
-            .stream
-            .in_stream
-            .lock()
-            .await
-            .peer_addr()?;
-        debug!("Starting receiver for the addr: {:?}", local_addr);
+        let remote_addr = conn.read().await.metadata.remote_addr;
+        debug!("Starting receiver for the addr: {:?}", remote_addr);
     }
 
-    loop {
-        // Get the length of the packet
-        let conn_read = conn.read().await;
+    if handle_legacy_ping(&conn, &state).await? {
+        let conn_id = conn.read().await.id;
+        return drop_conn(conn_id, state).await;
+    }
+
+    // Read from the stream without holding the connection lock, so packet handlers can still
+    // write to the connection while we're waiting for the client to send something.
+    let stream = conn.read().await.stream.clone();
+
+    let network_config = &get_global_config().network;
+    let mut rate_limiter = (network_config.max_packets_per_second > 0).then(|| {
+        RateLimiter::new(
+            network_config.max_packets_per_second,
+            network_config.packet_burst,
+        )
+    });
+
+    let max_packet_length = max_packet_length();
+    let packet_dump = conn.read().await.metadata.packet_dump.clone();
 
+    loop {
         trace!("Reading length buffer");
 
-        let (packet_length, buffer) = get_packet_length_and_buffer(&conn_read).await?;
+        // Get the length of the packet
+        let (packet_length, mut buffer) =
+            match get_packet_length_and_buffer(&stream, max_packet_length).await {
+                Ok(frame) => frame,
+                Err(Error::PacketTooLarge(length, max)) => {
+                    warn!(
+                        "Connection {} sent a packet of {} bytes, more than the maximum of {}",
+                        conn.read().await.id,
+                        length,
+                        max
+                    );
+                    conn.kick("Packet too large", state).await?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+
+        let conn_read = conn.read().await;
         let (conn_id, conn_state) = (conn_read.id, conn_read.state.clone());
-        // drop the handle to the write lock. to allow other tasks to write/read
-        // mainly cuz the packet tries to access ECS component. And some system tries to access connection turns into a deadlock!!
+        let compression_threshold = conn_read.metadata.compression_threshold;
+        conn_read
+            .metadata
+            .network
+            .record_read(packet_length.get_len() + buffer.len());
         drop(conn_read);
 
+        if let Some(threshold) = compression_threshold {
+            buffer = match decompress_packet(&buffer, threshold, max_packet_length).await {
+                Ok(buffer) => buffer,
+                Err(Error::PacketTooLarge(length, max)) => {
+                    warn!(
+                        "Connection {} sent a packet inflating to {} bytes, more than the maximum of {}",
+                        conn_id, length, max
+                    );
+                    conn.kick("Packet too large", state).await?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+        }
+
+        if let Some(packet_dump) = &packet_dump {
+            packet_dump
+                .record_inbound(conn_id, &conn_state, &buffer)
+                .await;
+        }
+
         trace!("Packet Length: {}", packet_length.get_val());
 
         let mut cursor = Cursor::new(buffer);
@@ -225,6 +470,32 @@ pub async fn manage_conn(conn: Arc<RwLock<Connection>>, state: GlobalState) -> R
 
         let packet_id = packet_id.get_val() as u8;
 
+        if let Some(rate_limiter) = rate_limiter.as_mut() {
+            if !is_rate_limit_exempt(packet_id, &conn_state) && !rate_limiter.try_acquire() {
+                warn!("Connection {} is sending too many packets, kicking", conn_id);
+                conn.kick("You are sending too many packets!", state).await?;
+                return Ok(());
+            }
+        }
+
+        // The whole frame has already been read, so skipping the packet keeps the stream aligned.
+        if !is_packet_registered(packet_id, &conn_state) {
+            trace!(
+                "Skipping unknown packet 0x{:02X} in state {} from connection {}",
+                packet_id,
+                conn_state,
+                conn_id
+            );
+            *conn
+                .write()
+                .await
+                .metadata
+                .skipped_packets
+                .entry(packet_id)
+                .or_default() += 1;
+            continue;
+        }
+
         let state_clone = state.clone();
         tokio::spawn(async move {
             handle_packet(packet_id, conn_id, &conn_state, &mut cursor, state_clone).await
@@ -244,13 +515,77 @@ pub async fn manage_conn(conn: Arc<RwLock<Connection>>, state: GlobalState) -> R
     #[allow(unreachable_code)]
     Ok(())
 }
+/// Packets that don't count towards the rate limit, so a lagging client catching up on them isn't
+/// kicked for it.
+fn is_rate_limit_exempt(packet_id: u8, conn_state: &State) -> bool {
+    const CONFIRM_TELEPORTATION: u8 = 0x00;
+    const KEEP_ALIVE: u8 = 0x12;
+    *conn_state == State::Play && matches!(packet_id, CONFIRM_TELEPORTATION | KEEP_ALIVE)
+}
+
+/// Checks if the connection was opened with a legacy (0xFE) server list ping, and answers it if so.
+///
+/// Has to happen before the regular packet reading starts, since legacy pings aren't VarInt framed.
+/// Returns `true` if a legacy ping was answered, in which case the connection should be closed.
+async fn handle_legacy_ping(conn: &Arc<RwLock<Connection>>, state: &GlobalState) -> Result<bool> {
+    let conn = conn.read().await;
+    let mut in_stream = conn.get_in_stream().await;
+
+    // Only what's arrived so far is looked at, same as vanilla, since legacy clients wait for an
+    // answer rather than sending anything more.
+    let mut start = [0u8; 3];
+    let read = in_stream.get_mut().peek(&mut start).await?;
+    if !is_legacy_ping(&start[..read]) {
+        return Ok(false);
+    }
+
+    debug!("Received legacy server list ping");
+
+    let config = get_global_config();
+    let motd = config.motd.first().map(String::as_str).unwrap_or_default();
+    let online_players = state.world.query::<&Player>().iter().await.count();
+
+    let mut out_stream = conn.get_out_stream().await;
+    respond_to_legacy_ping(
+        &mut *in_stream,
+        &mut *out_stream,
+        motd,
+        online_players,
+        config.max_players,
+    )
+    .await?;
+
+    Ok(true)
+}
+/// The largest packet clients can send, from the config but never above the protocol's limit.
+fn max_packet_length() -> usize {
+    (get_global_config().network.max_packet_size as usize).min(MAX_PACKET_LENGTH)
+}
+
 async fn get_packet_length_and_buffer(
-    conn: &RwLockReadGuard<'_, Connection>,
+    stream: &NetStream,
+    max_length: usize,
+) -> Result<(VarInt, Vec<u8>)> {
+    let mut in_stream = stream.in_stream.lock().await;
+    read_frame(&mut *in_stream, max_length).await
+}
+
+/// Reads one length-prefixed frame, returning the length and everything after it.
+///
+/// Always reads the full frame, whether or not the packet in it ends up being decoded. Frames
+/// longer than `max_length` are rejected before anything is allocated for them.
+async fn read_frame<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    max_length: usize,
 ) -> Result<(VarInt, Vec<u8>)> {
-    let mut conn = conn.get_in_stream().await;
-    let packet_length = VarInt::read(&mut *conn).await?;
-    let mut buffer = vec![0u8; packet_length.get_val() as usize];
-    conn.read_exact(&mut buffer).await?;
+    let packet_length = VarInt::read(reader).await?;
+    let length = usize::try_from(packet_length.get_val())
+        .map_err(|_| Error::InvalidPacketLength(packet_length.get_val()))?;
+    if length > max_length {
+        return Err(Error::PacketTooLarge(length, max_length));
+    }
+    let mut buffer = vec![0u8; length];
+    reader.read_exact(&mut buffer).await?;
     Ok((packet_length, buffer))
 }
 async fn drop_conn_if_flagged(conn: Arc<RwLock<Connection>>, state: GlobalState) -> Result<()> {
@@ -265,34 +600,98 @@ async fn drop_conn_if_flagged(conn: Arc<RwLock<Connection>>, state: GlobalState)
 
     Ok(())
 }
+/// Closes a connection and cleans up after it: players that joined the world are removed from
+/// everyone else's client, and the entity is deleted along with all its components.
+///
+/// Kicks and the read loop can both end up here for the same connection, so dropping one that's
+/// already gone does nothing.
 pub async fn drop_conn(connection_id: usize, state: GlobalState) -> Result<()> {
     debug!("Dropping connection with id: {}", connection_id);
     let connection = state.connections.connections.remove(&connection_id);
+    // Only the first drop gets the connection, so the rest, e.g. from the read loop erroring out
+    // after a kick, have nothing left to do.
     let Some((_, conn_arc)) = connection else {
-        return Err(Error::ConnectionNotFound(connection_id));
+        debug!("Connection {} was already dropped", connection_id);
+        return Ok(());
     };
     state
         .connections
         .connection_count
         .fetch_sub(1, atomic::Ordering::Relaxed);
+    state.connections.end_session(connection_id);
 
     {
         let read_lock = conn_arc.read().await;
+        if read_lock.metadata.has_player_slot {
+            state.connections.release_player_slot();
+        }
+        let stats = read_lock.metadata.network.snapshot();
+        debug!(
+            "Connection {} read {} bytes in {} packets, and wrote {} bytes in {} packets",
+            connection_id,
+            stats.bytes_read,
+            stats.packets_read,
+            stats.bytes_written,
+            stats.packets_written
+        );
+        if !read_lock.metadata.skipped_packets.is_empty() {
+            debug!(
+                "Connection {} sent packets without a handler: {:?}",
+                connection_id, read_lock.metadata.skipped_packets
+            );
+        }
         let entity_id = read_lock.id;
+        state.pending_pings.remove_connection(entity_id);
+        for dimension in state.dimensions.iter() {
+            dimension.players.remove(entity_id);
+        }
+        let joined_world = state
+            .world
+            .get_component::<VisibleEntities>(entity_id)
+            .await
+            .is_ok();
+        if joined_world {
+            let event = PlayerLeaveWorldEvent::new(entity_id);
+            state.dispatch_event(event).await;
+            hide_from_everyone(entity_id, &state).await;
+            remove_from_player_list(entity_id, &state).await;
+        }
+        // Only once everyone was told to remove the player, so nobody mixes them up with
+        // whoever gets the id next.
+        if let Ok(network_id) = state.network_id(entity_id).await {
+            state.entity_ids.release(network_id);
+        }
         state.world.delete_entity(entity_id).await?;
     }
 
-    // drop the connection in the end, just in case it errors out
-    let conn = conn_arc.read().await;
-    let mut conn = conn.get_out_stream().await;
-    conn.shutdown().await?;
+    // Close the connection in the end, once the writer task has sent everything that's queued.
+    let stream = conn_arc.read().await.stream.clone();
+    stream.outgoing.close().await;
     Ok(())
 }
 
 impl Connection {
+    /// Encodes `packet` and queues it for the connection's writer task. This only waits for the
+    /// socket if the queue is full.
     pub async fn send_packet(&self, packet: impl NetEncode) -> Result<()> {
-        let mut out_stream = self.get_out_stream().await;
-        packet.net_encode(&mut *out_stream).await?;
+        let mut buffer = Vec::new();
+        packet.net_encode(&mut buffer).await?;
+        let packets = count_packets(&buffer).await?;
+        if let Some(packet_dump) = &self.metadata.packet_dump {
+            packet_dump
+                .record_outbound(self.id, &self.state, &buffer)
+                .await;
+        }
+        if let Some(threshold) = self.metadata.compression_threshold {
+            buffer = compress_packets(&buffer, threshold).await?;
+        }
+
+        let bytes = buffer.len();
+        self.stream
+            .outgoing
+            .send(OutgoingMessage::Packet(buffer))
+            .await?;
+        self.metadata.network.record_written(bytes, packets);
         Ok(())
     }
 
@@ -301,15 +700,348 @@ impl Connection {
         self.send_packet(packets).await
     }
 
-    pub async fn get_in_stream(&self) -> MutexGuard<'_, tokio::net::tcp::OwnedReadHalf> {
+    pub async fn get_in_stream(&self) -> MutexGuard<'_, EncryptedReader<OwnedReadHalf>> {
         self.stream.in_stream.lock().await
     }
 
-    pub async fn get_out_stream(&self) -> MutexGuard<'_, tokio::net::tcp::OwnedWriteHalf> {
+    pub async fn get_out_stream(&self) -> MutexGuard<'_, EncryptedWriter<OwnedWriteHalf>> {
         self.stream.out_stream.lock().await
     }
 
+    /// Turns on AES/CFB8 encryption for everything sent and received from here on.
+    ///
+    /// Has to be called right after the encryption response is read, since the client starts
+    /// encrypting straight after sending it.
+    pub async fn enable_encryption(&self, shared_secret: &[u8]) -> Result<()> {
+        let (encryptor, decryptor) = create_ciphers(shared_secret)?;
+        *self
+            .stream
+            .decryptor
+            .lock()
+            .map_err(|_| Error::EncryptionError("Cipher lock poisoned".to_string()))? = Some(decryptor);
+        // Queued like a packet, so everything sent before this still goes out unencrypted.
+        self.stream
+            .outgoing
+            .send(OutgoingMessage::EnableEncryption(Box::new(encryptor)))
+            .await
+    }
+
     pub async fn drop_connection(&self, state: GlobalState) -> Result<()> {
         drop_conn(self.id, state).await
     }
 }
+
+/// Extra functionality for the shared connection handles stored in the [ConnectionList].
+pub trait ConnectionExt {
+    /// Sends `reason` to the client in the disconnect packet for its current state, then closes
+    /// the connection.
+    ///
+    /// Connections in the handshake or status state can't be shown a reason, so they're just closed.
+    #[allow(async_fn_in_trait)]
+    async fn kick(&self, reason: impl Into<TextComponent>, state: GlobalState) -> Result<()>;
+
+    /// Sends a message to the player's chat.
+    #[allow(async_fn_in_trait)]
+    async fn send_message(&self, message: impl Into<TextComponent>) -> Result<()>;
+
+    /// Shows a title in the middle of the player's screen, with its timings.
+    #[allow(async_fn_in_trait)]
+    async fn show_title(&self, title: &Title) -> Result<()>;
+
+    /// Takes away the title on the player's screen, if there is one.
+    #[allow(async_fn_in_trait)]
+    async fn clear_title(&self) -> Result<()>;
+
+    /// Shows a message above the player's hotbar for a few seconds.
+    #[allow(async_fn_in_trait)]
+    async fn action_bar(&self, message: impl Into<TextComponent>) -> Result<()>;
+
+    /// Opens a chest window with `rows` rows of 9 slots, holding `contents`. Missing slots are
+    /// left empty, and anything past the last slot is dropped.
+    ///
+    /// Returns the window that was opened, so clicks in it can be matched up with it.
+    #[allow(async_fn_in_trait)]
+    async fn open_inventory(
+        &self,
+        title: impl Into<TextComponent>,
+        rows: u8,
+        contents: Vec<Slot>,
+    ) -> Result<Window>;
+}
+
+impl ConnectionExt for Arc<RwLock<Connection>> {
+    async fn kick(&self, reason: impl Into<TextComponent>, state: GlobalState) -> Result<()> {
+        let reason = reason.into();
+
+        let conn = self.read().await;
+        let conn_id = conn.id;
+        debug!("Kicking connection {}: {}", conn_id, reason.text);
+        if let Some(packet) = encode_disconnect(&conn.state, reason).await? {
+            conn.send_packet(packet).await?;
+        }
+        drop(conn);
+
+        drop_conn(conn_id, state).await
+    }
+
+    async fn send_message(&self, message: impl Into<TextComponent>) -> Result<()> {
+        let conn = self.read().await;
+        conn.send_packet(SystemChatMessage::new(message)?).await
+    }
+
+    async fn show_title(&self, title: &Title) -> Result<()> {
+        // Encoded together, so nothing can be sent in between and change the timings.
+        let packets = title.encode().await?;
+        let conn = self.read().await;
+        conn.send_packet(packets).await
+    }
+
+    async fn clear_title(&self) -> Result<()> {
+        let conn = self.read().await;
+        conn.send_packet(ClearTitles::new(false)).await
+    }
+
+    async fn action_bar(&self, message: impl Into<TextComponent>) -> Result<()> {
+        let conn = self.read().await;
+        conn.send_packet(SetActionBarText::new(message)?).await
+    }
+
+    async fn open_inventory(
+        &self,
+        title: impl Into<TextComponent>,
+        rows: u8,
+        mut contents: Vec<Slot>,
+    ) -> Result<Window> {
+        if !(1..=MAX_ROWS).contains(&rows) {
+            return Err(Error::Generic(format!(
+                "Chest windows have 1 to {} rows, not {}",
+                MAX_ROWS, rows
+            )));
+        }
+        let window = self.write().await.metadata.windows.open(rows);
+        contents.resize(window.size(), Slot::EMPTY);
+
+        let mut packet_queue = PacketQueue::new();
+        packet_queue
+            .queue(OpenScreen::new(window.id, window.window_type(), title)?)
+            .await?;
+        packet_queue
+            .queue(SetContainerContent::new(window.id, 0, contents, Slot::EMPTY))
+            .await?;
+        let conn = self.read().await;
+        conn.send_packets(packet_queue).await?;
+        Ok(window)
+    }
+}
+
+/// Encodes the disconnect packet matching `conn_state`, or `None` if the state doesn't have one.
+async fn encode_disconnect(conn_state: &State, reason: TextComponent) -> Result<Option<Vec<u8>>> {
+    let mut packet = Vec::new();
+    match conn_state {
+        State::Login => DisconnectLogin::new(reason)?.net_encode(&mut packet).await?,
+        State::Play => DisconnectPlay::new(reason)?.net_encode(&mut packet).await?,
+        State::Unknown | State::Handshake | State::Status => return Ok(None),
+    }
+    Ok(Some(packet))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the packet id of an encoded packet. Only works for packets shorter than 128 bytes.
+    fn packet_id(packet: &[u8]) -> u8 {
+        packet[1]
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_packet_matches_state() {
+        let login = encode_disconnect(&State::Login, "Server is full".into())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(packet_id(&login), 0x00);
+
+        let play = encode_disconnect(&State::Play, "Kicked".into())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(packet_id(&play), 0x1A);
+    }
+
+    #[tokio::test]
+    async fn test_player_slots_are_not_oversold() {
+        const MAX_PLAYERS: u32 = 20;
+        let connections = Arc::new(ConnectionList {
+            connections: DashMap::new(),
+            connection_count: AtomicU32::new(0),
+            player_count: AtomicU32::new(0),
+            sessions: Default::default(),
+        });
+
+        // One more login than there are slots, all at once.
+        let logins = (0..=MAX_PLAYERS).map(|_| {
+            let connections = connections.clone();
+            tokio::spawn(async move { connections.try_reserve_player_slot(MAX_PLAYERS) })
+        });
+        let results = futures::future::join_all(logins).await;
+
+        let accepted = results.iter().filter(|r| *r.as_ref().unwrap()).count();
+        assert_eq!(accepted, MAX_PLAYERS as usize);
+        assert!(!connections.try_reserve_player_slot(MAX_PLAYERS));
+
+        connections.release_player_slot();
+        assert!(connections.try_reserve_player_slot(MAX_PLAYERS));
+    }
+
+    #[tokio::test]
+    async fn test_only_one_session_per_player() {
+        let connections = Arc::new(ConnectionList {
+            connections: DashMap::new(),
+            connection_count: AtomicU32::new(0),
+            player_count: AtomicU32::new(0),
+            sessions: Default::default(),
+        });
+
+        // The same player logging in on ten connections at once.
+        let logins = (0..10).map(|conn_id| {
+            let connections = connections.clone();
+            tokio::spawn(async move {
+                connections.claim_session(conn_id, 1, "Steve", DuplicateLoginPolicy::RejectNew)
+            })
+        });
+        let results = futures::future::join_all(logins).await;
+        let accepted = results
+            .iter()
+            .filter(|result| result.as_ref().unwrap().is_some())
+            .count();
+        assert_eq!(accepted, 1);
+
+        // Same name, different case, so it's the same player.
+        let online = *connections.sessions.lock().unwrap().keys().next().unwrap();
+        let kicked = connections.claim_session(20, 2, "STEVE", DuplicateLoginPolicy::KickExisting);
+        assert_eq!(kicked, Some(vec![online]));
+        assert_eq!(
+            connections.claim_session(21, 2, "Alex", DuplicateLoginPolicy::RejectNew),
+            None
+        );
+
+        connections.end_session(20);
+        assert_eq!(
+            connections.claim_session(21, 2, "Alex", DuplicateLoginPolicy::RejectNew),
+            Some(Vec::new())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_packet_is_skipped_without_desyncing() {
+        use crate::net::packets::incoming::handshake::Handshake;
+
+        async fn frame(packet: Vec<u8>) -> Vec<u8> {
+            let mut framed = Vec::new();
+            VarInt::new(packet.len() as i32)
+                .write(&mut framed)
+                .await
+                .unwrap();
+            framed.extend(packet);
+            framed
+        }
+
+        async fn handshake(server_address: &str) -> Vec<u8> {
+            let mut packet = Vec::new();
+            VarInt::new(0x00).write(&mut packet).await.unwrap();
+            VarInt::new(763).write(&mut packet).await.unwrap();
+            server_address
+                .to_string()
+                .net_encode(&mut packet)
+                .await
+                .unwrap();
+            25565u16.net_encode(&mut packet).await.unwrap();
+            VarInt::new(1).write(&mut packet).await.unwrap();
+            frame(packet).await
+        }
+
+        let mut stream = handshake("first").await;
+        // An id nothing is registered for, with a body that looks like a length prefix.
+        stream.extend(frame(vec![0x7F, 0x10, 0x00, 0x01]).await);
+        stream.extend(handshake("second").await);
+        let mut reader = &stream[..];
+
+        let mut decoded = Vec::new();
+        let mut skipped = Vec::new();
+        while !reader.is_empty() {
+            let (_, buffer) = read_frame(&mut reader, MAX_PACKET_LENGTH).await.unwrap();
+            let mut cursor = Cursor::new(buffer);
+            let packet_id = VarInt::read(&mut cursor).await.unwrap().get_val() as u8;
+
+            if !is_packet_registered(packet_id, &State::Handshake) {
+                skipped.push(packet_id);
+                continue;
+            }
+            let packet = Handshake::net_decode(&mut cursor).await.unwrap();
+            decoded.push(packet.server_address);
+        }
+
+        assert_eq!(decoded, vec!["first", "second"]);
+        assert_eq!(skipped, vec![0x7F]);
+    }
+
+    #[tokio::test]
+    async fn test_absurd_packet_length_is_rejected_before_allocating() {
+        // Claims a packet of 256 MB, with nothing behind it.
+        let mut stream = Vec::new();
+        VarInt::new(268_435_455).write(&mut stream).await.unwrap();
+        let mut reader = &stream[..];
+
+        let result = read_frame(&mut reader, MAX_PACKET_LENGTH).await;
+        assert!(matches!(
+            result,
+            Err(Error::PacketTooLarge(268_435_455, MAX_PACKET_LENGTH))
+        ));
+
+        let mut stream = Vec::new();
+        VarInt::new(-1).write(&mut stream).await.unwrap();
+        let mut reader = &stream[..];
+        let result = read_frame(&mut reader, MAX_PACKET_LENGTH).await;
+        assert!(matches!(result, Err(Error::InvalidPacketLength(-1))));
+    }
+
+    #[tokio::test]
+    async fn test_no_disconnect_packet_before_login() {
+        for conn_state in [State::Unknown, State::Handshake, State::Status] {
+            let packet = encode_disconnect(&conn_state, "Bye".into()).await.unwrap();
+            assert!(packet.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dropping_a_connection_twice() {
+        use tokio::net::{TcpListener, TcpStream};
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (socket, remote_addr) = listener.accept().await.unwrap();
+        let state = crate::create_state(vec![]).await.unwrap();
+
+        let connection = tokio::spawn(init_connection(socket, remote_addr, state.clone()));
+        let conn_id = loop {
+            if let Some(conn) = state.connections.connections.iter().next() {
+                break *conn.key();
+            }
+            tokio::task::yield_now().await;
+        };
+        let component_storage = state.world.get_component_storage();
+        component_storage.insert(conn_id, Player::new(1, "Steve".to_string()));
+        component_storage.insert(conn_id, VisibleEntities::default());
+
+        drop_conn(conn_id, state.clone()).await.unwrap();
+        drop_conn(conn_id, state.clone()).await.unwrap();
+        assert!(state.world.get_component::<Player>(conn_id).await.is_err());
+        assert!(!state.connections.connections.contains_key(&conn_id));
+
+        // The read loop stops once the connection is closed, without dropping it again.
+        connection.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,49 @@
+use tracing::{info, warn};
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::commands::{command_name, CommandSender};
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::chat::sanitize_chat_message;
+use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// A command typed in chat, without the leading slash.
+///
+/// The argument signatures and the acknowledged messages come after these fields, and are left
+/// unread like they are for [super::chat_message::PacketChatMessage].
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x04, state = "play")]
+pub struct ChatCommand {
+    pub command: String,
+    pub timestamp: i64,
+    pub salt: i64,
+}
+
+impl IncomingPacket for ChatCommand {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let command = sanitize_chat_message(&self.command);
+        {
+            let player = state.world.get_component::<Player>(conn_id).await?;
+            info!("{} ran command: /{}", player.username, command);
+        }
+
+        let reply = match state
+            .commands
+            .dispatch(state.clone(), CommandSender::Player(conn_id), &command)
+            .await
+        {
+            Some(Ok(output)) if output.is_empty() => return Ok(()),
+            Some(Ok(output)) => TextComponent::new(output),
+            Some(Err(e)) => {
+                warn!("Command /{} from {} failed: {}", command, conn_id, e);
+                TextComponent::new("An error occurred while running that command").color("red")
+            }
+            None => TextComponent::new(format!("Unknown command: {}", command_name(&command)))
+                .color("red"),
+        };
+        state.send_message(conn_id, reply).await
+    }
+}
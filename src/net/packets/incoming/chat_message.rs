@@ -1,30 +1,52 @@
-use tracing::debug;
+use std::sync::Arc;
+
+use tracing::{debug, info};
 
 use ferrumc_macros::{packet, NetDecode};
 
+use crate::events::chat_events::PlayerChatEvent;
+use crate::events::creation::dispatcher::EventDispatcherExt;
 use crate::net::packets::{ConnectionId, IncomingPacket};
 use crate::state::GlobalState;
+use crate::utils::chat::sanitize_chat_message;
 use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
 
-#[derive(NetDecode)]
+/// A chat message typed by the player. Commands come in their own packet.
+///
+/// The signature and the acknowledged messages come after these fields. Chat isn't signed in
+/// offline mode, so they're left unread.
+#[derive(NetDecode, Debug)]
 #[packet(packet_id = 0x05, state = "play")]
 pub struct PacketChatMessage {
     pub message: String,
     pub timestamp: i64,
+    pub salt: i64,
 }
 
 impl IncomingPacket for PacketChatMessage {
-    async fn handle(
-        self,
-        conn_id: ConnectionId,
-        state: GlobalState,
-    ) -> crate::utils::prelude::Result<()> {
-        let my_id = conn_id;
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let message = sanitize_chat_message(&self.message);
+        if message.is_empty() {
+            return Ok(());
+        }
 
-        let my_player = state.world.get_component::<Player>(my_id).await?;
+        let username = state
+            .world
+            .get_component::<Player>(conn_id)
+            .await?
+            .username
+            .clone();
 
-        debug!("[{}]: {}", my_player.username, self.message);
+        let event = Arc::new(PlayerChatEvent::new(conn_id, username, message));
+        state.dispatch_shared_event(event.clone()).await;
+        if event.is_cancelled() {
+            debug!("Chat message from {} was cancelled", event.username);
+            return Ok(());
+        }
 
-        Ok(())
+        let message = format!("<{}> {}", event.username, event.message());
+        info!("{}", message);
+        state.broadcast_message(message).await
     }
 }
@@ -0,0 +1,91 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::debug;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::outgoing::set_container_content::SetContainerContent;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::components::inventory::{ClickMode, Inventory, PLAYER_WINDOW_ID};
+use crate::utils::encoding::slot::Slot;
+use crate::utils::prelude::*;
+
+/// A slot the client changed with a click, and what it thinks is in it now.
+#[derive(Debug)]
+pub struct ChangedSlot {
+    pub slot: i16,
+    pub item: Slot,
+}
+
+impl NetDecode for ChangedSlot {
+    async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>>
+    where
+        T: AsyncRead + Unpin,
+    {
+        let slot = *i16::net_decode(bytes).await?;
+        let item = *Slot::net_decode(bytes).await?;
+        Ok(Box::new(Self { slot, item }))
+    }
+}
+
+/// Sent when the player clicks a slot in an open window. The client has already changed its
+/// inventory by the time it's sent, and `changed_slots` and `carried` are what it thinks it
+/// looks like now.
+///
+/// - `state_id`: The state id of the last change the client was sent.
+/// - `mode`: See [ClickMode::from_id].
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x0B, state = "play")]
+pub struct ClickContainer {
+    pub window_id: u8,
+    pub state_id: VarInt,
+    pub slot: i16,
+    pub button: i8,
+    pub mode: VarInt,
+    pub changed_slots: Vec<ChangedSlot>,
+    pub carried: Slot,
+}
+
+impl IncomingPacket for ClickContainer {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        if self.window_id != PLAYER_WINDOW_ID {
+            debug!(
+                "Connection {} clicked in window {}, which isn't open",
+                conn_id, self.window_id
+            );
+            return Ok(());
+        }
+
+        let mut inventory = state
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with(conn_id, Inventory::default)
+            .await;
+        let up_to_date = self.state_id.get_val() == inventory.state_id;
+        let applied = ClickMode::from_id(self.mode.get_val())
+            .is_some_and(|mode| inventory.click(self.slot, self.button, mode));
+        let changed_slots = self
+            .changed_slots
+            .into_iter()
+            .map(|changed| (changed.slot, changed.item))
+            .collect::<Vec<_>>();
+
+        // The client got it right, so there's nothing to tell it.
+        if up_to_date && applied && inventory.matches(&changed_slots, &self.carried) {
+            drop(inventory);
+            state.update_equipment(conn_id).await;
+            return Ok(());
+        }
+        debug!(
+            "Connection {}'s inventory is out of sync, sending it again",
+            conn_id
+        );
+        let packet = SetContainerContent::from_inventory(PLAYER_WINDOW_ID, &mut inventory);
+        drop(inventory);
+        state.update_equipment(conn_id).await;
+
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(packet).await
+    }
+}
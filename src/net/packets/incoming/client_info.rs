@@ -1,41 +1,64 @@
+use ferrumc_codec::network_types::varint::VarInt;
 use tracing::trace;
 
-use ferrumc_macros::{packet, Component, NetDecode};
+use ferrumc_macros::{packet, NetDecode};
 
 use crate::net::packets::{ConnectionId, IncomingPacket};
-use crate::net::systems::chunk_sender::ChunkSender;
+use crate::net::systems::chunk_sender::{view_distance, ChunkSender};
+use crate::net::utils::metadata::broadcast_player_metadata;
 use crate::state::GlobalState;
+use crate::utils::components::client_settings::ClientSettings;
+use crate::utils::prelude::*;
 
-#[derive(NetDecode, Component, Clone, Debug)]
+/// Sent right after joining, and whenever the player changes their settings. Stored as the
+/// entity's [ClientSettings].
+#[derive(NetDecode, Clone, Debug)]
 #[packet(packet_id = 0x08, state = "play")]
 pub struct ClientInfo {
     pub locale: String,
     pub view_distance: i8,
-    pub chat_mode: i8,
+    pub chat_mode: VarInt,
     pub chat_colors: bool,
     pub displayed_skin_parts: u8,
-    pub main_hand: i8,
+    pub main_hand: VarInt,
+    pub enable_text_filtering: bool,
+    pub allow_server_listings: bool,
 }
 
 impl IncomingPacket for ClientInfo {
-    async fn handle(
-        self,
-        entity_id: ConnectionId,
-        state: GlobalState,
-    ) -> crate::utils::prelude::Result<()> {
-        trace!("ClientInfo packet received");
-        trace!("Locale: {}", self.locale);
-        trace!("View Distance: {}", self.view_distance);
-        trace!("Chat Mode: {}", self.chat_mode);
-        trace!("Chat Colors: {}", self.chat_colors);
-        trace!("Displayed Skin Parts: {}", self.displayed_skin_parts);
-        trace!("Main Hand: {}", self.main_hand);
-
-        // ClientInfo is a packet & also a component.
-        state.world.get_component_storage().insert(entity_id, self);
-
-        // Send chunks again
-        ChunkSender::send_chunks_to_player(state.clone(), entity_id).await?;
+    async fn handle(self, entity_id: ConnectionId, state: GlobalState) -> Result<()> {
+        trace!("ClientInfo packet received: {:?}", self);
+
+        let settings = ClientSettings::from(&self);
+        let component_storage = state.world.get_component_storage();
+
+        // Until the first of these arrives, chunks are sent for the server's view distance.
+        let (old_distance, appearance_changed) =
+            match component_storage.get::<ClientSettings>(entity_id).await {
+                Ok(old_settings) => (
+                    view_distance(Some(&old_settings)),
+                    old_settings.displayed_skin_parts != settings.displayed_skin_parts
+                        || old_settings.main_hand != settings.main_hand,
+                ),
+                Err(_) => (view_distance(None), true),
+            };
+        let new_distance = view_distance(Some(&settings));
+        component_storage.insert(entity_id, settings);
+        state
+            .dimension_of(entity_id)
+            .await
+            .players
+            .set_view_distance(entity_id, new_distance);
+
+        // Other players see the skin layers and main hand through the player's metadata.
+        if appearance_changed {
+            broadcast_player_metadata(entity_id, &state).await?;
+        }
+
+        // Sends the chunks that came into view, or unloads the ones that went out of it.
+        if new_distance != old_distance {
+            ChunkSender::send_chunks_to_player(state, entity_id).await?;
+        }
 
         Ok(())
     }
@@ -0,0 +1,94 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::debug;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::outgoing::award_statistics::AwardStatistics;
+use crate::net::packets::outgoing::respawn::KEEP_NOTHING;
+use crate::net::packets::outgoing::set_container_content::SetContainerContent;
+use crate::net::packets::outgoing::set_held_item::SetHeldItem;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::state::GlobalState;
+use crate::utils::components::active_effects::ActiveEffects;
+use crate::utils::components::food::Food;
+use crate::utils::components::health::Health;
+use crate::utils::components::held_item::HeldItem;
+use crate::utils::components::inventory::{Inventory, PLAYER_WINDOW_ID};
+use crate::utils::prelude::*;
+use crate::world::dimensions::OVERWORLD;
+
+const PERFORM_RESPAWN: i32 = 0;
+const REQUEST_STATS: i32 = 1;
+
+/// Sent when the player clicks respawn on the death screen, or opens the statistics screen.
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x07, state = "play")]
+pub struct ClientStatus {
+    pub action_id: VarInt,
+}
+
+impl IncomingPacket for ClientStatus {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        match self.action_id.get_val() {
+            PERFORM_RESPAWN => respawn(conn_id, state).await,
+            REQUEST_STATS => {
+                // Statistics aren't tracked yet.
+                let conn = state.connections.get_connection(conn_id)?;
+                let conn = conn.read().await;
+                conn.send_packet(AwardStatistics::new(vec![])).await
+            }
+            action => {
+                debug!("Unknown client status {} from {}", action, conn_id);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Brings a dead player back to life at spawn, by moving them back into the overworld.
+///
+/// Players come back with full health and food and without their effects, and keep their
+/// inventory, since dropped items don't exist yet. The client starts over with an empty one
+/// after respawning, so it's sent again.
+async fn respawn(conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+    {
+        let mut health = component_storage
+            .get_mut_or_insert_with(conn_id, Health::default)
+            .await;
+        if !health.is_dead() {
+            debug!("Connection {} asked to respawn while alive", conn_id);
+            return Ok(());
+        }
+        health.reset();
+    }
+    component_storage.insert(conn_id, Food::default());
+    component_storage.insert(conn_id, ActiveEffects::default());
+
+    state
+        .move_player_to_dimension(conn_id, OVERWORLD, KEEP_NOTHING)
+        .await?;
+
+    state.send_health(conn_id).await?;
+
+    let mut packet_queue = PacketQueue::new();
+
+    let selected = component_storage
+        .get::<HeldItem>(conn_id)
+        .await
+        .map(|held_item| held_item.slot)
+        .unwrap_or_default();
+    packet_queue.queue(SetHeldItem::new(selected)).await?;
+    let inventory = {
+        let mut inventory = component_storage
+            .get_mut_or_insert_with(conn_id, Inventory::default)
+            .await;
+        SetContainerContent::from_inventory(PLAYER_WINDOW_ID, &mut inventory)
+    };
+    packet_queue.queue(inventory).await?;
+
+    let conn = state.connections.get_connection(conn_id)?;
+    let conn = conn.read().await;
+    conn.send_packets(packet_queue).await
+}
@@ -0,0 +1,31 @@
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::components::inventory::{Inventory, PLAYER_WINDOW_ID};
+use crate::utils::prelude::*;
+
+/// Sent when the player closes a window, including their own inventory.
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x0C, state = "play")]
+pub struct CloseContainer {
+    pub window_id: u8,
+}
+
+impl IncomingPacket for CloseContainer {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        if self.window_id == PLAYER_WINDOW_ID {
+            state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with(conn_id, Inventory::default)
+                .await
+                .close();
+            state.update_equipment(conn_id).await;
+        } else {
+            let conn = state.connections.get_connection(conn_id)?;
+            conn.write().await.metadata.windows.close(self.window_id);
+        }
+        Ok(())
+    }
+}
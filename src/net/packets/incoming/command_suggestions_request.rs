@@ -0,0 +1,35 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::outgoing::command_suggestions_response::CommandSuggestionsResponse;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// Sent while the player types an argument the server said it has suggestions for.
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x09, state = "play")]
+pub struct CommandSuggestionsRequest {
+    pub transaction_id: VarInt,
+    /// Everything before the cursor, including the slash.
+    pub text: String,
+}
+
+impl IncomingPacket for CommandSuggestionsRequest {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        // Clients don't mind never getting an answer.
+        let Some((target, suggestions)) = state.command_suggestions(&self.text).await else {
+            return Ok(());
+        };
+        let response = CommandSuggestionsResponse::new(
+            self.transaction_id.get_val(),
+            target.start,
+            target.prefix.len(),
+            suggestions,
+        );
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(response).await
+    }
+}
@@ -0,0 +1,38 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::debug;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::components::pending_teleports::PendingTeleports;
+use crate::utils::prelude::*;
+
+/// Sent by the client once it's moved to where a
+/// [SynchronizePlayerPosition](crate::net::packets::outgoing::synchronize_player_position::SynchronizePlayerPosition)
+/// told it to. Until then, its position packets are ignored.
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x00, state = "play")]
+pub struct ConfirmTeleportation {
+    pub teleport_id: VarInt,
+}
+
+impl IncomingPacket for ConfirmTeleportation {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let teleport_id = self.teleport_id.get_val();
+        let mut teleports = state
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with(conn_id, PendingTeleports::default)
+            .await;
+
+        if !teleports.confirm(teleport_id) {
+            // Not worth kicking for, it's most likely just one we've stopped waiting for.
+            debug!(
+                "Connection {} confirmed unknown teleport {}",
+                conn_id, teleport_id
+            );
+        }
+        Ok(())
+    }
+}
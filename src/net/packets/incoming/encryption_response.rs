@@ -0,0 +1,103 @@
+use tracing::{debug, warn};
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::incoming::login_start::LoginStart;
+use crate::net::packets::outgoing::login_success::LoginSuccess;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::authentication::{get_server_key, has_joined, server_hash};
+use crate::net::{drop_conn, ConnectionExt};
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// Sent by the client in online mode, in response to
+/// [crate::net::packets::outgoing::encryption_request::EncryptionRequest].
+///
+/// Both fields are encrypted with the server's public key. Once the verify token checks out,
+/// encryption is turned on and the player is authenticated with the session server before the
+/// login continues as usual.
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x01, state = "login")]
+pub struct EncryptionResponse {
+    pub shared_secret: Vec<u8>,
+    pub verify_token: Vec<u8>,
+}
+
+impl IncomingPacket for EncryptionResponse {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let conn = state.connections.get_connection(conn_id)?;
+
+        let (username, expected_token) = {
+            let mut conn = conn.write().await;
+            (
+                conn.metadata.username.take(),
+                conn.metadata.verify_token.take(),
+            )
+        };
+        let (Some(username), Some(expected_token)) = (username, expected_token) else {
+            warn!("Got an encryption response without requesting encryption, dropping connection");
+            return drop_conn(conn_id, state).await;
+        };
+
+        let server_key = get_server_key()?;
+        // Errors from here on would be dropped by the task handling the packet, leaving the
+        // connection stuck in the login state, so it's dropped instead.
+        let verify_token = match server_key.decrypt(&self.verify_token) {
+            Ok(verify_token) => verify_token,
+            Err(e) => {
+                warn!(
+                    "Couldn't decrypt the verify token from {}, dropping connection: {}",
+                    username, e
+                );
+                return drop_conn(conn_id, state).await;
+            }
+        };
+        if verify_token != expected_token {
+            warn!(
+                "Verify token mismatch for {}, dropping connection",
+                username
+            );
+            return drop_conn(conn_id, state).await;
+        }
+        let shared_secret = match server_key.decrypt(&self.shared_secret) {
+            Ok(shared_secret) => shared_secret,
+            Err(e) => {
+                warn!(
+                    "Couldn't decrypt the shared secret from {}, dropping connection: {}",
+                    username, e
+                );
+                return drop_conn(conn_id, state).await;
+            }
+        };
+
+        // Both sides encrypt everything after this packet, including the disconnect below.
+        let encryption = conn.read().await.enable_encryption(&shared_secret).await;
+        if let Err(e) = encryption {
+            warn!(
+                "Couldn't turn on encryption for {}, dropping connection: {}",
+                username, e
+            );
+            return drop_conn(conn_id, state).await;
+        }
+
+        let hash = server_hash("", &shared_secret, &server_key.public_key_der);
+        let profile = match has_joined(&username, &hash).await {
+            Ok(profile) => profile,
+            Err(e) => {
+                warn!("Failed to authenticate {}: {}", username, e);
+                return conn.kick("Failed to verify username!", state).await;
+            }
+        };
+        debug!("Authenticated {} ({})", profile.name, profile.id);
+
+        let login_success = LoginSuccess::new(profile.id, profile.name.clone(), profile.properties);
+
+        let login_start = LoginStart {
+            username: profile.name,
+            uuid: profile.id.as_u128(),
+        };
+        login_start
+            .finish_login(conn_id, state, login_success)
+            .await
+    }
+}
@@ -1,16 +1,22 @@
 use ferrumc_codec::network_types::varint::VarInt;
+use tracing::{debug, warn};
 
 use ferrumc_macros::{packet, NetDecode};
 
 use crate::net::packets::{ConnectionId, IncomingPacket};
-use crate::net::State;
+use crate::net::utils::proxy::parse_bungeecord_address;
+use crate::net::{ConnectionExt, State};
 use crate::state::GlobalState;
+use crate::utils::config::{get_global_config, ProtocolRange};
+use crate::utils::constants::{GAME_VERSION, PROTOCOL_VERSION};
 use crate::utils::prelude::*;
 
 /// The first packet sent by the client to the server.
 ///
 /// This packet is used to negotiate the protocol version, server address, server port, and the next state.
-#[derive(NetDecode)]
+///
+/// Behind BungeeCord, the server address also carries the forwarded player info.
+#[derive(NetDecode, Debug)]
 #[packet(packet_id = 0x00, state = "handshake")]
 pub struct Handshake {
     pub protocol_version: VarInt,
@@ -21,19 +27,102 @@ pub struct Handshake {
 
 impl IncomingPacket for Handshake {
     async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
-        let Some(conn) = state.connections.connections.get(&conn_id) else {
-            return Err(Error::ConnectionNotFound(conn_id));
-        };
+        let conn = state.connections.get_connection(conn_id)?;
 
-        let mut conn = conn.write().await;
+        let mut conn_write = conn.write().await;
 
-        conn.metadata.protocol_version = self.protocol_version.get_val();
-        conn.state = match self.next_state.get_val() {
+        conn_write.metadata.protocol_version = self.protocol_version.get_val();
+        conn_write.state = match self.next_state.get_val() {
             1 => State::Status,
             2 => State::Login,
             s => return Err(Error::InvalidState(s)),
         };
 
-        Ok(())
+        if conn_write.state != State::Login {
+            return Ok(());
+        }
+
+        let protocol_version = self.protocol_version.get_val();
+        let allowed_range = get_global_config().allow_protocol_range.as_ref();
+        if !is_supported_protocol(protocol_version, allowed_range) {
+            debug!(
+                "Connection {} uses unsupported protocol version {}",
+                conn_id, protocol_version
+            );
+            drop(conn_write);
+            return conn.kick(outdated_message(protocol_version), state).await;
+        }
+
+        if !get_global_config().proxy.bungeecord {
+            return Ok(());
+        }
+
+        match parse_bungeecord_address(&self.server_address) {
+            Ok((_, forwarded)) => {
+                debug!(
+                    "Connection {} forwarded by BungeeCord for {} ({})",
+                    conn_id, forwarded.address, forwarded.uuid
+                );
+                conn_write.metadata.forwarded = Some(forwarded);
+                Ok(())
+            }
+            Err(e) => {
+                // Without forwarding data, the client didn't come through the proxy.
+                warn!("Rejecting connection {}: {}", conn_id, e);
+                drop(conn_write);
+                conn.kick(
+                    "If you wish to use IP forwarding, please enable it in your BungeeCord config as well!",
+                    state,
+                )
+                .await
+            }
+        }
+    }
+}
+
+/// Whether clients with `protocol_version` can join. Only [PROTOCOL_VERSION] can, unless the config
+/// lets in a wider range.
+pub fn is_supported_protocol(protocol_version: i32, allowed_range: Option<&ProtocolRange>) -> bool {
+    protocol_version == PROTOCOL_VERSION
+        || allowed_range.is_some_and(|range| (range.min..=range.max).contains(&protocol_version))
+}
+
+/// The disconnect message for a client on the wrong version, like vanilla's.
+fn outdated_message(protocol_version: i32) -> String {
+    if protocol_version < PROTOCOL_VERSION {
+        format!("Outdated client! This server requires {}", GAME_VERSION)
+    } else {
+        format!("Outdated server! This server requires {}", GAME_VERSION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_the_servers_protocol_is_supported() {
+        assert!(is_supported_protocol(PROTOCOL_VERSION, None));
+        assert!(!is_supported_protocol(PROTOCOL_VERSION - 1, None));
+        assert!(!is_supported_protocol(PROTOCOL_VERSION + 1, None));
+    }
+
+    #[test]
+    fn test_allowed_protocol_range() {
+        let range = ProtocolRange {
+            min: PROTOCOL_VERSION - 1,
+            max: PROTOCOL_VERSION + 1,
+        };
+        assert!(is_supported_protocol(PROTOCOL_VERSION - 1, Some(&range)));
+        assert!(is_supported_protocol(PROTOCOL_VERSION, Some(&range)));
+        assert!(is_supported_protocol(PROTOCOL_VERSION + 1, Some(&range)));
+        assert!(!is_supported_protocol(PROTOCOL_VERSION + 2, Some(&range)));
+    }
+
+    #[test]
+    fn test_outdated_message() {
+        assert!(outdated_message(PROTOCOL_VERSION - 1).starts_with("Outdated client!"));
+        assert!(outdated_message(PROTOCOL_VERSION + 1).starts_with("Outdated server!"));
+        assert!(outdated_message(PROTOCOL_VERSION + 1).ends_with(GAME_VERSION));
     }
 }
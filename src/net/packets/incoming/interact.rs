@@ -0,0 +1,182 @@
+use std::sync::Arc;
+
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::io::AsyncRead;
+use tracing::{debug, warn};
+
+use ferrumc_macros::packet;
+
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::player_events::PlayerInteractEntityEvent;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::combat::{attack_player, within_reach};
+use crate::state::GlobalState;
+use crate::utils::components::exact_position::ExactPosition;
+use crate::utils::components::player::Player;
+use crate::utils::impls::packet_impls::NetDecode;
+use crate::utils::prelude::*;
+
+const INTERACT: i32 = 0;
+const ATTACK: i32 = 1;
+const INTERACT_AT: i32 = 2;
+
+/// What the player did to the entity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InteractAction {
+    /// Right-clicked it.
+    Interact { hand: i32 },
+    /// Left-clicked it.
+    Attack,
+    /// Right-clicked a particular spot on it, relative to its position. Sent along with
+    /// [InteractAction::Interact] for the same click.
+    InteractAt { x: f32, y: f32, z: f32, hand: i32 },
+}
+
+/// Sent when the player left- or right-clicks an entity.
+///
+/// - `hand`: 0 for the main hand, 1 for the off hand.
+#[derive(Debug)]
+#[packet(packet_id = 0x10, state = "play")]
+pub struct Interact {
+    pub entity_id: VarInt,
+    pub action: InteractAction,
+    pub sneaking: bool,
+}
+
+impl Interact {
+    /// Which fields are sent depends on the action, so this can't be derived.
+    pub async fn net_decode<T>(bytes: &mut T) -> Result<Self>
+    where
+        T: AsyncRead + Unpin,
+    {
+        let entity_id = *VarInt::net_decode(bytes).await?;
+        let action = match VarInt::net_decode(bytes).await?.get_val() {
+            INTERACT => InteractAction::Interact {
+                hand: VarInt::net_decode(bytes).await?.get_val(),
+            },
+            ATTACK => InteractAction::Attack,
+            INTERACT_AT => InteractAction::InteractAt {
+                x: *f32::net_decode(bytes).await?,
+                y: *f32::net_decode(bytes).await?,
+                z: *f32::net_decode(bytes).await?,
+                hand: VarInt::net_decode(bytes).await?.get_val(),
+            },
+            action => {
+                return Err(Error::Generic(format!(
+                    "Unknown interact action {}",
+                    action
+                )))
+            }
+        };
+        let sneaking = *bool::net_decode(bytes).await?;
+        Ok(Self {
+            entity_id,
+            action,
+            sneaking,
+        })
+    }
+}
+
+impl IncomingPacket for Interact {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        // Only players can be interacted with so far, anything else is ignored below.
+        let Some(target_id) = state.entity_ids.entity(self.entity_id.get_val()) else {
+            return Ok(());
+        };
+        if state.is_spectator(conn_id).await {
+            debug!(
+                "Connection {} interacted with entity {} as a spectator",
+                conn_id, target_id
+            );
+            return Ok(());
+        }
+        let component_storage = state.world.get_component_storage();
+        if target_id == conn_id || component_storage.get::<Player>(target_id).await.is_err() {
+            debug!(
+                "Connection {} interacted with entity {}, which isn't another player",
+                conn_id, target_id
+            );
+            return Ok(());
+        }
+
+        let position = *component_storage.get::<ExactPosition>(conn_id).await?;
+        let target_position = *component_storage.get::<ExactPosition>(target_id).await?;
+        if !within_reach(&position, &target_position) {
+            warn!(
+                "Connection {} at {} interacted with {} at {}, which is out of reach",
+                conn_id,
+                position.block(),
+                target_id,
+                target_position.block()
+            );
+            return Ok(());
+        }
+
+        match self.action {
+            InteractAction::Attack => attack_player(conn_id, target_id, state).await,
+            InteractAction::Interact { hand } => {
+                let event = PlayerInteractEntityEvent {
+                    entity_id: conn_id,
+                    target_id,
+                    hand,
+                    target_position: None,
+                    sneaking: self.sneaking,
+                };
+                state.dispatch_shared_event(Arc::new(event)).await;
+                Ok(())
+            }
+            InteractAction::InteractAt { x, y, z, hand } => {
+                let event = PlayerInteractEntityEvent {
+                    entity_id: conn_id,
+                    target_id,
+                    hand,
+                    target_position: Some((x, y, z)),
+                    sneaking: self.sneaking,
+                };
+                state.dispatch_shared_event(Arc::new(event)).await;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_decode_interact() {
+        // Attacking entity 300 while sneaking.
+        let attack = Interact::net_decode(&mut Cursor::new(vec![0xAC, 0x02, 0x01, 0x01]))
+            .await
+            .unwrap();
+        assert_eq!(attack.entity_id.get_val(), 300);
+        assert_eq!(attack.action, InteractAction::Attack);
+        assert!(attack.sneaking);
+
+        let mut data = vec![0x05, 0x02];
+        for coordinate in [0.5f32, 1.25, -0.5] {
+            data.extend_from_slice(&coordinate.to_be_bytes());
+        }
+        data.extend_from_slice(&[0x01, 0x00]);
+        let interact_at = Interact::net_decode(&mut Cursor::new(data)).await.unwrap();
+        assert_eq!(
+            interact_at.action,
+            InteractAction::InteractAt {
+                x: 0.5,
+                y: 1.25,
+                z: -0.5,
+                hand: 1
+            }
+        );
+        assert!(!interact_at.sneaking);
+
+        assert!(
+            Interact::net_decode(&mut Cursor::new(vec![0x05, 0x07, 0x00]))
+                .await
+                .is_err()
+        );
+    }
+}
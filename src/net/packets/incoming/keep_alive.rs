@@ -1,11 +1,16 @@
-use tracing::debug;
+use tracing::{debug, warn};
 
 use ferrumc_macros::{packet, NetDecode};
 
 use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::ConnectionExt;
 use crate::state::GlobalState;
 use crate::utils::components::keep_alive::KeepAlive;
 
+/// The client's answer to [crate::net::packets::outgoing::keep_alive::KeepAlivePacketOut].
+///
+/// Updates the player's ping if it echoes the id of the last keep alive we sent. Anything else is
+/// a strike, and too many of those get the player kicked.
 #[derive(NetDecode, Debug)]
 #[packet(packet_id = 0x12, state = "play")]
 pub struct KeepAlivePacketIn {
@@ -22,14 +27,30 @@ impl IncomingPacket for KeepAlivePacketIn {
 
         let player = conn;
 
-        debug!("Player: {:?}", player);
-
         let mut keep_alive = state.world.get_component_mut::<KeepAlive>(player).await?;
 
         debug!("KeepAlive for player: {:?}", *keep_alive);
 
-        keep_alive.last_received = std::time::Instant::now();
-
-        Ok(())
+        let expected_id = keep_alive.data;
+        if keep_alive.receive(self.keep_alive_id) {
+            debug!("Ping for player {}: {}ms", player, keep_alive.ping_ms);
+            return Ok(());
+        }
+
+        warn!(
+            "Unexpected keep alive id from player {}: expected {}, got {} (strike {})",
+            player, expected_id, self.keep_alive_id, keep_alive.strikes
+        );
+        if !keep_alive.is_struck_out() {
+            return Ok(());
+        }
+        // Kicking removes the component, so let go of it first.
+        drop(keep_alive);
+
+        state
+            .connections
+            .get_connection(player)?
+            .kick("Too many invalid keep alive responses", state)
+            .await
     }
 }
@@ -0,0 +1,84 @@
+use tracing::{debug, warn};
+
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::incoming::login_start::LoginStart;
+use crate::net::packets::outgoing::login_success::LoginSuccess;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::proxy::parse_velocity_player_info;
+use crate::net::ConnectionExt;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+use crate::utils::impls::packet_impls::RemainingBytes;
+use crate::utils::prelude::*;
+
+/// The client's answer to [crate::net::packets::outgoing::login_plugin_request::LoginPluginRequest].
+///
+/// The only request we send is for Velocity's player info, so this finishes the login with the
+/// forwarded player once the signature checks out.
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x02, state = "login")]
+pub struct LoginPluginResponse {
+    pub message_id: VarInt,
+    pub successful: bool,
+    // Only present if successful
+    pub data: RemainingBytes,
+}
+
+impl IncomingPacket for LoginPluginResponse {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let conn = state.connections.get_connection(conn_id)?;
+
+        {
+            let mut conn = conn.write().await;
+            if conn.metadata.velocity_message_id != Some(self.message_id.get_val()) {
+                debug!(
+                    "Ignoring login plugin response {} from connection {}",
+                    self.message_id, conn_id
+                );
+                return Ok(());
+            }
+            conn.metadata.velocity_message_id = None;
+        }
+
+        // Vanilla clients answer unknown channels with an unsuccessful response.
+        if !self.successful {
+            warn!(
+                "Connection {} didn't come through Velocity, disconnecting",
+                conn_id
+            );
+            return conn
+                .kick("This server requires you to connect with Velocity.", state)
+                .await;
+        }
+
+        let secret = get_global_config().proxy.velocity_secret.as_bytes();
+        let (username, forwarded) = match parse_velocity_player_info(&self.data.0, secret).await {
+            Ok(player_info) => player_info,
+            Err(e) => {
+                warn!(
+                    "Invalid Velocity forwarding from connection {}: {}",
+                    conn_id, e
+                );
+                return conn.kick("Unable to verify player details.", state).await;
+            }
+        };
+        debug!(
+            "Connection {} forwarded by Velocity for {} ({}, {})",
+            conn_id, username, forwarded.address, forwarded.uuid
+        );
+
+        conn.write().await.metadata.forwarded = Some(forwarded.clone());
+
+        let login_success =
+            LoginSuccess::new(forwarded.uuid, username.clone(), forwarded.properties);
+        let login_start = LoginStart {
+            username,
+            uuid: forwarded.uuid.as_u128(),
+        };
+        login_start
+            .finish_login(conn_id, state, login_success)
+            .await
+    }
+}
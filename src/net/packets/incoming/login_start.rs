@@ -1,30 +1,54 @@
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use ferrumc_codec::network_types::varint::VarInt;
 use rand::random;
-use tracing::{debug};
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
 use uuid::Uuid;
 
 use ferrumc_macros::{packet, NetDecode};
 use crate::events::creation::dispatcher::EventDispatcherExt;
 use crate::events::world_events::PlayerJoinWorldEvent;
-use crate::net::packets::outgoing::default_spawn_position::DefaultSpawnPosition;
+use crate::net::packets::outgoing::encryption_request::EncryptionRequest;
+use crate::net::packets::outgoing::initialize_world_border::InitializeWorldBorder;
 use crate::net::packets::outgoing::keep_alive::KeepAlivePacketOut;
+use crate::net::packets::outgoing::plugin_message::PluginMessage;
+use crate::net::packets::outgoing::resource_pack::ResourcePack;
 use crate::net::packets::outgoing::login_plugin_request::LoginPluginRequest;
 use crate::net::packets::outgoing::login_success::LoginSuccess;
-use crate::net::packets::outgoing::synchronize_player_position::SynchronizePlayerPosition;
+use crate::net::packets::outgoing::set_compression::SetCompression;
+use crate::net::packets::outgoing::set_container_content::SetContainerContent;
+use crate::net::packets::outgoing::set_held_item::SetHeldItem;
 use crate::net::packets::{ConnectionId, IncomingPacket};
-use crate::net::systems::chunk_sender::ChunkSender;
+use crate::net::systems::chunk_sender::{view_distance, ChunkSender};
+use crate::net::utils::authentication::{get_server_key, ProfileProperty};
 use crate::net::utils::packet_queue::PacketQueue;
-use crate::net::Connection;
+use crate::net::utils::proxy::{VELOCITY_CHANNEL, VELOCITY_FORWARDING_VERSION};
+use crate::net::utils::spawn::{place_player, spawn_position};
+use crate::net::{Connection, ConnectionExt};
 use crate::net::State::Play;
 use crate::state::GlobalState;
+use crate::utils::ban_list::Ban;
+use crate::utils::components::active_effects::ActiveEffects;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::equipment::Equipment;
+use crate::utils::components::exact_position::ExactPosition;
+use crate::utils::components::flying::Flying;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::grounded::Grounded;
+use crate::utils::components::held_item::HeldItem;
+use crate::utils::components::inventory::{Inventory, PLAYER_WINDOW_ID};
 use crate::utils::components::keep_alive::KeepAlive;
+use crate::utils::components::network_id::NetworkId;
+use crate::utils::components::pending_teleports::PendingTeleports;
 use crate::utils::components::player::Player;
-use crate::utils::components::rotation::Rotation;
-use crate::utils::constants::init;
-use crate::utils::encoding::position::Position;
+use crate::utils::components::profile_properties::ProfileProperties;
+use crate::utils::components::resource_pack::ResourcePackStatus;
+use crate::utils::config::get_global_config;
+use crate::utils::player_data::{load_player_data, PlayerData};
 use crate::utils::prelude::*;
+use crate::world::dimensions::Dimension;
 
 /// The login start packet is sent by the client to the server to start the login process.
 ///
@@ -34,7 +58,11 @@ use crate::utils::prelude::*;
 /// No response is required from the client while these are being sent.
 ///
 /// This is the final stage in the login process. The client is now in the play state.
-#[derive(NetDecode)]
+///
+/// In online mode the server first sends an [EncryptionRequest] instead, and the rest of the login
+/// happens once [crate::net::packets::incoming::encryption_response::EncryptionResponse] has
+/// authenticated the player.
+#[derive(NetDecode, Debug)]
 #[packet(packet_id = 0x00, state = "login")]
 pub struct LoginStart {
     pub username: String,
@@ -50,55 +78,305 @@ const NBT_CODEC: &[u8] = include_bytes!("../../../../.etc/nbt_codec.nbt");
 #[cfg(test)]
 const NBT_CODEC: &[u8] = &[0u8; 1];
 
+/// How long to wait for Velocity to answer the player info request.
+const VELOCITY_RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sent to a session that's kicked because the player logged in again.
+const LOGGED_IN_ELSEWHERE: &str = "You logged in from another location";
+/// Sent to a new login that's turned away because the player is already online.
+const ALREADY_ONLINE: &str = "You are already logged in to this server";
+/// Sent to players that aren't on the whitelist while it's enabled.
+const NOT_WHITELISTED: &str = "You are not whitelisted on this server";
+
 impl IncomingPacket for LoginStart {
     async fn handle(mut self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
         self.username = self.username.trim().to_string();
 
+        let conn = state.connections.get_connection(conn_id)?;
+        let lenient = get_global_config().lenient_usernames;
+        if let Err(problem) = validate_username(&self.username, lenient) {
+            debug!("Rejecting username {:?}: {}", self.username, problem);
+            return conn.kick(problem, state).await;
+        }
+        if !reserve_player_slot(&conn, &state).await? {
+            return conn.kick("The server is full!", state).await;
+        }
+
+        // A proxy in front of the server already authenticated the player, and told us who they are.
+        let forwarded = conn.read().await.metadata.forwarded.clone();
+        if let Some(forwarded) = forwarded {
+            debug!("Using forwarded UUID {} for {}", forwarded.uuid, self.username);
+            self.uuid = forwarded.uuid.as_u128();
+            let login_success =
+                LoginSuccess::new(forwarded.uuid, self.username.clone(), forwarded.properties);
+            return self.finish_login(conn_id, state, login_success).await;
+        }
+
+        if !get_global_config().proxy.velocity_secret.is_empty() {
+            return self.request_velocity_forwarding(conn_id, state).await;
+        }
+
+        if get_global_config().online_mode {
+            return self.request_encryption(conn_id, state).await;
+        }
+
+        let login_success = self.offline_login_success(&state).await;
+        self.finish_login(conn_id, state, login_success).await
+    }
+}
+
+impl LoginStart {
+    /// Starts the encryption handshake. The client's username and the verify token are kept in the
+    /// connection metadata until the encryption response comes back.
+    async fn request_encryption(&self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        debug!("LoginStart packet received, requesting encryption");
+        debug!("Username: {}", self.username);
+
+        let conn = state.connections.get_connection(conn_id)?;
+        let public_key = get_server_key()?.public_key_der.clone();
+        let verify_token = random::<[u8; 4]>().to_vec();
+
+        let mut conn = conn.write().await;
+        conn.metadata.username = Some(self.username.clone());
+        conn.metadata.verify_token = Some(verify_token.clone());
+
+        conn.send_packet(EncryptionRequest::new(public_key, verify_token))
+            .await
+    }
+
+    /// Asks Velocity for the player's info. The login is finished by
+    /// [crate::net::packets::incoming::login_plugin_response::LoginPluginResponse], or the client
+    /// is kicked if it doesn't answer in time.
+    async fn request_velocity_forwarding(
+        &self,
+        conn_id: ConnectionId,
+        state: GlobalState,
+    ) -> Result<()> {
+        debug!("LoginStart packet received, requesting Velocity player info");
+
+        let conn = state.connections.get_connection(conn_id)?;
+        let message_id = i32::from(random::<u16>());
+
+        {
+            let mut conn = conn.write().await;
+            conn.metadata.velocity_message_id = Some(message_id);
+            let request = LoginPluginRequest::new(
+                message_id,
+                VELOCITY_CHANNEL,
+                vec![VELOCITY_FORWARDING_VERSION],
+            );
+            conn.send_packet(request).await?;
+        }
+
+        // Velocity answers right away, so anything slower didn't come through it.
+        tokio::spawn(async move {
+            tokio::time::sleep(VELOCITY_RESPONSE_TIMEOUT).await;
+            if conn.read().await.metadata.velocity_message_id != Some(message_id) {
+                return;
+            }
+            warn!("Connection {} never answered the Velocity player info request", conn_id);
+            if let Err(e) = conn
+                .kick("This server requires you to connect with Velocity.", state)
+                .await
+            {
+                debug!("Failed to kick connection {}: {}", conn_id, e);
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Sends `login_success` along with everything the client needs to spawn, and moves the
+    /// connection into the play state.
+    pub(crate) async fn finish_login(
+        self,
+        conn_id: ConnectionId,
+        state: GlobalState,
+        login_success: LoginSuccess,
+    ) -> Result<()> {
         let conn = state.connections.get_connection(conn_id)?;
         // let conn = conn.read().await;
 
-        let mut packet_queue = PacketQueue::new();
+        if let Some(ban) = self.find_ban(&login_success, &conn, &state).await {
+            debug!("{} is banned, disconnecting", self.username);
+            return conn.kick(ban.disconnect_reason(), state).await;
+        }
+        if !is_whitelisted(&login_success, &state).await {
+            debug!("{} is not whitelisted, disconnecting", self.username);
+            return conn.kick(NOT_WHITELISTED, state).await;
+        }
+        if !self.resolve_duplicate_login(conn_id, &state).await {
+            debug!("{} is already online, disconnecting", self.username);
+            return conn.kick(ALREADY_ONLINE, state).await;
+        }
+
+        // Has to go out on its own, since everything after it is compressed.
+        self.set_compression(&mut *conn.write().await).await?;
 
-        self.send_login_success(&mut packet_queue).await?;
-        self.send_login_play(&mut packet_queue).await?;
-        self.send_spawn_position(&mut packet_queue).await?;
+        let mut packet_queue = PacketQueue::new();
 
-        let data: i64 = random();
-        let mut keep_alive = KeepAlive::new(Instant::now(), Instant::now(), data);
-        self.send_keep_alive(&mut packet_queue, &mut keep_alive)
+        let properties = login_success
+            .properties
+            .iter()
+            .map(ProfileProperty::from)
+            .collect();
+        packet_queue.queue(login_success).await?;
+        let network_id = state.entity_ids.allocate(conn_id);
+        state
+            .world
+            .get_component_storage()
+            .insert(conn_id, NetworkId::new(network_id));
+        // Returning players pick up where they left off.
+        let mut data = load_player_data(&state.database, self.uuid)
+            .await?
+            .unwrap_or_else(PlayerData::first_join);
+        let dimension = match state.dimensions.get(&data.dimension) {
+            Some(dimension) => dimension.clone(),
+            None => {
+                warn!(
+                    "{} left in {}, which isn't in the config anymore. Moving them to spawn",
+                    self.username, data.dimension
+                );
+                data.position = ExactPosition::corner_of(&spawn_position());
+                state.dimensions.overworld().clone()
+            }
+        };
+        let gamemode = data.gamemode;
+        self.send_login_play(network_id, gamemode, &dimension, &state, &mut packet_queue)
+            .await?;
+        packet_queue
+            .queue(InitializeWorldBorder::new(
+                &state.border.get(),
+                Instant::now(),
+            ))
             .await?;
-        self.update_world_state(&*conn.read().await, keep_alive, state.clone())
+        packet_queue
+            .queue(state.abilities(gamemode, gamemode.is_always_flying()))
             .await?;
 
-        self.synchronize_player_position(state.clone(), &*conn.read().await, &mut packet_queue)
+        let keep_alive_id: i64 = random();
+        let mut keep_alive = KeepAlive::new(keep_alive_id);
+        self.send_keep_alive(&mut packet_queue, &mut keep_alive)
             .await?;
+        let held_item = data.held_item;
+        self.update_world_state(
+            &*conn.read().await,
+            keep_alive,
+            properties,
+            data,
+            dimension,
+            state.clone(),
+        )
+        .await?;
+
+        place_player(conn_id, &state, &mut packet_queue).await?;
+
+        packet_queue.queue(SetHeldItem::new(held_item)).await?;
+        let inventory = {
+            let component_storage = state.world.get_component_storage();
+            let mut inventory = component_storage.get_mut::<Inventory>(conn_id).await?;
+            SetContainerContent::from_inventory(PLAYER_WINDOW_ID, &mut inventory)
+        };
+        packet_queue.queue(inventory).await?;
+
+        let brand = PluginMessage::server_brand(&get_global_config().brand).await?;
+        packet_queue.queue(brand).await?;
 
-        let packet = LoginPluginRequest::server_brand("🦀".repeat(100)).await;
-        // conn.send_packet(packet).await?;
-        packet_queue.queue(packet).await?;
+        let resource_pack = &get_global_config().resource_pack;
+        if resource_pack.is_enabled() {
+            packet_queue.queue(ResourcePack::new(resource_pack)?).await?;
+            state
+                .world
+                .get_component_storage()
+                .insert(conn_id, ResourcePackStatus::Pending);
+        }
 
+        // Send all the queued packets
+        conn.read().await.send_packets(packet_queue).await?;
+        conn.write().await.state = Play;
+
+        // Handlers can send the player packets from here on.
         let event = PlayerJoinWorldEvent::new(conn_id);
         state.dispatch_event(event).await;
 
-        let mut conn = conn.write().await;
-        // Send all the queued packets
-        conn.send_packets(packet_queue).await?;
+        ChunkSender::send_chunks_to_player(state.clone(), conn_id).await?;
 
-        conn.state = Play;
+        Ok(())
+    }
 
-        let entity = conn.id;
+    /// Checks whether the player is banned, either by UUID or by the IP a proxy forwarded for them.
+    /// Other IPs are already checked when the connection is accepted.
+    async fn find_ban(
+        &self,
+        login_success: &LoginSuccess,
+        conn: &RwLock<Connection>,
+        state: &GlobalState,
+    ) -> Option<Ban> {
+        let uuid = Uuid::from_slice(&login_success.uuid).ok()?;
+        if let Some(ban) = state.bans.player_ban(uuid).await {
+            return Some(ban);
+        }
+
+        let forwarded_ip = conn
+            .read()
+            .await
+            .metadata
+            .forwarded
+            .as_ref()
+            .map(|forwarded| forwarded.address);
+        match forwarded_ip {
+            Some(ip) => state.bans.ip_ban(ip).await,
+            None => None,
+        }
+    }
 
-        // Drop connection to avoid deadlock with chunk sender since it also needs to write to the connection
-        drop(conn);
+    /// Deals with sessions that are already online for the same player, following the
+    /// `duplicate_login` policy. Returns `false` if the new login should be turned away.
+    /// The new session is registered in the same step, see
+    /// [crate::net::ConnectionList::claim_session].
+    async fn resolve_duplicate_login(&self, conn_id: ConnectionId, state: &GlobalState) -> bool {
+        let Some(existing) = state.connections.claim_session(
+            conn_id,
+            self.uuid,
+            &self.username,
+            get_global_config().duplicate_login,
+        ) else {
+            return false;
+        };
 
-        ChunkSender::send_chunks_to_player(state.clone(), entity).await?;
+        for entity in existing {
+            debug!(
+                "{} logged in again, disconnecting connection {}",
+                self.username, entity
+            );
+            // It might have disconnected on its own in the meantime.
+            let Ok(old_conn) = state.connections.get_connection(entity) else {
+                continue;
+            };
+            // Kicking drops the connection, which despawns the player like any other quit.
+            if let Err(e) = old_conn.kick(LOGGED_IN_ELSEWHERE, state.clone()).await {
+                debug!("Failed to kick connection {}: {}", entity, e);
+            }
+        }
+        true
+    }
 
+    /// Turns on compression for the connection, unless it's disabled in the config.
+    async fn set_compression(&self, conn: &mut Connection) -> Result<()> {
+        let threshold = get_global_config().network_compression_threshold;
+        if threshold < 0 {
+            return Ok(());
+        }
+
+        conn.send_packet(SetCompression::new_auto(VarInt::new(threshold)))
+            .await?;
+        conn.metadata.compression_threshold = Some(threshold as usize);
         Ok(())
     }
-}
 
-impl LoginStart {
-    async fn send_login_success(&self, packet_queue: &mut PacketQueue) -> Result<()> {
+    /// The player's skin is only known if `fetch_skins` is on, since nobody vouched for their
+    /// name.
+    async fn offline_login_success(&mut self, state: &GlobalState) -> LoginSuccess {
         debug!("LoginStart packet received");
         debug!("Username: {}", self.username);
         let uuid = Uuid::from_u128(self.uuid);
@@ -106,38 +384,40 @@ impl LoginStart {
 
         let namespace_uuid = Uuid::new_v5(&Uuid::NAMESPACE_URL, "OfflinePlayer".as_bytes());
         let uuid = Uuid::new_v3(&namespace_uuid, self.username.as_bytes());
+        // The player's component has to have the UUID they're actually known by.
+        self.uuid = uuid.as_u128();
 
-        let response = LoginSuccess::new_auto(
-            uuid.as_bytes().into(),
-            "OfflinePlayer".to_string(),
-            VarInt::new(0),
-            vec![],
-        );
-
-        packet_queue.queue(response).await?;
-
-        // let mut cursor = std::io::Cursor::new(Vec::new());
-        // response.net_encode(&mut cursor).await?;
-        // let response = cursor.into_inner();
-        // conn.socket.write_all(&*response).await?;
-        Ok(())
+        let properties = if get_global_config().fetch_skins {
+            state.skin_for(&self.username).await
+        } else {
+            Vec::new()
+        };
+        LoginSuccess::new(uuid, self.username.clone(), properties)
     }
 
-    async fn send_login_play(&self, packet_queue: &mut PacketQueue) -> Result<()> {
+    async fn send_login_play(
+        &self,
+        network_id: i32,
+        gamemode: GameMode,
+        dimension: &Dimension,
+        state: &GlobalState,
+        packet_queue: &mut PacketQueue,
+    ) -> Result<()> {
+        let dimension_names = state.dimensions.names();
         let play_packet = crate::net::packets::outgoing::login_play::LoginPlay {
             packet_id: VarInt::from(0x28),
-            entity_id: 0,
+            entity_id: network_id,
             hardcore: false,
-            gamemode: 1,
+            gamemode: gamemode.id(),
             previous_gamemode: -1,
-            dimension_length: VarInt::new(1),
-            dimension_names: vec!["minecraft:overworld".to_string()],
+            dimension_length: VarInt::new(dimension_names.len() as i32),
+            dimension_names,
             registry_codec: NBT_CODEC,
-            dimension_type: "minecraft:overworld".to_string(),
-            dimension_name: "minecraft:overworld".to_string(),
+            dimension_type: dimension.dimension_type.clone(),
+            dimension_name: dimension.name.clone(),
             seed_hash: 0,
-            max_players: VarInt::new(20),
-            view_distance: VarInt::new(10),
+            max_players: VarInt::new(get_global_config().max_players.max(0)),
+            view_distance: VarInt::new(view_distance(None)),
             simulation_distance: VarInt::new(10),
             reduced_debug_info: false,
             enable_respawn_screen: true,
@@ -156,17 +436,6 @@ impl LoginStart {
         Ok(())
     }
 
-    async fn send_spawn_position(&self, packet_queue: &mut PacketQueue) -> Result<()> {
-        let player_position = Position {
-            x: init::DEFAULT_SPAWN_X_POS,
-            y: init::DEFAULT_SPAWN_Y_POS,
-            z: init::DEFAULT_SPAWN_Z_POS,
-        };
-        let spawn_position = DefaultSpawnPosition::new_auto(player_position.clone(), 0.0);
-        packet_queue.queue(spawn_position).await?;
-        Ok(())
-    }
-
     async fn send_keep_alive(
         &self,
         packet_queue: &mut PacketQueue,
@@ -182,46 +451,137 @@ impl LoginStart {
         &self,
         conn: &Connection,
         keep_alive: KeepAlive,
+        properties: Vec<ProfileProperty>,
+        data: PlayerData,
+        dimension: Arc<Dimension>,
         state: GlobalState,
     ) -> Result<()> {
         let entity = conn.id;
-
+        let gamemode = data.gamemode;
+        let mut equipment = Equipment::default();
+        equipment.update_from_inventory(&data.inventory, data.held_item);
+
+        // Chunks are sent for the server's view distance until the client's settings arrive.
+        dimension
+            .players
+            .insert(entity, &data.position.block(), view_distance(None));
         let component_storage = state.world.get_component_storage();
 
         component_storage
+            .insert(entity, data.position.block())
+            .insert(entity, data.position)
+            .insert(entity, data.rotation)
+            .insert(entity, CurrentDimension(dimension))
             .insert(
                 entity,
-                Position::new(
-                    init::DEFAULT_SPAWN_X_POS,
-                    init::DEFAULT_SPAWN_Y_POS,
-                    init::DEFAULT_SPAWN_Z_POS,
-                ),
-            )
-            .insert(
-                entity,
-                Rotation::new(init::DEFAULT_SPAWN_YAW, init::DEFAULT_SPAWN_PITCH),
+                Flying {
+                    is_flying: gamemode.is_always_flying(),
+                    ..Flying::default()
+                },
             )
+            .insert(entity, gamemode)
+            .insert(entity, Grounded::new(false))
+            .insert(entity, data.health)
+            .insert(entity, data.food)
+            .insert(entity, data.experience)
+            .insert(entity, ActiveEffects::default())
+            .insert(entity, HeldItem::new(data.held_item))
+            .insert(entity, equipment)
+            .insert(entity, data.inventory)
+            .insert(entity, PendingTeleports::default())
             .insert(entity, keep_alive)
-            .insert(entity, Player::new(self.uuid, self.username.clone()));
+            .insert(entity, Player::new(self.uuid, self.username.clone()))
+            .insert(entity, ProfileProperties::new(properties))
+            .insert(entity, ResourcePackStatus::default());
 
         Ok(())
     }
-    async fn synchronize_player_position(
-        &self,
-        state: GlobalState,
-        conn: &Connection,
-        packet_queue: &mut PacketQueue,
-    ) -> Result<()> {
-        let entity = conn.id;
-        let component_storage = state.world.get_component_storage();
+}
+
+/// Takes a player slot for the connection. Returns `false` if the server is full.
+///
+/// The slot is given back by [crate::net::drop_conn] once the connection closes.
+async fn reserve_player_slot(conn: &RwLock<Connection>, state: &GlobalState) -> Result<bool> {
+    let mut conn = conn.write().await;
+    if conn.metadata.has_player_slot {
+        return Ok(true);
+    }
+    // Checked while holding the lock, so that if the connection is being dropped right now,
+    // drop_conn is guaranteed to see the slot and give it back.
+    if !state.connections.connections.contains_key(&conn.id) {
+        return Err(Error::ConnectionNotFound(conn.id));
+    }
+
+    let max_players = get_global_config().max_players.max(0) as u32;
+    if !state.connections.try_reserve_player_slot(max_players) {
+        return Ok(false);
+    }
+    conn.metadata.has_player_slot = true;
+    Ok(true)
+}
 
-        let position = component_storage.get::<Position>(entity).await?;
-        let rotation = component_storage.get::<Rotation>(entity).await?;
+/// Whether the player can join as far as the whitelist is concerned. Always true while it's off.
+async fn is_whitelisted(login_success: &LoginSuccess, state: &GlobalState) -> bool {
+    if !get_global_config().whitelist.enabled {
+        return true;
+    }
+    match Uuid::from_slice(&login_success.uuid) {
+        Ok(uuid) => state.whitelist.contains(uuid).await,
+        Err(_) => false,
+    }
+}
 
-        let packet = SynchronizePlayerPosition::new(&position, &rotation);
+/// Usernames have to be 1 to 16 characters long, and only use letters, numbers and underscores.
+/// With `lenient` set, dots and dashes are allowed as well.
+///
+/// Returns the reason shown to the player if the username isn't valid.
+fn validate_username(username: &str, lenient: bool) -> core::result::Result<(), &'static str> {
+    if !(1..=16).contains(&username.chars().count()) {
+        return Err("Your username has to be between 1 and 16 characters long.");
+    }
 
-        packet_queue.queue(packet).await?;
+    let allowed =
+        |c: char| c.is_ascii_alphanumeric() || c == '_' || (lenient && (c == '.' || c == '-'));
+    if !username.chars().all(allowed) {
+        return Err(if lenient {
+            "Your username can only contain letters, numbers, underscores, dots and dashes."
+        } else {
+            "Your username can only contain letters, numbers and underscores."
+        });
+    }
+    Ok(())
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_username() {
+        assert!(validate_username("Notch", false).is_ok());
+        assert!(validate_username("a", false).is_ok());
+        assert!(validate_username("Player_1234", false).is_ok());
+        assert!(validate_username(&"a".repeat(16), false).is_ok());
+
+        assert!(validate_username("", false).is_err());
+        assert!(validate_username(&"a".repeat(17), false).is_err());
+        assert!(validate_username("Not ch", false).is_err());
+        assert!(validate_username("Notch\n", false).is_err());
+        assert!(validate_username("Nötch", false).is_err());
+        assert!(validate_username("名前", false).is_err());
+    }
+
+    #[test]
+    fn test_validate_username_lenient() {
+        assert!(validate_username("first.last", true).is_ok());
+        assert!(validate_username("some-player", true).is_ok());
+        assert!(validate_username("first.last", false).is_err());
+        assert!(validate_username("some-player", false).is_err());
+
+        assert!(validate_username("", true).is_err());
+        assert!(validate_username(&"a".repeat(17), true).is_err());
+        assert!(validate_username("Nötch", true).is_err());
+        // 16 characters, but more than 16 bytes.
+        assert!(validate_username(&"ö".repeat(16), true).is_err());
     }
 }
@@ -1,11 +1,30 @@
+pub mod chat_command;
 pub mod chat_message;
+pub mod click_container;
 pub mod client_info;
+pub mod client_status;
+pub mod close_container;
+pub mod command_suggestions_request;
+pub mod confirm_teleportation;
+pub mod encryption_response;
 pub mod handshake;
+pub mod interact;
 pub mod keep_alive;
+pub mod login_plugin_response;
 pub mod login_start;
 pub mod ping;
 pub mod player_abilities;
+pub mod player_action;
+pub mod player_command;
+pub mod plugin_message;
+pub mod pong;
+pub mod resource_pack_response;
+pub mod set_creative_mode_slot;
+pub mod set_held_item;
 pub mod set_player_pos_and_rotate;
 pub mod set_player_position;
 pub mod set_player_rotation;
 pub mod status;
+pub mod swing_arm;
+pub mod update_sign;
+pub mod use_item_on;
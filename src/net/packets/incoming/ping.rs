@@ -1,47 +1,39 @@
-use ferrumc_codec::network_types::varint::VarInt;
 use tracing::debug;
 
 use ferrumc_macros::{packet, NetDecode};
 
-use crate::net::packets::outgoing::ping::OutgoingPing;
+use crate::net::packets::outgoing::ping::PongResponse;
 use crate::net::packets::{ConnectionId, IncomingPacket};
 use crate::state::GlobalState;
 use crate::utils::prelude::*;
 
-/// This ping packet is sent by the client to the server to request a pong.
+/// This ping request packet is sent by the client to the server to request a pong.
 ///
 /// The payload is a random number that the server should return in the pong.
 /// For some reason, seems to be required for the client to acknowledge the server's status response.
-#[derive(NetDecode)]
+#[derive(NetDecode, Debug)]
 #[packet(packet_id = 0x01, state = "status")]
-pub struct Ping {
+pub struct PingRequest {
     pub payload: i64,
 }
 
-impl IncomingPacket for Ping {
+impl IncomingPacket for PingRequest {
     async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
-        debug!("Handling ping packet");
-
-        // tokio::io::AsyncWriteExt::write_all()
-        let response = OutgoingPing {
-            packet_id: VarInt::from(0x01),
-            payload: self.payload,
-        };
-
-        /*let mut cursor = std::io::Cursor::new(Vec::new());
-                response.net_encode(&mut cursor).await?;
-                let response = cursor.into_inner();
-        */
+        debug!("Handling ping request packet");
+
+        let response = PongResponse::new_auto(self.payload);
+
         let conn = state.connections.get_connection(conn_id)?;
-        let mut conn = conn.write().await;
+        let conn = conn.read().await;
 
-        conn.drop = true;
+        conn.send_packet(response).await?;
 
-        /* conn.socket
-        .write_all(&*response)
-        .await
-        .map_err(|e| e.into())*/
+        // The server list ping is over once the pong is sent, so there's no reason to keep the
+        // connection around until the client gets bored and closes it.
+        // Only a read lock is taken here, since the connection's read loop may be holding one
+        // while it waits for the next packet.
+        drop(conn);
 
-        conn.send_packet(response).await
+        crate::net::drop_conn(conn_id, state).await
     }
 }
@@ -1,24 +1,72 @@
-use tracing::trace;
+use tracing::{debug, warn};
 
 use ferrumc_macros::{packet, NetDecode};
 
+use crate::net::packets::outgoing::player_abilities::FLYING;
 use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::metadata::broadcast_player_metadata;
+use crate::net::ConnectionExt;
 use crate::state::GlobalState;
+use crate::utils::components::flying::Flying;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::prelude::*;
 
-#[derive(NetDecode)]
+/// Sent to players that keep trying to fly where they aren't allowed to, same as vanilla.
+pub const FLYING_NOT_ENABLED: &str = "Flying is not enabled on this server";
+
+/// Sent when the player starts or stops flying.
+///
+/// - `flags`: Only the [FLYING] bit means anything, the client can't change its other abilities.
+#[derive(NetDecode, Debug)]
 #[packet(packet_id = 0x1C, state = "play")]
-pub struct PlayerAbilities {
+pub struct PlayerAbilitiesPacketIn {
     pub flags: u8,
 }
 
-impl IncomingPacket for PlayerAbilities {
-    async fn handle(
-        self,
-        _: ConnectionId,
-        _state: GlobalState,
-    ) -> crate::utils::prelude::Result<()> {
-        trace!("PlayerAbilities packet received");
-        trace!("Flags: {}", self.flags);
-        Ok(())
+impl PlayerAbilitiesPacketIn {
+    pub fn is_flying(&self) -> bool {
+        self.flags & FLYING != 0
+    }
+}
+
+impl IncomingPacket for PlayerAbilitiesPacketIn {
+    /// Lets players that are allowed to fly do it. Anyone else is sent their real abilities back,
+    /// and kicked if they keep trying.
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let component_storage = state.world.get_component_storage();
+        let game_mode = component_storage
+            .get::<GameMode>(conn_id)
+            .await
+            .map(|game_mode| *game_mode)
+            .unwrap_or_default();
+        let is_flying = self.is_flying();
+
+        if !is_flying || state.may_fly(game_mode) {
+            component_storage
+                .get_mut_or_insert_with(conn_id, Flying::default)
+                .await
+                .set_flying(is_flying);
+            debug!("Connection {} set flying to {}", conn_id, is_flying);
+            return broadcast_player_metadata(conn_id, &state).await;
+        }
+
+        let struck_out = {
+            let mut flying = component_storage
+                .get_mut_or_insert_with(conn_id, Flying::default)
+                .await;
+            flying.add_violation();
+            warn!(
+                "Connection {} tried to fly in {:?} (violation {})",
+                conn_id, game_mode, flying.violations
+            );
+            flying.is_struck_out()
+        };
+
+        let conn = state.connections.get_connection(conn_id)?;
+        if struck_out {
+            return conn.kick(FLYING_NOT_ENABLED, state).await;
+        }
+        let conn = conn.read().await;
+        conn.send_packet(state.abilities(game_mode, false)).await
     }
 }
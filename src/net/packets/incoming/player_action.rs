@@ -0,0 +1,314 @@
+use std::sync::Arc;
+
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::{debug, warn};
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::events::block_events::BlockBreakEvent;
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::net::packets::outgoing::acknowledge_block_change::AcknowledgeBlockChange;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::combat::block_within_reach;
+use crate::net::utils::dropped_items::throw_held_item;
+use crate::state::GlobalState;
+use crate::utils::components::digging::Digging;
+use crate::utils::components::exact_position::ExactPosition;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::utils::sound::{block_center, Sound, SoundCategory};
+use crate::world::blocks::air;
+use crate::world::hardness::{breaks_instantly, is_unbreakable};
+
+/// What a [PlayerAction] is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerActionStatus {
+    StartedDigging,
+    CancelledDigging,
+    FinishedDigging,
+    DropItemStack,
+    DropItem,
+    /// Also sent when the player finishes eating.
+    ShootArrow,
+    SwapItemInHand,
+}
+
+/// Sent when the player digs a block, and for a few item actions that don't need a target.
+///
+/// - `face`: Which side of the block was hit, 0 to 5 for bottom, top, north, south, west, east.
+/// - `sequence`: Has to be acknowledged once we're done with the block, see
+///   [AcknowledgeBlockChange].
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x1D, state = "play")]
+pub struct PlayerAction {
+    pub status: VarInt,
+    pub location: Position,
+    pub face: i8,
+    pub sequence: VarInt,
+}
+
+impl PlayerAction {
+    pub fn status(&self) -> Option<PlayerActionStatus> {
+        Some(match self.status.get_val() {
+            0 => PlayerActionStatus::StartedDigging,
+            1 => PlayerActionStatus::CancelledDigging,
+            2 => PlayerActionStatus::FinishedDigging,
+            3 => PlayerActionStatus::DropItemStack,
+            4 => PlayerActionStatus::DropItem,
+            5 => PlayerActionStatus::ShootArrow,
+            6 => PlayerActionStatus::SwapItemInHand,
+            _ => return None,
+        })
+    }
+}
+
+impl IncomingPacket for PlayerAction {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        match self.status() {
+            Some(
+                status @ (PlayerActionStatus::StartedDigging
+                | PlayerActionStatus::CancelledDigging
+                | PlayerActionStatus::FinishedDigging),
+            ) => {
+                dig(conn_id, status, &self.location, state.clone()).await?;
+            }
+            // Not about a block, so there's nothing to acknowledge.
+            Some(PlayerActionStatus::DropItem) => {
+                return throw_held_item(conn_id, false, &state).await;
+            }
+            Some(PlayerActionStatus::DropItemStack) => {
+                return throw_held_item(conn_id, true, &state).await;
+            }
+            Some(status) => {
+                debug!("Ignoring player action {:?} from {}", status, conn_id);
+                return Ok(());
+            }
+            None => {
+                warn!(
+                    "Connection {} sent an unknown player action {}",
+                    conn_id,
+                    self.status.get_val()
+                );
+                return Ok(());
+            }
+        }
+
+        // Sent even if the block couldn't be broken, so the client stops waiting for it. The
+        // client puts back whatever it was last sent once it's acknowledged, so the block has
+        // to go first.
+        state.flush_block_changes().await;
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(AcknowledgeBlockChange::new(self.sequence))
+            .await
+    }
+}
+
+/// Handles a player digging the block at `location`.
+///
+/// Blocks break as soon as players in creative start digging them. In survival, players have to
+/// finish digging the block they started on, though how long that took isn't checked yet, unless
+/// it breaks straight away, see [breaks_instantly]. Clients don't say they've finished digging
+/// those.
+/// Adventure players and spectators can't break anything, and are sent the block back.
+async fn dig(
+    conn_id: ConnectionId,
+    status: PlayerActionStatus,
+    location: &Position,
+    state: GlobalState,
+) -> Result<()> {
+    let game_mode = state.game_mode(conn_id).await;
+    if !game_mode.can_build() {
+        debug!(
+            "Connection {} tried to break a block in {:?}",
+            conn_id, game_mode
+        );
+        let dimension = state.dimension_of(conn_id).await;
+        return state.resend_block(&dimension, conn_id, location).await;
+    }
+    if game_mode == GameMode::Creative {
+        return match status {
+            PlayerActionStatus::StartedDigging => break_block(conn_id, location, state).await,
+            _ => Ok(()),
+        };
+    }
+
+    let component_storage = state.world.get_component_storage();
+    let started = std::mem::take(
+        &mut component_storage
+            .get_mut_or_insert_with(conn_id, Digging::default)
+            .await
+            .location,
+    );
+    match status {
+        PlayerActionStatus::StartedDigging => {
+            let dimension = state.dimension_of(conn_id).await;
+            let block = state.get_block(&dimension, location).await?;
+            if block.is_some_and(|block| breaks_instantly(&block.name)) {
+                return break_block(conn_id, location, state).await;
+            }
+            component_storage
+                .get_mut_or_insert_with(conn_id, Digging::default)
+                .await
+                .location = Some(location.clone());
+        }
+        PlayerActionStatus::FinishedDigging if started.as_ref() == Some(location) => {
+            return break_block(conn_id, location, state).await;
+        }
+        PlayerActionStatus::FinishedDigging => {
+            debug!(
+                "Connection {} finished digging {} without starting on it",
+                conn_id, location
+            );
+            let dimension = state.dimension_of(conn_id).await;
+            return state.resend_block(&dimension, conn_id, location).await;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Turns the block at `location` into air, unless it's out of the player's reach, can't be broken
+/// outside of creative, or a [BlockBreakEvent] handler cancels it, in which case the player is
+/// sent the block back.
+///
+/// Players nearby hear it break. The player breaking it plays the sound themselves. Blocks broken
+/// by players in survival drop their item, see
+/// [ServerState::drop_block](crate::state::ServerState::drop_block).
+///
+/// Blocks in chunks that aren't loaded are left alone.
+async fn break_block(conn_id: ConnectionId, location: &Position, state: GlobalState) -> Result<()> {
+    let dimension = state.dimension_of(conn_id).await;
+    let position = *state.world.get_component::<ExactPosition>(conn_id).await?;
+    if !block_within_reach(&position, location) {
+        warn!(
+            "Connection {} at {} tried to break a block at {}, which is out of reach",
+            conn_id,
+            position.block(),
+            location
+        );
+        return state.resend_block(&dimension, conn_id, location).await;
+    }
+    let Some(block) = state.get_block(&dimension, location).await? else {
+        debug!(
+            "Connection {} tried to break a block at {}, which isn't loaded",
+            conn_id, location
+        );
+        return Ok(());
+    };
+
+    let game_mode = state.game_mode(conn_id).await;
+    if game_mode != GameMode::Creative && is_unbreakable(&block.name) {
+        debug!(
+            "Connection {} tried to break {} in {:?}",
+            conn_id, block.name, game_mode
+        );
+        return state.resend_block(&dimension, conn_id, location).await;
+    }
+
+    let sound = Sound::block_break(&block.name);
+    let block_name = block.name.clone();
+    let event = Arc::new(BlockBreakEvent::new(conn_id, location.clone(), block));
+    state.dispatch_shared_event(event.clone()).await;
+    if event.is_cancelled() {
+        return state.resend_block(&dimension, conn_id, location).await;
+    }
+
+    state.set_block(&dimension, location, air()).await?;
+    if game_mode == GameMode::Survival {
+        state
+            .drop_block(dimension.clone(), location, &block_name)
+            .await;
+    }
+    state
+        .play_sound_at(
+            &dimension,
+            block_center(location),
+            sound,
+            SoundCategory::Blocks,
+            1.0,
+            0.8,
+            Some(conn_id),
+        )
+        .await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::create_state;
+    use crate::world::conversions::default_block_state;
+
+    #[tokio::test]
+    async fn test_decode_player_action() {
+        // Finished digging at (1, 64, -1), on top, with sequence 5.
+        let location = ((1u64 & 0x3FFFFFF) << 38) | ((-1i64 as u64 & 0x3FFFFFF) << 12) | 64;
+        let mut data = vec![0x02];
+        data.extend_from_slice(&location.to_be_bytes());
+        data.extend_from_slice(&[0x01, 0x05]);
+
+        let packet = PlayerAction::net_decode(&mut Cursor::new(data))
+            .await
+            .unwrap();
+        assert_eq!(packet.status(), Some(PlayerActionStatus::FinishedDigging));
+        assert_eq!(
+            (packet.location.x, packet.location.y, packet.location.z),
+            (1, 64, -1)
+        );
+        assert_eq!(packet.face, 1);
+        assert_eq!(packet.sequence.get_val(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_instant_blocks_break_when_started_in_survival() {
+        let state = create_state(vec![TcpListener::bind("127.0.0.1:0").await.unwrap()])
+            .await
+            .unwrap();
+        let overworld = state.dimensions.overworld();
+        let (chunk_x, chunk_z) = (5_432, -8_765);
+        let chunk = overworld.generator.generate(chunk_x, chunk_z);
+        state.database.insert_chunk(chunk).await.unwrap();
+
+        let feet = Position::new(chunk_x * 16 + 8, 200, chunk_z * 16 + 8);
+        let torch = Position::new(feet.x + 1, 200, feet.z);
+        let stone = Position::new(feet.x - 1, 200, feet.z);
+        for (location, name) in [(&torch, "minecraft:torch"), (&stone, "minecraft:stone")] {
+            let block = default_block_state(name).unwrap();
+            state.set_block(overworld, location, block).await.unwrap();
+        }
+        let conn_id = 4_321;
+        state
+            .world
+            .get_component_storage()
+            .insert(conn_id, ExactPosition::corner_of(&feet))
+            .insert(conn_id, feet)
+            .insert(conn_id, GameMode::Survival);
+
+        let started = PlayerActionStatus::StartedDigging;
+        dig(conn_id, started, &torch, state.clone()).await.unwrap();
+        dig(conn_id, started, &stone, state.clone()).await.unwrap();
+        let block = |location| {
+            let state = state.clone();
+            async move {
+                state
+                    .get_block(overworld, &location)
+                    .await
+                    .unwrap()
+                    .unwrap()
+            }
+        };
+        assert_eq!(block(torch).await, air());
+        assert_eq!(block(stone.clone()).await.name, "minecraft:stone");
+
+        // Once it's been started on, the stone breaks when it's finished.
+        let finished = PlayerActionStatus::FinishedDigging;
+        dig(conn_id, finished, &stone, state.clone()).await.unwrap();
+        assert_eq!(block(stone).await, air());
+    }
+}
@@ -0,0 +1,60 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::{debug, warn};
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::metadata::broadcast_player_metadata;
+use crate::state::GlobalState;
+use crate::utils::components::sneaking::Sneaking;
+use crate::utils::components::sprinting::Sprinting;
+use crate::utils::prelude::*;
+
+const START_SNEAKING: i32 = 0;
+const STOP_SNEAKING: i32 = 1;
+const START_SPRINTING: i32 = 3;
+const STOP_SPRINTING: i32 = 4;
+
+/// Sent when the player starts or stops sneaking or sprinting, and for a few things to do with
+/// beds and horses.
+///
+/// - `entity_id`: The player's own entity id.
+/// - `jump_boost`: How hard a horse jumps, from 0 to 100. 0 for everything else.
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x1E, state = "play")]
+pub struct PlayerCommand {
+    pub entity_id: VarInt,
+    pub action_id: VarInt,
+    pub jump_boost: VarInt,
+}
+
+impl IncomingPacket for PlayerCommand {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        if self.entity_id.get_val() != state.network_id(conn_id).await? {
+            warn!(
+                "Connection {} sent a player command for entity {}",
+                conn_id,
+                self.entity_id.get_val()
+            );
+            return Ok(());
+        }
+
+        let component_storage = state.world.get_component_storage();
+        match self.action_id.get_val() {
+            action @ (START_SNEAKING | STOP_SNEAKING) => component_storage
+                .get_mut_or_insert_with(conn_id, Sneaking::default)
+                .await
+                .set_sneaking(action == START_SNEAKING),
+            action @ (START_SPRINTING | STOP_SPRINTING) => component_storage
+                .get_mut_or_insert_with(conn_id, Sprinting::default)
+                .await
+                .set_sprinting(action == START_SPRINTING),
+            action => {
+                debug!("Ignoring player command {} from {}", action, conn_id);
+                return Ok(());
+            }
+        }
+
+        broadcast_player_metadata(conn_id, &state).await
+    }
+}
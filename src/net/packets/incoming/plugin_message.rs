@@ -0,0 +1,78 @@
+use tracing::{debug, warn};
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::impls::packet_impls::RemainingBytes;
+use crate::utils::plugin_channels::{
+    parse_channel, parse_channel_list, MAX_REGISTERED_CHANNELS, REGISTER_CHANNEL,
+    UNREGISTER_CHANNEL,
+};
+use crate::utils::prelude::*;
+
+/// A custom payload sent by the client on a plugin channel.
+///
+/// Clients list the channels they can receive on with [REGISTER_CHANNEL] and
+/// [UNREGISTER_CHANNEL], which is kept track of for each connection. Everything else goes to
+/// whatever's listening on its channel, see
+/// [PluginChannels](crate::utils::plugin_channels::PluginChannels).
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x0D, state = "play")]
+pub struct ServerboundPluginMessage {
+    pub channel: String,
+    pub data: RemainingBytes,
+}
+
+impl IncomingPacket for ServerboundPluginMessage {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let Some(channel) = parse_channel(&self.channel) else {
+            debug!(
+                "Connection {} sent a plugin message on an invalid channel {:?}",
+                conn_id, self.channel
+            );
+            return Ok(());
+        };
+
+        match channel.as_str() {
+            REGISTER_CHANNEL => register_channels(conn_id, &self.data.0, state).await,
+            UNREGISTER_CHANNEL => {
+                let channels = parse_channel_list(&self.data.0);
+                let conn = state.connections.get_connection(conn_id)?;
+                let mut conn = conn.write().await;
+                for channel in channels {
+                    conn.metadata.registered_channels.remove(&channel);
+                }
+                Ok(())
+            }
+            _ => {
+                state
+                    .plugin_channels
+                    .dispatch(state.clone(), conn_id, channel, self.data.0)
+                    .await
+            }
+        }
+    }
+}
+
+async fn register_channels(conn_id: ConnectionId, data: &[u8], state: GlobalState) -> Result<()> {
+    let channels = parse_channel_list(data);
+    let conn = state.connections.get_connection(conn_id)?;
+    let mut conn = conn.write().await;
+    let registered = &mut conn.metadata.registered_channels;
+    for channel in channels {
+        if registered.len() >= MAX_REGISTERED_CHANNELS {
+            warn!(
+                "Connection {} tried to register more than {} plugin channels",
+                conn_id, MAX_REGISTERED_CHANNELS
+            );
+            break;
+        }
+        registered.insert(channel);
+    }
+    debug!(
+        "Connection {} registered plugin channels: {:?}",
+        conn_id, registered
+    );
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use tracing::debug;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// The client's answer to a [PlayPing](crate::net::packets::outgoing::play_ping::PlayPing).
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x20, state = "play")]
+pub struct Pong {
+    pub id: i32,
+}
+
+impl IncomingPacket for Pong {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        if !state.pending_pings.complete(conn_id, self.id) {
+            debug!(
+                "Connection {} answered ping {}, which isn't waiting for an answer",
+                conn_id, self.id
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::create_state;
+
+    #[tokio::test]
+    async fn test_pong_answers_ping() {
+        let state = create_state(vec![TcpListener::bind("0.0.0.0:0").await.unwrap()])
+            .await
+            .unwrap();
+        let (id, receiver) = state.pending_pings.start(7);
+        let (other_id, other_receiver) = state.pending_pings.start(7);
+
+        let pong = Pong::net_decode(&mut Cursor::new(id.to_be_bytes().to_vec()))
+            .await
+            .unwrap();
+        // From another connection, so it doesn't count.
+        Pong { id: pong.id }.handle(8, state.clone()).await.unwrap();
+        pong.handle(7, state.clone()).await.unwrap();
+        assert!(receiver.await.unwrap() < Duration::from_secs(5));
+
+        state.pending_pings.cancel(other_id);
+        assert!(other_receiver.await.is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::{debug, warn};
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::ConnectionExt;
+use crate::state::GlobalState;
+use crate::utils::components::resource_pack::ResourcePackStatus;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+/// The client's answer to a [ResourcePack](crate::net::packets::outgoing::resource_pack::ResourcePack),
+/// sent once when it accepts or declines it, and again once it's loaded or failed to download.
+///
+/// - `result`: See [ResourcePackStatus::from_result].
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x24, state = "play")]
+pub struct ResourcePackResponse {
+    pub result: VarInt,
+}
+
+impl IncomingPacket for ResourcePackResponse {
+    /// Records the player's answer, and kicks them if the pack is required and they didn't
+    /// end up with it.
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let Some(status) = ResourcePackStatus::from_result(self.result.get_val()) else {
+            warn!(
+                "Connection {} sent an unknown resource pack result {}",
+                conn_id,
+                self.result.get_val()
+            );
+            return Ok(());
+        };
+
+        {
+            let mut current = state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with(conn_id, ResourcePackStatus::default)
+                .await;
+            if *current == ResourcePackStatus::NotSent {
+                debug!(
+                    "Connection {} answered a resource pack it wasn't sent",
+                    conn_id
+                );
+                return Ok(());
+            }
+            *current = status;
+        }
+        debug!("Resource pack status for {}: {:?}", conn_id, status);
+
+        let config = &get_global_config().resource_pack;
+        if config.required && status.is_rejected() {
+            let conn = state.connections.get_connection(conn_id)?;
+            return conn.kick(config.kick_message.as_str(), state).await;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use tracing::warn;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::outgoing::set_container_slot::SetContainerSlot;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::inventory::{Inventory, PLAYER_WINDOW_ID};
+use crate::utils::encoding::slot::Slot;
+use crate::utils::prelude::*;
+
+/// Sent when a player in creative puts an item in their inventory, e.g. from the creative
+/// menu. A `slot` of -1 drops the item instead.
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x2B, state = "play")]
+pub struct SetCreativeModeSlot {
+    pub slot: i16,
+    pub item: Slot,
+}
+
+impl IncomingPacket for SetCreativeModeSlot {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let component_storage = state.world.get_component_storage();
+        let creative = component_storage
+            .get::<GameMode>(conn_id)
+            .await
+            .is_ok_and(|game_mode| *game_mode == GameMode::Creative);
+
+        let mut inventory = component_storage
+            .get_mut_or_insert_with(conn_id, Inventory::default)
+            .await;
+        if creative && (self.slot == -1 || inventory.set_slot(self.slot, self.item)) {
+            drop(inventory);
+            state.update_equipment(conn_id).await;
+            return Ok(());
+        }
+
+        warn!(
+            "Connection {} tried to set slot {} without being in creative",
+            conn_id, self.slot
+        );
+        // Undoes the change on the client.
+        let Some(index) = Inventory::index(self.slot) else {
+            return Ok(());
+        };
+        let packet = SetContainerSlot::new(
+            PLAYER_WINDOW_ID,
+            inventory.next_state_id(),
+            self.slot,
+            inventory.slots[index].clone(),
+        );
+        drop(inventory);
+
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(packet).await
+    }
+}
@@ -0,0 +1,39 @@
+use tracing::warn;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::ConnectionExt;
+use crate::state::GlobalState;
+use crate::utils::components::held_item::HeldItem;
+use crate::utils::prelude::*;
+
+/// Sent to clients that select a slot that isn't in the hotbar.
+pub const INVALID_HOTBAR_SLOT: &str = "Invalid hotbar slot selected";
+
+/// Sent when the player selects another hotbar slot, from 0 to 8.
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x28, state = "play")]
+pub struct SetHeldItemPacketIn {
+    pub slot: i16,
+}
+
+impl IncomingPacket for SetHeldItemPacketIn {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let component_storage = state.world.get_component_storage();
+        let mut held_item = component_storage
+            .get_mut_or_insert_with(conn_id, HeldItem::default)
+            .await;
+        if !held_item.select(self.slot) {
+            drop(held_item);
+            warn!("Connection {} selected hotbar slot {}", conn_id, self.slot);
+            let conn = state.connections.get_connection(conn_id)?;
+            return conn.kick(INVALID_HOTBAR_SLOT, state).await;
+        }
+
+        drop(held_item);
+
+        state.update_equipment(conn_id).await;
+        Ok(())
+    }
+}
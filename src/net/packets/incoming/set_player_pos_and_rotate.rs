@@ -1,12 +1,11 @@
 use crate::net::packets::{ConnectionId, IncomingPacket};
-use crate::net::systems::chunk_sender::ChunkSender;
+use crate::net::utils::movement::{handle_movement, Movement};
 use crate::state::GlobalState;
-use crate::utils::components::rotation::Rotation;
-use crate::utils::encoding::position::Position;
 use crate::utils::prelude::*;
 use ferrumc_macros::{packet, NetDecode};
-use tracing::trace;
 
+/// Sent instead of [super::set_player_position::SetPlayerPosition] when the player moved and
+/// turned in the same tick.
 #[derive(NetDecode, Debug)]
 #[packet(packet_id = 0x15, state = "play")]
 pub struct SetPlayerPosAndRotate {
@@ -20,33 +19,34 @@ pub struct SetPlayerPosAndRotate {
 
 impl IncomingPacket for SetPlayerPosAndRotate {
     async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
-        let my_entity_id = conn_id;
-
-        let component_storage = state.world.get_component_storage();
-
-        let mut position = component_storage.get_mut::<Position>(my_entity_id).await?;
-        let mut rotation = component_storage.get_mut::<Rotation>(my_entity_id).await?;
-
-        ChunkSender::send_chunks_to_player_if_needed(
-            state.clone(),
-            my_entity_id,
-            (position.x >> 4, position.z >> 4),
-        )
-        .await?;
-
-        *position = Position {
-            x: self.x as i32,
-            y: self.y as i16,
-            z: self.z as i32,
+        let movement = Movement {
+            position: Some((self.x, self.y, self.z)),
+            rotation: Some((self.yaw, self.pitch)),
+            on_ground: self.on_ground,
         };
+        handle_movement(conn_id, state, movement).await
+    }
+}
 
-        *rotation = Rotation {
-            yaw: self.yaw,
-            pitch: self.pitch,
-        };
-
-        trace!("SetPlayerPosAndRotate packet received: {:?}", self);
-
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_decode() {
+        // Standing at 8.5, 64, -12.25, looking west and slightly down.
+        let bytes = [
+            0x40, 0x21, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x50, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0xC0, 0x28, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42, 0xB4, 0x00, 0x00,
+            0x41, 0x70, 0x00, 0x00, 0x01,
+        ];
+        let packet = SetPlayerPosAndRotate::net_decode(&mut Cursor::new(bytes))
+            .await
+            .unwrap();
+        assert_eq!((packet.x, packet.y, packet.z), (8.5, 64.0, -12.25));
+        assert_eq!((packet.yaw, packet.pitch), (90.0, 15.0));
+        assert!(packet.on_ground);
     }
 }
@@ -1,10 +1,12 @@
 use ferrumc_macros::{packet, NetDecode};
 
 use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::movement::{handle_movement, Movement};
 use crate::state::GlobalState;
-use crate::utils::components::rotation::Rotation;
+use crate::utils::prelude::*;
 
-#[derive(NetDecode)]
+/// Sent when the player turned without moving.
+#[derive(NetDecode, Debug)]
 #[packet(packet_id = 0x16, state = "play")]
 pub struct SetPlayerRotation {
     pub yaw: f32,
@@ -13,22 +15,31 @@ pub struct SetPlayerRotation {
 }
 
 impl IncomingPacket for SetPlayerRotation {
-    async fn handle(
-        self,
-        conn_id: ConnectionId,
-        state: GlobalState,
-    ) -> crate::utils::prelude::Result<()> {
-        let my_entity_id = conn_id;
-
-        let component_storage = state.world.get_component_storage();
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let movement = Movement {
+            position: None,
+            rotation: Some((self.yaw, self.pitch)),
+            on_ground: self.on_ground,
+        };
+        handle_movement(conn_id, state, movement).await
+    }
+}
 
-        let mut rotation = component_storage
-            .get_mut_or_insert_with(my_entity_id, || Rotation::new(0.0, 0.0))
-            .await;
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
 
-        rotation.yaw = self.yaw;
-        rotation.pitch = self.pitch;
+    use super::*;
 
-        Ok(())
+    #[tokio::test]
+    async fn test_decode() {
+        // After turning left one and a quarter times, looking up a bit. Clients don't wrap the
+        // yaw around.
+        let bytes = [0xC3, 0xE1, 0x00, 0x00, 0x41, 0xF4, 0x00, 0x00, 0x00];
+        let packet = SetPlayerRotation::net_decode(&mut Cursor::new(bytes))
+            .await
+            .unwrap();
+        assert_eq!((packet.yaw, packet.pitch), (-450.0, 30.5));
+        assert!(!packet.on_ground);
     }
 }
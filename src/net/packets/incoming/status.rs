@@ -1,27 +1,26 @@
-use base64::Engine;
-use ferrumc_codec::network_types::varint::VarInt;
 use rand::prelude::IndexedRandom;
 use serde::Serialize;
-use tokio::io::AsyncReadExt;
-use tokio::sync::OnceCell;
 use tracing::debug;
 
 use ferrumc_macros::{packet, NetDecode};
 use uuid::Uuid;
 
-use crate::net::packets::outgoing::status::OutgoingStatusResponse;
+use crate::net::packets::incoming::handshake::is_supported_protocol;
+use crate::net::packets::outgoing::status::StatusResponse;
 use crate::net::packets::{ConnectionId, IncomingPacket};
 use crate::state::GlobalState;
 use crate::utils::components::player::Player;
 use crate::utils::config;
+use crate::utils::constants::{GAME_VERSION, PROTOCOL_VERSION};
 use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
 
-/// The status packet is sent by the client to the server to request the server's status.
+/// The status request packet is sent by the client to the server to request the server's status.
 ///
 /// Usually sent after handshaking is completed.
-#[derive(NetDecode)]
+#[derive(NetDecode, Debug)]
 #[packet(packet_id = 0x00, state = "status")]
-pub struct Status;
+pub struct StatusRequest;
 
 /// The response to the status packet.
 /// Sent as json.
@@ -29,14 +28,15 @@ pub struct Status;
 struct JsonResponse {
     version: Version,
     players: Players,
-    description: Description,
-    favicon: &'static String,
+    description: TextComponent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    favicon: Option<&'static str>,
 }
 
 #[derive(Serialize)]
 struct Version {
     name: String,
-    protocol: u32,
+    protocol: i32,
 }
 
 #[derive(Serialize)]
@@ -52,12 +52,7 @@ struct Sample {
     id: String,
 }
 
-#[derive(Serialize)]
-struct Description {
-    text: String,
-}
-
-impl IncomingPacket for Status {
+impl IncomingPacket for StatusRequest {
     async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
         debug!("Handling status request packet");
         let config = config::get_global_config();
@@ -65,6 +60,15 @@ impl IncomingPacket for Status {
         let conn = state.connections.get_connection(conn_id)?;
         let conn = conn.read().await;
 
+        // The client shows the server as incompatible if this doesn't match its own version.
+        let protocol_version = conn.metadata.protocol_version;
+        let allowed_range = config.allow_protocol_range.as_ref();
+        let protocol = if is_supported_protocol(protocol_version, allowed_range) {
+            protocol_version
+        } else {
+            PROTOCOL_VERSION
+        };
+
         let random_motd = config.motd.choose(&mut rand::thread_rng()).unwrap().clone();
 
         //Queries all players and makes a Sample struct from them
@@ -75,24 +79,22 @@ impl IncomingPacket for Status {
             id: Uuid::from_u128(player.uuid).to_string(),
         }).collect();
 
-        let response = OutgoingStatusResponse {
-            packet_id: VarInt::new(0x00),
-            json_response: serde_json::ser::to_string(&JsonResponse {
+        let response = StatusResponse::new_auto(
+            serde_json::ser::to_string(&JsonResponse {
                 version: Version {
-                    name: "1.20.6".to_string(),
-                    // Allow any protocol version for now. To check the ping and stuff
-                    protocol: conn.metadata.protocol_version as u32,
+                    name: GAME_VERSION.to_string(),
+                    protocol,
                 },
                 players: Players {
                     max: config.max_players,
                     online: player_samples.len() as i32,
                     sample: player_samples,
                 },
-                description: Description { text: random_motd },
-                favicon: get_encoded_favicon().await,
+                description: TextComponent::from_legacy(&random_motd),
+                favicon: config::get_favicon(),
             })
-            .unwrap(),
-        };
+            .map_err(|e| Error::SerializationError(e.to_string()))?,
+        );
 
         conn.send_packet(response).await?;
         /*let mut cursor = std::io::Cursor::new(Vec::new());
@@ -106,21 +108,3 @@ impl IncomingPacket for Status {
         Ok(())
     }
 }
-
-/// Get the favicon as a base64 encoded string.
-///
-/// This is cached in a `OnceCell` to avoid reading the file every time.
-async fn get_encoded_favicon() -> &'static String {
-    static FAVICON: OnceCell<String> = OnceCell::const_new();
-    FAVICON
-        .get_or_init(|| async {
-            let mut data = Vec::new();
-            let Ok(mut image) = tokio::fs::File::open("icon-64.png").await else {
-                return String::new();
-            };
-            image.read_to_end(&mut data).await.unwrap_or_default();
-            let data = base64::engine::general_purpose::STANDARD.encode(&data);
-            format!("data:image/png;base64,{}", data)
-        })
-        .await
-}
@@ -0,0 +1,44 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::outgoing::entity_animation::{
+    EntityAnimation, SWING_MAIN_ARM, SWING_OFFHAND,
+};
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+
+/// Sent whenever the player swings their arm, e.g. while digging or hitting something.
+///
+/// - `hand`: 0 for the main hand, 1 for the off hand.
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x2F, state = "play")]
+pub struct SwingArm {
+    pub hand: VarInt,
+}
+
+impl IncomingPacket for SwingArm {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let animation = match self.hand.get_val() {
+            1 => SWING_OFFHAND,
+            _ => SWING_MAIN_ARM,
+        };
+        let position = state
+            .world
+            .get_component::<Position>(conn_id)
+            .await?
+            .clone();
+        let network_id = state.network_id(conn_id).await?;
+        let dimension = state.dimension_of(conn_id).await;
+
+        // The player's client already shows the swing.
+        state
+            .send_to_players_near(&dimension, &position, Some(conn_id), || {
+                EntityAnimation::new(network_id, animation)
+            })
+            .await;
+        Ok(())
+    }
+}
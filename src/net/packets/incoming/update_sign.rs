@@ -0,0 +1,144 @@
+use tracing::{debug, warn};
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::combat::block_within_reach;
+use crate::state::GlobalState;
+use crate::utils::components::exact_position::ExactPosition;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+use crate::world::block_entities::is_sign;
+use crate::world::chunk_format::BlockEntity;
+
+/// How long a line on a sign can be, same as vanilla.
+pub const MAX_SIGN_LINE_LENGTH: usize = 384;
+
+/// Sent when the player is done editing the text on one side of a sign.
+///
+/// - `is_front_text`: Whether it's the front of the sign that was edited, or the back.
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x2E, state = "play")]
+pub struct UpdateSign {
+    pub location: Position,
+    pub is_front_text: bool,
+    pub line1: String,
+    pub line2: String,
+    pub line3: String,
+    pub line4: String,
+}
+
+impl UpdateSign {
+    /// The lines as they'll be stored, or `None` if any of them is too long.
+    ///
+    /// Control characters and section signs are taken out, so a sign can't be given formatting
+    /// codes.
+    pub fn lines(&self) -> Option<[String; 4]> {
+        let lines = [&self.line1, &self.line2, &self.line3, &self.line4];
+        if lines
+            .iter()
+            .any(|line| line.chars().count() > MAX_SIGN_LINE_LENGTH)
+        {
+            return None;
+        }
+        Some(lines.map(|line| {
+            line.chars()
+                .filter(|c| !c.is_control() && *c != '§')
+                .collect()
+        }))
+    }
+}
+
+impl IncomingPacket for UpdateSign {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let location = &self.location;
+        let dimension = state.dimension_of(conn_id).await;
+        let position = *state.world.get_component::<ExactPosition>(conn_id).await?;
+        if !block_within_reach(&position, location) {
+            warn!(
+                "Connection {} at {} edited a sign at {}, which is out of reach",
+                conn_id,
+                position.block(),
+                location
+            );
+            state.resend_block(&dimension, conn_id, location).await?;
+            return state
+                .resend_block_entity(&dimension, conn_id, location)
+                .await;
+        }
+        let Some(block) = state.get_block(&dimension, location).await? else {
+            debug!(
+                "Connection {} edited a sign at {}, which isn't loaded",
+                conn_id, location
+            );
+            return Ok(());
+        };
+        if !is_sign(&block) {
+            debug!(
+                "Connection {} edited a sign at {}, but it's {}",
+                conn_id, location, block.name
+            );
+            return state.resend_block(&dimension, conn_id, location).await;
+        }
+
+        let mut sign = match state.get_block_entity(&dimension, location).await? {
+            Some(sign) => sign,
+            None => BlockEntity::sign(location, &block)?,
+        };
+        let lines = match self.lines() {
+            Some(lines) if !sign.is_waxed()? => lines,
+            _ => {
+                debug!(
+                    "Connection {} sent sign text for {} that was turned down",
+                    conn_id, location
+                );
+                state.resend_block(&dimension, conn_id, location).await?;
+                return state
+                    .resend_block_entity(&dimension, conn_id, location)
+                    .await;
+            }
+        };
+
+        // Only the lines change, the sign keeps its dye and glow.
+        let mut text = sign.sign_text(self.is_front_text)?;
+        text.messages = lines
+            .into_iter()
+            .map(|line| TextComponent::new(line).to_json())
+            .collect::<Result<Vec<_>>>()?;
+        sign.set_sign_text(self.is_front_text, &text)?;
+        state.set_block_entity(&dimension, sign).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_decode_update_sign() {
+        let location = ((1u64 & 0x3FFFFFF) << 38) | ((2u64 & 0x3FFFFFF) << 12) | 64;
+        let mut data = location.to_be_bytes().to_vec();
+        data.push(0x01);
+        for line in ["Hello", "\u{7}§cworld", "", ""] {
+            data.push(line.len() as u8);
+            data.extend_from_slice(line.as_bytes());
+        }
+
+        let packet = UpdateSign::net_decode(&mut Cursor::new(data))
+            .await
+            .unwrap();
+        assert!(packet.is_front_text);
+        let lines = packet.lines().unwrap();
+        assert_eq!(lines[0], "Hello");
+        assert_eq!(lines[1], "cworld");
+
+        let packet = UpdateSign {
+            line4: "a".repeat(MAX_SIGN_LINE_LENGTH + 1),
+            ..packet
+        };
+        assert!(packet.lines().is_none());
+    }
+}
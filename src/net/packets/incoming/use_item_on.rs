@@ -0,0 +1,341 @@
+use std::sync::Arc;
+
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::{debug, warn};
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::events::block_events::BlockPlaceEvent;
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::net::packets::outgoing::acknowledge_block_change::AcknowledgeBlockChange;
+use crate::net::packets::outgoing::set_container_content::SetContainerContent;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::combat::block_within_reach;
+use crate::net::utils::dropped_items::placed_block;
+use crate::state::GlobalState;
+use crate::utils::components::exact_position::ExactPosition;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::held_item::HeldItem;
+use crate::utils::components::inventory::{Inventory, PLAYER_WINDOW_ID};
+use crate::utils::encoding::position::Position;
+use crate::utils::encoding::slot::ItemStack;
+use crate::utils::prelude::*;
+use crate::world::blocks::{MAX_BUILD_HEIGHT, MIN_BUILD_HEIGHT};
+use crate::world::conversions::default_block_state;
+use crate::world::dimensions::Dimension;
+use crate::world::physics::BoundingBox;
+
+/// Blocks that a placed block takes the place of, instead of going next to them.
+const REPLACEABLE_BLOCKS: [&str; 5] = [
+    "minecraft:air",
+    "minecraft:cave_air",
+    "minecraft:void_air",
+    "minecraft:water",
+    "minecraft:lava",
+];
+
+/// Sent when the player right-clicks a block, which places the block they're holding against it.
+///
+/// - `hand`: 0 for the main hand, 1 for the off hand.
+/// - `face`: The side of `location` that was clicked, 0 to 5 for bottom, top, north, south, west,
+///   east.
+/// - `cursor_x`, `cursor_y`, `cursor_z`: Where on that side it was clicked, from 0 to 1.
+/// - `inside_block`: Whether the player's head is inside a block.
+#[derive(NetDecode, Debug)]
+#[packet(packet_id = 0x31, state = "play")]
+pub struct UseItemOn {
+    pub hand: VarInt,
+    pub location: Position,
+    pub face: VarInt,
+    pub cursor_x: f32,
+    pub cursor_y: f32,
+    pub cursor_z: f32,
+    pub inside_block: bool,
+    pub sequence: VarInt,
+}
+
+impl UseItemOn {
+    /// Whether it was the item in the off hand that was used, rather than the main hand.
+    pub fn off_hand(&self) -> bool {
+        self.hand.get_val() == 1
+    }
+
+    /// The block next to `location` on the side that was clicked, which is where the new block
+    /// goes. `None` if the face isn't valid.
+    pub fn target(&self) -> Option<Position> {
+        let Position { x, y, z } = self.location;
+        Some(match self.face.get_val() {
+            0 => Position::new(x, y.checked_sub(1)?, z),
+            1 => Position::new(x, y.checked_add(1)?, z),
+            2 => Position::new(x, y, z - 1),
+            3 => Position::new(x, y, z + 1),
+            4 => Position::new(x - 1, y, z),
+            5 => Position::new(x + 1, y, z),
+            _ => return None,
+        })
+    }
+}
+
+impl IncomingPacket for UseItemOn {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        match self.target() {
+            Some(target) => place_block(conn_id, self.off_hand(), &target, state.clone()).await?,
+            None => warn!(
+                "Connection {} placed a block on an unknown face {}",
+                conn_id,
+                self.face.get_val()
+            ),
+        }
+
+        // Sent even if nothing was placed, so the client stops waiting for it. The client puts
+        // back whatever it was last sent once it's acknowledged, so the block has to go first.
+        state.flush_block_changes().await;
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(AcknowledgeBlockChange::new(self.sequence))
+            .await
+    }
+}
+
+/// Places the block the player is holding in their off hand if `off_hand`, or otherwise their
+/// main hand, at `target`. Nothing's placed if they aren't holding a block, they're in adventure or
+/// spectator, it's out of their reach or outside the world, something's already there, a player is
+/// standing there, or a [BlockPlaceEvent] handler cancels it. Players that aren't in creative use
+/// the block up placing it.
+///
+/// The player's client may already show the block, and have used up the item, so it's sent the
+/// real block and its inventory back if it wasn't placed, see [reject].
+async fn place_block(
+    conn_id: ConnectionId,
+    off_hand: bool,
+    target: &Position,
+    state: GlobalState,
+) -> Result<()> {
+    let dimension = state.dimension_of(conn_id).await;
+    let Some(item) = held_item(conn_id, off_hand, &state).await else {
+        return reject(conn_id, target, &dimension, &state).await;
+    };
+    let Some(block_name) = placed_block(&item) else {
+        debug!(
+            "Connection {} used item {}, which doesn't place a block",
+            conn_id, item.item_id
+        );
+        return reject(conn_id, target, &dimension, &state).await;
+    };
+    let game_mode = state.game_mode(conn_id).await;
+    if !game_mode.can_build() {
+        debug!(
+            "Connection {} tried to place a block in {:?}",
+            conn_id, game_mode
+        );
+        return reject(conn_id, target, &dimension, &state).await;
+    }
+    let position = *state.world.get_component::<ExactPosition>(conn_id).await?;
+    if !block_within_reach(&position, target) {
+        warn!(
+            "Connection {} at {} tried to place a block at {}, which is out of reach",
+            conn_id,
+            position.block(),
+            target
+        );
+        return reject(conn_id, target, &dimension, &state).await;
+    }
+    let y = target.y as i32;
+    if !(MIN_BUILD_HEIGHT..MAX_BUILD_HEIGHT).contains(&y) {
+        debug!(
+            "Connection {} tried to place a block outside the world at {}",
+            conn_id, target
+        );
+        return reject(conn_id, target, &dimension, &state).await;
+    }
+
+    let Some(existing) = state.get_block(&dimension, target).await? else {
+        debug!(
+            "Connection {} tried to place a block at {}, which isn't loaded",
+            conn_id, target
+        );
+        return Ok(());
+    };
+    if !REPLACEABLE_BLOCKS.contains(&existing.name.as_str())
+        || is_occupied_by_player(&dimension, target, &state).await
+    {
+        return reject(conn_id, target, &dimension, &state).await;
+    }
+
+    let Some(block) = default_block_state(block_name) else {
+        warn!(
+            "{} isn't a block, but item {} places it",
+            block_name, item.item_id
+        );
+        return reject(conn_id, target, &dimension, &state).await;
+    };
+    let event = Arc::new(BlockPlaceEvent::new(conn_id, target.clone(), block));
+    state.dispatch_shared_event(event.clone()).await;
+    if event.is_cancelled() {
+        return reject(conn_id, target, &dimension, &state).await;
+    }
+
+    // Checked again, since the player could have done something with it in the meantime.
+    if game_mode != GameMode::Creative && !use_held_item(conn_id, off_hand, &item, &state).await {
+        return reject(conn_id, target, &dimension, &state).await;
+    }
+    state
+        .set_block(&dimension, target, event.block.clone())
+        .await
+}
+
+/// What the player is holding in their off hand if `off_hand`, or otherwise their main hand.
+async fn held_item(
+    conn_id: ConnectionId,
+    off_hand: bool,
+    state: &GlobalState,
+) -> Option<ItemStack> {
+    let component_storage = state.world.get_component_storage();
+    let selected = component_storage
+        .get::<HeldItem>(conn_id)
+        .await
+        .map(|held_item| held_item.slot)
+        .unwrap_or_default();
+    let inventory = component_storage.get::<Inventory>(conn_id).await.ok()?;
+    inventory.held(selected, off_hand).0.clone()
+}
+
+/// Takes one `item` out of the player's hand, see [held_item]. Returns `false`, taking nothing, if
+/// that isn't what they're holding.
+async fn use_held_item(
+    conn_id: ConnectionId,
+    off_hand: bool,
+    item: &ItemStack,
+    state: &GlobalState,
+) -> bool {
+    let component_storage = state.world.get_component_storage();
+    let selected = component_storage
+        .get::<HeldItem>(conn_id)
+        .await
+        .map(|held_item| held_item.slot)
+        .unwrap_or_default();
+    {
+        let mut inventory = component_storage
+            .get_mut_or_insert_with(conn_id, Inventory::default)
+            .await;
+        let held = inventory.held(selected, off_hand).0.as_ref();
+        if !held.is_some_and(|held| held.is_same_item(item)) {
+            return false;
+        }
+        inventory.take_from_hand(selected, off_hand);
+    }
+    state.update_equipment(conn_id).await;
+    true
+}
+
+/// Sends the player the real block at `target`, and, unless they're in creative, their inventory,
+/// since their client took the block out of it when it placed it.
+async fn reject(
+    conn_id: ConnectionId,
+    target: &Position,
+    dimension: &Dimension,
+    state: &GlobalState,
+) -> Result<()> {
+    state.resend_block(dimension, conn_id, target).await?;
+    if state.game_mode(conn_id).await == GameMode::Creative {
+        return Ok(());
+    }
+    let packet = {
+        let mut inventory = state
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with(conn_id, Inventory::default)
+            .await;
+        SetContainerContent::from_inventory(PLAYER_WINDOW_ID, &mut inventory)
+    };
+    let conn = state.connections.get_connection(conn_id)?;
+    let conn = conn.read().await;
+    conn.send_packet(packet).await
+}
+
+/// Whether a block at `target` in `dimension` would be inside any player's hitbox, see
+/// [ExactPosition::hitbox]. Spectators don't get in the way.
+async fn is_occupied_by_player(
+    dimension: &Dimension,
+    target: &Position,
+    state: &GlobalState,
+) -> bool {
+    let block = BoundingBox::block(target);
+    // Anyone in the block has their feet in it, the one below it, or one of the blocks around
+    // those.
+    let nearby = dimension.players.players_in_radius(target, 2.0);
+    for (id, _) in nearby {
+        let Ok(position) = state.world.get_component::<ExactPosition>(id).await else {
+            continue;
+        };
+        if position.hitbox().intersects(&block) && !state.is_spectator(id).await {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::create_state;
+
+    #[tokio::test]
+    async fn test_decode_use_item_on() {
+        // Main hand, the east side of (10, -3, 4), with sequence 9.
+        let location =
+            ((10u64 & 0x3FFFFFF) << 38) | ((4u64 & 0x3FFFFFF) << 12) | (-3i64 as u64 & 0xFFF);
+        let mut data = vec![0x00];
+        data.extend_from_slice(&location.to_be_bytes());
+        data.push(0x05);
+        for cursor in [1.0f32, 0.5, 0.25] {
+            data.extend_from_slice(&cursor.to_be_bytes());
+        }
+        data.extend_from_slice(&[0x00, 0x09]);
+
+        let packet = UseItemOn::net_decode(&mut Cursor::new(data)).await.unwrap();
+        assert_eq!(packet.hand.get_val(), 0);
+        assert_eq!(packet.cursor_y, 0.5);
+        assert!(!packet.inside_block);
+        assert_eq!(packet.sequence.get_val(), 9);
+
+        let target = packet.target().unwrap();
+        assert_eq!((target.x, target.y, target.z), (11, -3, 4));
+    }
+
+    #[tokio::test]
+    async fn test_blocks_cant_be_placed_in_players() {
+        let state = create_state(vec![TcpListener::bind("127.0.0.1:0").await.unwrap()])
+            .await
+            .unwrap();
+        let overworld = state.dimensions.overworld();
+        // Standing near the east side of block x = 10, so they stick out into x = 11.
+        let position = ExactPosition((10.8, 64.0, 0.5));
+        let conn_id = 5_432;
+        overworld.players.insert(conn_id, &position.block(), 2);
+        state
+            .world
+            .get_component_storage()
+            .insert(conn_id, position);
+
+        for (x, y, occupied) in [
+            (10, 64, true),
+            (11, 64, true),
+            (11, 65, true),
+            (11, 66, false),
+            (9, 64, false),
+        ] {
+            let target = Position::new(x, y, 0);
+            assert_eq!(
+                is_occupied_by_player(overworld, &target, &state).await,
+                occupied,
+                "{}",
+                target
+            );
+        }
+    }
+}
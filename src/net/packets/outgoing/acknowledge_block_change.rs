@@ -0,0 +1,20 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Tells the client we're done with a block change it predicted. Any block updates sent before
+/// this replace its prediction.
+///
+/// - `sequence`: The sequence number of the packet that changed the block.
+#[derive(NetEncode)]
+pub struct AcknowledgeBlockChange {
+    #[encode(default = VarInt::from(0x06))]
+    pub packet_id: VarInt,
+    pub sequence: VarInt,
+}
+
+impl AcknowledgeBlockChange {
+    pub fn new(sequence: VarInt) -> Self {
+        Self::new_auto(sequence)
+    }
+}
@@ -0,0 +1,26 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// One statistic's value, e.g. how many times a block was mined.
+#[derive(NetEncode, Debug, Clone)]
+pub struct Statistic {
+    pub category_id: VarInt,
+    pub statistic_id: VarInt,
+    pub value: VarInt,
+}
+
+/// Sent when the player opens the statistics screen, with the values to show in it.
+#[derive(NetEncode)]
+pub struct AwardStatistics {
+    #[encode(default = VarInt::from(0x05))]
+    pub packet_id: VarInt,
+    pub count: VarInt,
+    pub statistics: Vec<Statistic>,
+}
+
+impl AwardStatistics {
+    pub fn new(statistics: Vec<Statistic>) -> Self {
+        Self::new_auto(VarInt::from(statistics.len() as i32), statistics)
+    }
+}
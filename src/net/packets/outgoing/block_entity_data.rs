@@ -0,0 +1,34 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::position::Position;
+use crate::utils::error::Error;
+use crate::world::chunk_format::BlockEntity;
+
+/// Changes the data of one block entity on the client, e.g. the text on a sign.
+///
+/// - `type_id`: The block entity's type, see [BlockEntity::type_id].
+/// - `data`: The block entity's NBT, see [BlockEntity::to_network_nbt].
+#[derive(NetEncode, Clone)]
+pub struct BlockEntityData {
+    #[encode(default = VarInt::from(0x08))]
+    pub packet_id: VarInt,
+    pub location: Position,
+    pub type_id: VarInt,
+    #[encode(raw_bytes(prepend_length = false))]
+    pub data: Vec<u8>,
+}
+
+impl BlockEntityData {
+    /// `None` if clients don't know about this kind of block entity.
+    pub fn new(block_entity: &BlockEntity) -> Result<Option<Self>, Error> {
+        let Some(type_id) = block_entity.type_id() else {
+            return Ok(None);
+        };
+        Ok(Some(Self::new_auto(
+            block_entity.position(),
+            VarInt::from(type_id),
+            block_entity.to_network_nbt()?,
+        )))
+    }
+}
@@ -0,0 +1,22 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::position::Position;
+
+/// Changes one block on the client.
+///
+/// - `block_id`: The block's state id, see [crate::world::conversions::block_id].
+#[derive(NetEncode)]
+pub struct BlockUpdate {
+    #[encode(default = VarInt::from(0x0A))]
+    pub packet_id: VarInt,
+    pub location: Position,
+    pub block_id: VarInt,
+}
+
+impl BlockUpdate {
+    pub fn new(location: Position, block_id: i32) -> Self {
+        Self::new_auto(location, VarInt::from(block_id))
+    }
+}
@@ -0,0 +1,163 @@
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::io::AsyncWrite;
+
+use ferrumc_macros::NetEncode;
+
+/// Darkens the sky while the bar is shown.
+pub const DARKEN_SKY: u8 = 0x01;
+/// Plays the end music while the bar is shown.
+pub const PLAY_BOSS_MUSIC: u8 = 0x02;
+/// Adds fog while the bar is shown.
+pub const CREATE_FOG: u8 = 0x04;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BossBarColor {
+    Pink,
+    Blue,
+    Red,
+    Green,
+    Yellow,
+    #[default]
+    Purple,
+    White,
+}
+
+/// How many notches the bar is split into.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BossBarDivision {
+    #[default]
+    None,
+    Six,
+    Ten,
+    Twelve,
+    Twenty,
+}
+
+/// What a [BossBarPacket] does to the bar. Titles are JSON text components, and health goes from
+/// 0 to 1.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BossBarAction {
+    Add {
+        title: String,
+        health: f32,
+        color: BossBarColor,
+        division: BossBarDivision,
+        flags: u8,
+    },
+    Remove,
+    UpdateHealth(f32),
+    UpdateTitle(String),
+    UpdateStyle {
+        color: BossBarColor,
+        division: BossBarDivision,
+    },
+    UpdateFlags(u8),
+}
+
+/// Adds, changes or removes a boss bar at the top of the player's screen. Bars are told apart by
+/// their UUID.
+#[derive(NetEncode)]
+pub struct BossBarPacket {
+    #[encode(default = VarInt::from(0x0B))]
+    pub packet_id: VarInt,
+    pub uuid: u128,
+    pub action: BossBarAction,
+}
+
+impl BossBarPacket {
+    pub fn new(uuid: u128, action: BossBarAction) -> Self {
+        Self::new_auto(uuid, action)
+    }
+}
+
+impl NetEncode for BossBarAction {
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        let action = match self {
+            BossBarAction::Add { .. } => 0,
+            BossBarAction::Remove => 1,
+            BossBarAction::UpdateHealth(_) => 2,
+            BossBarAction::UpdateTitle(_) => 3,
+            BossBarAction::UpdateStyle { .. } => 4,
+            BossBarAction::UpdateFlags(_) => 5,
+        };
+        VarInt::from(action).net_encode(bytes).await?;
+        match self {
+            BossBarAction::Add {
+                title,
+                health,
+                color,
+                division,
+                flags,
+            } => {
+                title.net_encode(bytes).await?;
+                health.net_encode(bytes).await?;
+                VarInt::from(*color as i32).net_encode(bytes).await?;
+                VarInt::from(*division as i32).net_encode(bytes).await?;
+                flags.net_encode(bytes).await?;
+            }
+            BossBarAction::Remove => {}
+            BossBarAction::UpdateHealth(health) => health.net_encode(bytes).await?,
+            BossBarAction::UpdateTitle(title) => title.net_encode(bytes).await?,
+            BossBarAction::UpdateStyle { color, division } => {
+                VarInt::from(*color as i32).net_encode(bytes).await?;
+                VarInt::from(*division as i32).net_encode(bytes).await?;
+            }
+            BossBarAction::UpdateFlags(flags) => flags.net_encode(bytes).await?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn encode(action: BossBarAction) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        BossBarPacket::new(1, action)
+            .net_encode(&mut bytes)
+            .await
+            .unwrap();
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_encode_add() {
+        let bytes = encode(BossBarAction::Add {
+            title: r#"{"text":"TPS"}"#.to_string(),
+            health: 1.0,
+            color: BossBarColor::Green,
+            division: BossBarDivision::Ten,
+            flags: DARKEN_SKY | CREATE_FOG,
+        })
+        .await;
+        let mut expected = vec![0x28, 0x0B];
+        expected.extend_from_slice(&1u128.to_be_bytes());
+        expected.push(0x00);
+        expected.push(14);
+        expected.extend_from_slice(br#"{"text":"TPS"}"#);
+        expected.extend_from_slice(&1.0f32.to_be_bytes());
+        expected.extend_from_slice(&[0x03, 0x02, 0x05]);
+        assert_eq!(bytes, expected);
+    }
+
+    #[tokio::test]
+    async fn test_encode_updates() {
+        let bytes = encode(BossBarAction::Remove).await;
+        assert_eq!(bytes[18..], [0x01]);
+
+        let bytes = encode(BossBarAction::UpdateHealth(0.5)).await;
+        assert_eq!(bytes[18..], [0x02, 0x3F, 0x00, 0x00, 0x00]);
+
+        let bytes = encode(BossBarAction::UpdateStyle {
+            color: BossBarColor::Pink,
+            division: BossBarDivision::Twenty,
+        })
+        .await;
+        assert_eq!(bytes[18..], [0x04, 0x00, 0x04]);
+    }
+}
@@ -1,178 +1,310 @@
-use crate::state::GlobalState;
-use crate::utils::encoding::bitset::BitSet;
-use crate::utils::error::Error;
-use crate::world::chunk_format::Heightmaps;
-use crate::Result;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::io::Cursor;
+
 use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::bitset::BitSet;
 use ferrumc_codec::network_types::varint::VarInt;
 use ferrumc_macros::NetEncode;
-use nbt_lib::NBTTag;
-use std::io::Cursor;
-use tracing::warn;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tracing::debug;
 
-const _SECTION_WIDTH: usize = 16;
-const _SECTION_HEIGHT: usize = 16;
+use crate::utils::error::Error;
+use crate::world::blocks::{MAX_BUILD_HEIGHT, MIN_BUILD_HEIGHT};
+use crate::world::chunk_format::{Chunk, Heightmaps, Section};
+use crate::Result;
 
-// Seperated light data from chunk data since clippy was complaining about the size of the struct
+/// The lowest and highest sections with blocks in them, by section y.
+const MIN_SECTION: i8 = (MIN_BUILD_HEIGHT >> 4) as i8;
+const MAX_SECTION: i8 = ((MAX_BUILD_HEIGHT >> 4) - 1) as i8;
+/// Light is sent for every section, and the ones just below and above the world.
+const LIGHT_SECTIONS: usize = (MAX_SECTION - MIN_SECTION) as usize + 3;
+/// A nibble for each block in a section.
+const LIGHT_ARRAY_SIZE: usize = 2048;
+/// Sent for sections that don't have any sky light stored, so they aren't pitch black.
+static FULL_BRIGHT: [u8; LIGHT_ARRAY_SIZE] = [0xFF; LIGHT_ARRAY_SIZE];
+
+/// Sends a chunk's blocks, block entities and light.
+///
+/// It borrows the chunk, and is encoded straight from it without copying its sections or light
+/// first.
 #[derive(NetEncode)]
-pub struct ChunkDataAndUpdateLight {
-/*    #[encode(default=VarInt::from(0x24))]
+pub struct ChunkDataAndUpdateLight<'a> {
+    #[encode(default = VarInt::from(0x24))]
     pub packet_id: VarInt,
     pub chunk_x: i32,
     pub chunk_z: i32,
-    pub heightmaps: Heightmaps,
-    #[encode(raw_bytes(prepend_length = true))]
-    pub data: Vec<u8>,
-    pub block_entities: Vec<BlockEntity>,
-    pub light_data: LightData,*/
-    #[encode(default=VarInt::from(0x24))]
-    pub packet_id: VarInt,
-    pub chunk_x: i32,
-    pub chunk_z: i32,
-    pub heightmaps: Heightmaps,
-    #[encode(raw_bytes(prepend_length = true))]
-    pub data: Vec<u8>,
-    pub block_entities_count: VarInt,
-    pub block_entities: Vec<BlockEntity>,
-    pub light_data: LightData,
+    pub data: ChunkData<'a>,
+    pub light: LightData<'a>,
 }
 
-#[derive(NetEncode)]
-pub struct LightData {
-    pub sky_light_mask: BitSet,
-    pub block_light_mask: BitSet,
-    pub empty_sky_light_mask: BitSet,
-    pub empty_block_light_mask: BitSet,
-    pub sky_light_array_count: VarInt,
-    pub sky_light_arrays: Vec<LightArray>,
-    pub block_light_array_count: VarInt,
-    pub block_light_arrays: Vec<LightArray>,
+impl<'a> ChunkDataAndUpdateLight<'a> {
+    pub fn new(chunk: &'a Chunk) -> Result<Self> {
+        let sections = chunk.sections.as_deref().ok_or(Error::InvalidChunk(
+            chunk.x_pos,
+            chunk.z_pos,
+            "Chunk is missing sections".to_string(),
+        ))?;
+        Ok(Self::new_auto(
+            chunk.x_pos,
+            chunk.z_pos,
+            ChunkData::new(chunk, sections)?,
+            LightData::new(sections),
+        ))
+    }
 }
 
-#[derive(NetEncode)]
-pub struct BlockEntity {
-    pub packed_xz: u8,
-    pub y: i16,
-    pub type_id: VarInt,
-    pub data: NBTTag,
+/// The blocks in a chunk, and what's needed to draw them.
+///
+/// - `heightmaps`: Worked out from the blocks if the chunk doesn't have any.
+/// - `sections`: Sent from the bottom of the world up. Sections that are missing are sent as air.
+/// - `block_entities`: Only the ones clients know about.
+pub struct ChunkData<'a> {
+    pub heightmaps: Cow<'a, Heightmaps>,
+    pub sections: &'a [Section],
+    pub block_entities: Vec<BlockEntity>,
 }
 
-#[derive(NetEncode, Clone)]
-pub struct LightArray {
-    #[encode(raw_bytes(prepend_length = true))]
-    pub data: Vec<u8>,
+impl<'a> ChunkData<'a> {
+    pub fn new(chunk: &'a Chunk, sections: &'a [Section]) -> Result<Self> {
+        let heightmaps = match &chunk.heightmaps {
+            Some(heightmaps) => Cow::Borrowed(heightmaps),
+            None => {
+                debug!("Chunk is missing heightmaps, working them out before sending it");
+                Cow::Owned(chunk.compute_heightmaps()?)
+            }
+        };
+        let mut block_entities = Vec::new();
+        for block_entity in chunk.block_entities.iter().flatten() {
+            block_entities.extend(BlockEntity::from_chunk(block_entity)?);
+        }
+        Ok(Self {
+            heightmaps,
+            sections,
+            block_entities,
+        })
+    }
 }
 
-impl ChunkDataAndUpdateLight {
-    pub async fn new(state: GlobalState, chunk_x: i32, chunk_z: i32) -> Result<Self> {
-        let chunk = state
-            .database
-            .get_chunk(chunk_x, chunk_z, "overworld".to_string())
-            .await?
-            .ok_or(Error::ChunkNotFound(chunk_x, chunk_z))?;
+impl NetEncode for ChunkData<'_> {
+    async fn net_encode<W>(&self, writer: &mut W) -> ferrumc_codec::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.heightmaps.net_encode(writer).await?;
 
-        // Serialize the chunk data
+        // The sections are prefixed with how many bytes they take up.
         let mut data = Cursor::new(Vec::new());
+        for y in MIN_SECTION..=MAX_SECTION {
+            match self.sections.iter().find(|section| section.y == y) {
+                Some(section) => section.net_encode(&mut data).await?,
+                None => empty_section(y).net_encode(&mut data).await?,
+            }
+        }
+        let data = data.into_inner();
+        VarInt::from(data.len() as i32).net_encode(writer).await?;
+        writer.write_all(&data).await?;
+
+        VarInt::from(self.block_entities.len() as i32)
+            .net_encode(writer)
+            .await?;
+        self.block_entities.net_encode(writer).await
+    }
+}
+
+fn empty_section(y: i8) -> Section {
+    Section {
+        block_states: None,
+        biomes: None,
+        y,
+        block_light: None,
+        sky_light: None,
+    }
+}
+
+/// A chunk's light, as the Chunk Data and Update Light packets send it.
+///
+/// Sections without sky light are sent at full brightness, since there's nothing to work it out
+/// yet. Sections without block light are sent as having none.
+pub struct LightData<'a> {
+    sky_light: [Option<&'a [i8]>; LIGHT_SECTIONS],
+    block_light: [Option<&'a [i8]>; LIGHT_SECTIONS],
+    /// Whether only the sections with sky light are sent, and clients keep the light they have
+    /// for the rest. See [LightData::sky_light_of].
+    only_sky_light: bool,
+}
+
+impl<'a> LightData<'a> {
+    pub fn new(sections: &'a [Section]) -> Self {
+        let mut light = Self {
+            sky_light: [None; LIGHT_SECTIONS],
+            block_light: [None; LIGHT_SECTIONS],
+            only_sky_light: false,
+        };
+        for section in sections {
+            let Some(index) = light_index(section.y) else {
+                continue;
+            };
+            light.sky_light[index] = light_array(&section.sky_light);
+            light.block_light[index] = light_array(&section.block_light);
+        }
+        light
+    }
 
-        if let Some(sections) = &chunk.sections {
-            for section in sections {
-                section.net_encode(&mut data).await?;
-                serialize_biomes().await?.net_encode(&mut data).await?;
+    /// Just the sky light of the sections at the y in `changed`, for sending light again after
+    /// blocks changed.
+    pub fn sky_light_of(sections: &'a [Section], changed: &BTreeSet<i8>) -> Self {
+        let mut light = Self {
+            sky_light: [None; LIGHT_SECTIONS],
+            block_light: [None; LIGHT_SECTIONS],
+            only_sky_light: true,
+        };
+        for section in sections
+            .iter()
+            .filter(|section| changed.contains(&section.y))
+        {
+            if let Some(index) = light_index(section.y) {
+                light.sky_light[index] = light_array(&section.sky_light);
             }
-        } else {
-            return Err(Error::InvalidChunk(
-                chunk.x_pos,
-                chunk.z_pos,
-                "Chunk is missing sections".to_string(),
-            ));
         }
+        light
+    }
+}
 
-        // 24 is the number of sections in a chunk
+/// Where the section at `section_y` is in the light masks and arrays. The first light section is
+/// the one below the world.
+fn light_index(section_y: i8) -> Option<usize> {
+    let index = section_y as i32 - MIN_SECTION as i32 + 1;
+    usize::try_from(index).ok().filter(|&i| i < LIGHT_SECTIONS)
+}
 
-        // -4 to 20
-        const SECTIONS: usize = 24;
+fn light_array(light: &Option<Vec<i8>>) -> Option<&[i8]> {
+    light
+        .as_deref()
+        .filter(|light| light.len() == LIGHT_ARRAY_SIZE)
+}
 
-        // let sky_light_mask = BitSet::from_iter((0..SECTIONS + 2).map(|_| 1));
-        // let block_light_mask = BitSet::from_iter((0..SECTIONS + 2).map(|_| 1));
-        // let empty_sky_light_mask = BitSet::from_iter((0..SECTIONS + 2).map(|_| 0));
-        // let empty_block_light_mask = BitSet::from_iter((0..SECTIONS + 2).map(|_| 0));
+impl NetEncode for LightData<'_> {
+    async fn net_encode<W>(&self, writer: &mut W) -> ferrumc_codec::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        if self.only_sky_light {
+            return self.encode_sky_light_only(writer).await;
+        }
 
-        let mut sky_light_mask = BitSet::new(SECTIONS + 2);
+        let mut sky_light_mask = BitSet::new(LIGHT_SECTIONS);
         sky_light_mask.set_all();
-        let mut block_light_mask = BitSet::new(SECTIONS + 2);
-        block_light_mask.set_all();
-        let empty_sky_light_mask = BitSet::new(SECTIONS + 2);
-        let empty_block_light_mask = BitSet::new(SECTIONS + 2);
-
-        // Create light arrays
-        let mut sky_light_arrays = Vec::new();
-        let mut block_light_arrays = Vec::new();
-
-        for section in chunk.sections.as_ref().unwrap() {
-            sky_light_arrays.push(if let Some(sky_light) = &section.sky_light {
-                LightArray {
-                    data: sky_light.iter().take(2048).map(|&x| x as u8).collect(),
-                }
-            } else {
-                LightArray {
-                    data: vec![0; 2048],
-                }
-            });
-            block_light_arrays.push(if let Some(block_light) = &section.block_light {
-                LightArray {
-                    data: block_light.iter().take(2048).map(|&x| x as u8).collect(),
-                }
-            } else {
-                LightArray {
-                    data: vec![0; 2048],
+        let mut block_light_mask = BitSet::new(LIGHT_SECTIONS);
+        let mut empty_block_light_mask = BitSet::new(LIGHT_SECTIONS);
+        for (index, light) in self.block_light.iter().enumerate() {
+            match light {
+                Some(_) => block_light_mask.set(index),
+                None => empty_block_light_mask.set(index),
+            }
+        }
+
+        sky_light_mask.net_encode(writer).await?;
+        block_light_mask.net_encode(writer).await?;
+        BitSet::new(LIGHT_SECTIONS).net_encode(writer).await?;
+        empty_block_light_mask.net_encode(writer).await?;
+
+        VarInt::from(LIGHT_SECTIONS as i32)
+            .net_encode(writer)
+            .await?;
+        for light in &self.sky_light {
+            match light {
+                Some(light) => encode_light_array(light, writer).await?,
+                None => {
+                    VarInt::from(LIGHT_ARRAY_SIZE as i32)
+                        .net_encode(writer)
+                        .await?;
+                    writer.write_all(&FULL_BRIGHT).await?;
                 }
-            });
+            }
+        }
+
+        VarInt::from(block_light_mask.count_ones() as i32)
+            .net_encode(writer)
+            .await?;
+        for light in self.block_light.iter().flatten() {
+            encode_light_array(light, writer).await?;
         }
-        block_light_arrays.push(LightArray {
-            data: vec![0; 2048],
-        });
-        sky_light_arrays.push(LightArray {
-            data: vec![0; 2048],
-        });
-        block_light_arrays.push(LightArray {
-            data: vec![0; 2048],
-        });
-        sky_light_arrays.push(LightArray {
-            data: vec![0; 2048],
-        });
-
-        let heightmaps = chunk.heightmaps.unwrap_or_else(|| {
-            warn!("Chunk is missing heightmaps, creating default heightmaps");
-            Heightmaps {
-                //motion_blocking_no_leaves: None,
-                motion_blocking: Some(vec![i64::MAX; 37]),
-                //ocean_floor: None,
-                world_surface: Some(vec![i64::MAX; 37]),
+        Ok(())
+    }
+}
+
+impl LightData<'_> {
+    /// Only the sections that have sky light, with every other mask left empty so clients don't
+    /// touch the rest.
+    async fn encode_sky_light_only<W>(&self, writer: &mut W) -> ferrumc_codec::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut sky_light_mask = BitSet::new(LIGHT_SECTIONS);
+        for (index, light) in self.sky_light.iter().enumerate() {
+            if light.is_some() {
+                sky_light_mask.set(index);
             }
-        });
+        }
+        sky_light_mask.net_encode(writer).await?;
+        for _ in 0..3 {
+            BitSet::new(LIGHT_SECTIONS).net_encode(writer).await?;
+        }
 
-        let res = ChunkDataAndUpdateLight {
-            packet_id: VarInt::from(0x24),
-            chunk_x,
-            chunk_z,
-            heightmaps,
-            data: data.into_inner(),
-            block_entities_count: VarInt::from(0),
-            block_entities: Vec::new(),
-            light_data: LightData {
-                sky_light_mask,
-                block_light_mask,
-                empty_sky_light_mask,
-                empty_block_light_mask,
-                sky_light_array_count: VarInt::from(sky_light_arrays.len() as i32),
-                sky_light_arrays,
-                block_light_array_count: VarInt::from(block_light_arrays.len() as i32),
-                block_light_arrays,
-            },
+        VarInt::from(sky_light_mask.count_ones() as i32)
+            .net_encode(writer)
+            .await?;
+        for light in self.sky_light.iter().flatten() {
+            encode_light_array(light, writer).await?;
+        }
+        VarInt::from(0).net_encode(writer).await
+    }
+}
+
+async fn encode_light_array<W>(light: &[i8], writer: &mut W) -> ferrumc_codec::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut bytes = [0u8; LIGHT_ARRAY_SIZE];
+    for (byte, &nibbles) in bytes.iter_mut().zip(light) {
+        *byte = nibbles as u8;
+    }
+    VarInt::from(LIGHT_ARRAY_SIZE as i32)
+        .net_encode(writer)
+        .await?;
+    writer.write_all(&bytes).await?;
+    Ok(())
+}
+
+/// A block entity as it's sent with its chunk.
+///
+/// - `packed_xz`: The x and z inside the chunk, as `x << 4 | z`.
+/// - `data`: Its NBT, see [crate::world::chunk_format::BlockEntity::to_network_nbt].
+#[derive(NetEncode)]
+pub struct BlockEntity {
+    pub packed_xz: u8,
+    pub y: i16,
+    pub type_id: VarInt,
+    #[encode(raw_bytes(prepend_length = false))]
+    pub data: Vec<u8>,
+}
+
+impl BlockEntity {
+    /// `None` if clients don't know about this kind of block entity.
+    fn from_chunk(block_entity: &crate::world::chunk_format::BlockEntity) -> Result<Option<Self>> {
+        let Some(type_id) = block_entity.type_id() else {
+            return Ok(None);
         };
-        Ok(res)
+        Ok(Some(Self {
+            packed_xz: (((block_entity.x & 15) << 4) | (block_entity.z & 15)) as u8,
+            y: block_entity.y as i16,
+            type_id: VarInt::from(type_id),
+            data: block_entity.to_network_nbt()?,
+        }))
     }
 }
+
 /*
 async fn serialize_block_states(block_states: &BlockStates) -> Result<Vec<u8>> {
     let mut data = Vec::new();
@@ -193,23 +325,6 @@ async fn serialize_block_states(block_states: &BlockStates) -> Result<Vec<u8>> {
 
     Ok(data)
 }*/
-async fn serialize_biomes() -> Result<Vec<u8>> {
-    let mut data: Vec<u8> = Vec::new();
-    let bits_per_biome = 6;
-    data.push(bits_per_biome);
-
-    // Direct biome encoding, no palette
-    let biome_data = vec![0u64; 64 * (bits_per_biome as usize) / 64]; // 64 biomes per section
-    VarInt::from(biome_data.len() as i32)
-        .net_encode(&mut data)
-        .await?;
-
-    for long in biome_data {
-        long.net_encode(&mut data).await?;
-    }
-
-    Ok(data)
-}
 /*
 fn create_basic_chunk(chunk_x: i32, chunk_z: i32) -> Chunk {
     let _rng = rand::thread_rng();
@@ -331,3 +446,127 @@ fn pack_entries(entries: &[u32], bits_per_entry: u8) -> Vec<i64> {
 //         _ => 0,
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use crate::world::chunk_format::BlockStates;
+
+    use super::*;
+
+    /// A chunk with stone in the corner of its lowest section, and nothing else.
+    fn single_section_chunk() -> Chunk {
+        let mut data = vec![0i64; 256];
+        data[0] = 1;
+        let section = Section {
+            block_states: Some(BlockStates {
+                non_air_blocks: Some(1),
+                bits_per_block: Some(4),
+                data: Some(data),
+                palette: None,
+                net_palette: Some(vec![VarInt::from(0), VarInt::from(1)]),
+            }),
+            biomes: None,
+            y: -4,
+            block_light: None,
+            sky_light: Some(vec![0x11; LIGHT_ARRAY_SIZE]),
+        };
+        Chunk {
+            dimension: None,
+            status: "full".to_string(),
+            data_version: 3465,
+            heightmaps: Some(Heightmaps {
+                motion_blocking: Some(vec![1]),
+                world_surface: None,
+            }),
+            is_light_on: Some(1),
+            inhabited_time: None,
+            y_pos: -4,
+            x_pos: 2,
+            z_pos: -1,
+            structures: None,
+            last_update: None,
+            sections: Some(vec![section]),
+            block_entities: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encode_single_section_chunk() {
+        let chunk = single_section_chunk();
+        let mut bytes = Vec::new();
+        ChunkDataAndUpdateLight::new(&chunk)
+            .unwrap()
+            .net_encode(&mut bytes)
+            .await
+            .unwrap();
+
+        let mut expected = vec![0x24, 0, 0, 0, 2, 0xFF, 0xFF, 0xFF, 0xFF];
+        // {"Heightmaps": {"MOTION_BLOCKING": [1L]}}
+        expected.extend_from_slice(&[0x0A, 0x00, 0x0A]);
+        expected.extend_from_slice(b"Heightmaps");
+        expected.extend_from_slice(&[0x0C, 0x00, 0x0F]);
+        expected.extend_from_slice(b"MOTION_BLOCKING");
+        expected.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0x00]);
+
+        // 2243 bytes of sections.
+        expected.extend_from_slice(&[0xC3, 0x11]);
+        // One block, four bits per block, air and stone, then 256 longs.
+        expected.extend_from_slice(&[0x00, 0x01, 0x04, 0x02, 0x00, 0x01, 0x80, 0x02]);
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        expected.extend_from_slice(&[0; 255 * 8]);
+        // All plains.
+        expected.extend_from_slice(&[0x00, 0x27, 0x00]);
+        for _ in 0..23 {
+            expected.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x27, 0x00]);
+        }
+        // No block entities.
+        expected.push(0x00);
+
+        let all_sections = [0x01, 0, 0, 0, 0, 0x03, 0xFF, 0xFF, 0xFF];
+        let no_sections = [0x01, 0, 0, 0, 0, 0, 0, 0, 0];
+        expected.extend_from_slice(&all_sections);
+        expected.extend_from_slice(&no_sections);
+        expected.extend_from_slice(&no_sections);
+        expected.extend_from_slice(&all_sections);
+        expected.push(26);
+        for index in 0..26 {
+            expected.extend_from_slice(&[0x80, 0x10]);
+            let light = if index == 1 { 0x11 } else { 0xFF };
+            expected.extend_from_slice(&[light; LIGHT_ARRAY_SIZE]);
+        }
+        expected.push(0x00);
+
+        let length = expected.len();
+        assert_eq!(
+            bytes[..3],
+            [
+                0x80 | (length & 0x7F) as u8,
+                (length >> 7) as u8 | 0x80,
+                (length >> 14) as u8
+            ]
+        );
+        assert_eq!(bytes[3..], expected[..]);
+    }
+
+    #[tokio::test]
+    async fn test_encode_sky_light_of_changed_sections() {
+        let chunk = single_section_chunk();
+        let sections = chunk.sections.as_deref().unwrap();
+        let mut bytes = Vec::new();
+        LightData::sky_light_of(sections, &BTreeSet::from([-4, 7]))
+            .net_encode(&mut bytes)
+            .await
+            .unwrap();
+
+        // Only the section that's there is sent, and nothing else is touched.
+        let mut expected = vec![0x01, 0, 0, 0, 0, 0, 0, 0, 0x02];
+        for _ in 0..3 {
+            expected.extend_from_slice(&[0x01, 0, 0, 0, 0, 0, 0, 0, 0]);
+        }
+        expected.push(1);
+        expected.extend_from_slice(&[0x80, 0x10]);
+        expected.extend_from_slice(&[0x11; LIGHT_ARRAY_SIZE]);
+        expected.push(0);
+        assert_eq!(bytes, expected);
+    }
+}
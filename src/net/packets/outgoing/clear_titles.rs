@@ -0,0 +1,18 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Takes away the title and subtitle on screen. With `reset` set, the next title also goes back
+/// to the default animation times and has no subtitle unless a new one is sent.
+#[derive(NetEncode)]
+pub struct ClearTitles {
+    #[encode(default = VarInt::from(0x0E))]
+    pub packet_id: VarInt,
+    pub reset: bool,
+}
+
+impl ClearTitles {
+    pub fn new(reset: bool) -> Self {
+        Self::new_auto(reset)
+    }
+}
@@ -0,0 +1,25 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// Shows the player the death screen, with `message` saying how they died.
+#[derive(NetEncode)]
+pub struct CombatDeath {
+    #[encode(default = VarInt::from(0x38))]
+    pub packet_id: VarInt,
+    pub player_id: VarInt,
+    /// The message as a JSON text component.
+    pub message: String,
+}
+
+impl CombatDeath {
+    pub fn new(player_id: i32, message: impl Into<TextComponent>) -> Result<Self> {
+        Ok(Self::new_auto(
+            VarInt::from(player_id),
+            message.into().to_json()?,
+        ))
+    }
+}
@@ -0,0 +1,66 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// One suggestion, with the text shown when it's hovered over, if any.
+#[derive(NetEncode, Clone)]
+pub struct Suggestion {
+    pub text: String,
+    pub has_tooltip: bool,
+    /// A JSON text component.
+    pub tooltip: Option<String>,
+}
+
+/// The answer to a Command Suggestions Request. The suggestions replace the `length` characters
+/// from `start` in the text the client sent.
+#[derive(NetEncode)]
+pub struct CommandSuggestionsResponse {
+    #[encode(default = VarInt::from(0x0F))]
+    pub packet_id: VarInt,
+    /// The id the client sent its request with.
+    pub transaction_id: VarInt,
+    pub start: VarInt,
+    pub length: VarInt,
+    pub count: VarInt,
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl CommandSuggestionsResponse {
+    pub fn new(transaction_id: i32, start: usize, length: usize, suggestions: Vec<String>) -> Self {
+        let suggestions = suggestions
+            .into_iter()
+            .map(|text| Suggestion {
+                text,
+                has_tooltip: false,
+                tooltip: None,
+            })
+            .collect::<Vec<_>>();
+        Self::new_auto(
+            VarInt::from(transaction_id),
+            VarInt::from(start as i32),
+            VarInt::from(length as i32),
+            VarInt::from(suggestions.len() as i32),
+            suggestions,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encode_suggestions() {
+        let mut bytes = Vec::new();
+        CommandSuggestionsResponse::new(7, 6, 2, vec!["Steve".to_string()])
+            .net_encode(&mut bytes)
+            .await
+            .unwrap();
+        assert_eq!(
+            bytes,
+            vec![0x0C, 0x0F, 0x07, 0x06, 0x02, 0x01, 0x05, b'S', b't', b'e', b'v', b'e', 0x00]
+        );
+    }
+}
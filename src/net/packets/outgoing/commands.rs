@@ -0,0 +1,20 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::commands::graph::CommandGraph;
+
+/// Tells the client every command it can run, so it can check and complete them as they're
+/// typed. Commands it isn't sent show up as unknown.
+#[derive(NetEncode)]
+pub struct Commands {
+    #[encode(default = VarInt::from(0x10))]
+    pub packet_id: VarInt,
+    pub graph: CommandGraph,
+}
+
+impl Commands {
+    pub fn new(graph: CommandGraph) -> Self {
+        Self::new_auto(graph)
+    }
+}
@@ -2,11 +2,21 @@ use ferrumc_codec::network_types::varint::VarInt;
 
 use ferrumc_macros::NetEncode;
 
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
 /// The login disconnect packet is sent by the server to the client to disconnect the client.
 /// Used to cancel the login process.
 #[derive(NetEncode)]
-pub struct LoginDisconnect {
+pub struct DisconnectLogin {
     #[encode(default = VarInt::from(0x00))]
     pub packet_id: VarInt,
+    /// The reason as a JSON text component.
     pub reason: String,
 }
+
+impl DisconnectLogin {
+    pub fn new(reason: impl Into<TextComponent>) -> Result<Self> {
+        Ok(Self::new_auto(reason.into().to_json()?))
+    }
+}
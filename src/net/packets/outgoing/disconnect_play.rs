@@ -0,0 +1,22 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// Sent by the server to kick a client that's in the play state. The reason is shown on the
+/// disconnect screen.
+#[derive(NetEncode)]
+pub struct DisconnectPlay {
+    #[encode(default = VarInt::from(0x1A))]
+    pub packet_id: VarInt,
+    /// The reason as a JSON text component.
+    pub reason: String,
+}
+
+impl DisconnectPlay {
+    pub fn new(reason: impl Into<TextComponent>) -> Result<Self> {
+        Ok(Self::new_auto(reason.into().to_json()?))
+    }
+}
@@ -0,0 +1,34 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Where on the screen an objective's scores are shown. Each slot shows one objective at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplaySlot {
+    /// Next to each player's name in the tab list.
+    List = 0,
+    /// On the right of the screen.
+    Sidebar = 1,
+    /// Under each player's name tag.
+    BelowName = 2,
+}
+
+/// Shows an objective in one of the display slots, replacing whatever was there.
+#[derive(NetEncode)]
+pub struct DisplayObjective {
+    #[encode(default = VarInt::from(0x51))]
+    pub packet_id: VarInt,
+    pub position: u8,
+    /// An empty name clears the slot.
+    pub objective_name: String,
+}
+
+impl DisplayObjective {
+    pub fn new(slot: DisplaySlot, objective_name: &str) -> Self {
+        Self::new_auto(slot as u8, objective_name.to_string())
+    }
+
+    pub fn clear(slot: DisplaySlot) -> Self {
+        Self::new(slot, "")
+    }
+}
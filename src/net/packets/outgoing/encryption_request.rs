@@ -0,0 +1,30 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Sent by the server in online mode to start the encryption handshake.
+///
+/// The client answers with [crate::net::packets::incoming::encryption_response::EncryptionResponse].
+#[derive(NetEncode)]
+pub struct EncryptionRequest {
+    #[encode(default = VarInt::from(0x01))]
+    pub packet_id: VarInt,
+    // Always empty since 1.7
+    pub server_id: String,
+    pub public_key_length: VarInt,
+    pub public_key: Vec<u8>,
+    pub verify_token_length: VarInt,
+    pub verify_token: Vec<u8>,
+}
+
+impl EncryptionRequest {
+    pub fn new(public_key: Vec<u8>, verify_token: Vec<u8>) -> Self {
+        Self::new_auto(
+            String::new(),
+            VarInt::from(public_key.len() as i32),
+            public_key,
+            VarInt::from(verify_token.len() as i32),
+            verify_token,
+        )
+    }
+}
@@ -0,0 +1,24 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+pub const SWING_MAIN_ARM: u8 = 0;
+pub const LEAVE_BED: u8 = 2;
+pub const SWING_OFFHAND: u8 = 3;
+pub const CRITICAL_EFFECT: u8 = 4;
+pub const MAGIC_CRITICAL_EFFECT: u8 = 5;
+
+/// Plays an animation on an entity, e.g. a player swinging their arm.
+#[derive(NetEncode)]
+pub struct EntityAnimation {
+    #[encode(default = VarInt::from(0x04))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub animation: u8,
+}
+
+impl EntityAnimation {
+    pub fn new(entity_id: i32, animation: u8) -> Self {
+        Self::new_auto(VarInt::from(entity_id), animation)
+    }
+}
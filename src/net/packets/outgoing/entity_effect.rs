@@ -0,0 +1,35 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::active_effects::ActiveEffect;
+
+/// Shows the client an effect on an entity, e.g. its icon and timer in the inventory.
+///
+/// - `duration`: How many ticks the effect has left.
+/// - `flags`: See [crate::utils::components::active_effects::SHOW_ICON] and the flags next to it.
+#[derive(NetEncode)]
+pub struct EntityEffect {
+    #[encode(default = VarInt::from(0x6C))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub effect_id: VarInt,
+    pub amplifier: u8,
+    pub duration: VarInt,
+    pub flags: u8,
+    /// Only used by darkness, which isn't supported yet.
+    pub has_factor_data: bool,
+}
+
+impl EntityEffect {
+    pub fn new(entity_id: i32, effect: &ActiveEffect) -> Self {
+        Self::new_auto(
+            VarInt::from(entity_id),
+            VarInt::from(effect.effect.id()),
+            effect.amplifier,
+            VarInt::from(effect.remaining_ticks),
+            effect.flags,
+            false,
+        )
+    }
+}
@@ -0,0 +1,21 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Plays the death animation, tipping the entity over before it disappears.
+pub const ENTITY_DEATH: i8 = 3;
+
+/// Triggers one of the effects an entity has, which depend on what kind of entity it is.
+#[derive(NetEncode)]
+pub struct EntityEvent {
+    #[encode(default = VarInt::from(0x1C))]
+    pub packet_id: VarInt,
+    pub entity_id: i32,
+    pub status: i8,
+}
+
+impl EntityEvent {
+    pub fn new(entity_id: i32, status: i8) -> Self {
+        Self::new_auto(entity_id, status)
+    }
+}
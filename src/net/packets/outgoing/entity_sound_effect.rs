@@ -0,0 +1,38 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::net::packets::outgoing::sound_effect::SoundEvent;
+
+/// Plays a sound that follows an entity around while it plays.
+#[derive(NetEncode)]
+pub struct EntitySoundEffect {
+    #[encode(default = VarInt::from(0x61))]
+    pub packet_id: VarInt,
+    pub sound: SoundEvent,
+    pub category: VarInt,
+    pub entity_id: VarInt,
+    pub volume: f32,
+    pub pitch: f32,
+    pub seed: i64,
+}
+
+impl EntitySoundEffect {
+    pub fn new(
+        sound: SoundEvent,
+        category: i32,
+        entity_id: i32,
+        volume: f32,
+        pitch: f32,
+        seed: i64,
+    ) -> Self {
+        Self::new_auto(
+            sound,
+            VarInt::from(category),
+            VarInt::from(entity_id),
+            volume,
+            pitch,
+            seed,
+        )
+    }
+}
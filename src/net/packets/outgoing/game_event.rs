@@ -0,0 +1,28 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+pub const BEGIN_RAINING: u8 = 1;
+pub const END_RAINING: u8 = 2;
+/// The value is the id of the new gamemode.
+pub const CHANGE_GAME_MODE: u8 = 3;
+/// The value is how hard it's raining, from 0 to 1.
+pub const RAIN_LEVEL_CHANGE: u8 = 7;
+/// The value is how hard it's thundering, from 0 to 1.
+pub const THUNDER_LEVEL_CHANGE: u8 = 8;
+
+/// Tells the client about a change to the game that doesn't have its own packet. What `value`
+/// means depends on the event.
+#[derive(NetEncode)]
+pub struct GameEvent {
+    #[encode(default = VarInt::from(0x1F))]
+    pub packet_id: VarInt,
+    pub event: u8,
+    pub value: f32,
+}
+
+impl GameEvent {
+    pub fn new(event: u8, value: f32) -> Self {
+        Self::new_auto(event, value)
+    }
+}
@@ -0,0 +1,20 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Flashes an entity red, and tilts the camera if it's the player that was hurt.
+///
+/// - `yaw`: Which way the damage came from, relative to where the entity is looking.
+#[derive(NetEncode)]
+pub struct HurtAnimation {
+    #[encode(default = VarInt::from(0x21))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub yaw: f32,
+}
+
+impl HurtAnimation {
+    pub fn new(entity_id: i32, yaw: f32) -> Self {
+        Self::new_auto(VarInt::from(entity_id), yaw)
+    }
+}
@@ -0,0 +1,43 @@
+use std::time::Instant;
+
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_codec::network_types::varlong::Varlong;
+
+use ferrumc_macros::NetEncode;
+
+use crate::world::border::WorldBorder;
+
+/// How far out the client lets portals take players, same as vanilla.
+const PORTAL_TELEPORT_BOUNDARY: i32 = 29999984;
+
+/// Sends the client the whole world border, including a transition that's still going.
+#[derive(NetEncode)]
+pub struct InitializeWorldBorder {
+    #[encode(default = VarInt::from(0x22))]
+    pub packet_id: VarInt,
+    pub x: f64,
+    pub z: f64,
+    pub old_diameter: f64,
+    pub new_diameter: f64,
+    /// How many milliseconds it takes to get from the old diameter to the new one.
+    pub speed: Varlong,
+    pub portal_teleport_boundary: VarInt,
+    pub warning_blocks: VarInt,
+    pub warning_time: VarInt,
+}
+
+impl InitializeWorldBorder {
+    /// The border as it is at `now`.
+    pub fn new(border: &WorldBorder, now: Instant) -> Self {
+        Self::new_auto(
+            border.center.0,
+            border.center.1,
+            border.diameter_at(now),
+            border.diameter,
+            Varlong::from(border.remaining_ms(now)),
+            VarInt::from(PORTAL_TELEPORT_BOUNDARY),
+            VarInt::from(border.warning_blocks),
+            VarInt::from(border.warning_time),
+        )
+    }
+}
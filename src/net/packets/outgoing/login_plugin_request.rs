@@ -1,23 +1,23 @@
-use ferrumc_codec::enc::NetEncode;
 use ferrumc_codec::network_types::varint::VarInt;
 use ferrumc_macros::NetEncode;
 
+/// Sent by the server during login to ask the client something on a plugin channel. The client has
+/// to answer with [crate::net::packets::incoming::login_plugin_response::LoginPluginResponse],
+/// using the same `message_id`.
+///
+/// Used for Velocity's modern forwarding, see [crate::net::utils::proxy].
 #[derive(NetEncode)]
 pub struct LoginPluginRequest {
-    #[encode(default = VarInt::from(0x17))]
+    #[encode(default = VarInt::from(0x04))]
     pub packet_id: VarInt,
+    pub message_id: VarInt,
     pub channel: String,
+    // Not length prefixed, it's just the rest of the packet.
     pub data: Vec<u8>,
 }
 
 impl LoginPluginRequest {
-    pub fn new(channel: impl Into<String>, data: Vec<u8>) -> Self {
-        Self::new_auto(channel.into(), data)
-    }
-
-    pub async fn server_brand(data: impl Into<String>) -> Self {
-        let mut str_buffer = Vec::new();
-        data.into().net_encode(&mut str_buffer).await.expect("tf");
-        Self::new("minecraft:brand", str_buffer)
+    pub fn new(message_id: i32, channel: impl Into<String>, data: Vec<u8>) -> Self {
+        Self::new_auto(VarInt::new(message_id), channel.into(), data)
     }
 }
@@ -1,6 +1,9 @@
 use ferrumc_codec::network_types::varint::VarInt;
 
 use ferrumc_macros::NetEncode;
+use uuid::Uuid;
+
+use crate::net::utils::authentication::ProfileProperty;
 
 /// Sent by the server to the client to start the play state.
 #[derive(NetEncode)]
@@ -9,17 +12,50 @@ pub struct LoginSuccess {
     pub packet_id: VarInt,
     pub uuid: Vec<u8>,
     pub username: String,
-    // Just set this to 0
     pub property_count: VarInt,
-    // TODO: Figure out how what in the everloving fuck this is
+    // The properties of the player's profile, i.e. their skin. Only known in online mode, behind a
+    // proxy, or if `fetch_skins` is on.
     pub properties: Vec<Property>,
 }
 
+impl LoginSuccess {
+    pub fn new(uuid: Uuid, username: String, properties: Vec<ProfileProperty>) -> Self {
+        let properties: Vec<Property> = properties.into_iter().map(Property::from).collect();
+        Self::new_auto(
+            uuid.as_bytes().into(),
+            username,
+            VarInt::from(properties.len() as i32),
+            properties,
+        )
+    }
+}
+
 #[derive(NetEncode)]
 pub struct Property {
     pub name: String,
     pub value: String,
     pub is_signed: bool,
     // Only if is_signed is true
-    pub signature: String,
+    pub signature: Option<String>,
+}
+
+impl From<ProfileProperty> for Property {
+    fn from(property: ProfileProperty) -> Self {
+        Self {
+            name: property.name,
+            value: property.value,
+            is_signed: property.signature.is_some(),
+            signature: property.signature,
+        }
+    }
+}
+
+impl From<&Property> for ProfileProperty {
+    fn from(property: &Property) -> Self {
+        Self {
+            name: property.name.clone(),
+            value: property.value.clone(),
+            signature: property.signature.clone(),
+        }
+    }
 }
@@ -0,0 +1,31 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// The window type of a chest with one row. Each row after it is the next id, up to 6 rows.
+pub const GENERIC_9X1: i32 = 0;
+
+/// Opens a window on the client. Its contents are sent after it with a Set Container Content.
+#[derive(NetEncode)]
+pub struct OpenScreen {
+    #[encode(default = VarInt::from(0x30))]
+    pub packet_id: VarInt,
+    pub window_id: VarInt,
+    /// The window's id in the menu registry.
+    pub window_type: VarInt,
+    /// The window's title as a JSON text component.
+    pub title: String,
+}
+
+impl OpenScreen {
+    pub fn new(window_id: u8, window_type: i32, title: impl Into<TextComponent>) -> Result<Self> {
+        Ok(Self::new_auto(
+            VarInt::from(window_id as i32),
+            VarInt::from(window_type),
+            title.into().to_json()?,
+        ))
+    }
+}
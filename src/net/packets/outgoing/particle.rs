@@ -0,0 +1,78 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::particle::ParticleData;
+
+/// Spawns `count` particles around a position.
+///
+/// Each particle is moved away from the position by a random amount, up to `offset` on each
+/// axis, and given a random speed up to `max_speed`. With a count of 0, a single particle is
+/// spawned right at the position, and the offset is its velocity instead.
+#[derive(NetEncode)]
+pub struct Particle {
+    #[encode(default = VarInt::from(0x26))]
+    pub packet_id: VarInt,
+    pub particle_id: VarInt,
+    /// Whether to show the particle even if it's far away, or the player has particles turned
+    /// down.
+    pub long_distance: bool,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub offset_z: f32,
+    pub max_speed: f32,
+    pub count: i32,
+    pub data: ParticleData,
+}
+
+impl Particle {
+    pub fn new(
+        particle: ParticleData,
+        (x, y, z): (f64, f64, f64),
+        (offset_x, offset_y, offset_z): (f32, f32, f32),
+        max_speed: f32,
+        count: i32,
+    ) -> Self {
+        Self::new_auto(
+            VarInt::from(particle.id()),
+            false,
+            x,
+            y,
+            z,
+            offset_x,
+            offset_y,
+            offset_z,
+            max_speed,
+            count,
+            particle,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_the_id_comes_from_the_data() {
+        let mut bytes = Vec::new();
+        Particle::new(
+            ParticleData::Block(1),
+            (0.0, 1.0, 0.0),
+            (0.5, 0.5, 0.5),
+            0.0,
+            4,
+        )
+        .net_encode(&mut bytes)
+        .await
+        .unwrap();
+        // Length, packet id, particle id, long distance.
+        assert_eq!(bytes[..4], [0x30, 0x26, 0x02, 0x00]);
+        assert_eq!(bytes[bytes.len() - 5..], [0x00, 0x00, 0x00, 0x04, 0x01]);
+    }
+}
@@ -2,11 +2,18 @@ use ferrumc_codec::network_types::varint::VarInt;
 
 use ferrumc_macros::NetEncode;
 
-/// The outgoing ping packet is sent by the server to the client to check the connection.
+/// The pong response packet is sent by the server to the client in reply to a ping request.
 /// Payload is just the same as whatever the client sent.
 #[derive(NetEncode)]
-pub struct OutgoingPing {
-    #[encode(default = VarInt::from(0x32))]
+pub struct PongResponse {
+    #[encode(default = VarInt::from(0x01))]
     pub packet_id: VarInt,
     pub payload: i64,
 }
+
+#[test]
+fn test_pong_echoes_payload() {
+    let packet = PongResponse::new_auto(0x1234_5678_9ABC_DEF0);
+    assert_eq!(packet.packet_id.get_val(), 0x01);
+    assert_eq!(packet.payload, 0x1234_5678_9ABC_DEF0);
+}
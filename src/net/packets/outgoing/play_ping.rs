@@ -0,0 +1,19 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Asks the client to answer with a [Pong](crate::net::packets::incoming::pong::Pong) with the
+/// same id, once it's handled every packet sent before this one. See
+/// [ServerState::ping_player](crate::state::ServerState::ping_player).
+#[derive(NetEncode)]
+pub struct PlayPing {
+    #[encode(default = VarInt::from(0x32))]
+    pub packet_id: VarInt,
+    pub id: i32,
+}
+
+impl PlayPing {
+    pub fn new(id: i32) -> Self {
+        Self::new_auto(id)
+    }
+}
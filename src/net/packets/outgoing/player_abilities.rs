@@ -0,0 +1,86 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::game_mode::GameMode;
+
+pub const INVULNERABLE: u8 = 0x01;
+pub const FLYING: u8 = 0x02;
+pub const ALLOW_FLYING: u8 = 0x04;
+/// Blocks break instantly.
+pub const INSTANT_BREAK: u8 = 0x08;
+
+/// Same as vanilla.
+pub const DEFAULT_FLYING_SPEED: f32 = 0.05;
+pub const DEFAULT_FOV_MODIFIER: f32 = 0.1;
+
+/// Tells the client what the player is allowed to do, e.g. whether they can fly.
+///
+/// - `flags`: A combination of the constants above.
+/// - `fov_modifier`: The walking speed, which the client also uses to change the field of view.
+///
+/// See [crate::state::ServerState::abilities] for the speeds set in the config.
+#[derive(NetEncode)]
+pub struct PlayerAbilities {
+    #[encode(default = VarInt::from(0x34))]
+    pub packet_id: VarInt,
+    pub flags: u8,
+    pub flying_speed: f32,
+    pub fov_modifier: f32,
+}
+
+impl PlayerAbilities {
+    /// The abilities a player in `game_mode` has. Players in gamemodes that can fly can always
+    /// fly, and `may_fly` lets players in the others fly too. Spectators are always flying.
+    pub fn new(game_mode: GameMode, may_fly: bool, is_flying: bool) -> Self {
+        let mut flags = match game_mode {
+            GameMode::Survival | GameMode::Adventure => 0,
+            GameMode::Creative => INVULNERABLE | INSTANT_BREAK,
+            GameMode::Spectator => INVULNERABLE,
+        };
+        if may_fly || game_mode.can_fly() {
+            flags |= ALLOW_FLYING;
+            if is_flying || game_mode.is_always_flying() {
+                flags |= FLYING;
+            }
+        }
+        Self::new_auto(flags, DEFAULT_FLYING_SPEED, DEFAULT_FOV_MODIFIER)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags_follow_game_mode() {
+        assert_eq!(
+            PlayerAbilities::new(GameMode::Survival, false, true).flags,
+            0
+        );
+        assert_eq!(
+            PlayerAbilities::new(GameMode::Creative, false, false).flags,
+            INVULNERABLE | ALLOW_FLYING | INSTANT_BREAK
+        );
+        assert_ne!(
+            PlayerAbilities::new(GameMode::Creative, false, true).flags & FLYING,
+            0
+        );
+        assert_ne!(
+            PlayerAbilities::new(GameMode::Spectator, false, false).flags & FLYING,
+            0
+        );
+    }
+
+    #[test]
+    fn test_flight_can_be_allowed_in_survival() {
+        assert_eq!(
+            PlayerAbilities::new(GameMode::Survival, true, false).flags,
+            ALLOW_FLYING
+        );
+        assert_eq!(
+            PlayerAbilities::new(GameMode::Adventure, true, true).flags,
+            ALLOW_FLYING | FLYING
+        );
+    }
+}
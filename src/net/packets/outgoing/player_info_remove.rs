@@ -0,0 +1,17 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Removes players from the client's player list, e.g. once they've left.
+#[derive(NetEncode)]
+pub struct PlayerInfoRemove {
+    #[encode(default = VarInt::from(0x39))]
+    pub packet_id: VarInt,
+    pub number_of_players: VarInt,
+    pub uuids: Vec<u128>,
+}
+
+impl PlayerInfoRemove {
+    pub fn new(uuids: Vec<u128>) -> Self {
+        Self::new_auto(VarInt::from(uuids.len() as i32), uuids)
+    }
+}
@@ -1,42 +1,230 @@
-#![allow(dead_code)]
+use ferrumc_codec::enc::NetEncode;
 use ferrumc_codec::network_types::varint::VarInt;
+use tokio::io::AsyncWrite;
+
 use ferrumc_macros::NetEncode;
 
+use crate::net::utils::authentication::ProfileProperty;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::text_component::TextComponent;
+
+/// Adds the player to the client's player list, by name. Nothing else can be updated for a
+/// player the client doesn't have yet.
+pub const ADD_PLAYER: u8 = 0x01;
+/// Chat signing isn't supported, so this is never sent.
+pub const INITIALIZE_CHAT: u8 = 0x02;
+pub const UPDATE_GAME_MODE: u8 = 0x04;
+/// Whether the player shows up in the tab list.
+pub const UPDATE_LISTED: u8 = 0x08;
+pub const UPDATE_LATENCY: u8 = 0x10;
+pub const UPDATE_DISPLAY_NAME: u8 = 0x20;
+
+/// Everything a new entry needs.
+pub const ADD_LISTED_PLAYER: u8 =
+    ADD_PLAYER | UPDATE_GAME_MODE | UPDATE_LISTED | UPDATE_LATENCY | UPDATE_DISPLAY_NAME;
+
+/// Adds players to the client's player list, or updates some of their entries. It has to know
+/// about a player before it can be sent [SpawnPlayer](super::spawn_player::SpawnPlayer) for
+/// them.
+///
+/// `actions` says what's in the packet. Every player has the same ones, in the order of their
+/// bits.
 #[derive(NetEncode)]
 pub struct PlayerInfoUpdatePacket {
-    actions: u8,
-    number_of_players: VarInt,
-    players: Vec<PlayerInfo>
+    #[encode(default = VarInt::from(0x3A))]
+    pub packet_id: VarInt,
+    pub actions: u8,
+    pub number_of_players: VarInt,
+    pub players: PlayerInfoList,
 }
 
-#[derive(NetEncode)]
-struct PlayerInfo {
-    uuid: u128,
-    actions: Vec<Action>
+/// A player's entry in the player list. Only the fields for the packet's actions are sent.
+///
+/// - `properties`: The properties of the player's profile, i.e. their skin.
+/// - `latency`: The player's ping in milliseconds, which decides how many bars they get.
+/// - `display_name`: Shown instead of their name, if it's set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerInfo {
+    pub uuid: u128,
+    pub name: String,
+    pub properties: Vec<ProfileProperty>,
+    pub game_mode: GameMode,
+    pub listed: bool,
+    pub latency: i32,
+    pub display_name: Option<TextComponent>,
 }
 
-#[derive(NetEncode)]
-enum Action {
-    AddPlayer(AddPlayer),
-    InitializeChat(InitializeChat),
+impl PlayerInfo {
+    pub fn new(uuid: u128, name: impl Into<String>) -> Self {
+        Self {
+            uuid,
+            name: name.into(),
+            properties: Vec::new(),
+            game_mode: GameMode::default(),
+            listed: true,
+            latency: 0,
+            display_name: None,
+        }
+    }
 }
 
-#[derive(NetEncode)]
-struct AddPlayer {
-    name: String,
-    number_of_properties: VarInt,
-    properties: Vec<Property>,
+/// The players in a [PlayerInfoUpdatePacket], encoded for its actions.
+pub struct PlayerInfoList {
+    pub actions: u8,
+    pub players: Vec<PlayerInfo>,
 }
 
-#[derive(NetEncode)]
-struct Property {
-    name: String,
-    value: String,
-    is_signed: bool,
-    signature: Option<String>,
+impl PlayerInfoUpdatePacket {
+    pub fn new(actions: u8, players: Vec<PlayerInfo>) -> Self {
+        Self::new_auto(
+            actions,
+            VarInt::from(players.len() as i32),
+            PlayerInfoList { actions, players },
+        )
+    }
+
+    /// Adds players to the player list and the tab list.
+    pub fn add_players(players: Vec<PlayerInfo>) -> Self {
+        Self::new(ADD_LISTED_PLAYER, players)
+    }
 }
 
-#[derive(NetEncode)]
-struct InitializeChat {
-    message: String,
-}
\ No newline at end of file
+impl NetEncode for PlayerInfoList {
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        for player in &self.players {
+            player.uuid.net_encode(bytes).await?;
+            if self.actions & ADD_PLAYER != 0 {
+                player.name.net_encode(bytes).await?;
+                VarInt::from(player.properties.len() as i32)
+                    .net_encode(bytes)
+                    .await?;
+                for property in &player.properties {
+                    property.name.net_encode(bytes).await?;
+                    property.value.net_encode(bytes).await?;
+                    match &property.signature {
+                        Some(signature) => {
+                            true.net_encode(bytes).await?;
+                            signature.net_encode(bytes).await?;
+                        }
+                        None => false.net_encode(bytes).await?,
+                    }
+                }
+            }
+            if self.actions & INITIALIZE_CHAT != 0 {
+                // No chat session.
+                false.net_encode(bytes).await?;
+            }
+            if self.actions & UPDATE_GAME_MODE != 0 {
+                VarInt::from(player.game_mode.id() as i32)
+                    .net_encode(bytes)
+                    .await?;
+            }
+            if self.actions & UPDATE_LISTED != 0 {
+                player.listed.net_encode(bytes).await?;
+            }
+            if self.actions & UPDATE_LATENCY != 0 {
+                VarInt::from(player.latency).net_encode(bytes).await?;
+            }
+            if self.actions & UPDATE_DISPLAY_NAME != 0 {
+                match &player.display_name {
+                    Some(display_name) => {
+                        true.net_encode(bytes).await?;
+                        display_name
+                            .to_json()
+                            .map_err(|e| ferrumc_codec::CodecError::Other(e.to_string()))?
+                            .net_encode(bytes)
+                            .await?;
+                    }
+                    None => false.net_encode(bytes).await?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn encode(packet: PlayerInfoUpdatePacket) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        packet.net_encode(&mut bytes).await.unwrap();
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_encode_add_player() {
+        let mut player = PlayerInfo::new(1, "Alex");
+        player.latency = 300;
+        let bytes = encode(PlayerInfoUpdatePacket::add_players(vec![player])).await;
+
+        let mut expected = vec![0x1E, 0x3A, ADD_LISTED_PLAYER, 0x01];
+        expected.extend_from_slice(&1u128.to_be_bytes());
+        // Name, no properties, creative, listed, 300 ms, no display name.
+        expected.extend_from_slice(&[0x04, b'A', b'l', b'e', b'x', 0x00]);
+        expected.extend_from_slice(&[0x01, 0x01, 0xAC, 0x02, 0x00]);
+        assert_eq!(bytes, expected);
+    }
+
+    #[tokio::test]
+    async fn test_encode_properties() {
+        let mut player = PlayerInfo::new(1, "A");
+        player.properties = vec![
+            ProfileProperty {
+                name: "t".to_string(),
+                value: "v".to_string(),
+                signature: Some("s".to_string()),
+            },
+            ProfileProperty {
+                name: "u".to_string(),
+                value: "w".to_string(),
+                signature: None,
+            },
+        ];
+        let bytes = encode(PlayerInfoUpdatePacket::new(ADD_PLAYER, vec![player])).await;
+
+        let mut expected = vec![0x22, 0x3A, ADD_PLAYER, 0x01];
+        expected.extend_from_slice(&1u128.to_be_bytes());
+        expected.extend_from_slice(&[0x01, b'A', 0x02]);
+        expected.extend_from_slice(&[0x01, b't', 0x01, b'v', 0x01, 0x01, b's']);
+        expected.extend_from_slice(&[0x01, b'u', 0x01, b'w', 0x00]);
+        assert_eq!(bytes, expected);
+    }
+
+    #[tokio::test]
+    async fn test_only_the_actions_are_encoded() {
+        let mut alex = PlayerInfo::new(1, "Alex");
+        alex.latency = 5;
+        let mut steve = PlayerInfo::new(2, "Steve");
+        steve.latency = 7;
+        let bytes = encode(PlayerInfoUpdatePacket::new(UPDATE_LATENCY, vec![alex, steve])).await;
+
+        let mut expected = vec![0x25, 0x3A, UPDATE_LATENCY, 0x02];
+        expected.extend_from_slice(&1u128.to_be_bytes());
+        expected.push(0x05);
+        expected.extend_from_slice(&2u128.to_be_bytes());
+        expected.push(0x07);
+        assert_eq!(bytes, expected);
+    }
+
+    #[tokio::test]
+    async fn test_encode_display_name() {
+        let mut player = PlayerInfo::new(1, "Alex");
+        player.game_mode = GameMode::Survival;
+        player.listed = false;
+        player.display_name = Some(TextComponent::new("A"));
+        let actions = UPDATE_GAME_MODE | UPDATE_LISTED | UPDATE_DISPLAY_NAME;
+        let bytes = encode(PlayerInfoUpdatePacket::new(actions, vec![player])).await;
+
+        let json = br#"{"text":"A"}"#;
+        let mut expected = vec![0x23, 0x3A, actions, 0x01];
+        expected.extend_from_slice(&1u128.to_be_bytes());
+        expected.extend_from_slice(&[0x00, 0x00, 0x01, json.len() as u8]);
+        expected.extend_from_slice(json);
+        assert_eq!(bytes, expected);
+    }
+}
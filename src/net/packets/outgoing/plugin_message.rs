@@ -0,0 +1,49 @@
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::prelude::*;
+
+/// The channel both sides announce their brand (e.g. "vanilla" or "FerrumC") on.
+pub const BRAND_CHANNEL: &str = "minecraft:brand";
+
+/// A custom payload sent to the client on a plugin channel while it's in the play state.
+#[derive(NetEncode)]
+pub struct PluginMessage {
+    #[encode(default = VarInt::from(0x17))]
+    pub packet_id: VarInt,
+    pub channel: String,
+    pub data: Vec<u8>,
+}
+
+impl PluginMessage {
+    pub fn new(channel: impl Into<String>, data: Vec<u8>) -> Self {
+        Self::new_auto(channel.into(), data)
+    }
+
+    /// A plugin message whose payload is just `value`, as a VarInt length prefixed string.
+    ///
+    /// The payload itself isn't length prefixed since it makes up the rest of the packet, so the
+    /// string has to be encoded on its own first.
+    pub async fn with_string(channel: impl Into<String>, value: &str) -> Result<Self> {
+        let mut data = Vec::with_capacity(value.len() + 3);
+        value.to_string().net_encode(&mut data).await?;
+        Ok(Self::new(channel, data))
+    }
+
+    pub async fn server_brand(brand: &str) -> Result<Self> {
+        Self::with_string(BRAND_CHANNEL, brand).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_brand_payload_is_length_prefixed() {
+        let packet = PluginMessage::server_brand("FerrumC").await.unwrap();
+        assert_eq!(packet.channel, BRAND_CHANNEL);
+        assert_eq!(packet.data, b"\x07FerrumC");
+    }
+}
@@ -0,0 +1,21 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Tells the client to forget entities, e.g. because they went out of view.
+#[derive(NetEncode)]
+pub struct RemoveEntities {
+    #[encode(default = VarInt::from(0x3E))]
+    pub packet_id: VarInt,
+    pub count: VarInt,
+    pub entity_ids: Vec<VarInt>,
+}
+
+impl RemoveEntities {
+    pub fn new(entity_ids: &[i32]) -> Self {
+        Self::new_auto(
+            VarInt::from(entity_ids.len() as i32),
+            entity_ids.iter().copied().map(VarInt::from).collect(),
+        )
+    }
+}
@@ -0,0 +1,20 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::active_effects::Effect;
+
+/// Takes an effect off an entity, e.g. because it ran out.
+#[derive(NetEncode)]
+pub struct RemoveEntityEffect {
+    #[encode(default = VarInt::from(0x3F))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub effect_id: VarInt,
+}
+
+impl RemoveEntityEffect {
+    pub fn new(entity_id: i32, effect: Effect) -> Self {
+        Self::new_auto(VarInt::from(entity_id), VarInt::from(effect.id()))
+    }
+}
@@ -0,0 +1,40 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::config;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// Asks the client to download and use a resource pack. The client answers with a
+/// [ResourcePackResponse](crate::net::packets::incoming::resource_pack_response::ResourcePackResponse).
+///
+/// - `hash`: The pack's SHA-1 hash in hex, or empty.
+/// - `forced`: Whether the client only gets to accept the pack, or disconnect.
+/// - `prompt`: The message to ask with as a JSON text component, only sent if `has_prompt`.
+#[derive(NetEncode)]
+pub struct ResourcePack {
+    #[encode(default = VarInt::from(0x40))]
+    pub packet_id: VarInt,
+    pub url: String,
+    pub hash: String,
+    pub forced: bool,
+    pub has_prompt: bool,
+    pub prompt: Option<String>,
+}
+
+impl ResourcePack {
+    pub fn new(config: &config::ResourcePack) -> Result<Self> {
+        let prompt = match config.prompt.as_str() {
+            "" => None,
+            prompt => Some(TextComponent::new(prompt).to_json()?),
+        };
+        Ok(Self::new_auto(
+            config.url.clone(),
+            config.sha1.to_lowercase(),
+            config.required,
+            prompt.is_some(),
+            prompt,
+        ))
+    }
+}
@@ -0,0 +1,52 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::game_mode::GameMode;
+use crate::world::dimensions::Dimension;
+
+/// Keeps nothing, which is what dying does.
+pub const KEEP_NOTHING: u8 = 0x00;
+pub const KEEP_ATTRIBUTES: u8 = 0x01;
+pub const KEEP_METADATA: u8 = 0x02;
+
+/// Respawns the player, or moves them to another dimension. The client throws away its player
+/// and makes a new one, keeping only what `data_kept` says.
+#[derive(NetEncode)]
+pub struct Respawn {
+    pub packet_id: VarInt,
+    pub dimension_type: String,
+    pub dimension_name: String,
+    pub seed_hash: i64,
+    pub gamemode: u8,
+    /// -1 if there wasn't one.
+    pub previous_gamemode: i8,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    pub has_death_location: bool,
+    pub portal_cooldown: VarInt,
+    pub data_kept: u8,
+}
+
+impl Respawn {
+    pub fn new(
+        dimension: &Dimension,
+        gamemode: GameMode,
+        previous_gamemode: Option<GameMode>,
+        data_kept: u8,
+    ) -> Self {
+        Self {
+            packet_id: VarInt::from(0x41),
+            dimension_type: dimension.dimension_type.clone(),
+            dimension_name: dimension.name.clone(),
+            seed_hash: 0,
+            gamemode: gamemode.id(),
+            previous_gamemode: previous_gamemode.map_or(-1, |previous| previous.id() as i8),
+            is_debug: false,
+            is_flat: false,
+            has_death_location: false,
+            portal_cooldown: VarInt::new(0),
+            data_kept,
+        }
+    }
+}
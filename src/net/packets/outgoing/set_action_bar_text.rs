@@ -0,0 +1,21 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// Text shown above the hotbar for a few seconds.
+#[derive(NetEncode)]
+pub struct SetActionBarText {
+    #[encode(default = VarInt::from(0x46))]
+    pub packet_id: VarInt,
+    /// The action bar as a JSON text component.
+    pub text: String,
+}
+
+impl SetActionBarText {
+    pub fn new(text: impl Into<TextComponent>) -> Result<Self> {
+        Ok(Self::new_auto(text.into().to_json()?))
+    }
+}
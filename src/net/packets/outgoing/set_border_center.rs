@@ -0,0 +1,18 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Moves the middle of the world border.
+#[derive(NetEncode)]
+pub struct SetBorderCenter {
+    #[encode(default = VarInt::from(0x47))]
+    pub packet_id: VarInt,
+    pub x: f64,
+    pub z: f64,
+}
+
+impl SetBorderCenter {
+    pub fn new(x: f64, z: f64) -> Self {
+        Self::new_auto(x, z)
+    }
+}
@@ -0,0 +1,21 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_codec::network_types::varlong::Varlong;
+
+use ferrumc_macros::NetEncode;
+
+/// Makes the world border grow or shrink smoothly. The client moves it along by itself.
+#[derive(NetEncode)]
+pub struct SetBorderLerpSize {
+    #[encode(default = VarInt::from(0x48))]
+    pub packet_id: VarInt,
+    pub old_diameter: f64,
+    pub new_diameter: f64,
+    /// How many milliseconds it takes to get from the old diameter to the new one.
+    pub speed: Varlong,
+}
+
+impl SetBorderLerpSize {
+    pub fn new(old_diameter: f64, new_diameter: f64, speed_ms: i64) -> Self {
+        Self::new_auto(old_diameter, new_diameter, Varlong::from(speed_ms))
+    }
+}
@@ -0,0 +1,17 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Sets the world border's diameter straight away.
+#[derive(NetEncode)]
+pub struct SetBorderSize {
+    #[encode(default = VarInt::from(0x49))]
+    pub packet_id: VarInt,
+    pub diameter: f64,
+}
+
+impl SetBorderSize {
+    pub fn new(diameter: f64) -> Self {
+        Self::new_auto(diameter)
+    }
+}
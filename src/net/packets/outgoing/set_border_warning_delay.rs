@@ -0,0 +1,17 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// How many seconds before a shrinking border reaches the player their screen starts going red.
+#[derive(NetEncode)]
+pub struct SetBorderWarningDelay {
+    #[encode(default = VarInt::from(0x4A))]
+    pub packet_id: VarInt,
+    pub warning_time: VarInt,
+}
+
+impl SetBorderWarningDelay {
+    pub fn new(warning_time: i32) -> Self {
+        Self::new_auto(VarInt::from(warning_time))
+    }
+}
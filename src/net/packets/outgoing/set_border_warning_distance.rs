@@ -0,0 +1,17 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// How many blocks from the world border the player's screen starts going red.
+#[derive(NetEncode)]
+pub struct SetBorderWarningDistance {
+    #[encode(default = VarInt::from(0x4B))]
+    pub packet_id: VarInt,
+    pub warning_blocks: VarInt,
+}
+
+impl SetBorderWarningDistance {
+    pub fn new(warning_blocks: i32) -> Self {
+        Self::new_auto(VarInt::from(warning_blocks))
+    }
+}
@@ -0,0 +1,14 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Sent by the server during login to turn on packet compression.
+///
+/// Every packet after this one, in both directions, uses the compressed packet format.
+/// Packets of at least `threshold` bytes get compressed.
+#[derive(NetEncode)]
+pub struct SetCompression {
+    #[encode(default = VarInt::from(0x03))]
+    pub packet_id: VarInt,
+    pub threshold: VarInt,
+}
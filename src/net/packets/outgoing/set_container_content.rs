@@ -0,0 +1,41 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::inventory::Inventory;
+use crate::utils::encoding::slot::Slot;
+
+/// Replaces everything in a window, and the item on the player's cursor.
+#[derive(NetEncode)]
+pub struct SetContainerContent {
+    #[encode(default = VarInt::from(0x12))]
+    pub packet_id: VarInt,
+    pub window_id: u8,
+    pub state_id: VarInt,
+    pub count: VarInt,
+    pub slots: Vec<Slot>,
+    pub carried: Slot,
+}
+
+impl SetContainerContent {
+    pub fn new(window_id: u8, state_id: i32, slots: Vec<Slot>, carried: Slot) -> Self {
+        Self::new_auto(
+            window_id,
+            VarInt::from(state_id),
+            VarInt::from(slots.len() as i32),
+            slots,
+            carried,
+        )
+    }
+
+    /// Everything in the player's inventory. Moves the inventory on to a new state id.
+    pub fn from_inventory(window_id: u8, inventory: &mut Inventory) -> Self {
+        let state_id = inventory.next_state_id();
+        Self::new(
+            window_id,
+            state_id,
+            inventory.slots.clone(),
+            inventory.cursor.clone(),
+        )
+    }
+}
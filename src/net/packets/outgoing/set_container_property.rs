@@ -0,0 +1,20 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Changes a property of a window, e.g. how far along a furnace's progress arrow is. What each
+/// property means depends on the window type.
+#[derive(NetEncode)]
+pub struct SetContainerProperty {
+    #[encode(default = VarInt::from(0x13))]
+    pub packet_id: VarInt,
+    pub window_id: u8,
+    pub property: i16,
+    pub value: i16,
+}
+
+impl SetContainerProperty {
+    pub fn new(window_id: u8, property: i16, value: i16) -> Self {
+        Self::new_auto(window_id, property, value)
+    }
+}
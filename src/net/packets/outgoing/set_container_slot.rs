@@ -0,0 +1,22 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::slot::Slot;
+
+/// Replaces one slot in a window.
+#[derive(NetEncode)]
+pub struct SetContainerSlot {
+    #[encode(default = VarInt::from(0x14))]
+    pub packet_id: VarInt,
+    pub window_id: i8,
+    pub state_id: VarInt,
+    pub slot: i16,
+    pub item: Slot,
+}
+
+impl SetContainerSlot {
+    pub fn new(window_id: u8, state_id: i32, slot: i16, item: Slot) -> Self {
+        Self::new_auto(window_id as i8, VarInt::from(state_id), slot, item)
+    }
+}
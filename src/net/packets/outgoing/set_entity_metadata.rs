@@ -0,0 +1,46 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::entity_metadata::EntityMetadata;
+
+/// Updates some of an entity's metadata, e.g. whether a player is crouching.
+#[derive(NetEncode)]
+pub struct SetEntityMetadata {
+    #[encode(default = VarInt::from(0x52))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub metadata: EntityMetadata,
+}
+
+impl SetEntityMetadata {
+    pub fn new(entity_id: i32, metadata: EntityMetadata) -> Self {
+        Self::new_auto(VarInt::from(entity_id), metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+    use crate::utils::encoding::entity_metadata::{player, MetadataValue, Pose};
+
+    #[tokio::test]
+    async fn test_encode_set_entity_metadata() {
+        let metadata = EntityMetadata::new()
+            .with(player::FLAGS, MetadataValue::Byte(player::FLAG_CROUCHING))
+            .with(player::POSE, MetadataValue::Pose(Pose::Sneaking));
+        let mut bytes = Vec::new();
+        SetEntityMetadata::new(1, metadata)
+            .net_encode(&mut bytes)
+            .await
+            .unwrap();
+
+        // Length, packet id, entity id, then the entries.
+        assert_eq!(
+            bytes,
+            vec![0x09, 0x52, 0x01, 0x00, 0x00, 0x02, 0x06, 0x14, 0x05, 0xFF]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::velocity::Velocity;
+
+/// The fastest an entity can be sent moving, in blocks per tick. Vanilla clamps to it too, since
+/// anything faster doesn't fit in the packet.
+pub const MAX_VELOCITY: f64 = 3.9;
+/// How many of the packet's units there are to a block per tick.
+const UNITS_PER_BLOCK: f64 = 8000.0;
+
+/// Sets how fast an entity is moving. Players are pushed by the velocity they're sent about
+/// themselves, everyone else's clients only use it to smooth out the entity's movement.
+///
+/// Each axis is in 1/8000 of a block per tick, see [to_fixed_point].
+#[derive(NetEncode)]
+pub struct SetEntityVelocity {
+    #[encode(default = VarInt::from(0x54))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
+
+impl SetEntityVelocity {
+    pub fn new(entity_id: i32, velocity: &Velocity) -> Self {
+        Self::new_auto(
+            VarInt::from(entity_id),
+            to_fixed_point(velocity.x),
+            to_fixed_point(velocity.y),
+            to_fixed_point(velocity.z),
+        )
+    }
+}
+
+/// One axis of a velocity in blocks per tick, as the packet sends it. It's clamped to
+/// [MAX_VELOCITY] either way, and rounded towards zero like vanilla does. NaN is sent as 0.
+pub fn to_fixed_point(velocity: f64) -> i16 {
+    (velocity.clamp(-MAX_VELOCITY, MAX_VELOCITY) * UNITS_PER_BLOCK) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    #[test]
+    fn test_fixed_point() {
+        assert_eq!(to_fixed_point(0.0), 0);
+        assert_eq!(to_fixed_point(0.4), 3200);
+        assert_eq!(to_fixed_point(-0.25), -2000);
+        // 9876.48 units, rounded towards zero.
+        assert_eq!(to_fixed_point(1.23456), 9876);
+        assert_eq!(to_fixed_point(-1.23456), -9876);
+        assert_eq!(to_fixed_point(f64::NAN), 0);
+    }
+
+    #[test]
+    fn test_fast_velocities_are_clamped() {
+        // 3.9 * 8000, which is nowhere near i16::MAX.
+        assert_eq!(to_fixed_point(3.9), 31200);
+        assert_eq!(to_fixed_point(5.0), 31200);
+        assert_eq!(to_fixed_point(-100.0), -31200);
+        assert_eq!(to_fixed_point(f64::INFINITY), 31200);
+    }
+
+    #[tokio::test]
+    async fn test_encode_set_entity_velocity() {
+        let packet = SetEntityVelocity::new(3, &Velocity::new(0.4, -0.25, 0.0));
+        let mut bytes = Vec::new();
+        packet.net_encode(&mut bytes).await.unwrap();
+        // 3200 is 0x0C80, and -2000 is 0xF830.
+        assert_eq!(
+            bytes,
+            vec![0x08, 0x54, 0x03, 0x0C, 0x80, 0xF8, 0x30, 0x00, 0x00]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::slot::Slot;
+
+/// Set on every equipment slot but the last, since the list isn't prefixed with its length.
+const MORE_EQUIPMENT_FOLLOWS: u8 = 0x80;
+
+/// Where an item is shown on an entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EquipmentSlot {
+    MainHand = 0,
+    OffHand = 1,
+    Boots = 2,
+    Leggings = 3,
+    Chestplate = 4,
+    Helmet = 5,
+}
+
+/// Shows what an entity is holding and wearing. Only the slots that changed need to be sent.
+#[derive(NetEncode)]
+pub struct SetEquipment {
+    #[encode(default = VarInt::from(0x55))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub equipment: EquipmentList,
+}
+
+impl SetEquipment {
+    pub fn new(entity_id: i32, equipment: Vec<(EquipmentSlot, Slot)>) -> Self {
+        Self::new_auto(VarInt::from(entity_id), EquipmentList(equipment))
+    }
+}
+
+/// Has to hold at least one slot.
+pub struct EquipmentList(pub Vec<(EquipmentSlot, Slot)>);
+
+impl NetEncode for EquipmentList {
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        let last = self.0.len().saturating_sub(1);
+        for (i, (equipment_slot, slot)) in self.0.iter().enumerate() {
+            let mut equipment_slot = *equipment_slot as u8;
+            if i < last {
+                equipment_slot |= MORE_EQUIPMENT_FOLLOWS;
+            }
+            bytes.write_u8(equipment_slot).await?;
+            slot.net_encode(bytes).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::encoding::slot::ItemStack;
+
+    #[tokio::test]
+    async fn test_encode_set_equipment() {
+        let packet = SetEquipment::new(
+            3,
+            vec![
+                (EquipmentSlot::MainHand, ItemStack::new(1, 1).into()),
+                (EquipmentSlot::Helmet, Slot::EMPTY),
+            ],
+        );
+        let mut bytes = Vec::new();
+        packet.net_encode(&mut bytes).await.unwrap();
+        assert_eq!(
+            bytes,
+            vec![0x09, 0x55, 0x03, 0x80, 0x01, 0x01, 0x01, 0x00, 0x05, 0x00]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_only_the_last_slot_has_no_continuation_bit() {
+        let packet = SetEquipment::new(
+            3,
+            vec![
+                (EquipmentSlot::OffHand, Slot::EMPTY),
+                (EquipmentSlot::Boots, Slot::EMPTY),
+                (EquipmentSlot::Leggings, Slot::EMPTY),
+            ],
+        );
+        let mut bytes = Vec::new();
+        packet.net_encode(&mut bytes).await.unwrap();
+        assert_eq!(
+            bytes,
+            vec![0x08, 0x55, 0x03, 0x81, 0x00, 0x82, 0x00, 0x03, 0x00]
+        );
+
+        let packet = SetEquipment::new(3, vec![(EquipmentSlot::Helmet, Slot::EMPTY)]);
+        let mut bytes = Vec::new();
+        packet.net_encode(&mut bytes).await.unwrap();
+        assert_eq!(bytes, vec![0x04, 0x55, 0x03, 0x05, 0x00]);
+    }
+}
@@ -0,0 +1,25 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::experience::Experience;
+
+/// Sets the player's experience bar and level.
+#[derive(NetEncode)]
+pub struct SetExperience {
+    #[encode(default = VarInt::from(0x56))]
+    pub packet_id: VarInt,
+    pub progress: f32,
+    pub level: VarInt,
+    pub total: VarInt,
+}
+
+impl SetExperience {
+    pub fn new(experience: &Experience) -> Self {
+        Self::new_auto(
+            experience.progress,
+            VarInt::from(experience.level),
+            VarInt::from(experience.total),
+        )
+    }
+}
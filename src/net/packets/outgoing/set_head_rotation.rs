@@ -0,0 +1,20 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::angle::Angle;
+
+/// Turns an entity's head. Without it, players look straight ahead of their body.
+#[derive(NetEncode)]
+pub struct SetHeadRotation {
+    #[encode(default = VarInt::from(0x42))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub head_yaw: Angle,
+}
+
+impl SetHeadRotation {
+    pub fn new(entity_id: i32, head_yaw: Angle) -> Self {
+        Self::new_auto(VarInt::from(entity_id), head_yaw)
+    }
+}
@@ -0,0 +1,21 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::food::Food;
+
+/// Sets the player's health and food bars. A health of 0 or less shows the death screen.
+#[derive(NetEncode)]
+pub struct SetHealth {
+    #[encode(default = VarInt::from(0x57))]
+    pub packet_id: VarInt,
+    pub health: f32,
+    pub food: VarInt,
+    pub food_saturation: f32,
+}
+
+impl SetHealth {
+    pub fn new(health: f32, food: &Food) -> Self {
+        Self::new_auto(health, VarInt::from(food.level), food.saturation)
+    }
+}
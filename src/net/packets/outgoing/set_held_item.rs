@@ -0,0 +1,17 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Changes which hotbar slot the player has selected, from 0 to 8.
+#[derive(NetEncode)]
+pub struct SetHeldItem {
+    #[encode(default = VarInt::from(0x4D))]
+    pub packet_id: VarInt,
+    pub slot: i8,
+}
+
+impl SetHeldItem {
+    pub fn new(slot: u8) -> Self {
+        Self::new_auto(slot as i8)
+    }
+}
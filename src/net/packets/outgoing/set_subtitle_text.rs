@@ -0,0 +1,21 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// The smaller text under the title. Only shows up once a title is sent after it.
+#[derive(NetEncode)]
+pub struct SetSubtitleText {
+    #[encode(default = VarInt::from(0x5D))]
+    pub packet_id: VarInt,
+    /// The subtitle as a JSON text component.
+    pub text: String,
+}
+
+impl SetSubtitleText {
+    pub fn new(text: impl Into<TextComponent>) -> Result<Self> {
+        Ok(Self::new_auto(text.into().to_json()?))
+    }
+}
@@ -0,0 +1,26 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// The text shown above and below the player list. Sending empty text takes away what was there.
+#[derive(NetEncode)]
+pub struct SetTabListHeaderAndFooter {
+    #[encode(default = VarInt::from(0x65))]
+    pub packet_id: VarInt,
+    /// The header as a JSON text component.
+    pub header: String,
+    /// The footer as a JSON text component.
+    pub footer: String,
+}
+
+impl SetTabListHeaderAndFooter {
+    pub fn new(header: impl Into<TextComponent>, footer: impl Into<TextComponent>) -> Result<Self> {
+        Ok(Self::new_auto(
+            header.into().to_json()?,
+            footer.into().to_json()?,
+        ))
+    }
+}
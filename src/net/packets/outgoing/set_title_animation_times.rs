@@ -0,0 +1,20 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// How long titles take to fade in, stay on screen, and fade out, all in ticks. Only applies to
+/// titles sent after it.
+#[derive(NetEncode)]
+pub struct SetTitleAnimationTimes {
+    #[encode(default = VarInt::from(0x60))]
+    pub packet_id: VarInt,
+    pub fade_in: i32,
+    pub stay: i32,
+    pub fade_out: i32,
+}
+
+impl SetTitleAnimationTimes {
+    pub fn new(fade_in: i32, stay: i32, fade_out: i32) -> Self {
+        Self::new_auto(fade_in, stay, fade_out)
+    }
+}
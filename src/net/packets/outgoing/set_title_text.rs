@@ -0,0 +1,21 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// The big text in the middle of the screen. Sending it is what shows the title, along with the last subtitle sent.
+#[derive(NetEncode)]
+pub struct SetTitleText {
+    #[encode(default = VarInt::from(0x5F))]
+    pub packet_id: VarInt,
+    /// The title as a JSON text component.
+    pub text: String,
+}
+
+impl SetTitleText {
+    pub fn new(text: impl Into<TextComponent>) -> Result<Self> {
+        Ok(Self::new_auto(text.into().to_json()?))
+    }
+}
@@ -0,0 +1,131 @@
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::io::AsyncWrite;
+
+use ferrumc_macros::NetEncode;
+
+/// Which sound to play, either by its id in the sound registry or by its identifier.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SoundEvent {
+    Registry(i32),
+    /// Also works for sounds from resource packs. Without a fixed range, how far the sound can be
+    /// heard depends on its volume.
+    Named {
+        identifier: String,
+        fixed_range: Option<f32>,
+    },
+}
+
+impl NetEncode for SoundEvent {
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        match self {
+            // 0 means the sound is sent inline, so registry ids are off by one.
+            SoundEvent::Registry(id) => VarInt::from(id + 1).net_encode(bytes).await,
+            SoundEvent::Named {
+                identifier,
+                fixed_range,
+            } => {
+                VarInt::from(0).net_encode(bytes).await?;
+                identifier.net_encode(bytes).await?;
+                fixed_range.is_some().net_encode(bytes).await?;
+                fixed_range.net_encode(bytes).await
+            }
+        }
+    }
+}
+
+/// Plays a sound at a position. The client picks which variant of the sound to play with `seed`,
+/// so everyone hears the same one.
+#[derive(NetEncode)]
+pub struct SoundEffect {
+    #[encode(default = VarInt::from(0x62))]
+    pub packet_id: VarInt,
+    pub sound: SoundEvent,
+    pub category: VarInt,
+    /// The position, in eighths of a block.
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub volume: f32,
+    pub pitch: f32,
+    pub seed: i64,
+}
+
+impl SoundEffect {
+    pub fn new(
+        sound: SoundEvent,
+        category: i32,
+        (x, y, z): (f64, f64, f64),
+        volume: f32,
+        pitch: f32,
+        seed: i64,
+    ) -> Self {
+        Self::new_auto(
+            sound,
+            VarInt::from(category),
+            to_fixed_point(x),
+            to_fixed_point(y),
+            to_fixed_point(z),
+            volume,
+            pitch,
+            seed,
+        )
+    }
+}
+
+/// Sound positions are sent in eighths of a block.
+pub fn to_fixed_point(coordinate: f64) -> i32 {
+    (coordinate * 8.0) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encode_sound_effect() {
+        let mut bytes = Vec::new();
+        SoundEffect::new(SoundEvent::Registry(5), 4, (0.5, -1.0, 2.0), 1.0, 0.5, 7)
+            .net_encode(&mut bytes)
+            .await
+            .unwrap();
+
+        let mut expected = vec![0x1F, 0x62, 0x06, 0x04];
+        expected.extend_from_slice(&4i32.to_be_bytes());
+        expected.extend_from_slice(&(-8i32).to_be_bytes());
+        expected.extend_from_slice(&16i32.to_be_bytes());
+        expected.extend_from_slice(&1.0f32.to_be_bytes());
+        expected.extend_from_slice(&0.5f32.to_be_bytes());
+        expected.extend_from_slice(&7i64.to_be_bytes());
+        assert_eq!(bytes, expected);
+    }
+
+    #[tokio::test]
+    async fn test_encode_named_sounds() {
+        let mut bytes = Vec::new();
+        SoundEvent::Named {
+            identifier: "a:b".to_string(),
+            fixed_range: None,
+        }
+        .net_encode(&mut bytes)
+        .await
+        .unwrap();
+        assert_eq!(bytes, vec![0x00, 0x03, b'a', b':', b'b', 0x00]);
+
+        let mut bytes = Vec::new();
+        SoundEvent::Named {
+            identifier: "a:b".to_string(),
+            fixed_range: Some(2.0),
+        }
+        .net_encode(&mut bytes)
+        .await
+        .unwrap();
+        assert_eq!(
+            bytes,
+            vec![0x00, 0x03, b'a', b':', b'b', 0x01, 0x40, 0x00, 0x00, 0x00]
+        );
+    }
+}
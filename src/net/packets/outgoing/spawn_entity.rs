@@ -0,0 +1,102 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::net::packets::outgoing::set_entity_velocity::to_fixed_point;
+use crate::utils::components::last_sent_movement::LastSentMovement;
+use crate::utils::components::velocity::Velocity;
+use crate::utils::encoding::angle::Angle;
+
+/// The id of dropped items in the entity type registry.
+pub const ITEM_ENTITY_TYPE: i32 = 54;
+
+/// Shows an entity that isn't a player to the client, see
+/// [SpawnPlayer](super::spawn_player::SpawnPlayer) for those.
+///
+/// - `data`: Means something different for each type, e.g. which way an item frame faces. 0 for
+///   types that don't use it.
+/// - `velocity_x`, `velocity_y`, `velocity_z`: In the same units as
+///   [SetEntityVelocity](super::set_entity_velocity::SetEntityVelocity).
+#[derive(NetEncode)]
+pub struct SpawnEntity {
+    #[encode(default = VarInt::from(0x01))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub uuid: u128,
+    pub entity_type: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub pitch: Angle,
+    pub yaw: Angle,
+    pub head_yaw: Angle,
+    pub data: VarInt,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
+
+impl SpawnEntity {
+    /// Spawns the entity where other players were last told it is, going at `velocity`.
+    pub fn new(
+        entity_id: i32,
+        uuid: u128,
+        entity_type: i32,
+        movement: &LastSentMovement,
+        velocity: &Velocity,
+    ) -> Self {
+        let (x, y, z) = movement.position;
+        Self::new_auto(
+            VarInt::from(entity_id),
+            uuid,
+            VarInt::from(entity_type),
+            x,
+            y,
+            z,
+            movement.pitch,
+            movement.yaw,
+            movement.yaw,
+            VarInt::from(0),
+            to_fixed_point(velocity.x),
+            to_fixed_point(velocity.y),
+            to_fixed_point(velocity.z),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encode_spawn_entity() {
+        let movement = LastSentMovement {
+            position: (0.5, 64.0, -1.5),
+            yaw: Angle(64),
+            pitch: Angle(0),
+        };
+        let packet = SpawnEntity::new(
+            7,
+            1,
+            ITEM_ENTITY_TYPE,
+            &movement,
+            &Velocity::new(0.0, 0.25, 0.0),
+        );
+        let mut bytes = Vec::new();
+        packet.net_encode(&mut bytes).await.unwrap();
+
+        let mut expected = vec![0x35, 0x01, 0x07];
+        expected.extend_from_slice(&1u128.to_be_bytes());
+        expected.push(54);
+        expected.extend_from_slice(&0.5f64.to_be_bytes());
+        expected.extend_from_slice(&64.0f64.to_be_bytes());
+        expected.extend_from_slice(&(-1.5f64).to_be_bytes());
+        // Pitch, yaw, head yaw, then no data.
+        expected.extend_from_slice(&[0x00, 0x40, 0x40, 0x00]);
+        // 0.25 blocks per tick is 2000 units up.
+        expected.extend_from_slice(&[0x00, 0x00, 0x07, 0xD0, 0x00, 0x00]);
+        assert_eq!(bytes, expected);
+    }
+}
@@ -0,0 +1,37 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::last_sent_movement::LastSentMovement;
+use crate::utils::encoding::angle::Angle;
+
+/// Shows another player to the client. The client ignores it unless it was sent the player's
+/// info first, see [PlayerInfoUpdatePacket](super::player_info_update::PlayerInfoUpdatePacket).
+#[derive(NetEncode)]
+pub struct SpawnPlayer {
+    #[encode(default = VarInt::from(0x03))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub uuid: u128,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: Angle,
+    pub pitch: Angle,
+}
+
+impl SpawnPlayer {
+    /// Spawns the player where other players were last told it is.
+    pub fn new(entity_id: i32, uuid: u128, movement: &LastSentMovement) -> Self {
+        let (x, y, z) = movement.position;
+        Self::new_auto(
+            VarInt::from(entity_id),
+            uuid,
+            x,
+            y,
+            z,
+            movement.yaw,
+            movement.pitch,
+        )
+    }
+}
@@ -0,0 +1,55 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+const HAS_CATEGORY: u8 = 0x01;
+const HAS_SOUND: u8 = 0x02;
+
+/// Stops sounds that are playing. Without a category or a sound, it stops every sound.
+#[derive(NetEncode)]
+pub struct StopSound {
+    #[encode(default = VarInt::from(0x63))]
+    pub packet_id: VarInt,
+    /// Which of the fields after it are sent.
+    pub flags: u8,
+    pub category: Option<VarInt>,
+    /// The sound's identifier.
+    pub sound: Option<String>,
+}
+
+impl StopSound {
+    pub fn new(category: Option<i32>, sound: Option<String>) -> Self {
+        let mut flags = 0;
+        if category.is_some() {
+            flags |= HAS_CATEGORY;
+        }
+        if sound.is_some() {
+            flags |= HAS_SOUND;
+        }
+        Self::new_auto(flags, category.map(VarInt::from), sound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_flags_match_the_fields_sent() {
+        let mut bytes = Vec::new();
+        StopSound::new(None, None)
+            .net_encode(&mut bytes)
+            .await
+            .unwrap();
+        assert_eq!(bytes, vec![0x02, 0x63, 0x00]);
+
+        let mut bytes = Vec::new();
+        StopSound::new(Some(4), Some("a:b".to_string()))
+            .net_encode(&mut bytes)
+            .await
+            .unwrap();
+        assert_eq!(bytes, vec![0x07, 0x63, 0x03, 0x04, 0x03, b'a', b':', b'b']);
+    }
+}
@@ -17,16 +17,24 @@ pub struct SynchronizePlayerPosition {
 }
 
 impl SynchronizePlayerPosition {
-    pub fn new(position: &Position, rotation: &Rotation) -> Self {
+    /// `teleport_id` comes from [crate::utils::components::pending_teleports::PendingTeleports],
+    /// so the client's confirmation can be matched up with it.
+    pub fn new(position: &Position, rotation: &Rotation, teleport_id: i32) -> Self {
+        let (x, y, z) = (position.x as f64, position.y as f64, position.z as f64);
+        Self::exact((x, y, z), rotation, teleport_id)
+    }
+
+    /// Teleports to exactly `x`, `y`, `z`, rather than the corner of a block.
+    pub fn exact((x, y, z): (f64, f64, f64), rotation: &Rotation, teleport_id: i32) -> Self {
         Self {
             packet_id: VarInt::from(0x3C),
-            x: position.x as f64,
-            y: position.y as f64,
-            z: position.z as f64,
+            x,
+            y,
+            z,
             yaw: rotation.yaw,
             pitch: rotation.pitch,
             flags: 0, // Absolute position & rotation
-            teleport_id: VarInt::from(0),
+            teleport_id: VarInt::from(teleport_id),
         }
     }
 }
@@ -0,0 +1,23 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// A chat message that isn't signed by a player, which is everything the server sends. Shown in
+/// the chat, or above the hotbar if `overlay` is set.
+#[derive(NetEncode)]
+pub struct SystemChatMessage {
+    #[encode(default = VarInt::from(0x64))]
+    pub packet_id: VarInt,
+    /// The message as a JSON text component.
+    pub content: String,
+    pub overlay: bool,
+}
+
+impl SystemChatMessage {
+    pub fn new(message: impl Into<TextComponent>) -> Result<Self> {
+        Ok(Self::new_auto(message.into().to_json()?, false))
+    }
+}
@@ -0,0 +1,42 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Plays the animation of `collector_id` picking up `collected_id`, which flies into them. It
+/// doesn't remove the item, that still needs
+/// [RemoveEntities](super::remove_entities::RemoveEntities).
+#[derive(NetEncode)]
+pub struct TakeItemEntity {
+    #[encode(default = VarInt::from(0x67))]
+    pub packet_id: VarInt,
+    pub collected_id: VarInt,
+    pub collector_id: VarInt,
+    pub count: VarInt,
+}
+
+impl TakeItemEntity {
+    pub fn new(collected_id: i32, collector_id: i32, count: i8) -> Self {
+        Self::new_auto(
+            VarInt::from(collected_id),
+            VarInt::from(collector_id),
+            VarInt::from(count as i32),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encode_take_item_entity() {
+        let mut bytes = Vec::new();
+        TakeItemEntity::new(12, 3, 64)
+            .net_encode(&mut bytes)
+            .await
+            .unwrap();
+        assert_eq!(bytes, vec![0x04, 0x67, 0x0C, 0x03, 0x40]);
+    }
+}
@@ -0,0 +1,32 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::angle::Angle;
+
+/// Moves an entity anywhere, for moves too far for
+/// [UpdateEntityPosition](super::update_entity_position::UpdateEntityPosition).
+#[derive(NetEncode)]
+pub struct TeleportEntity {
+    #[encode(default = VarInt::from(0x68))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: Angle,
+    pub pitch: Angle,
+    pub on_ground: bool,
+}
+
+impl TeleportEntity {
+    pub fn new(
+        entity_id: i32,
+        (x, y, z): (f64, f64, f64),
+        yaw: Angle,
+        pitch: Angle,
+        on_ground: bool,
+    ) -> Self {
+        Self::new_auto(VarInt::from(entity_id), x, y, z, yaw, pitch, on_ground)
+    }
+}
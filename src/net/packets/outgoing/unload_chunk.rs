@@ -0,0 +1,18 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Tells the client to forget a chunk, e.g. because it's out of view now.
+#[derive(NetEncode)]
+pub struct UnloadChunk {
+    #[encode(default = VarInt::from(0x1E))]
+    pub packet_id: VarInt,
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+}
+
+impl UnloadChunk {
+    pub fn new(chunk_x: i32, chunk_z: i32) -> Self {
+        Self::new_auto(chunk_x, chunk_z)
+    }
+}
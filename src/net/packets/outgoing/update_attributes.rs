@@ -0,0 +1,61 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Adds to a [Attribute]'s base value.
+pub const ADD: u8 = 0;
+/// Adds a multiple of the base value.
+pub const MULTIPLY_BASE: u8 = 1;
+/// Multiplies the value after everything else has been added.
+pub const MULTIPLY_TOTAL: u8 = 2;
+
+/// Something changing an attribute, e.g. a speed effect. The client keeps its own modifiers
+/// apart by `uuid`.
+///
+/// - `operation`: [ADD], [MULTIPLY_BASE] or [MULTIPLY_TOTAL].
+#[derive(NetEncode, Debug, Clone)]
+pub struct AttributeModifier {
+    pub uuid: u128,
+    pub amount: f64,
+    pub operation: u8,
+}
+
+/// One of an entity's attributes, e.g. `minecraft:generic.movement_speed`.
+#[derive(NetEncode, Debug, Clone)]
+pub struct Attribute {
+    pub key: String,
+    pub value: f64,
+    pub modifier_count: VarInt,
+    pub modifiers: Vec<AttributeModifier>,
+}
+
+impl Attribute {
+    pub fn new(key: &str, value: f64, modifiers: Vec<AttributeModifier>) -> Self {
+        Self {
+            key: key.to_string(),
+            value,
+            modifier_count: VarInt::from(modifiers.len() as i32),
+            modifiers,
+        }
+    }
+}
+
+/// Sets an entity's attributes, replacing the modifiers the client had for them.
+#[derive(NetEncode)]
+pub struct UpdateAttributes {
+    #[encode(default = VarInt::from(0x6A))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub count: VarInt,
+    pub attributes: Vec<Attribute>,
+}
+
+impl UpdateAttributes {
+    pub fn new(entity_id: i32, attributes: Vec<Attribute>) -> Self {
+        Self::new_auto(
+            VarInt::from(entity_id),
+            VarInt::from(attributes.len() as i32),
+            attributes,
+        )
+    }
+}
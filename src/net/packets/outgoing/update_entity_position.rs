@@ -0,0 +1,31 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Moves an entity by up to 8 blocks in each direction. The deltas are in 1/4096 of a block.
+#[derive(NetEncode)]
+pub struct UpdateEntityPosition {
+    #[encode(default = VarInt::from(0x2B))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    pub on_ground: bool,
+}
+
+impl UpdateEntityPosition {
+    pub fn new(
+        entity_id: i32,
+        (delta_x, delta_y, delta_z): (i16, i16, i16),
+        on_ground: bool,
+    ) -> Self {
+        Self::new_auto(
+            VarInt::from(entity_id),
+            delta_x,
+            delta_y,
+            delta_z,
+            on_ground,
+        )
+    }
+}
@@ -0,0 +1,40 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::angle::Angle;
+
+/// Moves and turns an entity at once. See
+/// [UpdateEntityPosition](super::update_entity_position::UpdateEntityPosition) for the deltas.
+#[derive(NetEncode)]
+pub struct UpdateEntityPositionAndRotation {
+    #[encode(default = VarInt::from(0x2C))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    pub yaw: Angle,
+    pub pitch: Angle,
+    pub on_ground: bool,
+}
+
+impl UpdateEntityPositionAndRotation {
+    pub fn new(
+        entity_id: i32,
+        (delta_x, delta_y, delta_z): (i16, i16, i16),
+        yaw: Angle,
+        pitch: Angle,
+        on_ground: bool,
+    ) -> Self {
+        Self::new_auto(
+            VarInt::from(entity_id),
+            delta_x,
+            delta_y,
+            delta_z,
+            yaw,
+            pitch,
+            on_ground,
+        )
+    }
+}
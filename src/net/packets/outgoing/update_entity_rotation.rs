@@ -0,0 +1,23 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::angle::Angle;
+
+/// Turns an entity's body. Its head is turned separately, with
+/// [SetHeadRotation](super::set_head_rotation::SetHeadRotation).
+#[derive(NetEncode)]
+pub struct UpdateEntityRotation {
+    #[encode(default = VarInt::from(0x2D))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub yaw: Angle,
+    pub pitch: Angle,
+    pub on_ground: bool,
+}
+
+impl UpdateEntityRotation {
+    pub fn new(entity_id: i32, yaw: Angle, pitch: Angle, on_ground: bool) -> Self {
+        Self::new_auto(VarInt::from(entity_id), yaw, pitch, on_ground)
+    }
+}
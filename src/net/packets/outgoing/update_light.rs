@@ -0,0 +1,35 @@
+use std::collections::BTreeSet;
+
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::net::packets::outgoing::chunk_and_light_data::LightData;
+use crate::world::chunk_format::Section;
+
+/// Sends the light of some of a chunk's sections again after it changed, without sending its
+/// blocks too. Clients keep the light they have for the rest.
+#[derive(NetEncode)]
+pub struct UpdateLight<'a> {
+    #[encode(default = VarInt::from(0x27))]
+    pub packet_id: VarInt,
+    pub chunk_x: VarInt,
+    pub chunk_z: VarInt,
+    pub light: LightData<'a>,
+}
+
+impl<'a> UpdateLight<'a> {
+    /// Sends the sky light of the sections at the y in `changed`.
+    pub fn new(
+        chunk_x: i32,
+        chunk_z: i32,
+        sections: &'a [Section],
+        changed: &BTreeSet<i8>,
+    ) -> Self {
+        Self::new_auto(
+            VarInt::from(chunk_x),
+            VarInt::from(chunk_z),
+            LightData::sky_light_of(sections, changed),
+        )
+    }
+}
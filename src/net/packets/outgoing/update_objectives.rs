@@ -0,0 +1,54 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+pub const CREATE_OBJECTIVE: u8 = 0;
+pub const REMOVE_OBJECTIVE: u8 = 1;
+pub const UPDATE_DISPLAY_NAME: u8 = 2;
+
+/// Scores are shown as numbers.
+pub const RENDER_INTEGER: i32 = 0;
+/// Scores are shown as hearts. Only works in the player list.
+pub const RENDER_HEARTS: i32 = 1;
+
+/// Creates, removes or renames a scoreboard objective on the client. Removing one also removes
+/// its scores, and takes it out of the slot it was displayed in.
+#[derive(NetEncode, Clone)]
+pub struct UpdateObjectives {
+    #[encode(default = VarInt::from(0x58))]
+    pub packet_id: VarInt,
+    /// The objective's name, which other scoreboard packets refer to it by.
+    pub objective_name: String,
+    pub mode: u8,
+    /// The name shown above the scores as a JSON text component. Not sent when removing.
+    pub display_name: Option<String>,
+    /// Not sent when removing.
+    pub render_type: Option<VarInt>,
+}
+
+impl UpdateObjectives {
+    pub fn create(objective_name: &str, display_name: &TextComponent) -> Result<Self> {
+        Ok(Self::new_auto(
+            objective_name.to_string(),
+            CREATE_OBJECTIVE,
+            Some(display_name.to_json()?),
+            Some(VarInt::from(RENDER_INTEGER)),
+        ))
+    }
+
+    pub fn update_display_name(objective_name: &str, display_name: &TextComponent) -> Result<Self> {
+        Ok(Self::new_auto(
+            objective_name.to_string(),
+            UPDATE_DISPLAY_NAME,
+            Some(display_name.to_json()?),
+            Some(VarInt::from(RENDER_INTEGER)),
+        ))
+    }
+
+    pub fn remove(objective_name: &str) -> Self {
+        Self::new_auto(objective_name.to_string(), REMOVE_OBJECTIVE, None, None)
+    }
+}
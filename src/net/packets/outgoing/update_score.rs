@@ -0,0 +1,70 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+const UPDATE_SCORE: i32 = 0;
+const REMOVE_SCORE: i32 = 1;
+
+/// Sets or removes the score of one entry in an objective. Entries are usually player names,
+/// but they can be any text, which is how sidebars show lines.
+#[derive(NetEncode)]
+pub struct UpdateScore {
+    #[encode(default = VarInt::from(0x5B))]
+    pub packet_id: VarInt,
+    pub entity_name: String,
+    pub action: VarInt,
+    pub objective_name: String,
+    /// Not sent when removing.
+    pub value: Option<VarInt>,
+}
+
+impl UpdateScore {
+    pub fn update(objective_name: &str, entry: &str, value: i32) -> Self {
+        Self::new_auto(
+            entry.to_string(),
+            VarInt::from(UPDATE_SCORE),
+            objective_name.to_string(),
+            Some(VarInt::from(value)),
+        )
+    }
+
+    pub fn remove(objective_name: &str, entry: &str) -> Self {
+        Self::new_auto(
+            entry.to_string(),
+            VarInt::from(REMOVE_SCORE),
+            objective_name.to_string(),
+            None,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encode_update_score() {
+        let mut bytes = Vec::new();
+        UpdateScore::update("obj", "Bob", 300)
+            .net_encode(&mut bytes)
+            .await
+            .unwrap();
+        assert_eq!(
+            bytes,
+            vec![0x0C, 0x5B, 0x03, b'B', b'o', b'b', 0x00, 0x03, b'o', b'b', b'j', 0xAC, 0x02]
+        );
+
+        // Removing leaves the value out.
+        let mut bytes = Vec::new();
+        UpdateScore::remove("obj", "Bob")
+            .net_encode(&mut bytes)
+            .await
+            .unwrap();
+        assert_eq!(
+            bytes,
+            vec![0x0A, 0x5B, 0x03, b'B', b'o', b'b', 0x01, 0x03, b'o', b'b', b'j']
+        );
+    }
+}
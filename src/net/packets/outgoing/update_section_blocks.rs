@@ -0,0 +1,87 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_codec::network_types::varlong::Varlong;
+
+use ferrumc_macros::NetEncode;
+
+/// Changes any number of blocks in one chunk section on the client.
+///
+/// - `section_position`: The section's x, y and z packed into a long, see
+///   [encode_section_position].
+/// - `blocks`: Each block's state id and where it is in the section, see [encode_block_change].
+#[derive(NetEncode)]
+pub struct UpdateSectionBlocks {
+    #[encode(default = VarInt::from(0x43))]
+    pub packet_id: VarInt,
+    pub section_position: i64,
+    pub block_count: VarInt,
+    pub blocks: Vec<Varlong>,
+}
+
+impl UpdateSectionBlocks {
+    /// `section` is the section's x, y and z in sections. `blocks` are each block's x, y and z
+    /// inside the section, and its state id.
+    pub fn new(section: (i32, i32, i32), blocks: &[((u8, u8, u8), i32)]) -> Self {
+        let (section_x, section_y, section_z) = section;
+        let blocks = blocks
+            .iter()
+            .map(|&((x, y, z), block_id)| Varlong::from(encode_block_change(x, y, z, block_id)))
+            .collect::<Vec<_>>();
+        Self::new_auto(
+            encode_section_position(section_x, section_y, section_z),
+            VarInt::from(blocks.len() as i32),
+            blocks,
+        )
+    }
+}
+
+/// Packs a section's position into a long: 22 bits of x, then 22 of z, then 20 of y.
+pub fn encode_section_position(x: i32, y: i32, z: i32) -> i64 {
+    ((x as i64 & 0x3FFFFF) << 42) | ((z as i64 & 0x3FFFFF) << 20) | (y as i64 & 0xFFFFF)
+}
+
+/// Packs one changed block into a long: the state id, then 4 bits each of x, z and y inside the
+/// section.
+pub fn encode_block_change(x: u8, y: u8, z: u8, block_id: i32) -> i64 {
+    ((block_id as i64) << 12) | ((x as i64 & 0xF) << 8) | ((z as i64 & 0xF) << 4) | (y as i64 & 0xF)
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    #[test]
+    fn test_section_positions_are_packed() {
+        assert_eq!(encode_section_position(0, 0, 0), 0);
+        assert_eq!(encode_section_position(1, 2, 3), (1 << 42) | (3 << 20) | 2);
+        // Negative coordinates only keep their low bits, so they don't spill into the others.
+        assert_eq!(
+            encode_section_position(-1, -4, -1),
+            (0x3FFFFF << 42) | (0x3FFFFF << 20) | 0xFFFFC
+        );
+        assert_eq!(encode_section_position(-1, 0, 0) >> 42, -1);
+        assert_eq!(encode_section_position(0, 0, -2) >> 20 & 0x3FFFFF, 0x3FFFFE);
+    }
+
+    #[test]
+    fn test_block_changes_are_packed() {
+        assert_eq!(encode_block_change(0, 0, 0, 1), 0x1000);
+        assert_eq!(encode_block_change(1, 2, 3, 0), 0x132);
+        assert_eq!(encode_block_change(15, 15, 15, 9), 0x9FFF);
+        // 26,000 and some state ids don't fit in the 20 bits below the id.
+        assert_eq!(encode_block_change(4, 5, 6, 26_000) >> 12, 26_000);
+    }
+
+    #[tokio::test]
+    async fn test_encode_update_section_blocks() {
+        let packet = UpdateSectionBlocks::new((0, 1, 0), &[((1, 2, 3), 1)]);
+        let mut bytes = Vec::new();
+        packet.net_encode(&mut bytes).await.unwrap();
+        assert_eq!(
+            bytes,
+            // Length, id, section position, count, then 0x1132 as a varlong.
+            vec![0x0C, 0x43, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x01, 0xB2, 0x22]
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Tells the client what time it is. The client keeps the time moving by itself in between, so
+/// this only has to be sent now and then.
+#[derive(NetEncode)]
+pub struct UpdateTime {
+    #[encode(default = VarInt::from(0x5E))]
+    pub packet_id: VarInt,
+    pub world_age: i64,
+    /// Negative if the daylight cycle is stopped, see
+    /// [WorldTime::time_of_day_for_client](crate::world::time::WorldTime::time_of_day_for_client).
+    pub time_of_day: i64,
+}
+
+impl UpdateTime {
+    pub fn new(world_age: i64, time_of_day: i64) -> Self {
+        Self::new_auto(world_age, time_of_day)
+    }
+}
@@ -0,0 +1,7 @@
+use crate::state::GlobalState;
+
+/// Sends players the blocks changed during each tick, see
+/// [ServerState::flush_block_changes](crate::state::ServerState::flush_block_changes).
+pub async fn tick(state: GlobalState) {
+    state.flush_block_changes().await;
+}
@@ -0,0 +1,29 @@
+use crate::state::GlobalState;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::loaded_chunks::LoadedChunks;
+
+/// Tells the chunk cache which chunks players can see, about once a second, so they're kept
+/// and the rest can be evicted once it's full.
+///
+/// Chunks are marked as loaded before they're sent, so ones on their way to a player count too.
+pub async fn tick(state: GlobalState) {
+    let in_view = state
+        .world
+        .query::<(&LoadedChunks, &CurrentDimension)>()
+        .iter()
+        .await
+        .flat_map(|(_, (loaded_chunks, dimension))| {
+            let key = dimension.0.key.clone();
+            loaded_chunks
+                .chunks
+                .iter()
+                .map(move |&(x, z)| (x, z, key.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    state.database.set_chunks_in_view(
+        in_view
+            .iter()
+            .map(|(x, z, dimension)| (*x, *z, dimension.as_str())),
+    );
+}
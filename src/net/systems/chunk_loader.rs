@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+use futures::future::join_all;
+
+use ferrumc_macros::AutoGenName;
+
+use crate::net::systems::System;
+use crate::state::GlobalState;
+
+/// Runs the [ChunkService](crate::world::chunk_service::ChunkService)'s workers, which load and
+/// generate the chunks players ask for.
+#[derive(AutoGenName)]
+pub struct ChunkLoaderSystem;
+
+#[async_trait]
+impl System for ChunkLoaderSystem {
+    async fn run(&self, state: GlobalState) {
+        let workers = (0..state.chunks.workers()).map(|_| state.chunks.run_worker(&state));
+        join_all(workers).await;
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
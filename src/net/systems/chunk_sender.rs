@@ -1,25 +1,49 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use ferrumc_codec::enc::NetEncode;
+use futures::stream::{FuturesUnordered, StreamExt};
 use tokio::sync::RwLock;
 use tracing::{debug, error, warn};
 
-use crate::net::packets::incoming::client_info::ClientInfo;
 use crate::net::packets::outgoing::chunk_and_light_data::ChunkDataAndUpdateLight;
 use crate::net::packets::outgoing::set_center_chunk::SetCenterChunk;
+use crate::net::packets::outgoing::unload_chunk::UnloadChunk;
+use crate::net::packets::ConnectionId;
 use crate::net::systems::System;
 use crate::net::{Connection, ConnectionWrapper};
 use crate::state::GlobalState;
-use crate::utils::components::last_chunk_tx_pos::LastChunkTxPos;
+use crate::utils::components::client_settings::{ClientSettings, MIN_VIEW_DISTANCE};
+use crate::utils::components::loaded_chunks::{chebyshev_distance, LoadedChunks};
 use crate::utils::components::player::Player;
+use crate::utils::config::get_global_config;
 use crate::utils::encoding::position::Position;
 use crate::utils::prelude::*;
+use crate::world::chunk_service::{ChunkKey, ChunkPriority};
 use ferrumc_macros::AutoGenName;
 
-pub const DEFAULT_CHUNK_RADIUS: i8 = 16;
 const CHUNK_TX_INTERVAL_MS: u64 = 50000;
+/// How far from where a player joins chunks are loaded before anyone else's.
+const SPAWN_CHUNK_RADIUS: i32 = 1;
+/// The most chunks a client will render, same as vanilla.
+const MAX_VIEW_DISTANCE: u32 = 32;
+
+/// How many chunks around a player to send: what the client asked for, but never more than the
+/// server's `view_distance`. Without the client's settings, it's the server's.
+pub fn view_distance(settings: Option<&ClientSettings>) -> i32 {
+    let server_distance = get_global_config()
+        .view_distance
+        .clamp(MIN_VIEW_DISTANCE as u32, MAX_VIEW_DISTANCE) as i32;
+    match settings {
+        Some(settings) => server_distance.min(settings.view_distance as i32),
+        None => server_distance,
+    }
+}
 
+/// Keeps every player's chunks in step with where they are. Moving into a new chunk updates them
+/// right away, see [ChunkSender::update_view], and this system catches up anyone that was missed
+/// every [CHUNK_TX_INTERVAL_MS].
 #[derive(AutoGenName)]
 pub struct ChunkSender;
 
@@ -54,6 +78,18 @@ impl System for ChunkSender {
 }
 
 impl ChunkSender {
+    /// Catches the player's chunks up with where they are now. Only the chunks that came into
+    /// view since the last update are sent, and nothing is if they're still in the same chunk.
+    pub async fn update_view(state: GlobalState, conn_id: ConnectionId) -> Result<()> {
+        let position = state.world.get_component::<Position>(conn_id).await?;
+        let chunk = (position.x >> 4, position.z >> 4);
+        drop(position);
+        ChunkSender::send_chunks_to_player_if_needed(state, conn_id, chunk).await
+    }
+
+    /// Called whenever the player moves. If they moved into a different chunk, the client is
+    /// told its new center chunk first, and then the chunks that came into view are sent and the
+    /// ones that went out of it unloaded in the background.
     pub async fn send_chunks_to_player_if_needed(
         state: GlobalState,
         entity_id: impl TryInto<usize>,
@@ -61,31 +97,34 @@ impl ChunkSender {
     ) -> Result<()> {
         let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
 
-        let mut last_chunk_tx_pos = state
+        let moved = state
             .world
             .get_component_storage()
-            .get_mut_or_insert_with::<LastChunkTxPos>(entity_id, Default::default)
-            .await;
-
-        let client_info = state.world.get_component::<ClientInfo>(entity_id).await?;
-
-        let distance = last_chunk_tx_pos.distance_to(current_pos.0, current_pos.1);
-
-        if distance < (client_info.view_distance as f64 / 5f64) {
+            .get_mut_or_insert_with(entity_id, LoadedChunks::default)
+            .await
+            .move_center(current_pos);
+        if !moved {
             return Ok(());
         }
 
-        last_chunk_tx_pos.set_last_chunk_tx_pos(current_pos.0, current_pos.1);
+        // The client throws away chunks too far from its center chunk, so it has to be moved
+        // before any new chunks are sent.
+        let conn = state.connections.get_connection(entity_id)?;
+        ChunkSender::send_set_center_chunk(current_pos, conn).await?;
 
         let state_clone = state.clone();
         tokio::spawn(async move {
-            if let Err(e) = ChunkSender::send_chunks_to_player(state_clone, entity_id).await {
+            if let Err(e) = ChunkSender::update_chunks(state_clone, entity_id, current_pos).await {
                 error!("Failed to send chunk to player: {}", e);
             }
         });
 
         Ok(())
     }
+
+    /// Sends the player their center chunk, and brings their [LoadedChunks] up to date with
+    /// where they are. Used when they haven't moved, but the chunks they should have did, e.g.
+    /// when they join or change their render distance.
     pub async fn send_chunks_to_player(
         state: GlobalState,
         entity_id: impl TryInto<usize>,
@@ -96,19 +135,8 @@ impl ChunkSender {
             .world
             .get_components::<(Player, Position, ConnectionWrapper)>(entity_id)
             .await?;
-
-        let c_info = state
-            .world
-            .get_component::<ClientInfo>(entity_id)
-            .await
-            .ok();
-
         let pos = c_pos.clone();
-        let view_distance: i8 = c_info
-            .as_ref()
-            .map_or(DEFAULT_CHUNK_RADIUS, |c| c.view_distance);
         let conn = c_conn.0.clone();
-
         drop(c_pos);
         drop(c_conn);
 
@@ -120,62 +148,162 @@ impl ChunkSender {
 
         drop(player);
 
-        ChunkSender::send_set_center_chunk(&pos, conn.clone()).await?;
-        ChunkSender::send_chunk_data_to_player(state.clone(), &pos, view_distance, conn.clone())
-            .await?;
+        let center = (pos.x >> 4, pos.z >> 4);
+        state
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with(entity_id, LoadedChunks::default)
+            .await
+            .move_center(center);
 
-        Ok(())
+        ChunkSender::send_set_center_chunk(center, conn).await?;
+        ChunkSender::update_chunks(state, entity_id, center).await
+    }
+
+    /// Unloads the chunks that are out of view from `center`, and sends the ones in view that the
+    /// client doesn't have yet. Does nothing if the player has moved on from `center` since, as
+    /// the update for where they are now takes care of it.
+    async fn update_chunks(state: GlobalState, entity_id: usize, center: (i32, i32)) -> Result<()> {
+        let conn = state
+            .world
+            .get_component::<ConnectionWrapper>(entity_id)
+            .await?
+            .0
+            .clone();
+        let view_distance = match state.world.get_component::<ClientSettings>(entity_id).await {
+            Ok(settings) => view_distance(Some(&settings)),
+            Err(_) => view_distance(None),
+        };
+
+        // Chunks are marked as loaded before they're sent, so a second update running at the
+        // same time doesn't send them again.
+        let (unloaded, missing) = {
+            let mut loaded_chunks = state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with(entity_id, LoadedChunks::default)
+                .await;
+            if loaded_chunks.center != Some(center) {
+                return Ok(());
+            }
+            let spawning = loaded_chunks.chunks.is_empty();
+            let unloaded = loaded_chunks.remove_out_of_view(center, view_distance);
+            let missing = loaded_chunks
+                .missing(center, view_distance)
+                .into_iter()
+                .map(|chunk| {
+                    let distance = chebyshev_distance(center, chunk);
+                    let priority = if spawning && distance <= SPAWN_CHUNK_RADIUS {
+                        ChunkPriority::Spawn
+                    } else {
+                        ChunkPriority::Distance(distance as u32)
+                    };
+                    (chunk, priority)
+                })
+                .collect::<Vec<_>>();
+            loaded_chunks
+                .chunks
+                .extend(missing.iter().map(|&(chunk, _)| chunk));
+            (unloaded, missing)
+        };
+
+        ChunkSender::unload_chunks(&unloaded, conn.clone()).await?;
+        ChunkSender::send_chunk_data_to_player(state, entity_id, &missing, conn).await
     }
 
+    /// Asks for every chunk in `chunks` at once, and sends each one as soon as it's loaded.
+    /// Asking for them nearest first means they mostly arrive nearest first too.
     async fn send_chunk_data_to_player(
         state: GlobalState,
-        pos: &Position,
-        player_view_distance: i8,
+        entity_id: usize,
+        chunks: &[((i32, i32), ChunkPriority)],
         conn: Arc<RwLock<Connection>>,
     ) -> Result<()> {
         let start = std::time::Instant::now();
 
-        let pos_x = pos.x;
-        let pos_z = pos.z;
-
-        let chunk_radius = player_view_distance as i32;
-
-        'x: for x in -chunk_radius..=chunk_radius {
-            for z in -chunk_radius..=chunk_radius {
-                let Ok(packet) =
-                    ChunkDataAndUpdateLight::new(state.clone(), (pos_x >> 4) + x, (pos_z >> 4) + z)
-                        .await
-                else {
-                    continue;
-                };
-                let conn_read = conn.read().await;
-                if let Err(e) = conn_read.send_packet(packet).await {
-                    warn!("Failed to send chunk to player: {} ; Cancelling.", e);
-                    break 'x;
+        let dimension = state.dimension_of(entity_id).await;
+        let mut requests = chunks
+            .iter()
+            .map(|&((x, z), priority)| {
+                let chunk = state
+                    .chunks
+                    .request(ChunkKey::new(&dimension, x, z), priority);
+                async move { ((x, z), chunk.await) }
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut not_sent = chunks
+            .iter()
+            .map(|&(chunk, _)| chunk)
+            .collect::<HashSet<_>>();
+        let mut bytes_sent = 0;
+        while let Some((position, chunk)) = requests.next().await {
+            let Ok(chunk) = chunk else {
+                continue;
+            };
+            let Ok(packet) = ChunkDataAndUpdateLight::new(&chunk) else {
+                continue;
+            };
+            let mut packet_bytes = Vec::new();
+            if packet.net_encode(&mut packet_bytes).await.is_err() {
+                continue;
+            }
+            let packet = packet_bytes;
+            bytes_sent += packet.len();
+            let conn_read = conn.read().await;
+            if let Err(e) = conn_read.send_packet(packet).await {
+                // The rest are still loaded and cached, they just aren't sent.
+                warn!("Failed to send chunk to player: {} ; Cancelling.", e);
+                break;
+            }
+            not_sent.remove(&position);
+        }
+
+        // The client doesn't have these, so they can be sent once they exist.
+        if !not_sent.is_empty() {
+            if let Ok(mut loaded_chunks) = state
+                .world
+                .get_component_storage()
+                .get_mut::<LoadedChunks>(entity_id)
+                .await
+            {
+                for chunk in &not_sent {
+                    loaded_chunks.chunks.remove(chunk);
                 }
             }
         }
 
-        // check the size of a single chunk and multiply it by the number of chunks sent
-        let sample_chunk =
-            ChunkDataAndUpdateLight::new(state.clone(), pos_x >> 4, pos_z >> 4).await?;
-        let mut vec = vec![];
-        sample_chunk.net_encode(&mut vec).await?;
-        let chunk_rad_axis = chunk_radius * 2 + 1;
         debug!(
-                "Send {}({}x{}) chunks to player in {:?}. Approximately {} kb of data (~{} kb per chunk)",
-                chunk_rad_axis * chunk_rad_axis,
-                chunk_rad_axis,
-                chunk_rad_axis,
-                start.elapsed(),
-                vec.len() as i32 * chunk_rad_axis * chunk_rad_axis / 1024,
-                vec.len() as i32 / 1024
-            );
+            "Sent {} chunks to player in {:?}. Approximately {} kb of data",
+            chunks.len() - not_sent.len(),
+            start.elapsed(),
+            bytes_sent / 1024
+        );
+
+        Ok(())
+    }
 
+    /// Tells the client to unload `chunks`. They stay in the chunk cache until it needs the
+    /// room, in case someone comes back for them.
+    async fn unload_chunks(
+        chunks: &[(i32, i32)],
+        conn: Arc<RwLock<Connection>>,
+    ) -> Result<()> {
+        if chunks.is_empty() {
+            return Ok(());
+        }
+        let conn = conn.read().await;
+        for &(x, z) in chunks {
+            conn.send_packet(UnloadChunk::new(x, z)).await?;
+        }
         Ok(())
     }
-    async fn send_set_center_chunk(pos: &Position, conn: Arc<RwLock<Connection>>) -> Result<()> {
-        let packet = SetCenterChunk::new(pos.x >> 4, pos.z >> 4);
+
+    async fn send_set_center_chunk(
+        (chunk_x, chunk_z): (i32, i32),
+        conn: Arc<RwLock<Connection>>,
+    ) -> Result<()> {
+        let packet = SetCenterChunk::new(chunk_x, chunk_z);
 
         let read_guard = conn.read().await;
 
@@ -184,3 +312,4 @@ impl ChunkSender {
         Ok(())
     }
 }
+
@@ -1,9 +1,19 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
 use crate::net::systems::System;
+use crate::net::utils::proxy_protocol::read_proxy_header;
+use crate::net::utils::socket_options::{apply_socket_options, log_socket_options};
 use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
 use crate::utils::prelude::*;
 use async_trait::async_trait;
 use ferrumc_macros::AutoGenName;
-use tracing::{debug, error, info_span, Instrument};
+use tokio::net::TcpListener;
+use tracing::{debug, error, info_span, warn, Instrument};
+
+/// How long a load balancer gets to send the PROXY header after connecting.
+const PROXY_HEADER_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(AutoGenName)]
 pub struct ConnectionHandler;
@@ -13,9 +23,13 @@ impl System for ConnectionHandler {
     async fn run(&self, state: GlobalState) {
         debug!("ConnectionHandler is starting up");
 
-        if let Err(e) = Self::handle_connections(state).await {
-            error!("There was an error in the ConnectionHandler: {:?}", e);
-        }
+        // One accept loop per address, all handing connections off the same way.
+        let accept_loops = state.server_streams.iter().map(|listener| async {
+            if let Err(e) = Self::handle_connections(listener, state.clone()).await {
+                error!("There was an error in the ConnectionHandler: {:?}", e);
+            }
+        });
+        futures::future::join_all(accept_loops).await;
     }
 
     fn name(&self) -> &'static str {
@@ -24,20 +38,63 @@ impl System for ConnectionHandler {
 }
 
 impl ConnectionHandler {
-    async fn handle_connections(state: GlobalState) -> Result<()> {
+    async fn handle_connections(listener: &TcpListener, state: GlobalState) -> Result<()> {
+        let socket_options = &get_global_config().network;
+        log_socket_options(listener, socket_options);
+
         loop {
-            let (stream, _) = state.server_stream.accept().await?;
-            debug!("Accepted connection from {:?}", stream.peer_addr()?);
-            let addy = stream.peer_addr()?;
-            tokio::task::spawn(
-                Self::handle_connection(state.clone(), stream)
-                    .instrument(info_span!("conn", %addy).or_current()),
-            );
+            let (stream, addy) = tokio::select! {
+                accepted = listener.accept() => accepted?,
+                _ = state.shutdown.cancelled() => {
+                    debug!("No longer accepting connections");
+                    return Ok(());
+                }
+            };
+            debug!("Accepted connection from {:?}", addy);
+            if let Err(e) = apply_socket_options(&stream, socket_options) {
+                warn!("Failed to set socket options for {}: {}", addy, e);
+            }
+            state
+                .connection_tasks
+                .spawn(Self::handle_connection(state.clone(), stream, addy));
         }
     }
 
-    async fn handle_connection(state: GlobalState, stream: tokio::net::TcpStream) -> Result<()> {
-        crate::net::init_connection(stream, state).await?;
+    async fn handle_connection(
+        state: GlobalState,
+        mut stream: tokio::net::TcpStream,
+        mut addy: SocketAddr,
+    ) -> Result<()> {
+        if get_global_config().network.proxy_protocol {
+            match tokio::time::timeout(PROXY_HEADER_TIMEOUT, read_proxy_header(&mut stream)).await {
+                Ok(Ok(Some(real_addy))) => {
+                    debug!("Connection from {} is proxied for {}", addy, real_addy);
+                    addy = real_addy;
+                }
+                // The balancer's own connections, like health checks
+                Ok(Ok(None)) => {}
+                Ok(Err(e)) => {
+                    warn!("Dropping connection from {}: {}", addy, e);
+                    return Ok(());
+                }
+                Err(_) => {
+                    warn!(
+                        "Dropping connection from {}: no PROXY header was sent",
+                        addy
+                    );
+                    return Ok(());
+                }
+            }
+        }
+
+        if state.bans.ip_ban(addy.ip()).await.is_some() {
+            debug!("Dropping connection from banned IP {}", addy);
+            return Ok(());
+        }
+
+        crate::net::init_connection(stream, addy, state)
+            .instrument(info_span!("conn", %addy).or_current())
+            .await?;
         Ok(())
     }
 }
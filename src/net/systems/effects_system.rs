@@ -0,0 +1,26 @@
+use tracing::debug;
+
+use crate::net::utils::visibility::players_in_world;
+use crate::state::GlobalState;
+use crate::utils::components::active_effects::ActiveEffects;
+
+/// Counts everyone's effects down every tick, and takes them off once they run out.
+pub async fn tick(state: GlobalState) {
+    tick_effects(&state).await;
+}
+
+async fn tick_effects(state: &GlobalState) {
+    let component_storage = state.world.get_component_storage();
+    for conn_id in players_in_world(state).await {
+        let expired = match component_storage.get_mut::<ActiveEffects>(conn_id).await {
+            Ok(mut effects) => effects.tick(),
+            Err(_) => continue,
+        };
+        if expired.is_empty() {
+            continue;
+        }
+        if let Err(e) = state.send_removed_effects(conn_id, &expired).await {
+            debug!("Failed to remove the expired effects of {}: {}", conn_id, e);
+        }
+    }
+}
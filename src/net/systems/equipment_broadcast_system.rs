@@ -0,0 +1,61 @@
+use tracing::warn;
+
+use crate::net::packets::outgoing::set_equipment::SetEquipment;
+use crate::net::utils::visibility::viewers_of_entities;
+use crate::state::GlobalState;
+use crate::utils::components::equipment::Equipment;
+
+/// Shows players what the entities they can see changed in their hands or armor, once a tick.
+///
+/// Players that start seeing an entity are sent all of its equipment when it's spawned, see
+/// [crate::net::utils::visibility::update_visible_players].
+pub async fn tick(state: GlobalState) {
+    broadcast_equipment(&state).await;
+}
+
+async fn broadcast_equipment(state: &GlobalState) {
+    let changed = state
+        .world
+        .query::<&Equipment>()
+        .iter()
+        .await
+        .filter(|(_, equipment)| equipment.slots != equipment.sent)
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    if changed.is_empty() {
+        return;
+    }
+
+    let viewers = viewers_of_entities(state).await;
+    let component_storage = state.world.get_component_storage();
+    for entity_id in changed {
+        // Taken even when nobody's looking, since they'll get everything once they do.
+        let changes = match component_storage.get_mut::<Equipment>(entity_id).await {
+            Ok(mut equipment) => equipment.take_changes(),
+            Err(_) => continue,
+        };
+        let Some(viewers) = viewers.get(&entity_id) else {
+            continue;
+        };
+        if changes.is_empty() {
+            continue;
+        }
+        let Ok(network_id) = state.network_id(entity_id).await else {
+            continue;
+        };
+
+        for &viewer in viewers {
+            let Ok(conn) = state.connections.get_connection(viewer) else {
+                continue;
+            };
+            let conn = conn.read().await;
+            let packet = SetEquipment::new(network_id, changes.clone());
+            if let Err(e) = conn.send_packet(packet).await {
+                warn!(
+                    "Failed to send the equipment of {} to {}: {}",
+                    entity_id, viewer, e
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use tracing::debug;
+
+use crate::net::packets::ConnectionId;
+use crate::net::utils::damage::{DamageSource, VOID_DAMAGE, VOID_LEVEL};
+use crate::net::utils::visibility::players_in_world;
+use crate::state::GlobalState;
+use crate::utils::components::food::{Food, FoodEffect};
+use crate::utils::components::health::Health;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+
+/// How many ticks go by between the void hurting a player, same as vanilla's invulnerability
+/// time after being hurt.
+const VOID_DAMAGE_INTERVAL: u64 = 10;
+
+/// Runs everyone's food bar every tick, healing players that are fed and starving players that
+/// aren't, and hurts players that have fallen out of the world.
+pub async fn tick(state: GlobalState) {
+    let hurt_by_void = state
+        .scheduler
+        .current_tick()
+        .is_multiple_of(VOID_DAMAGE_INTERVAL);
+    for conn_id in players_in_world(&state).await {
+        if let Err(e) = tick_player(conn_id, hurt_by_void, &state).await {
+            debug!("Failed to tick the health of {}: {}", conn_id, e);
+        }
+    }
+}
+
+async fn tick_player(conn_id: ConnectionId, hurt_by_void: bool, state: &GlobalState) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+    let effect = {
+        let mut health = component_storage.get_mut::<Health>(conn_id).await?;
+        if health.is_dead() {
+            return Ok(());
+        }
+        let mut food = component_storage.get_mut::<Food>(conn_id).await?;
+        let effect = food.tick(&health);
+        if let FoodEffect::Heal(amount) = effect {
+            health.heal(amount);
+        }
+        effect
+    };
+    match effect {
+        FoodEffect::Nothing => {}
+        FoodEffect::Heal(_) => state.send_health(conn_id).await?,
+        FoodEffect::Starve(amount) => {
+            state
+                .damage_player(conn_id, amount, DamageSource::Starvation)
+                .await?
+        }
+    }
+
+    if hurt_by_void {
+        let y = component_storage.get::<Position>(conn_id).await?.y;
+        if y < VOID_LEVEL {
+            state
+                .damage_player(conn_id, VOID_DAMAGE, DamageSource::Void)
+                .await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,10 @@
+use crate::net::utils::dropped_items::{age_items, pick_up_items, update_visible_items};
+use crate::state::GlobalState;
+
+/// Despawns items that have been lying around for too long, has players pick up the ones
+/// they're close to, and sends them the ones they can see, once a tick.
+pub async fn tick(state: GlobalState) {
+    age_items(&state).await;
+    pick_up_items(&state).await;
+    update_visible_items(&state).await;
+}
@@ -1,14 +1,36 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use futures::stream::FuturesUnordered;
-use tracing::{debug_span, info, Instrument};
+use tracing::{debug, debug_span, info, Instrument};
 
 use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
 use crate::utils::prelude::*;
+use crate::utils::scheduler::Scheduler;
 
+pub mod block_change_system;
+pub mod chunk_cache_system;
+pub mod chunk_loader;
 pub mod chunk_sender;
 pub mod connection_handler;
+pub mod effects_system;
+pub mod equipment_broadcast_system;
+pub mod health_system;
+pub mod item_system;
 pub mod keep_alive_system;
+pub mod movement_broadcast_system;
+pub mod physics_system;
+pub mod player_list_system;
+pub mod query_system;
+pub mod rcon_system;
+pub mod save_system;
+pub mod sidebar_system;
+pub mod tab_list_system;
 pub mod tick_system;
+pub mod time_system;
+pub mod tps_boss_bar_system;
+pub mod weather_system;
 
 #[async_trait]
 pub trait System: Send + Sync {
@@ -19,20 +41,82 @@ pub trait System: Send + Sync {
 
 pub static ALL_SYSTEMS: &[&dyn System] = &[
     &tick_system::TickSystem,
-    &keep_alive_system::KeepAliveSystem,
+    &chunk_loader::ChunkLoaderSystem,
     &chunk_sender::ChunkSender,
     &connection_handler::ConnectionHandler,
+    &query_system::QuerySystem,
+    &rcon_system::RconSystem,
 ];
 
+/// Registers the systems that run on the game's ticks, see [Scheduler].
+pub fn register_tick_systems(scheduler: &Scheduler) {
+    let config = get_global_config();
+    // Every 0 seconds would be every tick, which nobody means.
+    let keep_alive_interval = Duration::from_secs(config.keep_alive_interval.max(1));
+    let tab_list_interval = Duration::from_secs(config.tab_list.refresh_interval.max(1));
+    let save_interval = Duration::from_secs(config.database.save_interval.max(1));
+
+    scheduler.register("time", 1, time_system::tick);
+    scheduler.register("weather", 1, weather_system::tick);
+    scheduler.register("health", 1, health_system::tick);
+    scheduler.register("effects", 1, effects_system::tick);
+    scheduler.register("block_changes", 1, block_change_system::tick);
+    scheduler.register("physics", 1, physics_system::tick);
+    scheduler.register("items", 1, item_system::tick);
+    scheduler.register("movement_broadcast", 1, movement_broadcast_system::tick);
+    scheduler.register("equipment_broadcast", 1, equipment_broadcast_system::tick);
+    scheduler.register(
+        "keep_alive",
+        scheduler.ticks_in(keep_alive_interval),
+        keep_alive_system::tick,
+    );
+    scheduler.register(
+        "player_list",
+        scheduler.ticks_in(keep_alive_interval),
+        player_list_system::tick,
+    );
+    scheduler.register(
+        "tab_list",
+        scheduler.ticks_in(tab_list_interval),
+        tab_list_system::tick,
+    );
+    scheduler.register(
+        "chunk_cache",
+        scheduler.ticks_in(Duration::from_secs(1)),
+        chunk_cache_system::tick,
+    );
+    scheduler.register("save", scheduler.ticks_in(save_interval), save_system::tick);
+    if config.debug.tps_boss_bar {
+        scheduler.register(
+            "tps_boss_bar",
+            scheduler.ticks_in(Duration::from_secs(1)),
+            tps_boss_bar_system::tick,
+        );
+    }
+    if config.debug.player_count_sidebar {
+        scheduler.register(
+            "sidebar",
+            scheduler.ticks_in(Duration::from_secs(1)),
+            sidebar_system::tick,
+        );
+    }
+}
+
 pub async fn start_all_systems(state: GlobalState) -> Result<()> {
     let handles = FuturesUnordered::new();
     for system in ALL_SYSTEMS {
         let name = system.name();
 
+        let state = state.clone();
         let handle = tokio::spawn(
-            system
-                .run(state.clone())
-                .instrument(debug_span!("sys", %name)),
+            async move {
+                // Systems loop forever, so they're stopped from out here once shutdown starts.
+                tokio::select! {
+                    _ = system.run(state.clone()) => {}
+                    _ = state.shutdown.cancelled() => debug!("Stopping system"),
+                }
+            }
+            .instrument(debug_span!("sys", %name)),
         );
         handles.push(handle);
     }
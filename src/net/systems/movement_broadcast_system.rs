@@ -0,0 +1,82 @@
+use tracing::warn;
+
+use crate::net::utils::entity_movement::encode_movement;
+use crate::net::utils::visibility::viewers_of_entities;
+use crate::state::GlobalState;
+use crate::utils::components::exact_position::ExactPosition;
+use crate::utils::components::grounded::Grounded;
+use crate::utils::components::last_sent_movement::LastSentMovement;
+use crate::utils::components::physics_body::PhysicsBody;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::encoding::position::Position;
+
+/// Shows players how the entities they can see moved, once a tick.
+///
+/// Clients can send movement packets as fast as they like, so they're only ever relayed this
+/// often, however many came in. Players are sent exactly where their [ExactPosition] is, and
+/// entities with a [PhysicsBody] exactly where it is. Everything else is sent the block it's in.
+pub async fn tick(state: GlobalState) {
+    broadcast_movement(&state).await;
+}
+
+async fn broadcast_movement(state: &GlobalState) {
+    let viewers = viewers_of_entities(state).await;
+
+    let mut moved = state
+        .world
+        .query::<(&Position, &Rotation, &LastSentMovement)>()
+        .iter()
+        .await
+        .map(|(id, (position, rotation, last))| {
+            (id, *last, LastSentMovement::new(&position, &rotation))
+        })
+        .collect::<Vec<_>>();
+
+    let component_storage = state.world.get_component_storage();
+    for (entity_id, _, now) in &mut moved {
+        if let Ok(body) = component_storage.get::<PhysicsBody>(*entity_id).await {
+            now.position = body.position;
+        } else if let Ok(exact) = component_storage.get::<ExactPosition>(*entity_id).await {
+            now.position = exact.0;
+        }
+    }
+
+    for (entity_id, last, now) in moved.into_iter().filter(|(_, last, now)| last != now) {
+        if let Ok(mut last_sent) = component_storage
+            .get_mut::<LastSentMovement>(entity_id)
+            .await
+        {
+            *last_sent = now;
+        }
+        let Some(viewers) = viewers.get(&entity_id) else {
+            continue;
+        };
+        let on_ground = component_storage
+            .get::<Grounded>(entity_id)
+            .await
+            .is_ok_and(|grounded| grounded.is_grounded);
+        let Ok(network_id) = state.network_id(entity_id).await else {
+            continue;
+        };
+        let packets = match encode_movement(network_id, &last, &now, on_ground).await {
+            Ok(packets) => packets,
+            Err(e) => {
+                warn!("Failed to encode the movement of {}: {}", entity_id, e);
+                continue;
+            }
+        };
+
+        for &viewer in viewers {
+            let Ok(conn) = state.connections.get_connection(viewer) else {
+                continue;
+            };
+            let conn = conn.read().await;
+            if let Err(e) = conn.send_packet(packets.clone()).await {
+                warn!(
+                    "Failed to send the movement of {} to {}: {}",
+                    entity_id, viewer, e
+                );
+            }
+        }
+    }
+}
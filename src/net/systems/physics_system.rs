@@ -0,0 +1,105 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use tracing::debug;
+
+use crate::state::GlobalState;
+use crate::utils::components::grounded::Grounded;
+use crate::utils::components::physics_body::PhysicsBody;
+use crate::utils::components::player::Player;
+use crate::utils::components::velocity::Velocity;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::world::chunk_format::Chunk;
+use crate::world::physics::{self, BoundingBox};
+
+/// Chunks already looked up this tick, by dimension and then by chunk. `None` if the chunk
+/// hasn't been made yet.
+type ChunkCache = HashMap<String, HashMap<(i32, i32), Option<Chunk>>>;
+
+/// Moves every entity with a [PhysicsBody] by its [Velocity], once a tick, see
+/// [physics::step]. Players' clients move them, so they're left alone.
+pub async fn tick(state: GlobalState) {
+    let bodies = state
+        .world
+        .query::<(&PhysicsBody, &Velocity)>()
+        .iter()
+        .await
+        .map(|(id, (body, velocity))| (id, *body, *velocity))
+        .collect::<Vec<_>>();
+
+    let mut chunks = ChunkCache::new();
+    for (entity_id, body, velocity) in bodies {
+        if state.world.get_component::<Player>(entity_id).await.is_ok() {
+            continue;
+        }
+        if let Err(e) = tick_entity(entity_id, &body, &velocity, &mut chunks, &state).await {
+            debug!("Failed to move entity {}: {}", entity_id, e);
+        }
+    }
+}
+
+async fn tick_entity(
+    entity_id: usize,
+    body: &PhysicsBody,
+    velocity: &Velocity,
+    chunks: &mut ChunkCache,
+    state: &GlobalState,
+) -> Result<()> {
+    let dimension = state.dimension_of(entity_id).await;
+    let chunks = chunks.entry(dimension.key.clone()).or_default();
+
+    // Every chunk it could move through this tick.
+    let bounds =
+        BoundingBox::of(body).expanded_by((velocity.x, velocity.y - body.gravity, velocity.z));
+    let (min_x, max_x) = (
+        bounds.min[0].floor() as i32 >> 4,
+        bounds.max[0].floor() as i32 >> 4,
+    );
+    let (min_z, max_z) = (
+        bounds.min[2].floor() as i32 >> 4,
+        bounds.max[2].floor() as i32 >> 4,
+    );
+    for chunk_x in min_x..=max_x {
+        for chunk_z in min_z..=max_z {
+            if let Entry::Vacant(entry) = chunks.entry((chunk_x, chunk_z)) {
+                let chunk = state
+                    .database
+                    .get_chunk(chunk_x, chunk_z, dimension.key.clone())
+                    .await?;
+                entry.insert(chunk);
+            }
+        }
+    }
+    // Entities in chunks that haven't been made yet stay put until they are, like vanilla.
+    let all_loaded = (min_x..=max_x)
+        .all(|chunk_x| (min_z..=max_z).all(|chunk_z| chunks[&(chunk_x, chunk_z)].is_some()));
+    if !all_loaded {
+        return Ok(());
+    }
+
+    let is_solid = |x: i32, y: i32, z: i32| match chunks.get(&(x >> 4, z >> 4)) {
+        // Blocks above and below the world aren't anything.
+        Some(Some(chunk)) => chunk
+            .get_block(x, y, z)
+            .is_ok_and(|block| physics::is_solid(&block)),
+        // Its velocity was changed since the chunks were looked up, so it's stopped at the edge of
+        // them until next tick.
+        _ => true,
+    };
+
+    let component_storage = state.world.get_component_storage();
+    let body = {
+        let mut body = component_storage.get_mut::<PhysicsBody>(entity_id).await?;
+        let mut velocity = component_storage.get_mut::<Velocity>(entity_id).await?;
+        physics::step(&mut body, &mut velocity, is_solid);
+        *body
+    };
+
+    let (x, y, z) = body.position;
+    let position = Position::new(x.floor() as i32, y.floor() as i16, z.floor() as i32);
+    component_storage
+        .insert(entity_id, position)
+        .insert(entity_id, Grounded::new(body.on_ground));
+    Ok(())
+}
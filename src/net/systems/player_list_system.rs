@@ -0,0 +1,8 @@
+use crate::net::utils::player_list::broadcast_latency;
+use crate::state::GlobalState;
+
+/// Updates everyone's ping in the tab list every `keep_alive_interval` seconds, since that's how
+/// often it's measured.
+pub async fn tick(state: GlobalState) {
+    broadcast_latency(&state).await;
+}
@@ -0,0 +1,214 @@
+use std::net::SocketAddr;
+
+use async_trait::async_trait;
+use tokio::net::UdpSocket;
+use tracing::{debug, error, info, trace};
+
+use ferrumc_macros::AutoGenName;
+
+use crate::net::systems::System;
+use crate::net::utils::query::{
+    basic_stat_response, full_stat_response, handshake_response, ChallengeTokens, QueryRequest,
+    ServerInfo, CHALLENGE_TOKEN_LIFETIME,
+};
+use crate::state::GlobalState;
+use crate::utils::components::player::Player;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+/// The largest request we care about is 15 bytes, anything longer is cut off and still parsed.
+const MAX_REQUEST_SIZE: usize = 64;
+
+/// Answers GameSpy4 queries over UDP, which server lists and hosting panels use to show the
+/// player count and who's online.
+///
+/// Only runs if `query.enabled` is set. Listens on the same IPs as the server, on `query.port`.
+#[derive(AutoGenName)]
+pub struct QuerySystem;
+
+#[async_trait]
+impl System for QuerySystem {
+    async fn run(&self, state: GlobalState) {
+        let config = &get_global_config().query;
+        if !config.enabled {
+            return;
+        }
+        let port = match config.port {
+            0 => get_global_config().port,
+            port => port,
+        };
+
+        let mut sockets = Vec::new();
+        for listener in &state.server_streams {
+            let Ok(local_addr) = listener.local_addr() else {
+                continue;
+            };
+            let addr = SocketAddr::new(local_addr.ip(), port as u16);
+            match UdpSocket::bind(addr).await {
+                Ok(socket) => {
+                    info!("Answering queries on {}", addr);
+                    sockets.push(socket);
+                }
+                Err(e) => error!("Failed to bind the query socket to {}: {}", addr, e),
+            }
+        }
+
+        let query_loops = sockets.into_iter().map(|socket| async {
+            if let Err(e) = answer_queries(socket, state.clone()).await {
+                error!("There was an error in the QuerySystem: {:?}", e);
+            }
+        });
+        futures::future::join_all(query_loops).await;
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
+
+/// Answers every query sent to `socket`. Invalid requests and ones with a wrong or expired
+/// challenge token are ignored, like vanilla does.
+async fn answer_queries(socket: UdpSocket, state: GlobalState) -> Result<()> {
+    let mut tokens = ChallengeTokens::new(CHALLENGE_TOKEN_LIFETIME);
+    let mut buffer = [0u8; MAX_REQUEST_SIZE];
+
+    loop {
+        let (length, addr) = match socket.recv_from(&mut buffer).await {
+            Ok(received) => received,
+            // On Windows, an ICMP "port unreachable" from an earlier reply shows up here.
+            Err(e) if e.kind() == std::io::ErrorKind::ConnectionReset => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let Some(request) = QueryRequest::parse(&buffer[..length]) else {
+            trace!("Ignoring invalid query from {}", addr);
+            continue;
+        };
+
+        let response = match request {
+            QueryRequest::Handshake { session_id } => {
+                handshake_response(session_id, tokens.issue(addr))
+            }
+            QueryRequest::BasicStat { session_id, token } if tokens.is_valid(addr, token) => {
+                basic_stat_response(session_id, &server_info(&socket, &state).await?)
+            }
+            QueryRequest::FullStat { session_id, token } if tokens.is_valid(addr, token) => {
+                full_stat_response(session_id, &server_info(&socket, &state).await?)
+            }
+            _ => {
+                debug!(
+                    "Ignoring query with an invalid challenge token from {}",
+                    addr
+                );
+                continue;
+            }
+        };
+
+        if let Err(e) = socket.send_to(&response, addr).await {
+            debug!("Failed to answer query from {}: {}", addr, e);
+        }
+    }
+}
+
+async fn server_info(socket: &UdpSocket, state: &GlobalState) -> Result<ServerInfo> {
+    let config = get_global_config();
+    let players = state
+        .world
+        .query::<&Player>()
+        .iter()
+        .await
+        .map(|(_, player)| player.username.clone())
+        .collect();
+
+    Ok(ServerInfo {
+        motd: config.motd.first().cloned().unwrap_or_default(),
+        map: config.world.clone(),
+        players,
+        max_players: config.max_players,
+        host_port: config.port as u16,
+        host_ip: socket.local_addr()?.ip().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::create_state;
+
+    const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+    async fn request(client: &UdpSocket, kind: u8, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0xFE, 0xFD, kind];
+        packet.extend_from_slice(&7i32.to_be_bytes());
+        packet.extend_from_slice(payload);
+        client.send(&packet).await.unwrap();
+
+        let mut buffer = [0u8; 1024];
+        let length = tokio::time::timeout(RESPONSE_TIMEOUT, client.recv(&mut buffer))
+            .await
+            .expect("no response to the query")
+            .unwrap();
+        buffer[..length].to_vec()
+    }
+
+    /// Splits the null terminated strings in `data`.
+    fn strings(data: &[u8]) -> Vec<String> {
+        data.split(|&b| b == 0)
+            .map(|s| String::from_utf8_lossy(s).into_owned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_query_protocol() {
+        let state = create_state(vec![TcpListener::bind("127.0.0.1:0").await.unwrap()])
+            .await
+            .unwrap();
+        state
+            .world
+            .create_entity()
+            .await
+            .with(Player::new(1, "Notch".to_string()))
+            .build();
+
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        client.connect(server.local_addr().unwrap()).await.unwrap();
+        tokio::spawn(answer_queries(server, state));
+
+        // Handshake
+        let response = request(&client, 0x09, &[]).await;
+        assert_eq!(response[..5], [0x09, 0, 0, 0, 7]);
+        let token: i32 = strings(&response[5..])[0].parse().unwrap();
+
+        // Basic stat
+        let response = request(&client, 0x00, &token.to_be_bytes()).await;
+        assert_eq!(response[..5], [0x00, 0, 0, 0, 7]);
+        let fields = strings(&response[5..]);
+        assert_eq!(fields[1], "SMP");
+        assert_eq!(fields[2], get_global_config().world);
+        assert_eq!(fields[3], "1");
+        assert_eq!(fields[4], get_global_config().max_players.to_string());
+
+        // Full stat
+        let mut payload = token.to_be_bytes().to_vec();
+        payload.extend_from_slice(&[0; 4]);
+        let response = request(&client, 0x00, &payload).await;
+        let fields = strings(&response[5 + 11..]);
+        let numplayers = fields.iter().position(|f| f == "numplayers").unwrap();
+        assert_eq!(fields[numplayers + 1], "1");
+        let players = fields.iter().position(|f| f == "\u{1}player_").unwrap();
+        assert_eq!(fields[players + 2], "Notch");
+
+        // A wrong token doesn't get an answer.
+        let mut packet = vec![0xFE, 0xFD, 0x00];
+        packet.extend_from_slice(&7i32.to_be_bytes());
+        packet.extend_from_slice(&token.wrapping_add(1).to_be_bytes());
+        client.send(&packet).await.unwrap();
+        let mut buffer = [0u8; 1024];
+        let answer = tokio::time::timeout(Duration::from_millis(200), client.recv(&mut buffer));
+        assert!(answer.await.is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+
+use ferrumc_macros::AutoGenName;
+
+use crate::net::systems::System;
+use crate::net::utils::rcon::{
+    password_matches, split_response, AuthFailures, RconPacket, AUTH_FAILED_ID, TYPE_AUTH,
+    TYPE_AUTH_RESPONSE, TYPE_EXEC_COMMAND, TYPE_RESPONSE_VALUE,
+};
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+/// How many wrong passwords an IP can send before it's locked out.
+const MAX_AUTH_FAILURES: u32 = 3;
+/// How long an IP is locked out for, counting from its first failed login.
+const AUTH_FAILURE_WINDOW: Duration = Duration::from_secs(60);
+/// How long a new connection has to log in.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a logged in connection can go without sending a command before it's closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Lets admins run commands remotely over the Source RCON protocol.
+///
+/// Only runs if `rcon.enabled` is set and there's a password. Listens on the same IPs as the
+/// server, on `rcon.port`.
+#[derive(AutoGenName)]
+pub struct RconSystem;
+
+#[async_trait]
+impl System for RconSystem {
+    async fn run(&self, state: GlobalState) {
+        let config = &get_global_config().rcon;
+        if !config.enabled {
+            return;
+        }
+        if config.password.is_empty() {
+            error!("RCON is enabled, but no password is set. Not starting it.");
+            return;
+        }
+
+        let mut listeners = Vec::new();
+        for server_stream in &state.server_streams {
+            let Ok(local_addr) = server_stream.local_addr() else {
+                continue;
+            };
+            let addr = SocketAddr::new(local_addr.ip(), config.port as u16);
+            match TcpListener::bind(addr).await {
+                Ok(listener) => {
+                    info!("RCON listening on {}", addr);
+                    listeners.push(listener);
+                }
+                Err(e) => error!("Failed to bind RCON to {}: {}", addr, e),
+            }
+        }
+
+        let failures = Arc::new(Mutex::new(AuthFailures::new(
+            MAX_AUTH_FAILURES,
+            AUTH_FAILURE_WINDOW,
+        )));
+        let accept_loops = listeners.into_iter().map(|listener| {
+            serve_rcon(listener, state.clone(), &config.password, failures.clone())
+        });
+        futures::future::join_all(accept_loops).await;
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
+
+async fn serve_rcon(
+    listener: TcpListener,
+    state: GlobalState,
+    password: &'static str,
+    failures: Arc<Mutex<AuthFailures>>,
+) {
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                error!("Failed to accept RCON connection: {}", e);
+                continue;
+            }
+        };
+
+        // Checked again when it tries to log in, this just saves reading anything.
+        if failures.lock().await.is_locked_out(addr.ip()) {
+            debug!("Dropping RCON connection from locked out IP {}", addr);
+            continue;
+        }
+
+        let state = state.clone();
+        let failures = failures.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_rcon_connection(stream, addr, state, password, failures).await {
+                debug!("RCON connection from {} closed: {}", addr, e);
+            }
+        });
+    }
+}
+
+/// The client has to log in with its first packet, and can run commands after that. A wrong
+/// password closes the connection.
+async fn handle_rcon_connection(
+    mut stream: TcpStream,
+    addr: SocketAddr,
+    state: GlobalState,
+    password: &str,
+    failures: Arc<Mutex<AuthFailures>>,
+) -> Result<()> {
+    let login = read_packet(&mut stream, LOGIN_TIMEOUT).await?;
+    {
+        // Held from the check to the failure being recorded, so connections that were all
+        // opened before the lockout can't each get a guess in at the same time.
+        let mut failures = failures.lock().await;
+        if failures.is_locked_out(addr.ip()) {
+            debug!("Turning away RCON login from locked out IP {}", addr);
+            return Ok(());
+        }
+        if login.kind != TYPE_AUTH || !password_matches(&login.body, password) {
+            warn!("Failed RCON login from {}", addr);
+            failures.record_failure(addr.ip());
+            drop(failures);
+            RconPacket::new(AUTH_FAILED_ID, TYPE_AUTH_RESPONSE, "")
+                .write(&mut stream)
+                .await?;
+            return Ok(());
+        }
+    }
+
+    info!("RCON connection from {} logged in", addr);
+    RconPacket::new(login.request_id, TYPE_AUTH_RESPONSE, "")
+        .write(&mut stream)
+        .await?;
+
+    loop {
+        let request = read_packet(&mut stream, IDLE_TIMEOUT).await?;
+        let responses = match request.kind {
+            TYPE_EXEC_COMMAND => {
+                info!("RCON {} ran command: {}", addr, request.body);
+                let output = state.execute_command(&request.body).await;
+                split_response(request.request_id, &output)
+            }
+            kind => vec![RconPacket::new(
+                request.request_id,
+                TYPE_RESPONSE_VALUE,
+                format!("Unknown request {:x}", kind),
+            )],
+        };
+        for response in responses {
+            response.write(&mut stream).await?;
+        }
+    }
+}
+
+/// Reads the next packet, giving up on the client if it doesn't send one within `timeout`.
+async fn read_packet(stream: &mut TcpStream, timeout: Duration) -> Result<RconPacket> {
+    tokio::time::timeout(timeout, RconPacket::read(stream))
+        .await
+        .map_err(|_| Error::RconError("Timed out waiting for a packet".to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_state;
+
+    const PASSWORD: &str = "hunter2";
+
+    async fn start_rcon(max_failures: u32) -> SocketAddr {
+        let state = create_state(vec![TcpListener::bind("127.0.0.1:0").await.unwrap()])
+            .await
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let failures = Arc::new(Mutex::new(AuthFailures::new(
+            max_failures,
+            AUTH_FAILURE_WINDOW,
+        )));
+        tokio::spawn(serve_rcon(listener, state, PASSWORD, failures));
+        addr
+    }
+
+    async fn login(addr: SocketAddr, password: &str) -> (TcpStream, RconPacket) {
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        RconPacket::new(1, TYPE_AUTH, password)
+            .write(&mut client)
+            .await
+            .unwrap();
+        let response = RconPacket::read(&mut client).await.unwrap();
+        (client, response)
+    }
+
+    #[tokio::test]
+    async fn test_login_and_run_command() {
+        let addr = start_rcon(MAX_AUTH_FAILURES).await;
+        let (mut client, response) = login(addr, PASSWORD).await;
+        assert_eq!(response, RconPacket::new(1, TYPE_AUTH_RESPONSE, ""));
+
+        RconPacket::new(2, TYPE_EXEC_COMMAND, "list")
+            .write(&mut client)
+            .await
+            .unwrap();
+        let response = RconPacket::read(&mut client).await.unwrap();
+        assert_eq!(response.request_id, 2);
+        assert_eq!(response.kind, TYPE_RESPONSE_VALUE);
+        assert!(response.body.starts_with("There are 0 players online"));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_password_closes_and_locks_out() {
+        let addr = start_rcon(2).await;
+
+        for _ in 0..2 {
+            let (mut client, response) = login(addr, "wrong").await;
+            assert_eq!(response.request_id, AUTH_FAILED_ID);
+            // The server hung up.
+            assert!(RconPacket::read(&mut client).await.is_err());
+        }
+
+        // Locked out now, even with the right password.
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        RconPacket::new(1, TYPE_AUTH, PASSWORD)
+            .write(&mut client)
+            .await
+            .ok();
+        assert!(RconPacket::read(&mut client).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connections_opened_before_a_lockout_are_locked_out() {
+        let addr = start_rcon(2).await;
+        // All connected before any of them have failed.
+        let mut clients = Vec::new();
+        for _ in 0..3 {
+            clients.push(TcpStream::connect(addr).await.unwrap());
+        }
+        let mut right = clients.pop().unwrap();
+        for mut client in clients {
+            RconPacket::new(1, TYPE_AUTH, "wrong")
+                .write(&mut client)
+                .await
+                .unwrap();
+            let response = RconPacket::read(&mut client).await.unwrap();
+            assert_eq!(response.request_id, AUTH_FAILED_ID);
+        }
+
+        RconPacket::new(1, TYPE_AUTH, PASSWORD)
+            .write(&mut right)
+            .await
+            .ok();
+        assert!(RconPacket::read(&mut right).await.is_err());
+    }
+}
@@ -0,0 +1,23 @@
+use std::sync::atomic::Ordering;
+
+use tracing::{debug, error};
+
+use crate::state::GlobalState;
+
+/// Saves the world every `database.save_interval` seconds, so a crash only loses what changed
+/// since the last save.
+///
+/// Saving can take longer than a tick, so it's done in the background. If the last save still
+/// hasn't finished, this one's skipped, and what's changed since goes in the next.
+pub async fn tick(state: GlobalState) {
+    if state.saving.swap(true, Ordering::AcqRel) {
+        debug!("Skipping a save, the last one is still going");
+        return;
+    }
+    tokio::spawn(async move {
+        if let Err(e) = state.save_all().await {
+            error!("Failed to save the world: {:?}", e);
+        }
+        state.saving.store(false, Ordering::Release);
+    });
+}
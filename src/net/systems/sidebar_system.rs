@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::net::packets::outgoing::display_objective::DisplaySlot;
+use crate::net::utils::visibility::players_in_world;
+use crate::state::GlobalState;
+use crate::utils::scoreboard::Scoreboard;
+
+/// Made on the first tick, since it needs the state.
+static BOARD: Mutex<Option<Scoreboard>> = Mutex::const_new(None);
+
+/// Shows everyone a sidebar with how many players are online, every second.
+///
+/// Only registered if `debug.player_count_sidebar` is set.
+pub async fn tick(state: GlobalState) {
+    let mut board = BOARD.lock().await;
+    let board = match board.as_mut() {
+        Some(board) => board,
+        None => {
+            let mut new_board = Scoreboard::new(state.clone(), "ferrumc_debug", "FerrumC");
+            new_board.set_display_slot(Some(DisplaySlot::Sidebar)).await;
+            board.insert(new_board)
+        }
+    };
+
+    let players = players_in_world(&state).await;
+    // Players that joined since the last update.
+    for &conn_id in &players {
+        if let Err(e) = board.add_viewer(conn_id).await {
+            debug!("Failed to show the sidebar to {}: {}", conn_id, e);
+        }
+    }
+
+    let online = players.len().try_into().unwrap_or(i32::MAX);
+    board
+        .set_scores(HashMap::from([("Online".to_string(), online)]))
+        .await;
+}
@@ -0,0 +1,8 @@
+use crate::net::utils::tab_list::refresh_tab_list;
+use crate::state::GlobalState;
+
+/// Sends everyone the tab list header and footer every `tab_list.refresh_interval` seconds, so
+/// the placeholders in them stay up to date.
+pub async fn tick(state: GlobalState) {
+    refresh_tab_list(&state).await;
+}
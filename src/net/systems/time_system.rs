@@ -0,0 +1,19 @@
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+
+/// How many ticks go by between sending everyone the time, so about once a second. The client
+/// moves the sun along by itself in between.
+const BROADCAST_INTERVAL: i64 = 20;
+
+/// Moves every dimension's time on every tick, and keeps everyone's sky in sync with it.
+///
+/// The time of day only moves while `game_rules.do_daylight_cycle` is on.
+pub async fn tick(state: GlobalState) {
+    let daylight_cycle = get_global_config().game_rules.do_daylight_cycle;
+    for dimension in state.dimensions.iter() {
+        dimension.time.tick(daylight_cycle);
+    }
+    if state.dimensions.overworld().time.get().age % BROADCAST_INTERVAL == 0 {
+        state.broadcast_time().await;
+    }
+}
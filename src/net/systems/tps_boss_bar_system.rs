@@ -0,0 +1,48 @@
+use tokio::sync::Mutex;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::net::packets::outgoing::boss_bar::{BossBarColor, BossBarDivision};
+use crate::net::utils::visibility::players_in_world;
+use crate::state::GlobalState;
+use crate::utils::boss_bar::BossBar;
+use crate::utils::text_component::TextComponent;
+
+/// Made on the first tick, since it needs the state.
+static BAR: Mutex<Option<BossBar>> = Mutex::const_new(None);
+
+/// Shows everyone the server's ticks per second in a boss bar, every second.
+///
+/// Only registered if `debug.tps_boss_bar` is set.
+pub async fn tick(state: GlobalState) {
+    let mut bar = BAR.lock().await;
+    let bar = bar.get_or_insert_with(|| {
+        BossBar::new(
+            state.clone(),
+            Uuid::new_v4().as_u128(),
+            "TPS",
+            BossBarColor::Green,
+            BossBarDivision::Twenty,
+        )
+    });
+    // Players that joined since the last update.
+    for conn_id in players_in_world(&state).await {
+        if let Err(e) = bar.add_viewer(conn_id).await {
+            debug!("Failed to show the TPS boss bar to {}: {}", conn_id, e);
+        }
+    }
+
+    let target = state.scheduler.target_ticks_per_second();
+    let tps = state.tick_rate.ticks_per_second();
+    let color = match tps / target {
+        ratio if ratio >= 0.9 => BossBarColor::Green,
+        ratio if ratio >= 0.75 => BossBarColor::Yellow,
+        _ => BossBarColor::Red,
+    };
+    bar.set_style(color, BossBarDivision::Twenty).await;
+    bar.set_progress((tps / target) as f32).await;
+    let title = TextComponent::new(format!("TPS: {:.1}", tps));
+    if let Err(e) = bar.set_title(title).await {
+        debug!("Failed to update the TPS boss bar: {}", e);
+    }
+}
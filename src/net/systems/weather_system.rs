@@ -0,0 +1,30 @@
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::world_events::WeatherChangeEvent;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+
+/// Moves every dimension's weather on every tick, and tells the players in each one as it starts
+/// or stops raining and thundering.
+///
+/// The weather only changes by itself while `game_rules.do_weather_cycle` is on. Dimensions
+/// without a sky, see [Dimension::has_sky_light](crate::world::dimensions::Dimension::has_sky_light),
+/// don't have any.
+pub async fn tick(state: GlobalState) {
+    let weather_cycle = get_global_config().game_rules.do_weather_cycle;
+    for dimension in state.dimensions.iter() {
+        if !dimension.has_sky_light() {
+            continue;
+        }
+        let (before, after) = dimension.weather.tick(weather_cycle);
+        state.broadcast_weather(dimension, &before, &after).await;
+        if before.weather != after.weather {
+            let event = WeatherChangeEvent::new(
+                dimension.clone(),
+                before.weather,
+                after.weather,
+                after.remaining,
+            );
+            state.dispatch_event(event).await;
+        }
+    }
+}
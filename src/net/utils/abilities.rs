@@ -0,0 +1,88 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use tracing::warn;
+
+use crate::net::packets::outgoing::player_abilities::PlayerAbilities;
+use crate::net::packets::ConnectionId;
+use crate::net::utils::metadata::broadcast_player_metadata;
+use crate::net::utils::visibility::players_in_world;
+use crate::state::ServerState;
+use crate::utils::components::flying::Flying;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+impl ServerState {
+    /// Whether players in `game_mode` may fly, either because the gamemode lets them or because
+    /// flight is allowed for everyone, see [ServerState::set_allow_flight].
+    pub fn may_fly(&self, game_mode: GameMode) -> bool {
+        game_mode.can_fly() || self.allow_flight.load(Ordering::Relaxed)
+    }
+
+    /// The abilities a player in `game_mode` has, with the speeds from the config's `abilities`.
+    pub fn abilities(&self, game_mode: GameMode, is_flying: bool) -> PlayerAbilities {
+        let config = &get_global_config().abilities;
+        PlayerAbilities {
+            flying_speed: config.flying_speed,
+            fov_modifier: config.walking_speed,
+            ..PlayerAbilities::new(game_mode, self.may_fly(game_mode), is_flying)
+        }
+    }
+
+    /// Sends a player what they're allowed to do in their gamemode, and whether they're flying.
+    pub async fn send_abilities(&self, conn_id: ConnectionId) -> Result<()> {
+        let component_storage = self.world.get_component_storage();
+        let game_mode = component_storage
+            .get::<GameMode>(conn_id)
+            .await
+            .map(|game_mode| *game_mode)
+            .unwrap_or_default();
+        let is_flying = component_storage
+            .get::<Flying>(conn_id)
+            .await
+            .is_ok_and(|flying| flying.is_flying);
+        let packet = self.abilities(game_mode, is_flying);
+
+        let conn = self.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(packet).await
+    }
+
+    /// Lets players fly in every gamemode, or only in the ones that always can. Starts out as
+    /// the config's `abilities.allow_flight`.
+    ///
+    /// Everyone in the world is sent their abilities again, and players that can't fly anymore
+    /// are dropped out of the air.
+    pub async fn set_allow_flight(self: &Arc<Self>, allow_flight: bool) {
+        if self.allow_flight.swap(allow_flight, Ordering::Relaxed) == allow_flight {
+            return;
+        }
+        for conn_id in players_in_world(self).await {
+            let game_mode = self
+                .world
+                .get_component::<GameMode>(conn_id)
+                .await
+                .map(|game_mode| *game_mode)
+                .unwrap_or_default();
+            if !self.may_fly(game_mode) {
+                let was_flying = {
+                    let mut flying = self
+                        .world
+                        .get_component_storage()
+                        .get_mut_or_insert_with(conn_id, Flying::default)
+                        .await;
+                    std::mem::replace(&mut flying.is_flying, false)
+                };
+                if was_flying {
+                    if let Err(e) = broadcast_player_metadata(conn_id, self).await {
+                        warn!("Failed to send that {} stopped flying: {}", conn_id, e);
+                    }
+                }
+            }
+            if let Err(e) = self.send_abilities(conn_id).await {
+                warn!("Failed to send {} their abilities: {}", conn_id, e);
+            }
+        }
+    }
+}
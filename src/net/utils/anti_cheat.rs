@@ -0,0 +1,609 @@
+use std::collections::{HashMap, HashSet};
+
+use tracing::warn;
+
+use crate::net::packets::ConnectionId;
+use crate::net::utils::movement::synchronize_position;
+use crate::net::ConnectionExt;
+use crate::state::GlobalState;
+use crate::utils::components::active_effects::{ActiveEffects, Effect};
+use crate::utils::components::exact_position::ExactPosition;
+use crate::utils::components::flying::Flying;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::velocity::Velocity;
+use crate::utils::components::violations::Violations;
+use crate::utils::config::{get_global_config, AntiCheat};
+use crate::utils::prelude::*;
+use crate::world::block_states::BlockState;
+use crate::world::dimensions::Dimension;
+use crate::world::heightmaps::has_fluid;
+use crate::world::lighting::{opacity, MAX_LIGHT};
+use crate::world::physics::BoundingBox;
+
+/// Sent to players whose moves were turned down more than `anti_cheat.max_violations` times.
+pub const ILLEGAL_MOVEMENT: &str = "Illegal movement";
+
+/// How much further each level of Speed lets players move sideways, same as vanilla.
+const SPEED_PER_LEVEL: f64 = 0.2;
+/// How much faster each level of Jump Boost makes jumps start going up, same as vanilla.
+const JUMP_BOOST_PER_LEVEL: f64 = 0.1;
+/// Roughly how many more moves each level of Jump Boost keeps a jump going up for.
+const JUMP_BOOST_MOVES_PER_LEVEL: u32 = 2;
+
+const PLAYER_WIDTH: f64 = 0.6;
+const PLAYER_HEIGHT: f64 = 1.8;
+/// How much of the bottom of a player isn't checked for being inside blocks. Players stand on
+/// plenty of blocks that aren't quite full, like soul sand and stonecutters.
+const FEET_MARGIN: f64 = 0.5;
+/// How much smaller the rest of the player is made on every side before checking it, so players
+/// that are only touching a wall aren't counted as in it.
+const MARGIN: f64 = 0.05;
+
+/// Blocks players can go up without touching the ground in, besides water and lava.
+const CLIMBABLE: &[&str] = &[
+    "minecraft:ladder",
+    "minecraft:vine",
+    "minecraft:scaffolding",
+    "minecraft:bubble_column",
+    "minecraft:twisting_vines",
+    "minecraft:twisting_vines_plant",
+    "minecraft:weeping_vines",
+    "minecraft:weeping_vines_plant",
+    "minecraft:cave_vines",
+    "minecraft:cave_vines_plant",
+];
+/// Blocks that don't let light through but don't fill their whole block either, so players can
+/// be partly inside them.
+const NOT_FULL: &[&str] = &[
+    "minecraft:powder_snow",
+    "minecraft:bamboo",
+    "minecraft:cactus",
+    "minecraft:pointed_dripstone",
+    "minecraft:kelp",
+    "minecraft:kelp_plant",
+    "minecraft:seagrass",
+    "minecraft:tall_seagrass",
+    "minecraft:big_dripleaf",
+    "minecraft:small_dripleaf",
+    "minecraft:chorus_plant",
+    "minecraft:chorus_flower",
+    "minecraft:azalea",
+    "minecraft:flowering_azalea",
+    "minecraft:piston_head",
+    "minecraft:moving_piston",
+];
+
+/// Why a move was turned down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Violation {
+    /// Moved further sideways than the player could have, by this much.
+    HorizontalSpeed(f64),
+    /// Went further up than the player could have, by this much.
+    VerticalSpeed(f64),
+    /// Moved into a solid block.
+    NoClip,
+    /// Kept going up without touching the ground for longer than a jump lasts.
+    Flying,
+}
+
+/// What lets a player move further or differently than usual.
+///
+/// - `flying`: Whether they're flying, which they're only allowed to do if they may.
+/// - `spectator`: Whether they're in spectator, which lets them go through blocks.
+/// - `speed`, `jump_boost`: The levels of their Speed and Jump Boost effects, 0 without them.
+/// - `levitating`: Whether they have Levitation, which lifts them off the ground.
+/// - `velocity`: A velocity the server sent them lately, e.g. knockback, which they can move
+///   that much further by, see [Violations::sent_velocity].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MoveContext {
+    pub flying: bool,
+    pub spectator: bool,
+    pub speed: u32,
+    pub jump_boost: u32,
+    pub levitating: bool,
+    pub velocity: Velocity,
+}
+
+/// Checks how far the player got from `from` to `to` against the config's limits, which are for
+/// one tick, times `ticks`. Both can be exceeded by being quick and lucky with lag, so they
+/// shouldn't be tight.
+///
+/// `from` is where the player was before they started moving this tick, so every move they make
+/// on it adds up, and `ticks` how many ticks' worth of moving they get, see
+/// [Violations::start_move].
+pub fn check_speed(
+    from: (f64, f64, f64),
+    to: (f64, f64, f64),
+    ticks: u64,
+    context: &MoveContext,
+    config: &AntiCheat,
+) -> Option<Violation> {
+    let (dx, dy, dz) = (to.0 - from.0, to.1 - from.1, to.2 - from.2);
+    let flying = match context.flying {
+        true => config.flying_multiplier,
+        false => 1.0,
+    };
+    let multiplier = flying * ticks as f64;
+
+    let horizontal = (dx * dx + dz * dz).sqrt();
+    let velocity = &context.velocity;
+    let max_horizontal =
+        config.max_horizontal_speed * (1.0 + SPEED_PER_LEVEL * context.speed as f64) * multiplier;
+    let max_horizontal =
+        max_horizontal + (velocity.x * velocity.x + velocity.z * velocity.z).sqrt();
+    if horizontal > max_horizontal {
+        return Some(Violation::HorizontalSpeed(horizontal - max_horizontal));
+    }
+
+    // Falling is only limited by how far a packet can move the player at all.
+    let max_vertical =
+        (config.max_vertical_speed + JUMP_BOOST_PER_LEVEL * context.jump_boost as f64) * multiplier;
+    let max_vertical = max_vertical + velocity.y.max(0.0);
+    if dy > max_vertical {
+        return Some(Violation::VerticalSpeed(dy - max_vertical));
+    }
+    None
+}
+
+/// Checks where a move from `from` to `to` went, and returns how many moves in a row the player
+/// has now gone up, or stayed level, without touching the ground, see
+/// [Violations::ascending_moves]. Hovering counts as much as going up, and only coming down
+/// starts the count over.
+///
+/// `block_at` gives the block at `x`, `y`, `z`, or `None` if its chunk isn't loaded. Moves are
+/// only turned down for blocks that are known.
+///
+/// Players can't move into full, solid blocks unless they're spectators. Ones that were already
+/// in some, say because a block was placed on them, can move around in those to get out, but not
+/// into any others.
+pub fn check_position(
+    from: (f64, f64, f64),
+    to: (f64, f64, f64),
+    on_ground: bool,
+    ascending_moves: u32,
+    context: &MoveContext,
+    config: &AntiCheat,
+    block_at: impl Fn(i32, i32, i32) -> Option<BlockState>,
+) -> std::result::Result<u32, Violation> {
+    if !context.spectator {
+        let full_blocks = |position| {
+            body(position)
+                .blocks()
+                .filter(|&(x, y, z)| block_at(x, y, z).is_some_and(is_full_block))
+        };
+        let already_in = full_blocks(from).collect::<HashSet<_>>();
+        if full_blocks(to).any(|block| !already_in.contains(&block)) {
+            return Err(Violation::NoClip);
+        }
+    }
+
+    let ascending = !on_ground && to.1 >= from.1 && !context.flying && !context.levitating;
+    let climbing = || {
+        [from, to].into_iter().any(|position| {
+            hitbox(position)
+                .blocks()
+                .any(|(x, y, z)| block_at(x, y, z).is_some_and(is_climbable))
+        })
+    };
+    if !ascending || climbing() {
+        return Ok(0);
+    }
+    let ascending_moves = ascending_moves + 1;
+    let max_ascending_moves =
+        config.max_ascending_moves + JUMP_BOOST_MOVES_PER_LEVEL * context.jump_boost;
+    match ascending_moves > max_ascending_moves {
+        true => Err(Violation::Flying),
+        false => Ok(ascending_moves),
+    }
+}
+
+/// Whether players can't be inside `state` at all. Only blocks that don't let light through
+/// count, since nearly all of them fill their whole block.
+fn is_full_block(state: BlockState) -> bool {
+    opacity(state.id() as i32) == MAX_LIGHT
+        && !NOT_FULL.contains(&state.name())
+        && !is_climbable(state)
+}
+
+/// Whether players can go up without touching the ground in `state`.
+fn is_climbable(state: BlockState) -> bool {
+    CLIMBABLE.contains(&state.name()) || has_fluid(state)
+}
+
+/// A player at `position`.
+fn hitbox((x, y, z): (f64, f64, f64)) -> BoundingBox {
+    let half_width = PLAYER_WIDTH / 2.0;
+    BoundingBox {
+        min: [x - half_width, y, z - half_width],
+        max: [x + half_width, y + PLAYER_HEIGHT, z + half_width],
+    }
+}
+
+/// The part of a player at `position` that's checked for being inside blocks, see [FEET_MARGIN]
+/// and [MARGIN].
+fn body(position: (f64, f64, f64)) -> BoundingBox {
+    let mut body = hitbox(position);
+    body.min[1] += FEET_MARGIN;
+    for axis in 0..3 {
+        body.max[axis] -= MARGIN;
+        if axis != 1 {
+            body.min[axis] += MARGIN;
+        }
+    }
+    body
+}
+
+/// Checks a move the player sent to `to`, from the last place they were allowed to be, their
+/// [ExactPosition], see [check_speed] and [check_position]. Returns whether it passed.
+///
+/// Moves that don't are undone by teleporting the player back, and once more than
+/// `anti_cheat.max_violations` of them haven't been forgiven, the player is kicked.
+pub async fn check_movement(
+    conn_id: ConnectionId,
+    state: &GlobalState,
+    dimension: &Dimension,
+    to: (f64, f64, f64),
+    on_ground: bool,
+) -> Result<bool> {
+    let config = &get_global_config().anti_cheat;
+    // Positions that aren't numbers are kicked for by the movement handler.
+    if !(to.0.is_finite() && to.1.is_finite() && to.2.is_finite()) {
+        return Ok(true);
+    }
+    if !config.enabled {
+        return Ok(true);
+    }
+
+    let component_storage = state.world.get_component_storage();
+    let from = component_storage.get::<ExactPosition>(conn_id).await?.0;
+    let tick = state.scheduler.current_tick();
+    let ((tick_start, ticks), ascending_moves, velocity) = {
+        let mut violations = component_storage
+            .get_mut_or_insert_with(conn_id, Violations::default)
+            .await;
+        (
+            violations.start_move(tick, from),
+            violations.ascending_moves,
+            violations.take_velocity(),
+        )
+    };
+    let context = MoveContext {
+        velocity,
+        ..move_context(conn_id, state).await
+    };
+
+    let verdict = match check_speed(tick_start, to, ticks, &context, config) {
+        Some(violation) => Err(violation),
+        None => {
+            let blocks = load_blocks(state, dimension, &[hitbox(from), hitbox(to)]).await?;
+            let block_at = |x: i32, y: i32, z: i32| blocks.get(&(x, y, z)).copied();
+            check_position(
+                from,
+                to,
+                on_ground,
+                ascending_moves,
+                &context,
+                config,
+                block_at,
+            )
+        }
+    };
+
+    let violation = match verdict {
+        Ok(ascending_moves) => {
+            component_storage
+                .get_mut_or_insert_with(conn_id, Violations::default)
+                .await
+                .pass(ascending_moves, config.forgive_after);
+            return Ok(true);
+        }
+        Err(violation) => violation,
+    };
+    let count = component_storage
+        .get_mut_or_insert_with(conn_id, Violations::default)
+        .await
+        .add();
+    warn!(
+        "Connection {} moved illegally: {:?} (violation {})",
+        conn_id, violation, count
+    );
+    if config.max_violations > 0 && count > config.max_violations {
+        let conn = state.connections.get_connection(conn_id)?;
+        conn.kick(ILLEGAL_MOVEMENT, state.clone()).await?;
+    } else {
+        synchronize_position(conn_id, state.clone()).await?;
+    }
+    Ok(false)
+}
+
+/// What the player's gamemode and effects let them do, see [MoveContext].
+async fn move_context(conn_id: ConnectionId, state: &GlobalState) -> MoveContext {
+    let component_storage = state.world.get_component_storage();
+    let game_mode = component_storage
+        .get::<GameMode>(conn_id)
+        .await
+        .map(|game_mode| *game_mode)
+        .unwrap_or_default();
+    let spectator = game_mode.is_spectator();
+    let flying = spectator
+        || (state.may_fly(game_mode)
+            && component_storage
+                .get::<Flying>(conn_id)
+                .await
+                .is_ok_and(|flying| flying.is_flying));
+    let (speed, jump_boost, levitating) =
+        match component_storage.get::<ActiveEffects>(conn_id).await {
+            Ok(effects) => {
+                let level = |effect| {
+                    effects
+                        .get(effect)
+                        .map_or(0, |active| active.amplifier as u32 + 1)
+                };
+                (
+                    level(Effect::Speed),
+                    level(Effect::JumpBoost),
+                    level(Effect::Levitation) > 0,
+                )
+            }
+            Err(_) => (0, 0, false),
+        };
+    MoveContext {
+        flying,
+        spectator,
+        speed,
+        jump_boost,
+        levitating,
+        velocity: Velocity::default(),
+    }
+}
+
+/// The blocks `boxes` are in, by their x, y and z. Blocks in chunks that aren't loaded are left
+/// out.
+///
+/// Each chunk is only looked up once, and only the blocks that are needed are read out of it.
+async fn load_blocks(
+    state: &GlobalState,
+    dimension: &Dimension,
+    boxes: &[BoundingBox],
+) -> Result<HashMap<(i32, i32, i32), BlockState>> {
+    let mut by_chunk = HashMap::<(i32, i32), Vec<(i32, i32, i32)>>::new();
+    for (x, y, z) in boxes.iter().flat_map(BoundingBox::blocks) {
+        by_chunk
+            .entry((x >> 4, z >> 4))
+            .or_default()
+            .push((x, y, z));
+    }
+
+    let mut blocks = HashMap::new();
+    for ((chunk_x, chunk_z), positions) in by_chunk {
+        let read = state
+            .database
+            .read_chunk(chunk_x, chunk_z, &dimension.key, |chunk| {
+                positions
+                    .iter()
+                    .filter_map(|&(x, y, z)| {
+                        let id = chunk.get_block_id(x, y, z).ok()?;
+                        Some(((x, y, z), BlockState::from_id(id as u32)?))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .await?;
+        blocks.extend(read.into_iter().flatten());
+    }
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::config::ServerConfig;
+
+    fn config() -> AntiCheat {
+        ServerConfig::default().anti_cheat
+    }
+
+    fn block(name: &str) -> Option<BlockState> {
+        BlockState::default_state(name)
+    }
+
+    /// Stone floor at y 63, with a stone wall at x 2 and a ladder against it at x 1, and air
+    /// everywhere else.
+    fn world(x: i32, y: i32, _z: i32) -> Option<BlockState> {
+        match (x, y) {
+            (_, 63) | (2, _) => block("minecraft:stone"),
+            (1, 64..=70) => block("minecraft:ladder"),
+            _ => block("minecraft:air"),
+        }
+    }
+
+    #[test]
+    fn test_speed_limits() {
+        let config = config();
+        let walking = MoveContext::default();
+        let from = (0.5, 64.0, 0.5);
+
+        assert_eq!(
+            check_speed(from, (1.1, 64.0, 1.1), 1, &walking, &config),
+            None
+        );
+        // 1.2 blocks at a limit of 1.
+        let Some(Violation::HorizontalSpeed(over)) =
+            check_speed(from, (1.7, 64.0, 0.5), 1, &walking, &config)
+        else {
+            panic!("a 1.2 block move passed");
+        };
+        assert!((over - 0.2).abs() < 1e-9);
+        // Speed II lets them go 1.4 times as far.
+        let fast = MoveContext {
+            speed: 2,
+            ..walking
+        };
+        assert_eq!(check_speed(from, (1.7, 64.0, 0.5), 1, &fast, &config), None);
+        // Flying lets them go 4 times as far, either way.
+        let flying = MoveContext {
+            flying: true,
+            ..walking
+        };
+        assert_eq!(
+            check_speed(from, (3.5, 66.5, 0.5), 1, &flying, &config),
+            None
+        );
+        // Knockback the server sent them lets them go that much further.
+        let knocked_back = MoveContext {
+            velocity: Velocity::new(0.9, 0.4, 0.0),
+            ..walking
+        };
+        assert_eq!(
+            check_speed(from, (2.3, 64.8, 0.5), 1, &knocked_back, &config),
+            None
+        );
+        assert!(check_speed(from, (2.5, 64.0, 0.5), 1, &knocked_back, &config).is_some());
+        // Catching up on ticks without moves goes further.
+        assert_eq!(
+            check_speed(from, (2.5, 64.0, 0.5), 2, &walking, &config),
+            None
+        );
+        assert!(check_speed(from, (3.5, 64.0, 0.5), 2, &walking, &config).is_some());
+    }
+
+    #[test]
+    fn test_going_up_is_limited_but_falling_is_not() {
+        let config = config();
+        let walking = MoveContext::default();
+        let from = (0.5, 64.0, 0.5);
+
+        // A jump starts at 0.42.
+        assert_eq!(
+            check_speed(from, (0.5, 64.42, 0.5), 1, &walking, &config),
+            None
+        );
+        assert!(matches!(
+            check_speed(from, (0.5, 65.0, 0.5), 1, &walking, &config),
+            Some(Violation::VerticalSpeed(_))
+        ));
+        // Jump Boost II makes room for 0.2 more.
+        let jumpy = MoveContext {
+            jump_boost: 2,
+            ..walking
+        };
+        assert_eq!(
+            check_speed(from, (0.5, 64.9, 0.5), 1, &jumpy, &config),
+            None
+        );
+        assert_eq!(
+            check_speed(from, (0.5, 60.0, 0.5), 1, &walking, &config),
+            None
+        );
+    }
+
+    #[test]
+    fn test_walking_into_walls() {
+        let config = config();
+        let walking = MoveContext::default();
+        let check = |from, to| check_position(from, to, true, 0, &walking, &config, world);
+
+        // Right up against the wall, which starts at x 2, is fine.
+        assert_eq!(check((1.5, 64.0, 0.5), (1.7, 64.0, 0.5)), Ok(0));
+        assert_eq!(
+            check((1.5, 64.0, 0.5), (2.1, 64.0, 0.5)),
+            Err(Violation::NoClip)
+        );
+        // Standing on the floor isn't being in it, even a bit into it.
+        assert_eq!(check((0.5, 64.0, 0.5), (0.5, 63.8, 0.5)), Ok(0));
+        // Spectators go through anything.
+        let spectator = MoveContext {
+            spectator: true,
+            ..walking
+        };
+        let moved = check_position(
+            (1.5, 64.0, 0.5),
+            (3.5, 64.0, 0.5),
+            true,
+            0,
+            &spectator,
+            &config,
+            world,
+        );
+        assert_eq!(moved, Ok(0));
+        // Players that are already stuck can move to get out, but not on through the wall.
+        assert_eq!(check((2.5, 64.0, 0.5), (2.3, 64.0, 0.5)), Ok(0));
+        assert_eq!(
+            check((2.5, 64.0, 0.5), (2.5, 64.0, 2.5)),
+            Err(Violation::NoClip)
+        );
+    }
+
+    #[test]
+    fn test_unknown_blocks_are_not_walls() {
+        let config = config();
+        let moved = check_position(
+            (1.5, 64.0, 0.5),
+            (2.1, 64.0, 0.5),
+            true,
+            0,
+            &MoveContext::default(),
+            &config,
+            |_, _, _| None,
+        );
+        assert_eq!(moved, Ok(0));
+    }
+
+    #[test]
+    fn test_going_up_without_touching_the_ground() {
+        let config = config();
+        let walking = MoveContext::default();
+        let rise = |moves, from: (f64, f64, f64), context: &MoveContext| {
+            let to = (from.0, from.1 + 0.1, from.2);
+            check_position(from, to, false, moves, context, &config, world)
+        };
+
+        let from = (-5.5, 70.0, 0.5);
+        assert_eq!(rise(0, from, &walking), Ok(1));
+        assert_eq!(rise(19, from, &walking), Ok(20));
+        assert_eq!(rise(20, from, &walking), Err(Violation::Flying));
+        // Jump Boost II keeps jumps going up for 4 more.
+        let jumpy = MoveContext {
+            jump_boost: 2,
+            ..walking
+        };
+        assert_eq!(rise(23, from, &jumpy), Ok(24));
+
+        // Staying level in the air counts too.
+        let level = check_position(from, from, false, 19, &walking, &config, world);
+        assert_eq!(level, Ok(20));
+        let level = check_position(from, from, false, 20, &walking, &config, world);
+        assert_eq!(level, Err(Violation::Flying));
+
+        // Landing, or starting to fall, starts the count over.
+        let landed = check_position(from, from, true, 20, &walking, &config, world);
+        assert_eq!(landed, Ok(0));
+        let falling = check_position(from, (-5.5, 69.9, 0.5), false, 20, &walking, &config, world);
+        assert_eq!(falling, Ok(0));
+
+        // Climbing a ladder, flying, and levitating never count.
+        assert_eq!(rise(100, (1.5, 65.0, 0.5), &walking), Ok(0));
+        let flying = MoveContext {
+            flying: true,
+            ..walking
+        };
+        assert_eq!(rise(100, from, &flying), Ok(0));
+        let levitating = MoveContext {
+            levitating: true,
+            ..walking
+        };
+        assert_eq!(rise(100, from, &levitating), Ok(0));
+    }
+
+    #[test]
+    fn test_full_blocks() {
+        let full = |name| is_full_block(block(name).unwrap());
+        assert!(full("minecraft:stone"));
+        assert!(full("minecraft:oak_planks"));
+        assert!(!full("minecraft:air"));
+        assert!(!full("minecraft:glass"));
+        assert!(!full("minecraft:oak_slab"));
+        assert!(!full("minecraft:ladder"));
+        assert!(!full("minecraft:water"));
+        assert!(!full("minecraft:powder_snow"));
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::OnceLock;
+
+use num_bigint::BigInt;
+use reqwest::StatusCode;
+use rsa::pkcs8::EncodePublicKey;
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::utils::prelude::*;
+
+const SESSION_SERVER_URL: &str = "https://sessionserver.mojang.com/session/minecraft/hasJoined";
+/// Same key size the vanilla server uses.
+const RSA_KEY_BITS: usize = 1024;
+
+/// The RSA keypair used to exchange the shared secret with clients in online mode.
+pub struct ServerKey {
+    private_key: RsaPrivateKey,
+    /// The public key in the DER encoded X.509 format the client expects.
+    pub public_key_der: Vec<u8>,
+}
+
+impl ServerKey {
+    fn generate() -> Result<Self> {
+        let private_key = RsaPrivateKey::new(&mut rsa::rand_core::OsRng, RSA_KEY_BITS)
+            .map_err(|e| Error::EncryptionError(e.to_string()))?;
+        let public_key_der = private_key
+            .to_public_key()
+            .to_public_key_der()
+            .map_err(|e| Error::EncryptionError(e.to_string()))?
+            .into_vec();
+        Ok(Self {
+            private_key,
+            public_key_der,
+        })
+    }
+
+    /// Decrypts data the client encrypted with our public key.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.private_key
+            .decrypt(Pkcs1v15Encrypt, data)
+            .map_err(|e| Error::EncryptionError(e.to_string()))
+    }
+}
+
+/// Get the server's RSA keypair. It's generated the first time this is called, and kept for
+/// the lifetime of the server.
+pub fn get_server_key() -> Result<&'static ServerKey> {
+    static SERVER_KEY: OnceLock<ServerKey> = OnceLock::new();
+    if let Some(key) = SERVER_KEY.get() {
+        return Ok(key);
+    }
+    debug!("Generating {} bit RSA keypair", RSA_KEY_BITS);
+    let key = ServerKey::generate()?;
+    Ok(SERVER_KEY.get_or_init(|| key))
+}
+
+/// Computes the server hash sent to the session server.
+///
+/// This is a SHA-1 digest, but printed the way Java's `BigInteger` would: as a signed, two's
+/// complement number without leading zeros.
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    let digest = Sha1::new()
+        .chain_update(server_id.as_bytes())
+        .chain_update(shared_secret)
+        .chain_update(public_key)
+        .finalize();
+    BigInt::from_signed_bytes_be(&digest).to_str_radix(16)
+}
+
+/// The profile of an authenticated player, as returned by the session server.
+#[derive(Debug, Deserialize)]
+pub struct GameProfile {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub properties: Vec<ProfileProperty>,
+}
+
+/// A profile property. In practice this is just the `textures` property holding the player's skin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+
+/// Asks the session server whether `username` has joined the server identified by `server_hash`.
+///
+/// Returns the player's profile if they have, or an error if the session server couldn't verify them.
+pub async fn has_joined(username: &str, server_hash: &str) -> Result<GameProfile> {
+    let response = reqwest::Client::new()
+        .get(SESSION_SERVER_URL)
+        .query(&[("username", username), ("serverId", server_hash)])
+        .send()
+        .await?;
+
+    // The session server answers with 204 No Content if the player couldn't be verified.
+    if response.status() != StatusCode::OK {
+        return Err(Error::AuthenticationError(format!(
+            "Session server responded with {} for {}",
+            response.status(),
+            username
+        )));
+    }
+
+    Ok(response.json().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_hash_matches_java_digest() {
+        // Reference values from wiki.vg
+        assert_eq!(
+            server_hash("Notch", &[], &[]),
+            "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48"
+        );
+        assert_eq!(
+            server_hash("jeb_", &[], &[]),
+            "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1"
+        );
+        assert_eq!(
+            server_hash("simon", &[], &[]),
+            "88e16a1019277b15d58faf0541e11910eb756f6"
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::player_events::PlayerAttackEntityEvent;
+use crate::net::packets::ConnectionId;
+use crate::net::utils::damage::DamageSource;
+use crate::state::GlobalState;
+use crate::utils::components::exact_position::ExactPosition;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::health::Health;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::components::sprinting::Sprinting;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+
+/// How far away a player can hit or click an entity from, measured from their eyes. Same as
+/// vanilla's survival reach.
+pub const MAX_REACH: f64 = 4.5;
+/// How far away a player can break or place a block from, measured from their eyes to the middle
+/// of the block. Same as the limit vanilla servers hold every gamemode to, which leaves room for
+/// creative's longer reach.
+pub const MAX_BLOCK_REACH: f64 = 6.0;
+/// How much an attack does. Items don't have attack damage yet, so everything hits like a fist.
+pub const ATTACK_DAMAGE: f32 = 1.0;
+/// How hard an attack knocks its victim back, same as vanilla's without the Knockback
+/// enchantment.
+pub const ATTACK_KNOCKBACK: f64 = 0.4;
+/// How much harder an attack knocks back when the attacker is sprinting.
+pub const SPRINT_KNOCKBACK: f64 = 0.5;
+
+const PLAYER_EYE_HEIGHT: f64 = 1.62;
+
+/// Where the eyes of a player standing at `position` are.
+fn eyes(position: &ExactPosition) -> [f64; 3] {
+    let (x, y, z) = position.0;
+    [x, y + PLAYER_EYE_HEIGHT, z]
+}
+
+/// Whether a player at `attacker` can reach a player at `target`, going by the closest point of
+/// the target's hitbox to the attacker's eyes, see [ExactPosition::hitbox].
+pub fn within_reach(attacker: &ExactPosition, target: &ExactPosition) -> bool {
+    let eyes = eyes(attacker);
+    let hitbox = target.hitbox();
+    let distance_squared: f64 = (0..3)
+        .map(|axis| eyes[axis] - eyes[axis].clamp(hitbox.min[axis], hitbox.max[axis]))
+        .map(|distance| distance * distance)
+        .sum();
+    distance_squared <= MAX_REACH * MAX_REACH
+}
+
+/// Whether a player at `player` can reach the block at `block`, to break it or place a block
+/// against it.
+pub fn block_within_reach(player: &ExactPosition, block: &Position) -> bool {
+    let eyes = eyes(player);
+    let middle = [
+        block.x as f64 + 0.5,
+        block.y as f64 + 0.5,
+        block.z as f64 + 0.5,
+    ];
+    let distance_squared: f64 = (0..3)
+        .map(|axis| eyes[axis] - middle[axis])
+        .map(|distance| distance * distance)
+        .sum();
+    distance_squared <= MAX_BLOCK_REACH * MAX_BLOCK_REACH
+}
+
+/// Which way a hit from `attacker` came from, relative to the way the victim at `victim` is
+/// looking, for [HurtAnimation].
+pub fn hurt_direction(attacker: &Position, victim: &Position, victim_yaw: f32) -> f32 {
+    let dx = (attacker.x - victim.x) as f64;
+    let dz = (attacker.z - victim.z) as f64;
+    if dx == 0.0 && dz == 0.0 {
+        return 0.0;
+    }
+    // Yaw 0 faces south (+z), and it goes clockwise from above.
+    let towards_attacker = (-dx).atan2(dz).to_degrees() as f32;
+    (towards_attacker - victim_yaw).rem_euclid(360.0)
+}
+
+/// Which way a hit from `attacker` knocks back the victim at `victim`, as an x and z: straight
+/// away from the attacker, or the way they're looking if they're right on top of each other.
+pub fn knockback_direction(
+    attacker: (f64, f64, f64),
+    victim: (f64, f64, f64),
+    attacker_yaw: f32,
+) -> (f64, f64) {
+    let (dx, dz) = (victim.0 - attacker.0, victim.2 - attacker.2);
+    // Same as vanilla, anything closer than this is too close to tell which way is away.
+    if dx * dx + dz * dz < 1.0e-4 {
+        let yaw = (attacker_yaw as f64).to_radians();
+        return (-yaw.sin(), yaw.cos());
+    }
+    (dx, dz)
+}
+
+/// Has `attacker` hit the player `victim`, unless a [PlayerAttackEntityEvent] handler cancels it.
+/// Players in creative or spectator can't be hurt, and neither can players that are already dead.
+///
+/// The victim is knocked back away from the attacker, further if the attacker is sprinting,
+/// unless the hit killed them.
+pub async fn attack_player(
+    attacker: ConnectionId,
+    victim: ConnectionId,
+    state: GlobalState,
+) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+    let invulnerable = component_storage
+        .get::<GameMode>(victim)
+        .await
+        .is_ok_and(|game_mode| matches!(*game_mode, GameMode::Creative | GameMode::Spectator));
+    let dead = component_storage
+        .get::<Health>(victim)
+        .await
+        .is_ok_and(|health| health.is_dead());
+    if invulnerable || dead {
+        return Ok(());
+    }
+
+    let event = Arc::new(PlayerAttackEntityEvent::new(
+        attacker,
+        victim,
+        ATTACK_DAMAGE,
+    ));
+    state.dispatch_shared_event(event.clone()).await;
+    if event.is_cancelled() {
+        return Ok(());
+    }
+
+    state
+        .damage_player(victim, event.damage, DamageSource::Player(attacker))
+        .await?;
+
+    let killed = component_storage
+        .get::<Health>(victim)
+        .await
+        .is_ok_and(|health| health.is_dead());
+    if killed {
+        return Ok(());
+    }
+
+    let attacker_position = state
+        .world
+        .get_component::<ExactPosition>(attacker)
+        .await?
+        .0;
+    let victim_position = state.world.get_component::<ExactPosition>(victim).await?.0;
+    let attacker_yaw = component_storage.get::<Rotation>(attacker).await?.yaw;
+    let direction = knockback_direction(attacker_position, victim_position, attacker_yaw);
+    let sprinting = component_storage
+        .get::<Sprinting>(attacker)
+        .await
+        .is_ok_and(|sprinting| sprinting.is_sprinting);
+    let strength = match sprinting {
+        true => ATTACK_KNOCKBACK + SPRINT_KNOCKBACK,
+        false => ATTACK_KNOCKBACK,
+    };
+    state.apply_knockback(victim, direction, strength).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reach() {
+        let attacker = ExactPosition((0.5, 64.0, 0.5));
+        let target = |x, y, z| ExactPosition((x, y, z));
+        assert!(within_reach(&attacker, &target(3.5, 64.0, 0.5)));
+        // The edge of their hitbox is 4.5 blocks away, but they're standing 4.8 away.
+        assert!(within_reach(&attacker, &target(5.3, 64.0, 0.5)));
+        assert!(!within_reach(&attacker, &target(5.4, 64.0, 0.5)));
+        assert!(within_reach(&attacker, &target(-2.5, 66.0, 2.5)));
+        assert!(!within_reach(&attacker, &target(0.5, 58.0, 0.5)));
+        assert!(!within_reach(&attacker, &target(4.0, 64.0, 4.0)));
+    }
+
+    #[test]
+    fn test_block_reach() {
+        let player = ExactPosition((0.5, 64.0, 0.5));
+        assert!(block_within_reach(&player, &Position::new(0, 63, 0)));
+        assert!(block_within_reach(&player, &Position::new(5, 65, 0)));
+        assert!(block_within_reach(&player, &Position::new(0, 61, -3)));
+        assert!(!block_within_reach(&player, &Position::new(6, 65, 0)));
+        assert!(!block_within_reach(&player, &Position::new(0, 58, -4)));
+        assert!(!block_within_reach(&player, &Position::new(5, 64, 5)));
+        // From the edge of their block, a block can be out of reach from its middle.
+        let edge = ExactPosition((0.99, 64.0, 0.5));
+        assert!(block_within_reach(&edge, &Position::new(6, 65, 0)));
+    }
+
+    #[test]
+    fn test_hurt_direction() {
+        let victim = Position::new(0, 64, 0);
+        // Looking south, at an attacker to the south.
+        assert_eq!(hurt_direction(&Position::new(0, 64, 2), &victim, 0.0), 0.0);
+        // An attacker to the west is on the right.
+        assert_eq!(
+            hurt_direction(&Position::new(-2, 64, 0), &victim, 0.0),
+            90.0
+        );
+        assert_eq!(
+            hurt_direction(&Position::new(-2, 64, 0), &victim, 90.0),
+            0.0
+        );
+        assert_eq!(hurt_direction(&victim, &victim, 45.0), 0.0);
+    }
+
+    #[test]
+    fn test_knockback_direction() {
+        let attacker = (0.5, 64.0, 0.5);
+        assert_eq!(
+            knockback_direction(attacker, (2.5, 64.0, -0.5), 90.0),
+            (2.0, -1.0)
+        );
+        // In the same block still goes away from the attacker.
+        let (x, z) = knockback_direction(attacker, (0.2, 64.0, 0.9), 90.0);
+        assert!((x + 0.3).abs() < 1e-9 && (z - 0.4).abs() < 1e-9);
+        // Right on top of them, it goes the way the attacker is looking: south, then west.
+        assert_eq!(knockback_direction(attacker, attacker, 0.0), (0.0, 1.0));
+        let (x, z) = knockback_direction(attacker, (0.5, 65.0, 0.5), 90.0);
+        assert!((x + 1.0).abs() < 1e-9 && z.abs() < 1e-9);
+    }
+}
@@ -0,0 +1,230 @@
+use std::io::{Cursor, Read, Write};
+
+use ferrumc_codec::network_types::varint::VarInt;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::utils::prelude::*;
+
+/// Converts packets in the regular format into the compressed format.
+///
+/// `packets` can hold several packets back to back, like the contents of a
+/// [crate::net::utils::packet_queue::PacketQueue]. Each one is re-framed on its own.
+pub async fn compress_packets(packets: &[u8], threshold: usize) -> Result<Vec<u8>> {
+    let mut cursor = Cursor::new(packets);
+    let mut compressed = Vec::with_capacity(packets.len());
+
+    while (cursor.position() as usize) < packets.len() {
+        let length = VarInt::read(&mut cursor).await?.get_val() as usize;
+        let start = cursor.position() as usize;
+        let packet = packets.get(start..start + length).ok_or_else(|| {
+            Error::BadlyCompressedPacket(format!(
+                "Packet length of {} is longer than the remaining {} bytes",
+                length,
+                packets.len() - start
+            ))
+        })?;
+
+        compress_packet(packet, threshold, &mut compressed).await?;
+        cursor.set_position((start + length) as u64);
+    }
+
+    Ok(compressed)
+}
+
+/// Writes a single packet (id and data) to `out` in the compressed format.
+///
+/// Packets smaller than `threshold` are written as is, with a data length of 0.
+pub async fn compress_packet(packet: &[u8], threshold: usize, out: &mut Vec<u8>) -> Result<()> {
+    if packet.len() < threshold {
+        let data_length = VarInt::new(0);
+        VarInt::new((data_length.get_len() + packet.len()) as i32)
+            .write(out)
+            .await?;
+        data_length.write(out).await?;
+        out.extend_from_slice(packet);
+        return Ok(());
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(packet).map_err(Error::CompressionError)?;
+    let body = encoder.finish().map_err(Error::CompressionError)?;
+
+    let data_length = VarInt::new(packet.len() as i32);
+    VarInt::new((data_length.get_len() + body.len()) as i32)
+        .write(out)
+        .await?;
+    data_length.write(out).await?;
+    out.extend_from_slice(&body);
+    Ok(())
+}
+
+/// Turns a packet received in the compressed format back into its id and data.
+///
+/// `frame` is everything after the packet length. Following the protocol, packets that should
+/// have been compressed but weren't (or the other way around) are rejected, as are packets that
+/// would inflate to more than `max_length` bytes.
+pub async fn decompress_packet(
+    frame: &[u8],
+    threshold: usize,
+    max_length: usize,
+) -> Result<Vec<u8>> {
+    let mut cursor = Cursor::new(frame);
+    let data_length = VarInt::read(&mut cursor).await?.get_val();
+    let body = &frame[cursor.position() as usize..];
+
+    if data_length == 0 {
+        if body.len() >= threshold {
+            return Err(Error::BadlyCompressedPacket(format!(
+                "Uncompressed packet of size {} is not below the threshold of {}",
+                body.len(),
+                threshold
+            )));
+        }
+        return Ok(body.to_vec());
+    }
+
+    let data_length = usize::try_from(data_length).map_err(|_| {
+        Error::BadlyCompressedPacket(format!("Negative data length {}", data_length))
+    })?;
+    if data_length < threshold {
+        return Err(Error::BadlyCompressedPacket(format!(
+            "Compressed packet of size {} is below the threshold of {}",
+            data_length, threshold
+        )));
+    }
+    if data_length > max_length {
+        return Err(Error::PacketTooLarge(data_length, max_length));
+    }
+
+    let mut packet = Vec::with_capacity(data_length);
+    // Read one byte past the expected length, so a lying client can't make us inflate forever.
+    ZlibDecoder::new(body)
+        .take(data_length as u64 + 1)
+        .read_to_end(&mut packet)
+        .map_err(Error::CompressionError)?;
+
+    if packet.len() != data_length {
+        return Err(Error::BadlyCompressedPacket(format!(
+            "Data length was {}, but the packet inflated to {} bytes",
+            data_length,
+            packet.len()
+        )));
+    }
+
+    Ok(packet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: usize = 256;
+    const MAX_LENGTH: usize = 200_000;
+
+    /// Frames `packet` the way the packet encoder does when compression is off.
+    async fn frame(packet: &[u8]) -> Vec<u8> {
+        let mut framed = Vec::new();
+        VarInt::new(packet.len() as i32)
+            .write(&mut framed)
+            .await
+            .unwrap();
+        framed.extend_from_slice(packet);
+        framed
+    }
+
+    /// Compresses `packet` and reads it back like the connection read loop does.
+    /// Returns the data length that was sent along with the decompressed packet.
+    async fn round_trip(packet: &[u8]) -> (i32, Vec<u8>) {
+        let compressed = compress_packets(&frame(packet).await, THRESHOLD)
+            .await
+            .unwrap();
+
+        let mut cursor = Cursor::new(&compressed[..]);
+        let length = VarInt::read(&mut cursor).await.unwrap().get_val() as usize;
+        let start = cursor.position() as usize;
+        assert_eq!(start + length, compressed.len());
+
+        let frame = &compressed[start..];
+        let data_length = VarInt::read(&mut Cursor::new(frame))
+            .await
+            .unwrap()
+            .get_val();
+        let decompressed = decompress_packet(frame, THRESHOLD, MAX_LENGTH)
+            .await
+            .unwrap();
+        (data_length, decompressed)
+    }
+
+    #[tokio::test]
+    async fn test_packet_below_threshold_is_not_compressed() {
+        let packet = vec![0x2A; THRESHOLD - 1];
+        let (data_length, decompressed) = round_trip(&packet).await;
+        assert_eq!(data_length, 0);
+        assert_eq!(decompressed, packet);
+    }
+
+    #[tokio::test]
+    async fn test_packet_at_threshold_is_compressed() {
+        let packet = vec![0x2A; THRESHOLD];
+        let (data_length, decompressed) = round_trip(&packet).await;
+        assert_eq!(data_length, THRESHOLD as i32);
+        assert_eq!(decompressed, packet);
+    }
+
+    #[tokio::test]
+    async fn test_packet_above_threshold_is_compressed() {
+        let packet: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+        let (data_length, decompressed) = round_trip(&packet).await;
+        assert_eq!(data_length, packet.len() as i32);
+        assert_eq!(decompressed, packet);
+    }
+
+    #[tokio::test]
+    async fn test_multiple_packets_are_framed_separately() {
+        let mut packets = frame(&[0x01; 10]).await;
+        packets.extend(frame(&[0x02; 1000]).await);
+
+        let compressed = compress_packets(&packets, THRESHOLD).await.unwrap();
+        let mut cursor = Cursor::new(&compressed[..]);
+        let mut decompressed = Vec::new();
+        while (cursor.position() as usize) < compressed.len() {
+            let length = VarInt::read(&mut cursor).await.unwrap().get_val() as usize;
+            let start = cursor.position() as usize;
+            let frame = &compressed[start..start + length];
+            decompressed.push(
+                decompress_packet(frame, THRESHOLD, MAX_LENGTH)
+                    .await
+                    .unwrap(),
+            );
+            cursor.set_position((start + length) as u64);
+        }
+
+        assert_eq!(decompressed, vec![vec![0x01; 10], vec![0x02; 1000]]);
+    }
+
+    #[tokio::test]
+    async fn test_uncompressed_packet_over_threshold_is_rejected() {
+        let mut frame = Vec::new();
+        VarInt::new(0).write(&mut frame).await.unwrap();
+        frame.extend_from_slice(&[0x2A; THRESHOLD + 1]);
+
+        let result = decompress_packet(&frame, THRESHOLD, MAX_LENGTH).await;
+        assert!(matches!(result, Err(Error::BadlyCompressedPacket(_))));
+    }
+
+    #[tokio::test]
+    async fn test_packet_inflating_past_max_length_is_rejected() {
+        let packet = vec![0u8; MAX_LENGTH + 1];
+        let compressed = compress_packets(&frame(&packet).await, THRESHOLD)
+            .await
+            .unwrap();
+        let mut cursor = Cursor::new(&compressed[..]);
+        VarInt::read(&mut cursor).await.unwrap();
+        let frame = &compressed[cursor.position() as usize..];
+
+        let result = decompress_packet(frame, THRESHOLD, MAX_LENGTH).await;
+        assert!(matches!(result, Err(Error::PacketTooLarge(_, MAX_LENGTH))));
+    }
+}
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use tracing::debug;
+
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::player_events::PlayerDeathEvent;
+use crate::net::packets::outgoing::combat_death::CombatDeath;
+use crate::net::packets::outgoing::entity_event::{EntityEvent, ENTITY_DEATH};
+use crate::net::packets::outgoing::hurt_animation::HurtAnimation;
+use crate::net::packets::outgoing::set_health::SetHealth;
+use crate::net::packets::ConnectionId;
+use crate::net::utils::combat::hurt_direction;
+use crate::state::{GlobalState, ServerState};
+use crate::utils::components::food::Food;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::health::Health;
+use crate::utils::components::player::Player;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+
+/// Players below this fall out of the world, 64 blocks under the bottom of the overworld.
+pub const VOID_LEVEL: i16 = -128;
+/// How much falling out of the world does every tick.
+pub const VOID_DAMAGE: f32 = 4.0;
+
+/// What hurt a player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageSource {
+    /// Falling out of the bottom of the world.
+    Void,
+    /// Having an empty food bar.
+    Starvation,
+    /// Being hit by another player.
+    Player(ConnectionId),
+}
+
+impl DamageSource {
+    /// Whether it hurts players in creative too. Nothing hurts spectators.
+    pub fn bypasses_creative(self) -> bool {
+        self == DamageSource::Void
+    }
+
+    /// The player that did it, if it was one.
+    pub fn attacker(self) -> Option<ConnectionId> {
+        match self {
+            DamageSource::Player(attacker) => Some(attacker),
+            _ => None,
+        }
+    }
+}
+
+impl ServerState {
+    /// Sends a player their health and food bars.
+    pub async fn send_health(&self, conn_id: ConnectionId) -> Result<()> {
+        let component_storage = self.world.get_component_storage();
+        let packet = {
+            let health = component_storage
+                .get_mut_or_insert_with(conn_id, Health::default)
+                .await
+                .health;
+            let food = component_storage
+                .get_mut_or_insert_with(conn_id, Food::default)
+                .await;
+            SetHealth::new(health, &food)
+        };
+        let conn = self.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(packet).await
+    }
+
+    /// Hurts a player, down to 0 health at the least, and shows everyone near them that they were
+    /// hurt. A player that's killed by it gets a [PlayerDeathEvent], and the death screen.
+    ///
+    /// Players in spectator can't be hurt, and neither can players in creative unless the source
+    /// [bypasses it](DamageSource::bypasses_creative). Players that are already dead aren't hurt
+    /// again.
+    pub async fn damage_player(
+        self: &Arc<Self>,
+        conn_id: ConnectionId,
+        amount: f32,
+        source: DamageSource,
+    ) -> Result<()> {
+        let state = self;
+        let component_storage = state.world.get_component_storage();
+        let game_mode = component_storage
+            .get::<GameMode>(conn_id)
+            .await
+            .map(|game_mode| *game_mode)
+            .unwrap_or_default();
+        let invulnerable = match game_mode {
+            GameMode::Spectator => true,
+            GameMode::Creative => !source.bypasses_creative(),
+            GameMode::Survival | GameMode::Adventure => false,
+        };
+        if invulnerable {
+            return Ok(());
+        }
+
+        let (health, died) = {
+            let mut health = component_storage
+                .get_mut_or_insert_with(conn_id, Health::default)
+                .await;
+            if health.is_dead() {
+                return Ok(());
+            }
+            let died = health.damage(amount);
+            (health.health, died)
+        };
+        debug!(
+            "{} took {} damage from {:?}, leaving {} health",
+            conn_id, amount, source, health
+        );
+
+        let position = component_storage.get::<Position>(conn_id).await?.clone();
+        let yaw = match source.attacker() {
+            Some(attacker) => {
+                let attacker_position = component_storage.get::<Position>(attacker).await?.clone();
+                let victim_yaw = component_storage.get::<Rotation>(conn_id).await?.yaw;
+                hurt_direction(&attacker_position, &position, victim_yaw)
+            }
+            None => 0.0,
+        };
+        let network_id = state.network_id(conn_id).await?;
+        let dimension = state.dimension_of(conn_id).await;
+        state
+            .send_to_players_near(&dimension, &position, None, || {
+                HurtAnimation::new(network_id, yaw)
+            })
+            .await;
+        state.send_health(conn_id).await?;
+
+        if died {
+            state
+                .dispatch_event(PlayerDeathEvent::new(conn_id, source))
+                .await;
+            kill_player(conn_id, source, state).await?;
+        }
+        Ok(())
+    }
+}
+
+/// The message everyone's told when a player dies.
+async fn death_message(
+    victim: ConnectionId,
+    source: DamageSource,
+    state: &GlobalState,
+) -> Result<String> {
+    let component_storage = state.world.get_component_storage();
+    let victim_name = component_storage
+        .get::<Player>(victim)
+        .await?
+        .username
+        .clone();
+    Ok(match source {
+        DamageSource::Void => format!("{} fell out of the world", victim_name),
+        DamageSource::Starvation => format!("{} starved to death", victim_name),
+        DamageSource::Player(killer) => {
+            let killer_name = component_storage
+                .get::<Player>(killer)
+                .await?
+                .username
+                .clone();
+            format!("{} was slain by {}", victim_name, killer_name)
+        }
+    })
+}
+
+/// Shows everyone near `victim` that they died, tells everyone how, and shows the victim the
+/// death screen. They stay dead until they click respawn, see
+/// [ClientStatus](crate::net::packets::incoming::client_status::ClientStatus).
+pub async fn kill_player(
+    victim: ConnectionId,
+    source: DamageSource,
+    state: &GlobalState,
+) -> Result<()> {
+    let position = state
+        .world
+        .get_component_storage()
+        .get::<Position>(victim)
+        .await?
+        .clone();
+    let network_id = state.network_id(victim).await?;
+    let dimension = state.dimension_of(victim).await;
+    state
+        .send_to_players_near(&dimension, &position, Some(victim), || {
+            EntityEvent::new(network_id, ENTITY_DEATH)
+        })
+        .await;
+
+    let message = death_message(victim, source, state).await?;
+    state.broadcast_message(message.clone()).await?;
+
+    let conn = state.connections.get_connection(victim)?;
+    let conn = conn.read().await;
+    conn.send_packet(CombatDeath::new(network_id, message)?)
+        .await
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::net::packets::outgoing::initialize_world_border::InitializeWorldBorder;
+use crate::net::packets::outgoing::respawn::{Respawn, KEEP_METADATA};
+use crate::net::packets::outgoing::set_entity_metadata::SetEntityMetadata;
+use crate::net::packets::ConnectionId;
+use crate::net::systems::chunk_sender::{view_distance, ChunkSender};
+use crate::net::utils::metadata::player_metadata;
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::net::utils::spawn::{spawn_player, spawn_position};
+use crate::net::utils::visibility::update_visible_players;
+use crate::state::ServerState;
+use crate::utils::components::client_settings::ClientSettings;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::flying::Flying;
+use crate::utils::components::game_mode::{GameMode, PreviousGameMode};
+use crate::utils::components::loaded_chunks::LoadedChunks;
+use crate::utils::components::visible_entities::VisibleEntities;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::world::dimensions::Dimension;
+
+impl ServerState {
+    /// Moves a player to spawn in the dimension called `dimension`, which is also how they
+    /// respawn after dying. Fails if there's no dimension called that.
+    ///
+    /// The client throws away its world and player when it's sent the Respawn, so the player's
+    /// abilities, world border, position, time, chunks and the players around them are all sent
+    /// again. `data_kept` is what the client holds on to, see
+    /// [KEEP_ATTRIBUTES](crate::net::packets::outgoing::respawn::KEEP_ATTRIBUTES) and
+    /// [KEEP_METADATA]. The player's own metadata is sent again if it isn't kept. Attributes
+    /// aren't sent to begin with, so there's nothing to send again for those.
+    pub async fn move_player_to_dimension(
+        self: &Arc<Self>,
+        conn_id: ConnectionId,
+        dimension: &str,
+        data_kept: u8,
+    ) -> Result<()> {
+        let Some(dimension) = self.dimensions.get(dimension).cloned() else {
+            return Err(Error::Generic(format!("Unknown dimension {}", dimension)));
+        };
+        let component_storage = self.world.get_component_storage();
+        self.enter_dimension(conn_id, &dimension).await?;
+
+        let gamemode = component_storage
+            .get::<GameMode>(conn_id)
+            .await
+            .map(|game_mode| *game_mode)
+            .unwrap_or_default();
+        let previous_gamemode = component_storage
+            .get::<PreviousGameMode>(conn_id)
+            .await
+            .map(|previous| previous.0)
+            .ok();
+        // Spectators keep flying, same as when they switched.
+        component_storage
+            .get_mut_or_insert_with(conn_id, Flying::default)
+            .await
+            .set_flying(gamemode.is_always_flying());
+        // The client drops all its chunks and entities.
+        component_storage.insert(conn_id, LoadedChunks::default());
+        component_storage.insert(conn_id, VisibleEntities::default());
+
+        let mut packet_queue = PacketQueue::new();
+        packet_queue
+            .queue(Respawn::new(
+                &dimension,
+                gamemode,
+                previous_gamemode,
+                data_kept,
+            ))
+            .await?;
+        packet_queue
+            .queue(InitializeWorldBorder::new(
+                &self.border.get(),
+                Instant::now(),
+            ))
+            .await?;
+        packet_queue
+            .queue(self.abilities(gamemode, gamemode.is_always_flying()))
+            .await?;
+        spawn_player(conn_id, self, &mut packet_queue).await?;
+        if data_kept & KEEP_METADATA == 0 {
+            let metadata = player_metadata(conn_id, self).await;
+            packet_queue
+                .queue(SetEntityMetadata::new(self.network_id(conn_id).await?, metadata))
+                .await?;
+        }
+        {
+            let conn = self.connections.get_connection(conn_id)?;
+            let conn = conn.read().await;
+            conn.send_packets(packet_queue).await?;
+        }
+        self.send_time(conn_id).await?;
+        self.send_weather(conn_id).await?;
+
+        update_visible_players(conn_id, self).await?;
+        let spawn = spawn_position();
+        ChunkSender::send_chunks_to_player_if_needed(
+            self.clone(),
+            conn_id,
+            (spawn.x >> 4, spawn.z >> 4),
+        )
+        .await
+    }
+
+    /// Takes a player out of the dimension they were in, and puts them in `dimension` where they
+    /// are now. They're moved to its spawn afterwards, see [spawn_player].
+    async fn enter_dimension(
+        &self,
+        conn_id: ConnectionId,
+        dimension: &Arc<Dimension>,
+    ) -> Result<()> {
+        self.dimension_of(conn_id).await.players.remove(conn_id);
+        let position = self.world.get_component::<Position>(conn_id).await?.clone();
+        let settings = self
+            .world
+            .get_component::<ClientSettings>(conn_id)
+            .await
+            .ok();
+        let view_distance = view_distance(settings.as_deref());
+        drop(settings);
+        self.world
+            .get_component_storage()
+            .insert(conn_id, CurrentDimension(dimension.clone()));
+        dimension.players.insert(conn_id, &position, view_distance);
+        Ok(())
+    }
+}
@@ -0,0 +1,621 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::net::packets::outgoing::remove_entities::RemoveEntities;
+use crate::net::packets::outgoing::set_container_content::SetContainerContent;
+use crate::net::packets::outgoing::set_entity_metadata::SetEntityMetadata;
+use crate::net::packets::outgoing::spawn_entity::{SpawnEntity, ITEM_ENTITY_TYPE};
+use crate::net::packets::outgoing::take_item_entity::TakeItemEntity;
+use crate::net::packets::ConnectionId;
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::net::utils::visibility::{hide_from_everyone, players_in_world};
+use crate::state::{GlobalState, ServerState};
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::health::Health;
+use crate::utils::components::held_item::HeldItem;
+use crate::utils::components::inventory::{Inventory, PLAYER_WINDOW_ID};
+use crate::utils::components::item_entity::{
+    ItemEntity, BLOCK_DROP_PICKUP_DELAY, THROWN_PICKUP_DELAY,
+};
+use crate::utils::components::last_sent_movement::LastSentMovement;
+use crate::utils::components::network_id::NetworkId;
+use crate::utils::components::physics_body::PhysicsBody;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::components::velocity::Velocity;
+use crate::utils::components::visible_entities::VisibleEntities;
+use crate::utils::encoding::entity_metadata::{item, EntityMetadata, MetadataValue};
+use crate::utils::encoding::position::Position;
+use crate::utils::encoding::slot::ItemStack;
+use crate::utils::prelude::*;
+use crate::world::dimensions::Dimension;
+use crate::world::physics::BoundingBox;
+
+/// The item each block drops when it's broken, by the block's name. There's no item registry
+/// yet, so only the most common blocks are in here, and everything else drops nothing.
+const BLOCK_DROPS: &[(&str, i32)] = &[
+    ("minecraft:stone", 22),
+    ("minecraft:granite", 2),
+    ("minecraft:polished_granite", 3),
+    ("minecraft:diorite", 4),
+    ("minecraft:polished_diorite", 5),
+    ("minecraft:andesite", 6),
+    ("minecraft:polished_andesite", 7),
+    ("minecraft:deepslate", 9),
+    ("minecraft:cobbled_deepslate", 9),
+    ("minecraft:polished_deepslate", 10),
+    ("minecraft:calcite", 11),
+    ("minecraft:tuff", 12),
+    ("minecraft:dripstone_block", 13),
+    ("minecraft:grass_block", 15),
+    ("minecraft:dirt", 15),
+    ("minecraft:coarse_dirt", 16),
+    ("minecraft:podzol", 15),
+    ("minecraft:rooted_dirt", 18),
+    ("minecraft:mud", 19),
+    ("minecraft:cobblestone", 22),
+    ("minecraft:oak_planks", 23),
+    ("minecraft:spruce_planks", 24),
+    ("minecraft:birch_planks", 25),
+    ("minecraft:jungle_planks", 26),
+    ("minecraft:acacia_planks", 27),
+    ("minecraft:cherry_planks", 28),
+    ("minecraft:dark_oak_planks", 29),
+    ("minecraft:mangrove_planks", 30),
+    ("minecraft:bamboo_planks", 31),
+    ("minecraft:crimson_planks", 32),
+    ("minecraft:warped_planks", 33),
+    ("minecraft:bamboo_mosaic", 34),
+    ("minecraft:sand", 44),
+    ("minecraft:red_sand", 47),
+    ("minecraft:gravel", 48),
+];
+
+/// The block each item places, by the item's id. Like [BLOCK_DROPS], only the most common blocks
+/// are in here, and every other item doesn't place anything.
+const BLOCK_ITEMS: &[(i32, &str)] = &[
+    (1, "minecraft:stone"),
+    (2, "minecraft:granite"),
+    (3, "minecraft:polished_granite"),
+    (4, "minecraft:diorite"),
+    (5, "minecraft:polished_diorite"),
+    (6, "minecraft:andesite"),
+    (7, "minecraft:polished_andesite"),
+    (8, "minecraft:deepslate"),
+    (9, "minecraft:cobbled_deepslate"),
+    (10, "minecraft:polished_deepslate"),
+    (11, "minecraft:calcite"),
+    (12, "minecraft:tuff"),
+    (13, "minecraft:dripstone_block"),
+    (14, "minecraft:grass_block"),
+    (15, "minecraft:dirt"),
+    (16, "minecraft:coarse_dirt"),
+    (17, "minecraft:podzol"),
+    (18, "minecraft:rooted_dirt"),
+    (19, "minecraft:mud"),
+    (20, "minecraft:crimson_nylium"),
+    (21, "minecraft:warped_nylium"),
+    (22, "minecraft:cobblestone"),
+    (23, "minecraft:oak_planks"),
+    (24, "minecraft:spruce_planks"),
+    (25, "minecraft:birch_planks"),
+    (26, "minecraft:jungle_planks"),
+    (27, "minecraft:acacia_planks"),
+    (28, "minecraft:cherry_planks"),
+    (29, "minecraft:dark_oak_planks"),
+    (30, "minecraft:mangrove_planks"),
+    (31, "minecraft:bamboo_planks"),
+    (32, "minecraft:crimson_planks"),
+    (33, "minecraft:warped_planks"),
+    (34, "minecraft:bamboo_mosaic"),
+    (43, "minecraft:bedrock"),
+    (44, "minecraft:sand"),
+    (47, "minecraft:red_sand"),
+    (48, "minecraft:gravel"),
+];
+
+/// How far below their eyes players throw items from, same as vanilla.
+const THROW_HEIGHT: f64 = 1.62 - 0.3;
+/// How fast players throw items, in blocks per tick.
+const THROW_SPEED: f64 = 0.3;
+/// How far players reach for items around them, on top of their hitbox, same as vanilla.
+const PICKUP_REACH: (f64, f64, f64) = (1.0, 0.5, 1.0);
+/// No player more than this many blocks from an item can reach it.
+const PICKUP_SEARCH_RADIUS: f64 = 4.0;
+
+const PLAYER_WIDTH: f64 = 0.6;
+const PLAYER_HEIGHT: f64 = 1.8;
+
+/// What breaking `block_name` drops, or `None` if it drops nothing, see [BLOCK_DROPS].
+pub fn block_drop(block_name: &str) -> Option<ItemStack> {
+    BLOCK_DROPS
+        .iter()
+        .find(|(name, _)| *name == block_name)
+        .map(|&(_, item_id)| ItemStack::new(item_id, 1))
+}
+
+/// The block `item` places, or `None` if it doesn't place one, see [BLOCK_ITEMS].
+pub fn placed_block(item: &ItemStack) -> Option<&'static str> {
+    BLOCK_ITEMS
+        .iter()
+        .find(|(item_id, _)| *item_id == item.item_id)
+        .map(|&(_, name)| name)
+}
+
+/// How fast a player facing `yaw` and `pitch`, in degrees, throws an item: forwards, and a bit
+/// up. Vanilla spreads them out a little at random on top of this.
+pub fn throw_velocity(yaw: f32, pitch: f32) -> Velocity {
+    let (yaw, pitch) = ((yaw as f64).to_radians(), (pitch as f64).to_radians());
+    Velocity::new(
+        -yaw.sin() * pitch.cos() * THROW_SPEED,
+        -pitch.sin() * THROW_SPEED + 0.1,
+        yaw.cos() * pitch.cos() * THROW_SPEED,
+    )
+}
+
+/// Where a player at `position` can pick up items. Positions are only tracked to the block, so
+/// the player is taken to be in the middle of theirs.
+pub fn pickup_area(position: &Position) -> BoundingBox {
+    let (x, y, z) = (
+        position.x as f64 + 0.5,
+        position.y as f64,
+        position.z as f64 + 0.5,
+    );
+    let half_width = PLAYER_WIDTH / 2.0;
+    BoundingBox {
+        min: [x - half_width, y, z - half_width],
+        max: [x + half_width, y + PLAYER_HEIGHT, z + half_width],
+    }
+    .grown(PICKUP_REACH)
+}
+
+/// The metadata clients need to draw an item as `stack`.
+pub fn item_metadata(stack: &ItemStack) -> EntityMetadata {
+    EntityMetadata::new().with(item::ITEM, MetadataValue::Slot(stack.clone().into()))
+}
+
+impl ServerState {
+    /// Spawns `stack` as an item at `position` in `dimension`, going at `velocity`. Players
+    /// can't pick it up for `pickup_delay` ticks. Returns its entity id.
+    ///
+    /// Players near it are sent it on the next tick, see [update_visible_items].
+    pub async fn drop_item(
+        &self,
+        dimension: Arc<Dimension>,
+        position: (f64, f64, f64),
+        velocity: Velocity,
+        stack: ItemStack,
+        pickup_delay: u32,
+    ) -> usize {
+        let (x, y, z) = position;
+        let rotation = Rotation::new(0.0, 0.0);
+        let item = ItemEntity::new(Uuid::new_v4().as_u128(), stack, pickup_delay);
+        let entity_id = self
+            .world
+            .create_entity()
+            .await
+            .with(item)
+            .with(PhysicsBody::item(position))
+            .with(velocity)
+            .with(Position::new(
+                x.floor() as i32,
+                y.floor() as i16,
+                z.floor() as i32,
+            ))
+            .with(LastSentMovement::exact(position, &rotation))
+            .with(rotation)
+            .with(CurrentDimension(dimension))
+            .build();
+        let network_id = self.entity_ids.allocate(entity_id);
+        self.world
+            .get_component_storage()
+            .insert(entity_id, NetworkId::new(network_id));
+        entity_id
+    }
+
+    /// Drops what breaking `block_name` at `location` drops, if anything, see [block_drop]. It
+    /// pops out somewhere around the middle of the block, like vanilla.
+    pub async fn drop_block(
+        &self,
+        dimension: Arc<Dimension>,
+        location: &Position,
+        block_name: &str,
+    ) {
+        let Some(stack) = block_drop(block_name) else {
+            return;
+        };
+        let offset = || rand::random::<f64>() * 0.5 - 0.25;
+        let position = (
+            location.x as f64 + 0.5 + offset(),
+            // Less half the item's height, so it's centered on the block.
+            location.y as f64 + 0.5 + offset() - 0.125,
+            location.z as f64 + 0.5 + offset(),
+        );
+        let spread = || rand::random::<f64>() * 0.2 - 0.1;
+        let velocity = Velocity::new(spread(), 0.2, spread());
+        self.drop_item(
+            dimension,
+            position,
+            velocity,
+            stack,
+            BLOCK_DROP_PICKUP_DELAY,
+        )
+        .await;
+    }
+}
+
+/// Throws one of the items the player is holding, or the whole stack if `whole_stack`, in the
+/// direction they're facing. The client already took it out of its inventory. Does nothing if
+/// they're not holding anything or are a spectator.
+pub async fn throw_held_item(
+    conn_id: ConnectionId,
+    whole_stack: bool,
+    state: &GlobalState,
+) -> Result<()> {
+    if state.is_spectator(conn_id).await {
+        return Ok(());
+    }
+    let component_storage = state.world.get_component_storage();
+    let selected = component_storage
+        .get::<HeldItem>(conn_id)
+        .await
+        .map(|held_item| held_item.slot)
+        .unwrap_or_default();
+    let Some(stack) = component_storage
+        .get_mut_or_insert_with(conn_id, Inventory::default)
+        .await
+        .take_from_hotbar(selected, whole_stack)
+    else {
+        debug!("Connection {} threw an item it doesn't have", conn_id);
+        return Ok(());
+    };
+    state.update_equipment(conn_id).await;
+
+    let position = component_storage.get::<Position>(conn_id).await?.clone();
+    let rotation = component_storage.get::<Rotation>(conn_id).await?.clone();
+    let from = (
+        position.x as f64 + 0.5,
+        position.y as f64 + THROW_HEIGHT,
+        position.z as f64 + 0.5,
+    );
+    let dimension = state.dimension_of(conn_id).await;
+    state
+        .drop_item(
+            dimension,
+            from,
+            throw_velocity(rotation.yaw, rotation.pitch),
+            stack,
+            THROWN_PICKUP_DELAY,
+        )
+        .await;
+    Ok(())
+}
+
+/// Removes an item from the world, and from every player that can see it.
+pub async fn remove_item(entity_id: usize, state: &GlobalState) -> Result<()> {
+    hide_from_everyone(entity_id, state).await;
+    // Only once everyone was told to remove it, so nobody mixes it up with whatever gets the id
+    // next.
+    if let Ok(network_id) = state.network_id(entity_id).await {
+        state.entity_ids.release(network_id);
+    }
+    state.world.delete_entity(entity_id).await
+}
+
+/// Ages every item by a tick, and removes the ones that have been around for too long, see
+/// [ItemEntity::tick].
+pub async fn age_items(state: &GlobalState) {
+    let despawned = state
+        .world
+        .get_component_storage()
+        .query_mut::<ItemEntity>()
+        .await
+        .into_iter()
+        .filter_map(|(id, mut item)| item.tick().then_some(id))
+        .collect::<Vec<_>>();
+    for entity_id in despawned {
+        if let Err(e) = remove_item(entity_id, state).await {
+            warn!("Failed to despawn item {}: {}", entity_id, e);
+        }
+    }
+}
+
+/// Spawns items for the players that can see them now, and removes the ones they can't see
+/// anymore, e.g. because either of them moved. Players can see items in any chunk within their
+/// view distance.
+///
+/// Each player's [VisibleEntities] keeps track of which items they've been sent, alongside the
+/// players they've been sent, see [crate::net::utils::visibility::update_visible_players].
+pub async fn update_visible_items(state: &GlobalState) {
+    let items = state
+        .world
+        .query::<(&ItemEntity, &Position, &CurrentDimension)>()
+        .iter()
+        .await
+        .map(|(id, (_, position, dimension))| {
+            (id, dimension.0.clone(), (position.x >> 4, position.z >> 4))
+        })
+        .collect::<Vec<_>>();
+    let item_ids = items.iter().map(|(id, _, _)| *id).collect::<HashSet<_>>();
+
+    let mut in_view = HashMap::<ConnectionId, HashSet<usize>>::new();
+    for (entity_id, dimension, (chunk_x, chunk_z)) in &items {
+        for viewer in dimension.players.players_viewing_chunk(*chunk_x, *chunk_z) {
+            in_view.entry(viewer).or_default().insert(*entity_id);
+        }
+    }
+
+    let component_storage = state.world.get_component_storage();
+    for viewer in players_in_world(state).await {
+        let in_view = in_view.remove(&viewer).unwrap_or_default();
+        let (spawned, removed) = {
+            let Ok(mut visible) = component_storage.get_mut::<VisibleEntities>(viewer).await else {
+                continue;
+            };
+            let spawned = in_view
+                .iter()
+                .copied()
+                .filter(|id| !visible.contains(*id))
+                .collect::<Vec<_>>();
+            let removed = visible
+                .entities
+                .iter()
+                .copied()
+                .filter(|id| item_ids.contains(id) && !in_view.contains(id))
+                .collect::<Vec<_>>();
+            visible.entities.extend(&spawned);
+            for id in &removed {
+                visible.entities.remove(id);
+            }
+            (spawned, removed)
+        };
+        if let Err(e) = show_and_hide_items(viewer, &spawned, &removed, state).await {
+            warn!("Failed to update the items {} can see: {}", viewer, e);
+        }
+    }
+}
+
+/// Sends `viewer` the items in `spawned`, and removes the ones in `removed`.
+async fn show_and_hide_items(
+    viewer: ConnectionId,
+    spawned: &[usize],
+    removed: &[usize],
+    state: &GlobalState,
+) -> Result<()> {
+    if spawned.is_empty() && removed.is_empty() {
+        return Ok(());
+    }
+
+    let component_storage = state.world.get_component_storage();
+    let mut packet_queue = PacketQueue::new();
+    if !removed.is_empty() {
+        let mut network_ids = Vec::with_capacity(removed.len());
+        for &id in removed {
+            if let Ok(network_id) = state.network_id(id).await {
+                network_ids.push(network_id);
+            }
+        }
+        packet_queue
+            .queue(RemoveEntities::new(&network_ids))
+            .await?;
+    }
+    for &entity_id in spawned {
+        let Ok(network_id) = state.network_id(entity_id).await else {
+            continue;
+        };
+        let Ok(item) = component_storage.get::<ItemEntity>(entity_id).await else {
+            continue;
+        };
+        let (uuid, metadata) = (item.uuid, item_metadata(&item.stack));
+        drop(item);
+        let movement = *component_storage.get::<LastSentMovement>(entity_id).await?;
+        let velocity = component_storage
+            .get::<Velocity>(entity_id)
+            .await
+            .map(|velocity| *velocity)
+            .unwrap_or_default();
+        packet_queue
+            .queue(SpawnEntity::new(
+                network_id,
+                uuid,
+                ITEM_ENTITY_TYPE,
+                &movement,
+                &velocity,
+            ))
+            .await?;
+        packet_queue
+            .queue(SetEntityMetadata::new(network_id, metadata))
+            .await?;
+    }
+
+    let conn = state.connections.get_connection(viewer)?;
+    let conn = conn.read().await;
+    conn.send_packets(packet_queue).await
+}
+
+/// Has players pick up the items they're close enough to, see [pickup_area]. Items that are
+/// still in their [ItemEntity::pickup_delay] are left alone.
+pub async fn pick_up_items(state: &GlobalState) {
+    let items = state
+        .world
+        .query::<(&ItemEntity, &PhysicsBody, &CurrentDimension)>()
+        .iter()
+        .await
+        .filter(|(_, (item, _, _))| item.can_be_picked_up())
+        .map(|(id, (_, body, dimension))| (id, BoundingBox::of(&body), dimension.0.clone()))
+        .collect::<Vec<_>>();
+
+    for (entity_id, bounds, dimension) in items {
+        let (x, y, z) = (bounds.min[0], bounds.min[1], bounds.min[2]);
+        let position = Position::new(x.floor() as i32, y.floor() as i16, z.floor() as i32);
+        let nearby = dimension
+            .players
+            .players_in_radius(&position, PICKUP_SEARCH_RADIUS);
+        for (conn_id, player_position) in nearby {
+            if !pickup_area(&player_position).intersects(&bounds)
+                || !can_pick_up(conn_id, state).await
+            {
+                continue;
+            }
+            match pick_up(entity_id, conn_id, &dimension, state).await {
+                // It's gone, so there's nothing left for anyone else to pick up.
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(e) => warn!(
+                    "Failed to have {} pick up item {}: {}",
+                    conn_id, entity_id, e
+                ),
+            }
+        }
+    }
+}
+
+/// Whether `conn_id` can pick up items: they've joined the world, and aren't a spectator or dead.
+async fn can_pick_up(conn_id: ConnectionId, state: &GlobalState) -> bool {
+    let component_storage = state.world.get_component_storage();
+    let joined_world = component_storage
+        .get::<VisibleEntities>(conn_id)
+        .await
+        .is_ok();
+    let dead = component_storage
+        .get::<Health>(conn_id)
+        .await
+        .is_ok_and(|health| health.is_dead());
+    joined_world && !dead && !state.is_spectator(conn_id).await
+}
+
+/// Puts as much of the item `entity_id` as fits into the inventory of `conn_id`, and shows
+/// everyone nearby them picking it up. Returns whether all of it was picked up, in which case
+/// the item is removed. What doesn't fit is left lying there.
+async fn pick_up(
+    entity_id: usize,
+    conn_id: ConnectionId,
+    dimension: &Dimension,
+    state: &GlobalState,
+) -> Result<bool> {
+    let component_storage = state.world.get_component_storage();
+    let stack = component_storage
+        .get::<ItemEntity>(entity_id)
+        .await?
+        .stack
+        .clone();
+    let (left, packet) = {
+        let mut inventory = component_storage
+            .get_mut_or_insert_with(conn_id, Inventory::default)
+            .await;
+        let left = inventory.add(stack.clone()).0;
+        if left.as_ref().is_some_and(|left| left.count == stack.count) {
+            return Ok(false);
+        }
+        let packet = SetContainerContent::from_inventory(PLAYER_WINDOW_ID, &mut inventory);
+        (left, packet)
+    };
+    state.update_equipment(conn_id).await;
+    let conn = state.connections.get_connection(conn_id)?;
+    conn.read().await.send_packet(packet).await?;
+
+    let picked_up = stack.count - left.as_ref().map_or(0, |left| left.count);
+    let item_id = state.network_id(entity_id).await?;
+    let collector_id = state.network_id(conn_id).await?;
+    let position = component_storage.get::<Position>(entity_id).await?.clone();
+    // The collector sees themselves pick it up too.
+    state
+        .send_to_players_near(dimension, &position, None, || {
+            TakeItemEntity::new(item_id, collector_id, picked_up)
+        })
+        .await;
+
+    let Some(left) = left else {
+        remove_item(entity_id, state).await?;
+        return Ok(true);
+    };
+    let metadata = item_metadata(&left);
+    component_storage
+        .get_mut::<ItemEntity>(entity_id)
+        .await?
+        .stack = left;
+    state
+        .send_to_players_near(dimension, &position, None, || {
+            SetEntityMetadata::new(item_id, metadata.clone())
+        })
+        .await;
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} isn't {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_block_drops() {
+        assert_eq!(
+            block_drop("minecraft:oak_planks"),
+            Some(ItemStack::new(23, 1))
+        );
+        // Stone drops cobblestone, and grass drops dirt, without Silk Touch.
+        assert_eq!(
+            block_drop("minecraft:stone"),
+            block_drop("minecraft:cobblestone")
+        );
+        assert_eq!(
+            block_drop("minecraft:grass_block"),
+            block_drop("minecraft:dirt")
+        );
+        assert_eq!(block_drop("minecraft:air"), None);
+        assert_eq!(block_drop("minecraft:bedrock"), None);
+    }
+
+    #[test]
+    fn test_placed_blocks() {
+        assert_eq!(placed_block(&ItemStack::new(1, 1)), Some("minecraft:stone"));
+        // Blocks that drop themselves are placed again by what they drop.
+        for name in ["minecraft:dirt", "minecraft:oak_planks", "minecraft:gravel"] {
+            assert_eq!(placed_block(&block_drop(name).unwrap()), Some(name));
+        }
+        // Not a block, like a diamond sword.
+        assert_eq!(placed_block(&ItemStack::new(818, 1)), None);
+    }
+
+    #[test]
+    fn test_items_are_thrown_forwards() {
+        // Facing south, level.
+        let velocity = throw_velocity(0.0, 0.0);
+        assert_close(velocity.x, 0.0);
+        assert_close(velocity.y, 0.1);
+        assert_close(velocity.z, 0.3);
+        // Facing west, so towards -x.
+        let velocity = throw_velocity(90.0, 0.0);
+        assert_close(velocity.x, -0.3);
+        assert_close(velocity.z, 0.0);
+        // Straight up.
+        let velocity = throw_velocity(0.0, -90.0);
+        assert_close(velocity.x, 0.0);
+        assert_close(velocity.y, 0.4);
+        assert_close(velocity.z, 0.0);
+    }
+
+    #[test]
+    fn test_items_within_a_block_are_picked_up() {
+        let area = pickup_area(&Position::new(0, 64, 0));
+        let item_at = |x, y, z| BoundingBox::of(&PhysicsBody::item((x, y, z)));
+
+        assert!(area.intersects(&item_at(0.5, 64.0, 0.5)));
+        // Just over a block to the side, and a bit below their feet.
+        assert!(area.intersects(&item_at(-0.9, 64.0, 0.5)));
+        assert!(area.intersects(&item_at(0.5, 63.3, 0.5)));
+        assert!(!area.intersects(&item_at(2.1, 64.0, 0.5)));
+        assert!(!area.intersects(&item_at(0.5, 62.0, 0.5)));
+        assert!(!area.intersects(&item_at(0.5, 66.5, 0.5)));
+    }
+}
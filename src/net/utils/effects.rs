@@ -0,0 +1,180 @@
+use crate::net::packets::outgoing::entity_effect::EntityEffect;
+use crate::net::packets::outgoing::remove_entity_effect::RemoveEntityEffect;
+use crate::net::packets::outgoing::update_attributes::{
+    Attribute, AttributeModifier, UpdateAttributes, MULTIPLY_TOTAL,
+};
+use crate::net::packets::ConnectionId;
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::state::ServerState;
+use crate::utils::components::active_effects::{ActiveEffect, ActiveEffects, Effect};
+use crate::utils::components::sprinting::Sprinting;
+use crate::utils::prelude::*;
+
+pub const MOVEMENT_SPEED: &str = "minecraft:generic.movement_speed";
+/// How fast players walk without anything changing it.
+pub const BASE_MOVEMENT_SPEED: f64 = 0.1;
+
+/// The modifiers vanilla uses, so they line up with the ones the client adds by itself.
+const SPRINTING_MODIFIER: u128 = 0x662A6B8D_DA3E_4C1C_8813_96EA6097278D;
+const SPEED_MODIFIER: u128 = 0x91AEAA56_376B_4498_935B_2F7F68070635;
+const SLOWNESS_MODIFIER: u128 = 0x7107DE5E_7CE8_4030_940E_514C1F160890;
+
+/// How much faster sprinting, and each level of speed, make players, and how much slower each
+/// level of slowness does.
+const SPRINTING_BOOST: f64 = 0.3;
+const SPEED_PER_LEVEL: f64 = 0.2;
+const SLOWNESS_PER_LEVEL: f64 = -0.15;
+
+/// The movement speed modifiers from sprinting and the effects in `effects`.
+pub fn movement_speed_modifiers(
+    effects: &ActiveEffects,
+    sprinting: bool,
+) -> Vec<AttributeModifier> {
+    let mut modifiers = Vec::new();
+    if sprinting {
+        modifiers.push(AttributeModifier {
+            uuid: SPRINTING_MODIFIER,
+            amount: SPRINTING_BOOST,
+            operation: MULTIPLY_TOTAL,
+        });
+    }
+    for (effect, uuid, per_level) in [
+        (Effect::Speed, SPEED_MODIFIER, SPEED_PER_LEVEL),
+        (Effect::Slowness, SLOWNESS_MODIFIER, SLOWNESS_PER_LEVEL),
+    ] {
+        if let Some(active) = effects.get(effect) {
+            modifiers.push(AttributeModifier {
+                uuid,
+                amount: per_level * (active.amplifier as f64 + 1.0),
+                operation: MULTIPLY_TOTAL,
+            });
+        }
+    }
+    modifiers
+}
+
+impl ServerState {
+    /// Puts `effect` on a player for `duration` ticks. `amplifier` is one less than its level.
+    ///
+    /// Same as vanilla, nothing happens if they already have it stronger, or just as strong for
+    /// longer. Returns whether it was applied.
+    pub async fn apply_effect(
+        &self,
+        conn_id: ConnectionId,
+        effect: Effect,
+        amplifier: u8,
+        duration: i32,
+    ) -> Result<bool> {
+        let active = ActiveEffect::new(effect, amplifier, duration);
+        let applied = self
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with(conn_id, ActiveEffects::default)
+            .await
+            .apply(active);
+        if !applied {
+            return Ok(false);
+        }
+
+        let mut packet_queue = PacketQueue::new();
+        packet_queue
+            .queue(EntityEffect::new(self.network_id(conn_id).await?, &active))
+            .await?;
+        if effect.changes_movement_speed() {
+            packet_queue
+                .queue(self.movement_speed(conn_id).await?)
+                .await?;
+        }
+        let conn = self.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packets(packet_queue).await?;
+        Ok(true)
+    }
+
+    /// Takes `effects` off a player's client, which already have been taken off on the server,
+    /// e.g. because they ran out.
+    pub async fn send_removed_effects(
+        &self,
+        conn_id: ConnectionId,
+        effects: &[Effect],
+    ) -> Result<()> {
+        let network_id = self.network_id(conn_id).await?;
+        let mut packet_queue = PacketQueue::new();
+        for &effect in effects {
+            packet_queue
+                .queue(RemoveEntityEffect::new(network_id, effect))
+                .await?;
+        }
+        if effects.iter().any(|effect| effect.changes_movement_speed()) {
+            packet_queue
+                .queue(self.movement_speed(conn_id).await?)
+                .await?;
+        }
+        let conn = self.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packets(packet_queue).await
+    }
+
+    /// Takes `effect` off a player. Returns `false` if they didn't have it.
+    pub async fn remove_effect(&self, conn_id: ConnectionId, effect: Effect) -> Result<bool> {
+        let removed = self
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with(conn_id, ActiveEffects::default)
+            .await
+            .remove(effect);
+        if removed {
+            self.send_removed_effects(conn_id, &[effect]).await?;
+        }
+        Ok(removed)
+    }
+
+    /// The player's movement speed with everything changing it, so the client moves them as
+    /// fast as the server thinks they go.
+    async fn movement_speed(&self, conn_id: ConnectionId) -> Result<UpdateAttributes> {
+        let component_storage = self.world.get_component_storage();
+        let sprinting = component_storage
+            .get::<Sprinting>(conn_id)
+            .await
+            .is_ok_and(|sprinting| sprinting.is_sprinting);
+        let modifiers = match component_storage.get::<ActiveEffects>(conn_id).await {
+            Ok(effects) => movement_speed_modifiers(&effects, sprinting),
+            Err(_) => movement_speed_modifiers(&ActiveEffects::default(), sprinting),
+        };
+        Ok(UpdateAttributes::new(
+            self.network_id(conn_id).await?,
+            vec![Attribute::new(
+                MOVEMENT_SPEED,
+                BASE_MOVEMENT_SPEED,
+                modifiers,
+            )],
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speed_and_slowness_modifiers() {
+        let mut effects = ActiveEffects::default();
+        assert!(movement_speed_modifiers(&effects, false).is_empty());
+
+        effects.apply(ActiveEffect::new(Effect::Speed, 1, 100));
+        effects.apply(ActiveEffect::new(Effect::Slowness, 0, 100));
+        let modifiers = movement_speed_modifiers(&effects, true);
+        let amounts = modifiers
+            .iter()
+            .map(|modifier| (modifier.uuid, modifier.amount))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            amounts,
+            vec![
+                (SPRINTING_MODIFIER, SPRINTING_BOOST),
+                (SPEED_MODIFIER, 0.4),
+                (SLOWNESS_MODIFIER, -0.15)
+            ]
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+
+use cfb8::cipher::generic_array::GenericArray;
+use cfb8::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::utils::prelude::*;
+
+pub type Encryptor = cfb8::Encryptor<aes::Aes128>;
+pub type Decryptor = cfb8::Decryptor<aes::Aes128>;
+
+/// Creates the AES/CFB8 cipher pair for a connection. Minecraft uses the shared secret as both
+/// the key and the IV.
+pub fn create_ciphers(shared_secret: &[u8]) -> Result<(Encryptor, Decryptor)> {
+    let encryptor = Encryptor::new_from_slices(shared_secret, shared_secret)
+        .map_err(|e| Error::EncryptionError(e.to_string()))?;
+    let decryptor = Decryptor::new_from_slices(shared_secret, shared_secret)
+        .map_err(|e| Error::EncryptionError(e.to_string()))?;
+    Ok((encryptor, decryptor))
+}
+
+fn encrypt_in_place(cipher: &mut Encryptor, data: &mut [u8]) {
+    for byte in data.chunks_mut(1) {
+        cipher.encrypt_block_mut(GenericArray::from_mut_slice(byte));
+    }
+}
+
+fn decrypt_in_place(cipher: &mut Decryptor, data: &mut [u8]) {
+    for byte in data.chunks_mut(1) {
+        cipher.decrypt_block_mut(GenericArray::from_mut_slice(byte));
+    }
+}
+
+/// A reader that decrypts everything read from `inner` once a cipher has been set, and passes
+/// bytes through untouched before that.
+///
+/// The cipher sits behind its own lock rather than requiring `&mut self`, since the connection's
+/// read loop holds on to the reader while it waits for the next packet. That's exactly when
+/// encryption gets turned on.
+pub struct EncryptedReader<R> {
+    inner: R,
+    cipher: Arc<Mutex<Option<Decryptor>>>,
+}
+
+impl<R> EncryptedReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            cipher: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// A handle to the cipher slot, which can be filled in without locking the reader itself.
+    pub fn cipher(&self) -> Arc<Mutex<Option<Decryptor>>> {
+        self.cipher.clone()
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for EncryptedReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let already_filled = buf.filled().len();
+
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+
+        let mut cipher = this
+            .cipher
+            .lock()
+            .map_err(|_| io::Error::other("cipher lock poisoned"))?;
+        if let Some(cipher) = cipher.as_mut() {
+            decrypt_in_place(cipher, &mut buf.filled_mut()[already_filled..]);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A writer that encrypts everything written to it once a cipher has been set.
+///
+/// Encrypted bytes that the socket doesn't accept straight away are kept in a buffer, since the
+/// cipher has already advanced past them. Make sure to flush after writing a packet.
+pub struct EncryptedWriter<W> {
+    inner: W,
+    cipher: Option<Encryptor>,
+    pending: Vec<u8>,
+    pending_start: usize,
+}
+
+impl<W> EncryptedWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            cipher: None,
+            pending: Vec::new(),
+            pending_start: 0,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn set_cipher(&mut self, cipher: Encryptor) {
+        self.cipher = Some(cipher);
+    }
+}
+
+impl<W: AsyncWrite + Unpin> EncryptedWriter<W> {
+    fn poll_write_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.pending_start < self.pending.len() {
+            let written = ready!(
+                Pin::new(&mut self.inner).poll_write(cx, &self.pending[self.pending_start..])
+            )?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.pending_start += written;
+        }
+        self.pending.clear();
+        self.pending_start = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for EncryptedWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.cipher.is_none() {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+
+        // Anything left over from the last write has to go out first to keep the stream in order.
+        ready!(this.poll_write_pending(cx))?;
+
+        let start = this.pending.len();
+        this.pending.extend_from_slice(buf);
+        if let Some(cipher) = this.cipher.as_mut() {
+            encrypt_in_place(cipher, &mut this.pending[start..]);
+        }
+
+        // The bytes are ours now either way, so a pending socket isn't a reason to hold up the caller.
+        if let Poll::Ready(Err(e)) = this.poll_write_pending(cx) {
+            return Poll::Ready(Err(e));
+        }
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_pending(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_pending(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    const SHARED_SECRET: [u8; 16] = *b"0123456789abcdef";
+
+    #[tokio::test]
+    async fn test_encrypted_round_trip() {
+        let (client, server) = tokio::io::duplex(64);
+        let (encryptor, _) = create_ciphers(&SHARED_SECRET).unwrap();
+        let (_, decryptor) = create_ciphers(&SHARED_SECRET).unwrap();
+
+        let mut writer = EncryptedWriter::new(client);
+        writer.set_cipher(encryptor);
+        let mut reader = EncryptedReader::new(server);
+        *reader.cipher().lock().unwrap() = Some(decryptor);
+
+        // Bigger than the duplex buffer, so part of it has to wait in the pending buffer.
+        let message: Vec<u8> = (0..=255).collect();
+        let expected = message.clone();
+        let write = tokio::spawn(async move {
+            writer.write_all(&message).await.unwrap();
+            writer.flush().await.unwrap();
+        });
+
+        let mut received = vec![0u8; expected.len()];
+        reader.read_exact(&mut received).await.unwrap();
+        write.await.unwrap();
+
+        assert_eq!(received, expected);
+    }
+
+    #[tokio::test]
+    async fn test_plaintext_without_cipher() {
+        let mut writer = EncryptedWriter::new(Vec::new());
+        writer.write_all(b"hello").await.unwrap();
+        assert_eq!(writer.get_ref(), b"hello");
+
+        writer.set_cipher(create_ciphers(&SHARED_SECRET).unwrap().0);
+        writer.write_all(b"hello").await.unwrap();
+        assert_ne!(&writer.get_ref()[5..], b"hello");
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Mutex;
+
+use dashmap::DashMap;
+
+use crate::state::ServerState;
+use crate::utils::components::network_id::NetworkId;
+use crate::utils::prelude::*;
+
+/// The first id handed out, same as vanilla.
+const FIRST_ENTITY_ID: i32 = 1;
+
+/// Hands out the ids entities are known by on clients, which are separate from their ids in the
+/// ECS.
+///
+/// Ids of despawned entities are given out again, oldest first, so one isn't reused right after
+/// a client was told to remove the entity that had it.
+pub struct EntityIdAllocator {
+    next: AtomicI32,
+    free: Mutex<VecDeque<i32>>,
+    entities: DashMap<i32, usize>,
+}
+
+impl Default for EntityIdAllocator {
+    fn default() -> Self {
+        Self {
+            next: AtomicI32::new(FIRST_ENTITY_ID),
+            free: Mutex::new(VecDeque::new()),
+            entities: DashMap::new(),
+        }
+    }
+}
+
+impl EntityIdAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gives `entity` an id that no other entity has.
+    pub fn allocate(&self, entity: usize) -> i32 {
+        let reused = self
+            .free
+            .lock()
+            .expect("entity id free list poisoned")
+            .pop_front();
+        let id = reused.unwrap_or_else(|| self.next.fetch_add(1, Ordering::Relaxed));
+        self.entities.insert(id, entity);
+        id
+    }
+
+    /// Frees `id` once its entity despawned. Returns the entity that had it, or `None` if it
+    /// wasn't given out.
+    pub fn release(&self, id: i32) -> Option<usize> {
+        let (_, entity) = self.entities.remove(&id)?;
+        self.free
+            .lock()
+            .expect("entity id free list poisoned")
+            .push_back(id);
+        Some(entity)
+    }
+
+    /// The entity with `id`, e.g. for a packet that targets it.
+    pub fn entity(&self, id: i32) -> Option<usize> {
+        self.entities.get(&id).map(|entity| *entity)
+    }
+}
+
+impl ServerState {
+    /// The id `entity` is known by on clients, for packets that refer to it.
+    pub async fn network_id(&self, entity: usize) -> Result<i32> {
+        Ok(self.world.get_component::<NetworkId>(entity).await?.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_ids_are_reused_after_despawning() {
+        let ids = EntityIdAllocator::new();
+        let first = ids.allocate(10);
+        let second = ids.allocate(11);
+        assert_eq!((first, second), (FIRST_ENTITY_ID, FIRST_ENTITY_ID + 1));
+        assert_eq!(ids.entity(second), Some(11));
+
+        assert_eq!(ids.release(first), Some(10));
+        assert_eq!(ids.release(first), None);
+        assert_eq!(ids.entity(first), None);
+        ids.release(second);
+
+        // Oldest first.
+        assert_eq!(ids.allocate(12), first);
+        assert_eq!(ids.allocate(13), second);
+        assert_eq!(ids.allocate(14), FIRST_ENTITY_ID + 2);
+        assert_eq!(ids.entity(first), Some(12));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_allocations_are_unique() {
+        let ids = Arc::new(EntityIdAllocator::new());
+        let first: Vec<_> = (0..50).map(|entity| ids.allocate(entity)).collect();
+        for id in first {
+            if id % 2 == 0 {
+                ids.release(id);
+            }
+        }
+
+        let tasks = (0..8)
+            .map(|task| {
+                let ids = ids.clone();
+                tokio::spawn(async move {
+                    (0..100)
+                        .map(|i| ids.allocate(100 + task * 100 + i))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut allocated = HashSet::new();
+        for task in tasks {
+            for id in task.await.unwrap() {
+                assert!(allocated.insert(id), "{} was given out twice", id);
+            }
+        }
+        assert_eq!(allocated.len(), 800);
+        assert!(allocated.iter().all(|id| id % 2 == 0 || *id > 50));
+    }
+}
@@ -0,0 +1,164 @@
+use ferrumc_codec::enc::NetEncode;
+
+use crate::net::packets::outgoing::set_head_rotation::SetHeadRotation;
+use crate::net::packets::outgoing::teleport_entity::TeleportEntity;
+use crate::net::packets::outgoing::update_entity_position::UpdateEntityPosition;
+use crate::net::packets::outgoing::update_entity_position_and_rotation::UpdateEntityPositionAndRotation;
+use crate::net::packets::outgoing::update_entity_rotation::UpdateEntityRotation;
+use crate::utils::components::last_sent_movement::LastSentMovement;
+use crate::utils::prelude::*;
+
+/// How relative moves are sent: in 1/4096 of a block.
+const DELTA_SCALE: f64 = 4096.0;
+
+/// Which packet tells viewers about a move, picked by [movement_update].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MovementUpdate {
+    /// Moved, by a delta that fits in the relative move packets.
+    Position((i16, i16, i16)),
+    /// Moved by a delta that fits, and turned.
+    PositionAndRotation((i16, i16, i16)),
+    /// Only turned.
+    Rotation,
+    /// Moved too far for a relative move.
+    Teleport,
+}
+
+/// How far `from` is from `to` in 1/4096 of a block, or `None` if that doesn't fit in an i16,
+/// i.e. it's 8 blocks or more.
+fn delta(from: f64, to: f64) -> Option<i16> {
+    let delta = (to * DELTA_SCALE).round() as i64 - (from * DELTA_SCALE).round() as i64;
+    i16::try_from(delta).ok()
+}
+
+/// Picks the packet that moves an entity from `last` to `now`, or `None` if viewers wouldn't
+/// see a difference.
+pub fn movement_update(last: &LastSentMovement, now: &LastSentMovement) -> Option<MovementUpdate> {
+    let moved = last.position != now.position;
+    let turned = (last.yaw, last.pitch) != (now.yaw, now.pitch);
+    if !moved {
+        return turned.then_some(MovementUpdate::Rotation);
+    }
+
+    let (from_x, from_y, from_z) = last.position;
+    let (to_x, to_y, to_z) = now.position;
+    let deltas = match (
+        delta(from_x, to_x),
+        delta(from_y, to_y),
+        delta(from_z, to_z),
+    ) {
+        (Some(x), Some(y), Some(z)) => (x, y, z),
+        _ => return Some(MovementUpdate::Teleport),
+    };
+    Some(if turned {
+        MovementUpdate::PositionAndRotation(deltas)
+    } else {
+        MovementUpdate::Position(deltas)
+    })
+}
+
+/// Encodes the packets that show viewers `entity_id` moved from `last` to `now`, along with
+/// its head turning if it did. Empty if nothing changed.
+pub async fn encode_movement(
+    entity_id: i32,
+    last: &LastSentMovement,
+    now: &LastSentMovement,
+    on_ground: bool,
+) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    match movement_update(last, now) {
+        None => return Ok(bytes),
+        Some(MovementUpdate::Position(deltas)) => {
+            UpdateEntityPosition::new(entity_id, deltas, on_ground)
+                .net_encode(&mut bytes)
+                .await?
+        }
+        Some(MovementUpdate::PositionAndRotation(deltas)) => {
+            UpdateEntityPositionAndRotation::new(entity_id, deltas, now.yaw, now.pitch, on_ground)
+                .net_encode(&mut bytes)
+                .await?
+        }
+        Some(MovementUpdate::Rotation) => {
+            UpdateEntityRotation::new(entity_id, now.yaw, now.pitch, on_ground)
+                .net_encode(&mut bytes)
+                .await?
+        }
+        Some(MovementUpdate::Teleport) => {
+            TeleportEntity::new(entity_id, now.position, now.yaw, now.pitch, on_ground)
+                .net_encode(&mut bytes)
+                .await?
+        }
+    }
+    if last.yaw != now.yaw {
+        SetHeadRotation::new(entity_id, now.yaw)
+            .net_encode(&mut bytes)
+            .await?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::encoding::angle::Angle;
+
+    fn movement(position: (f64, f64, f64), yaw: u8) -> LastSentMovement {
+        LastSentMovement {
+            position,
+            yaw: Angle(yaw),
+            pitch: Angle(0),
+        }
+    }
+
+    #[test]
+    fn test_small_moves_are_relative() {
+        let last = movement((0.0, 64.0, 0.0), 0);
+        assert_eq!(movement_update(&last, &last), None);
+        assert_eq!(
+            movement_update(&last, &movement((1.0, 63.0, 0.5), 0)),
+            Some(MovementUpdate::Position((4096, -4096, 2048)))
+        );
+        assert_eq!(
+            movement_update(&last, &movement((1.0, 64.0, 0.0), 64)),
+            Some(MovementUpdate::PositionAndRotation((4096, 0, 0)))
+        );
+        assert_eq!(
+            movement_update(&last, &movement((0.0, 64.0, 0.0), 64)),
+            Some(MovementUpdate::Rotation)
+        );
+    }
+
+    #[test]
+    fn test_large_moves_teleport() {
+        let last = movement((0.0, 64.0, 0.0), 0);
+        // Just under 8 blocks still fits in an i16.
+        assert_eq!(
+            movement_update(&last, &movement((-8.0, 64.0, 7.99), 0)),
+            Some(MovementUpdate::Position((-32768, 0, 32727)))
+        );
+        assert_eq!(
+            movement_update(&last, &movement((8.0, 64.0, 0.0), 0)),
+            Some(MovementUpdate::Teleport)
+        );
+        assert_eq!(
+            movement_update(&last, &movement((0.0, -64.0, 0.0), 32)),
+            Some(MovementUpdate::Teleport)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_turning_also_turns_the_head() {
+        let last = movement((0.0, 64.0, 0.0), 0);
+        let bytes = encode_movement(5, &last, &movement((0.0, 64.0, 0.0), 64), true)
+            .await
+            .unwrap();
+        // Update Entity Rotation, then Set Head Rotation.
+        assert_eq!(
+            bytes,
+            vec![0x05, 0x2D, 0x05, 64, 0, 0x01, 0x03, 0x42, 0x05, 64]
+        );
+
+        let bytes = encode_movement(5, &last, &last, true).await.unwrap();
+        assert!(bytes.is_empty());
+    }
+}
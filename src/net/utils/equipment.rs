@@ -0,0 +1,25 @@
+use crate::net::packets::ConnectionId;
+use crate::state::ServerState;
+use crate::utils::components::equipment::Equipment;
+use crate::utils::components::held_item::HeldItem;
+use crate::utils::components::inventory::Inventory;
+
+impl ServerState {
+    /// Updates what other players see a player holding and wearing from their inventory. Any
+    /// changes are sent to them by [crate::net::systems::equipment_broadcast_system].
+    pub async fn update_equipment(&self, conn_id: ConnectionId) {
+        let component_storage = self.world.get_component_storage();
+        let selected = component_storage
+            .get::<HeldItem>(conn_id)
+            .await
+            .map(|held_item| held_item.slot)
+            .unwrap_or_default();
+        let Ok(inventory) = component_storage.get::<Inventory>(conn_id).await else {
+            return;
+        };
+        component_storage
+            .get_mut_or_insert_with(conn_id, Equipment::default)
+            .await
+            .update_from_inventory(&inventory, selected);
+    }
+}
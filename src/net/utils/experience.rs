@@ -0,0 +1,52 @@
+use crate::net::packets::outgoing::set_experience::SetExperience;
+use crate::net::packets::ConnectionId;
+use crate::state::ServerState;
+use crate::utils::components::experience::Experience;
+use crate::utils::prelude::*;
+use crate::utils::sound::{Sound, SoundCategory};
+
+/// Vanilla plays the level-up sound on every fifth level.
+const LEVEL_UP_SOUND_EVERY: i32 = 5;
+/// The level-up sound gets louder up to this level.
+const LOUDEST_LEVEL_UP: i32 = 30;
+
+impl ServerState {
+    /// Sends a player their experience bar and level.
+    pub async fn send_experience(&self, conn_id: ConnectionId) -> Result<()> {
+        let experience = *self
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with(conn_id, Experience::default)
+            .await;
+        let conn = self.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(SetExperience::new(&experience)).await
+    }
+
+    /// Gives a player experience, or takes it away if `amount` is negative, and updates their
+    /// bar. Reaching every fifth level plays the level-up sound, like in vanilla.
+    pub async fn give_experience(&self, conn_id: ConnectionId, amount: i32) -> Result<()> {
+        let (levels_gained, level) = {
+            let mut experience = self
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with(conn_id, Experience::default)
+                .await;
+            (experience.add(amount), experience.level)
+        };
+        self.send_experience(conn_id).await?;
+
+        if levels_gained > 0 && level % LEVEL_UP_SOUND_EVERY == 0 {
+            let volume = level.min(LOUDEST_LEVEL_UP) as f32 / LOUDEST_LEVEL_UP as f32 * 0.75;
+            self.play_sound_from_entity(
+                conn_id,
+                Sound::EntityPlayerLevelUp,
+                SoundCategory::Players,
+                volume,
+                1.0,
+            )
+            .await;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use crate::net::packets::outgoing::game_event::{GameEvent, CHANGE_GAME_MODE};
+use crate::net::packets::ConnectionId;
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::net::utils::player_list::broadcast_game_mode;
+use crate::net::utils::visibility::update_visible_players;
+use crate::state::ServerState;
+use crate::utils::components::flying::Flying;
+use crate::utils::components::game_mode::{GameMode, PreviousGameMode};
+use crate::utils::prelude::*;
+
+impl ServerState {
+    /// The player's gamemode. Players without one yet are in the default, creative.
+    pub async fn game_mode(&self, conn_id: ConnectionId) -> GameMode {
+        self.world
+            .get_component::<GameMode>(conn_id)
+            .await
+            .map(|gamemode| *gamemode)
+            .unwrap_or_default()
+    }
+
+    /// Whether the player is in spectator, see [GameMode::is_spectator]. Players without a
+    /// gamemode yet aren't.
+    pub async fn is_spectator(&self, conn_id: ConnectionId) -> bool {
+        self.world
+            .get_component::<GameMode>(conn_id)
+            .await
+            .is_ok_and(|gamemode| gamemode.is_spectator())
+    }
+
+    /// Switches a player to another gamemode. Their client is told, along with what they're
+    /// allowed to do in it, and everyone's tab list shows the new gamemode.
+    ///
+    /// Players that can't fly in the new gamemode, see [ServerState::may_fly], are dropped out of
+    /// the air, and spectators start flying straight away. Players going in or out of spectator
+    /// disappear for, or show up again to, everyone that isn't a spectator.
+    pub async fn set_gamemode(
+        self: &Arc<Self>,
+        conn_id: ConnectionId,
+        gamemode: GameMode,
+    ) -> Result<()> {
+        let component_storage = self.world.get_component_storage();
+
+        let previous = {
+            let mut current = component_storage
+                .get_mut_or_insert_with(conn_id, GameMode::default)
+                .await;
+            std::mem::replace(&mut *current, gamemode)
+        };
+        if previous == gamemode {
+            return Ok(());
+        }
+        component_storage.insert(conn_id, PreviousGameMode(previous));
+
+        let is_flying = {
+            let mut flying = component_storage
+                .get_mut_or_insert_with(conn_id, Flying::default)
+                .await;
+            let is_flying =
+                gamemode.is_always_flying() || (self.may_fly(gamemode) && flying.is_flying);
+            flying.set_flying(is_flying);
+            is_flying
+        };
+
+        let mut packet_queue = PacketQueue::new();
+        packet_queue
+            .queue(GameEvent::new(CHANGE_GAME_MODE, gamemode.id() as f32))
+            .await?;
+        packet_queue
+            .queue(self.abilities(gamemode, is_flying))
+            .await?;
+        {
+            let conn = self.connections.get_connection(conn_id)?;
+            let conn = conn.read().await;
+            conn.send_packets(packet_queue).await?;
+        }
+
+        broadcast_game_mode(conn_id, self).await?;
+        if previous.is_spectator() != gamemode.is_spectator() {
+            update_visible_players(conn_id, self).await?;
+        }
+        Ok(())
+    }
+}
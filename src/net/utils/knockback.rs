@@ -0,0 +1,119 @@
+use crate::net::packets::outgoing::set_entity_velocity::SetEntityVelocity;
+use crate::state::ServerState;
+use crate::utils::components::grounded::Grounded;
+use crate::utils::components::physics_body::PhysicsBody;
+use crate::utils::components::player::Player;
+use crate::utils::components::velocity::Velocity;
+use crate::utils::components::violations::Violations;
+use crate::utils::config::get_global_config;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+
+/// The most a knockback can throw an entity upwards, in blocks per tick.
+const MAX_KNOCKBACK_HOP: f64 = 0.4;
+
+/// The velocity an entity moving at `old` has after being knocked `strength` towards
+/// `direction`, an x and z that don't have to be normalized. Same as vanilla: it keeps half of
+/// what it had and is pushed on top of that, and hops up if it was on the ground.
+///
+/// Nothing changes if `direction` is zero or `strength` isn't positive.
+pub fn knockback_velocity(
+    old: &Velocity,
+    (x, z): (f64, f64),
+    strength: f64,
+    on_ground: bool,
+) -> Velocity {
+    let length = (x * x + z * z).sqrt();
+    if strength <= 0.0 || length < 1e-5 {
+        return *old;
+    }
+    let (push_x, push_z) = (x / length * strength, z / length * strength);
+    let y = match on_ground {
+        true => (old.y / 2.0 + strength).min(MAX_KNOCKBACK_HOP),
+        false => old.y,
+    };
+    Velocity::new(old.x / 2.0 + push_x, y, old.z / 2.0 + push_z)
+}
+
+impl ServerState {
+    /// Knocks `entity` back `strength` towards `direction`, see [knockback_velocity].
+    ///
+    /// Players move themselves, so they're sent the velocity instead, along with everyone that
+    /// can see them. The server doesn't know how fast they were already going, so it's taken to
+    /// be nothing. Everything else has its [Velocity] changed, which moves it if it has a
+    /// [PhysicsBody].
+    pub async fn apply_knockback(
+        &self,
+        entity: usize,
+        direction: (f64, f64),
+        strength: f64,
+    ) -> Result<()> {
+        let component_storage = self.world.get_component_storage();
+        if component_storage.get::<Player>(entity).await.is_err() {
+            let on_ground = component_storage
+                .get::<PhysicsBody>(entity)
+                .await
+                .is_ok_and(|body| body.on_ground);
+            let mut velocity = component_storage
+                .get_mut_or_insert_with(entity, Velocity::default)
+                .await;
+            *velocity = knockback_velocity(&velocity, direction, strength, on_ground);
+            return Ok(());
+        }
+
+        let on_ground = component_storage
+            .get::<Grounded>(entity)
+            .await
+            .is_ok_and(|grounded| grounded.is_grounded);
+        let velocity = knockback_velocity(&Velocity::default(), direction, strength, on_ground);
+        if velocity.is_zero() {
+            return Ok(());
+        }
+        // So the anti-cheat doesn't turn down the moves it makes.
+        if get_global_config().anti_cheat.enabled {
+            component_storage
+                .get_mut_or_insert_with(entity, Violations::default)
+                .await
+                .sent_velocity(velocity);
+        }
+        let network_id = self.network_id(entity).await?;
+        let position = component_storage.get::<Position>(entity).await?.clone();
+        let dimension = self.dimension_of(entity).await;
+        // The player themselves is near their own position too.
+        self.send_to_players_near(&dimension, &position, None, || {
+            SetEntityVelocity::new(network_id, &velocity)
+        })
+        .await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_knockback_from_standing_still() {
+        // A 3-4-5 triangle, so the push is split 0.6 and 0.8 between x and z.
+        let velocity = knockback_velocity(&Velocity::default(), (3.0, -4.0), 0.5, true);
+        assert_eq!(velocity, Velocity::new(0.3, 0.4, -0.4));
+        // Off the ground, it isn't thrown up.
+        let velocity = knockback_velocity(&Velocity::default(), (0.0, 2.0), 0.4, false);
+        assert_eq!(velocity, Velocity::new(0.0, 0.0, 0.4));
+    }
+
+    #[test]
+    fn test_knockback_keeps_half_the_old_velocity() {
+        let old = Velocity::new(0.2, -0.4, -0.6);
+        let velocity = knockback_velocity(&old, (1.0, 0.0), 0.4, true);
+        assert_eq!(velocity, Velocity::new(0.5, 0.2, -0.3));
+        let velocity = knockback_velocity(&old, (1.0, 0.0), 0.4, false);
+        assert_eq!(velocity.y, -0.4);
+        // Already going up, it's thrown up no faster than 0.4.
+        let velocity = knockback_velocity(&Velocity::new(0.0, 0.4, 0.0), (1.0, 0.0), 0.4, true);
+        assert_eq!(velocity.y, 0.4);
+
+        assert_eq!(knockback_velocity(&old, (0.0, 0.0), 0.4, true), old);
+        assert_eq!(knockback_velocity(&old, (1.0, 0.0), 0.0, true), old);
+    }
+}
@@ -0,0 +1,146 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::utils::constants::GAME_VERSION;
+use crate::utils::prelude::*;
+
+/// The first byte sent by pre-1.7 clients (and a lot of monitoring tools) when pinging a server.
+///
+/// A modern handshake starts with a VarInt packet length instead, which starts with this too for
+/// lengths of 126 more than a multiple of 128, like 254, so it isn't enough on its own, see
+/// [is_legacy_ping].
+pub const LEGACY_PING_ID: u8 = 0xFE;
+
+/// Sent after [LEGACY_PING_ID] by 1.4 to 1.6 clients.
+const LEGACY_PING_PAYLOAD: u8 = 0x01;
+
+/// The id of the plugin message 1.6 clients send after [LEGACY_PING_PAYLOAD].
+const LEGACY_PLUGIN_MESSAGE_ID: u8 = 0xFA;
+
+/// The packet id of the legacy kick packet, which is used to respond to the legacy ping.
+const LEGACY_KICK_ID: u8 = 0xFF;
+
+/// The protocol version sent in the legacy response. Old clients will show the server as outdated,
+/// which is the best we can do since they can't join anyway.
+const LEGACY_PROTOCOL_VERSION: i32 = 127;
+
+/// Whether a connection that's sent `start` so far is pinging the way clients before 1.7 did,
+/// going by the first three bytes at most. Like vanilla, that's a lone 0xFE, or 0xFE 0x01 on its
+/// own or followed by 0xFA. Anything else, like the 0xFE 0x01 0x00 a 254 byte handshake starts
+/// with, is a modern packet.
+pub fn is_legacy_ping(start: &[u8]) -> bool {
+    match start {
+        [LEGACY_PING_ID] | [LEGACY_PING_ID, LEGACY_PING_PAYLOAD] => true,
+        [LEGACY_PING_ID, LEGACY_PING_PAYLOAD, next, ..] => *next == LEGACY_PLUGIN_MESSAGE_ID,
+        _ => false,
+    }
+}
+
+/// Responds to a legacy (0xFE) server list ping.
+///
+/// Reads the leading 0xFE byte from `reader` and writes the legacy kick packet to `writer`. Whatever
+/// follows the 0xFE (0x01, the 0xFA plugin message from 1.6 clients) is ignored, since the
+/// connection is closed straight after anyway.
+pub async fn respond_to_legacy_ping<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    motd: &str,
+    online_players: usize,
+    max_players: i32,
+) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let id = reader.read_u8().await?;
+    if id != LEGACY_PING_ID {
+        return Err(Error::InvalidPacketId(id as u32));
+    }
+
+    let response = legacy_ping_response(motd, online_players, max_players);
+    writer.write_all(&response).await?;
+    writer.flush().await?;
+
+    Ok(())
+}
+
+/// Builds the legacy kick packet: the 0xFF id, the length of the string in UTF-16 code units, and
+/// the §-prefixed, null delimited string itself encoded as UTF-16BE.
+fn legacy_ping_response(motd: &str, online_players: usize, max_players: i32) -> Vec<u8> {
+    let payload = format!(
+        "§1\0{}\0{}\0{}\0{}\0{}",
+        LEGACY_PROTOCOL_VERSION, GAME_VERSION, motd, online_players, max_players
+    );
+    let payload: Vec<u16> = payload.encode_utf16().collect();
+
+    let mut response = Vec::with_capacity(3 + payload.len() * 2);
+    response.push(LEGACY_KICK_ID);
+    response.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    for unit in payload {
+        response.extend_from_slice(&unit.to_be_bytes());
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_legacy_ping_reply() {
+        // What a 1.6 client sends: 0xFE 0x01, then a MC|PingHost plugin message.
+        let mut request = Cursor::new(vec![
+            0xFE, 0x01, 0xFA, 0x00, 0x0B, 0x00, 0x4D, 0x00, 0x43, 0x00, 0x7C, 0x00, 0x50, 0x00,
+            0x69, 0x00, 0x6E, 0x00, 0x67, 0x00, 0x48, 0x00, 0x6F, 0x00, 0x73, 0x00, 0x74,
+        ]);
+        let mut reply = Vec::new();
+
+        respond_to_legacy_ping(&mut request, &mut reply, "A Server", 3, 20)
+            .await
+            .unwrap();
+
+        let expected = format!("§1\0127\0{}\0A Server\x003\x0020", GAME_VERSION);
+        let mut expected_bytes = vec![0xFF];
+        let units: Vec<u16> = expected.encode_utf16().collect();
+        expected_bytes.extend_from_slice(&(units.len() as u16).to_be_bytes());
+        for unit in &units {
+            expected_bytes.extend_from_slice(&unit.to_be_bytes());
+        }
+
+        assert_eq!(reply, expected_bytes);
+        // Spot check the start of the encoding by hand: 0xFF, length, then '§' and '1' as UTF-16BE.
+        assert_eq!(&reply[3..7], &[0x00, 0xA7, 0x00, 0x31]);
+    }
+
+    #[tokio::test]
+    async fn test_non_legacy_ping_is_rejected() {
+        let mut request = Cursor::new(vec![0x10, 0x00]);
+        let mut reply = Vec::new();
+
+        let result = respond_to_legacy_ping(&mut request, &mut reply, "A Server", 0, 20).await;
+
+        assert!(result.is_err());
+        assert!(reply.is_empty());
+    }
+
+    #[test]
+    fn test_is_legacy_ping() {
+        assert!(is_legacy_ping(&[0xFE]));
+        assert!(is_legacy_ping(&[0xFE, 0x01]));
+        assert!(is_legacy_ping(&[0xFE, 0x01, 0xFA]));
+        assert!(!is_legacy_ping(&[]));
+        assert!(!is_legacy_ping(&[0x10, 0x00]));
+
+        // A 254 byte handshake, e.g. with a long hostname, has a length starting with 0xFE 0x01.
+        let hostname = "a".repeat(246);
+        let mut handshake = vec![0x00, 0xFF, 0x05, 0xF6, 0x01];
+        handshake.extend_from_slice(hostname.as_bytes());
+        handshake.extend_from_slice(&[0x63, 0xDD, 0x02]);
+        assert_eq!(handshake.len(), 254);
+        let mut frame = vec![0xFE, 0x01];
+        frame.extend_from_slice(&handshake);
+        assert!(!is_legacy_ping(&frame[..3]));
+        assert!(!is_legacy_ping(&frame));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::net::packets::outgoing::set_entity_metadata::SetEntityMetadata;
+use crate::net::packets::ConnectionId;
+use crate::state::GlobalState;
+use crate::utils::components::client_settings::{ClientSettings, MainHand};
+use crate::utils::components::flying::Flying;
+use crate::utils::components::sneaking::Sneaking;
+use crate::utils::components::sprinting::Sprinting;
+use crate::utils::encoding::entity_metadata::{player, EntityMetadata, MetadataValue, Pose};
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+
+/// The flags and pose other players need to see the player crouch and sprint, and the skin layers
+/// and main hand from their client settings.
+///
+/// Players flying in creative hold sneak to go down, which doesn't make them crouch.
+pub async fn player_metadata(conn_id: ConnectionId, state: &GlobalState) -> EntityMetadata {
+    let component_storage = state.world.get_component_storage();
+    let sneaking = component_storage
+        .get::<Sneaking>(conn_id)
+        .await
+        .is_ok_and(|sneaking| sneaking.is_sneaking);
+    let sprinting = component_storage
+        .get::<Sprinting>(conn_id)
+        .await
+        .is_ok_and(|sprinting| sprinting.is_sprinting);
+    let flying = component_storage
+        .get::<Flying>(conn_id)
+        .await
+        .is_ok_and(|flying| flying.is_flying);
+    let crouching = sneaking && !flying;
+
+    let mut flags = 0;
+    if crouching {
+        flags |= player::FLAG_CROUCHING;
+    }
+    if sprinting {
+        flags |= player::FLAG_SPRINTING;
+    }
+    let pose = if crouching {
+        Pose::Sneaking
+    } else {
+        Pose::Standing
+    };
+
+    // Vanilla shows no skin layers until the client sends its settings.
+    let (skin_parts, main_hand) = match component_storage.get::<ClientSettings>(conn_id).await {
+        Ok(settings) => (settings.displayed_skin_parts, settings.main_hand),
+        Err(_) => (0, MainHand::default()),
+    };
+    let main_hand = match main_hand {
+        MainHand::Left => 0,
+        MainHand::Right => 1,
+    };
+
+    EntityMetadata::new()
+        .with(player::FLAGS, MetadataValue::Byte(flags))
+        .with(player::POSE, MetadataValue::Pose(pose))
+        .with(
+            player::DISPLAYED_SKIN_PARTS,
+            MetadataValue::Byte(skin_parts),
+        )
+        .with(player::MAIN_HAND, MetadataValue::Byte(main_hand))
+}
+
+/// Sends the player's [player_metadata] to everyone near them. Their own client already shows
+/// it, so they're left out.
+pub async fn broadcast_player_metadata(conn_id: ConnectionId, state: &GlobalState) -> Result<()> {
+    let metadata = player_metadata(conn_id, state).await;
+    let network_id = state.network_id(conn_id).await?;
+    let position = state
+        .world
+        .get_component_storage()
+        .get::<Position>(conn_id)
+        .await?
+        .clone();
+    let dimension = state.dimension_of(conn_id).await;
+    state
+        .send_to_players_near(&dimension, &position, Some(conn_id), || {
+            SetEntityMetadata::new(network_id, metadata.clone())
+        })
+        .await;
+    Ok(())
+}
@@ -1 +1,35 @@
+pub mod abilities;
+pub mod anti_cheat;
+pub mod authentication;
+pub mod combat;
+pub mod compression;
+pub mod damage;
+pub mod dimension;
+pub mod dropped_items;
+pub mod effects;
+pub mod encryption;
+pub mod entity_ids;
+pub mod entity_movement;
+pub mod equipment;
+pub mod experience;
+pub mod game_mode;
+pub mod knockback;
+pub mod legacy_ping;
+pub mod metadata;
+pub mod movement;
+pub mod network_stats;
+pub mod packet_dump;
 pub mod packet_queue;
+pub mod packet_writer;
+pub mod ping;
+pub mod player_list;
+pub mod proxy;
+pub mod proxy_protocol;
+pub mod query;
+pub mod rate_limiter;
+pub mod rcon;
+pub mod skins;
+pub mod socket_options;
+pub mod spawn;
+pub mod tab_list;
+pub mod visibility;
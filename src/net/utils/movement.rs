@@ -0,0 +1,290 @@
+use std::time::Duration;
+
+use tracing::warn;
+
+use crate::net::packets::outgoing::synchronize_player_position::SynchronizePlayerPosition;
+use crate::net::packets::ConnectionId;
+use crate::net::systems::chunk_sender::ChunkSender;
+use crate::net::utils::anti_cheat::check_movement;
+use crate::net::utils::visibility::update_visible_players;
+use crate::net::ConnectionExt;
+use crate::state::GlobalState;
+use crate::utils::components::exact_position::ExactPosition;
+use crate::utils::components::grounded::Grounded;
+use crate::utils::components::pending_teleports::PendingTeleports;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::components::violations::Violations;
+use crate::utils::config::get_global_config;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+
+/// How far from 0, 0 a player can go on the x and z axis, same as vanilla.
+pub const MAX_HORIZONTAL_COORDINATE: f64 = 30_000_000.0;
+/// How far a player can move in one packet. The client sends its position every tick, and even
+/// falling at full speed doesn't get anywhere near this.
+pub const MAX_MOVEMENT_PER_PACKET: f64 = 10.0;
+
+/// Sent to clients that send a position that isn't a number.
+pub const INVALID_MOVEMENT: &str = "Invalid move player packet received";
+/// Sent to clients that leave more than `network.max_pending_teleports` teleports unconfirmed.
+pub const TELEPORT_NOT_CONFIRMED: &str = "Teleport wasn't confirmed";
+
+/// How long to wait for a teleport to be confirmed before sending it again.
+const TELEPORT_RESEND_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What a movement packet changed. Each of the movement packets only sends some of it.
+#[derive(Debug, Clone, Copy)]
+pub struct Movement {
+    pub position: Option<(f64, f64, f64)>,
+    /// Yaw and pitch, in degrees.
+    pub rotation: Option<(f32, f32)>,
+    pub on_ground: bool,
+}
+
+/// Validates a movement packet and updates the player's components to match. Sends new chunks and
+/// players if the player moved into a different chunk, and kicks them if the packet had invalid
+/// values in it. Moves the player couldn't have made are undone, see [check_movement].
+///
+/// Positions sent while a teleport is still unconfirmed are from before it, so they're ignored,
+/// and the teleport is sent again if it's been a while.
+///
+/// Clients send movement packets every tick, so this has to stay cheap.
+pub async fn handle_movement(
+    conn_id: ConnectionId,
+    state: GlobalState,
+    movement: Movement,
+) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+
+    if movement.position.is_some() {
+        let teleports = component_storage
+            .get_mut_or_insert_with(conn_id, PendingTeleports::default)
+            .await;
+        if teleports.is_awaiting_confirmation() {
+            let resend = teleports.last_sent.elapsed() >= TELEPORT_RESEND_INTERVAL;
+            drop(teleports);
+            if resend {
+                synchronize_position(conn_id, state).await?;
+            }
+            return Ok(());
+        }
+    }
+
+    let rotation = match movement.rotation {
+        Some((yaw, pitch)) => match validate_rotation(yaw, pitch) {
+            Some(rotation) => Some(rotation),
+            None => return kick_for_invalid_movement(conn_id, state, movement).await,
+        },
+        None => None,
+    };
+
+    let moved = movement.position.is_some();
+    let mut changed_chunk = false;
+    if let Some((x, y, z)) = movement.position {
+        let dimension = state.dimension_of(conn_id).await;
+        if !check_movement(conn_id, &state, &dimension, (x, y, z), movement.on_ground).await? {
+            return Ok(());
+        }
+        let mut position = component_storage.get_mut::<Position>(conn_id).await?;
+        let Some(exact) = validate_position(&position, x, y, z) else {
+            drop(position);
+            return kick_for_invalid_movement(conn_id, state, movement).await;
+        };
+        let new_position = exact.block();
+        let old_chunk_pos = (position.x >> 4, position.z >> 4);
+        dimension.players.update(conn_id, &new_position);
+        *position = new_position;
+        changed_chunk = (position.x >> 4, position.z >> 4) != old_chunk_pos;
+        drop(position);
+        *component_storage
+            .get_mut_or_insert_with(conn_id, || exact)
+            .await = exact;
+    }
+
+    if let Some(rotation) = rotation {
+        *component_storage
+            .get_mut_or_insert_with(conn_id, || Rotation::new(0.0, 0.0))
+            .await = rotation;
+    }
+
+    component_storage
+        .get_mut_or_insert_with(conn_id, Grounded::default)
+        .await
+        .set_grounded(movement.on_ground);
+
+    if changed_chunk {
+        update_visible_players(conn_id, &state).await?;
+    }
+    if moved {
+        ChunkSender::update_view(state, conn_id).await?;
+    }
+    Ok(())
+}
+
+async fn kick_for_invalid_movement(
+    conn_id: ConnectionId,
+    state: GlobalState,
+    movement: Movement,
+) -> Result<()> {
+    warn!(
+        "Connection {} sent invalid movement: {:?}",
+        conn_id, movement
+    );
+    let conn = state.connections.get_connection(conn_id)?;
+    conn.kick(INVALID_MOVEMENT, state).await
+}
+
+/// Teleports the player to the last place they were allowed to be, their [ExactPosition], and
+/// waits for the client to confirm it. Kicks them instead if they've left too many teleports
+/// unconfirmed already.
+pub async fn synchronize_position(conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+
+    let (teleport_id, unconfirmed) = {
+        let mut teleports = component_storage
+            .get_mut_or_insert_with(conn_id, PendingTeleports::default)
+            .await;
+        let teleport_id = teleports.start();
+        (teleport_id, teleports.pending.len())
+    };
+
+    let max_pending = get_global_config().network.max_pending_teleports as usize;
+    if max_pending > 0 && unconfirmed > max_pending {
+        warn!(
+            "Connection {} left {} teleports unconfirmed",
+            conn_id,
+            unconfirmed - 1
+        );
+        let conn = state.connections.get_connection(conn_id)?;
+        return conn.kick(TELEPORT_NOT_CONFIRMED, state).await;
+    }
+
+    let packet = {
+        let position = *component_storage.get::<ExactPosition>(conn_id).await?;
+        let rotation = component_storage.get::<Rotation>(conn_id).await?;
+        component_storage
+            .get_mut_or_insert_with(conn_id, Violations::default)
+            .await
+            .teleported();
+        SynchronizePlayerPosition::exact(position.0, &rotation, teleport_id)
+    };
+    let conn = state.connections.get_connection(conn_id)?;
+    let conn = conn.read().await;
+    conn.send_packet(packet).await
+}
+
+/// Checks a position the client says it moved to from `previous`, and returns exactly where the
+/// player is now.
+///
+/// Returns `None` if any coordinate is NaN or infinite, which vanilla kicks for. Otherwise the
+/// position is kept inside the world, and a move further than [MAX_MOVEMENT_PER_PACKET] is cut
+/// short in the same direction. The client will keep sending where it really is, so a player
+/// that was cut short catches up over the next few packets.
+pub fn validate_position(previous: &Position, x: f64, y: f64, z: f64) -> Option<ExactPosition> {
+    if !(x.is_finite() && y.is_finite() && z.is_finite()) {
+        return None;
+    }
+
+    let x = x.clamp(-MAX_HORIZONTAL_COORDINATE, MAX_HORIZONTAL_COORDINATE);
+    // Block positions only have room for an i16.
+    let y = y.clamp(i16::MIN as f64, i16::MAX as f64);
+    let z = z.clamp(-MAX_HORIZONTAL_COORDINATE, MAX_HORIZONTAL_COORDINATE);
+
+    let (from_x, from_y, from_z) = (previous.x as f64, previous.y as f64, previous.z as f64);
+    let (dx, dy, dz) = (x - from_x, y - from_y, z - from_z);
+    let distance = (dx * dx + dy * dy + dz * dz).sqrt();
+    let (x, y, z) = if distance > MAX_MOVEMENT_PER_PACKET {
+        let scale = MAX_MOVEMENT_PER_PACKET / distance;
+        (
+            from_x + dx * scale,
+            from_y + dy * scale,
+            from_z + dz * scale,
+        )
+    } else {
+        (x, y, z)
+    };
+
+    Some(ExactPosition((x, y, z)))
+}
+
+/// Checks a rotation sent by the client. Returns `None` if either angle is NaN or infinite.
+///
+/// Clients don't wrap the yaw around, so after a few turns it can be anything. It's normalized
+/// into [-180, 180), and the pitch is kept between straight up and straight down.
+pub fn validate_rotation(yaw: f32, pitch: f32) -> Option<Rotation> {
+    if !(yaw.is_finite() && pitch.is_finite()) {
+        return None;
+    }
+
+    let mut yaw = (yaw + 180.0).rem_euclid(360.0) - 180.0;
+    // rem_euclid can round up to 360 for angles just below a multiple of it.
+    if yaw >= 180.0 {
+        yaw -= 360.0;
+    }
+    Some(Rotation::new(yaw, pitch.clamp(-90.0, 90.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coordinates(position: ExactPosition) -> (i32, i16, i32) {
+        let block = position.block();
+        (block.x, block.y, block.z)
+    }
+
+    #[test]
+    fn test_small_moves_are_kept() {
+        let previous = Position::new(0, 64, 0);
+        let position = validate_position(&previous, 1.5, 64.0, -0.3).unwrap();
+        // Negative coordinates round down to the block they're in.
+        assert_eq!(coordinates(position), (1, 64, -1));
+    }
+
+    #[test]
+    fn test_invalid_coordinates_are_rejected() {
+        let previous = Position::new(0, 64, 0);
+        assert!(validate_position(&previous, f64::NAN, 64.0, 0.0).is_none());
+        assert!(validate_position(&previous, 0.0, f64::INFINITY, 0.0).is_none());
+        assert!(validate_position(&previous, 0.0, 64.0, f64::NEG_INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_large_moves_are_clamped() {
+        let previous = Position::new(0, 64, 0);
+        let position = validate_position(&previous, 1000.0, 64.0, 0.0).unwrap();
+        assert_eq!(coordinates(position), (10, 64, 0));
+
+        let previous = Position::new(29_999_995, 64, 0);
+        let position = validate_position(&previous, 1e300, 64.0, 0.0).unwrap();
+        assert_eq!(coordinates(position), (30_000_000, 64, 0));
+    }
+
+    #[test]
+    fn test_rotation_is_normalized() {
+        let angles = |rotation: Rotation| (rotation.yaw, rotation.pitch);
+        assert_eq!(angles(validate_rotation(90.0, 15.0).unwrap()), (90.0, 15.0));
+        assert_eq!(
+            angles(validate_rotation(-450.0, 0.0).unwrap()),
+            (-90.0, 0.0)
+        );
+        assert_eq!(
+            angles(validate_rotation(180.0, 0.0).unwrap()),
+            (-180.0, 0.0)
+        );
+        assert_eq!(
+            angles(validate_rotation(-180.0, 0.0).unwrap()),
+            (-180.0, 0.0)
+        );
+        assert_eq!(
+            angles(validate_rotation(720.0, 120.0).unwrap()),
+            (0.0, 90.0)
+        );
+
+        let yaw = validate_rotation(-180.00001, 0.0).unwrap().yaw;
+        assert!((-180.0..180.0).contains(&yaw));
+
+        assert!(validate_rotation(f32::NAN, 0.0).is_none());
+        assert!(validate_rotation(0.0, f32::INFINITY).is_none());
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use ferrumc_codec::network_types::varint::VarInt;
+
+use crate::net::packets::ConnectionId;
+use crate::state::ServerState;
+use crate::utils::prelude::*;
+
+/// How much traffic went over the wire, counting compressed sizes and every frame header.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkStats {
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    pub packets_read: u64,
+    pub packets_written: u64,
+}
+
+/// A snapshot of the traffic for the whole server, and for each connection that's still open.
+#[derive(Debug, Default, Clone)]
+pub struct ServerNetworkStats {
+    /// Everything since the server started, including connections that have closed since.
+    pub total: NetworkStats,
+    pub connections: HashMap<ConnectionId, NetworkStats>,
+}
+
+/// The counters behind [NetworkStats]. They're atomic, so they can be updated while only holding
+/// a read lock on the connection.
+///
+/// A connection's counters also add everything to the server's totals.
+#[derive(Debug, Default)]
+pub struct NetworkCounters {
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    packets_read: AtomicU64,
+    packets_written: AtomicU64,
+    total: Option<Arc<NetworkCounters>>,
+}
+
+impl NetworkCounters {
+    /// Counters that also count towards `total`.
+    pub fn with_total(total: Arc<NetworkCounters>) -> Self {
+        Self {
+            total: Some(total),
+            ..Default::default()
+        }
+    }
+
+    /// Counts one packet read, `bytes` long including its length prefix.
+    pub fn record_read(&self, bytes: usize) {
+        self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.packets_read.fetch_add(1, Ordering::Relaxed);
+        if let Some(total) = &self.total {
+            total.record_read(bytes);
+        }
+    }
+
+    /// Counts `packets` packets written, `bytes` long in total.
+    pub fn record_written(&self, bytes: usize, packets: usize) {
+        self.bytes_written
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        self.packets_written
+            .fetch_add(packets as u64, Ordering::Relaxed);
+        if let Some(total) = &self.total {
+            total.record_written(bytes, packets);
+        }
+    }
+
+    pub fn snapshot(&self) -> NetworkStats {
+        NetworkStats {
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            packets_read: self.packets_read.load(Ordering::Relaxed),
+            packets_written: self.packets_written.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl ServerState {
+    /// How much traffic the server has sent and received, in total and for each open connection.
+    pub async fn network_stats(&self) -> ServerNetworkStats {
+        let connections = self
+            .connections
+            .connections
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect::<Vec<_>>();
+
+        let mut stats = ServerNetworkStats {
+            total: self.network_totals.snapshot(),
+            connections: HashMap::with_capacity(connections.len()),
+        };
+        for (conn_id, conn) in connections {
+            let snapshot = conn.read().await.metadata.network.snapshot();
+            stats.connections.insert(conn_id, snapshot);
+        }
+        stats
+    }
+}
+
+/// Counts the length-prefixed packets in `packets`, which can hold several back to back like the
+/// contents of a [crate::net::utils::packet_queue::PacketQueue].
+pub async fn count_packets(packets: &[u8]) -> Result<usize> {
+    let mut cursor = Cursor::new(packets);
+    let mut count = 0;
+    while (cursor.position() as usize) < packets.len() {
+        let length = VarInt::read(&mut cursor).await?.get_val() as u64;
+        cursor.set_position(cursor.position() + length);
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_counters_add_to_the_total() {
+        let total = Arc::new(NetworkCounters::default());
+        let first = NetworkCounters::with_total(total.clone());
+        let second = NetworkCounters::with_total(total.clone());
+
+        first.record_read(10);
+        first.record_written(100, 3);
+        second.record_read(5);
+
+        assert_eq!(
+            first.snapshot(),
+            NetworkStats {
+                bytes_read: 10,
+                bytes_written: 100,
+                packets_read: 1,
+                packets_written: 3,
+            }
+        );
+        assert_eq!(second.snapshot().bytes_read, 5);
+        assert_eq!(
+            total.snapshot(),
+            NetworkStats {
+                bytes_read: 15,
+                bytes_written: 100,
+                packets_read: 2,
+                packets_written: 3,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_count_packets() {
+        assert_eq!(count_packets(&[]).await.unwrap(), 0);
+        assert_eq!(count_packets(&[1, 0x00]).await.unwrap(), 1);
+        assert_eq!(
+            count_packets(&[2, 0x01, 0xAA, 1, 0x02, 3, 0x03, 0xBB, 0xCC])
+                .await
+                .unwrap(),
+            3
+        );
+    }
+}
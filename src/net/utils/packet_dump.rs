@@ -0,0 +1,376 @@
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tracing::{debug, warn};
+
+use crate::net::packets::{debug_decode_packet, ConnectionId};
+use crate::net::State;
+use crate::utils::prelude::*;
+
+/// Every dump file starts with this, followed by [DUMP_VERSION].
+const DUMP_MAGIC: &[u8; 8] = b"FRMCDUMP";
+const DUMP_VERSION: u8 = 1;
+
+/// How many bytes of each frame to show per line when replaying.
+const HEX_LINE_LENGTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// One captured frame, after decompression and decryption. `data` starts with the packet id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DumpRecord {
+    /// Microseconds since the unix epoch.
+    pub timestamp: u64,
+    pub direction: Direction,
+    pub conn_id: ConnectionId,
+    pub state: State,
+    pub packet_id: i32,
+    pub data: Vec<u8>,
+}
+
+/// Captures every frame sent and received on a connection, for debugging the protocol.
+///
+/// Only exists if `debug.packet_dump` is set, so connections without one don't pay anything for
+/// it. The frames are written by a separate task, in two files in that directory:
+///
+/// - `<time>-<conn id>.dump`: [DUMP_MAGIC] and [DUMP_VERSION], then one record after another.
+///   A record is the timestamp in microseconds (u64), the direction (u8, 0 in and 1 out), the
+///   connection id (u64), the state (u8), the packet id (i32), the length of the data (u32) and
+///   the data. Everything is big endian.
+/// - `<time>-<conn id>.idx`: The offset of every record in the dump (u64) and its timestamp
+///   (u64), so tools can jump to a point in time without reading everything before it.
+///
+/// Read the dump back with `ferrumc dump-replay <file>`.
+#[derive(Debug, Clone)]
+pub struct PacketDump {
+    sender: UnboundedSender<DumpRecord>,
+}
+
+impl PacketDump {
+    /// Creates the dump files for `conn_id` in `directory`, creating the directory if needed.
+    pub async fn create(directory: impl AsRef<Path>, conn_id: ConnectionId) -> Result<Self> {
+        let directory = directory.as_ref();
+        tokio::fs::create_dir_all(directory).await?;
+
+        let name = format!("{}-{}", unix_micros() / 1000, conn_id);
+        let dump_path = directory.join(format!("{}.dump", name));
+        let mut dump = BufWriter::new(File::create(&dump_path).await?);
+        let index = BufWriter::new(File::create(directory.join(format!("{}.idx", name))).await?);
+
+        dump.write_all(DUMP_MAGIC).await?;
+        dump.write_u8(DUMP_VERSION).await?;
+
+        let (sender, receiver) = unbounded_channel();
+        tokio::spawn(async move {
+            if let Err(e) = write_records(receiver, dump, index).await {
+                warn!("Failed to write packet dump {}: {}", dump_path.display(), e);
+            }
+        });
+        Ok(Self { sender })
+    }
+
+    /// Records a frame received from the client. `frame` is the decompressed packet, starting
+    /// with its id.
+    pub async fn record_inbound(&self, conn_id: ConnectionId, state: &State, frame: &[u8]) {
+        self.record(Direction::Inbound, conn_id, state, frame).await;
+    }
+
+    /// Records everything in `packets`, which can hold several length-prefixed frames, like
+    /// the ones [crate::net::Connection::send_packet] gets before compressing them.
+    pub async fn record_outbound(&self, conn_id: ConnectionId, state: &State, packets: &[u8]) {
+        let mut rest = packets;
+        while !rest.is_empty() {
+            let Ok(length) = VarInt::read(&mut rest).await else {
+                return;
+            };
+            let length = (length.get_val().max(0) as usize).min(rest.len());
+            let (frame, remaining) = rest.split_at(length);
+            self.record(Direction::Outbound, conn_id, state, frame)
+                .await;
+            rest = remaining;
+        }
+    }
+
+    async fn record(
+        &self,
+        direction: Direction,
+        conn_id: ConnectionId,
+        state: &State,
+        frame: &[u8],
+    ) {
+        let packet_id = match VarInt::read(&mut &frame[..]).await {
+            Ok(packet_id) => packet_id.get_val(),
+            Err(_) => -1,
+        };
+        // The writer task only stops once every sender is gone, or if writing failed, in which
+        // case that's already been logged.
+        let _ = self.sender.send(DumpRecord {
+            timestamp: unix_micros(),
+            direction,
+            conn_id,
+            state: state.clone(),
+            packet_id,
+            data: frame.to_vec(),
+        });
+    }
+}
+
+/// Writes records until the connection is gone. Flushes whenever it's caught up, so the files
+/// are complete up to the last frame even if the server is killed.
+async fn write_records(
+    mut receiver: UnboundedReceiver<DumpRecord>,
+    mut dump: BufWriter<File>,
+    mut index: BufWriter<File>,
+) -> Result<()> {
+    let mut offset = (DUMP_MAGIC.len() + 1) as u64;
+    while let Some(record) = receiver.recv().await {
+        let encoded = encode_record(&record);
+        index.write_u64(offset).await?;
+        index.write_u64(record.timestamp).await?;
+        dump.write_all(&encoded).await?;
+        offset += encoded.len() as u64;
+
+        if receiver.is_empty() {
+            dump.flush().await?;
+            index.flush().await?;
+        }
+    }
+    dump.flush().await?;
+    index.flush().await?;
+    Ok(())
+}
+
+fn encode_record(record: &DumpRecord) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(26 + record.data.len());
+    encoded.extend_from_slice(&record.timestamp.to_be_bytes());
+    encoded.push(match record.direction {
+        Direction::Inbound => 0,
+        Direction::Outbound => 1,
+    });
+    encoded.extend_from_slice(&(record.conn_id as u64).to_be_bytes());
+    encoded.push(state_to_id(&record.state));
+    encoded.extend_from_slice(&record.packet_id.to_be_bytes());
+    encoded.extend_from_slice(&(record.data.len() as u32).to_be_bytes());
+    encoded.extend_from_slice(&record.data);
+    encoded
+}
+
+/// Reads every record in a dump. A record cut off at the end, e.g. because the server was
+/// killed while writing it, is left out.
+pub async fn read_dump<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Vec<DumpRecord>> {
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic).await?;
+    if &magic != DUMP_MAGIC {
+        return Err(Error::PacketDumpError("Not a packet dump".to_string()));
+    }
+    let version = reader.read_u8().await?;
+    if version != DUMP_VERSION {
+        return Err(Error::PacketDumpError(format!(
+            "Unsupported version {}",
+            version
+        )));
+    }
+
+    let mut records = Vec::new();
+    loop {
+        match read_record(reader).await {
+            Ok(record) => records.push(record),
+            Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                return Ok(records)
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+async fn read_record<R: AsyncRead + Unpin>(reader: &mut R) -> Result<DumpRecord> {
+    let timestamp = reader.read_u64().await?;
+    let direction = match reader.read_u8().await? {
+        0 => Direction::Inbound,
+        1 => Direction::Outbound,
+        direction => {
+            return Err(Error::PacketDumpError(format!(
+                "Invalid direction {}",
+                direction
+            )))
+        }
+    };
+    let conn_id = reader.read_u64().await? as ConnectionId;
+    let state = state_from_id(reader.read_u8().await?);
+    let packet_id = reader.read_i32().await?;
+    let length = reader.read_u32().await? as usize;
+    let mut data = vec![0u8; length];
+    reader.read_exact(&mut data).await?;
+
+    Ok(DumpRecord {
+        timestamp,
+        direction,
+        conn_id,
+        state,
+        packet_id,
+        data,
+    })
+}
+
+/// Prints every frame in the dump at `path` as hex, along with the decoded packet for the ones
+/// the server has a decoder for, which is only the ones it receives.
+pub async fn replay(path: impl AsRef<Path>) -> Result<()> {
+    let path: PathBuf = path.as_ref().to_path_buf();
+    let mut file = tokio::io::BufReader::new(File::open(&path).await?);
+    let records = read_dump(&mut file).await?;
+    debug!("Read {} frames from {}", records.len(), path.display());
+
+    let start = records.first().map_or(0, |record| record.timestamp);
+    for record in records {
+        println!("{}", describe_record(&record, start).await);
+    }
+    Ok(())
+}
+
+/// A header line with when and where the frame was sent, the frame as hex, and the decoded
+/// packet if there's a decoder for it.
+async fn describe_record(record: &DumpRecord, start: u64) -> String {
+    let elapsed = record.timestamp.saturating_sub(start);
+    let mut description = format!(
+        "[{}.{:06}] conn {} {} {} 0x{:02X} ({} bytes)\n",
+        elapsed / 1_000_000,
+        elapsed % 1_000_000,
+        record.conn_id,
+        match record.direction {
+            Direction::Inbound => "IN ",
+            Direction::Outbound => "OUT",
+        },
+        record.state,
+        record.packet_id,
+        record.data.len()
+    );
+    description.push_str(&hex_dump(&record.data));
+
+    if record.direction == Direction::Inbound && (0..=u8::MAX as i32).contains(&record.packet_id) {
+        let mut cursor = Cursor::new(record.data.clone());
+        // Skip the packet id, the decoders start after it.
+        if VarInt::read(&mut cursor).await.is_ok() {
+            match debug_decode_packet(record.packet_id as u8, &record.state, &mut cursor).await {
+                Some(Ok(packet)) => description.push_str(&packet),
+                Some(Err(e)) => description.push_str(&format!("Failed to decode: {}", e)),
+                None => {}
+            }
+        }
+    }
+    description
+}
+
+/// Formats `data` like `0000: 00 fa 0b ...`, [HEX_LINE_LENGTH] bytes per line.
+fn hex_dump(data: &[u8]) -> String {
+    data.chunks(HEX_LINE_LENGTH)
+        .enumerate()
+        .map(|(line, bytes)| {
+            let hex = bytes
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("  {:04x}: {}\n", line * HEX_LINE_LENGTH, hex)
+        })
+        .collect()
+}
+
+fn state_to_id(state: &State) -> u8 {
+    match state {
+        State::Unknown => 0,
+        State::Handshake => 1,
+        State::Status => 2,
+        State::Login => 3,
+        State::Play => 4,
+    }
+}
+
+fn state_from_id(id: u8) -> State {
+    match id {
+        1 => State::Handshake,
+        2 => State::Status,
+        3 => State::Login,
+        4 => State::Play,
+        _ => State::Unknown,
+    }
+}
+
+fn unix_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_dump_round_trip() {
+        let directory = std::env::temp_dir().join(format!("ferrumc-dump-{}", Uuid::new_v4()));
+        let dump = PacketDump::create(&directory, 7).await.unwrap();
+
+        // A keep alive from the client, and two packets sent in one go.
+        let keep_alive = [0x12, 0, 0, 0, 0, 0, 0, 0, 42];
+        dump.record_inbound(7, &State::Play, &keep_alive).await;
+        dump.record_outbound(7, &State::Play, &[2, 0x01, 0xAA, 1, 0x02])
+            .await;
+        drop(dump);
+
+        // Wait for the writer task to finish up.
+        let mut entries = Vec::new();
+        for _ in 0..100 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            entries = std::fs::read_dir(&directory)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .collect();
+            let index = entries
+                .iter()
+                .find(|path| path.extension().unwrap() == "idx");
+            if index.is_some_and(|index| std::fs::metadata(index).unwrap().len() == 3 * 16) {
+                break;
+            }
+        }
+        let dump_path = entries
+            .iter()
+            .find(|path| path.extension().unwrap() == "dump")
+            .unwrap();
+
+        let contents = std::fs::read(dump_path).unwrap();
+        let records = read_dump(&mut &contents[..]).await.unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].direction, Direction::Inbound);
+        assert_eq!(records[0].packet_id, 0x12);
+        assert_eq!(records[0].data, keep_alive);
+        assert_eq!(records[1].direction, Direction::Outbound);
+        assert_eq!(records[1].data, vec![0x01, 0xAA]);
+        assert_eq!(records[2].packet_id, 0x02);
+        assert!(records
+            .iter()
+            .all(|r| r.conn_id == 7 && r.state == State::Play));
+
+        // The keep alive has a decoder, the outgoing packets don't.
+        let description = describe_record(&records[0], records[0].timestamp).await;
+        assert!(description.contains("0000: 12 00 00"));
+        assert!(description.contains("42"));
+
+        // A record cut off halfway through is dropped.
+        let truncated = &contents[..contents.len() - 1];
+        assert_eq!(read_dump(&mut &truncated[..]).await.unwrap().len(), 2);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
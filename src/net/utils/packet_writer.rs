@@ -0,0 +1,193 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::error::SendTimeoutError;
+use tokio::sync::{mpsc, Mutex};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use crate::net::utils::encryption::{EncryptedWriter, Encryptor};
+use crate::utils::prelude::*;
+
+/// Something for a connection's writer task to do, in the order it was queued.
+pub enum OutgoingMessage {
+    /// An encoded (and compressed, if enabled) packet, or several back to back.
+    Packet(Vec<u8>),
+    /// Encrypt everything written after this.
+    EnableEncryption(Box<Encryptor>),
+    /// Write out everything queued so far, then close the socket.
+    Close,
+}
+
+/// The sending half of a connection's outgoing packet queue.
+///
+/// Sending only waits for room in the queue, never for the socket, so a slow client can't hold
+/// up whoever is sending to it. If the queue stays full for longer than the configured timeout,
+/// the connection is closed.
+pub struct PacketSender {
+    sender: mpsc::Sender<OutgoingMessage>,
+    closed: CancellationToken,
+    timeout: Duration,
+}
+
+impl PacketSender {
+    pub async fn send(&self, message: OutgoingMessage) -> Result<()> {
+        match self.sender.send_timeout(message, self.timeout).await {
+            Ok(()) => Ok(()),
+            Err(SendTimeoutError::Timeout(_)) => {
+                warn!(
+                    "Outgoing queue was full for more than {:?}, closing connection",
+                    self.timeout
+                );
+                self.closed.cancel();
+                Err(Error::OutgoingQueueFull)
+            }
+            Err(SendTimeoutError::Closed(_)) => Err(Error::ConnectionClosed),
+        }
+    }
+
+    /// Closes the connection once everything already queued has been written. If the queue
+    /// doesn't drain in time, the connection is closed right away instead.
+    pub async fn close(&self) {
+        if let Err(SendTimeoutError::Timeout(_)) = self
+            .sender
+            .send_timeout(OutgoingMessage::Close, self.timeout)
+            .await
+        {
+            self.closed.cancel();
+        }
+    }
+}
+
+/// Spawns the task that writes a connection's queued packets to `writer`.
+///
+/// The task stops once the connection is closed, or `closed` is cancelled. It cancels `closed`
+/// itself when it stops, so the connection's read loop stops along with it.
+pub fn spawn_packet_writer<W>(
+    writer: Arc<Mutex<EncryptedWriter<W>>>,
+    queue_size: usize,
+    timeout: Duration,
+    closed: CancellationToken,
+) -> PacketSender
+where
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(queue_size.max(1));
+
+    let task_closed = closed.clone();
+    tokio::spawn(async move {
+        tokio::select! {
+            result = write_packets(writer, receiver) => {
+                if let Err(e) = result {
+                    debug!("Failed to write to connection: {}", e);
+                }
+            }
+            _ = task_closed.cancelled() => {}
+        }
+        task_closed.cancel();
+    });
+
+    PacketSender {
+        sender,
+        closed,
+        timeout,
+    }
+}
+
+async fn write_packets<W: AsyncWrite + Unpin>(
+    writer: Arc<Mutex<EncryptedWriter<W>>>,
+    mut receiver: mpsc::Receiver<OutgoingMessage>,
+) -> Result<()> {
+    loop {
+        let message = receiver.recv().await;
+        // Only locked per message, since the legacy ping writes to the socket directly.
+        let mut out_stream = writer.lock().await;
+        match message {
+            Some(OutgoingMessage::Packet(packet)) => {
+                out_stream.write_all(&packet).await?;
+                // Flushing once the queue is empty sends bursts of packets out together.
+                if receiver.is_empty() {
+                    out_stream.flush().await?;
+                }
+            }
+            Some(OutgoingMessage::EnableEncryption(encryptor)) => {
+                out_stream.set_cipher(*encryptor);
+            }
+            Some(OutgoingMessage::Close) | None => {
+                out_stream.flush().await?;
+                out_stream.shutdown().await?;
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    #[tokio::test]
+    async fn test_packets_are_written_in_order_before_closing() {
+        let (client, mut server) = tokio::io::duplex(64);
+        let closed = CancellationToken::new();
+        let sender = spawn_packet_writer(
+            Arc::new(Mutex::new(EncryptedWriter::new(client))),
+            4,
+            TIMEOUT,
+            closed.clone(),
+        );
+
+        // More than the queue and the socket can hold, so sending has to wait for the writer.
+        let reading = tokio::spawn(async move {
+            let mut received = Vec::new();
+            server.read_to_end(&mut received).await.unwrap();
+            received
+        });
+        for i in 0..32u8 {
+            sender
+                .send(OutgoingMessage::Packet(vec![i; 16]))
+                .await
+                .unwrap();
+        }
+        sender.close().await;
+
+        let received = reading.await.unwrap();
+        let expected: Vec<u8> = (0..32u8).flat_map(|i| vec![i; 16]).collect();
+        assert_eq!(received, expected);
+
+        closed.cancelled().await;
+        assert!(matches!(
+            sender.send(OutgoingMessage::Packet(vec![0])).await,
+            Err(Error::ConnectionClosed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_closes_the_connection() {
+        // Nobody reads from the other end, so the writer gets stuck once the socket is full.
+        let (client, _server) = tokio::io::duplex(16);
+        let closed = CancellationToken::new();
+        let sender = spawn_packet_writer(
+            Arc::new(Mutex::new(EncryptedWriter::new(client))),
+            1,
+            TIMEOUT,
+            closed.clone(),
+        );
+
+        let mut result = Ok(());
+        for _ in 0..8 {
+            result = sender.send(OutgoingMessage::Packet(vec![0; 16])).await;
+            if result.is_err() {
+                break;
+            }
+        }
+
+        assert!(matches!(result, Err(Error::OutgoingQueueFull)));
+        assert!(closed.is_cancelled());
+    }
+}
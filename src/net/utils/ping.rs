@@ -0,0 +1,120 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use tokio::sync::oneshot;
+use tracing::debug;
+
+use crate::net::packets::outgoing::play_ping::PlayPing;
+use crate::net::packets::ConnectionId;
+use crate::state::ServerState;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+struct PendingPing {
+    conn_id: ConnectionId,
+    sent: Instant,
+    sender: oneshot::Sender<Duration>,
+}
+
+/// The pings sent to clients that haven't been answered yet, by id.
+///
+/// Clients answer pings in order, after everything sent before them, so a pong means the client
+/// has handled all of it. See [ServerState::ping_player].
+#[derive(Default)]
+pub struct PendingPings {
+    next_id: AtomicI32,
+    pending: DashMap<i32, PendingPing>,
+}
+
+impl PendingPings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a ping to `conn_id` that's being sent right now. Returns its id, and a receiver
+    /// for the round trip time once it's answered.
+    pub fn start(&self, conn_id: ConnectionId) -> (i32, oneshot::Receiver<Duration>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        let ping = PendingPing {
+            conn_id,
+            sent: Instant::now(),
+            sender,
+        };
+        self.pending.insert(id, ping);
+        (id, receiver)
+    }
+
+    /// Answers the ping with `id`. Returns `false` if `conn_id` wasn't sent a ping with that
+    /// id, or it already timed out.
+    pub fn complete(&self, conn_id: ConnectionId, id: i32) -> bool {
+        let Some((_, ping)) = self
+            .pending
+            .remove_if(&id, |_, ping| ping.conn_id == conn_id)
+        else {
+            return false;
+        };
+        // Whoever sent it may have stopped waiting, which is fine.
+        let _ = ping.sender.send(ping.sent.elapsed());
+        true
+    }
+
+    /// Gives up on a ping. Its receiver gets an error instead of a round trip time.
+    pub fn cancel(&self, id: i32) {
+        self.pending.remove(&id);
+    }
+
+    /// Gives up on every ping sent to a connection, e.g. once it's closed.
+    pub fn remove_connection(&self, conn_id: ConnectionId) {
+        self.pending.retain(|_, ping| ping.conn_id != conn_id);
+    }
+}
+
+impl ServerState {
+    /// Sends a player a ping, and returns a receiver for the round trip time once they answer.
+    ///
+    /// The receiver gets an error instead if they haven't answered after
+    /// `network.ping_timeout` seconds, or they disconnect first.
+    pub async fn ping_player(&self, conn_id: ConnectionId) -> Result<oneshot::Receiver<Duration>> {
+        let conn = self.connections.get_connection(conn_id)?;
+        let (id, receiver) = self.pending_pings.start(conn_id);
+        if let Err(e) = conn.read().await.send_packet(PlayPing::new(id)).await {
+            self.pending_pings.cancel(id);
+            return Err(e);
+        }
+
+        let timeout = Duration::from_secs(get_global_config().network.ping_timeout);
+        let pending_pings = Arc::clone(&self.pending_pings);
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            if pending_pings.pending.contains_key(&id) {
+                debug!("Ping {} to connection {} timed out", id, conn_id);
+                pending_pings.cancel(id);
+            }
+        });
+        Ok(receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pings_are_answered_by_the_right_connection() {
+        let pings = PendingPings::new();
+        let (first, first_receiver) = pings.start(1);
+        let (second, second_receiver) = pings.start(2);
+        assert_ne!(first, second);
+
+        assert!(!pings.complete(2, first));
+        assert!(pings.complete(1, first));
+        assert!(!pings.complete(1, first));
+        assert!(first_receiver.await.is_ok());
+
+        pings.remove_connection(2);
+        assert!(second_receiver.await.is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use crate::net::packets::outgoing::player_info_remove::PlayerInfoRemove;
+use crate::net::packets::outgoing::player_info_update::{
+    PlayerInfo, PlayerInfoUpdatePacket, UPDATE_GAME_MODE, UPDATE_LATENCY,
+};
+use crate::net::packets::ConnectionId;
+use crate::net::utils::visibility::players_in_world;
+use crate::state::ServerState;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::keep_alive::KeepAlive;
+use crate::utils::components::player::Player;
+use crate::utils::components::profile_properties::ProfileProperties;
+use crate::utils::prelude::*;
+
+/// The player's entry in everyone's player list, made from their components.
+pub async fn player_info(conn_id: ConnectionId, state: &ServerState) -> Result<PlayerInfo> {
+    let component_storage = state.world.get_component_storage();
+    let mut info = {
+        let player = component_storage.get::<Player>(conn_id).await?;
+        PlayerInfo::new(player.uuid, player.username.clone())
+    };
+    if let Ok(profile) = component_storage.get::<ProfileProperties>(conn_id).await {
+        info.properties = profile.properties.clone();
+    }
+    if let Ok(game_mode) = component_storage.get::<GameMode>(conn_id).await {
+        info.game_mode = *game_mode;
+    }
+    if let Ok(keep_alive) = component_storage.get::<KeepAlive>(conn_id).await {
+        info.latency = keep_alive.ping_ms.try_into().unwrap_or(i32::MAX);
+    }
+    Ok(info)
+}
+
+async fn player_infos(players: &[ConnectionId], state: &ServerState) -> Vec<PlayerInfo> {
+    let mut infos = Vec::with_capacity(players.len());
+    for &id in players {
+        if let Ok(info) = player_info(id, state).await {
+            infos.push(info);
+        }
+    }
+    infos
+}
+
+/// Adds a player that just joined to everyone's tab list, and sends them everyone that was
+/// already online. Has to happen before they're spawned for anyone.
+pub async fn add_to_player_list(conn_id: ConnectionId, state: &ServerState) -> Result<()> {
+    let info = player_info(conn_id, state).await?;
+    let players = players_in_world(state).await;
+    state
+        .send_to_players(players.iter().copied(), || {
+            PlayerInfoUpdatePacket::add_players(vec![info.clone()])
+        })
+        .await;
+
+    let others = players
+        .into_iter()
+        .filter(|&id| id != conn_id)
+        .collect::<Vec<_>>();
+    let others = player_infos(&others, state).await;
+    if others.is_empty() {
+        return Ok(());
+    }
+    let conn = state.connections.get_connection(conn_id)?;
+    let conn = conn.read().await;
+    conn.send_packet(PlayerInfoUpdatePacket::add_players(others))
+        .await
+}
+
+/// Removes a player that's leaving from everyone else's tab list.
+pub async fn remove_from_player_list(conn_id: ConnectionId, state: &ServerState) {
+    let Ok(uuid) = state
+        .world
+        .get_component::<Player>(conn_id)
+        .await
+        .map(|player| player.uuid)
+    else {
+        return;
+    };
+    let others = players_in_world(state)
+        .await
+        .into_iter()
+        .filter(|&id| id != conn_id);
+    state
+        .send_to_players(others, || PlayerInfoRemove::new(vec![uuid]))
+        .await;
+}
+
+/// Sends everyone the latest ping of every player, from their keep alives.
+pub async fn broadcast_latency(state: &ServerState) {
+    let players = players_in_world(state).await;
+    let infos = player_infos(&players, state).await;
+    if infos.is_empty() {
+        return;
+    }
+    state
+        .send_to_players(players, || {
+            PlayerInfoUpdatePacket::new(UPDATE_LATENCY, infos.clone())
+        })
+        .await;
+}
+
+/// Sends everyone a player's new gamemode, so their entry in the tab list shows it.
+pub async fn broadcast_game_mode(conn_id: ConnectionId, state: &ServerState) -> Result<()> {
+    let info = player_info(conn_id, state).await?;
+    state
+        .broadcast(|| PlayerInfoUpdatePacket::new(UPDATE_GAME_MODE, vec![info.clone()]))
+        .await;
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use std::io::Cursor;
+use std::net::IpAddr;
+
+use ferrumc_codec::network_types::varint::VarInt;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::net::utils::authentication::ProfileProperty;
+use crate::utils::impls::packet_impls::NetDecode;
+use crate::utils::prelude::*;
+
+/// The login plugin channel Velocity forwards player info on.
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+/// The modern forwarding version we ask Velocity for. Version 1 is the base every release supports.
+pub const VELOCITY_FORWARDING_VERSION: u8 = 1;
+/// Velocity signs the player info with HMAC-SHA256, which puts a 32 byte signature in front of it.
+const VELOCITY_SIGNATURE_LENGTH: usize = 32;
+
+/// Who a proxy in front of the server says the player is. The proxy has already authenticated
+/// them, so this is trusted instead of the client's own login.
+#[derive(Debug, Clone)]
+pub struct ForwardedPlayer {
+    /// The player's real address, instead of the proxy's.
+    pub address: IpAddr,
+    pub uuid: Uuid,
+    pub properties: Vec<ProfileProperty>,
+}
+
+/// Splits the handshake's server address into the actual host and the data BungeeCord forwards
+/// when `ip_forward` is on.
+///
+/// BungeeCord appends the player's address, their UUID and (in online mode) their profile
+/// properties, all separated by NUL characters.
+pub fn parse_bungeecord_address(server_address: &str) -> Result<(&str, ForwardedPlayer)> {
+    let mut parts = server_address.split('\0');
+    let host = parts.next().unwrap_or_default();
+
+    let (Some(address), Some(uuid)) = (parts.next(), parts.next()) else {
+        return Err(Error::ProxyForwardingError(
+            "Handshake is missing the forwarded address and UUID".to_string(),
+        ));
+    };
+    let address = address.parse::<IpAddr>().map_err(|e| {
+        Error::ProxyForwardingError(format!("Invalid forwarded address {:?}: {}", address, e))
+    })?;
+    let uuid = Uuid::parse_str(uuid).map_err(|e| {
+        Error::ProxyForwardingError(format!("Invalid forwarded UUID {:?}: {}", uuid, e))
+    })?;
+    let properties = match parts.next() {
+        Some(properties) => serde_json::from_str(properties).map_err(|e| {
+            Error::ProxyForwardingError(format!("Invalid forwarded properties: {}", e))
+        })?,
+        None => Vec::new(),
+    };
+
+    Ok((
+        host,
+        ForwardedPlayer {
+            address,
+            uuid,
+            properties,
+        },
+    ))
+}
+
+/// Checks the signature on Velocity's player info, and reads the forwarded player from it.
+///
+/// `data` is the login plugin response's payload and `secret` the forwarding secret shared with
+/// Velocity. Returns the player's username along with the forwarded info.
+pub async fn parse_velocity_player_info(
+    data: &[u8],
+    secret: &[u8],
+) -> Result<(String, ForwardedPlayer)> {
+    if data.len() < VELOCITY_SIGNATURE_LENGTH {
+        return Err(Error::ProxyForwardingError(
+            "Velocity player info is too short to be signed".to_string(),
+        ));
+    }
+    let (signature, payload) = data.split_at(VELOCITY_SIGNATURE_LENGTH);
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+        .map_err(|e| Error::ProxyForwardingError(e.to_string()))?;
+    mac.update(payload);
+    mac.verify_slice(signature).map_err(|_| {
+        Error::ProxyForwardingError(
+            "Velocity player info has an invalid signature, check the forwarding secret"
+                .to_string(),
+        )
+    })?;
+
+    let mut payload = Cursor::new(payload);
+    let version = VarInt::read(&mut payload).await?.get_val();
+    if version < 1 {
+        return Err(Error::ProxyForwardingError(format!(
+            "Unsupported Velocity forwarding version {}",
+            version
+        )));
+    }
+
+    let address = *String::net_decode(&mut payload).await?;
+    let address = address.parse::<IpAddr>().map_err(|e| {
+        Error::ProxyForwardingError(format!("Invalid forwarded address {:?}: {}", address, e))
+    })?;
+    let uuid = Uuid::from_u128(*u128::net_decode(&mut payload).await?);
+    let username = *String::net_decode(&mut payload).await?;
+
+    let property_count = VarInt::read(&mut payload).await?.get_val();
+    let mut properties = Vec::new();
+    for _ in 0..property_count {
+        let name = *String::net_decode(&mut payload).await?;
+        let value = *String::net_decode(&mut payload).await?;
+        let signature = if *bool::net_decode(&mut payload).await? {
+            Some(*String::net_decode(&mut payload).await?)
+        } else {
+            None
+        };
+        properties.push(ProfileProperty {
+            name,
+            value,
+            signature,
+        });
+    }
+
+    Ok((
+        username,
+        ForwardedPlayer {
+            address,
+            uuid,
+            properties,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    const SECRET: &[u8] = b"hunter2";
+
+    async fn velocity_player_info(secret: &[u8]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        VarInt::new(1).net_encode(&mut payload).await.unwrap();
+        "198.51.100.4"
+            .to_string()
+            .net_encode(&mut payload)
+            .await
+            .unwrap();
+        payload.extend_from_slice(&0x069a79f444e94726a5befca90e38aaf5u128.to_be_bytes());
+        "Notch".to_string().net_encode(&mut payload).await.unwrap();
+        VarInt::new(1).net_encode(&mut payload).await.unwrap();
+        "textures"
+            .to_string()
+            .net_encode(&mut payload)
+            .await
+            .unwrap();
+        "abc".to_string().net_encode(&mut payload).await.unwrap();
+        true.net_encode(&mut payload).await.unwrap();
+        "def".to_string().net_encode(&mut payload).await.unwrap();
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(&payload);
+        let mut data = mac.finalize().into_bytes().to_vec();
+        data.extend(payload);
+        data
+    }
+
+    #[tokio::test]
+    async fn test_parse_velocity_player_info() {
+        let data = velocity_player_info(SECRET).await;
+
+        let (username, forwarded) = parse_velocity_player_info(&data, SECRET).await.unwrap();
+        assert_eq!(username, "Notch");
+        assert_eq!(forwarded.address, "198.51.100.4".parse::<IpAddr>().unwrap());
+        assert_eq!(forwarded.uuid.as_u128(), 0x069a79f444e94726a5befca90e38aaf5);
+        assert_eq!(forwarded.properties.len(), 1);
+        assert_eq!(forwarded.properties[0].signature.as_deref(), Some("def"));
+    }
+
+    #[tokio::test]
+    async fn test_velocity_player_info_with_wrong_secret_is_rejected() {
+        let data = velocity_player_info(b"not the secret").await;
+        assert!(parse_velocity_player_info(&data, SECRET).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_velocity_player_info_is_rejected() {
+        let mut data = velocity_player_info(SECRET).await;
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(parse_velocity_player_info(&data, SECRET).await.is_err());
+    }
+
+    #[test]
+    fn test_parse_bungeecord_address() {
+        let server_address = "play.example.com\u{0}203.0.113.7\u{0}069a79f444e94726a5befca90e38aaf5\u{0}[{\"name\":\"textures\",\"value\":\"abc\",\"signature\":\"def\"}]";
+
+        let (host, forwarded) = parse_bungeecord_address(server_address).unwrap();
+        assert_eq!(host, "play.example.com");
+        assert_eq!(forwarded.address, "203.0.113.7".parse::<IpAddr>().unwrap());
+        assert_eq!(
+            forwarded.uuid,
+            Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()
+        );
+        assert_eq!(forwarded.properties.len(), 1);
+        assert_eq!(forwarded.properties[0].name, "textures");
+        assert_eq!(forwarded.properties[0].signature.as_deref(), Some("def"));
+    }
+
+    #[test]
+    fn test_parse_bungeecord_address_without_properties() {
+        let server_address = "localhost\u{0}::1\u{0}069a79f444e94726a5befca90e38aaf5";
+
+        let (host, forwarded) = parse_bungeecord_address(server_address).unwrap();
+        assert_eq!(host, "localhost");
+        assert!(forwarded.address.is_ipv6());
+        assert!(forwarded.properties.is_empty());
+    }
+
+    #[test]
+    fn test_plain_address_is_rejected() {
+        assert!(parse_bungeecord_address("localhost").is_err());
+        assert!(parse_bungeecord_address("localhost\u{0}not an ip\u{0}abc").is_err());
+    }
+}
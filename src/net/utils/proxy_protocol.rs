@@ -0,0 +1,269 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::utils::prelude::*;
+
+/// Every v2 header starts with this.
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+/// Every v1 header starts with this.
+const V1_PREFIX: &[u8; 6] = b"PROXY ";
+/// The longest a v1 header can be, including the trailing CRLF.
+const V1_MAX_LENGTH: usize = 107;
+
+const V2_COMMAND_LOCAL: u8 = 0x0;
+const V2_COMMAND_PROXY: u8 = 0x1;
+const V2_FAMILY_UNSPEC: u8 = 0x0;
+const V2_FAMILY_INET: u8 = 0x1;
+const V2_FAMILY_INET6: u8 = 0x2;
+const V2_TRANSPORT_STREAM: u8 = 0x1;
+
+/// Reads a PROXY protocol (v1 or v2) header from the start of `reader`.
+///
+/// Only the header itself is consumed, so the handshake can be read from `reader` right after.
+/// Returns the client's real address, or `None` if the proxy didn't know it (`UNKNOWN` or
+/// `LOCAL`, e.g. for the balancer's own health checks).
+pub async fn read_proxy_header<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<SocketAddr>> {
+    let mut start = [0u8; 6];
+    reader.read_exact(&mut start).await?;
+
+    if &start == V1_PREFIX {
+        read_v1_header(reader).await
+    } else if start == V2_SIGNATURE[..6] {
+        let mut rest = [0u8; 6];
+        reader.read_exact(&mut rest).await?;
+        if rest != V2_SIGNATURE[6..] {
+            return Err(Error::ProxyProtocolError(
+                "Invalid v2 signature".to_string(),
+            ));
+        }
+        read_v2_header(reader).await
+    } else {
+        Err(Error::ProxyProtocolError(
+            "Connection didn't start with a PROXY header".to_string(),
+        ))
+    }
+}
+
+/// Reads the rest of a v1 header, e.g. `TCP4 203.0.113.7 10.0.0.1 51234 25565\r\n`.
+async fn read_v1_header<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<SocketAddr>> {
+    // Read byte by byte, since anything after the CRLF already belongs to the handshake.
+    let mut line = Vec::new();
+    while !line.ends_with(b"\r\n") {
+        if V1_PREFIX.len() + line.len() >= V1_MAX_LENGTH {
+            return Err(Error::ProxyProtocolError(
+                "v1 header is too long".to_string(),
+            ));
+        }
+        line.push(reader.read_u8().await?);
+    }
+    line.truncate(line.len() - 2);
+
+    let line = std::str::from_utf8(&line)
+        .map_err(|_| Error::ProxyProtocolError("v1 header isn't valid ASCII".to_string()))?;
+    parse_v1_fields(line)
+}
+
+fn parse_v1_fields(line: &str) -> Result<Option<SocketAddr>> {
+    let fields: Vec<&str> = line.split(' ').collect();
+    match fields.as_slice() {
+        ["UNKNOWN", ..] => Ok(None),
+        [protocol @ ("TCP4" | "TCP6"), source, _destination, source_port, _destination_port] => {
+            let address = source.parse::<IpAddr>().map_err(|_| {
+                Error::ProxyProtocolError(format!("Invalid source address {:?}", source))
+            })?;
+            if address.is_ipv4() != (*protocol == "TCP4") {
+                return Err(Error::ProxyProtocolError(format!(
+                    "Source address {} doesn't match {}",
+                    address, protocol
+                )));
+            }
+            let port = source_port.parse::<u16>().map_err(|_| {
+                Error::ProxyProtocolError(format!("Invalid source port {:?}", source_port))
+            })?;
+            Ok(Some(SocketAddr::new(address, port)))
+        }
+        _ => Err(Error::ProxyProtocolError(format!(
+            "Malformed v1 header {:?}",
+            line
+        ))),
+    }
+}
+
+/// Reads the rest of a v2 header, after the signature.
+async fn read_v2_header<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<SocketAddr>> {
+    let version_command = reader.read_u8().await?;
+    let family_transport = reader.read_u8().await?;
+    let length = reader.read_u16().await? as usize;
+
+    // Always read the whole address block, so the stream is left at the handshake.
+    let mut addresses = vec![0u8; length];
+    reader.read_exact(&mut addresses).await?;
+
+    if version_command >> 4 != 2 {
+        return Err(Error::ProxyProtocolError(format!(
+            "Unsupported version {}",
+            version_command >> 4
+        )));
+    }
+    match version_command & 0x0F {
+        V2_COMMAND_LOCAL => return Ok(None),
+        V2_COMMAND_PROXY => {}
+        command => {
+            return Err(Error::ProxyProtocolError(format!(
+                "Unknown command {}",
+                command
+            )))
+        }
+    }
+
+    let (family, transport) = (family_transport >> 4, family_transport & 0x0F);
+    if family == V2_FAMILY_UNSPEC {
+        return Ok(None);
+    }
+    if transport != V2_TRANSPORT_STREAM {
+        return Err(Error::ProxyProtocolError(format!(
+            "Unsupported transport {}",
+            transport
+        )));
+    }
+
+    // Source address, destination address, source port, destination port. TLVs may follow.
+    let too_short = || {
+        Error::ProxyProtocolError(format!(
+            "Address block of {} bytes is too short for family {}",
+            length, family
+        ))
+    };
+    match family {
+        V2_FAMILY_INET => {
+            let block = addresses.get(..12).ok_or_else(too_short)?;
+            let address = Ipv4Addr::new(block[0], block[1], block[2], block[3]);
+            let port = u16::from_be_bytes([block[8], block[9]]);
+            Ok(Some(SocketAddr::new(IpAddr::V4(address), port)))
+        }
+        V2_FAMILY_INET6 => {
+            let block = addresses.get(..36).ok_or_else(too_short)?;
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&block[..16]);
+            let address = Ipv6Addr::from(octets);
+            let port = u16::from_be_bytes([block[32], block[33]]);
+            Ok(Some(SocketAddr::new(IpAddr::V6(address), port)))
+        }
+        _ => Err(Error::ProxyProtocolError(format!(
+            "Unsupported address family {}",
+            family
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `header` followed by some handshake bytes, and checks that only the header was read.
+    async fn parse(header: &[u8]) -> Result<Option<SocketAddr>> {
+        let mut data = header.to_vec();
+        data.extend_from_slice(&[0x10, 0x00]);
+        let mut reader = &data[..];
+        let address = read_proxy_header(&mut reader).await?;
+        assert_eq!(reader, [0x10, 0x00]);
+        Ok(address)
+    }
+
+    fn v2_header(command: u8, family_transport: u8, addresses: &[u8]) -> Vec<u8> {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.push(0x20 | command);
+        header.push(family_transport);
+        header.extend_from_slice(&(addresses.len() as u16).to_be_bytes());
+        header.extend_from_slice(addresses);
+        header
+    }
+
+    #[tokio::test]
+    async fn test_v1_tcp4() {
+        let address = parse(b"PROXY TCP4 203.0.113.7 10.0.0.1 51234 25565\r\n")
+            .await
+            .unwrap();
+        assert_eq!(address, Some("203.0.113.7:51234".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_v1_tcp6() {
+        let address = parse(b"PROXY TCP6 2001:db8::1 2001:db8::2 51234 25565\r\n")
+            .await
+            .unwrap();
+        assert_eq!(address, Some("[2001:db8::1]:51234".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_v1_unknown() {
+        assert_eq!(parse(b"PROXY UNKNOWN\r\n").await.unwrap(), None);
+        assert_eq!(
+            parse(b"PROXY UNKNOWN 203.0.113.7 10.0.0.1 51234 25565\r\n")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_v1_malformed_is_rejected() {
+        assert!(parse(b"PROXY TCP4 203.0.113.7\r\n").await.is_err());
+        assert!(parse(b"PROXY TCP4 2001:db8::1 10.0.0.1 51234 25565\r\n")
+            .await
+            .is_err());
+        assert!(parse(&[b'A'; 200]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_v2_inet() {
+        let mut addresses = vec![203, 0, 113, 7, 10, 0, 0, 1];
+        addresses.extend_from_slice(&51234u16.to_be_bytes());
+        addresses.extend_from_slice(&25565u16.to_be_bytes());
+
+        let address = parse(&v2_header(V2_COMMAND_PROXY, 0x11, &addresses))
+            .await
+            .unwrap();
+        assert_eq!(address, Some("203.0.113.7:51234".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_v2_inet6_with_tlvs() {
+        let source: Ipv6Addr = "2001:db8::1".parse().unwrap();
+        let mut addresses = source.octets().to_vec();
+        addresses.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        addresses.extend_from_slice(&51234u16.to_be_bytes());
+        addresses.extend_from_slice(&25565u16.to_be_bytes());
+        // A NOOP TLV, which should be skipped over.
+        addresses.extend_from_slice(&[0x04, 0x00, 0x01, 0x00]);
+
+        let address = parse(&v2_header(V2_COMMAND_PROXY, 0x21, &addresses))
+            .await
+            .unwrap();
+        assert_eq!(address, Some("[2001:db8::1]:51234".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_v2_unspec_and_local() {
+        assert_eq!(
+            parse(&v2_header(V2_COMMAND_PROXY, 0x00, &[]))
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            parse(&v2_header(V2_COMMAND_LOCAL, 0x00, &[]))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_header_is_rejected() {
+        // A regular handshake packet
+        let result = parse(&[0x10, 0x00, 0xFD, 0x05, 0x09, b'l', b'o']).await;
+        assert!(matches!(result, Err(Error::ProxyProtocolError(_))));
+    }
+}
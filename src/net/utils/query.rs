@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use rand::random;
+
+use crate::utils::constants::GAME_VERSION;
+
+/// Every query request starts with this.
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+const TYPE_HANDSHAKE: u8 = 0x09;
+const TYPE_STAT: u8 = 0x00;
+
+/// Comes before the key-value section of a full stat response. Clients skip over it.
+const FULL_STAT_KV_PADDING: &[u8] = b"splitnum\0\x80\0";
+/// Comes before the player list of a full stat response.
+const FULL_STAT_PLAYERS_PADDING: &[u8] = b"\x01player_\0\0";
+
+/// How long a challenge token can be used for after the handshake, same as vanilla.
+pub const CHALLENGE_TOKEN_LIFETIME: Duration = Duration::from_secs(30);
+
+/// A request in the GameSpy4 query protocol.
+///
+/// The client starts with a handshake to get a challenge token, and has to send that token back
+/// with its stat requests. That way the server can't be used to reflect traffic at spoofed
+/// addresses, since those never get to see the token.
+#[derive(Debug, PartialEq)]
+pub enum QueryRequest {
+    Handshake { session_id: i32 },
+    BasicStat { session_id: i32, token: i32 },
+    FullStat { session_id: i32, token: i32 },
+}
+
+impl QueryRequest {
+    /// Parses a request datagram. Returns `None` for anything that isn't a valid query request,
+    /// which should just be ignored.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let (magic, data) = data.split_first_chunk::<2>()?;
+        if *magic != MAGIC {
+            return None;
+        }
+        let (&kind, data) = data.split_first()?;
+        let (session_id, payload) = data.split_first_chunk::<4>()?;
+        let session_id = i32::from_be_bytes(*session_id);
+
+        match kind {
+            TYPE_HANDSHAKE => Some(Self::Handshake { session_id }),
+            TYPE_STAT => {
+                let (token, padding) = payload.split_first_chunk::<4>()?;
+                let token = i32::from_be_bytes(*token);
+                // Full stat requests are padded with 4 more bytes.
+                if padding.len() >= 4 {
+                    Some(Self::FullStat { session_id, token })
+                } else {
+                    Some(Self::BasicStat { session_id, token })
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The challenge tokens that have been handed out, by client address.
+pub struct ChallengeTokens {
+    tokens: HashMap<SocketAddr, (i32, Instant)>,
+    lifetime: Duration,
+}
+
+impl ChallengeTokens {
+    pub fn new(lifetime: Duration) -> Self {
+        Self {
+            tokens: HashMap::new(),
+            lifetime,
+        }
+    }
+
+    /// Hands out a new token for `addr`, replacing its old one.
+    pub fn issue(&mut self, addr: SocketAddr) -> i32 {
+        // Expired tokens are only ever dropped here, which keeps the map from growing forever.
+        let lifetime = self.lifetime;
+        self.tokens
+            .retain(|_, (_, issued)| issued.elapsed() < lifetime);
+
+        let token = random::<i32>();
+        self.tokens.insert(addr, (token, Instant::now()));
+        token
+    }
+
+    /// Whether `token` is the unexpired token handed out to `addr`.
+    pub fn is_valid(&self, addr: SocketAddr, token: i32) -> bool {
+        self.tokens
+            .get(&addr)
+            .is_some_and(|&(issued_token, issued)| {
+                issued_token == token && issued.elapsed() < self.lifetime
+            })
+    }
+}
+
+/// What the server reports about itself in stat responses.
+pub struct ServerInfo {
+    pub motd: String,
+    pub map: String,
+    pub players: Vec<String>,
+    pub max_players: i32,
+    pub host_port: u16,
+    pub host_ip: String,
+}
+
+/// The answer to a handshake, carrying the token as a decimal string.
+pub fn handshake_response(session_id: i32, token: i32) -> Vec<u8> {
+    let mut response = response_header(TYPE_HANDSHAKE, session_id);
+    push_string(&mut response, &token.to_string());
+    response
+}
+
+/// MOTD, game type, map, player count, max players, host port and host IP, in that order.
+pub fn basic_stat_response(session_id: i32, info: &ServerInfo) -> Vec<u8> {
+    let mut response = response_header(TYPE_STAT, session_id);
+    push_string(&mut response, &info.motd);
+    push_string(&mut response, "SMP");
+    push_string(&mut response, &info.map);
+    push_string(&mut response, &info.players.len().to_string());
+    push_string(&mut response, &info.max_players.to_string());
+    // The only little endian field in the whole protocol.
+    response.extend_from_slice(&info.host_port.to_le_bytes());
+    push_string(&mut response, &info.host_ip);
+    response
+}
+
+/// Everything in the basic stat as key-value pairs, plus the names of the online players.
+pub fn full_stat_response(session_id: i32, info: &ServerInfo) -> Vec<u8> {
+    let mut response = response_header(TYPE_STAT, session_id);
+    response.extend_from_slice(FULL_STAT_KV_PADDING);
+
+    let values = [
+        ("hostname", info.motd.as_str()),
+        ("gametype", "SMP"),
+        ("game_id", "MINECRAFT"),
+        ("version", GAME_VERSION),
+        ("plugins", ""),
+        ("map", &info.map),
+        ("numplayers", &info.players.len().to_string()),
+        ("maxplayers", &info.max_players.to_string()),
+        ("hostport", &info.host_port.to_string()),
+        ("hostip", &info.host_ip),
+    ];
+    for (key, value) in values {
+        push_string(&mut response, key);
+        push_string(&mut response, value);
+    }
+    response.push(0);
+
+    response.extend_from_slice(FULL_STAT_PLAYERS_PADDING);
+    for player in &info.players {
+        push_string(&mut response, player);
+    }
+    response.push(0);
+    response
+}
+
+fn response_header(kind: u8, session_id: i32) -> Vec<u8> {
+    let mut response = vec![kind];
+    response.extend_from_slice(&session_id.to_be_bytes());
+    response
+}
+
+fn push_string(buffer: &mut Vec<u8>, string: &str) {
+    buffer.extend_from_slice(string.as_bytes());
+    buffer.push(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_requests() {
+        let mut handshake = vec![0xFE, 0xFD, 0x09];
+        handshake.extend_from_slice(&1i32.to_be_bytes());
+        assert_eq!(
+            QueryRequest::parse(&handshake),
+            Some(QueryRequest::Handshake { session_id: 1 })
+        );
+
+        let mut basic_stat = vec![0xFE, 0xFD, 0x00];
+        basic_stat.extend_from_slice(&1i32.to_be_bytes());
+        basic_stat.extend_from_slice(&9513307i32.to_be_bytes());
+        assert_eq!(
+            QueryRequest::parse(&basic_stat),
+            Some(QueryRequest::BasicStat {
+                session_id: 1,
+                token: 9513307
+            })
+        );
+
+        let mut full_stat = basic_stat.clone();
+        full_stat.extend_from_slice(&[0; 4]);
+        assert_eq!(
+            QueryRequest::parse(&full_stat),
+            Some(QueryRequest::FullStat {
+                session_id: 1,
+                token: 9513307
+            })
+        );
+
+        assert_eq!(QueryRequest::parse(&[0xFE, 0xFD, 0x09, 0x00]), None);
+        assert_eq!(QueryRequest::parse(&[0xFE, 0xFD, 0x00, 0, 0, 0, 1]), None);
+        assert_eq!(QueryRequest::parse(&[0xFE, 0x01, 0x09, 0, 0, 0, 1]), None);
+    }
+
+    #[test]
+    fn test_challenge_tokens_expire() {
+        let addr: SocketAddr = "203.0.113.7:51234".parse().unwrap();
+        let other: SocketAddr = "203.0.113.8:51234".parse().unwrap();
+
+        let mut tokens = ChallengeTokens::new(CHALLENGE_TOKEN_LIFETIME);
+        let token = tokens.issue(addr);
+        assert!(tokens.is_valid(addr, token));
+        assert!(!tokens.is_valid(addr, token.wrapping_add(1)));
+        assert!(!tokens.is_valid(other, token));
+
+        let mut tokens = ChallengeTokens::new(Duration::ZERO);
+        let token = tokens.issue(addr);
+        assert!(!tokens.is_valid(addr, token));
+    }
+
+    #[test]
+    fn test_basic_stat_response() {
+        let info = ServerInfo {
+            motd: "A Server".to_string(),
+            map: "world".to_string(),
+            players: vec!["Notch".to_string()],
+            max_players: 20,
+            host_port: 25565,
+            host_ip: "127.0.0.1".to_string(),
+        };
+        let mut expected = vec![0x00, 0, 0, 0, 1];
+        expected.extend_from_slice(b"A Server\0SMP\0world\x001\x0020\0");
+        expected.extend_from_slice(&[0xDD, 0x63]);
+        expected.extend_from_slice(b"127.0.0.1\0");
+        assert_eq!(basic_stat_response(1, &info), expected);
+    }
+}
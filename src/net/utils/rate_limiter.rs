@@ -0,0 +1,101 @@
+use std::time::Instant;
+
+/// A token bucket limiting how many packets a connection can send.
+///
+/// The bucket holds up to `burst` tokens and refills at `rate` tokens per second. Every packet
+/// takes a token, so a client can send short bursts above the rate, but not keep it up.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(rate: u32, burst: u32) -> Self {
+        Self::new_at(rate, burst, Instant::now())
+    }
+
+    fn new_at(rate: u32, burst: u32, now: Instant) -> Self {
+        // Anything below one would never let a packet through.
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate: f64::from(rate),
+            burst,
+            tokens: burst,
+            last_refill: now,
+        }
+    }
+
+    /// Takes a token for a packet. Returns `false` if the connection is over the limit.
+    pub fn try_acquire(&mut self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last_refill = now;
+
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_burst_is_allowed_then_limited() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new_at(10, 5, start);
+
+        for _ in 0..5 {
+            assert!(limiter.try_acquire_at(start));
+        }
+        assert!(!limiter.try_acquire_at(start));
+    }
+
+    #[test]
+    fn test_tokens_refill_at_rate() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new_at(10, 5, start);
+        for _ in 0..5 {
+            limiter.try_acquire_at(start);
+        }
+
+        // 10 per second is one every 100ms.
+        assert!(!limiter.try_acquire_at(start + Duration::from_millis(50)));
+        assert!(limiter.try_acquire_at(start + Duration::from_millis(150)));
+        assert!(!limiter.try_acquire_at(start + Duration::from_millis(160)));
+    }
+
+    #[test]
+    fn test_refill_is_capped_at_burst() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new_at(10, 5, start);
+
+        let later = start + Duration::from_secs(60);
+        for _ in 0..5 {
+            assert!(limiter.try_acquire_at(later));
+        }
+        assert!(!limiter.try_acquire_at(later));
+    }
+
+    #[test]
+    fn test_steady_rate_is_never_limited() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new_at(20, 1, start);
+
+        for i in 0..1000 {
+            assert!(limiter.try_acquire_at(start + Duration::from_millis(50 * i)));
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::utils::prelude::*;
+
+/// Sent by the client to log in with the password.
+pub const TYPE_AUTH: i32 = 3;
+/// Sent by the client to run a command. Shares its value with [TYPE_AUTH_RESPONSE].
+pub const TYPE_EXEC_COMMAND: i32 = 2;
+/// The answer to [TYPE_AUTH]. The request id is -1 if the password was wrong.
+pub const TYPE_AUTH_RESPONSE: i32 = 2;
+/// The output of a command.
+pub const TYPE_RESPONSE_VALUE: i32 = 0;
+
+/// The request id sent back when authentication fails.
+pub const AUTH_FAILED_ID: i32 = -1;
+
+/// The request id, the type and the two null bytes at the end.
+const MIN_PACKET_LENGTH: i32 = 10;
+/// Same as vanilla. Nobody types commands anywhere near this long.
+const MAX_REQUEST_LENGTH: i32 = 1460;
+/// Longer command output is split over several packets.
+pub const MAX_RESPONSE_BODY: usize = 4096;
+
+/// A Source RCON packet.
+///
+/// All integers are little endian, and the body is followed by two null bytes.
+#[derive(Debug, PartialEq)]
+pub struct RconPacket {
+    pub request_id: i32,
+    pub kind: i32,
+    pub body: String,
+}
+
+impl RconPacket {
+    pub fn new(request_id: i32, kind: i32, body: impl Into<String>) -> Self {
+        Self {
+            request_id,
+            kind,
+            body: body.into(),
+        }
+    }
+
+    pub async fn read<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self> {
+        let length = reader.read_i32_le().await?;
+        if !(MIN_PACKET_LENGTH..=MAX_REQUEST_LENGTH).contains(&length) {
+            return Err(Error::RconError(format!(
+                "Invalid packet length {}",
+                length
+            )));
+        }
+
+        let request_id = reader.read_i32_le().await?;
+        let kind = reader.read_i32_le().await?;
+        let mut body = vec![0u8; length as usize - 8];
+        reader.read_exact(&mut body).await?;
+        if !body.ends_with(&[0, 0]) {
+            return Err(Error::RconError(
+                "Packet body isn't null terminated".to_string(),
+            ));
+        }
+        body.truncate(body.len() - 2);
+
+        let body = String::from_utf8(body)
+            .map_err(|_| Error::RconError("Packet body isn't valid UTF-8".to_string()))?;
+        Ok(Self {
+            request_id,
+            kind,
+            body,
+        })
+    }
+
+    pub async fn write<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> Result<()> {
+        let mut packet = Vec::with_capacity(14 + self.body.len());
+        packet.extend_from_slice(&(MIN_PACKET_LENGTH + self.body.len() as i32).to_le_bytes());
+        packet.extend_from_slice(&self.request_id.to_le_bytes());
+        packet.extend_from_slice(&self.kind.to_le_bytes());
+        packet.extend_from_slice(self.body.as_bytes());
+        packet.extend_from_slice(&[0, 0]);
+
+        writer.write_all(&packet).await?;
+        Ok(())
+    }
+}
+
+/// Splits command output into response packets of at most [MAX_RESPONSE_BODY] bytes each,
+/// without splitting up any characters. Empty output still gets one empty packet.
+pub fn split_response(request_id: i32, output: &str) -> Vec<RconPacket> {
+    let mut packets = Vec::new();
+    let mut rest = output;
+    loop {
+        let mut end = rest.len().min(MAX_RESPONSE_BODY);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (body, remaining) = rest.split_at(end);
+        packets.push(RconPacket::new(request_id, TYPE_RESPONSE_VALUE, body));
+        if remaining.is_empty() {
+            return packets;
+        }
+        rest = remaining;
+    }
+}
+
+/// Whether `given` is the password. Takes the same time however much of it is right, so it
+/// can't be guessed a character at a time. Both are hashed first, so their lengths don't give
+/// anything away either.
+pub fn password_matches(given: &str, password: &str) -> bool {
+    let given = Sha256::digest(given.as_bytes());
+    let password = Sha256::digest(password.as_bytes());
+    given
+        .iter()
+        .zip(password.iter())
+        .fold(0, |difference, (a, b)| difference | (a ^ b))
+        == 0
+}
+
+/// Keeps track of failed logins, so the password can't be brute forced.
+///
+/// An IP that fails `max_failures` times is locked out until `window` has passed since its
+/// first failure.
+pub struct AuthFailures {
+    failures: HashMap<IpAddr, (u32, Instant)>,
+    max_failures: u32,
+    window: Duration,
+}
+
+impl AuthFailures {
+    pub fn new(max_failures: u32, window: Duration) -> Self {
+        Self {
+            failures: HashMap::new(),
+            max_failures,
+            window,
+        }
+    }
+
+    /// Whether `ip` has failed too often to be allowed to try again yet.
+    pub fn is_locked_out(&mut self, ip: IpAddr) -> bool {
+        self.prune();
+        self.failures
+            .get(&ip)
+            .is_some_and(|&(count, _)| count >= self.max_failures)
+    }
+
+    pub fn record_failure(&mut self, ip: IpAddr) {
+        self.prune();
+        self.failures.entry(ip).or_insert((0, Instant::now())).0 += 1;
+    }
+
+    fn prune(&mut self) {
+        let window = self.window;
+        self.failures
+            .retain(|_, (_, first_failure)| first_failure.elapsed() < window);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_packet_round_trip() {
+        let packet = RconPacket::new(42, TYPE_EXEC_COMMAND, "list");
+        let mut buffer = Vec::new();
+        packet.write(&mut buffer).await.unwrap();
+        assert_eq!(buffer.len(), 4 + 14);
+        assert_eq!(buffer[..4], 14i32.to_le_bytes());
+
+        let read = RconPacket::read(&mut &buffer[..]).await.unwrap();
+        assert_eq!(read, packet);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_packets_are_rejected() {
+        let mut too_long = Vec::new();
+        too_long.extend_from_slice(&(MAX_REQUEST_LENGTH + 1).to_le_bytes());
+        assert!(RconPacket::read(&mut &too_long[..]).await.is_err());
+
+        let mut unterminated = Vec::new();
+        unterminated.extend_from_slice(&10i32.to_le_bytes());
+        unterminated.extend_from_slice(&[0; 8]);
+        unterminated.extend_from_slice(b"ab");
+        assert!(RconPacket::read(&mut &unterminated[..]).await.is_err());
+    }
+
+    #[test]
+    fn test_long_output_is_split() {
+        let output = "é".repeat(MAX_RESPONSE_BODY);
+        let packets = split_response(7, &output);
+        assert_eq!(packets.len(), 2);
+        assert!(packets.iter().all(|packet| packet.request_id == 7
+            && packet.kind == TYPE_RESPONSE_VALUE
+            && packet.body.len() <= MAX_RESPONSE_BODY));
+        let joined: String = packets.into_iter().map(|packet| packet.body).collect();
+        assert_eq!(joined, output);
+
+        assert_eq!(split_response(7, ""), vec![RconPacket::new(7, 0, "")]);
+    }
+
+    #[test]
+    fn test_password_matches() {
+        assert!(password_matches("hunter2", "hunter2"));
+        assert!(!password_matches("hunter3", "hunter2"));
+        assert!(!password_matches("hunter", "hunter2"));
+        assert!(!password_matches("", "hunter2"));
+    }
+
+    #[test]
+    fn test_auth_failures_lock_out() {
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let mut failures = AuthFailures::new(2, Duration::from_secs(60));
+        assert!(!failures.is_locked_out(ip));
+        failures.record_failure(ip);
+        assert!(!failures.is_locked_out(ip));
+        failures.record_failure(ip);
+        assert!(failures.is_locked_out(ip));
+        assert!(!failures.is_locked_out("203.0.113.8".parse().unwrap()));
+
+        let mut failures = AuthFailures::new(1, Duration::ZERO);
+        failures.record_failure(ip);
+        assert!(!failures.is_locked_out(ip));
+    }
+}
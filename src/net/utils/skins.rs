@@ -0,0 +1,121 @@
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use crate::net::utils::authentication::{GameProfile, ProfileProperty};
+use crate::state::ServerState;
+use crate::utils::prelude::*;
+
+const PROFILE_LOOKUP_URL: &str = "https://api.mojang.com/users/profiles/minecraft/";
+const PROFILE_URL: &str = "https://sessionserver.mojang.com/session/minecraft/profile/";
+/// How long a lookup can take before it's given up on, so a slow API can't pile them up.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Shared by every lookup, so they reuse its connections.
+static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .timeout(LOOKUP_TIMEOUT)
+        .build()
+        .expect("The skin lookup client should build")
+});
+
+/// What the profile lookup answers with. Only the UUID is needed.
+#[derive(Debug, Deserialize)]
+struct ProfileId {
+    id: String,
+}
+
+/// Looks up the skin of the account called `username`, as a signed `textures` property.
+/// Returns no properties if there's no such account.
+pub async fn fetch_skin(username: &str) -> Result<Vec<ProfileProperty>> {
+    let response = CLIENT
+        .get(format!("{}{}", PROFILE_LOOKUP_URL, username))
+        .send()
+        .await?;
+    // Unknown names are either 204 No Content or 404 Not Found, depending on the day.
+    if matches!(
+        response.status(),
+        StatusCode::NO_CONTENT | StatusCode::NOT_FOUND
+    ) {
+        return Ok(Vec::new());
+    }
+    if response.status() != StatusCode::OK {
+        return Err(Error::Generic(format!(
+            "Profile lookup responded with {} for {}",
+            response.status(),
+            username
+        )));
+    }
+    let ProfileId { id } = response.json().await?;
+
+    let profile: GameProfile = CLIENT
+        .get(format!("{}{}", PROFILE_URL, id))
+        .query(&[("unsigned", "false")])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(profile.properties)
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+impl ServerState {
+    /// The skin of the account called `username`, for players whose skin isn't known from
+    /// logging in, e.g. in offline mode. Skins are cached in [ServerState::skins] so the API
+    /// isn't asked on every join.
+    ///
+    /// Skins that aren't cached are looked up while the player waits, for up to
+    /// [LOOKUP_TIMEOUT]. If it takes any longer, it's left to finish in the background, and they
+    /// have it from their next join. Until then, or if it can't be looked up, they get no skin,
+    /// i.e. Steve or Alex.
+    pub async fn skin_for(self: &Arc<Self>, username: &str) -> Vec<ProfileProperty> {
+        let now = unix_time();
+        if let Some(properties) = self.skins.get(username, now).await {
+            return properties;
+        }
+        if !self.skins.start_lookup(username, now).await {
+            return Vec::new();
+        }
+        let state = self.clone();
+        let name = username.to_string();
+        let lookup = tokio::spawn(async move { state.look_up_skin(&name, now).await });
+        match tokio::time::timeout(LOOKUP_TIMEOUT, lookup).await {
+            Ok(Ok(Some(properties))) => properties,
+            Ok(_) => Vec::new(),
+            Err(_) => {
+                debug!(
+                    "Looking up the skin of {} is taking too long, going without it",
+                    username
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    /// Looks up and caches `username`'s skin. `None` if it couldn't be looked up.
+    async fn look_up_skin(&self, username: &str, now: u64) -> Option<Vec<ProfileProperty>> {
+        let properties = match fetch_skin(username).await {
+            Ok(properties) => properties,
+            Err(e) => {
+                // Not cached, so it's tried again once it's been long enough.
+                warn!("Failed to look up the skin of {}: {}", username, e);
+                return None;
+            }
+        };
+        debug!("Looked up the skin of {}", username);
+        if let Err(e) = self.skins.insert(username, properties.clone(), now).await {
+            warn!("Failed to cache the skin of {}: {}", username, e);
+        }
+        Some(properties)
+    }
+}
@@ -0,0 +1,92 @@
+use socket2::SockRef;
+use tokio::net::{lookup_host, TcpListener, TcpSocket, TcpStream};
+use tracing::info;
+
+use crate::utils::config::Network;
+use crate::utils::prelude::*;
+
+/// How many connections can be waiting to be accepted.
+const LISTEN_BACKLOG: u32 = 1024;
+
+/// Binds the server's listener to `addr`, with the socket options from the config.
+pub async fn bind_listener(addr: &str, options: &Network) -> Result<TcpListener> {
+    let addr = lookup_host(addr)
+        .await?
+        .next()
+        .ok_or_else(|| Error::TcpError(format!("{} didn't resolve to any address", addr)))?;
+
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        let socket = TcpSocket::new_v6()?;
+        // Otherwise `[::]` takes IPv4 as well on some systems, and `0.0.0.0` on the same port
+        // fails to bind.
+        SockRef::from(&socket).set_only_v6(true)?;
+        socket
+    };
+    socket.set_reuseaddr(options.so_reuseaddr)?;
+    socket.set_keepalive(options.tcp_keepalive)?;
+    socket.bind(addr)?;
+    Ok(socket.listen(LISTEN_BACKLOG)?)
+}
+
+/// Logs the options connections are going to get. The listener's are read back from the socket,
+/// in case the OS didn't go along with them.
+pub fn log_socket_options(listener: &TcpListener, options: &Network) {
+    let socket = SockRef::from(listener);
+    let effective = |option: std::io::Result<bool>| match option {
+        Ok(enabled) => enabled.to_string(),
+        Err(_) => "unknown".to_string(),
+    };
+    let addr = listener
+        .local_addr()
+        .map_or_else(|_| "unknown address".to_string(), |addr| addr.to_string());
+    info!(
+        "Socket options for {}: TCP_NODELAY={}, SO_REUSEADDR={}, SO_KEEPALIVE={}",
+        addr,
+        options.tcp_nodelay,
+        effective(socket.reuse_address()),
+        effective(socket.keepalive())
+    );
+}
+
+/// Applies the socket options from the config to an accepted connection.
+pub fn apply_socket_options(stream: &TcpStream, options: &Network) -> Result<()> {
+    stream.set_nodelay(options.tcp_nodelay)?;
+    SockRef::from(stream).set_keepalive(options.tcp_keepalive)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::config::ServerConfig;
+
+    #[tokio::test]
+    async fn test_options_are_applied() {
+        let mut options = ServerConfig::default().network;
+        options.tcp_keepalive = true;
+
+        let listener = bind_listener("127.0.0.1:0", &options).await.unwrap();
+        assert!(SockRef::from(&listener).reuse_address().unwrap());
+
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        apply_socket_options(&stream, &options).unwrap();
+        assert!(stream.nodelay().unwrap());
+        assert!(SockRef::from(&stream).keepalive().unwrap());
+        drop(client);
+    }
+
+    #[tokio::test]
+    async fn test_ipv6_listeners_leave_ipv4_alone() {
+        let options = ServerConfig::default().network;
+        // Not every machine has IPv6.
+        let Ok(listener) = bind_listener("[::1]:0", &options).await else {
+            return;
+        };
+        assert!(SockRef::from(&listener).only_v6().unwrap());
+    }
+}
@@ -0,0 +1,79 @@
+use crate::net::packets::outgoing::default_spawn_position::DefaultSpawnPosition;
+use crate::net::packets::outgoing::synchronize_player_position::SynchronizePlayerPosition;
+use crate::net::packets::ConnectionId;
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::state::GlobalState;
+use crate::utils::components::exact_position::ExactPosition;
+use crate::utils::components::pending_teleports::PendingTeleports;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::components::violations::Violations;
+use crate::utils::constants::init;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+
+/// Where players spawn, and respawn after dying.
+pub fn spawn_position() -> Position {
+    Position::new(
+        init::DEFAULT_SPAWN_X_POS,
+        init::DEFAULT_SPAWN_Y_POS,
+        init::DEFAULT_SPAWN_Z_POS,
+    )
+}
+
+/// Moves the player to spawn, and queues the packets that put them there, see [place_player].
+///
+/// Expects their [Position], [ExactPosition], [Rotation] and [PendingTeleports] to be there
+/// already.
+pub async fn spawn_player(
+    conn_id: ConnectionId,
+    state: &GlobalState,
+    packet_queue: &mut PacketQueue,
+) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+    let position = spawn_position();
+    state
+        .dimension_of(conn_id)
+        .await
+        .players
+        .update(conn_id, &position);
+    *component_storage.get_mut::<ExactPosition>(conn_id).await? =
+        ExactPosition::corner_of(&position);
+    *component_storage.get_mut::<Position>(conn_id).await? = position;
+    *component_storage.get_mut::<Rotation>(conn_id).await? =
+        Rotation::new(init::DEFAULT_SPAWN_YAW, init::DEFAULT_SPAWN_PITCH);
+    place_player(conn_id, state, packet_queue).await
+}
+
+/// Queues the packets that put the player where their [ExactPosition] and [Rotation] say they
+/// are: the spawn position, then a teleport that the client has to confirm.
+///
+/// Used when a player joins, so they're back where they left, and by [spawn_player].
+pub async fn place_player(
+    conn_id: ConnectionId,
+    state: &GlobalState,
+    packet_queue: &mut PacketQueue,
+) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+    let position = *component_storage.get::<ExactPosition>(conn_id).await?;
+    let rotation = component_storage.get::<Rotation>(conn_id).await?.clone();
+
+    packet_queue
+        .queue(DefaultSpawnPosition::new_auto(spawn_position(), 0.0))
+        .await?;
+
+    let teleport_id = component_storage
+        .get_mut::<PendingTeleports>(conn_id)
+        .await?
+        .start();
+    component_storage
+        .get_mut_or_insert_with(conn_id, Violations::default)
+        .await
+        .teleported();
+    packet_queue
+        .queue(SynchronizePlayerPosition::exact(
+            position.0,
+            &rotation,
+            teleport_id,
+        ))
+        .await
+}
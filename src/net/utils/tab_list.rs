@@ -0,0 +1,49 @@
+use tracing::warn;
+
+use crate::net::packets::outgoing::set_tab_list_header_and_footer::SetTabListHeaderAndFooter;
+use crate::net::packets::ConnectionId;
+use crate::net::utils::visibility::players_in_world;
+use crate::state::GlobalState;
+use crate::utils::components::player::Player;
+use crate::utils::config::get_global_config;
+use crate::utils::placeholders::{fill_placeholders, Placeholders};
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// Sends a player the tab list header and footer from the config, with the placeholders filled
+/// in for them.
+async fn send_tab_list(
+    conn_id: ConnectionId,
+    state: &GlobalState,
+    values: &Placeholders<'_>,
+) -> Result<()> {
+    let config = &get_global_config().tab_list;
+    let username = state
+        .world
+        .get_component::<Player>(conn_id)
+        .await?
+        .username
+        .clone();
+    let values = Placeholders {
+        player: Some(&username),
+        ..values.clone()
+    };
+    let packet = SetTabListHeaderAndFooter::new(
+        TextComponent::from_legacy(&fill_placeholders(&config.header, &values)),
+        TextComponent::from_legacy(&fill_placeholders(&config.footer, &values)),
+    )?;
+    let conn = state.connections.get_connection(conn_id)?;
+    let conn = conn.read().await;
+    conn.send_packet(packet).await
+}
+
+/// Sends everyone in the world the tab list header and footer again, so the player count and
+/// TPS in it are up to date.
+pub async fn refresh_tab_list(state: &GlobalState) {
+    let values = Placeholders::current(state).await;
+    for conn_id in players_in_world(state).await {
+        if let Err(e) = send_tab_list(conn_id, state, &values).await {
+            warn!("Failed to send the tab list to {}: {}", conn_id, e);
+        }
+    }
+}
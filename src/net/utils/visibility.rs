@@ -0,0 +1,355 @@
+use std::collections::{HashMap, HashSet};
+
+use tracing::warn;
+
+use crate::net::packets::outgoing::remove_entities::RemoveEntities;
+use crate::net::packets::outgoing::set_entity_metadata::SetEntityMetadata;
+use crate::net::packets::outgoing::set_equipment::SetEquipment;
+use crate::net::packets::outgoing::set_head_rotation::SetHeadRotation;
+use crate::net::packets::outgoing::spawn_player::SpawnPlayer;
+use crate::net::packets::ConnectionId;
+use crate::net::systems::chunk_sender::view_distance;
+use crate::net::utils::metadata::player_metadata;
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::state::{GlobalState, ServerState};
+use crate::utils::components::client_settings::ClientSettings;
+use crate::utils::components::equipment::Equipment;
+use crate::utils::components::exact_position::ExactPosition;
+use crate::utils::components::last_sent_movement::LastSentMovement;
+use crate::utils::components::network_id::NetworkId;
+use crate::utils::components::player::Player;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::components::visible_entities::VisibleEntities;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+
+/// What a viewer needs to know about a player to spawn them.
+struct PlayerSnapshot {
+    id: ConnectionId,
+    network_id: i32,
+    uuid: u128,
+    position: Position,
+    /// Which dimension they're in, see [crate::world::dimensions::Dimension::key].
+    dimension: String,
+    view_distance: i32,
+    is_spectator: bool,
+}
+
+impl PlayerSnapshot {
+    fn chunk(&self) -> (i32, i32) {
+        (self.position.x >> 4, self.position.z >> 4)
+    }
+
+    /// Whether this player is in the same dimension as `other` and close enough to see them.
+    /// Spectators are only seen by other spectators.
+    fn can_see(&self, other: &PlayerSnapshot) -> bool {
+        if self.dimension != other.dimension || (other.is_spectator && !self.is_spectator) {
+            return false;
+        }
+        let (x, z) = self.chunk();
+        let (other_x, other_z) = other.chunk();
+        (x - other_x).abs().max((z - other_z).abs()) <= self.view_distance
+    }
+}
+
+/// The players in `ids` that have joined the world. Players that are still logging in don't
+/// have [VisibleEntities] yet, since they can't be sent other players, and anything else isn't a
+/// player.
+async fn snapshot_players(
+    ids: impl IntoIterator<Item = ConnectionId>,
+    state: &GlobalState,
+) -> Vec<PlayerSnapshot> {
+    let mut snapshots = Vec::new();
+    for id in ids {
+        let world = &state.world;
+        let (Ok(uuid), Ok(network_id), Ok(position)) = (
+            world
+                .get_component::<Player>(id)
+                .await
+                .map(|player| player.uuid),
+            world.get_component::<NetworkId>(id).await.map(|id| id.id),
+            world
+                .get_component::<Position>(id)
+                .await
+                .map(|position| position.clone()),
+        ) else {
+            continue;
+        };
+        if world.get_component::<VisibleEntities>(id).await.is_err() {
+            continue;
+        }
+        let settings = state.world.get_component::<ClientSettings>(id).await.ok();
+        let is_spectator = state.is_spectator(id).await;
+        let dimension = state.dimension_of(id).await.key.clone();
+        snapshots.push(PlayerSnapshot {
+            id,
+            network_id,
+            uuid,
+            position,
+            dimension,
+            view_distance: view_distance(settings.as_deref()),
+            is_spectator,
+        });
+    }
+    snapshots
+}
+
+/// Spawns and removes players after `conn_id` joined, moved into a different chunk, or went in or
+/// out of spectator: they're sent every other player they can see, and every other player that
+/// can see them now is sent them. Players that went out of range are removed on both sides.
+///
+/// Only the players that could be affected are looked at: the ones in `conn_id`'s view distance,
+/// the ones with its chunk in theirs, see [crate::utils::spatial_index::SpatialIndex], and the
+/// ones either side can see already.
+///
+/// Each player's [VisibleEntities] keeps track of who they've been sent, so nobody is spawned
+/// twice. Entities that aren't players are left as they are, see
+/// [crate::net::utils::dropped_items::update_visible_items]. Does nothing if `conn_id` hasn't
+/// joined the world yet.
+pub async fn update_visible_players(conn_id: ConnectionId, state: &GlobalState) -> Result<()> {
+    let Ok(position) = state.world.get_component::<Position>(conn_id).await else {
+        return Ok(());
+    };
+    let chunk = (position.x >> 4, position.z >> 4);
+    drop(position);
+    let Ok(visible) = state.world.get_component::<VisibleEntities>(conn_id).await else {
+        return Ok(());
+    };
+    let mut candidates = visible.entities.clone();
+    drop(visible);
+    let dimension = state.dimension_of(conn_id).await;
+    candidates.extend(dimension.players.players_in_view_of(conn_id));
+    candidates.extend(dimension.players.players_viewing_chunk(chunk.0, chunk.1));
+    candidates.extend(viewers_of(conn_id, state).await);
+    candidates.insert(conn_id);
+
+    let players = snapshot_players(candidates, state).await;
+    let Some(moved) = players.iter().find(|player| player.id == conn_id) else {
+        return Ok(());
+    };
+
+    let mut in_view = players
+        .iter()
+        .filter(|other| other.id != conn_id && moved.can_see(other))
+        .map(|other| other.id)
+        .collect::<HashSet<_>>();
+    let (spawned, removed) = {
+        let mut visible = state
+            .world
+            .get_component_storage()
+            .get_mut::<VisibleEntities>(conn_id)
+            .await?;
+        let player_ids = players
+            .iter()
+            .map(|player| player.id)
+            .collect::<HashSet<_>>();
+        in_view.extend(
+            visible
+                .entities
+                .iter()
+                .copied()
+                .filter(|id| !player_ids.contains(id)),
+        );
+        visible.update(&in_view)
+    };
+    let spawned = players
+        .iter()
+        .filter(|other| spawned.contains(&other.id))
+        .collect::<Vec<_>>();
+    show_and_hide(conn_id, &spawned, &removed, state).await?;
+
+    for viewer in players.iter().filter(|viewer| viewer.id != conn_id) {
+        let can_see = viewer.can_see(moved);
+        let was_visible = {
+            let Ok(mut visible) = state
+                .world
+                .get_component_storage()
+                .get_mut::<VisibleEntities>(viewer.id)
+                .await
+            else {
+                continue;
+            };
+            let was_visible = visible.contains(conn_id);
+            if can_see {
+                visible.entities.insert(conn_id);
+            } else {
+                visible.entities.remove(&conn_id);
+            }
+            was_visible
+        };
+
+        let result = match (was_visible, can_see) {
+            (false, true) => show_and_hide(viewer.id, &[moved], &[], state).await,
+            (true, false) => show_and_hide(viewer.id, &[], &[conn_id], state).await,
+            _ => Ok(()),
+        };
+        // One broken connection shouldn't stop everyone else from seeing the player.
+        if let Err(e) = result {
+            warn!("Failed to update the players {} can see: {}", viewer.id, e);
+        }
+    }
+    Ok(())
+}
+
+/// Every player that's joined the world, i.e. can be sent other players.
+pub async fn players_in_world(state: &ServerState) -> Vec<ConnectionId> {
+    state
+        .world
+        .query::<&VisibleEntities>()
+        .iter()
+        .await
+        .map(|(id, _)| id)
+        .collect()
+}
+
+/// Who can see each entity, by its id.
+pub async fn viewers_of_entities(state: &ServerState) -> HashMap<usize, Vec<ConnectionId>> {
+    let mut viewers = HashMap::<usize, Vec<ConnectionId>>::new();
+    for (viewer, visible) in state.world.query::<&VisibleEntities>().iter().await {
+        for &entity_id in &visible.entities {
+            viewers.entry(entity_id).or_default().push(viewer);
+        }
+    }
+    viewers
+}
+
+/// Every player that can see `entity_id`.
+async fn viewers_of(entity_id: usize, state: &GlobalState) -> Vec<ConnectionId> {
+    state
+        .world
+        .query::<&VisibleEntities>()
+        .iter()
+        .await
+        .filter(|(_, visible)| visible.contains(entity_id))
+        .map(|(id, _)| id)
+        .collect()
+}
+
+/// Removes `conn_id` from every player that can see it.
+pub async fn hide_from_everyone(conn_id: ConnectionId, state: &GlobalState) {
+    for viewer in viewers_of(conn_id, state).await {
+        if let Ok(mut visible) = state
+            .world
+            .get_component_storage()
+            .get_mut::<VisibleEntities>(viewer)
+            .await
+        {
+            visible.entities.remove(&conn_id);
+        }
+        if let Err(e) = show_and_hide(viewer, &[], &[conn_id], state).await {
+            warn!("Failed to remove {} for {}: {}", conn_id, viewer, e);
+        }
+    }
+}
+
+/// Sends `viewer` the players in `spawned`, along with what they're holding and wearing, and
+/// removes the entities in `removed`.
+async fn show_and_hide(
+    viewer: ConnectionId,
+    spawned: &[&PlayerSnapshot],
+    removed: &[ConnectionId],
+    state: &GlobalState,
+) -> Result<()> {
+    if spawned.is_empty() && removed.is_empty() {
+        return Ok(());
+    }
+
+    let mut packet_queue = PacketQueue::new();
+    if !removed.is_empty() {
+        let mut network_ids = Vec::with_capacity(removed.len());
+        for &id in removed {
+            if let Ok(network_id) = state.network_id(id).await {
+                network_ids.push(network_id);
+            }
+        }
+        packet_queue.queue(RemoveEntities::new(&network_ids)).await?;
+    }
+    for player in spawned {
+        let movement = match state
+            .world
+            .get_component::<LastSentMovement>(player.id)
+            .await
+        {
+            Ok(movement) => *movement,
+            Err(_) => {
+                let rotation = state
+                    .world
+                    .get_component::<Rotation>(player.id)
+                    .await
+                    .map(|rotation| rotation.clone())
+                    .unwrap_or_else(|_| Rotation::new(0.0, 0.0));
+                match state.world.get_component::<ExactPosition>(player.id).await {
+                    Ok(exact) => LastSentMovement::exact(exact.0, &rotation),
+                    Err(_) => LastSentMovement::new(&player.position, &rotation),
+                }
+            }
+        };
+        packet_queue
+            .queue(SpawnPlayer::new(player.network_id, player.uuid, &movement))
+            .await?;
+        packet_queue
+            .queue(SetHeadRotation::new(player.network_id, movement.yaw))
+            .await?;
+        let metadata = player_metadata(player.id, state).await;
+        packet_queue
+            .queue(SetEntityMetadata::new(player.network_id, metadata))
+            .await?;
+        let equipment = state
+            .world
+            .get_component::<Equipment>(player.id)
+            .await
+            .map(|equipment| equipment.non_empty())
+            .unwrap_or_default();
+        if !equipment.is_empty() {
+            packet_queue
+                .queue(SetEquipment::new(player.network_id, equipment))
+                .await?;
+        }
+    }
+
+    let conn = state.connections.get_connection(viewer)?;
+    let conn = conn.read().await;
+    conn.send_packets(packet_queue).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(id: ConnectionId, x: i32, is_spectator: bool) -> PlayerSnapshot {
+        PlayerSnapshot {
+            id,
+            network_id: id as i32,
+            uuid: id as u128,
+            position: Position::new(x, 64, 0),
+            dimension: "overworld".to_string(),
+            view_distance: 2,
+            is_spectator,
+        }
+    }
+
+    #[test]
+    fn test_spectators_are_only_seen_by_spectators() {
+        let survival = player(1, 0, false);
+        let spectator = player(2, 16, true);
+        let other_spectator = player(3, -16, true);
+        let far_away = player(4, 64, false);
+
+        assert!(survival.can_see(&player(5, 20, false)));
+        assert!(!survival.can_see(&far_away));
+        assert!(!survival.can_see(&spectator));
+        assert!(spectator.can_see(&survival));
+        assert!(spectator.can_see(&other_spectator));
+        assert!(!spectator.can_see(&far_away));
+    }
+
+    #[test]
+    fn test_players_in_other_dimensions_are_not_seen() {
+        let overworld = player(1, 0, false);
+        let mut nether = player(2, 0, false);
+        nether.dimension = "the_nether".to_string();
+
+        assert!(!overworld.can_see(&nether));
+        assert!(!nether.can_see(&overworld));
+    }
+}
@@ -72,24 +72,223 @@ pub(crate) async fn setup() -> Result<(), Error> {
 /// Not using ServerConfig::default(), since it doesn't have documentation on the usage of each field.
 pub static BASE_CONFIG: &str = r#"
 # The network address to bind to. Usually just 0.0.0.0 or 127.0.0.1 if you don't want to expose the server to the internet.
+# Can also be a list of addresses with ports, e.g. ["0.0.0.0:25565", "[::]:25565"] to listen on both IPv4 and IPv6.
 host = "0.0.0.0"
-# The port to bind to. Default is 25565.
+# The port to bind to, for addresses that don't have one. Default is 25565.
 port = 25565
-# The message displayed in the server list.
+# The message displayed in the server list. Legacy § formatting codes like §a work here.
 motd = ["A supersonic FerrumC server."]
 # The maximum number of players that can be connected at once.
 max_players = 20
+# How many chunks around them players can see, from 2 to 32. Players that set a lower render distance get less.
+view_distance = 10
 # How many network updates to process per second per user. 0 means no limit.
 # This is the number of times per second the server will send updates to the client.
 # Having this too low will cause noticable lag for clients but may improve server performance.
 network_tick_rate = 0
+# How many game ticks run per second. Everything in the game, like movement, time and hunger, moves on once a tick.
+# Vanilla runs 20, which is what clients expect.
+tick_rate = 20
+# Packets of at least this many bytes are compressed before being sent. -1 turns compression off.
+# Lower values save bandwidth at the cost of CPU time.
+network_compression_threshold = 256
+# How often to send keep alives to players, in seconds. Should stay below 20, or clients will time out.
+keep_alive_interval = 15
+# How long a player can go without answering a keep alive before being kicked, in seconds.
+keep_alive_timeout = 30
+# How long to wait for players to be disconnected when the server shuts down, in seconds.
+shutdown_grace_period = 10
 # The default world name. You can switch between mutliple worlds by changing this value.
+# Chunks are also read from a vanilla world in a directory with this name, if there is one.
 world = "world"
+# Path to the icon shown in the server list. Must be a 64x64 PNG.
+favicon = "icon-64.png"
+# Whether to authenticate players with Mojang's session servers. Only players with a paid Minecraft account can join when this is on.
+# Leave this off if the server is behind a proxy that already handles authentication.
+online_mode = false
+# Whether to look up players' skins by their username with Mojang's API when they don't come from logging in, e.g. in offline mode.
+# Skins are cached in skin_cache.json for a day.
+fetch_skins = false
+# What to do when a player joins while they're already online.
+# "kick_existing" disconnects the old session, like vanilla does. "reject_new" turns away the new login instead.
+duplicate_login = "kick_existing"
+# Whether to also allow dots and dashes in usernames. Vanilla only allows letters, numbers and underscores.
+# Some offline mode servers need this for players that use other launchers.
+lenient_usernames = false
+# Also lets clients with a protocol version in this range join, even though the server only speaks 763 (1.20.1).
+# Only meant for testing, since other versions will most likely break.
+# allow_protocol_range = { min = 763, max = 765 }
+# The server brand shown in the client's debug screen (F3).
+brand = "FerrumC"
+# The gamemode players join in for the first time: "survival", "creative", "adventure" or
+# "spectator", or its id from 0 to 3.
+default_gamemode = "creative"
 
 [database]
-# The cache size in KB. We recommend leaving this at the default value.
-cache_size = 1024
+# How much memory chunks can take before the least recently used ones are dropped, in MB.
+# Chunks players can see and chunks with unsaved changes are always kept.
+cache_size_mb = 256
 # The compression algorithm to use. "fast" is recommended for most use cases.
 # "best" is slower but may provide better compression ratio.
 compression = "fast"
+# How often chunks that have changed are saved, in seconds. They're always saved on shutdown.
+save_interval = 60
+
+[network]
+# How many packets a client can send per second on average before being kicked. 0 means no limit.
+# Keep alives and teleport confirmations don't count towards this.
+max_packets_per_second = 500
+# How many packets a client can send in a short burst, e.g. while catching up after a lag spike.
+packet_burst = 1000
+# Set this to true if the server is behind a load balancer (e.g. HAProxy) that sends a PROXY protocol v1 or v2 header.
+# Connections without a valid header are dropped when this is on, so only enable it if every connection goes through the balancer.
+proxy_protocol = false
+# The largest packet in bytes a client can send before being disconnected. Can't go above the protocol's limit of 2097151.
+max_packet_size = 1048576
+# How many packets can be waiting to be sent to a client. Sending more waits until there's room again.
+outgoing_queue_size = 1024
+# How long in seconds a client's packet queue can stay full before it's disconnected for not keeping up.
+outgoing_queue_timeout = 10
+# Send small packets like movement right away, instead of letting the OS batch them up (Nagle's algorithm).
+tcp_nodelay = true
+# Allows binding the port again straight after a restart, while old connections are still closing.
+so_reuseaddr = true
+# Have the OS check whether idle connections are still alive. Keep alive packets already do this for players.
+tcp_keepalive = false
+# How many teleports a client can leave unconfirmed before it's kicked. Its movement is ignored until it confirms them,
+# and they're sent again about once a second. 0 means it's never kicked.
+max_pending_teleports = 20
+# How long in seconds to wait for a client to answer a ping before giving up on it. The server pings clients to find out
+# when they've caught up with everything it sent.
+ping_timeout = 30
+
+[proxy]
+# Set this to true if the server is behind BungeeCord with ip_forward enabled, so players get their real UUID, skin and IP.
+# Make sure the server can't be reached without going through the proxy, since anyone could pretend to be anyone otherwise.
+bungeecord = false
+# Set this to the forwarding secret from Velocity's config to use its modern forwarding. Leave empty to turn it off.
+# When this is set, players can only join through Velocity.
+velocity_secret = ""
+
+[query]
+# Whether to answer GameSpy4 queries over UDP, which server lists and hosting panels use to show who's online.
+enabled = false
+# The UDP port to answer queries on. 0 uses the same port as the server.
+port = 0
+
+[rcon]
+# Whether to let admins run commands remotely over RCON.
+enabled = false
+# The TCP port to listen for RCON connections on.
+port = 25575
+# The password RCON clients have to log in with. RCON won't start without one, so pick a strong one.
+password = ""
+
+[whitelist]
+# Whether only players in whitelist.json can join. Manage it with the whitelist commands, or edit the
+# file and reload it.
+enabled = false
+
+[resource_pack]
+# Where players download the server's resource pack from. Leave empty to not send one.
+url = ""
+# The pack's SHA-1 hash, as 40 hex digits. Lets players skip downloading a pack they already have, and catches broken downloads.
+sha1 = ""
+# Whether players that decline the pack, or fail to download it, are kicked.
+required = false
+# A message shown when players are asked to use the pack. Leave empty for the client's default.
+prompt = ""
+# What players are kicked with when they don't use a required pack.
+kick_message = "This server requires its resource pack"
+
+[tab_list]
+# The text shown above and below the player list. Leave empty to show nothing.
+# %online%, %max%, %tps% and %player% are replaced with the players online, the player limit, the server's ticks per second
+# and the name of the player looking at the list.
+header = ""
+footer = ""
+# How often in seconds to send everyone the header and footer again, so the placeholders stay up to date.
+refresh_interval = 5
+
+[welcome_title]
+# Shown in the middle of the screen when players join, with the same placeholders as the tab list. Leave empty to not show one.
+title = ""
+# The smaller text under the title.
+subtitle = ""
+# How long the title takes to fade in, stays on screen, and takes to fade out, in ticks. There are 20 ticks in a second.
+fade_in = 10
+stay = 70
+fade_out = 20
+
+[game_rules]
+# Whether the sun and moon move. Turn it off to keep the time wherever it is.
+do_daylight_cycle = true
+# Whether it starts and stops raining and thundering by itself. Turn it off to keep the weather whatever it is.
+do_weather_cycle = true
+
+[abilities]
+# Whether players can fly in survival and adventure too.
+allow_flight = false
+# How fast players fly, and walk. The client also zooms the field of view with the walking speed.
+flying_speed = 0.05
+walking_speed = 0.1
+
+[anti_cheat]
+# Whether players' movement is checked. Moves that fail are undone by sending the player back to where they were.
+enabled = true
+# How far a player can move sideways, and up, in one tick, in blocks, however many moves they send in it. Speed and Jump Boost add to them.
+max_horizontal_speed = 1.0
+max_vertical_speed = 0.75
+# How many times further players that are flying can move.
+flying_multiplier = 4.0
+# How many moves in a row a player that isn't flying can keep going up, or stay level, without touching the ground.
+max_ascending_moves = 20
+# How many moves can be undone before the player is kicked, 0 for never. Lag makes honest players fail now and then too.
+max_violations = 20
+# How many moves in a row have to pass for one violation to be forgiven, 0 for never.
+forgive_after = 200
+
+[world_border]
+# The world border new worlds start with. Once a world has been saved, it keeps its own.
+center_x = 0.0
+center_z = 0.0
+# How wide the border is, in blocks.
+diameter = 59999968.0
+# How close to the border, in blocks, and how many seconds before a shrinking border reaches them, players' screens start going red.
+warning_blocks = 5
+warning_time = 15
+
+[world_generation]
+# How chunks that aren't in the world yet are made: "superflat", or "default" for hills and oceans.
+generator = "superflat"
+# What the "default" generator's terrain comes from. Changing it only changes chunks that haven't been generated yet.
+seed = 0
+# The layers of a superflat world, bottom first. "2xdirt" is 2 layers of dirt.
+superflat_layers = "bedrock,2xdirt,grass_block"
+# The biome generated chunks are in, e.g. "minecraft:desert". It decides the color of grass and water.
+biome = "minecraft:plains"
+
+# The dimensions besides the overworld, which is made by [world_generation] above. Each one needs:
+# - name: What it's called, e.g. "minecraft:the_nether". Players are moved between dimensions by name.
+# - dimension_type: How clients draw it: "minecraft:overworld", "minecraft:the_nether" or "minecraft:the_end".
+# - world_generation: How its chunks are made, the same as [world_generation].
+# Remove them all to only have the overworld.
+[[dimensions]]
+name = "minecraft:the_nether"
+dimension_type = "minecraft:the_nether"
+world_generation = { generator = "superflat", seed = 0, superflat_layers = "bedrock,3xnetherrack", biome = "minecraft:nether_wastes" }
+
+[[dimensions]]
+name = "minecraft:the_end"
+dimension_type = "minecraft:the_end"
+world_generation = { generator = "superflat", seed = 0, superflat_layers = "3xend_stone", biome = "minecraft:the_end" }
+
+[debug]
+# A directory to capture every packet in, one file per connection, for debugging the protocol.
+# Read the files with `ferrumc dump-replay <file>`. Leave empty to turn it off.
+packet_dump = ""
+# Show everyone how many ticks per second the server is running in a boss bar.
+tps_boss_bar = false
+# Show everyone how many players are online in a scoreboard sidebar.
+player_count_sidebar = false
 "#;
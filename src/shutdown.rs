@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use tokio::time::{timeout_at, Instant};
+use tracing::{debug, info, warn};
+
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::server_events::ServerShutdownEvent;
+use crate::net::ConnectionExt;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+const SHUTDOWN_REASON: &str = "Server closing";
+
+/// Shuts the server down without just dropping everyone.
+///
+/// Stops accepting connections and all systems, disconnects every connection with a reason, and
+/// waits up to `shutdown_grace_period` seconds for their queued packets to go out. After that the
+/// [ServerShutdownEvent] is dispatched, so anything kept in memory can be saved.
+pub async fn shutdown(state: GlobalState) -> Result<()> {
+    let grace_period = Duration::from_secs(get_global_config().shutdown_grace_period);
+    shutdown_within(state, grace_period).await
+}
+
+async fn shutdown_within(state: GlobalState, grace_period: Duration) -> Result<()> {
+    info!("Shutting down...");
+    let deadline = Instant::now() + grace_period;
+    state.shutdown.cancel();
+
+    let connections = state
+        .connections
+        .connections
+        .iter()
+        .map(|entry| entry.value().clone())
+        .collect::<Vec<_>>();
+    debug!("Disconnecting {} connections", connections.len());
+
+    let kicks = connections.into_iter().map(|conn| {
+        let state = state.clone();
+        async move {
+            if let Err(e) = conn.kick(SHUTDOWN_REASON, state).await {
+                debug!("Failed to disconnect connection: {}", e);
+            }
+        }
+    });
+    if timeout_at(deadline, futures::future::join_all(kicks))
+        .await
+        .is_err()
+    {
+        warn!("Not every connection could be disconnected in time");
+    }
+
+    // Kicking only queues the disconnect, so wait for the connections to actually close.
+    state.connection_tasks.close();
+    if timeout_at(deadline, state.connection_tasks.wait())
+        .await
+        .is_err()
+    {
+        warn!(
+            "{} connections didn't close within {:?}",
+            state.connection_tasks.len(),
+            grace_period
+        );
+    }
+
+    state.dispatch_event(ServerShutdownEvent).await;
+
+    info!("Shutdown complete");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+    use crate::create_state;
+    use crate::net::systems::connection_handler::ConnectionHandler;
+    use crate::net::systems::System;
+
+    const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+    #[tokio::test]
+    async fn test_shutdown_closes_connections_within_deadline() {
+        let state = create_state(vec![TcpListener::bind("127.0.0.1:0").await.unwrap()])
+            .await
+            .unwrap();
+        let addr = state.server_streams[0].local_addr().unwrap();
+
+        let accept_loop = tokio::spawn({
+            let state = state.clone();
+            async move { ConnectionHandler.run(state).await }
+        });
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        while state.connections.connections.is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let start = Instant::now();
+        shutdown_within(state.clone(), GRACE_PERIOD).await.unwrap();
+        assert!(start.elapsed() < GRACE_PERIOD);
+
+        tokio::time::timeout(GRACE_PERIOD, accept_loop)
+            .await
+            .expect("accept loop didn't stop")
+            .unwrap();
+        assert!(state.connection_tasks.is_empty());
+        assert!(state.connections.connections.is_empty());
+
+        // The server closed its end of the socket.
+        let mut buffer = Vec::new();
+        client.read_to_end(&mut buffer).await.unwrap();
+    }
+}
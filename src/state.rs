@@ -1,15 +1,66 @@
+use crate::commands::CommandDispatcher;
 use crate::database::Database;
 use crate::ecs::world::World;
 use crate::net::ConnectionList;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use crate::events::creation::dispatcher::EventDispatcher;
+use crate::net::utils::entity_ids::EntityIdAllocator;
+use crate::net::utils::network_stats::NetworkCounters;
+use crate::net::utils::ping::PendingPings;
+use crate::world::chunk_service::ChunkService;
+use crate::world::dimensions::Dimensions;
+use crate::world::border::SharedWorldBorder;
+use crate::utils::ban_list::BanList;
+use crate::utils::plugin_channels::PluginChannels;
+use crate::utils::scheduler::Scheduler;
+use crate::utils::skin_cache::SkinCache;
+use crate::utils::tick_rate::TickRate;
+use crate::utils::whitelist::PlayerWhitelist;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
 
 pub struct ServerState {
     pub world: Arc<World>,
     pub connections: ConnectionList,
     pub database: Database,
-    pub server_stream: tokio::net::TcpListener,
+    /// The overworld and every other dimension in the config, with their own chunks and time.
+    pub dimensions: Dimensions,
+    /// Loads and generates chunks in the background, see [ChunkService::request].
+    pub chunks: ChunkService,
+    /// One listener for every address in the config's `host`.
+    pub server_streams: Vec<tokio::net::TcpListener>,
     pub event_dispatcher: Arc<EventDispatcher>,
+    /// The ids entities are known by on clients, see [ServerState::network_id].
+    pub entity_ids: EntityIdAllocator,
+    pub bans: BanList,
+    /// Only checked if `whitelist.enabled` is set.
+    pub whitelist: PlayerWhitelist,
+    /// Skins looked up with the config's `fetch_skins`, see [ServerState::skin_for].
+    pub skins: SkinCache,
+    pub commands: CommandDispatcher,
+    /// What to do with payloads clients send on plugin channels.
+    pub plugin_channels: PluginChannels,
+    /// Pings sent with [ServerState::ping_player] that haven't been answered yet.
+    pub pending_pings: Arc<PendingPings>,
+    /// Runs the game's tick loop, and the systems on it.
+    pub scheduler: Scheduler,
+    /// How many ticks the server's been running per second, and how long they take, recorded
+    /// by [ServerState::scheduler].
+    pub tick_rate: TickRate,
+    /// The world border, see [ServerState::set_border].
+    pub border: SharedWorldBorder,
+    /// Whether players can fly in every gamemode, see [ServerState::set_allow_flight].
+    pub allow_flight: AtomicBool,
+    /// Whether the save system's last save is still going, see
+    /// [crate::net::systems::save_system].
+    pub saving: AtomicBool,
+    /// Cancelled when the server starts shutting down. Long-running systems stop once it is.
+    pub shutdown: CancellationToken,
+    /// The tasks handling each connection, so shutdown can wait for them to finish.
+    pub connection_tasks: TaskTracker,
+    /// Traffic over every connection since the server started, see [ServerState::network_stats].
+    pub network_totals: Arc<NetworkCounters>,
 }
 
 pub type GlobalState = Arc<ServerState>;
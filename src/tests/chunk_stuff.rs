@@ -6,7 +6,7 @@ pub async fn dump_heightmaps() -> Result<(), Box<dyn std::error::Error>> {
     use crate::utils::setup_logger;
     use tokio::net::TcpListener;
     setup_logger().unwrap();
-    let state = crate::create_state(TcpListener::bind("0.0.0.0:0").await.unwrap())
+    let state = crate::create_state(vec![TcpListener::bind("0.0.0.0:0").await.unwrap()])
         .await
         .unwrap();
 
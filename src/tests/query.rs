@@ -6,7 +6,7 @@ use tokio::net::TcpListener;
 #[tokio::test]
 async fn demonstrate_simple_query_usage() {
     // Create the game state, including setting up a TCP listener for the server.
-    let state = create_state(TcpListener::bind("0.0.0.0:25565").await.unwrap())
+    let state = create_state(vec![TcpListener::bind("0.0.0.0:25565").await.unwrap()])
         .await
         .unwrap();
 
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::state::ServerState;
+use crate::utils::json_file::{read_json_file, write_json_file};
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// A single ban. `expires` is a unix timestamp in seconds, or `None` for a permanent ban.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ban {
+    pub reason: Option<String>,
+    pub expires: Option<u64>,
+}
+
+impl Ban {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    /// The message shown to the banned player when they try to join.
+    pub fn disconnect_reason(&self) -> TextComponent {
+        let mut message = TextComponent::new("You are banned from this server.");
+        if let Some(reason) = &self.reason {
+            message = message.extra(format!("\nReason: {}", reason));
+        }
+        if let Some(expires) = self.expires {
+            let remaining = expires.saturating_sub(unix_now());
+            message = message.extra(format!(
+                "\nYour ban expires in {}",
+                format_duration(remaining)
+            ));
+        }
+        message
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Bans {
+    #[serde(default)]
+    players: HashMap<Uuid, Ban>,
+    #[serde(default)]
+    ips: HashMap<IpAddr, Ban>,
+}
+
+/// UUID and IP bans, kept in a JSON file next to the config.
+///
+/// Every change is written to the file straight away. Expired bans are only removed once they're
+/// checked, and don't make it into the file after the next change.
+pub struct BanList {
+    path: PathBuf,
+    bans: Mutex<Bans>,
+}
+
+impl BanList {
+    /// Loads the ban list from `path`. Starts out empty if the file doesn't exist yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let bans: Bans = read_json_file(&path)?;
+        debug!(
+            "Loaded {} player and {} IP bans",
+            bans.players.len(),
+            bans.ips.len()
+        );
+
+        Ok(Self {
+            path,
+            bans: Mutex::new(bans),
+        })
+    }
+
+    /// Returns the player's ban, unless they aren't banned or it has expired.
+    pub async fn player_ban(&self, uuid: Uuid) -> Option<Ban> {
+        let mut bans = self.bans.lock().await;
+        check(&mut bans.players, &uuid)
+    }
+
+    /// Returns the ban for `ip`, unless it isn't banned or the ban has expired.
+    pub async fn ip_ban(&self, ip: IpAddr) -> Option<Ban> {
+        let mut bans = self.bans.lock().await;
+        check(&mut bans.ips, &ip)
+    }
+
+    pub async fn ban_player(&self, uuid: Uuid, ban: Ban) -> Result<()> {
+        let mut bans = self.bans.lock().await;
+        bans.players.insert(uuid, ban);
+        self.save(&bans).await
+    }
+
+    /// Returns `false` if the player wasn't banned.
+    pub async fn unban_player(&self, uuid: Uuid) -> Result<bool> {
+        let mut bans = self.bans.lock().await;
+        if bans.players.remove(&uuid).is_none() {
+            return Ok(false);
+        }
+        self.save(&bans).await?;
+        Ok(true)
+    }
+
+    pub async fn ban_ip(&self, ip: IpAddr, ban: Ban) -> Result<()> {
+        let mut bans = self.bans.lock().await;
+        bans.ips.insert(ip, ban);
+        self.save(&bans).await
+    }
+
+    /// Returns `false` if the IP wasn't banned.
+    pub async fn unban_ip(&self, ip: IpAddr) -> Result<bool> {
+        let mut bans = self.bans.lock().await;
+        if bans.ips.remove(&ip).is_none() {
+            return Ok(false);
+        }
+        self.save(&bans).await?;
+        Ok(true)
+    }
+
+    /// Writes the bans that haven't expired to the file. Called with the lock held, so saves
+    /// can't overtake each other.
+    async fn save(&self, bans: &Bans) -> Result<()> {
+        let now = unix_now();
+        let bans = Bans {
+            players: unexpired(&bans.players, now),
+            ips: unexpired(&bans.ips, now),
+        };
+        write_json_file(&self.path, &bans).await
+    }
+}
+
+impl ServerState {
+    /// Bans a player until `expires`, or forever if it's `None`. Players that are online stay
+    /// online, this only stops them from joining again.
+    pub async fn ban_player(
+        &self,
+        uuid: Uuid,
+        reason: Option<String>,
+        expires: Option<SystemTime>,
+    ) -> Result<()> {
+        info!("Banning player {}", uuid);
+        let expires = expires.map(unix_time);
+        self.bans.ban_player(uuid, Ban { reason, expires }).await
+    }
+
+    /// Lifts a player's ban. Returns `false` if they weren't banned.
+    pub async fn unban_player(&self, uuid: Uuid) -> Result<bool> {
+        info!("Unbanning player {}", uuid);
+        self.bans.unban_player(uuid).await
+    }
+
+    /// Bans an IP until `expires`, or forever if it's `None`.
+    pub async fn ban_ip(
+        &self,
+        ip: IpAddr,
+        reason: Option<String>,
+        expires: Option<SystemTime>,
+    ) -> Result<()> {
+        info!("Banning IP {}", ip);
+        let expires = expires.map(unix_time);
+        self.bans.ban_ip(ip, Ban { reason, expires }).await
+    }
+
+    /// Lifts an IP ban. Returns `false` if the IP wasn't banned.
+    pub async fn unban_ip(&self, ip: IpAddr) -> Result<bool> {
+        info!("Unbanning IP {}", ip);
+        self.bans.unban_ip(ip).await
+    }
+}
+
+/// Looks up a ban, removing it if it has expired.
+fn check<K: Eq + std::hash::Hash>(bans: &mut HashMap<K, Ban>, key: &K) -> Option<Ban> {
+    let ban = bans.get(key)?;
+    if ban.is_expired(unix_now()) {
+        bans.remove(key);
+        return None;
+    }
+    Some(ban.clone())
+}
+
+fn unexpired<K: Eq + std::hash::Hash + Clone>(bans: &HashMap<K, Ban>, now: u64) -> HashMap<K, Ban> {
+    bans.iter()
+        .filter(|(_, ban)| !ban.is_expired(now))
+        .map(|(key, ban)| (key.clone(), ban.clone()))
+        .collect()
+}
+
+fn unix_now() -> u64 {
+    unix_time(SystemTime::now())
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Formats a number of seconds like `2d 3h 15m`, leaving out the days and hours if they're 0.
+fn format_duration(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes.max(1)),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h {}m", days, hours, minutes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("ferrumc-bans-{}.json", Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_bans_are_persisted() {
+        let path = temp_path();
+        let uuid = Uuid::new_v4();
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+
+        let ban_list = BanList::load(&path).unwrap();
+        let ban = Ban {
+            reason: Some("Griefing".to_string()),
+            expires: None,
+        };
+        ban_list.ban_player(uuid, ban.clone()).await.unwrap();
+        ban_list.ban_ip(ip, ban.clone()).await.unwrap();
+
+        let reloaded = BanList::load(&path).unwrap();
+        assert_eq!(reloaded.player_ban(uuid).await, Some(ban.clone()));
+        assert_eq!(reloaded.ip_ban(ip).await, Some(ban));
+        assert_eq!(reloaded.player_ban(Uuid::new_v4()).await, None);
+
+        assert!(reloaded.unban_player(uuid).await.unwrap());
+        assert!(!reloaded.unban_player(uuid).await.unwrap());
+        let reloaded = BanList::load(&path).unwrap();
+        assert_eq!(reloaded.player_ban(uuid).await, None);
+        assert!(reloaded.ip_ban(ip).await.is_some());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_bans_are_pruned() {
+        let path = temp_path();
+        let uuid = Uuid::new_v4();
+        let ban_list = BanList::load(&path).unwrap();
+
+        let expired = Ban {
+            reason: None,
+            expires: Some(unix_now() - 1),
+        };
+        ban_list.ban_player(uuid, expired).await.unwrap();
+        assert_eq!(ban_list.player_ban(uuid).await, None);
+        assert!(ban_list.bans.lock().await.players.is_empty());
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains(&uuid.to_string()));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(30), "1m");
+        assert_eq!(format_duration(3 * 3600 + 15 * 60), "3h 15m");
+        assert_eq!(format_duration(2 * 86400 + 3600), "2d 1h 0m");
+    }
+}
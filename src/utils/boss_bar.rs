@@ -0,0 +1,180 @@
+use tracing::warn;
+
+use crate::net::packets::outgoing::boss_bar::{
+    BossBarAction, BossBarColor, BossBarDivision, BossBarPacket,
+};
+use crate::net::packets::ConnectionId;
+use crate::state::GlobalState;
+use crate::utils::components::boss_bars::ShownBossBars;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// A boss bar at the top of the screen, shown to whichever players are added as viewers.
+///
+/// Every change is sent to the viewers straight away. The viewers are kept in each player's
+/// [ShownBossBars], so players that disconnect stop being viewers without anything else to do.
+///
+/// ```ignore
+/// let mut bar = BossBar::new(state, uuid, "Loading", BossBarColor::Blue, BossBarDivision::Ten);
+/// bar.add_viewer(conn_id).await?;
+/// bar.set_progress(0.5).await;
+/// ```
+pub struct BossBar {
+    state: GlobalState,
+    uuid: u128,
+    title: TextComponent,
+    progress: f32,
+    color: BossBarColor,
+    division: BossBarDivision,
+    flags: u8,
+}
+
+impl BossBar {
+    /// A full bar with no flags and no viewers yet.
+    pub fn new(
+        state: GlobalState,
+        uuid: u128,
+        title: impl Into<TextComponent>,
+        color: BossBarColor,
+        division: BossBarDivision,
+    ) -> Self {
+        Self {
+            state,
+            uuid,
+            title: title.into(),
+            progress: 1.0,
+            color,
+            division,
+            flags: 0,
+        }
+    }
+
+    pub fn uuid(&self) -> u128 {
+        self.uuid
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    /// The players the bar is shown to.
+    pub async fn viewers(&self) -> Vec<ConnectionId> {
+        self.state
+            .world
+            .query::<&ShownBossBars>()
+            .iter()
+            .await
+            .filter(|(_, shown)| shown.bars.contains(&self.uuid))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Shows the bar to a player. Does nothing if they can already see it.
+    pub async fn add_viewer(&self, conn_id: ConnectionId) -> Result<()> {
+        let conn = self.state.connections.get_connection(conn_id)?;
+        let added = self
+            .state
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with(conn_id, ShownBossBars::default)
+            .await
+            .bars
+            .insert(self.uuid);
+        if !added {
+            return Ok(());
+        }
+        let packet = BossBarPacket::new(
+            self.uuid,
+            BossBarAction::Add {
+                title: self.title.to_json()?,
+                health: self.progress,
+                color: self.color,
+                division: self.division,
+                flags: self.flags,
+            },
+        );
+        let conn = conn.read().await;
+        conn.send_packet(packet).await
+    }
+
+    /// Takes the bar away from a player. Does nothing if they couldn't see it.
+    pub async fn remove_viewer(&self, conn_id: ConnectionId) -> Result<()> {
+        let removed = match self
+            .state
+            .world
+            .get_component_storage()
+            .get_mut::<ShownBossBars>(conn_id)
+            .await
+        {
+            Ok(mut shown) => shown.bars.remove(&self.uuid),
+            Err(_) => false,
+        };
+        if !removed {
+            return Ok(());
+        }
+        let conn = self.state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(BossBarPacket::new(self.uuid, BossBarAction::Remove))
+            .await
+    }
+
+    /// Takes the bar away from everyone that can see it.
+    pub async fn remove_all_viewers(&self) {
+        for conn_id in self.viewers().await {
+            if let Err(e) = self.remove_viewer(conn_id).await {
+                warn!("Failed to remove a boss bar from {}: {}", conn_id, e);
+            }
+        }
+    }
+
+    /// Sets how full the bar is, from 0 to 1. Values outside that are clamped.
+    pub async fn set_progress(&mut self, progress: f32) {
+        let progress = progress.clamp(0.0, 1.0);
+        if progress == self.progress {
+            return;
+        }
+        self.progress = progress;
+        self.send_to_viewers(BossBarAction::UpdateHealth(progress))
+            .await;
+    }
+
+    pub async fn set_title(&mut self, title: impl Into<TextComponent>) -> Result<()> {
+        let title = title.into();
+        if title == self.title {
+            return Ok(());
+        }
+        let json = title.to_json()?;
+        self.title = title;
+        self.send_to_viewers(BossBarAction::UpdateTitle(json)).await;
+        Ok(())
+    }
+
+    pub async fn set_style(&mut self, color: BossBarColor, division: BossBarDivision) {
+        if (color, division) == (self.color, self.division) {
+            return;
+        }
+        self.color = color;
+        self.division = division;
+        self.send_to_viewers(BossBarAction::UpdateStyle { color, division })
+            .await;
+    }
+
+    /// Sets the bar's flags, e.g. [DARKEN_SKY](crate::net::packets::outgoing::boss_bar::DARKEN_SKY).
+    pub async fn set_flags(&mut self, flags: u8) {
+        if flags == self.flags {
+            return;
+        }
+        self.flags = flags;
+        self.send_to_viewers(BossBarAction::UpdateFlags(flags))
+            .await;
+    }
+
+    /// Players it can't be sent to are skipped.
+    async fn send_to_viewers(&self, action: BossBarAction) {
+        self.state
+            .send_to_players(self.viewers().await, || {
+                BossBarPacket::new(self.uuid, action.clone())
+            })
+            .await;
+    }
+}
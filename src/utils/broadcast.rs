@@ -0,0 +1,65 @@
+use ferrumc_codec::enc::NetEncode;
+use tracing::warn;
+
+use crate::net::packets::ConnectionId;
+use crate::net::utils::visibility::players_in_world;
+use crate::state::ServerState;
+use crate::utils::encoding::position::Position;
+use crate::world::dimensions::Dimension;
+
+impl ServerState {
+    /// Sends a packet made by `packet` to every player in `dimension` that has `position` within
+    /// their view distance, except `except`.
+    ///
+    /// Only the chunks around `position` are looked at, see [Dimension::players].
+    pub async fn send_to_players_near<P: NetEncode>(
+        &self,
+        dimension: &Dimension,
+        position: &Position,
+        except: Option<ConnectionId>,
+        packet: impl Fn() -> P,
+    ) {
+        let players = dimension
+            .players
+            .players_viewing_chunk(position.x >> 4, position.z >> 4)
+            .into_iter()
+            .filter(|&id| Some(id) != except);
+        self.send_to_players(players, packet).await;
+    }
+
+    /// Sends a packet made by `packet` to every player in `dimension`.
+    pub async fn send_to_dimension<P: NetEncode>(
+        &self,
+        dimension: &Dimension,
+        packet: impl Fn() -> P,
+    ) {
+        self.send_to_players(dimension.players.players(), packet)
+            .await;
+    }
+
+    /// Sends a packet made by `packet` to every player that's joined the world.
+    pub async fn broadcast<P: NetEncode>(&self, packet: impl Fn() -> P) {
+        self.send_to_players(players_in_world(self).await, packet)
+            .await;
+    }
+
+    /// Sends a packet made by `packet` to each of `players`.
+    ///
+    /// Players it can't be sent to are skipped, so one broken connection doesn't stop everyone
+    /// else from getting it. So are players that have already left.
+    pub async fn send_to_players<P: NetEncode>(
+        &self,
+        players: impl IntoIterator<Item = ConnectionId>,
+        packet: impl Fn() -> P,
+    ) {
+        for id in players {
+            let Ok(conn) = self.connections.get_connection(id) else {
+                continue;
+            };
+            let conn = conn.read().await;
+            if let Err(e) = conn.send_packet(packet()).await {
+                warn!("Failed to send a packet to {}: {}", id, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use crate::net::packets::outgoing::system_chat_message::SystemChatMessage;
+use crate::net::packets::ConnectionId;
+use crate::net::ConnectionExt;
+use crate::state::ServerState;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// The longest chat message a client can send, same as vanilla.
+pub const MAX_CHAT_MESSAGE_LENGTH: usize = 256;
+
+/// Strips control characters and formatting codes out of a chat message, and cuts it off at
+/// [MAX_CHAT_MESSAGE_LENGTH] characters.
+pub fn sanitize_chat_message(message: &str) -> String {
+    message
+        .chars()
+        .filter(|c| !c.is_control() && *c != '§')
+        .take(MAX_CHAT_MESSAGE_LENGTH)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+impl ServerState {
+    /// Sends a message to one player's chat.
+    pub async fn send_message(
+        &self,
+        conn_id: ConnectionId,
+        message: impl Into<TextComponent>,
+    ) -> Result<()> {
+        let conn = self.connections.get_connection(conn_id)?;
+        conn.send_message(message).await
+    }
+
+    /// Sends a message to the chat of every player that's in the game. Players it can't be sent
+    /// to are skipped.
+    pub async fn broadcast_message(&self, message: impl Into<TextComponent>) -> Result<()> {
+        let content = message.into().to_json()?;
+        self.broadcast(|| SystemChatMessage::new_auto(content.clone(), false))
+            .await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_chat_message() {
+        assert_eq!(sanitize_chat_message("hello there"), "hello there");
+        assert_eq!(sanitize_chat_message("  padded \n"), "padded");
+        assert_eq!(sanitize_chat_message("a\u{0}b\u{7}c\u{7F}d"), "abcd");
+        assert_eq!(sanitize_chat_message("§cred"), "cred");
+        assert_eq!(
+            sanitize_chat_message(&"ö".repeat(300)),
+            "ö".repeat(MAX_CHAT_MESSAGE_LENGTH)
+        );
+    }
+}
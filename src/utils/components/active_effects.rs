@@ -0,0 +1,182 @@
+use ferrumc_macros::Component;
+
+/// The effect came from a beacon or conduit, so its particles are fainter.
+pub const AMBIENT: u8 = 0x01;
+pub const SHOW_PARTICLES: u8 = 0x02;
+/// Shows the effect's icon in the corner of the screen.
+pub const SHOW_ICON: u8 = 0x04;
+
+/// Status effects, by their protocol ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Effect {
+    Speed = 1,
+    Slowness = 2,
+    Haste = 3,
+    MiningFatigue = 4,
+    Strength = 5,
+    InstantHealth = 6,
+    InstantDamage = 7,
+    JumpBoost = 8,
+    Nausea = 9,
+    Regeneration = 10,
+    Resistance = 11,
+    FireResistance = 12,
+    WaterBreathing = 13,
+    Invisibility = 14,
+    Blindness = 15,
+    NightVision = 16,
+    Hunger = 17,
+    Weakness = 18,
+    Poison = 19,
+    Wither = 20,
+    HealthBoost = 21,
+    Absorption = 22,
+    Saturation = 23,
+    Glowing = 24,
+    Levitation = 25,
+    Luck = 26,
+    Unluck = 27,
+    SlowFalling = 28,
+    ConduitPower = 29,
+    DolphinsGrace = 30,
+    BadOmen = 31,
+    HeroOfTheVillage = 32,
+}
+
+impl Effect {
+    pub fn id(self) -> i32 {
+        self as i32
+    }
+
+    /// Whether the effect changes how fast the entity moves, which the client has to be told
+    /// through its attributes.
+    pub fn changes_movement_speed(self) -> bool {
+        matches!(self, Effect::Speed | Effect::Slowness)
+    }
+}
+
+/// An effect on an entity.
+///
+/// - `amplifier`: One less than the effect's level, so 0 is level I.
+/// - `remaining_ticks`: How long until the effect runs out.
+/// - `flags`: A combination of [AMBIENT], [SHOW_PARTICLES] and [SHOW_ICON].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActiveEffect {
+    pub effect: Effect,
+    pub amplifier: u8,
+    pub remaining_ticks: i32,
+    pub flags: u8,
+}
+
+impl ActiveEffect {
+    pub fn new(effect: Effect, amplifier: u8, remaining_ticks: i32) -> Self {
+        Self {
+            effect,
+            amplifier,
+            remaining_ticks,
+            flags: SHOW_PARTICLES | SHOW_ICON,
+        }
+    }
+
+    /// Whether this effect should replace `existing`, the same effect already on the entity.
+    /// Like in vanilla, stronger effects always do, and ones just as strong only do if they
+    /// last longer.
+    fn replaces(&self, existing: &ActiveEffect) -> bool {
+        self.amplifier > existing.amplifier
+            || (self.amplifier == existing.amplifier
+                && self.remaining_ticks > existing.remaining_ticks)
+    }
+}
+
+/// The effects on an entity. Counted down by
+/// [effects_system](crate::net::systems::effects_system), and added with
+/// [ServerState::apply_effect](crate::state::ServerState::apply_effect).
+#[derive(Component, Debug, Clone, Default)]
+pub struct ActiveEffects {
+    pub effects: Vec<ActiveEffect>,
+}
+
+impl ActiveEffects {
+    pub fn get(&self, effect: Effect) -> Option<&ActiveEffect> {
+        self.effects.iter().find(|active| active.effect == effect)
+    }
+
+    /// Adds `effect`, unless the entity already has it at least as strong and for at least as
+    /// long. Returns whether it was added.
+    pub fn apply(&mut self, effect: ActiveEffect) -> bool {
+        match self
+            .effects
+            .iter_mut()
+            .find(|active| active.effect == effect.effect)
+        {
+            Some(existing) if !effect.replaces(existing) => false,
+            Some(existing) => {
+                *existing = effect;
+                true
+            }
+            None => {
+                self.effects.push(effect);
+                true
+            }
+        }
+    }
+
+    /// Takes `effect` off. Returns `false` if it wasn't on.
+    pub fn remove(&mut self, effect: Effect) -> bool {
+        let count = self.effects.len();
+        self.effects.retain(|active| active.effect != effect);
+        self.effects.len() != count
+    }
+
+    /// Counts every effect down by a tick, and returns the ones that ran out.
+    pub fn tick(&mut self) -> Vec<Effect> {
+        let mut expired = Vec::new();
+        self.effects.retain_mut(|active| {
+            active.remaining_ticks -= 1;
+            if active.remaining_ticks > 0 {
+                return true;
+            }
+            expired.push(active.effect);
+            false
+        });
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_stronger_or_longer_effects_replace() {
+        let mut effects = ActiveEffects::default();
+        assert!(effects.apply(ActiveEffect::new(Effect::Speed, 0, 200)));
+        assert!(!effects.apply(ActiveEffect::new(Effect::Speed, 0, 100)));
+        assert_eq!(effects.get(Effect::Speed).unwrap().remaining_ticks, 200);
+
+        assert!(effects.apply(ActiveEffect::new(Effect::Speed, 0, 300)));
+        assert_eq!(effects.get(Effect::Speed).unwrap().remaining_ticks, 300);
+
+        assert!(effects.apply(ActiveEffect::new(Effect::Speed, 1, 20)));
+        assert!(!effects.apply(ActiveEffect::new(Effect::Speed, 0, 1000)));
+        assert_eq!(
+            *effects.get(Effect::Speed).unwrap(),
+            ActiveEffect::new(Effect::Speed, 1, 20)
+        );
+        assert_eq!(effects.effects.len(), 1);
+    }
+
+    #[test]
+    fn test_effects_run_out() {
+        let mut effects = ActiveEffects::default();
+        effects.apply(ActiveEffect::new(Effect::Speed, 0, 1));
+        effects.apply(ActiveEffect::new(Effect::Haste, 0, 2));
+        assert_eq!(effects.tick(), vec![Effect::Speed]);
+        assert_eq!(effects.tick(), vec![Effect::Haste]);
+        assert!(effects.tick().is_empty());
+
+        effects.apply(ActiveEffect::new(Effect::Glowing, 0, 10));
+        assert!(effects.remove(Effect::Glowing));
+        assert!(!effects.remove(Effect::Glowing));
+    }
+}
@@ -0,0 +1,10 @@
+use std::collections::HashSet;
+
+use ferrumc_macros::Component;
+
+/// The UUIDs of the boss bars a player's client is showing. Being a component, it goes away with
+/// the player, so bars never have to be told about disconnects.
+#[derive(Component, Debug, Clone, Default)]
+pub struct ShownBossBars {
+    pub bars: HashSet<u128>,
+}
@@ -0,0 +1,86 @@
+use ferrumc_macros::Component;
+
+use crate::net::packets::incoming::client_info::ClientInfo;
+
+/// Vanilla clients won't go below this.
+pub const MIN_VIEW_DISTANCE: u8 = 2;
+
+/// Which chat messages the player wants to see.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChatMode {
+    #[default]
+    Enabled,
+    CommandsOnly,
+    Hidden,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MainHand {
+    Left,
+    #[default]
+    Right,
+}
+
+/// The settings the client sent in [ClientInfo]. Clients send them right after joining, and again
+/// whenever the player changes them.
+///
+/// - `view_distance`: How many chunks the client wants to see around it. The server can send less,
+///   see [crate::net::systems::chunk_sender::view_distance].
+/// - `displayed_skin_parts`: A bit mask of the cape, jacket, sleeves, pants legs and hat layers.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct ClientSettings {
+    pub locale: String,
+    pub view_distance: u8,
+    pub chat_mode: ChatMode,
+    pub chat_colors: bool,
+    pub displayed_skin_parts: u8,
+    pub main_hand: MainHand,
+    pub allow_server_listings: bool,
+}
+
+impl From<&ClientInfo> for ClientSettings {
+    fn from(info: &ClientInfo) -> Self {
+        Self {
+            locale: info.locale.clone(),
+            view_distance: (info.view_distance.max(0) as u8).max(MIN_VIEW_DISTANCE),
+            chat_mode: match info.chat_mode.get_val() {
+                1 => ChatMode::CommandsOnly,
+                2 => ChatMode::Hidden,
+                _ => ChatMode::Enabled,
+            },
+            chat_colors: info.chat_colors,
+            displayed_skin_parts: info.displayed_skin_parts,
+            main_hand: match info.main_hand.get_val() {
+                0 => MainHand::Left,
+                _ => MainHand::Right,
+            },
+            allow_server_listings: info.allow_server_listings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::network_types::varint::VarInt;
+
+    use super::*;
+
+    #[test]
+    fn test_settings_from_client_info() {
+        let info = ClientInfo {
+            locale: "en_us".to_string(),
+            view_distance: -5,
+            chat_mode: VarInt::new(1),
+            chat_colors: true,
+            displayed_skin_parts: 0x7F,
+            main_hand: VarInt::new(0),
+            enable_text_filtering: false,
+            allow_server_listings: true,
+        };
+        let settings = ClientSettings::from(&info);
+        assert_eq!(settings.view_distance, MIN_VIEW_DISTANCE);
+        assert_eq!(settings.chat_mode, ChatMode::CommandsOnly);
+        assert_eq!(settings.main_hand, MainHand::Left);
+        assert_eq!(settings.displayed_skin_parts, 0x7F);
+    }
+}
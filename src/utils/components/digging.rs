@@ -0,0 +1,10 @@
+use ferrumc_macros::Component;
+
+use crate::utils::encoding::position::Position;
+
+/// The block a player outside of creative started digging, from the Player Action packet. It's
+/// only broken once they finish digging that same block.
+#[derive(Debug, Default, Component)]
+pub struct Digging {
+    pub location: Option<Position>,
+}
@@ -0,0 +1,9 @@
+use std::sync::Arc;
+
+use ferrumc_macros::Component;
+
+use crate::world::dimensions::Dimension;
+
+/// The dimension a player is in, see [crate::state::ServerState::dimension_of].
+#[derive(Component, Debug, Clone)]
+pub struct CurrentDimension(pub Arc<Dimension>);
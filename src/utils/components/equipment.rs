@@ -0,0 +1,132 @@
+use ferrumc_macros::Component;
+
+use crate::net::packets::outgoing::set_equipment::EquipmentSlot;
+use crate::utils::components::inventory::{Inventory, ARMOR, OFFHAND};
+use crate::utils::encoding::slot::Slot;
+
+/// Every equipment slot, in the order [Equipment::slots] holds them.
+pub const EQUIPMENT_SLOTS: [EquipmentSlot; 6] = [
+    EquipmentSlot::MainHand,
+    EquipmentSlot::OffHand,
+    EquipmentSlot::Boots,
+    EquipmentSlot::Leggings,
+    EquipmentSlot::Chestplate,
+    EquipmentSlot::Helmet,
+];
+
+/// What an entity is holding and wearing, as other players see it.
+///
+/// - `slots`: What's in each slot now, by [EquipmentSlot].
+/// - `sent`: What viewers were last sent, so only the slots that changed since go out.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Equipment {
+    pub slots: [Slot; 6],
+    pub sent: [Slot; 6],
+}
+
+impl Equipment {
+    pub fn get(&self, slot: EquipmentSlot) -> &Slot {
+        &self.slots[slot as usize]
+    }
+
+    pub fn set(&mut self, slot: EquipmentSlot, item: Slot) {
+        self.slots[slot as usize] = item;
+    }
+
+    /// Copies the held item, off hand and armor out of a player's inventory. `selected` is the
+    /// hotbar slot they have selected.
+    pub fn update_from_inventory(&mut self, inventory: &Inventory, selected: u8) {
+        self.set(
+            EquipmentSlot::MainHand,
+            inventory.hotbar_slot(selected).clone(),
+        );
+        self.set(EquipmentSlot::OffHand, inventory.slots[OFFHAND].clone());
+        // The inventory has the armor from the helmet down.
+        for (index, slot) in ARMOR.zip([
+            EquipmentSlot::Helmet,
+            EquipmentSlot::Chestplate,
+            EquipmentSlot::Leggings,
+            EquipmentSlot::Boots,
+        ]) {
+            self.set(slot, inventory.slots[index].clone());
+        }
+    }
+
+    /// The slots that changed since this was last called, which are then counted as sent.
+    pub fn take_changes(&mut self) -> Vec<(EquipmentSlot, Slot)> {
+        let mut changes = Vec::new();
+        for slot in EQUIPMENT_SLOTS {
+            let index = slot as usize;
+            if self.slots[index] != self.sent[index] {
+                self.sent[index] = self.slots[index].clone();
+                changes.push((slot, self.slots[index].clone()));
+            }
+        }
+        changes
+    }
+
+    /// Every slot that has something in it, for players that just started seeing the entity.
+    pub fn non_empty(&self) -> Vec<(EquipmentSlot, Slot)> {
+        EQUIPMENT_SLOTS
+            .into_iter()
+            .filter(|&slot| !self.get(slot).is_empty())
+            .map(|slot| (slot, self.get(slot).clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::components::inventory::HOTBAR;
+    use crate::utils::encoding::slot::ItemStack;
+
+    const STONE: i32 = 1;
+    const IRON_HELMET: i32 = 10;
+
+    #[test]
+    fn test_update_from_inventory() {
+        let mut inventory = Inventory::default();
+        inventory.slots[HOTBAR.start + 2] = ItemStack::new(STONE, 3).into();
+        inventory.slots[ARMOR.start] = ItemStack::new(IRON_HELMET, 1).into();
+
+        let mut equipment = Equipment::default();
+        equipment.update_from_inventory(&inventory, 2);
+        assert_eq!(
+            *equipment.get(EquipmentSlot::MainHand),
+            Slot::from(ItemStack::new(STONE, 3))
+        );
+        assert_eq!(
+            *equipment.get(EquipmentSlot::Helmet),
+            Slot::from(ItemStack::new(IRON_HELMET, 1))
+        );
+        assert!(equipment.get(EquipmentSlot::Boots).is_empty());
+
+        equipment.update_from_inventory(&inventory, 0);
+        assert!(equipment.get(EquipmentSlot::MainHand).is_empty());
+    }
+
+    #[test]
+    fn test_only_changes_are_taken() {
+        let mut equipment = Equipment::default();
+        assert!(equipment.take_changes().is_empty());
+
+        equipment.set(EquipmentSlot::MainHand, ItemStack::new(STONE, 1).into());
+        equipment.set(EquipmentSlot::Helmet, ItemStack::new(IRON_HELMET, 1).into());
+        assert_eq!(equipment.take_changes().len(), 2);
+        assert!(equipment.take_changes().is_empty());
+
+        equipment.set(EquipmentSlot::MainHand, Slot::EMPTY);
+        assert_eq!(
+            equipment.take_changes(),
+            vec![(EquipmentSlot::MainHand, Slot::EMPTY)]
+        );
+        assert_eq!(
+            equipment.non_empty(),
+            vec![(
+                EquipmentSlot::Helmet,
+                Slot::from(ItemStack::new(IRON_HELMET, 1))
+            )]
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use ferrumc_macros::Component;
+
+use crate::utils::encoding::position::Position;
+use crate::world::physics::BoundingBox;
+
+/// How wide players are, same as vanilla.
+pub const PLAYER_WIDTH: f64 = 0.6;
+/// How tall players are while standing, same as vanilla.
+pub const PLAYER_HEIGHT: f64 = 1.8;
+
+/// Exactly where a player is, at their feet, as of their last move that was allowed or the last
+/// place they were teleported to. Their [Position] is kept as the block this is in.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ExactPosition(pub (f64, f64, f64));
+
+impl ExactPosition {
+    /// The corner of `position`, for players that are put in a block rather than somewhere
+    /// exact, like at spawn.
+    pub fn corner_of(position: &Position) -> Self {
+        Self((position.x as f64, position.y as f64, position.z as f64))
+    }
+
+    /// The block this is in.
+    pub fn block(&self) -> Position {
+        let (x, y, z) = self.0;
+        Position::new(x.floor() as i32, y.floor() as i16, z.floor() as i32)
+    }
+
+    /// The hitbox of a player standing here.
+    pub fn hitbox(&self) -> BoundingBox {
+        let (x, y, z) = self.0;
+        let half_width = PLAYER_WIDTH / 2.0;
+        BoundingBox {
+            min: [x - half_width, y, z - half_width],
+            max: [x + half_width, y + PLAYER_HEIGHT, z + half_width],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_rounds_down() {
+        let block = ExactPosition((1.5, 64.0, -0.3)).block();
+        assert_eq!((block.x, block.y, block.z), (1, 64, -1));
+        assert_eq!(ExactPosition::corner_of(&block).0, (1.0, 64.0, -1.0));
+    }
+
+    #[test]
+    fn test_hitbox() {
+        let hitbox = ExactPosition((10.8, 64.0, 0.5)).hitbox();
+        assert!(hitbox.intersects(&BoundingBox::block(&Position::new(11, 65, 0))));
+        assert!(!hitbox.intersects(&BoundingBox::block(&Position::new(11, 66, 0))));
+        assert!(!hitbox.intersects(&BoundingBox::block(&Position::new(9, 64, 0))));
+    }
+}
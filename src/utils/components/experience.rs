@@ -0,0 +1,95 @@
+use ferrumc_macros::Component;
+
+/// A player's experience.
+///
+/// - `level`: The number shown above the hotbar.
+/// - `total`: All the experience the player has, which `level` and `progress` come from.
+/// - `progress`: How far the bar is towards the next level, from 0 to 1.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Experience {
+    pub level: i32,
+    pub total: i32,
+    pub progress: f32,
+}
+
+impl Experience {
+    /// The level and progress `total` experience gets a player to, using vanilla's level curve.
+    /// Negative totals are treated as none.
+    pub fn from_total(total: i32) -> Self {
+        let total = total.max(0);
+        let mut level = 0;
+        let mut remaining = total;
+        while remaining >= experience_to_next_level(level) {
+            remaining -= experience_to_next_level(level);
+            level += 1;
+        }
+        Self {
+            level,
+            total,
+            progress: remaining as f32 / experience_to_next_level(level) as f32,
+        }
+    }
+
+    /// Adds `amount` experience, or takes it away if it's negative, and returns how many levels
+    /// that went up by.
+    pub fn add(&mut self, amount: i32) -> i32 {
+        let level = self.level;
+        *self = Self::from_total(self.total.saturating_add(amount));
+        self.level - level
+    }
+}
+
+/// How much experience it takes to get from `level` to the next one.
+pub fn experience_to_next_level(level: i32) -> i32 {
+    match level {
+        ..15 => 2 * level + 7,
+        15..30 => 5 * level - 38,
+        _ => 9 * level - 158,
+    }
+}
+
+/// How much experience it takes to get from nothing to `level`.
+pub fn total_experience_for_level(level: i32) -> i32 {
+    (0..level).map(experience_to_next_level).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_curve_matches_vanilla() {
+        // Totals from the vanilla formulas for each part of the curve.
+        for (level, total) in [
+            (0, 0),
+            (1, 7),
+            (15, 315),
+            (16, 352),
+            (30, 1395),
+            (31, 1507),
+            (32, 1628),
+            (50, 5345),
+        ] {
+            assert_eq!(total_experience_for_level(level), total);
+            assert_eq!(Experience::from_total(total).level, level);
+            assert_eq!(Experience::from_total(total - 1).level, (level - 1).max(0));
+        }
+    }
+
+    #[test]
+    fn test_progress_towards_the_next_level() {
+        // Level 16 needs 42 more to get to 17.
+        let experience = Experience::from_total(352 + 21);
+        assert_eq!(experience.level, 16);
+        assert_eq!(experience.progress, 0.5);
+    }
+
+    #[test]
+    fn test_adding_experience_returns_levels_gained() {
+        let mut experience = Experience::default();
+        assert_eq!(experience.add(7), 1);
+        assert_eq!(experience.add(1), 0);
+        assert_eq!(experience.add(-100), -1);
+        assert_eq!(experience, Experience::default());
+    }
+}
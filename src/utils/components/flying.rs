@@ -0,0 +1,48 @@
+use ferrumc_macros::Component;
+
+/// How many times a player can try to fly where they aren't allowed to before being kicked.
+pub const MAX_FLIGHT_VIOLATIONS: u8 = 5;
+
+/// Whether the player is flying. Set from the Player Abilities packet.
+///
+/// - `violations`: How many times the client has started flying without being allowed to.
+#[derive(Debug, Default, Clone, Component)]
+pub struct Flying {
+    pub is_flying: bool,
+    pub violations: u8,
+}
+
+impl Flying {
+    pub fn set_flying(&mut self, is_flying: bool) {
+        self.is_flying = is_flying;
+    }
+
+    /// Records an attempt to fly without being allowed to. The player isn't flying after it,
+    /// since their client gets corrected.
+    pub fn add_violation(&mut self) {
+        self.is_flying = false;
+        self.violations = self.violations.saturating_add(1);
+    }
+
+    pub fn is_struck_out(&self) -> bool {
+        self.violations >= MAX_FLIGHT_VIOLATIONS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations_add_up() {
+        let mut flying = Flying::default();
+        flying.set_flying(true);
+        flying.add_violation();
+        assert!(!flying.is_flying);
+        for _ in 1..MAX_FLIGHT_VIOLATIONS {
+            assert!(!flying.is_struck_out());
+            flying.add_violation();
+        }
+        assert!(flying.is_struck_out());
+    }
+}
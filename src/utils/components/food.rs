@@ -0,0 +1,169 @@
+use ferrumc_macros::Component;
+
+use crate::utils::components::health::Health;
+
+/// A full food bar.
+pub const MAX_FOOD: i32 = 20;
+/// What players start with, same as vanilla.
+pub const DEFAULT_SATURATION: f32 = 5.0;
+
+/// How much exhaustion uses up one point of saturation, or of food once there's none left.
+const EXHAUSTION_PER_POINT: f32 = 4.0;
+/// Players with at least this much food slowly heal.
+const REGENERATION_FOOD: i32 = 18;
+/// How many ticks healing and starving take, and how many healing from saturation takes.
+const REGENERATION_TICKS: u32 = 80;
+const SATURATED_REGENERATION_TICKS: u32 = 10;
+/// Healing one point of health costs this much exhaustion.
+const REGENERATION_EXHAUSTION: f32 = 6.0;
+/// Starving never takes players below this, same as vanilla on normal difficulty.
+const MIN_STARVING_HEALTH: f32 = 1.0;
+
+/// What a tick of [Food::tick] does to the player's health.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FoodEffect {
+    Nothing,
+    Heal(f32),
+    Starve(f32),
+}
+
+/// The player's food bar.
+///
+/// - `saturation`: Hidden food that's used up before the food bar goes down. Never more than
+///   `level`.
+/// - `exhaustion`: Builds up from healing, and takes away saturation or food as it does.
+/// - `tick_timer`: Ticks since the player last healed or starved.
+#[derive(Component, Debug, Clone)]
+pub struct Food {
+    pub level: i32,
+    pub saturation: f32,
+    pub exhaustion: f32,
+    pub tick_timer: u32,
+}
+
+impl Default for Food {
+    fn default() -> Self {
+        Self {
+            level: MAX_FOOD,
+            saturation: DEFAULT_SATURATION,
+            exhaustion: 0.0,
+            tick_timer: 0,
+        }
+    }
+}
+
+impl Food {
+    pub fn add_exhaustion(&mut self, amount: f32) {
+        self.exhaustion += amount.max(0.0);
+    }
+
+    /// Runs one tick of natural regeneration and starving, like vanilla's food data on normal
+    /// difficulty. Players with a full bar and saturation left heal quickly, players with most of
+    /// their food heal slowly, and players with none starve.
+    pub fn tick(&mut self, health: &Health) -> FoodEffect {
+        if self.exhaustion > EXHAUSTION_PER_POINT {
+            self.exhaustion -= EXHAUSTION_PER_POINT;
+            if self.saturation > 0.0 {
+                self.saturation = (self.saturation - 1.0).max(0.0);
+            } else {
+                self.level = (self.level - 1).max(0);
+            }
+        }
+
+        if self.saturation > 0.0 && health.is_hurt() && self.level >= MAX_FOOD {
+            self.tick_timer += 1;
+            if self.tick_timer >= SATURATED_REGENERATION_TICKS {
+                let used = self.saturation.min(REGENERATION_EXHAUSTION);
+                self.add_exhaustion(used);
+                self.tick_timer = 0;
+                return FoodEffect::Heal(used / REGENERATION_EXHAUSTION);
+            }
+        } else if self.level >= REGENERATION_FOOD && health.is_hurt() {
+            self.tick_timer += 1;
+            if self.tick_timer >= REGENERATION_TICKS {
+                self.add_exhaustion(REGENERATION_EXHAUSTION);
+                self.tick_timer = 0;
+                return FoodEffect::Heal(1.0);
+            }
+        } else if self.level <= 0 {
+            self.tick_timer += 1;
+            if self.tick_timer >= REGENERATION_TICKS {
+                self.tick_timer = 0;
+                if health.health > MIN_STARVING_HEALTH {
+                    return FoodEffect::Starve(1.0);
+                }
+            }
+        } else {
+            self.tick_timer = 0;
+        }
+        FoodEffect::Nothing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hurt(health: f32) -> Health {
+        Health {
+            health,
+            ..Health::default()
+        }
+    }
+
+    fn tick_until_effect(food: &mut Food, health: &Health) -> (u32, FoodEffect) {
+        for ticks in 1..=1000 {
+            let effect = food.tick(health);
+            if effect != FoodEffect::Nothing {
+                return (ticks, effect);
+            }
+        }
+        panic!("Nothing happened");
+    }
+
+    #[test]
+    fn test_saturated_players_heal_quickly() {
+        let mut food = Food::default();
+        let (ticks, effect) = tick_until_effect(&mut food, &hurt(10.0));
+        assert_eq!(ticks, 10);
+        assert_eq!(effect, FoodEffect::Heal(5.0 / 6.0));
+        assert_eq!(food.exhaustion, 5.0);
+    }
+
+    #[test]
+    fn test_fed_players_heal_slowly_and_get_hungry() {
+        let mut food = Food {
+            level: 18,
+            saturation: 0.0,
+            ..Food::default()
+        };
+        let (ticks, effect) = tick_until_effect(&mut food, &hurt(10.0));
+        assert_eq!((ticks, effect), (80, FoodEffect::Heal(1.0)));
+        // The exhaustion takes a point of food on the next tick.
+        food.tick(&hurt(10.0));
+        assert_eq!(food.level, 17);
+    }
+
+    #[test]
+    fn test_starving_stops_at_half_a_heart() {
+        let mut food = Food {
+            level: 0,
+            saturation: 0.0,
+            ..Food::default()
+        };
+        let (ticks, effect) = tick_until_effect(&mut food, &hurt(10.0));
+        assert_eq!((ticks, effect), (80, FoodEffect::Starve(1.0)));
+
+        for _ in 0..REGENERATION_TICKS * 2 {
+            assert_eq!(food.tick(&hurt(1.0)), FoodEffect::Nothing);
+        }
+    }
+
+    #[test]
+    fn test_healthy_players_dont_heal() {
+        let mut food = Food::default();
+        for _ in 0..100 {
+            assert_eq!(food.tick(&Health::default()), FoodEffect::Nothing);
+        }
+    }
+}
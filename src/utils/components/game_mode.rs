@@ -0,0 +1,140 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use ferrumc_macros::Component;
+
+/// The player's gamemode. Players join in the config's `default_gamemode`, and it's changed with
+/// [ServerState::set_gamemode](crate::state::ServerState::set_gamemode).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Component, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    Survival = 0,
+    #[default]
+    Creative = 1,
+    Adventure = 2,
+    Spectator = 3,
+}
+
+impl GameMode {
+    /// The id it's sent to the client as.
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Some(match id {
+            0 => GameMode::Survival,
+            1 => GameMode::Creative,
+            2 => GameMode::Adventure,
+            3 => GameMode::Spectator,
+            _ => return None,
+        })
+    }
+
+    /// Reads a gamemode from its name, e.g. "survival", in any case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "survival" => GameMode::Survival,
+            "creative" => GameMode::Creative,
+            "adventure" => GameMode::Adventure,
+            "spectator" => GameMode::Spectator,
+            _ => return None,
+        })
+    }
+
+    /// Whether players in this gamemode are allowed to fly.
+    pub fn can_fly(self) -> bool {
+        matches!(self, GameMode::Creative | GameMode::Spectator)
+    }
+
+    /// Whether players in this gamemode fly all the time. Spectators can't land.
+    pub fn is_always_flying(self) -> bool {
+        self == GameMode::Spectator
+    }
+
+    /// Whether players in this gamemode can break and place blocks. Adventure players can't, and
+    /// neither can spectators.
+    pub fn can_build(self) -> bool {
+        matches!(self, GameMode::Survival | GameMode::Creative)
+    }
+
+    /// Spectators pass through the world without touching it: they can't break or place blocks
+    /// or hit anyone, don't get in the way of blocks being placed, and only other spectators
+    /// can see them.
+    pub fn is_spectator(self) -> bool {
+        self == GameMode::Spectator
+    }
+}
+
+/// Gamemodes can be written by name or by id, so `default_gamemode = "survival"` and
+/// `default_gamemode = 0` in the config are the same.
+impl<'de> Deserialize<'de> for GameMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum NameOrId {
+            Id(i64),
+            Name(String),
+        }
+
+        match NameOrId::deserialize(deserializer)? {
+            NameOrId::Id(id) => u8::try_from(id)
+                .ok()
+                .and_then(GameMode::from_id)
+                .ok_or_else(|| D::Error::custom(format!("Unknown gamemode id {}", id))),
+            NameOrId::Name(name) => GameMode::from_name(&name)
+                .ok_or_else(|| D::Error::custom(format!("Unknown gamemode \"{}\"", name))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Config {
+        gamemode: GameMode,
+    }
+
+    fn parse(value: &str) -> Option<GameMode> {
+        config::Config::builder()
+            .add_source(config::File::from_str(
+                &format!("gamemode = {}", value),
+                config::FileFormat::Toml,
+            ))
+            .build()
+            .ok()?
+            .try_deserialize::<Config>()
+            .ok()
+            .map(|config| config.gamemode)
+    }
+
+    #[test]
+    fn test_parsing_names_and_ids() {
+        assert_eq!(parse(r#""survival""#), Some(GameMode::Survival));
+        assert_eq!(parse(r#""Spectator""#), Some(GameMode::Spectator));
+        assert_eq!(parse("2"), Some(GameMode::Adventure));
+        assert_eq!(parse("1"), Some(GameMode::Creative));
+        assert_eq!(parse("4"), None);
+        assert_eq!(parse("-1"), None);
+        assert_eq!(parse(r#""hardcore""#), None);
+    }
+
+    #[test]
+    fn test_ids_round_trip() {
+        for gamemode in [
+            GameMode::Survival,
+            GameMode::Creative,
+            GameMode::Adventure,
+            GameMode::Spectator,
+        ] {
+            assert_eq!(GameMode::from_id(gamemode.id()), Some(gamemode));
+        }
+    }
+}
+
+/// The gamemode the player was in before their current one. The client's gamemode switcher
+/// (F3 + F4) starts from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct PreviousGameMode(pub GameMode);
@@ -0,0 +1,84 @@
+use ferrumc_macros::Component;
+
+/// How much health players have when they're at full health, which is 10 hearts.
+pub const MAX_HEALTH: f32 = 20.0;
+
+/// How much health an entity has left, out of `max`. At 0 it's dead.
+#[derive(Component, Debug, Clone)]
+pub struct Health {
+    pub health: f32,
+    pub max: f32,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            health: MAX_HEALTH,
+            max: MAX_HEALTH,
+        }
+    }
+}
+
+impl Health {
+    /// Takes `amount` off, without going below 0. Returns whether that killed it, so it's false
+    /// if it was already dead.
+    pub fn damage(&mut self, amount: f32) -> bool {
+        if self.is_dead() {
+            return false;
+        }
+        self.health = (self.health - amount.max(0.0)).max(0.0);
+        self.is_dead()
+    }
+
+    /// Gives back `amount`, without going over the maximum.
+    pub fn heal(&mut self, amount: f32) {
+        self.health = (self.health + amount.max(0.0)).min(self.max);
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0.0
+    }
+
+    /// Whether it's missing any health.
+    pub fn is_hurt(&self) -> bool {
+        self.health < self.max
+    }
+
+    /// Back to full health, e.g. after respawning.
+    pub fn reset(&mut self) {
+        self.health = self.max;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_damage_until_dead() {
+        let mut health = Health::default();
+        assert!(!health.damage(15.0));
+        assert!(!health.damage(-5.0));
+        assert_eq!(health.health, 5.0);
+        assert!(health.damage(7.5));
+        assert_eq!(health.health, 0.0);
+        // It only dies once.
+        assert!(!health.damage(1.0));
+
+        health.reset();
+        assert!(!health.is_dead());
+        assert_eq!(health.health, MAX_HEALTH);
+    }
+
+    #[test]
+    fn test_healing_stops_at_the_maximum() {
+        let mut health = Health::default();
+        health.damage(3.0);
+        assert!(health.is_hurt());
+        health.heal(1.0);
+        assert_eq!(health.health, 18.0);
+        health.heal(10.0);
+        assert_eq!(health.health, MAX_HEALTH);
+        assert!(!health.is_hurt());
+    }
+}
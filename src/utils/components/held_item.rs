@@ -0,0 +1,21 @@
+use ferrumc_macros::{Component, Constructor, Getter};
+
+/// How many slots the hotbar has.
+pub const HOTBAR_SLOTS: u8 = 9;
+
+/// The hotbar slot the player has selected, from 0 to 8.
+#[derive(Debug, Default, Component, Getter, Constructor)]
+pub struct HeldItem {
+    pub slot: u8,
+}
+
+impl HeldItem {
+    /// Returns `false`, leaving the selection alone, if `slot` isn't a hotbar slot.
+    pub fn select(&mut self, slot: i16) -> bool {
+        if !(0..HOTBAR_SLOTS as i16).contains(&slot) {
+            return false;
+        }
+        self.slot = slot as u8;
+        true
+    }
+}
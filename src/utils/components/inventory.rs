@@ -0,0 +1,570 @@
+use std::ops::Range;
+
+use ferrumc_macros::Component;
+
+use crate::utils::encoding::slot::{ItemStack, Slot};
+
+/// The player inventory window always has this id.
+pub const PLAYER_WINDOW_ID: u8 = 0;
+/// Crafting output, the 2x2 crafting grid, armor, the main inventory, the hotbar and the off
+/// hand, in the order the client numbers them.
+pub const INVENTORY_SIZE: usize = 46;
+pub const CRAFTING_OUTPUT: usize = 0;
+pub const CRAFTING_GRID: Range<usize> = 1..5;
+/// From the helmet down to the boots.
+pub const ARMOR: Range<usize> = 5..9;
+pub const MAIN_INVENTORY: Range<usize> = 9..36;
+pub const HOTBAR: Range<usize> = 36..45;
+pub const OFFHAND: usize = 45;
+/// The slot clicks outside the window are sent for.
+pub const OUTSIDE_WINDOW: i16 = -999;
+/// Items don't have their own stack sizes yet, so everything stacks to this.
+pub const MAX_STACK_SIZE: i8 = 64;
+
+/// The button sent by the client for swapping with the off hand (F by default).
+const OFFHAND_SWAP_BUTTON: i8 = 40;
+
+/// How a slot was clicked, from the Click Container packet's mode and button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickMode {
+    /// Left or right clicking a slot, or outside the window.
+    Pickup,
+    /// Shift clicking a slot.
+    QuickMove,
+    /// Pressing a hotbar key, or the off hand key, over a slot.
+    Swap,
+    /// Middle clicking a slot in creative.
+    Clone,
+    /// Pressing the drop key over a slot.
+    Throw,
+    /// Dragging over several slots.
+    QuickCraft,
+    /// Double clicking a slot.
+    PickupAll,
+}
+
+impl ClickMode {
+    pub fn from_id(mode: i32) -> Option<Self> {
+        Some(match mode {
+            0 => ClickMode::Pickup,
+            1 => ClickMode::QuickMove,
+            2 => ClickMode::Swap,
+            3 => ClickMode::Clone,
+            4 => ClickMode::Throw,
+            5 => ClickMode::QuickCraft,
+            6 => ClickMode::PickupAll,
+            _ => return None,
+        })
+    }
+}
+
+/// A player's inventory, as the server sees it. Clients change theirs as soon as they click,
+/// and it's up to the server to tell them when they got it wrong.
+///
+/// - `cursor`: The item the player is carrying around with their mouse.
+/// - `state_id`: Goes up every time the client is sent changes, so clicks made before they
+///   arrived can be told apart.
+#[derive(Component, Debug, Clone)]
+pub struct Inventory {
+    pub slots: Vec<Slot>,
+    pub cursor: Slot,
+    pub state_id: i32,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self {
+            slots: vec![Slot::EMPTY; INVENTORY_SIZE],
+            cursor: Slot::EMPTY,
+            state_id: 0,
+        }
+    }
+}
+
+impl Inventory {
+    /// The index of `slot` in [Inventory::slots], if it's one.
+    pub fn index(slot: i16) -> Option<usize> {
+        usize::try_from(slot)
+            .ok()
+            .filter(|&index| index < INVENTORY_SIZE)
+    }
+
+    /// What's in the hotbar slot `selected`, from 0 to 8.
+    pub fn hotbar_slot(&self, selected: u8) -> &Slot {
+        &self.slots[HOTBAR.start + selected as usize]
+    }
+
+    /// What's in the player's off hand if `off_hand`, or otherwise their main hand, the hotbar
+    /// slot `selected`.
+    pub fn held(&self, selected: u8, off_hand: bool) -> &Slot {
+        match off_hand {
+            true => &self.slots[OFFHAND],
+            false => self.hotbar_slot(selected),
+        }
+    }
+
+    /// Moves on to a new state id, for sending the client changes with.
+    pub fn next_state_id(&mut self) -> i32 {
+        // Same as vanilla, which keeps it positive.
+        self.state_id = (self.state_id + 1) & 0x7FFF;
+        self.state_id
+    }
+
+    /// Applies a click the same way the client does. Returns `false`, leaving the inventory as
+    /// it was, if the click isn't valid or isn't supported yet.
+    ///
+    /// Items dropped out of the window are gone for good. Only the ones dropped from the hotbar
+    /// with the drop key are thrown into the world, see
+    /// [crate::net::utils::dropped_items].
+    pub fn click(&mut self, slot: i16, button: i8, mode: ClickMode) -> bool {
+        match mode {
+            ClickMode::Pickup => self.pickup(slot, button),
+            ClickMode::QuickMove => self.quick_move(slot),
+            ClickMode::Swap => self.swap(slot, button),
+            ClickMode::Throw => self.throw(slot, button),
+            ClickMode::PickupAll => self.pickup_all(slot),
+            ClickMode::Clone | ClickMode::QuickCraft => false,
+        }
+    }
+
+    /// Whether the slots and cursor the client thinks it has after a click are the same as ours.
+    pub fn matches(&self, changed_slots: &[(i16, Slot)], carried: &Slot) -> bool {
+        self.cursor == *carried
+            && changed_slots.iter().all(|(slot, item)| {
+                Self::index(*slot).is_some_and(|index| self.slots[index] == *item)
+            })
+    }
+
+    /// Puts `item` in `slot`, for players in creative who can conjure up whatever they like.
+    /// Returns `false` if `slot` can't be set.
+    pub fn set_slot(&mut self, slot: i16, item: Slot) -> bool {
+        match Self::index(slot) {
+            Some(index) if index != CRAFTING_OUTPUT => {
+                self.slots[index] = item;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Puts as much of `stack` as fits into the hotbar and the main inventory, like picking it up
+    /// does: stacks of the same item are topped up first, then empty slots are filled, hotbar
+    /// first. Returns what didn't fit.
+    pub fn add(&mut self, stack: ItemStack) -> Slot {
+        self.move_into(stack, HOTBAR.chain(MAIN_INVENTORY))
+    }
+
+    /// Takes one item, or the whole stack if `whole_stack`, out of the hotbar slot `selected`, for
+    /// the player to throw. `None` if it's empty.
+    pub fn take_from_hotbar(&mut self, selected: u8, whole_stack: bool) -> Option<ItemStack> {
+        let index = HOTBAR.start + selected as usize;
+        if !HOTBAR.contains(&index) {
+            return None;
+        }
+        let stack = self.slots[index].0.take()?;
+        let amount = if whole_stack { stack.count } else { 1 };
+        let (taken, left) = split_off(stack, amount);
+        self.slots[index] = left;
+        taken.0
+    }
+
+    /// Takes one item out of what the player's holding, see [Inventory::held], for them to place
+    /// it. `None` if their hand is empty.
+    pub fn take_from_hand(&mut self, selected: u8, off_hand: bool) -> Option<ItemStack> {
+        if !off_hand {
+            return self.take_from_hotbar(selected, false);
+        }
+        let stack = self.slots[OFFHAND].0.take()?;
+        let (taken, left) = split_off(stack, 1);
+        self.slots[OFFHAND] = left;
+        taken.0
+    }
+
+    /// Called when the player closes their inventory. What's left in the crafting grid goes back
+    /// into the inventory, and whatever doesn't fit, or is on the cursor, is dropped.
+    pub fn close(&mut self) {
+        for index in CRAFTING_GRID {
+            let stack = self.slots[index].0.take();
+            if let Some(stack) = stack {
+                self.move_into(stack, MAIN_INVENTORY.start..HOTBAR.end);
+            }
+        }
+        self.slots[CRAFTING_OUTPUT] = Slot::EMPTY;
+        self.cursor = Slot::EMPTY;
+    }
+
+    fn pickup(&mut self, slot: i16, button: i8) -> bool {
+        let right_click = match button {
+            0 => false,
+            1 => true,
+            _ => return false,
+        };
+        if slot == OUTSIDE_WINDOW {
+            // Drops the cursor, or one item off it for a right click.
+            self.cursor = match self.cursor.0.take() {
+                Some(stack) if right_click => split_off(stack, 1).1,
+                _ => Slot::EMPTY,
+            };
+            return true;
+        }
+        let Some(index) = Self::index(slot).filter(|&index| index != CRAFTING_OUTPUT) else {
+            return false;
+        };
+
+        let (slot, cursor) = match (self.slots[index].0.take(), self.cursor.0.take()) {
+            (None, None) => (None, None),
+            (Some(stack), None) if right_click => {
+                let half = (stack.count + 1) / 2;
+                let (taken, left) = split_off(stack, half);
+                (left.0, taken.0)
+            }
+            (Some(stack), None) => (None, Some(stack)),
+            (None, Some(held)) if right_click => {
+                let (placed, left) = split_off(held, 1);
+                (placed.0, left.0)
+            }
+            (None, Some(held)) => (Some(held), None),
+            (Some(mut stack), Some(held)) if stack.is_same_item(&held) => {
+                let amount = if right_click { 1 } else { held.count };
+                let amount = amount.min(MAX_STACK_SIZE - stack.count).max(0);
+                stack.count += amount;
+                (Some(stack), split_off(held, amount).1 .0)
+            }
+            (Some(stack), Some(held)) => (Some(held), Some(stack)),
+        };
+        self.slots[index] = Slot(slot);
+        self.cursor = Slot(cursor);
+        true
+    }
+
+    /// Moves a stack between the hotbar and the rest of the inventory. Anything that doesn't fit
+    /// stays where it was.
+    fn quick_move(&mut self, slot: i16) -> bool {
+        let Some(index) = Self::index(slot).filter(|&index| index != CRAFTING_OUTPUT) else {
+            return false;
+        };
+        let Some(stack) = self.slots[index].0.take() else {
+            return true;
+        };
+        let targets = if HOTBAR.contains(&index) {
+            MAIN_INVENTORY
+        } else if MAIN_INVENTORY.contains(&index) {
+            HOTBAR
+        } else {
+            MAIN_INVENTORY.start..HOTBAR.end
+        };
+        self.slots[index] = self.move_into(stack, targets);
+        true
+    }
+
+    fn swap(&mut self, slot: i16, button: i8) -> bool {
+        let Some(index) = Self::index(slot).filter(|&index| index != CRAFTING_OUTPUT) else {
+            return false;
+        };
+        let other = match button {
+            0..=8 => HOTBAR.start + button as usize,
+            OFFHAND_SWAP_BUTTON => OFFHAND,
+            _ => return false,
+        };
+        self.slots.swap(index, other);
+        true
+    }
+
+    fn throw(&mut self, slot: i16, button: i8) -> bool {
+        if slot == OUTSIDE_WINDOW {
+            return true;
+        }
+        let Some(index) = Self::index(slot) else {
+            return false;
+        };
+        // The client only lets you drop items while your cursor is empty.
+        if !self.cursor.is_empty() {
+            return true;
+        }
+        self.slots[index] = match (self.slots[index].0.take(), button) {
+            (Some(stack), 0) => split_off(stack, 1).1,
+            (Some(_), 1) | (None, _) => Slot::EMPTY,
+            (Some(stack), _) => {
+                self.slots[index] = Slot(Some(stack));
+                return false;
+            }
+        };
+        true
+    }
+
+    /// Gathers as much of the item on the cursor as fits onto it, from stacks that aren't full
+    /// first.
+    fn pickup_all(&mut self, slot: i16) -> bool {
+        if Self::index(slot).is_none() {
+            return false;
+        }
+        let Some(mut held) = self.cursor.0.take() else {
+            return true;
+        };
+        for take_full_stacks in [false, true] {
+            for index in CRAFTING_GRID.start..INVENTORY_SIZE {
+                if held.count >= MAX_STACK_SIZE {
+                    break;
+                }
+                let Some(stack) = self.slots[index].0.take() else {
+                    continue;
+                };
+                if !stack.is_same_item(&held)
+                    || (stack.count >= MAX_STACK_SIZE && !take_full_stacks)
+                {
+                    self.slots[index] = Slot(Some(stack));
+                    continue;
+                }
+                let amount = stack.count.min(MAX_STACK_SIZE - held.count);
+                held.count += amount;
+                self.slots[index] = split_off(stack, amount).1;
+            }
+        }
+        self.cursor = Slot(Some(held));
+        true
+    }
+
+    /// Moves as much of `stack` as fits into the slots in `targets`, topping up stacks of the
+    /// same item before using empty slots. Returns what didn't fit.
+    fn move_into(
+        &mut self,
+        mut stack: ItemStack,
+        targets: impl Iterator<Item = usize> + Clone,
+    ) -> Slot {
+        for index in targets.clone() {
+            if let Some(existing) = &mut self.slots[index].0 {
+                if existing.is_same_item(&stack) && existing.count < MAX_STACK_SIZE {
+                    let amount = stack.count.min(MAX_STACK_SIZE - existing.count);
+                    existing.count += amount;
+                    stack.count -= amount;
+                    if stack.count == 0 {
+                        return Slot::EMPTY;
+                    }
+                }
+            }
+        }
+        for index in targets {
+            if self.slots[index].is_empty() {
+                self.slots[index] = Slot(Some(stack));
+                return Slot::EMPTY;
+            }
+        }
+        Slot(Some(stack))
+    }
+}
+
+/// Takes `amount` items off `stack`. Returns the part taken and what's left, either of which is
+/// empty if there aren't any.
+fn split_off(stack: ItemStack, amount: i8) -> (Slot, Slot) {
+    let amount = amount.clamp(0, stack.count);
+    let with_count = |count: i8| {
+        (count > 0).then(|| ItemStack {
+            count,
+            ..stack.clone()
+        })
+    };
+    (
+        Slot(with_count(amount)),
+        Slot(with_count(stack.count - amount)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STONE: i32 = 1;
+    const DIRT: i32 = 10;
+
+    fn stack(item_id: i32, count: i8) -> Slot {
+        ItemStack::new(item_id, count).into()
+    }
+
+    fn inventory_with(slots: &[(usize, Slot)]) -> Inventory {
+        let mut inventory = Inventory::default();
+        for (index, slot) in slots {
+            inventory.slots[*index] = slot.clone();
+        }
+        inventory
+    }
+
+    #[test]
+    fn test_left_click_picks_up_and_places() {
+        let mut inventory = inventory_with(&[(9, stack(STONE, 10))]);
+        assert!(inventory.click(9, 0, ClickMode::Pickup));
+        assert_eq!(inventory.cursor, stack(STONE, 10));
+        assert!(inventory.slots[9].is_empty());
+
+        assert!(inventory.click(10, 0, ClickMode::Pickup));
+        assert!(inventory.cursor.is_empty());
+        assert_eq!(inventory.slots[10], stack(STONE, 10));
+    }
+
+    #[test]
+    fn test_left_click_merges_and_swaps() {
+        let mut inventory = inventory_with(&[(9, stack(STONE, 60)), (10, stack(DIRT, 1))]);
+        inventory.cursor = stack(STONE, 10);
+        assert!(inventory.click(9, 0, ClickMode::Pickup));
+        assert_eq!(inventory.slots[9], stack(STONE, 64));
+        assert_eq!(inventory.cursor, stack(STONE, 6));
+
+        assert!(inventory.click(10, 0, ClickMode::Pickup));
+        assert_eq!(inventory.slots[10], stack(STONE, 6));
+        assert_eq!(inventory.cursor, stack(DIRT, 1));
+    }
+
+    #[test]
+    fn test_right_click_splits_and_places_one() {
+        let mut inventory = inventory_with(&[(9, stack(STONE, 5))]);
+        assert!(inventory.click(9, 1, ClickMode::Pickup));
+        assert_eq!(inventory.cursor, stack(STONE, 3));
+        assert_eq!(inventory.slots[9], stack(STONE, 2));
+
+        assert!(inventory.click(9, 1, ClickMode::Pickup));
+        assert!(inventory.click(10, 1, ClickMode::Pickup));
+        assert_eq!(inventory.slots[9], stack(STONE, 3));
+        assert_eq!(inventory.slots[10], stack(STONE, 1));
+        assert_eq!(inventory.cursor, stack(STONE, 1));
+
+        assert!(inventory.click(OUTSIDE_WINDOW, 1, ClickMode::Pickup));
+        assert!(inventory.cursor.is_empty());
+    }
+
+    #[test]
+    fn test_shift_click_moves_between_hotbar_and_inventory() {
+        let mut inventory = inventory_with(&[
+            (36, stack(STONE, 40)),
+            (12, stack(STONE, 50)),
+            (9, stack(DIRT, 1)),
+        ]);
+        assert!(inventory.click(36, 0, ClickMode::QuickMove));
+        assert_eq!(inventory.slots[12], stack(STONE, 64));
+        assert_eq!(inventory.slots[10], stack(STONE, 26));
+        assert!(inventory.slots[36].is_empty());
+
+        assert!(inventory.click(9, 0, ClickMode::QuickMove));
+        assert_eq!(inventory.slots[36], stack(DIRT, 1));
+
+        // Nothing happens when there's nowhere to put it.
+        let mut full = Inventory::default();
+        for index in HOTBAR {
+            full.slots[index] = stack(DIRT, 64);
+        }
+        full.slots[9] = stack(STONE, 1);
+        assert!(full.click(9, 0, ClickMode::QuickMove));
+        assert_eq!(full.slots[9], stack(STONE, 1));
+    }
+
+    #[test]
+    fn test_picked_up_items_go_to_the_hotbar_first() {
+        let mut inventory = inventory_with(&[(9, stack(STONE, 60)), (36, stack(DIRT, 1))]);
+        // Tops up the stone in the main inventory before taking an empty slot in the hotbar.
+        assert!(inventory.add(ItemStack::new(STONE, 10)).is_empty());
+        assert_eq!(inventory.slots[9], stack(STONE, 64));
+        assert_eq!(inventory.slots[37], stack(STONE, 6));
+
+        let mut full = Inventory::default();
+        for index in MAIN_INVENTORY.start..HOTBAR.end {
+            full.slots[index] = stack(DIRT, 64);
+        }
+        full.slots[20] = stack(STONE, 62);
+        assert_eq!(full.add(ItemStack::new(STONE, 5)), stack(STONE, 3));
+        assert_eq!(full.slots[20], stack(STONE, 64));
+    }
+
+    #[test]
+    fn test_taking_items_out_of_the_hotbar_to_throw() {
+        let mut inventory = inventory_with(&[(HOTBAR.start + 2, stack(STONE, 3))]);
+        assert_eq!(
+            inventory.take_from_hotbar(2, false),
+            Some(ItemStack::new(STONE, 1))
+        );
+        assert_eq!(inventory.slots[HOTBAR.start + 2], stack(STONE, 2));
+        assert_eq!(
+            inventory.take_from_hotbar(2, true),
+            Some(ItemStack::new(STONE, 2))
+        );
+        assert!(inventory.slots[HOTBAR.start + 2].is_empty());
+        assert_eq!(inventory.take_from_hotbar(2, true), None);
+        // 9 would be the off hand.
+        inventory.slots[OFFHAND] = stack(DIRT, 1);
+        assert_eq!(inventory.take_from_hotbar(9, true), None);
+    }
+
+    #[test]
+    fn test_taking_items_out_of_either_hand() {
+        let mut inventory = inventory_with(&[
+            (HOTBAR.start + 4, stack(STONE, 2)),
+            (OFFHAND, stack(DIRT, 1)),
+        ]);
+        assert_eq!(*inventory.held(4, false), stack(STONE, 2));
+        assert_eq!(*inventory.held(4, true), stack(DIRT, 1));
+        assert_eq!(
+            inventory.take_from_hand(4, true),
+            Some(ItemStack::new(DIRT, 1))
+        );
+        assert!(inventory.held(4, true).is_empty());
+        assert_eq!(inventory.take_from_hand(4, true), None);
+        assert_eq!(
+            inventory.take_from_hand(4, false),
+            Some(ItemStack::new(STONE, 1))
+        );
+        assert_eq!(*inventory.held(4, false), stack(STONE, 1));
+    }
+
+    #[test]
+    fn test_hotbar_and_offhand_swaps() {
+        let mut inventory = inventory_with(&[(9, stack(STONE, 1)), (37, stack(DIRT, 2))]);
+        assert!(inventory.click(9, 1, ClickMode::Swap));
+        assert_eq!(inventory.slots[9], stack(DIRT, 2));
+        assert_eq!(inventory.slots[37], stack(STONE, 1));
+
+        assert!(inventory.click(9, OFFHAND_SWAP_BUTTON, ClickMode::Swap));
+        assert_eq!(inventory.slots[OFFHAND], stack(DIRT, 2));
+        assert!(!inventory.click(9, 9, ClickMode::Swap));
+    }
+
+    #[test]
+    fn test_throw_and_double_click() {
+        let mut inventory = inventory_with(&[(9, stack(STONE, 3)), (10, stack(STONE, 64))]);
+        assert!(inventory.click(9, 0, ClickMode::Throw));
+        assert_eq!(inventory.slots[9], stack(STONE, 2));
+
+        inventory.cursor = stack(STONE, 1);
+        assert!(inventory.click(11, 0, ClickMode::PickupAll));
+        // Partial stacks are gathered before full ones.
+        assert_eq!(inventory.cursor, stack(STONE, 64));
+        assert!(inventory.slots[9].is_empty());
+        assert_eq!(inventory.slots[10], stack(STONE, 3));
+    }
+
+    #[test]
+    fn test_invalid_clicks_change_nothing() {
+        let mut inventory = inventory_with(&[(9, stack(STONE, 3))]);
+        assert!(!inventory.click(46, 0, ClickMode::Pickup));
+        assert!(!inventory.click(9, 2, ClickMode::Pickup));
+        assert!(!inventory.click(CRAFTING_OUTPUT as i16, 0, ClickMode::Pickup));
+        assert!(!inventory.click(9, 0, ClickMode::QuickCraft));
+        assert_eq!(inventory.slots[9], stack(STONE, 3));
+        assert!(inventory.cursor.is_empty());
+    }
+
+    #[test]
+    fn test_matches_client_view() {
+        let mut inventory = inventory_with(&[(9, stack(STONE, 3))]);
+        inventory.click(9, 0, ClickMode::Pickup);
+        assert!(inventory.matches(&[(9, Slot::EMPTY)], &stack(STONE, 3)));
+        assert!(!inventory.matches(&[(9, stack(STONE, 3))], &Slot::EMPTY));
+        assert!(!inventory.matches(&[(99, Slot::EMPTY)], &stack(STONE, 3)));
+    }
+
+    #[test]
+    fn test_close_returns_crafting_grid() {
+        let mut inventory = inventory_with(&[(1, stack(STONE, 3)), (9, stack(STONE, 1))]);
+        inventory.cursor = stack(DIRT, 1);
+        inventory.close();
+        assert_eq!(inventory.slots[9], stack(STONE, 4));
+        assert!(inventory.slots[1].is_empty());
+        assert!(inventory.cursor.is_empty());
+    }
+}
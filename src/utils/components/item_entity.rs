@@ -0,0 +1,70 @@
+use ferrumc_macros::Component;
+
+use crate::utils::encoding::slot::ItemStack;
+
+/// How many ticks an item lies around for before it despawns, 5 minutes like vanilla.
+pub const DESPAWN_AFTER: u32 = 6000;
+/// How many ticks items dropped by blocks can't be picked up for, same as vanilla.
+pub const BLOCK_DROP_PICKUP_DELAY: u32 = 10;
+/// How many ticks items thrown by a player can't be picked up for, so they don't go straight
+/// back into the inventory they came from. Same as vanilla.
+pub const THROWN_PICKUP_DELAY: u32 = 40;
+
+/// An item lying around in the world, which players pick up by walking into it. See
+/// [crate::net::utils::dropped_items].
+///
+/// - `uuid`: Sent to clients when it spawns. Nothing else refers to it by it.
+/// - `stack`: The items it's made of.
+/// - `age`: How many ticks it's been around for.
+/// - `pickup_delay`: How many more ticks it has to lie there before it can be picked up.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct ItemEntity {
+    pub uuid: u128,
+    pub stack: ItemStack,
+    pub age: u32,
+    pub pickup_delay: u32,
+}
+
+impl ItemEntity {
+    pub fn new(uuid: u128, stack: ItemStack, pickup_delay: u32) -> Self {
+        Self {
+            uuid,
+            stack,
+            age: 0,
+            pickup_delay,
+        }
+    }
+
+    /// Ages the item by a tick. Returns `true` once it's old enough to despawn.
+    pub fn tick(&mut self) -> bool {
+        self.age = self.age.saturating_add(1);
+        self.pickup_delay = self.pickup_delay.saturating_sub(1);
+        self.age >= DESPAWN_AFTER
+    }
+
+    pub fn can_be_picked_up(&self) -> bool {
+        self.pickup_delay == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_items_despawn_after_five_minutes() {
+        let mut item = ItemEntity::new(1, ItemStack::new(1, 1), THROWN_PICKUP_DELAY);
+        for _ in 0..THROWN_PICKUP_DELAY - 1 {
+            assert!(!item.tick());
+        }
+        assert!(!item.can_be_picked_up());
+        item.tick();
+        assert!(item.can_be_picked_up());
+
+        for _ in THROWN_PICKUP_DELAY..DESPAWN_AFTER - 1 {
+            assert!(!item.tick());
+        }
+        assert!(item.tick());
+        assert_eq!(item.age, 6000);
+    }
+}
@@ -1,8 +1,106 @@
-use ferrumc_macros::{Component, Constructor};
+use std::time::Instant;
 
-#[derive(Component, Constructor, Debug, Clone)]
+use ferrumc_macros::Component;
+
+/// How many wrong or unsolicited keep alive responses a player gets before being kicked.
+pub const MAX_KEEP_ALIVE_STRIKES: u8 = 3;
+
+/// Keep alive bookkeeping for a player.
+///
+/// - `last_received`: When the client last answered a keep alive.
+/// - `last_sent`: When the last keep alive was sent.
+/// - `data`: The id of the last keep alive sent.
+/// - `ping_ms`: The round trip time of the last answered keep alive, in milliseconds.
+/// - `strikes`: How many wrong or unsolicited responses the client has sent.
+/// - `awaiting_response`: Whether the last keep alive still has to be answered.
+#[derive(Component, Debug, Clone)]
 pub struct KeepAlive {
-    pub last_received: std::time::Instant,
-    pub last_sent: std::time::Instant,
+    pub last_received: Instant,
+    pub last_sent: Instant,
     pub data: i64,
+    pub ping_ms: u32,
+    pub strikes: u8,
+    pub awaiting_response: bool,
+}
+
+impl KeepAlive {
+    /// Creates the component for a keep alive with id `data` that's being sent right now.
+    pub fn new(data: i64) -> Self {
+        let now = Instant::now();
+        Self {
+            last_received: now,
+            last_sent: now,
+            data,
+            ping_ms: 0,
+            strikes: 0,
+            awaiting_response: true,
+        }
+    }
+
+    /// Records that a new keep alive with id `data` is being sent.
+    pub fn send(&mut self, data: i64) {
+        self.data = data;
+        self.last_sent = Instant::now();
+        self.awaiting_response = true;
+    }
+
+    /// Records the client's response to a keep alive, updating the ping.
+    ///
+    /// Returns `false` and adds a strike if `id` doesn't answer the outstanding keep alive.
+    pub fn receive(&mut self, id: i64) -> bool {
+        self.receive_at(id, Instant::now())
+    }
+
+    fn receive_at(&mut self, id: i64, now: Instant) -> bool {
+        if !self.awaiting_response || id != self.data {
+            self.strikes = self.strikes.saturating_add(1);
+            return false;
+        }
+
+        self.awaiting_response = false;
+        self.last_received = now;
+        self.ping_ms = now
+            .saturating_duration_since(self.last_sent)
+            .as_millis()
+            .try_into()
+            .unwrap_or(u32::MAX);
+        true
+    }
+
+    pub fn is_struck_out(&self) -> bool {
+        self.strikes >= MAX_KEEP_ALIVE_STRIKES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_matching_response_records_ping() {
+        let mut keep_alive = KeepAlive::new(42);
+        let sent = keep_alive.last_sent;
+
+        assert!(keep_alive.receive_at(42, sent + Duration::from_millis(75)));
+        assert_eq!(keep_alive.ping_ms, 75);
+        assert!(!keep_alive.awaiting_response);
+        assert_eq!(keep_alive.strikes, 0);
+    }
+
+    #[test]
+    fn test_wrong_and_unsolicited_responses_are_strikes() {
+        let mut keep_alive = KeepAlive::new(42);
+        let sent = keep_alive.last_sent;
+
+        assert!(!keep_alive.receive_at(7, sent));
+        assert!(keep_alive.receive_at(42, sent));
+        // Already answered, so the same id again is unsolicited.
+        assert!(!keep_alive.receive_at(42, sent));
+        assert!(!keep_alive.is_struck_out());
+
+        assert!(!keep_alive.receive_at(42, sent));
+        assert!(keep_alive.is_struck_out());
+    }
 }
@@ -0,0 +1,34 @@
+use ferrumc_macros::Component;
+
+use crate::utils::components::rotation::Rotation;
+use crate::utils::encoding::angle::Angle;
+use crate::utils::encoding::position::Position;
+
+/// Where other players were last told an entity is and which way it's facing, so they're only
+/// sent what changed since.
+///
+/// Players that start seeing the entity are spawned it here too, so the moves sent after that
+/// line up with where they think it is.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct LastSentMovement {
+    pub position: (f64, f64, f64),
+    pub yaw: Angle,
+    pub pitch: Angle,
+}
+
+impl LastSentMovement {
+    pub fn new(position: &Position, rotation: &Rotation) -> Self {
+        let (x, y, z) = (position.x as f64, position.y as f64, position.z as f64);
+        Self::exact((x, y, z), rotation)
+    }
+
+    /// At exactly `position`, for entities that are tracked more closely than the block they're
+    /// in, see [crate::utils::components::physics_body::PhysicsBody].
+    pub fn exact(position: (f64, f64, f64), rotation: &Rotation) -> Self {
+        Self {
+            position,
+            yaw: Angle::from_degrees(rotation.yaw),
+            pitch: Angle::from_degrees(rotation.pitch),
+        }
+    }
+}
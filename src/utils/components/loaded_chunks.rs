@@ -0,0 +1,211 @@
+use std::collections::HashSet;
+
+use ferrumc_macros::Component;
+
+/// The chunks a player's client has been sent and hasn't been told to unload yet.
+///
+/// - `center`: The chunk the player was in when chunks were last sent, or `None` if they haven't
+///   been sent any yet.
+/// - `chunks`: The x and z of every chunk the client has.
+#[derive(Component, Debug, Clone, Default)]
+pub struct LoadedChunks {
+    pub center: Option<(i32, i32)>,
+    pub chunks: HashSet<(i32, i32)>,
+}
+
+/// Whether `chunk` is within `view_distance` chunks of `center`. Views are squares, same as
+/// vanilla, so the corners count too.
+pub fn is_in_view(center: (i32, i32), chunk: (i32, i32), view_distance: i32) -> bool {
+    chebyshev_distance(center, chunk) <= view_distance
+}
+
+/// How many chunks apart `a` and `b` are, counting diagonal steps as one.
+pub fn chebyshev_distance(a: (i32, i32), b: (i32, i32)) -> i32 {
+    (a.0 - b.0).abs().max((a.1 - b.1).abs())
+}
+
+/// Every chunk within `view_distance` of `center`, spiralling out from it: the center, then each
+/// ring around it in turn, so the closest chunks come first and each one is next to the last.
+pub fn spiral(center: (i32, i32), view_distance: i32) -> Vec<(i32, i32)> {
+    let (x, z) = center;
+    let side = 2 * view_distance.max(0) as usize + 1;
+    let mut chunks = Vec::with_capacity(side * side);
+    chunks.push(center);
+    for ring in 1..=view_distance {
+        // Each side of the ring starts one past the corner the last side ended on.
+        chunks.extend((0..2 * ring).map(|i| (x + ring, z - ring + 1 + i)));
+        chunks.extend((0..2 * ring).map(|i| (x + ring - 1 - i, z + ring)));
+        chunks.extend((0..2 * ring).map(|i| (x - ring, z + ring - 1 - i)));
+        chunks.extend((0..2 * ring).map(|i| (x - ring + 1 + i, z - ring)));
+    }
+    chunks
+}
+
+impl LoadedChunks {
+    /// Moves the center to the chunk the player is in now. Returns `false` if they were already
+    /// in it, so nothing needs to be sent.
+    pub fn move_center(&mut self, center: (i32, i32)) -> bool {
+        self.center.replace(center) != Some(center)
+    }
+
+    pub fn contains(&self, chunk: (i32, i32)) -> bool {
+        self.chunks.contains(&chunk)
+    }
+
+    /// Returns the chunks in view from `center` that the client doesn't have yet, in [spiral]
+    /// order so the closest come first.
+    pub fn missing(&self, center: (i32, i32), view_distance: i32) -> Vec<(i32, i32)> {
+        spiral(center, view_distance)
+            .into_iter()
+            .filter(|chunk| !self.chunks.contains(chunk))
+            .collect()
+    }
+
+    /// Forgets the chunks that aren't in view from `center` anymore, and returns them so they can
+    /// be unloaded.
+    pub fn remove_out_of_view(
+        &mut self,
+        center: (i32, i32),
+        view_distance: i32,
+    ) -> Vec<(i32, i32)> {
+        let out_of_view = self
+            .chunks
+            .iter()
+            .copied()
+            .filter(|&chunk| !is_in_view(center, chunk, view_distance))
+            .collect::<Vec<_>>();
+        for chunk in &out_of_view {
+            self.chunks.remove(chunk);
+        }
+        out_of_view
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut chunks: Vec<(i32, i32)>) -> Vec<(i32, i32)> {
+        chunks.sort();
+        chunks
+    }
+
+    fn loaded_around(center: (i32, i32), view_distance: i32) -> LoadedChunks {
+        let mut loaded = LoadedChunks::default();
+        let missing = loaded.missing(center, view_distance);
+        loaded.chunks.extend(missing);
+        loaded.center = Some(center);
+        loaded
+    }
+
+    #[test]
+    fn test_view_includes_the_boundary() {
+        assert!(is_in_view((0, 0), (2, -2), 2));
+        assert!(is_in_view((5, 5), (3, 7), 2));
+        assert!(!is_in_view((0, 0), (3, 0), 2));
+        assert!(!is_in_view((0, 0), (0, -3), 2));
+
+        let loaded = loaded_around((0, 0), 2);
+        assert_eq!(loaded.chunks.len(), 25);
+        assert!(loaded.missing((0, 0), 2).is_empty());
+    }
+
+    #[test]
+    fn test_center_only_moves_between_chunks() {
+        let mut loaded = LoadedChunks::default();
+        assert!(loaded.move_center((0, 0)));
+        assert!(!loaded.move_center((0, 0)));
+        assert!(loaded.move_center((0, -1)));
+        assert_eq!(loaded.center, Some((0, -1)));
+    }
+
+    #[test]
+    fn test_moving_one_chunk_swaps_an_edge() {
+        let mut loaded = loaded_around((0, 0), 2);
+
+        let unloaded = loaded.remove_out_of_view((1, 0), 2);
+        assert_eq!(
+            sorted(unloaded),
+            vec![(-2, -2), (-2, -1), (-2, 0), (-2, 1), (-2, 2)]
+        );
+        let missing = loaded.missing((1, 0), 2);
+        assert_eq!(
+            sorted(missing),
+            vec![(3, -2), (3, -1), (3, 0), (3, 1), (3, 2)]
+        );
+        // The chunks on the new boundary were already there.
+        assert!(loaded.contains((-1, 2)));
+        assert!(loaded.contains((2, -2)));
+    }
+
+    #[test]
+    fn test_moving_diagonally_swaps_two_edges() {
+        let mut loaded = loaded_around((0, 0), 2);
+
+        let unloaded = loaded.remove_out_of_view((1, 1), 2);
+        // One row and one column, sharing a corner.
+        assert_eq!(unloaded.len(), 9);
+        assert!(unloaded.iter().all(|&(x, z)| x == -2 || z == -2));
+        assert_eq!(loaded.missing((1, 1), 2).len(), 9);
+    }
+
+    #[test]
+    fn test_shrinking_the_view_unloads_the_outer_ring() {
+        let mut loaded = loaded_around((0, 0), 2);
+
+        let unloaded = loaded.remove_out_of_view((0, 0), 1);
+        assert_eq!(unloaded.len(), 16);
+        assert_eq!(loaded.chunks.len(), 9);
+        assert!(loaded.missing((0, 0), 1).is_empty());
+    }
+
+    #[test]
+    fn test_spiral_covers_the_view_once() {
+        assert_eq!(spiral((3, -7), 0), vec![(3, -7)]);
+        assert_eq!(spiral((0, 0), -1), vec![(0, 0)]);
+
+        let chunks = spiral((3, -7), 4);
+        assert_eq!(chunks.len(), 81);
+        assert_eq!(chunks.iter().collect::<HashSet<_>>().len(), 81);
+        assert!(chunks.iter().all(|&chunk| is_in_view((3, -7), chunk, 4)));
+    }
+
+    #[test]
+    fn test_spiral_goes_out_one_step_at_a_time() {
+        let chunks = spiral((0, 0), 3);
+        assert_eq!(&chunks[..3], &[(0, 0), (1, 0), (1, 1)]);
+        // Every chunk is next to the one before it, even going from one ring to the next.
+        for pair in chunks.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            assert!(chebyshev_distance((0, 0), from) <= chebyshev_distance((0, 0), to));
+            assert_eq!(chebyshev_distance(from, to), 1);
+        }
+    }
+
+    #[test]
+    fn test_going_back_only_sends_what_was_unloaded() {
+        let mut loaded = loaded_around((0, 0), 2);
+
+        // Teleported three chunks over and back, e.g. by a command.
+        let unloaded = loaded.remove_out_of_view((3, 0), 2);
+        assert_eq!(unloaded.len(), 15);
+        let missing = loaded.missing((3, 0), 2);
+        assert_eq!(missing.len(), 15);
+        loaded.chunks.extend(missing);
+        loaded.remove_out_of_view((0, 0), 2);
+        assert_eq!(sorted(loaded.missing((0, 0), 2)), sorted(unloaded));
+
+        // Respawning where they already were sends nothing.
+        let mut loaded = loaded_around((0, 0), 2);
+        assert!(loaded.remove_out_of_view((0, 0), 2).is_empty());
+        assert!(loaded.missing((0, 0), 2).is_empty());
+    }
+
+    #[test]
+    fn test_missing_chunks_are_closest_first() {
+        let loaded = LoadedChunks::default();
+        let missing = loaded.missing((4, -4), 1);
+        assert_eq!(missing[0], (4, -4));
+        assert_eq!(missing.len(), 9);
+    }
+}
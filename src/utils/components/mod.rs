@@ -1,5 +1,32 @@
+pub mod active_effects;
+pub mod boss_bars;
+pub mod equipment;
+pub mod exact_position;
+pub mod experience;
+pub mod flying;
+pub mod food;
+pub mod game_mode;
 pub mod grounded;
+pub mod health;
+pub mod held_item;
+pub mod inventory;
+pub mod item_entity;
+pub mod client_settings;
+pub mod digging;
+pub mod dimension;
 pub mod keep_alive;
-pub mod last_chunk_tx_pos;
+pub mod last_sent_movement;
+pub mod loaded_chunks;
+pub mod network_id;
+pub mod pending_teleports;
+pub mod physics_body;
 pub mod player;
+pub mod profile_properties;
+pub mod resource_pack;
 pub mod rotation;
+pub mod scoreboards;
+pub mod sneaking;
+pub mod sprinting;
+pub mod velocity;
+pub mod violations;
+pub mod visible_entities;
@@ -0,0 +1,8 @@
+use ferrumc_macros::{Component, Constructor};
+
+/// The id the entity is known by on clients, given out by
+/// [EntityIdAllocator](crate::net::utils::entity_ids::EntityIdAllocator) when it spawns.
+#[derive(Debug, Clone, Copy, Component, Constructor)]
+pub struct NetworkId {
+    pub id: i32,
+}
@@ -0,0 +1,81 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use ferrumc_macros::Component;
+
+/// The teleports sent to a player that the client hasn't confirmed yet.
+///
+/// Every [SynchronizePlayerPosition](crate::net::packets::outgoing::synchronize_player_position::SynchronizePlayerPosition)
+/// gets the next id, and the client answers with a Confirm Teleportation for it once it's moved.
+/// Until then, any position it sends is from before the teleport.
+///
+/// - `next_id`: The id the next teleport gets.
+/// - `pending`: The ids that haven't been confirmed yet, oldest first.
+/// - `last_sent`: When the last teleport was sent.
+#[derive(Component, Debug, Clone)]
+pub struct PendingTeleports {
+    pub next_id: i32,
+    pub pending: VecDeque<i32>,
+    pub last_sent: Instant,
+}
+
+impl Default for PendingTeleports {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            pending: VecDeque::new(),
+            last_sent: Instant::now(),
+        }
+    }
+}
+
+impl PendingTeleports {
+    /// Records a teleport that's being sent right now, and returns its id.
+    pub fn start(&mut self) -> i32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.pending.push_back(id);
+        self.last_sent = Instant::now();
+        id
+    }
+
+    /// Returns `false` if no teleport with this id is waiting to be confirmed.
+    ///
+    /// Confirming a teleport also confirms every one sent before it, since the client only
+    /// ends up at the last place it was sent to.
+    pub fn confirm(&mut self, id: i32) -> bool {
+        let Some(index) = self.pending.iter().position(|&pending| pending == id) else {
+            return false;
+        };
+        self.pending.drain(..=index);
+        true
+    }
+
+    pub fn is_awaiting_confirmation(&self) -> bool {
+        !self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_teleports_are_confirmed() {
+        let mut teleports = PendingTeleports::default();
+        assert!(!teleports.is_awaiting_confirmation());
+
+        let first = teleports.start();
+        let second = teleports.start();
+        let third = teleports.start();
+        assert_eq!((first, second, third), (0, 1, 2));
+        assert!(teleports.is_awaiting_confirmation());
+
+        assert!(!teleports.confirm(7));
+        assert!(teleports.confirm(second));
+        assert_eq!(teleports.pending, [third]);
+        assert!(!teleports.confirm(first));
+        assert!(teleports.confirm(third));
+        assert!(!teleports.is_awaiting_confirmation());
+    }
+}
@@ -0,0 +1,35 @@
+use ferrumc_macros::Component;
+
+/// An entity that falls and bumps into blocks, moved every tick by its
+/// [crate::utils::components::velocity::Velocity]. See [crate::world::physics::step].
+///
+/// - `position`: Exactly where the entity is, at the middle of the bottom of its hitbox. Its
+///   [crate::utils::encoding::position::Position] is kept as the block this is in.
+/// - `width`, `height`: The size of its hitbox, which is as wide as it is deep.
+/// - `gravity`: How much faster it falls every tick.
+/// - `drag`: How much of its velocity it keeps every tick.
+/// - `on_ground`: Whether it's standing on a block.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsBody {
+    pub position: (f64, f64, f64),
+    pub width: f64,
+    pub height: f64,
+    pub gravity: f64,
+    pub drag: f64,
+    pub on_ground: bool,
+}
+
+impl PhysicsBody {
+    /// A body at `position` with vanilla's item physics: 0.25 blocks across, and falling with
+    /// 0.04 blocks per tick of gravity.
+    pub fn item(position: (f64, f64, f64)) -> Self {
+        Self {
+            position,
+            width: 0.25,
+            height: 0.25,
+            gravity: 0.04,
+            drag: 0.98,
+            on_ground: false,
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use ferrumc_macros::{Component, Constructor};
+
+use crate::net::utils::authentication::ProfileProperty;
+
+/// The properties of the player's profile as they were sent in
+/// [LoginSuccess](crate::net::packets::outgoing::login_success::LoginSuccess), so other players
+/// see the same skin in their player list.
+#[derive(Debug, Default, Component, Constructor)]
+pub struct ProfileProperties {
+    pub properties: Vec<ProfileProperty>,
+}
@@ -0,0 +1,57 @@
+use ferrumc_macros::Component;
+
+/// Where the player is with the server's resource pack. Set from the Resource Pack Response
+/// packet.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Component)]
+pub enum ResourcePackStatus {
+    /// The server doesn't have a resource pack, so it wasn't sent.
+    #[default]
+    NotSent,
+    /// The pack was sent, but the player hasn't answered yet.
+    Pending,
+    /// The player accepted the pack, and it's downloading.
+    Accepted,
+    Declined,
+    Loaded,
+    FailedDownload,
+}
+
+impl ResourcePackStatus {
+    /// The status for a result sent in the Resource Pack Response packet. `None` if it isn't
+    /// one of them.
+    pub fn from_result(result: i32) -> Option<Self> {
+        match result {
+            0 => Some(Self::Loaded),
+            1 => Some(Self::Declined),
+            2 => Some(Self::FailedDownload),
+            3 => Some(Self::Accepted),
+            _ => None,
+        }
+    }
+
+    /// Whether the player ended up without the pack.
+    pub fn is_rejected(self) -> bool {
+        matches!(self, Self::Declined | Self::FailedDownload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results() {
+        assert_eq!(
+            ResourcePackStatus::from_result(0),
+            Some(ResourcePackStatus::Loaded)
+        );
+        assert_eq!(
+            ResourcePackStatus::from_result(3),
+            Some(ResourcePackStatus::Accepted)
+        );
+        assert_eq!(ResourcePackStatus::from_result(4), None);
+        assert!(ResourcePackStatus::Declined.is_rejected());
+        assert!(ResourcePackStatus::FailedDownload.is_rejected());
+        assert!(!ResourcePackStatus::Accepted.is_rejected());
+    }
+}
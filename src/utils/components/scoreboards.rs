@@ -0,0 +1,10 @@
+use std::collections::HashSet;
+
+use ferrumc_macros::Component;
+
+/// The ids of the [Scoreboard](crate::utils::scoreboard::Scoreboard)s a player's client is
+/// showing. Like [ShownBossBars](super::boss_bars::ShownBossBars), it goes away with the player.
+#[derive(Component, Debug, Clone, Default)]
+pub struct ShownScoreboards {
+    pub boards: HashSet<u64>,
+}
@@ -0,0 +1,13 @@
+use ferrumc_macros::{Component, Constructor, Getter};
+
+/// Whether the player is crouching. Set from the Player Command packet.
+#[derive(Debug, Default, Component, Getter, Constructor)]
+pub struct Sneaking {
+    pub is_sneaking: bool,
+}
+
+impl Sneaking {
+    pub fn set_sneaking(&mut self, is_sneaking: bool) {
+        self.is_sneaking = is_sneaking;
+    }
+}
@@ -0,0 +1,13 @@
+use ferrumc_macros::{Component, Constructor, Getter};
+
+/// Whether the player is sprinting. Set from the Player Command packet.
+#[derive(Debug, Default, Component, Getter, Constructor)]
+pub struct Sprinting {
+    pub is_sprinting: bool,
+}
+
+impl Sprinting {
+    pub fn set_sprinting(&mut self, is_sprinting: bool) {
+        self.is_sprinting = is_sprinting;
+    }
+}
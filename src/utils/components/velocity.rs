@@ -0,0 +1,23 @@
+use ferrumc_macros::Component;
+
+/// How fast an entity is moving, in blocks per tick.
+///
+/// Entities with a [crate::utils::components::physics_body::PhysicsBody] are moved by it every
+/// tick, see [crate::net::systems::physics_system]. Players move themselves, so theirs is only
+/// ever sent to them, see [crate::state::ServerState::apply_knockback].
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Velocity {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Velocity {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.x == 0.0 && self.y == 0.0 && self.z == 0.0
+    }
+}
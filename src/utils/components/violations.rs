@@ -0,0 +1,192 @@
+use ferrumc_macros::Component;
+
+use crate::utils::components::velocity::Velocity;
+
+/// How many moves after the server sends a player a velocity, e.g. knockback, can go further by
+/// it. It takes them about this long to slow down again.
+pub const VELOCITY_MOVES: u32 = 10;
+/// How many ticks' worth of moving a player can catch up on in one tick, after ticks they didn't
+/// move in. Lagging clients send several ticks of moves at once.
+pub const MAX_CATCH_UP_TICKS: u64 = 5;
+
+/// How a player's moves have gone through [crate::net::utils::anti_cheat].
+///
+/// - `count`: How many of their moves were turned down, less the ones forgiven since.
+/// - `passed_in_a_row`: How many moves in a row have passed since the last violation was added
+///   or forgiven.
+/// - `ascending_moves`: How many moves in a row they've gone up, or stayed level, without
+///   touching the ground.
+/// - `tick`, `tick_start`, `tick_budget`: The server tick they last moved on, where they were
+///   before their first move on it, and how many ticks' worth of moving that tick gets, see
+///   [Violations::start_move].
+/// - `sent_velocity`: The last velocity the server sent them, see [Violations::sent_velocity].
+/// - `velocity_moves`: How many more of their moves can go further by it.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Violations {
+    pub count: u32,
+    pub passed_in_a_row: u32,
+    pub ascending_moves: u32,
+    pub tick: u64,
+    pub tick_start: Option<(f64, f64, f64)>,
+    pub tick_budget: u64,
+    pub sent_velocity: Velocity,
+    pub velocity_moves: u32,
+}
+
+impl Violations {
+    /// Records a move that was turned down, and returns how many violations there are now.
+    pub fn add(&mut self) -> u32 {
+        self.count = self.count.saturating_add(1);
+        self.passed_in_a_row = 0;
+        self.count
+    }
+
+    /// Records a move that passed. Every `forgive_after` of them in a row forgive a violation, or
+    /// none do if it's 0.
+    pub fn pass(&mut self, ascending_moves: u32, forgive_after: u32) {
+        self.ascending_moves = ascending_moves;
+        if self.count == 0 || forgive_after == 0 {
+            return;
+        }
+        self.passed_in_a_row += 1;
+        if self.passed_in_a_row >= forgive_after {
+            self.count -= 1;
+            self.passed_in_a_row = 0;
+        }
+    }
+
+    /// Lets the player's next [VELOCITY_MOVES] moves go as much further as `velocity`, which the
+    /// server just sent them, e.g. knockback.
+    pub fn sent_velocity(&mut self, velocity: Velocity) {
+        self.sent_velocity = velocity;
+        self.velocity_moves = VELOCITY_MOVES;
+    }
+
+    /// How much further than usual the move that's being checked can go, see
+    /// [Violations::sent_velocity]. Counts it as one of the moves that can.
+    pub fn take_velocity(&mut self) -> Velocity {
+        if self.velocity_moves == 0 {
+            return Velocity::default();
+        }
+        self.velocity_moves -= 1;
+        self.sent_velocity
+    }
+
+    /// Where a move on `tick` from `from` is checked from, and how many ticks' worth of moving it
+    /// can add up to.
+    ///
+    /// Every move on the same tick is checked from where the player was before the first one, so
+    /// sending more of them doesn't get the player any further. The first move after ticks
+    /// without any gets to make up for them, up to [MAX_CATCH_UP_TICKS].
+    pub fn start_move(&mut self, tick: u64, from: (f64, f64, f64)) -> ((f64, f64, f64), u64) {
+        match self.tick_start {
+            Some(start) if tick == self.tick => return (start, self.tick_budget),
+            Some(_) => {
+                self.tick_budget = tick.saturating_sub(self.tick).clamp(1, MAX_CATCH_UP_TICKS)
+            }
+            None => self.tick_budget = 1,
+        }
+        self.tick = tick;
+        self.tick_start = Some(from);
+        (from, self.tick_budget)
+    }
+
+    /// Starts checking the player's moves over, after they're teleported.
+    pub fn teleported(&mut self) {
+        self.ascending_moves = 0;
+        self.tick_start = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations_are_forgiven_slowly() {
+        let mut violations = Violations::default();
+        assert_eq!(violations.add(), 1);
+        assert_eq!(violations.add(), 2);
+
+        for _ in 0..2 {
+            violations.pass(0, 3);
+        }
+        assert_eq!(violations.count, 2);
+        // Another violation starts the count over.
+        violations.add();
+        for _ in 0..3 {
+            violations.pass(0, 3);
+        }
+        assert_eq!(violations.count, 2);
+        for _ in 0..9 {
+            violations.pass(0, 3);
+        }
+        assert_eq!(violations.count, 0);
+
+        // 0 never forgives anything.
+        violations.add();
+        for _ in 0..10 {
+            violations.pass(0, 0);
+        }
+        assert_eq!(violations.count, 1);
+    }
+
+    #[test]
+    fn test_teleporting_starts_the_checks_over() {
+        let mut violations = Violations::default();
+        violations.pass(4, 0);
+        assert_eq!(violations.ascending_moves, 4);
+
+        violations.start_move(7, (0.5, 64.0, 0.5));
+        violations.teleported();
+        assert_eq!(violations.ascending_moves, 0);
+        assert_eq!(
+            violations.start_move(7, (9.5, 64.0, 0.5)),
+            ((9.5, 64.0, 0.5), 1)
+        );
+    }
+
+    #[test]
+    fn test_moves_on_the_same_tick_add_up() {
+        let mut violations = Violations::default();
+        assert_eq!(
+            violations.start_move(10, (0.5, 64.0, 0.5)),
+            ((0.5, 64.0, 0.5), 1)
+        );
+        // More moves on the same tick are checked from the same place.
+        assert_eq!(
+            violations.start_move(10, (1.5, 64.0, 0.5)),
+            ((0.5, 64.0, 0.5), 1)
+        );
+        assert_eq!(
+            violations.start_move(11, (1.5, 64.0, 0.5)),
+            ((1.5, 64.0, 0.5), 1)
+        );
+        // Ticks without moves can be made up for, but only a few.
+        assert_eq!(
+            violations.start_move(14, (2.5, 64.0, 0.5)),
+            ((2.5, 64.0, 0.5), 3)
+        );
+        assert_eq!(
+            violations.start_move(14, (3.5, 64.0, 0.5)),
+            ((2.5, 64.0, 0.5), 3)
+        );
+        assert_eq!(
+            violations.start_move(100, (3.5, 64.0, 0.5)).1,
+            MAX_CATCH_UP_TICKS
+        );
+    }
+
+    #[test]
+    fn test_sent_velocity_lasts_a_few_moves() {
+        let mut violations = Violations::default();
+        assert_eq!(violations.take_velocity(), Velocity::default());
+
+        let knockback = Velocity::new(0.9, 0.4, 0.0);
+        violations.sent_velocity(knockback);
+        for _ in 0..VELOCITY_MOVES {
+            assert_eq!(violations.take_velocity(), knockback);
+        }
+        assert_eq!(violations.take_velocity(), Velocity::default());
+    }
+}
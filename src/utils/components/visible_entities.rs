@@ -0,0 +1,53 @@
+use std::collections::HashSet;
+
+use ferrumc_macros::Component;
+
+/// The entities a player's client has been sent, and hasn't been told to remove yet. Spawning
+/// an entity the client already has would show it twice.
+#[derive(Component, Debug, Clone, Default)]
+pub struct VisibleEntities {
+    pub entities: HashSet<usize>,
+}
+
+impl VisibleEntities {
+    pub fn contains(&self, entity_id: usize) -> bool {
+        self.entities.contains(&entity_id)
+    }
+
+    /// Makes the visible entities match `in_view`. Returns the ones that have to be spawned, and
+    /// the ones that have to be removed.
+    pub fn update(&mut self, in_view: &HashSet<usize>) -> (Vec<usize>, Vec<usize>) {
+        let spawned = in_view
+            .difference(&self.entities)
+            .copied()
+            .collect::<Vec<_>>();
+        let removed = self
+            .entities
+            .difference(in_view)
+            .copied()
+            .collect::<Vec<_>>();
+        self.entities.clone_from(in_view);
+        (spawned, removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entities_are_only_spawned_once() {
+        let mut visible = VisibleEntities::default();
+        let (mut spawned, removed) = visible.update(&HashSet::from([1, 2]));
+        spawned.sort();
+        assert_eq!((spawned, removed), (vec![1, 2], vec![]));
+
+        let (spawned, removed) = visible.update(&HashSet::from([2, 3]));
+        assert_eq!((spawned, removed), (vec![3], vec![1]));
+
+        let (spawned, removed) = visible.update(&HashSet::from([2, 3]));
+        assert!(spawned.is_empty() && removed.is_empty());
+        assert!(visible.contains(3));
+        assert!(!visible.contains(1));
+    }
+}
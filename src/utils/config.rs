@@ -1,32 +1,337 @@
 use std::io::ErrorKind::NotFound;
 use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::OnceLock;
 
 use crate::utils::constants::{
-    DEFAULT_CONFIG_FILE, DEFAULT_MAX_PLAYERS, DEFAULT_MOTD, DEFAULT_SERVER_HOST,
-    DEFAULT_SERVER_PORT,
+    DEFAULT_BIOME, DEFAULT_BORDER_DIAMETER, DEFAULT_CACHE_SIZE_MB, DEFAULT_END_BIOME, DEFAULT_END_LAYERS, DEFAULT_NETHER_BIOME,
+    DEFAULT_FLYING_MULTIPLIER, DEFAULT_FORGIVE_AFTER, DEFAULT_MAX_ASCENDING_MOVES,
+    DEFAULT_MAX_HORIZONTAL_SPEED, DEFAULT_MAX_VERTICAL_SPEED, DEFAULT_MAX_VIOLATIONS,
+    DEFAULT_NETHER_LAYERS, DEFAULT_COMPRESSION_THRESHOLD, DEFAULT_CONFIG_FILE, DEFAULT_FAVICON,
+    DEFAULT_KEEP_ALIVE_INTERVAL, DEFAULT_KEEP_ALIVE_TIMEOUT, DEFAULT_MAX_PACKETS_PER_SECOND,
+    DEFAULT_MAX_PACKET_SIZE, DEFAULT_MAX_PENDING_TELEPORTS, DEFAULT_MAX_PLAYERS, DEFAULT_MOTD, DEFAULT_OUTGOING_QUEUE_SIZE,
+    DEFAULT_OUTGOING_QUEUE_TIMEOUT, DEFAULT_SHUTDOWN_GRACE_PERIOD, DEFAULT_PACKET_BURST, DEFAULT_PING_TIMEOUT, DEFAULT_RCON_PORT, DEFAULT_RESOURCE_PACK_KICK_MESSAGE, DEFAULT_SERVER_BRAND, DEFAULT_SERVER_HOST,
+    DEFAULT_SAVE_INTERVAL, DEFAULT_SERVER_PORT, DEFAULT_SUPERFLAT_LAYERS, DEFAULT_TAB_LIST_REFRESH_INTERVAL, DEFAULT_TICK_RATE, DEFAULT_VIEW_DISTANCE,
 };
+use crate::net::packets::outgoing::player_abilities::{DEFAULT_FLYING_SPEED, DEFAULT_FOV_MODIFIER};
+use crate::utils::components::game_mode::GameMode;
 use crate::utils::error::Error;
+use crate::world::dimensions::{THE_END, THE_NETHER};
+use base64::Engine;
 use config::{Config, ConfigError};
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use crate::setup::BASE_CONFIG;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ServerConfig {
-    pub host: String,
+    pub host: BindAddresses,
     pub port: u32,
     pub motd: Vec<String>,
     pub max_players: i32,
+    pub view_distance: u32,
     pub network_tick_rate: u32,
+    pub tick_rate: u32,
+    pub network_compression_threshold: i32,
+    pub keep_alive_interval: u64,
+    pub keep_alive_timeout: u64,
+    pub shutdown_grace_period: u64,
     pub database: Database,
+    pub network: Network,
+    pub proxy: Proxy,
+    pub query: Query,
+    pub rcon: Rcon,
+    pub whitelist: Whitelist,
+    pub resource_pack: ResourcePack,
+    pub tab_list: TabList,
+    pub welcome_title: WelcomeTitle,
+    pub game_rules: GameRules,
+    pub abilities: Abilities,
+    pub anti_cheat: AntiCheat,
+    pub world_border: InitialWorldBorder,
+    pub world_generation: WorldGeneration,
+    pub dimensions: Vec<DimensionConfig>,
+    pub debug: DebugOptions,
     pub world: String,
+    pub favicon: String,
+    pub online_mode: bool,
+    pub fetch_skins: bool,
+    pub duplicate_login: DuplicateLoginPolicy,
+    pub lenient_usernames: bool,
+    pub allow_protocol_range: Option<ProtocolRange>,
+    pub brand: String,
+    pub default_gamemode: GameMode,
 }
 
+/// Protocol versions, inclusive, that can join on top of
+/// [crate::utils::constants::PROTOCOL_VERSION]. Only meant for testing other clients.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ProtocolRange {
+    pub min: i32,
+    pub max: i32,
+}
+
+/// What to do when a player logs in while they're already online.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateLoginPolicy {
+    /// Disconnect the session that's already online, like vanilla does.
+    #[default]
+    KickExisting,
+    /// Turn away the new login, and leave the existing session alone.
+    RejectNew,
+}
+
+/// The addresses to listen on, either just one (`host = "0.0.0.0"`) or a list of them
+/// (`host = ["0.0.0.0:25565", "[::]:25565"]`). Addresses without a port use [ServerConfig::port].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BindAddresses {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+/// - `cache_size_mb`: How much memory chunks can take before the least recently used ones are
+///   dropped, in megabytes.
+/// - `save_interval`: How often changed chunks and online players are saved to the database, in
+///   seconds.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Database {
-    pub cache_size: u32,
+    pub cache_size_mb: u32,
     pub compression: String,
+    pub save_interval: u64,
+}
+
+/// - `max_packets_per_second`: How many packets a client can send per second on average. 0 means no limit.
+/// - `packet_burst`: How many packets a client can send in a short burst above that rate.
+/// - `proxy_protocol`: Whether connections start with a PROXY protocol header from a load balancer.
+/// - `max_packet_size`: The largest packet a client can send in bytes, both as sent and once
+///   decompressed. Can't go above the protocol's limit of 2097151.
+/// - `outgoing_queue_size`: How many packets can be waiting to be sent to a client.
+/// - `outgoing_queue_timeout`: How long in seconds the queue can stay full before the client is disconnected.
+/// - `tcp_nodelay`: Whether to send small packets right away instead of batching them (Nagle's algorithm).
+/// - `so_reuseaddr`: Whether the port can be bound again right after a restart.
+/// - `tcp_keepalive`: Whether the OS should probe idle connections to notice dead ones.
+/// - `max_pending_teleports`: How many teleports a client can leave unconfirmed before it's kicked.
+///   0 means it's never kicked.
+/// - `ping_timeout`: How long in seconds to wait for a client to answer a ping, see
+///   [crate::state::ServerState::ping_player].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Network {
+    pub max_packets_per_second: u32,
+    pub packet_burst: u32,
+    pub proxy_protocol: bool,
+    pub max_packet_size: u32,
+    pub outgoing_queue_size: usize,
+    pub outgoing_queue_timeout: u64,
+    pub tcp_nodelay: bool,
+    pub so_reuseaddr: bool,
+    pub tcp_keepalive: bool,
+    pub max_pending_teleports: u32,
+    pub ping_timeout: u64,
+}
+
+/// - `bungeecord`: Whether to trust the player info BungeeCord forwards in the handshake.
+/// - `velocity_secret`: The secret for Velocity's modern forwarding. Empty turns it off.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Proxy {
+    pub bungeecord: bool,
+    pub velocity_secret: String,
+}
+
+/// - `enabled`: Whether to answer GameSpy4 queries over UDP.
+/// - `port`: The UDP port for queries. 0 uses the same port as the server.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Query {
+    pub enabled: bool,
+    pub port: u32,
+}
+
+/// - `enabled`: Whether to let admins run commands remotely over RCON.
+/// - `port`: The TCP port for RCON.
+/// - `password`: The password RCON clients have to log in with. RCON won't start without one.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Rcon {
+    pub enabled: bool,
+    pub port: u32,
+    pub password: String,
+}
+
+/// - `enabled`: Whether only players in `whitelist.json` can join.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Whitelist {
+    pub enabled: bool,
+}
+
+/// - `url`: Where clients download the resource pack from. Empty means no pack is sent.
+/// - `sha1`: The pack's SHA-1 hash as 40 hex digits, so clients can cache it. Can be left empty.
+/// - `required`: Whether players that decline the pack, or fail to load it, are kicked.
+/// - `prompt`: Shown to players when they're asked to use the pack. Empty uses the client's
+///   default message.
+/// - `kick_message`: What players kicked for not using a required pack are shown.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResourcePack {
+    pub url: String,
+    pub sha1: String,
+    pub required: bool,
+    pub prompt: String,
+    pub kick_message: String,
+}
+
+impl ResourcePack {
+    pub fn is_enabled(&self) -> bool {
+        !self.url.is_empty()
+    }
+
+    /// Checks that the hash, if there is one, is 40 hex digits. Clients don't check the pack
+    /// against a hash in any other format, they just fail to load it.
+    fn validate(&self) -> Result<(), Error> {
+        if !self.sha1.is_empty() && !is_sha1(&self.sha1) {
+            return Err(Error::Generic(format!(
+                "resource_pack.sha1 has to be 40 hex digits, not \"{}\"",
+                self.sha1
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn is_sha1(hash: &str) -> bool {
+    hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Shown above and below the player list. Both can use the placeholders in
+/// [crate::utils::placeholders::Placeholders].
+///
+/// - `header`: The text above the list. Empty shows nothing.
+/// - `footer`: The text below the list. Empty shows nothing.
+/// - `refresh_interval`: How often in seconds to send everyone the header and footer again, so
+///   the placeholders stay up to date.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TabList {
+    pub header: String,
+    pub footer: String,
+    pub refresh_interval: u64,
+}
+
+/// Shown to players in the middle of the screen when they join. Both texts can use the
+/// placeholders in [crate::utils::placeholders::Placeholders].
+///
+/// - `title`: The big text. Empty turns the welcome title off.
+/// - `subtitle`: The smaller text under it. Empty shows nothing.
+/// - `fade_in`, `stay` and `fade_out`: How long each part of showing it takes, in ticks.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WelcomeTitle {
+    pub title: String,
+    pub subtitle: String,
+    pub fade_in: i32,
+    pub stay: i32,
+    pub fade_out: i32,
+}
+
+/// - `do_daylight_cycle`: Whether the sun and moon move. With it off, the time stays wherever it
+///   was.
+/// - `do_weather_cycle`: Whether the weather changes by itself. With it off, it stays whatever it
+///   was until it's set, see [crate::state::ServerState::set_weather].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GameRules {
+    pub do_daylight_cycle: bool,
+    pub do_weather_cycle: bool,
+}
+
+/// - `allow_flight`: Whether players can fly in survival and adventure too. Can be changed
+///   while the server's running with [crate::state::ServerState::set_allow_flight].
+/// - `flying_speed`: How fast players fly.
+/// - `walking_speed`: How fast players walk, which the client also zooms the field of view by.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Abilities {
+    pub allow_flight: bool,
+    pub flying_speed: f32,
+    pub walking_speed: f32,
+}
+
+/// How players' movement is checked, see [crate::net::utils::anti_cheat]. Lag makes players that
+/// aren't cheating move in bursts too, so the defaults leave plenty of room.
+///
+/// - `enabled`: Whether movement is checked at all.
+/// - `max_horizontal_speed`: How far a player can move sideways in one tick, in blocks, before
+///   effects like Speed, however many moves they send in it. Sprint jumping is about 0.6.
+/// - `max_vertical_speed`: How far a player can go up in one tick, before Jump Boost. Jumps start
+///   at 0.42.
+/// - `flying_multiplier`: How many times further players that are flying can go.
+/// - `max_ascending_moves`: How many moves in a row a player that isn't flying can keep going up,
+///   or stay level, without touching the ground. Jumps go up for about 6.
+/// - `max_violations`: How many moves can be turned down before the player is kicked. 0 never
+///   kicks.
+/// - `forgive_after`: How many moves in a row have to pass for one violation to be forgiven. 0
+///   never forgives any.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AntiCheat {
+    pub enabled: bool,
+    pub max_horizontal_speed: f64,
+    pub max_vertical_speed: f64,
+    pub flying_multiplier: f64,
+    pub max_ascending_moves: u32,
+    pub max_violations: u32,
+    pub forgive_after: u32,
+}
+
+/// The world border new worlds start with. Worlds that have been saved keep theirs.
+///
+/// - `center_x` and `center_z`: Where the middle of the border is.
+/// - `diameter`: How wide the border is, in blocks.
+/// - `warning_blocks`: How close to the border players' screens start going red.
+/// - `warning_time`: How many seconds before a shrinking border reaches players their screens
+///   start going red.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InitialWorldBorder {
+    pub center_x: f64,
+    pub center_z: f64,
+    pub diameter: f64,
+    pub warning_blocks: i32,
+    pub warning_time: i32,
+}
+
+/// How chunks that aren't in the world yet are made, see
+/// [crate::world::generation::ChunkGenerator].
+///
+/// - `generator`: Which generator to use, `superflat` or `default` for hills and oceans.
+/// - `seed`: What the `default` generator's terrain comes from. The same seed always makes the
+///   same world.
+/// - `superflat_layers`: The layers of a superflat world, bottom first, like
+///   `bedrock,2xdirt,grass_block`.
+/// - `biome`: The biome generated chunks are in, which decides the color of grass and water.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorldGeneration {
+    pub generator: String,
+    pub seed: i64,
+    pub superflat_layers: String,
+    pub biome: String,
+}
+
+/// A dimension besides the overworld, see [crate::world::dimensions::Dimension].
+///
+/// - `name`: What it's called, like `minecraft:the_nether`. Players are moved between dimensions
+///   by name.
+/// - `dimension_type`: How clients draw it, one of
+///   [DIMENSION_TYPES](crate::world::dimensions::DIMENSION_TYPES).
+/// - `world_generation`: How its chunks are made, the same as the overworld's.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DimensionConfig {
+    pub name: String,
+    pub dimension_type: String,
+    pub world_generation: WorldGeneration,
+}
+
+/// - `packet_dump`: A directory to capture every packet sent and received in, one file per
+///   connection. Empty turns it off. See [crate::net::utils::packet_dump::PacketDump].
+/// - `tps_boss_bar`: Whether to show everyone the server's ticks per second in a boss bar.
+/// - `player_count_sidebar`: Whether to show everyone how many players are online in a sidebar.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DebugOptions {
+    pub packet_dump: String,
+    pub tps_boss_bar: bool,
+    pub player_count_sidebar: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,6 +340,18 @@ pub struct Server {
 }
 
 impl ServerConfig {
+    /// Every address in [ServerConfig::host], with the port filled in where it's missing.
+    pub fn bind_addresses(&self) -> Vec<String> {
+        let hosts = match &self.host {
+            BindAddresses::Single(host) => std::slice::from_ref(host),
+            BindAddresses::Multiple(hosts) => hosts.as_slice(),
+        };
+        hosts
+            .iter()
+            .map(|host| with_port(host, self.port))
+            .collect()
+    }
+
     /// Load the server configuration from the config file
     pub fn new() -> Result<Self, Error> {
         let settings = Config::builder()
@@ -75,10 +392,24 @@ impl ServerConfig {
             Err(Error::from(e))
         })?;
 
+        de_settings.resource_pack.validate()?;
         Ok(de_settings)
     }
 }
 
+/// Adds `port` to `host`, unless it already has one. Bare IPv6 addresses are put in brackets.
+fn with_port(host: &str, port: u32) -> String {
+    if host.parse::<SocketAddr>().is_ok() {
+        return host.to_string();
+    }
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(ip)) => format!("[{}]:{}", ip, port),
+        // Anything else with a colon in it is a hostname with a port already.
+        _ if host.contains(':') => host.to_string(),
+        _ => format!("{}:{}", host, port),
+    }
+}
+
 /// Check if the error is a not found error
 fn is_not_found(err: &ConfigError) -> bool {
     let ConfigError::Foreign(foreign_error) = err else {
@@ -123,15 +454,134 @@ fn create_config_file() -> Result<(), Error> {
 impl Default for ServerConfig {
     fn default() -> Self {
         Self {
-            host: DEFAULT_SERVER_HOST.to_string(),
+            host: BindAddresses::Single(DEFAULT_SERVER_HOST.to_string()),
             port: DEFAULT_SERVER_PORT,
             motd: vec![DEFAULT_MOTD.to_string()],
             max_players: DEFAULT_MAX_PLAYERS as i32,
+            view_distance: DEFAULT_VIEW_DISTANCE,
             network_tick_rate: 0,
+            tick_rate: DEFAULT_TICK_RATE,
+            network_compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            keep_alive_interval: DEFAULT_KEEP_ALIVE_INTERVAL,
+            keep_alive_timeout: DEFAULT_KEEP_ALIVE_TIMEOUT,
+            shutdown_grace_period: DEFAULT_SHUTDOWN_GRACE_PERIOD,
             world: "world".to_string(),
+            favicon: DEFAULT_FAVICON.to_string(),
+            online_mode: false,
+            fetch_skins: false,
+            duplicate_login: DuplicateLoginPolicy::default(),
+            lenient_usernames: false,
+            allow_protocol_range: None,
+            brand: DEFAULT_SERVER_BRAND.to_string(),
+            default_gamemode: GameMode::default(),
             database: Database {
-                cache_size: 1024,
+                cache_size_mb: DEFAULT_CACHE_SIZE_MB,
                 compression: "fast".to_string(),
+                save_interval: DEFAULT_SAVE_INTERVAL,
+            },
+            network: Network {
+                max_packets_per_second: DEFAULT_MAX_PACKETS_PER_SECOND,
+                packet_burst: DEFAULT_PACKET_BURST,
+                proxy_protocol: false,
+                max_packet_size: DEFAULT_MAX_PACKET_SIZE,
+                outgoing_queue_size: DEFAULT_OUTGOING_QUEUE_SIZE,
+                outgoing_queue_timeout: DEFAULT_OUTGOING_QUEUE_TIMEOUT,
+                tcp_nodelay: true,
+                so_reuseaddr: true,
+                tcp_keepalive: false,
+                max_pending_teleports: DEFAULT_MAX_PENDING_TELEPORTS,
+                ping_timeout: DEFAULT_PING_TIMEOUT,
+            },
+            proxy: Proxy {
+                bungeecord: false,
+                velocity_secret: String::new(),
+            },
+            query: Query {
+                enabled: false,
+                port: 0,
+            },
+            rcon: Rcon {
+                enabled: false,
+                port: DEFAULT_RCON_PORT,
+                password: String::new(),
+            },
+            whitelist: Whitelist { enabled: false },
+            resource_pack: ResourcePack {
+                url: String::new(),
+                sha1: String::new(),
+                required: false,
+                prompt: String::new(),
+                kick_message: DEFAULT_RESOURCE_PACK_KICK_MESSAGE.to_string(),
+            },
+            tab_list: TabList {
+                header: String::new(),
+                footer: String::new(),
+                refresh_interval: DEFAULT_TAB_LIST_REFRESH_INTERVAL,
+            },
+            welcome_title: WelcomeTitle {
+                title: String::new(),
+                subtitle: String::new(),
+                fade_in: 10,
+                stay: 70,
+                fade_out: 20,
+            },
+            game_rules: GameRules {
+                do_daylight_cycle: true,
+                do_weather_cycle: true,
+            },
+            abilities: Abilities {
+                allow_flight: false,
+                flying_speed: DEFAULT_FLYING_SPEED,
+                walking_speed: DEFAULT_FOV_MODIFIER,
+            },
+            anti_cheat: AntiCheat {
+                enabled: true,
+                max_horizontal_speed: DEFAULT_MAX_HORIZONTAL_SPEED,
+                max_vertical_speed: DEFAULT_MAX_VERTICAL_SPEED,
+                flying_multiplier: DEFAULT_FLYING_MULTIPLIER,
+                max_ascending_moves: DEFAULT_MAX_ASCENDING_MOVES,
+                max_violations: DEFAULT_MAX_VIOLATIONS,
+                forgive_after: DEFAULT_FORGIVE_AFTER,
+            },
+            world_border: InitialWorldBorder {
+                center_x: 0.0,
+                center_z: 0.0,
+                diameter: DEFAULT_BORDER_DIAMETER,
+                warning_blocks: 5,
+                warning_time: 15,
+            },
+            world_generation: WorldGeneration {
+                generator: "superflat".to_string(),
+                seed: 0,
+                superflat_layers: DEFAULT_SUPERFLAT_LAYERS.to_string(),
+                biome: DEFAULT_BIOME.to_string(),
+            },
+            dimensions: vec![
+                DimensionConfig {
+                    name: THE_NETHER.to_string(),
+                    dimension_type: THE_NETHER.to_string(),
+                    world_generation: WorldGeneration {
+                        generator: "superflat".to_string(),
+                        seed: 0,
+                        superflat_layers: DEFAULT_NETHER_LAYERS.to_string(),
+                        biome: DEFAULT_NETHER_BIOME.to_string(),
+                    },
+                },
+                DimensionConfig {
+                    name: THE_END.to_string(),
+                    dimension_type: THE_END.to_string(),
+                    world_generation: WorldGeneration {
+                        generator: "superflat".to_string(),
+                        seed: 0,
+                        superflat_layers: DEFAULT_END_LAYERS.to_string(),
+                        biome: DEFAULT_END_BIOME.to_string(),
+                    },
+                },
+            ],
+            debug: DebugOptions {
+                packet_dump: String::new(),
+                tps_boss_bar: false,
+                player_count_sidebar: false,
             },
         }
     }
@@ -142,3 +592,164 @@ pub fn get_global_config() -> &'static ServerConfig {
     static CONFIG: OnceLock<ServerConfig> = OnceLock::new();
     CONFIG.get_or_init(|| ServerConfig::new().expect("Failed to load config"))
 }
+
+/// Get the server favicon as a base64 encoded `data:` URI, ready to be put in the status response.
+///
+/// The file at [ServerConfig::favicon] is only read and encoded once, the first time this is called.
+/// Returns `None` if the file is missing or isn't a valid 64x64 PNG.
+pub fn get_favicon() -> Option<&'static str> {
+    static FAVICON: OnceLock<Option<String>> = OnceLock::new();
+    FAVICON
+        .get_or_init(|| {
+            let path = &get_global_config().favicon;
+            let data = match std::fs::read(path) {
+                Ok(data) => data,
+                Err(e) => {
+                    warn!("Could not read favicon at \"{}\": {}", path, e);
+                    return None;
+                }
+            };
+            match encode_favicon(&data) {
+                Ok(favicon) => Some(favicon),
+                Err(e) => {
+                    warn!("Not using favicon at \"{}\": {}", path, e);
+                    None
+                }
+            }
+        })
+        .as_deref()
+}
+
+/// Validates that `data` is a 64x64 PNG and encodes it as a base64 `data:` URI.
+fn encode_favicon(data: &[u8]) -> Result<String, Error> {
+    const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+    // The IHDR chunk is always first, so the dimensions are at a fixed offset.
+    if data.len() < 24 || data[..8] != PNG_SIGNATURE || &data[12..16] != b"IHDR" {
+        return Err(Error::Generic("favicon is not a PNG file".to_string()));
+    }
+    let width = u32::from_be_bytes([data[16], data[17], data[18], data[19]]);
+    let height = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
+    if width != 64 || height != 64 {
+        return Err(Error::Generic(format!(
+            "favicon must be 64x64, but is {}x{}",
+            width, height
+        )));
+    }
+
+    let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+    Ok(format!("data:image/png;base64,{}", encoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_favicon, BindAddresses, ResourcePack, ServerConfig};
+    use crate::setup::BASE_CONFIG;
+
+    fn parse_config(host: &str) -> ServerConfig {
+        let contents = BASE_CONFIG.replace("host = \"0.0.0.0\"", &format!("host = {}", host));
+        config::Config::builder()
+            .add_source(config::File::from_str(&contents, config::FileFormat::Toml))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_single_host() {
+        let config = parse_config("\"0.0.0.0\"");
+        assert_eq!(config.host, BindAddresses::Single("0.0.0.0".to_string()));
+        assert_eq!(config.bind_addresses(), vec!["0.0.0.0:25565"]);
+    }
+
+    #[test]
+    fn test_host_list() {
+        let config = parse_config(r#"["0.0.0.0:25566", "[::]:25567", "::1", "localhost"]"#);
+        assert_eq!(
+            config.bind_addresses(),
+            vec![
+                "0.0.0.0:25566",
+                "[::]:25567",
+                "[::1]:25565",
+                "localhost:25565"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bundled_dimensions() {
+        let config = parse_config("\"0.0.0.0\"");
+        let defaults = ServerConfig::default();
+        assert_eq!(config.dimensions.len(), defaults.dimensions.len());
+        for (dimension, default) in config.dimensions.iter().zip(&defaults.dimensions) {
+            assert_eq!(dimension.name, default.name);
+            assert_eq!(dimension.dimension_type, default.dimension_type);
+            assert_eq!(
+                dimension.world_generation.superflat_layers,
+                default.world_generation.superflat_layers
+            );
+            assert_eq!(
+                dimension.world_generation.biome,
+                default.world_generation.biome
+            );
+        }
+    }
+
+    fn png_header(width: u32, height: u32) -> Vec<u8> {
+        let mut data = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        data.extend_from_slice(&13u32.to_be_bytes());
+        data.extend_from_slice(b"IHDR");
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        data.extend_from_slice(&[8, 6, 0, 0, 0]);
+        data
+    }
+
+    #[test]
+    fn test_favicon_data_uri() {
+        let favicon = encode_favicon(&png_header(64, 64)).unwrap();
+        assert!(favicon.starts_with("data:image/png;base64,"));
+        assert!(favicon.len() > "data:image/png;base64,".len());
+    }
+
+    #[test]
+    fn test_bundled_icon_is_valid() {
+        let data = std::fs::read("icon-64.png").unwrap();
+        assert!(encode_favicon(&data).is_ok());
+    }
+
+    #[test]
+    fn test_favicon_rejects_wrong_size() {
+        assert!(encode_favicon(&png_header(128, 128)).is_err());
+    }
+
+    #[test]
+    fn test_favicon_rejects_non_png() {
+        assert!(encode_favicon(b"definitely not a png file at all").is_err());
+    }
+
+    #[test]
+    fn test_resource_pack_sha1() {
+        let config = parse_config("\"0.0.0.0\"");
+        assert!(!config.resource_pack.is_enabled());
+        assert!(config.resource_pack.validate().is_ok());
+
+        let pack = |sha1: &str| ResourcePack {
+            url: "https://example.com/pack.zip".to_string(),
+            sha1: sha1.to_string(),
+            ..ServerConfig::default().resource_pack
+        };
+        assert!(pack("").validate().is_ok());
+        assert!(pack("2ef7bde608ce5404e97d5f042f95f89f1c232871")
+            .validate()
+            .is_ok());
+        assert!(pack("2EF7BDE608CE5404E97D5F042F95F89F1C232871")
+            .validate()
+            .is_ok());
+        assert!(pack("2ef7bde608ce5404e97d5f042f95f89f1c23287").validate().is_err());
+        assert!(pack("zef7bde608ce5404e97d5f042f95f89f1c232871")
+            .validate()
+            .is_err());
+    }
+}
@@ -1,10 +1,72 @@
 pub const DEFAULT_LOG_LEVEL: &str = "debug";
+// The game version advertised in the server list
+pub const GAME_VERSION: &str = "1.20.1";
+// The protocol version of GAME_VERSION, the only one the server speaks
+pub const PROTOCOL_VERSION: i32 = 763;
+// How many ticks run every second, same as vanilla
+pub const DEFAULT_TICK_RATE: u32 = 20;
 pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+pub const DEFAULT_BANS_FILE: &str = "bans.json";
+pub const DEFAULT_WHITELIST_FILE: &str = "whitelist.json";
+pub const DEFAULT_SKIN_CACHE_FILE: &str = "skin_cache.json";
 pub const DEFAULT_SERVER_HOST: &str = "localhost";
 // Default port for a Minecraft server
 pub const DEFAULT_SERVER_PORT: u32 = 25565;
+// Same as the vanilla server
+pub const DEFAULT_RCON_PORT: u32 = 25575;
 pub const DEFAULT_MOTD: &str = "A FerrumC Server";
 pub const DEFAULT_MAX_PLAYERS: u32 = 20;
+// In chunks, same as the vanilla server
+pub const DEFAULT_VIEW_DISTANCE: u32 = 10;
+pub const DEFAULT_FAVICON: &str = "icon-64.png";
+// Shown in the client's F3 screen
+pub const DEFAULT_SERVER_BRAND: &str = "FerrumC";
+// Same as the vanilla server
+pub const DEFAULT_COMPRESSION_THRESHOLD: i32 = 256;
+// In seconds. The vanilla client disconnects itself after 20 seconds without a keep alive.
+pub const DEFAULT_KEEP_ALIVE_INTERVAL: u64 = 15;
+pub const DEFAULT_KEEP_ALIVE_TIMEOUT: u64 = 30;
+// In seconds
+pub const DEFAULT_SHUTDOWN_GRACE_PERIOD: u64 = 10;
+pub const DEFAULT_MAX_PACKETS_PER_SECOND: u32 = 500;
+pub const DEFAULT_PACKET_BURST: u32 = 1000;
+// The protocol's limit on the length of a packet, 2^21 - 1, which is as much as a 3 byte VarInt can hold.
+pub const MAX_PACKET_LENGTH: usize = 2097151;
+// Vanilla clients never send packets anywhere near this big.
+pub const DEFAULT_MAX_PACKET_SIZE: u32 = 1048576;
+// Enough for a full view distance worth of chunks
+pub const DEFAULT_OUTGOING_QUEUE_SIZE: usize = 1024;
+// In seconds
+pub const DEFAULT_OUTGOING_QUEUE_TIMEOUT: u64 = 10;
+// Teleports are sent again once a second while they're unconfirmed, so this is about 20 seconds
+pub const DEFAULT_MAX_PENDING_TELEPORTS: u32 = 20;
+// In seconds
+pub const DEFAULT_PING_TIMEOUT: u64 = 30;
+pub const DEFAULT_RESOURCE_PACK_KICK_MESSAGE: &str = "This server requires its resource pack";
+// In seconds
+pub const DEFAULT_TAB_LIST_REFRESH_INTERVAL: u64 = 5;
+// Vanilla's, as far out as the world goes
+pub const DEFAULT_BORDER_DIAMETER: f64 = 59999968.0;
+// Vanilla's classic flat, from the bottom up
+pub const DEFAULT_SUPERFLAT_LAYERS: &str = "bedrock,2xdirt,grass_block";
+pub const DEFAULT_BIOME: &str = "minecraft:plains";
+// Flat versions of the nether and the end, from the bottom up
+pub const DEFAULT_NETHER_LAYERS: &str = "bedrock,3xnetherrack";
+pub const DEFAULT_NETHER_BIOME: &str = "minecraft:nether_wastes";
+pub const DEFAULT_END_LAYERS: &str = "3xend_stone";
+pub const DEFAULT_END_BIOME: &str = "minecraft:the_end";
+pub const DEFAULT_CACHE_SIZE_MB: u32 = 256;
+// In seconds
+pub const DEFAULT_SAVE_INTERVAL: u64 = 60;
+// In blocks per tick, with room for sprint jumping on ice and a bit of lag
+pub const DEFAULT_MAX_HORIZONTAL_SPEED: f64 = 1.0;
+pub const DEFAULT_MAX_VERTICAL_SPEED: f64 = 0.75;
+// Flying sprinting in creative is a bit over 1 block per tick
+pub const DEFAULT_FLYING_MULTIPLIER: f64 = 4.0;
+pub const DEFAULT_MAX_ASCENDING_MOVES: u32 = 20;
+pub const DEFAULT_MAX_VIOLATIONS: u32 = 20;
+// About 10 seconds of moving around
+pub const DEFAULT_FORGIVE_AFTER: u32 = 200;
 
 pub mod init {
     pub const DEFAULT_SPAWN_X_POS: i32 = 0;
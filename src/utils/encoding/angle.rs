@@ -0,0 +1,36 @@
+use ferrumc_codec::enc::NetEncode;
+use tokio::io::AsyncWrite;
+
+/// A rotation in steps of 1/256 of a full turn, which is how entity rotations are sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Angle(pub u8);
+
+impl Angle {
+    /// Converts an angle in degrees. Angles outside [0, 360) wrap around.
+    pub fn from_degrees(degrees: f32) -> Self {
+        Angle((degrees / 360.0 * 256.0).rem_euclid(256.0) as u8)
+    }
+}
+
+impl NetEncode for Angle {
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        self.0.net_encode(bytes).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_angles_wrap_around() {
+        assert_eq!(Angle::from_degrees(0.0), Angle(0));
+        assert_eq!(Angle::from_degrees(90.0), Angle(64));
+        assert_eq!(Angle::from_degrees(-90.0), Angle(192));
+        assert_eq!(Angle::from_degrees(-180.0), Angle(128));
+        assert_eq!(Angle::from_degrees(360.0), Angle(0));
+    }
+}
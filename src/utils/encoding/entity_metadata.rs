@@ -0,0 +1,224 @@
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::utils::encoding::position::Position;
+use crate::utils::encoding::slot::Slot;
+use crate::utils::text_component::TextComponent;
+
+/// Ends the list of entries, since it isn't prefixed with its length.
+const END_OF_METADATA: u8 = 0xFF;
+
+/// What an entity is doing, which decides how it's drawn and how big its hitbox is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pose {
+    Standing = 0,
+    FallFlying = 1,
+    Sleeping = 2,
+    Swimming = 3,
+    SpinAttack = 4,
+    Sneaking = 5,
+    LongJumping = 6,
+    Dying = 7,
+}
+
+/// One metadata value. Each is sent with its type id in front of it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataValue {
+    Byte(u8),
+    VarInt(i32),
+    Float(f32),
+    String(String),
+    /// A text component, or nothing, e.g. an entity without a custom name.
+    OptionalChat(Option<TextComponent>),
+    Slot(Slot),
+    Boolean(bool),
+    /// A block position.
+    Position(Position),
+    Pose(Pose),
+}
+
+impl MetadataValue {
+    fn type_id(&self) -> i32 {
+        match self {
+            MetadataValue::Byte(_) => 0,
+            MetadataValue::VarInt(_) => 1,
+            MetadataValue::Float(_) => 3,
+            MetadataValue::String(_) => 4,
+            MetadataValue::OptionalChat(_) => 6,
+            MetadataValue::Slot(_) => 7,
+            MetadataValue::Boolean(_) => 8,
+            MetadataValue::Position(_) => 10,
+            MetadataValue::Pose(_) => 20,
+        }
+    }
+}
+
+/// Entity metadata, as a list of values with the index they're at for the entity's type.
+///
+/// Only the entries that changed need to be sent. The indices are different for every entity
+/// type, see the constants in [entity] and [player].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EntityMetadata {
+    pub entries: Vec<(u8, MetadataValue)>,
+}
+
+impl EntityMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, index: u8, value: MetadataValue) -> Self {
+        self.entries.push((index, value));
+        self
+    }
+}
+
+/// Metadata indices and flags every entity has.
+pub mod entity {
+    /// A [Byte](super::MetadataValue::Byte) of the flags below.
+    pub const FLAGS: u8 = 0;
+    /// An [OptionalChat](super::MetadataValue::OptionalChat).
+    pub const CUSTOM_NAME: u8 = 2;
+    /// A [Boolean](super::MetadataValue::Boolean). Whether the custom name shows when the entity
+    /// isn't being looked at.
+    pub const CUSTOM_NAME_VISIBLE: u8 = 3;
+    /// A [Boolean](super::MetadataValue::Boolean).
+    pub const SILENT: u8 = 4;
+    /// A [Boolean](super::MetadataValue::Boolean).
+    pub const NO_GRAVITY: u8 = 5;
+    /// A [Pose](super::MetadataValue::Pose).
+    pub const POSE: u8 = 6;
+
+    pub const FLAG_ON_FIRE: u8 = 0x01;
+    pub const FLAG_CROUCHING: u8 = 0x02;
+    pub const FLAG_SPRINTING: u8 = 0x08;
+    pub const FLAG_SWIMMING: u8 = 0x10;
+    pub const FLAG_INVISIBLE: u8 = 0x20;
+    pub const FLAG_GLOWING: u8 = 0x40;
+    pub const FLAG_FLYING_WITH_ELYTRA: u8 = 0x80;
+}
+
+/// Metadata indices for dropped items, on top of the ones in [entity].
+pub mod item {
+    pub use super::entity::*;
+
+    /// A [Slot](super::MetadataValue::Slot), the items it's made of.
+    pub const ITEM: u8 = 8;
+}
+
+/// Metadata indices for players, on top of the ones in [entity].
+pub mod player {
+    pub use super::entity::*;
+
+    /// A [Float](super::MetadataValue::Float).
+    pub const HEALTH: u8 = 9;
+    /// A [Byte](super::MetadataValue::Byte) bit mask of the skin layers to show, from the
+    /// player's client settings.
+    pub const DISPLAYED_SKIN_PARTS: u8 = 17;
+    /// A [Byte](super::MetadataValue::Byte), 0 for left and 1 for right.
+    pub const MAIN_HAND: u8 = 18;
+}
+
+impl NetEncode for EntityMetadata {
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        for (index, value) in &self.entries {
+            bytes.write_u8(*index).await?;
+            VarInt::from(value.type_id()).net_encode(bytes).await?;
+            match value {
+                MetadataValue::Byte(byte) => byte.net_encode(bytes).await?,
+                MetadataValue::VarInt(int) => VarInt::from(*int).net_encode(bytes).await?,
+                MetadataValue::Float(float) => float.net_encode(bytes).await?,
+                MetadataValue::String(string) => string.net_encode(bytes).await?,
+                MetadataValue::OptionalChat(chat) => {
+                    chat.is_some().net_encode(bytes).await?;
+                    if let Some(chat) = chat {
+                        chat.to_json()
+                            .map_err(ferrumc_codec::CodecError::from_external_error)?
+                            .net_encode(bytes)
+                            .await?;
+                    }
+                }
+                MetadataValue::Slot(slot) => slot.net_encode(bytes).await?,
+                MetadataValue::Boolean(boolean) => boolean.net_encode(bytes).await?,
+                MetadataValue::Position(position) => position.net_encode(bytes).await?,
+                MetadataValue::Pose(pose) => VarInt::from(*pose as i32).net_encode(bytes).await?,
+            }
+        }
+        bytes.write_u8(END_OF_METADATA).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::encoding::slot::ItemStack;
+
+    async fn encode(metadata: &EntityMetadata) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        metadata.net_encode(&mut bytes).await.unwrap();
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_empty_metadata_is_terminated() {
+        assert_eq!(encode(&EntityMetadata::new()).await, vec![0xFF]);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_entries_are_type_prefixed() {
+        let metadata = EntityMetadata::new()
+            .with(
+                player::FLAGS,
+                MetadataValue::Byte(player::FLAG_CROUCHING | player::FLAG_SPRINTING),
+            )
+            .with(player::POSE, MetadataValue::Pose(Pose::Sneaking))
+            .with(player::HEALTH, MetadataValue::Float(20.0))
+            .with(16, MetadataValue::VarInt(300))
+            .with(player::SILENT, MetadataValue::Boolean(true));
+
+        let mut expected = vec![0x00, 0x00, 0x0A, 0x06, 0x14, 0x05, 0x09, 0x03];
+        expected.extend_from_slice(&20.0f32.to_be_bytes());
+        expected.extend_from_slice(&[0x10, 0x01, 0xAC, 0x02, 0x04, 0x08, 0x01, 0xFF]);
+        assert_eq!(encode(&metadata).await, expected);
+    }
+
+    #[tokio::test]
+    async fn test_encode_mixed_metadata() {
+        let metadata = EntityMetadata::new()
+            .with(
+                entity::CUSTOM_NAME,
+                MetadataValue::OptionalChat(Some(TextComponent::new("Bob"))),
+            )
+            .with(entity::CUSTOM_NAME_VISIBLE, MetadataValue::Boolean(false))
+            .with(1, MetadataValue::String("hi".to_string()))
+            .with(14, MetadataValue::Position(Position::new(1, 2, 3)))
+            .with(20, MetadataValue::OptionalChat(None));
+
+        let mut expected = vec![0x02, 0x06, 0x01, 0x0E];
+        expected.extend_from_slice(br#"{"text":"Bob"}"#);
+        expected.extend_from_slice(&[0x03, 0x08, 0x00]);
+        expected.extend_from_slice(&[0x01, 0x04, 0x02, b'h', b'i']);
+        // x = 1, z = 3, y = 2, packed into a long.
+        expected.extend_from_slice(&[0x0E, 0x0A, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x30, 0x02]);
+        expected.extend_from_slice(&[0x14, 0x06, 0x00, 0xFF]);
+        assert_eq!(encode(&metadata).await, expected);
+    }
+
+    #[tokio::test]
+    async fn test_encode_item_metadata() {
+        let metadata = EntityMetadata::new().with(
+            item::ITEM,
+            MetadataValue::Slot(ItemStack::new(1, 64).into()),
+        );
+        // Present, item 1, 64 of them, no NBT.
+        assert_eq!(
+            encode(&metadata).await,
+            vec![0x08, 0x07, 0x01, 0x01, 0x40, 0x00, 0xFF]
+        );
+    }
+}
@@ -1,5 +1,8 @@
-pub mod bitset;
+pub mod angle;
+pub mod entity_metadata;
+pub mod particle;
 pub mod position;
+pub mod slot;
 pub mod velocity;
 
 /*impl<S: NBTSerialize> Encode for &S {
@@ -0,0 +1,175 @@
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::io::AsyncWrite;
+
+use crate::utils::encoding::slot::Slot;
+
+/// A particle, along with the data that particle type needs.
+///
+/// Encodes just the data. The particle type's id goes at the start of the packet, separately
+/// from it, see [ParticleData::id].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParticleData {
+    AngryVillager,
+    /// Pieces of a block, by block state id.
+    Block(i32),
+    /// Shows a block, like barriers and light blocks do when held.
+    BlockMarker(i32),
+    Cloud,
+    Crit,
+    DamageIndicator,
+    /// Colored dust, like redstone's. The color is `0xRRGGBB`, and the scale goes from 0.01 to 4.
+    Dust {
+        color: u32,
+        scale: f32,
+    },
+    /// Dust that fades from one color to another.
+    DustColorTransition {
+        from: u32,
+        to: u32,
+        scale: f32,
+    },
+    Enchant,
+    EndRod,
+    Explosion,
+    /// Dust falling off the bottom of a block, by block state id.
+    FallingDust(i32),
+    Flame,
+    HappyVillager,
+    Heart,
+    /// Pieces of an item.
+    Item(Slot),
+    Note,
+    Poof,
+    Portal,
+    /// The roll in radians.
+    SculkCharge(f32),
+    /// How long to wait before the particle shows up, in ticks.
+    Shriek(i32),
+    Smoke,
+    TotemOfUndying,
+}
+
+impl ParticleData {
+    /// The particle type's id in the particle type registry.
+    pub fn id(&self) -> i32 {
+        match self {
+            ParticleData::AngryVillager => 1,
+            ParticleData::Block(_) => 2,
+            ParticleData::BlockMarker(_) => 3,
+            ParticleData::Cloud => 5,
+            ParticleData::Crit => 6,
+            ParticleData::DamageIndicator => 7,
+            ParticleData::Dust { .. } => 14,
+            ParticleData::DustColorTransition { .. } => 15,
+            ParticleData::Enchant => 19,
+            ParticleData::EndRod => 20,
+            ParticleData::Explosion => 23,
+            ParticleData::FallingDust(_) => 25,
+            ParticleData::Flame => 28,
+            ParticleData::SculkCharge(_) => 31,
+            ParticleData::HappyVillager => 36,
+            ParticleData::Heart => 38,
+            ParticleData::Item(_) => 40,
+            ParticleData::Note => 47,
+            ParticleData::Poof => 48,
+            ParticleData::Portal => 49,
+            ParticleData::Smoke => 51,
+            ParticleData::TotemOfUndying => 56,
+            ParticleData::Shriek(_) => 93,
+        }
+    }
+}
+
+impl NetEncode for ParticleData {
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        match self {
+            ParticleData::Block(state)
+            | ParticleData::BlockMarker(state)
+            | ParticleData::FallingDust(state) => VarInt::from(*state).net_encode(bytes).await,
+            ParticleData::Dust { color, scale } => {
+                encode_color(*color, bytes).await?;
+                scale.net_encode(bytes).await
+            }
+            ParticleData::DustColorTransition { from, to, scale } => {
+                encode_color(*from, bytes).await?;
+                scale.net_encode(bytes).await?;
+                encode_color(*to, bytes).await
+            }
+            ParticleData::Item(slot) => slot.net_encode(bytes).await,
+            ParticleData::SculkCharge(roll) => roll.net_encode(bytes).await,
+            ParticleData::Shriek(delay) => VarInt::from(*delay).net_encode(bytes).await,
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Colors are sent as red, green and blue floats from 0 to 1.
+async fn encode_color<T>(color: u32, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+where
+    T: AsyncWrite + Unpin,
+{
+    for shift in [16, 8, 0] {
+        let channel = ((color >> shift) & 0xFF) as f32 / 255.0;
+        channel.net_encode(bytes).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::encoding::slot::ItemStack;
+
+    async fn encode(particle: &ParticleData) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        particle.net_encode(&mut bytes).await.unwrap();
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_encode_dust() {
+        let dust = ParticleData::Dust {
+            color: 0xFF0000,
+            scale: 2.0,
+        };
+        assert_eq!(dust.id(), 14);
+        let mut expected = Vec::new();
+        for float in [1.0f32, 0.0, 0.0, 2.0] {
+            expected.extend_from_slice(&float.to_be_bytes());
+        }
+        assert_eq!(encode(&dust).await, expected);
+
+        let transition = ParticleData::DustColorTransition {
+            from: 0x000000,
+            to: 0x0000FF,
+            scale: 1.0,
+        };
+        let mut expected = Vec::new();
+        for float in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0] {
+            expected.extend_from_slice(&float.to_be_bytes());
+        }
+        assert_eq!(encode(&transition).await, expected);
+    }
+
+    #[tokio::test]
+    async fn test_encode_block_and_item() {
+        let block = ParticleData::Block(300);
+        assert_eq!(block.id(), 2);
+        assert_eq!(encode(&block).await, vec![0xAC, 0x02]);
+
+        let item = ParticleData::Item(ItemStack::new(1, 1).into());
+        assert_eq!(item.id(), 40);
+        assert_eq!(encode(&item).await, vec![0x01, 0x01, 0x01, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn test_particles_without_data() {
+        assert_eq!(ParticleData::Flame.id(), 28);
+        assert!(encode(&ParticleData::Flame).await.is_empty());
+        assert!(encode(&ParticleData::Heart).await.is_empty());
+    }
+}
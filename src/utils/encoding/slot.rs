@@ -0,0 +1,311 @@
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::utils::constants::MAX_PACKET_LENGTH;
+use crate::utils::error::Error;
+use crate::utils::impls::packet_impls::NetDecode;
+
+/// Written in place of an item's NBT when it doesn't have any.
+const NO_NBT: u8 = 0x00;
+const TAG_COMPOUND: u8 = 10;
+/// Same as vanilla.
+const MAX_NBT_DEPTH: usize = 512;
+
+/// A stack of items.
+///
+/// - `item_id`: The item's id in the item registry.
+/// - `nbt`: The item's NBT data, e.g. enchantments, as it was sent. It's kept as it is, since
+///   nothing looks inside it yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item_id: i32,
+    pub count: i8,
+    pub nbt: Option<Vec<u8>>,
+}
+
+impl ItemStack {
+    pub fn new(item_id: i32, count: i8) -> Self {
+        Self {
+            item_id,
+            count,
+            nbt: None,
+        }
+    }
+
+    /// Whether the two can be stacked together, ignoring how many there are of each.
+    pub fn is_same_item(&self, other: &ItemStack) -> bool {
+        self.item_id == other.item_id && self.nbt == other.nbt
+    }
+}
+
+/// An inventory slot, which is either empty or holds an [ItemStack].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Slot(pub Option<ItemStack>);
+
+impl Slot {
+    pub const EMPTY: Slot = Slot(None);
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+}
+
+impl From<ItemStack> for Slot {
+    fn from(stack: ItemStack) -> Self {
+        Slot(Some(stack))
+    }
+}
+
+impl NetEncode for Slot {
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        match &self.0 {
+            Some(stack) => {
+                true.net_encode(bytes).await?;
+                VarInt::from(stack.item_id).net_encode(bytes).await?;
+                stack.count.net_encode(bytes).await?;
+                match &stack.nbt {
+                    Some(nbt) => bytes.write_all(nbt).await?,
+                    None => bytes.write_u8(NO_NBT).await?,
+                }
+            }
+            None => false.net_encode(bytes).await?,
+        }
+        Ok(())
+    }
+}
+
+impl NetDecode for Slot {
+    /// Decodes a slot. Stacks with a count of 0 or less are empty, same as vanilla.
+    async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>, Error>
+    where
+        T: AsyncRead + Unpin,
+    {
+        if bytes.read_u8().await? == 0 {
+            return Ok(Box::new(Slot::EMPTY));
+        }
+        let item_id = VarInt::read(bytes).await?.get_val();
+        let count = bytes.read_i8().await?;
+        let nbt = read_nbt(bytes).await?;
+        if count <= 0 {
+            return Ok(Box::new(Slot::EMPTY));
+        }
+        Ok(Box::new(Slot(Some(ItemStack {
+            item_id,
+            count,
+            nbt,
+        }))))
+    }
+}
+
+/// What's left to read of a compound or list while reading NBT.
+enum NbtFrame {
+    Compound,
+    List { tag_type: u8, remaining: i32 },
+}
+
+/// Reads an item's NBT without looking inside it, and returns the bytes it was sent as.
+/// `None` if the item doesn't have any.
+///
+/// Read without recursion, so deeply nested NBT can't overflow the stack.
+async fn read_nbt<T: AsyncRead + Unpin>(bytes: &mut T) -> Result<Option<Vec<u8>>, Error> {
+    let mut nbt = vec![bytes.read_u8().await?];
+    match nbt[0] {
+        NO_NBT => return Ok(None),
+        TAG_COMPOUND => {}
+        tag_type => {
+            return Err(Error::InvalidNbt(format!(
+                "Item NBT has to be a compound, not tag type {}",
+                tag_type
+            )))
+        }
+    }
+    let name_length = read_into::<2, _>(bytes, &mut nbt).await?;
+    read_n(bytes, &mut nbt, u16::from_be_bytes(name_length) as usize).await?;
+
+    let mut stack = vec![NbtFrame::Compound];
+    while let Some(frame) = stack.last_mut() {
+        let tag_type = match frame {
+            NbtFrame::Compound => {
+                let [tag_type] = read_into::<1, _>(bytes, &mut nbt).await?;
+                if tag_type == 0 {
+                    stack.pop();
+                    continue;
+                }
+                let name_length = read_into::<2, _>(bytes, &mut nbt).await?;
+                read_n(bytes, &mut nbt, u16::from_be_bytes(name_length) as usize).await?;
+                tag_type
+            }
+            NbtFrame::List {
+                tag_type,
+                remaining,
+            } => {
+                if *remaining <= 0 {
+                    stack.pop();
+                    continue;
+                }
+                *remaining -= 1;
+                *tag_type
+            }
+        };
+
+        let frame = match tag_type {
+            1 => read_n(bytes, &mut nbt, 1).await.map(|_| None)?,
+            2 => read_n(bytes, &mut nbt, 2).await.map(|_| None)?,
+            3 | 5 => read_n(bytes, &mut nbt, 4).await.map(|_| None)?,
+            4 | 6 => read_n(bytes, &mut nbt, 8).await.map(|_| None)?,
+            7 | 11 | 12 => {
+                let length = i32::from_be_bytes(read_into::<4, _>(bytes, &mut nbt).await?);
+                let element_size = match tag_type {
+                    7 => 1,
+                    11 => 4,
+                    _ => 8,
+                };
+                read_n(bytes, &mut nbt, array_length(length)? * element_size).await?;
+                None
+            }
+            8 => {
+                let length = read_into::<2, _>(bytes, &mut nbt).await?;
+                read_n(bytes, &mut nbt, u16::from_be_bytes(length) as usize).await?;
+                None
+            }
+            9 => {
+                let [element_type] = read_into::<1, _>(bytes, &mut nbt).await?;
+                let length = i32::from_be_bytes(read_into::<4, _>(bytes, &mut nbt).await?);
+                array_length(length)?;
+                Some(NbtFrame::List {
+                    tag_type: element_type,
+                    remaining: length,
+                })
+            }
+            TAG_COMPOUND => Some(NbtFrame::Compound),
+            _ => return Err(Error::InvalidNbt(format!("Unknown tag type {}", tag_type))),
+        };
+        if let Some(frame) = frame {
+            if stack.len() >= MAX_NBT_DEPTH {
+                return Err(Error::InvalidNbt("NBT is nested too deeply".to_string()));
+            }
+            stack.push(frame);
+        }
+    }
+    Ok(Some(nbt))
+}
+
+/// Checks a length read from NBT. Anything longer than a packet can't be real.
+fn array_length(length: i32) -> Result<usize, Error> {
+    match usize::try_from(length) {
+        Ok(length) if length <= MAX_PACKET_LENGTH => Ok(length),
+        _ => Err(Error::InvalidNbt(format!("Invalid length {}", length))),
+    }
+}
+
+async fn read_into<const N: usize, T: AsyncRead + Unpin>(
+    bytes: &mut T,
+    out: &mut Vec<u8>,
+) -> Result<[u8; N], Error> {
+    let mut buffer = [0; N];
+    bytes.read_exact(&mut buffer).await?;
+    out.extend_from_slice(&buffer);
+    Ok(buffer)
+}
+
+/// Reads `n` more bytes into `out`. NBT that would be longer than a packet can't be real, so
+/// that fails before anything is allocated for it.
+async fn read_n<T: AsyncRead + Unpin>(
+    bytes: &mut T,
+    out: &mut Vec<u8>,
+    n: usize,
+) -> Result<(), Error> {
+    let start = out.len();
+    if start + n > MAX_PACKET_LENGTH {
+        return Err(Error::InvalidNbt(format!(
+            "{} bytes is too long",
+            start + n
+        )));
+    }
+    out.resize(start + n, 0);
+    bytes.read_exact(&mut out[start..]).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encode_slots() {
+        let mut bytes = Vec::new();
+        Slot::EMPTY.net_encode(&mut bytes).await.unwrap();
+        assert_eq!(bytes, vec![0x00]);
+
+        let mut bytes = Vec::new();
+        Slot::from(ItemStack::new(300, 64))
+            .net_encode(&mut bytes)
+            .await
+            .unwrap();
+        assert_eq!(bytes, vec![0x01, 0xAC, 0x02, 0x40, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn test_slots_round_trip() {
+        for slot in [Slot::EMPTY, Slot::from(ItemStack::new(300, 64))] {
+            let mut bytes = Vec::new();
+            slot.net_encode(&mut bytes).await.unwrap();
+            let decoded = Slot::net_decode(&mut Cursor::new(bytes)).await.unwrap();
+            assert_eq!(*decoded, slot);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slots_with_nbt_round_trip() {
+        // {"": {ench: [{lvl: 5s}], name: "x"}}
+        let nbt = vec![
+            0x0A, 0x00, 0x00, // Root compound
+            0x09, 0x00, 0x04, b'e', b'n', b'c', b'h', 0x0A, 0x00, 0x00, 0x00, 0x01, // List
+            0x02, 0x00, 0x03, b'l', b'v', b'l', 0x00, 0x05, 0x00, // Compound in the list
+            0x08, 0x00, 0x04, b'n', b'a', b'm', b'e', 0x00, 0x01, b'x', // String
+            0x00, // End of the root
+        ];
+        let slot = Slot(Some(ItemStack {
+            item_id: 1,
+            count: 1,
+            nbt: Some(nbt.clone()),
+        }));
+        let mut bytes = Vec::new();
+        slot.net_encode(&mut bytes).await.unwrap();
+        assert_eq!(bytes[3..], nbt[..]);
+
+        // Something after the slot, to check it's not read too far.
+        bytes.push(0x42);
+        let mut cursor = Cursor::new(bytes);
+        let decoded = Slot::net_decode(&mut cursor).await.unwrap();
+        assert_eq!(*decoded, slot);
+        assert_eq!(cursor.read_u8().await.unwrap(), 0x42);
+    }
+
+    #[tokio::test]
+    async fn test_decode_invalid_slots() {
+        let empty = Slot::net_decode(&mut Cursor::new(vec![0x00]))
+            .await
+            .unwrap();
+        assert!(empty.is_empty());
+        let no_items = Slot::net_decode(&mut Cursor::new(vec![0x01, 0x01, 0x00, 0x00]))
+            .await
+            .unwrap();
+        assert!(no_items.is_empty());
+
+        let huge_array = vec![
+            0x01, 0x01, 0x01, 0x0A, 0x00, 0x00, 0x07, 0x00, 0x00, 0x7F, 0xFF, 0xFF, 0xFF,
+        ];
+        assert!(Slot::net_decode(&mut Cursor::new(huge_array))
+            .await
+            .is_err());
+        let truncated = vec![0x01, 0x01, 0x01, 0x0A, 0x00, 0x00, 0x01, 0x00];
+        assert!(Slot::net_decode(&mut Cursor::new(truncated)).await.is_err());
+    }
+}
@@ -66,6 +66,21 @@ pub enum Error {
     #[error("TCP Error: {0}")]
     TcpError(String),
 
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
+    #[error("Authentication failed: {0}")]
+    AuthenticationError(String),
+    #[error("Invalid proxy forwarding data: {0}")]
+    ProxyForwardingError(String),
+    #[error("Invalid PROXY protocol header: {0}")]
+    ProxyProtocolError(String),
+    #[error("Invalid RCON packet: {0}")]
+    RconError(String),
+    #[error("Invalid packet dump: {0}")]
+    PacketDumpError(String),
+    #[error(transparent)]
+    HttpError(#[from] reqwest::Error),
+
     #[error("Invalid NBT: {0}")]
     GenericNbtError(String),
 
@@ -90,6 +105,16 @@ pub enum Error {
     ConversionError,
     #[error(transparent)]
     CompressionError(std::io::Error),
+    #[error("Connection is closed")]
+    ConnectionClosed,
+    #[error("Outgoing packet queue is full")]
+    OutgoingQueueFull,
+    #[error("Invalid packet length {0}")]
+    InvalidPacketLength(i32),
+    #[error("Packet of {0} bytes is bigger than the maximum of {1}")]
+    PacketTooLarge(usize, usize),
+    #[error("Badly compressed packet: {0}")]
+    BadlyCompressedPacket(String),
 
     #[error("Database error: {0}")]
     LmdbError(#[from] heed::Error),
@@ -269,6 +269,23 @@ impl<V: NetDecode + Unpin> NetDecode for Vec<V> {
     }
 }
 
+/// The rest of a packet's bytes, for fields that aren't length prefixed since they make up the end
+/// of the packet, like plugin message payloads.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RemainingBytes(pub Vec<u8>);
+
+impl NetDecode for RemainingBytes {
+    /// Decodes everything left in the byte stream. Only makes sense as the last field of a packet.
+    async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>, Error>
+    where
+        T: AsyncRead + Unpin,
+    {
+        let mut remaining = Vec::new();
+        bytes.read_to_end(&mut remaining).await?;
+        Ok(Box::from(RemainingBytes(remaining)))
+    }
+}
+
 impl NetDecode for Position {
     /// Decodes a Position from a byte stream. A Position is a 64-bit integer, where the 26 MSB
     /// are the x coordinate, the next 26 bits are the z coordinate, and the 12 LSB are
@@ -0,0 +1,66 @@
+use std::io::ErrorKind::NotFound;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::utils::prelude::*;
+
+/// Reads what's in the JSON file at `path`, like the ban list or the whitelist, or the default
+/// if it doesn't exist yet.
+pub fn read_json_file<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| Error::DeserializationError(format!("{}: {}", path.display(), e))),
+        Err(e) if e.kind() == NotFound => Ok(T::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes `value` to the file at `path`. It's written to a temporary file first and then moved
+/// over the old one, so a crash halfway through can't lose what was in it.
+///
+/// Callers have to make sure two writes to the same file can't overtake each other, e.g. by
+/// holding a lock over what's being written.
+pub async fn write_json_file<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<()> {
+    let contents = serde_json::to_string_pretty(value)
+        .map_err(|e| Error::SerializationError(e.to_string()))?;
+
+    let temp_path = path.with_extension("json.tmp");
+    tokio::fs::write(&temp_path, contents).await?;
+    tokio::fs::rename(&temp_path, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("ferrumc-json-file-{}.json", Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let path = temp_path();
+        let empty: HashMap<String, u32> = read_json_file(&path).unwrap();
+        assert!(empty.is_empty());
+
+        let values = HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+        write_json_file(&path, &values).await.unwrap();
+        assert_eq!(
+            read_json_file::<HashMap<String, u32>>(&path).unwrap(),
+            values
+        );
+        assert!(!path.with_extension("json.tmp").exists());
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(read_json_file::<HashMap<String, u32>>(&path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
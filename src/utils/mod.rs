@@ -4,7 +4,11 @@ use tracing_subscriber::filter::Directive;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
+pub mod ban_list;
+pub mod chat;
 pub mod binary_utils;
+pub mod boss_bar;
+pub mod broadcast;
 pub mod components;
 pub mod config;
 pub mod constants;
@@ -12,7 +16,22 @@ pub mod encoding;
 pub mod error;
 pub mod hash;
 pub mod impls;
+pub mod json_file;
+pub mod particles;
+pub mod placeholders;
+pub mod player_data;
+pub mod plugin_channels;
 pub mod prelude;
+pub mod scheduler;
+pub mod scoreboard;
+pub mod skin_cache;
+pub mod sound;
+pub mod spatial_index;
+pub mod text_component;
+pub mod tick_rate;
+pub mod title;
+pub mod whitelist;
+pub mod window;
 
 /// Sets up the logger. Needs to be run before anything else in order for logging to run end.
 pub fn setup_logger() -> Result<()> {
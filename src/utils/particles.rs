@@ -0,0 +1,32 @@
+use crate::net::packets::outgoing::particle::Particle;
+use crate::net::packets::ConnectionId;
+use crate::state::ServerState;
+use crate::utils::encoding::particle::ParticleData;
+use crate::utils::encoding::position::Position;
+use crate::world::dimensions::Dimension;
+
+impl ServerState {
+    /// Spawns particles around `position` in `dimension` for every player near it, except
+    /// `except`. See [Particle] for how `count`, `offset` and `speed` spread them out.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn spawn_particles(
+        &self,
+        dimension: &Dimension,
+        position: (f64, f64, f64),
+        particle: ParticleData,
+        count: i32,
+        offset: (f32, f32, f32),
+        speed: f32,
+        except: Option<ConnectionId>,
+    ) {
+        let block = Position::new(
+            position.0.floor() as i32,
+            position.1.floor() as i16,
+            position.2.floor() as i32,
+        );
+        self.send_to_players_near(dimension, &block, except, || {
+            Particle::new(particle.clone(), position, offset, speed, count)
+        })
+        .await;
+    }
+}
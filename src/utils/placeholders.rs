@@ -0,0 +1,116 @@
+use crate::net::utils::visibility::players_in_world;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+
+/// The values placeholders in configurable messages, like the tab list, are filled in with.
+///
+/// - `online`: `%online%`, how many players are in the world.
+/// - `max`: `%max%`, the most players that can be online at once.
+/// - `tps`: `%tps%`, how many ticks the server ran in the last second.
+/// - `player`: `%player%`, the name of the player the message is for. Left as it is in messages
+///   that aren't for anyone in particular.
+#[derive(Debug, Clone, Default)]
+pub struct Placeholders<'a> {
+    pub online: usize,
+    pub max: i32,
+    pub tps: f64,
+    pub player: Option<&'a str>,
+}
+
+impl Placeholders<'_> {
+    /// The values as they are right now, without a player.
+    pub async fn current(state: &GlobalState) -> Placeholders<'static> {
+        Placeholders {
+            online: players_in_world(state).await.len(),
+            max: get_global_config().max_players,
+            tps: state.tick_rate.ticks_per_second(),
+            player: None,
+        }
+    }
+
+    fn value(&self, name: &str) -> Option<String> {
+        match name {
+            "online" => Some(self.online.to_string()),
+            "max" => Some(self.max.to_string()),
+            "tps" => Some(format!("{:.1}", self.tps)),
+            "player" => self.player.map(str::to_string),
+            _ => None,
+        }
+    }
+}
+
+/// Replaces every placeholder in `template` with its value. Anything between two `%` that isn't a
+/// placeholder is left alone, so a plain `100%` stays as it is.
+///
+/// The template is only read once, so a value that looks like a placeholder isn't replaced again.
+pub fn fill_placeholders(template: &str, values: &Placeholders) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('%') {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let placeholder = after
+            .find('%')
+            .and_then(|end| values.value(&after[..end]).map(|value| (end, value)));
+        match placeholder {
+            Some((end, value)) => {
+                filled.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                filled.push('%');
+                rest = after;
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> Placeholders<'static> {
+        Placeholders {
+            online: 3,
+            max: 20,
+            tps: 19.96,
+            player: Some("Steve"),
+        }
+    }
+
+    #[test]
+    fn test_placeholders_are_filled() {
+        assert_eq!(
+            fill_placeholders("Hi %player%! %online%/%max% online, %tps% TPS", &values()),
+            "Hi Steve! 3/20 online, 20.0 TPS"
+        );
+        assert_eq!(fill_placeholders("%online%%max%", &values()), "320");
+        assert_eq!(fill_placeholders("", &values()), "");
+    }
+
+    #[test]
+    fn test_other_percent_signs_are_kept() {
+        assert_eq!(
+            fill_placeholders("100% uptime, %online% on", &values()),
+            "100% uptime, 3 on"
+        );
+        assert_eq!(fill_placeholders("%unknown% %", &values()), "%unknown% %");
+
+        let no_player = Placeholders {
+            player: None,
+            ..values()
+        };
+        assert_eq!(fill_placeholders("%player%", &no_player), "%player%");
+    }
+
+    #[test]
+    fn test_values_are_not_filled_again() {
+        let sneaky = Placeholders {
+            player: Some("%online%"),
+            ..values()
+        };
+        assert_eq!(fill_placeholders("%player%", &sneaky), "%online%");
+    }
+}
@@ -0,0 +1,414 @@
+use tracing::warn;
+
+use crate::database::Database;
+use crate::net::packets::ConnectionId;
+use crate::net::utils::spawn::spawn_position;
+use crate::state::ServerState;
+use crate::utils::components::exact_position::ExactPosition;
+use crate::utils::components::experience::Experience;
+use crate::utils::components::food::Food;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::health::Health;
+use crate::utils::components::held_item::{HeldItem, HOTBAR_SLOTS};
+use crate::utils::components::inventory::Inventory;
+use crate::utils::components::player::Player;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::config::get_global_config;
+use crate::utils::constants::init;
+use crate::utils::encoding::position::Position;
+use crate::utils::encoding::slot::{ItemStack, Slot};
+use crate::utils::prelude::*;
+use crate::world::dimensions::OVERWORLD;
+
+/// Goes up whenever something is added to what's saved. Saves from older versions are still
+/// read, with whatever they didn't have yet left as it is for a new player.
+///
+/// - 0: Health and food, and later experience too, from before saves had a version.
+/// - 1: Position, rotation, gamemode, inventory and held item.
+/// - 2: Dimension.
+/// - 3: Exactly where they are, instead of the block they're in. Older saves put them at its
+///   corner.
+const FORMAT_VERSION: u8 = 3;
+/// Version 0 saves are just their fields, and are always this long. Ones from before experience
+/// are [HEALTH_ONLY_LEN] long, and those players start with none.
+const UNVERSIONED_LEN: usize = 24;
+const HEALTH_ONLY_LEN: usize = 20;
+
+/// What's kept about a player between sessions.
+///
+/// - `inventory`: Only the slots are saved. Whatever's on the cursor is dropped, like it is
+///   when the inventory is closed.
+/// - `dimension`: The name of the dimension they're in, like `minecraft:the_nether`.
+#[derive(Debug, Clone)]
+pub struct PlayerData {
+    pub position: ExactPosition,
+    pub rotation: Rotation,
+    pub gamemode: GameMode,
+    pub health: Health,
+    pub food: Food,
+    pub experience: Experience,
+    pub inventory: Inventory,
+    pub held_item: u8,
+    pub dimension: String,
+}
+
+impl PlayerData {
+    /// What players start with the first time they join: at spawn in the overworld in the
+    /// config's `default_gamemode`, with full health and food, and nothing else.
+    pub fn first_join() -> Self {
+        Self {
+            position: ExactPosition::corner_of(&spawn_position()),
+            rotation: Rotation::new(init::DEFAULT_SPAWN_YAW, init::DEFAULT_SPAWN_PITCH),
+            gamemode: get_global_config().default_gamemode,
+            health: Health::default(),
+            food: Food::default(),
+            experience: Experience::default(),
+            inventory: Inventory::default(),
+            held_item: 0,
+            dimension: OVERWORLD.to_string(),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![FORMAT_VERSION];
+        bytes.extend_from_slice(&self.health.health.to_le_bytes());
+        bytes.extend_from_slice(&self.health.max.to_le_bytes());
+        bytes.extend_from_slice(&self.food.level.to_le_bytes());
+        bytes.extend_from_slice(&self.food.saturation.to_le_bytes());
+        bytes.extend_from_slice(&self.food.exhaustion.to_le_bytes());
+        // The level and bar come from the total, so they're not saved.
+        bytes.extend_from_slice(&self.experience.total.to_le_bytes());
+
+        let (x, y, z) = self.position.0;
+        bytes.extend_from_slice(&x.to_le_bytes());
+        bytes.extend_from_slice(&y.to_le_bytes());
+        bytes.extend_from_slice(&z.to_le_bytes());
+        bytes.extend_from_slice(&self.rotation.yaw.to_le_bytes());
+        bytes.extend_from_slice(&self.rotation.pitch.to_le_bytes());
+        bytes.push(self.gamemode.id());
+        bytes.push(self.held_item);
+        bytes.extend_from_slice(&(self.inventory.slots.len() as u16).to_le_bytes());
+        for slot in &self.inventory.slots {
+            let Some(stack) = &slot.0 else {
+                bytes.push(0);
+                continue;
+            };
+            bytes.push(1);
+            bytes.extend_from_slice(&stack.item_id.to_le_bytes());
+            bytes.extend_from_slice(&stack.count.to_le_bytes());
+            let nbt = stack.nbt.as_deref().unwrap_or_default();
+            bytes.extend_from_slice(&(nbt.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(nbt);
+        }
+        bytes.extend_from_slice(&(self.dimension.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(self.dimension.as_bytes());
+        bytes
+    }
+
+    /// `None` if the bytes are corrupted, or were saved by a newer version of the server.
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (version, mut reader) = if matches!(bytes.len(), HEALTH_ONLY_LEN | UNVERSIONED_LEN) {
+            (0, Reader(bytes))
+        } else {
+            let (version, rest) = bytes.split_first()?;
+            (*version, Reader(rest))
+        };
+        if version > FORMAT_VERSION {
+            return None;
+        }
+
+        let mut data = Self::first_join();
+        data.health = Health {
+            health: f32::from_le_bytes(reader.take()?),
+            max: f32::from_le_bytes(reader.take()?),
+        };
+        data.food = Food {
+            level: i32::from_le_bytes(reader.take()?),
+            saturation: f32::from_le_bytes(reader.take()?),
+            exhaustion: f32::from_le_bytes(reader.take()?),
+            ..Food::default()
+        };
+        if bytes.len() == HEALTH_ONLY_LEN {
+            return Some(data);
+        }
+        data.experience = Experience::from_total(i32::from_le_bytes(reader.take()?));
+        if version < 1 {
+            return Some(data);
+        }
+
+        data.position = if version < 3 {
+            ExactPosition::corner_of(&Position::new(
+                i32::from_le_bytes(reader.take()?),
+                i16::from_le_bytes(reader.take()?),
+                i32::from_le_bytes(reader.take()?),
+            ))
+        } else {
+            let (x, y, z) = (
+                f64::from_le_bytes(reader.take()?),
+                f64::from_le_bytes(reader.take()?),
+                f64::from_le_bytes(reader.take()?),
+            );
+            if !(x.is_finite() && y.is_finite() && z.is_finite()) {
+                return None;
+            }
+            ExactPosition((x, y, z))
+        };
+        data.rotation = Rotation::new(
+            f32::from_le_bytes(reader.take()?),
+            f32::from_le_bytes(reader.take()?),
+        );
+        data.gamemode = GameMode::from_id(u8::from_le_bytes(reader.take()?))?;
+        data.held_item = u8::from_le_bytes(reader.take()?);
+        if data.held_item >= HOTBAR_SLOTS {
+            return None;
+        }
+        let slots = u16::from_le_bytes(reader.take()?) as usize;
+        if slots != data.inventory.slots.len() {
+            return None;
+        }
+        for slot in data.inventory.slots.iter_mut() {
+            if u8::from_le_bytes(reader.take()?) == 0 {
+                continue;
+            }
+            let mut stack = ItemStack::new(
+                i32::from_le_bytes(reader.take()?),
+                i8::from_le_bytes(reader.take()?),
+            );
+            let nbt_len = u32::from_le_bytes(reader.take()?) as usize;
+            if nbt_len > 0 {
+                stack.nbt = Some(reader.take_slice(nbt_len)?.to_vec());
+            }
+            *slot = Slot::from(stack);
+        }
+        if version < 2 {
+            return Some(data);
+        }
+
+        let dimension_len = u16::from_le_bytes(reader.take()?) as usize;
+        data.dimension = String::from_utf8(reader.take_slice(dimension_len)?.to_vec()).ok()?;
+        Some(data)
+    }
+}
+
+/// Reads a save from the front, failing once it runs out.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take_slice(N)?.try_into().ok()
+    }
+
+    fn take_slice(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(taken)
+    }
+}
+
+/// Loads what was saved about a player, or `None` if they're new here. Players whose data is
+/// corrupted are treated as new.
+pub async fn load_player_data(database: &Database, uuid: u128) -> Result<Option<PlayerData>> {
+    let Some(bytes) = database.get_player_data(uuid).await? else {
+        return Ok(None);
+    };
+    let data = PlayerData::from_bytes(&bytes);
+    if data.is_none() {
+        warn!("The saved data for player {:032x} is corrupted", uuid);
+    }
+    Ok(data)
+}
+
+impl ServerState {
+    /// Saves where a player is, what they have and how they're doing, so they have it all again
+    /// next time they join.
+    pub async fn save_player_data(&self, conn_id: ConnectionId) -> Result<()> {
+        let component_storage = self.world.get_component_storage();
+        let uuid = component_storage.get::<Player>(conn_id).await?.uuid;
+        let data = PlayerData {
+            position: *component_storage.get::<ExactPosition>(conn_id).await?,
+            rotation: component_storage.get::<Rotation>(conn_id).await?.clone(),
+            gamemode: *component_storage.get::<GameMode>(conn_id).await?,
+            health: component_storage.get::<Health>(conn_id).await?.clone(),
+            food: component_storage.get::<Food>(conn_id).await?.clone(),
+            experience: *component_storage.get::<Experience>(conn_id).await?,
+            inventory: Inventory {
+                slots: component_storage
+                    .get::<Inventory>(conn_id)
+                    .await?
+                    .slots
+                    .clone(),
+                ..Inventory::default()
+            },
+            held_item: component_storage.get::<HeldItem>(conn_id).await?.slot,
+            dimension: self.dimension_of(conn_id).await.name.clone(),
+        };
+        self.database.set_player_data(uuid, data.to_bytes()).await
+    }
+
+    /// Saves every player that's online. Returns how many were saved.
+    pub async fn save_players(&self) -> Result<usize> {
+        let players = self
+            .world
+            .query::<&Player>()
+            .iter()
+            .await
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        let mut saved = 0;
+        for conn_id in players {
+            match self.save_player_data(conn_id).await {
+                Ok(()) => saved += 1,
+                // They could have left while the others were being saved.
+                Err(Error::ComponentNotFound(..)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(saved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::components::inventory::{HOTBAR, OFFHAND};
+    use crate::world::dimensions::THE_NETHER;
+
+    use super::*;
+
+    fn full_data() -> PlayerData {
+        let mut inventory = Inventory::default();
+        inventory.slots[HOTBAR.start + 4] = Slot::from(ItemStack::new(1, 64));
+        inventory.slots[OFFHAND] = Slot::from(ItemStack {
+            item_id: 802,
+            count: 1,
+            nbt: Some(vec![0x0A, 0x00, 0x00, 0x00]),
+        });
+        inventory.cursor = Slot::from(ItemStack::new(5, 3));
+        PlayerData {
+            position: ExactPosition((-1_234.25, -60.0, 98_765.5)),
+            rotation: Rotation::new(-90.5, 45.0),
+            gamemode: GameMode::Adventure,
+            health: Health {
+                health: 7.5,
+                max: 20.0,
+            },
+            food: Food {
+                level: 12,
+                saturation: 1.25,
+                exhaustion: 3.0,
+                tick_timer: 40,
+            },
+            experience: Experience::from_total(400),
+            inventory,
+            held_item: 4,
+            dimension: THE_NETHER.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_player_data_round_trips_through_bytes() {
+        let data = full_data();
+        let loaded = PlayerData::from_bytes(&data.to_bytes()).unwrap();
+        assert_eq!(loaded.position, data.position);
+        assert_eq!(loaded.rotation.yaw, -90.5);
+        assert_eq!(loaded.rotation.pitch, 45.0);
+        assert_eq!(loaded.gamemode, GameMode::Adventure);
+        assert_eq!(loaded.health.health, 7.5);
+        assert_eq!(loaded.health.max, 20.0);
+        assert_eq!(loaded.food.level, 12);
+        assert_eq!(loaded.food.saturation, 1.25);
+        assert_eq!(loaded.food.exhaustion, 3.0);
+        // The timer isn't worth saving.
+        assert_eq!(loaded.food.tick_timer, 0);
+        assert_eq!(loaded.experience, data.experience);
+        assert_eq!(loaded.inventory.slots, data.inventory.slots);
+        assert_eq!(loaded.inventory.cursor, Slot::EMPTY);
+        assert_eq!(loaded.held_item, 4);
+        assert_eq!(loaded.dimension, THE_NETHER);
+    }
+
+    /// A save like `bytes`, but from `version`, before exact positions, with the block the
+    /// player is in instead.
+    fn with_block_position(bytes: &[u8], version: u8) -> Vec<u8> {
+        // The position comes after the version and 6 fields.
+        let start = 1 + 24;
+        let position = full_data().position.block();
+        let mut old = vec![version];
+        old.extend_from_slice(&bytes[1..start]);
+        old.extend_from_slice(&position.x.to_le_bytes());
+        old.extend_from_slice(&position.y.to_le_bytes());
+        old.extend_from_slice(&position.z.to_le_bytes());
+        old.extend_from_slice(&bytes[start + 24..]);
+        old
+    }
+
+    #[test]
+    fn test_saves_from_before_exact_positions_are_at_the_corner() {
+        let loaded = PlayerData::from_bytes(&with_block_position(&full_data().to_bytes(), 2));
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.position.0, (-1_235.0, -60.0, 98_765.0));
+        assert_eq!(loaded.held_item, 4);
+        assert_eq!(loaded.dimension, THE_NETHER);
+    }
+
+    #[test]
+    fn test_saves_from_before_dimensions_are_in_the_overworld() {
+        let mut bytes = full_data().to_bytes();
+        let dimension_len = 2 + THE_NETHER.len();
+        bytes.truncate(bytes.len() - dimension_len);
+        let bytes = with_block_position(&bytes, 1);
+
+        let loaded = PlayerData::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.position.0, (-1_235.0, -60.0, 98_765.0));
+        assert_eq!(loaded.held_item, 4);
+        assert_eq!(loaded.dimension, OVERWORLD);
+    }
+
+    #[test]
+    fn test_unversioned_saves_still_load() {
+        let data = full_data();
+        let bytes = data.to_bytes();
+        assert_eq!(bytes[0], FORMAT_VERSION);
+        let unversioned = &bytes[1..UNVERSIONED_LEN + 1];
+
+        let loaded = PlayerData::from_bytes(unversioned).unwrap();
+        assert_eq!(loaded.health.health, 7.5);
+        assert_eq!(loaded.experience, data.experience);
+        // Everything saved since is the same as for a new player.
+        let new = PlayerData::first_join();
+        assert_eq!(loaded.position, new.position);
+        assert_eq!(loaded.gamemode, new.gamemode);
+        assert_eq!(loaded.inventory.slots, new.inventory.slots);
+    }
+
+    #[test]
+    fn test_saves_from_before_experience_start_with_none() {
+        let bytes = full_data().to_bytes();
+        let loaded = PlayerData::from_bytes(&bytes[1..HEALTH_ONLY_LEN + 1]).unwrap();
+        assert_eq!(loaded.health.health, 7.5);
+        assert_eq!(loaded.food.exhaustion, 3.0);
+        assert_eq!(loaded.experience, Experience::default());
+    }
+
+    #[test]
+    fn test_bad_saves_are_turned_down() {
+        let bytes = full_data().to_bytes();
+        assert!(PlayerData::from_bytes(&[1, 2, 3]).is_none());
+        assert!(PlayerData::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+
+        let mut newer = bytes.clone();
+        newer[0] = FORMAT_VERSION + 1;
+        assert!(PlayerData::from_bytes(&newer).is_none());
+
+        // The gamemode comes after the version, 6 fields, position and rotation.
+        let mut bad_gamemode = bytes.clone();
+        bad_gamemode[1 + 24 + 24 + 8] = 9;
+        assert!(PlayerData::from_bytes(&bad_gamemode).is_none());
+
+        let mut bad_position = bytes;
+        bad_position[1 + 24..1 + 24 + 8].copy_from_slice(&f64::NAN.to_le_bytes());
+        assert!(PlayerData::from_bytes(&bad_position).is_none());
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use tracing::{debug, trace, warn};
+
+use crate::net::packets::outgoing::plugin_message::{PluginMessage, BRAND_CHANNEL};
+use crate::net::packets::ConnectionId;
+use crate::state::{GlobalState, ServerState};
+use crate::utils::impls::packet_impls::NetDecode;
+use crate::utils::prelude::*;
+
+/// Clients list the channels they can receive on this one.
+pub const REGISTER_CHANNEL: &str = "minecraft:register";
+/// And take channels back off the list on this one.
+pub const UNREGISTER_CHANNEL: &str = "minecraft:unregister";
+/// How many channels a client can register. Same as Bukkit, so clients can't make the server
+/// keep track of as many as they like.
+pub const MAX_REGISTERED_CHANNELS: usize = 128;
+
+pub type PluginMessageFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+type PluginMessageHandler = Arc<dyn Fn(PluginMessageContext) -> PluginMessageFuture + Send + Sync>;
+
+/// Everything a plugin channel handler gets.
+///
+/// - `conn_id`: Who sent the payload.
+/// - `data`: The payload, which is the rest of the packet after the channel.
+pub struct PluginMessageContext {
+    pub state: GlobalState,
+    pub conn_id: ConnectionId,
+    pub channel: String,
+    pub data: Vec<u8>,
+}
+
+/// The plugin channels the server listens on, and what to do with payloads sent on them.
+///
+/// Anything from the server can listen on a channel, see [ServerState::register_plugin_channel].
+/// Payloads on channels nobody listens on are dropped, and counted in
+/// [PluginChannels::unknown_messages].
+#[derive(Default)]
+pub struct PluginChannels {
+    handlers: RwLock<HashMap<String, PluginMessageHandler>>,
+    unknown_messages: AtomicU64,
+}
+
+impl PluginChannels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Channels with the server's own handlers, e.g. for the client's brand, already registered.
+    pub fn with_builtin_channels() -> Self {
+        let channels = Self::new();
+        channels.register(BRAND_CHANNEL, handle_brand);
+        channels
+    }
+
+    /// Listens on `channel`, replacing whatever was listening on it before. Returns `false`,
+    /// without registering anything, if `channel` isn't a valid identifier.
+    pub fn register<F, Fut>(&self, channel: &str, handler: F) -> bool
+    where
+        F: Fn(PluginMessageContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let Some(channel) = parse_channel(channel) else {
+            warn!("Can't listen on {}, it isn't a valid channel", channel);
+            return false;
+        };
+        let handler: PluginMessageHandler = Arc::new(move |ctx| Box::pin(handler(ctx)));
+        self.handlers
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(channel, handler);
+        true
+    }
+
+    /// Stops listening on `channel`. Returns `false` if nothing was listening on it.
+    pub fn unregister(&self, channel: &str) -> bool {
+        let Some(channel) = parse_channel(channel) else {
+            return false;
+        };
+        self.handlers
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&channel)
+            .is_some()
+    }
+
+    pub fn is_registered(&self, channel: &str) -> bool {
+        parse_channel(channel).is_some_and(|channel| {
+            self.handlers
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .contains_key(&channel)
+        })
+    }
+
+    /// How many payloads were dropped since the server started, because nothing was listening
+    /// on their channel.
+    pub fn unknown_messages(&self) -> u64 {
+        self.unknown_messages.load(Ordering::Relaxed)
+    }
+
+    /// Hands a payload to whatever's listening on its channel, or drops it if nothing is.
+    pub async fn dispatch(
+        &self,
+        state: GlobalState,
+        conn_id: ConnectionId,
+        channel: String,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        // Cloned out, so the lock isn't held while the handler runs.
+        let handler = self
+            .handlers
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&channel)
+            .cloned();
+        let Some(handler) = handler else {
+            self.unknown_messages.fetch_add(1, Ordering::Relaxed);
+            trace!(
+                "Dropping plugin message on {} from connection {}",
+                channel,
+                conn_id
+            );
+            return Ok(());
+        };
+
+        let ctx = PluginMessageContext {
+            state,
+            conn_id,
+            channel,
+            data,
+        };
+        handler(ctx).await
+    }
+}
+
+/// Parses a channel identifier, e.g. "minecraft:brand". Channels without a namespace are in the
+/// "minecraft" one, same as vanilla. Returns `None` if it isn't a valid identifier.
+pub fn parse_channel(channel: &str) -> Option<String> {
+    let (namespace, path) = channel.split_once(':').unwrap_or(("minecraft", channel));
+    let valid = |c: char| matches!(c, 'a'..='z' | '0'..='9' | '.' | '_' | '-');
+    if namespace.is_empty() || path.is_empty() {
+        return None;
+    }
+    if !namespace.chars().all(valid) || !path.chars().all(|c| valid(c) || c == '/') {
+        return None;
+    }
+    Some(format!("{}:{}", namespace, path))
+}
+
+/// The channels in a register or unregister payload, which are separated by null bytes. Invalid
+/// ones are left out.
+pub fn parse_channel_list(data: &[u8]) -> Vec<String> {
+    data.split(|&byte| byte == 0)
+        .filter_map(|channel| std::str::from_utf8(channel).ok())
+        .filter_map(parse_channel)
+        .collect()
+}
+
+async fn handle_brand(ctx: PluginMessageContext) -> Result<()> {
+    let mut data = ctx.data.as_slice();
+    let brand = *String::net_decode(&mut data).await?;
+    debug!("Connection {} is using the {} client", ctx.conn_id, brand);
+
+    let conn = ctx.state.connections.get_connection(ctx.conn_id)?;
+    conn.write().await.metadata.client_brand = Some(brand);
+    Ok(())
+}
+
+impl ServerState {
+    /// Listens on a plugin channel, see [PluginChannels::register].
+    ///
+    /// ```ignore
+    /// state.register_plugin_channel("example:hello", |ctx| async move {
+    ///     ctx.state.send_plugin_message(ctx.conn_id, "example:hello", ctx.data).await
+    /// });
+    /// ```
+    pub fn register_plugin_channel<F, Fut>(&self, channel: &str, handler: F) -> bool
+    where
+        F: Fn(PluginMessageContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.plugin_channels.register(channel, handler)
+    }
+
+    /// Sends a payload to one player on a plugin channel.
+    pub async fn send_plugin_message(
+        &self,
+        conn_id: ConnectionId,
+        channel: &str,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let conn = self.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(PluginMessage::new(channel, data)).await
+    }
+
+    /// Sends a payload to every player that's in the game on a plugin channel. Players it can't
+    /// be sent to are skipped.
+    pub async fn broadcast_plugin_message(&self, channel: &str, data: Vec<u8>) {
+        self.broadcast(|| PluginMessage::new(channel, data.clone()))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_channel() {
+        assert_eq!(parse_channel("minecraft:brand").unwrap(), "minecraft:brand");
+        assert_eq!(parse_channel("brand").unwrap(), "minecraft:brand");
+        assert_eq!(
+            parse_channel("my_mod:config/sync").unwrap(),
+            "my_mod:config/sync"
+        );
+        assert!(parse_channel("Upper:case").is_none());
+        assert!(parse_channel("a:b:c").is_none());
+        assert!(parse_channel("namespace/slash:path").is_none());
+        assert!(parse_channel(":path").is_none());
+        assert!(parse_channel("").is_none());
+    }
+
+    #[test]
+    fn test_parse_channel_list() {
+        let channels = parse_channel_list(b"fabric:registry\0bad channel\0\0worldedit:cui");
+        assert_eq!(channels, ["fabric:registry", "worldedit:cui"]);
+    }
+
+    #[test]
+    fn test_register_channels() {
+        let channels = PluginChannels::new();
+        assert!(channels.register("example:hello", |_| async { Ok(()) }));
+        assert!(!channels.register("not valid", |_| async { Ok(()) }));
+        assert!(channels.is_registered("example:hello"));
+        assert!(channels.unregister("example:hello"));
+        assert!(!channels.is_registered("example:hello"));
+        assert!(!channels.unregister("example:hello"));
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use tokio::time::MissedTickBehavior;
+use tracing::warn;
+
+use crate::state::GlobalState;
+
+/// How many of the slowest systems are named when a tick takes too long.
+const SLOWEST_SYSTEMS_LOGGED: usize = 3;
+
+pub type TickFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+type TickHandler = Arc<dyn Fn(GlobalState) -> TickFuture + Send + Sync>;
+
+struct ScheduledSystem {
+    name: &'static str,
+    every: u64,
+    handler: TickHandler,
+}
+
+/// How long a system has been taking.
+///
+/// - `last`: How long its last run took.
+/// - `max`: Its slowest run.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SystemTimings {
+    pub runs: u64,
+    pub last: Duration,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl SystemTimings {
+    fn record(&mut self, duration: Duration) {
+        self.runs += 1;
+        self.last = duration;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    /// How long its runs took on average.
+    pub fn average(&self) -> Duration {
+        match self.runs {
+            0 => Duration::ZERO,
+            runs => self.total / runs as u32,
+        }
+    }
+}
+
+/// Runs the game's tick loop. Systems registered with [Scheduler::register] run one after the
+/// other on the ticks they're due, in the order they were registered.
+///
+/// A tick that takes longer than it has is logged along with its slowest systems, and the next
+/// one starts late instead of running back to back to catch up. How fast the server's actually
+/// ticking is in [ServerState::tick_rate](crate::state::ServerState::tick_rate).
+pub struct Scheduler {
+    tick_duration: Duration,
+    systems: RwLock<Vec<ScheduledSystem>>,
+    timings: Mutex<HashMap<&'static str, SystemTimings>>,
+    current_tick: AtomicU64,
+}
+
+impl Scheduler {
+    /// A scheduler running `ticks_per_second` ticks every second, without any systems. 0 is
+    /// taken as 1.
+    pub fn new(ticks_per_second: u32) -> Self {
+        Self {
+            tick_duration: Duration::from_secs(1) / ticks_per_second.max(1),
+            systems: RwLock::new(Vec::new()),
+            timings: Mutex::new(HashMap::new()),
+            current_tick: AtomicU64::new(0),
+        }
+    }
+
+    /// A scheduler with the server's own systems, e.g. for movement and keep alives, already
+    /// registered.
+    pub fn with_builtin_systems(ticks_per_second: u32) -> Self {
+        let scheduler = Self::new(ticks_per_second);
+        crate::net::systems::register_tick_systems(&scheduler);
+        scheduler
+    }
+
+    /// Runs `system` every `every` ticks, starting with the first. 0 is taken as 1.
+    pub fn register<F, Fut>(&self, name: &'static str, every: u64, system: F)
+    where
+        F: Fn(GlobalState) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handler: TickHandler = Arc::new(move |state| Box::pin(system(state)));
+        self.systems
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(ScheduledSystem {
+                name,
+                every: every.max(1),
+                handler,
+            });
+    }
+
+    /// How long each tick has, e.g. 50ms at 20 ticks per second.
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+
+    /// How many ticks go by every second, if none of them run late.
+    pub fn target_ticks_per_second(&self) -> f64 {
+        1.0 / self.tick_duration.as_secs_f64()
+    }
+
+    /// How many ticks fit in `duration`, for systems that run every so many seconds. At least 1.
+    pub fn ticks_in(&self, duration: Duration) -> u64 {
+        (duration.as_nanos() / self.tick_duration.as_nanos()).max(1) as u64
+    }
+
+    /// The tick that's running, or ran last. Counts up from 0.
+    pub fn current_tick(&self) -> u64 {
+        self.current_tick.load(Ordering::Relaxed)
+    }
+
+    /// How long each system has been taking, slowest on average first.
+    pub fn timings(&self) -> Vec<(&'static str, SystemTimings)> {
+        let mut timings = self
+            .timings
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(|(&name, &timings)| (name, timings))
+            .collect::<Vec<_>>();
+        timings.sort_by_key(|(_, timings)| std::cmp::Reverse(timings.average()));
+        timings
+    }
+
+    /// Ticks until the task is stopped, see
+    /// [TickSystem](crate::net::systems::tick_system::TickSystem).
+    pub async fn run(&self, state: &GlobalState) {
+        let mut interval = tokio::time::interval(self.tick_duration);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut tick = 0;
+        loop {
+            interval.tick().await;
+            self.current_tick.store(tick, Ordering::Relaxed);
+
+            let start = Instant::now();
+            let mut durations = self.run_tick(tick, state).await;
+            let elapsed = start.elapsed();
+            state.tick_rate.record(elapsed);
+
+            if elapsed > self.tick_duration {
+                durations.sort_by(|(_, a), (_, b)| b.cmp(a));
+                durations.truncate(SLOWEST_SYSTEMS_LOGGED);
+                warn!(
+                    "Tick {} took {:?}, more than the {:?} it has. Slowest systems: {:?}",
+                    tick, elapsed, self.tick_duration, durations
+                );
+            }
+            tick += 1;
+        }
+    }
+
+    /// Runs the systems that are due on `tick`. Returns how long each of them took.
+    async fn run_tick(&self, tick: u64, state: &GlobalState) -> Vec<(&'static str, Duration)> {
+        // Cloned out, so systems can register others without waiting on themselves.
+        let due = self
+            .systems
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .filter(|system| tick.is_multiple_of(system.every))
+            .map(|system| (system.name, system.handler.clone()))
+            .collect::<Vec<_>>();
+
+        let mut durations = Vec::with_capacity(due.len());
+        for (name, handler) in due {
+            let start = Instant::now();
+            handler(state.clone()).await;
+            let duration = start.elapsed();
+            durations.push((name, duration));
+            self.timings
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .entry(name)
+                .or_default()
+                .record(duration);
+        }
+        durations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::create_state;
+
+    #[test]
+    fn test_tick_duration() {
+        let scheduler = Scheduler::new(20);
+        assert_eq!(scheduler.tick_duration(), Duration::from_millis(50));
+        assert_eq!(scheduler.target_ticks_per_second(), 20.0);
+        assert_eq!(scheduler.ticks_in(Duration::from_secs(15)), 300);
+        assert_eq!(scheduler.ticks_in(Duration::ZERO), 1);
+        assert_eq!(Scheduler::new(0).tick_duration(), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_systems_run_on_their_ticks() {
+        let state = create_state(vec![TcpListener::bind("127.0.0.1:0").await.unwrap()])
+            .await
+            .unwrap();
+        let scheduler = Scheduler::new(20);
+        let every_tick = Arc::new(AtomicUsize::new(0));
+        let every_third = Arc::new(AtomicUsize::new(0));
+        {
+            let every_tick = every_tick.clone();
+            scheduler.register("every_tick", 1, move |_| {
+                let every_tick = every_tick.clone();
+                async move {
+                    every_tick.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+            let every_third = every_third.clone();
+            scheduler.register("every_third", 3, move |_| {
+                let every_third = every_third.clone();
+                async move {
+                    every_third.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+
+        for tick in 0..7 {
+            scheduler.run_tick(tick, &state).await;
+        }
+        assert_eq!(every_tick.load(Ordering::Relaxed), 7);
+        // Ticks 0, 3 and 6.
+        assert_eq!(every_third.load(Ordering::Relaxed), 3);
+
+        let timings = scheduler.timings();
+        assert_eq!(timings.len(), 2);
+        let (_, every_tick_timings) = timings
+            .iter()
+            .find(|(name, _)| *name == "every_tick")
+            .unwrap();
+        assert_eq!(every_tick_timings.runs, 7);
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tracing::warn;
+
+use crate::net::packets::outgoing::display_objective::{DisplayObjective, DisplaySlot};
+use crate::net::packets::outgoing::update_objectives::UpdateObjectives;
+use crate::net::packets::outgoing::update_score::UpdateScore;
+use crate::net::packets::ConnectionId;
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::state::GlobalState;
+use crate::utils::components::scoreboards::ShownScoreboards;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// Gives every board its own id, since different boards can have the same objective name.
+static NEXT_BOARD_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A scoreboard objective and its scores, shown to whichever players are added as viewers.
+///
+/// Every player can be shown a different board, e.g. a sidebar with their own stats, by giving
+/// each one a board of their own. A board everyone sees is just one with everyone added. The
+/// objective name only has to be unique among the boards one player can see.
+///
+/// Like [BossBar](crate::utils::boss_bar::BossBar), every change is sent to the viewers straight
+/// away, and the viewers are kept in each player's [ShownScoreboards].
+///
+/// ```ignore
+/// let mut board = Scoreboard::new(state, "stats", "Stats");
+/// board.set_display_slot(Some(DisplaySlot::Sidebar)).await;
+/// board.add_viewer(conn_id).await?;
+/// board.set_score("Kills", 3).await;
+/// ```
+pub struct Scoreboard {
+    state: GlobalState,
+    id: u64,
+    name: String,
+    display_name: TextComponent,
+    slot: Option<DisplaySlot>,
+    scores: HashMap<String, i32>,
+}
+
+impl Scoreboard {
+    /// An objective with no scores, not shown in any slot and with no viewers yet.
+    pub fn new(state: GlobalState, name: &str, display_name: impl Into<TextComponent>) -> Self {
+        Self {
+            state,
+            id: NEXT_BOARD_ID.fetch_add(1, Ordering::Relaxed),
+            name: name.to_string(),
+            display_name: display_name.into(),
+            slot: None,
+            scores: HashMap::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn score(&self, entry: &str) -> Option<i32> {
+        self.scores.get(entry).copied()
+    }
+
+    /// The players the board is shown to.
+    pub async fn viewers(&self) -> Vec<ConnectionId> {
+        self.state
+            .world
+            .query::<&ShownScoreboards>()
+            .iter()
+            .await
+            .filter(|(_, shown)| shown.boards.contains(&self.id))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Shows the board to a player, with all of its scores. Does nothing if they can already
+    /// see it.
+    pub async fn add_viewer(&self, conn_id: ConnectionId) -> Result<()> {
+        let conn = self.state.connections.get_connection(conn_id)?;
+        let added = self
+            .state
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with(conn_id, ShownScoreboards::default)
+            .await
+            .boards
+            .insert(self.id);
+        if !added {
+            return Ok(());
+        }
+
+        let mut packet_queue = PacketQueue::new();
+        packet_queue
+            .queue(UpdateObjectives::create(&self.name, &self.display_name)?)
+            .await?;
+        for (entry, &value) in &self.scores {
+            packet_queue
+                .queue(UpdateScore::update(&self.name, entry, value))
+                .await?;
+        }
+        if let Some(slot) = self.slot {
+            packet_queue
+                .queue(DisplayObjective::new(slot, &self.name))
+                .await?;
+        }
+        let conn = conn.read().await;
+        conn.send_packets(packet_queue).await
+    }
+
+    /// Takes the board away from a player. Does nothing if they couldn't see it.
+    pub async fn remove_viewer(&self, conn_id: ConnectionId) -> Result<()> {
+        let removed = match self
+            .state
+            .world
+            .get_component_storage()
+            .get_mut::<ShownScoreboards>(conn_id)
+            .await
+        {
+            Ok(mut shown) => shown.boards.remove(&self.id),
+            Err(_) => false,
+        };
+        if !removed {
+            return Ok(());
+        }
+        let conn = self.state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(UpdateObjectives::remove(&self.name)).await
+    }
+
+    /// Takes the board away from everyone that can see it.
+    pub async fn remove_all_viewers(&self) {
+        for conn_id in self.viewers().await {
+            if let Err(e) = self.remove_viewer(conn_id).await {
+                warn!("Failed to remove a scoreboard from {}: {}", conn_id, e);
+            }
+        }
+    }
+
+    pub async fn set_display_name(&mut self, display_name: impl Into<TextComponent>) -> Result<()> {
+        let display_name = display_name.into();
+        if display_name == self.display_name {
+            return Ok(());
+        }
+        let packet = UpdateObjectives::update_display_name(&self.name, &display_name)?;
+        self.display_name = display_name;
+        self.send_to_viewers(|| packet.clone()).await;
+        Ok(())
+    }
+
+    /// Shows the board in a slot, e.g. the sidebar, or takes it out of the one it's in with
+    /// `None`.
+    pub async fn set_display_slot(&mut self, slot: Option<DisplaySlot>) {
+        if slot == self.slot {
+            return;
+        }
+        let previous = std::mem::replace(&mut self.slot, slot);
+        let name = self.name.clone();
+        match slot {
+            Some(slot) => {
+                self.send_to_viewers(|| DisplayObjective::new(slot, &name))
+                    .await
+            }
+            None => {
+                if let Some(previous) = previous {
+                    self.send_to_viewers(|| DisplayObjective::clear(previous))
+                        .await
+                }
+            }
+        }
+    }
+
+    /// Sets the score of an entry, adding it if it isn't there yet. Nothing is sent if it
+    /// already had this score.
+    pub async fn set_score(&mut self, entry: &str, value: i32) {
+        if self.scores.get(entry) == Some(&value) {
+            return;
+        }
+        self.scores.insert(entry.to_string(), value);
+        let name = self.name.clone();
+        self.send_to_viewers(|| UpdateScore::update(&name, entry, value))
+            .await;
+    }
+
+    pub async fn remove_score(&mut self, entry: &str) {
+        if self.scores.remove(entry).is_none() {
+            return;
+        }
+        let name = self.name.clone();
+        self.send_to_viewers(|| UpdateScore::remove(&name, entry))
+            .await;
+    }
+
+    /// Replaces every score on the board, e.g. all the lines of a sidebar. Only the entries
+    /// that changed or went away are sent.
+    pub async fn set_scores(&mut self, scores: HashMap<String, i32>) {
+        let (updated, removed) = score_changes(&self.scores, &scores);
+        for entry in removed {
+            self.remove_score(&entry).await;
+        }
+        for (entry, value) in updated {
+            self.set_score(&entry, value).await;
+        }
+    }
+
+    /// Players it can't be sent to are skipped.
+    async fn send_to_viewers<P: ferrumc_codec::enc::NetEncode>(&self, packet: impl Fn() -> P) {
+        self.state
+            .send_to_players(self.viewers().await, packet)
+            .await;
+    }
+}
+
+/// The entries that have to be sent to go from `old` to `new` scores: the ones with a new or
+/// different score, and the ones that aren't there anymore.
+fn score_changes(
+    old: &HashMap<String, i32>,
+    new: &HashMap<String, i32>,
+) -> (Vec<(String, i32)>, Vec<String>) {
+    let updated = new
+        .iter()
+        .filter(|(entry, value)| old.get(*entry) != Some(value))
+        .map(|(entry, &value)| (entry.clone(), value))
+        .collect();
+    let removed = old
+        .keys()
+        .filter(|entry| !new.contains_key(*entry))
+        .cloned()
+        .collect();
+    (updated, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scores(entries: &[(&str, i32)]) -> HashMap<String, i32> {
+        entries
+            .iter()
+            .map(|&(entry, value)| (entry.to_string(), value))
+            .collect()
+    }
+
+    #[test]
+    fn test_only_changed_scores_are_sent() {
+        let old = scores(&[("Online", 1), ("TPS", 20), ("Kills", 3)]);
+        let new = scores(&[("Online", 2), ("TPS", 20), ("Deaths", 0)]);
+
+        let (mut updated, removed) = score_changes(&old, &new);
+        updated.sort();
+        assert_eq!(
+            updated,
+            vec![("Deaths".to_string(), 0), ("Online".to_string(), 2)]
+        );
+        assert_eq!(removed, vec!["Kills".to_string()]);
+
+        let (updated, removed) = score_changes(&new, &new);
+        assert!(updated.is_empty() && removed.is_empty());
+    }
+}
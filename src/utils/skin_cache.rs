@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::net::utils::authentication::ProfileProperty;
+use crate::utils::json_file::{read_json_file, write_json_file};
+use crate::utils::prelude::*;
+
+/// How long a skin is kept before it's looked up again, in seconds. Players that change their
+/// skin see it within a day.
+pub const SKIN_CACHE_TTL: u64 = 24 * 60 * 60;
+/// How long to wait before looking up a skin again after it was last looked up, in seconds, so
+/// a name whose lookup keeps failing isn't looked up on every join.
+pub const SKIN_RETRY_INTERVAL: u64 = 5 * 60;
+
+/// A looked up skin. `properties` is empty if there's no account with the name, which is kept
+/// too, so offline players with made up names aren't looked up on every join.
+///
+/// - `fetched_at`: When it was looked up, in seconds since the Unix epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedSkin {
+    pub fetched_at: u64,
+    pub properties: Vec<ProfileProperty>,
+}
+
+/// The skins looked up with the config's `fetch_skins`, by lowercase username, kept in a JSON
+/// file so they survive restarts.
+///
+/// - `lookups`: When each name that isn't cached was last looked up, see
+///   [SkinCache::start_lookup]. Only kept in memory.
+pub struct SkinCache {
+    path: PathBuf,
+    entries: Mutex<HashMap<String, CachedSkin>>,
+    lookups: Mutex<HashMap<String, u64>>,
+}
+
+impl SkinCache {
+    /// Loads the cache from `path`. Starts out empty if the file doesn't exist yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entries: HashMap<String, CachedSkin> = read_json_file(&path)?;
+        debug!("Loaded {} cached skins", entries.len());
+
+        Ok(Self {
+            path,
+            entries: Mutex::new(entries),
+            lookups: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The skin cached for `username`, unless it's older than [SKIN_CACHE_TTL] at `now`.
+    pub async fn get(&self, username: &str, now: u64) -> Option<Vec<ProfileProperty>> {
+        self.entries
+            .lock()
+            .await
+            .get(&username.to_lowercase())
+            .filter(|cached| now.saturating_sub(cached.fetched_at) < SKIN_CACHE_TTL)
+            .map(|cached| cached.properties.clone())
+    }
+
+    /// Whether `username`'s skin should be looked up at `now`, which it isn't if it was already
+    /// looked up in the last [SKIN_RETRY_INTERVAL], whether that's still going or failed. If it
+    /// should be, it counts as looked up from now.
+    pub async fn start_lookup(&self, username: &str, now: u64) -> bool {
+        let mut lookups = self.lookups.lock().await;
+        lookups.retain(|_, started| now.saturating_sub(*started) < SKIN_RETRY_INTERVAL);
+        let username = username.to_lowercase();
+        if lookups.contains_key(&username) {
+            return false;
+        }
+        lookups.insert(username, now);
+        true
+    }
+
+    /// Caches `properties` for `username`, and writes the cache to its file. Skins that expired
+    /// are dropped while it's at it.
+    pub async fn insert(
+        &self,
+        username: &str,
+        properties: Vec<ProfileProperty>,
+        now: u64,
+    ) -> Result<()> {
+        let mut entries = self.entries.lock().await;
+        entries.retain(|_, cached| now.saturating_sub(cached.fetched_at) < SKIN_CACHE_TTL);
+        entries.insert(
+            username.to_lowercase(),
+            CachedSkin {
+                fetched_at: now,
+                properties,
+            },
+        );
+        self.lookups.lock().await.remove(&username.to_lowercase());
+        write_json_file(&self.path, &*entries).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn textures() -> Vec<ProfileProperty> {
+        vec![ProfileProperty {
+            name: "textures".to_string(),
+            value: "abc".to_string(),
+            signature: Some("def".to_string()),
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_skins_are_persisted_until_they_expire() {
+        let path = std::env::temp_dir().join(format!("ferrumc-skins-{}.json", Uuid::new_v4()));
+
+        let cache = SkinCache::load(&path).unwrap();
+        assert!(cache.get("Notch", 100).await.is_none());
+        cache.insert("Notch", textures(), 100).await.unwrap();
+        cache.insert("nobody_here", Vec::new(), 100).await.unwrap();
+
+        let reloaded = SkinCache::load(&path).unwrap();
+        let skin = reloaded
+            .get("notch", 100 + SKIN_CACHE_TTL - 1)
+            .await
+            .unwrap();
+        assert_eq!(skin[0].signature.as_deref(), Some("def"));
+        assert!(reloaded.get("nobody_here", 100).await.unwrap().is_empty());
+        assert!(reloaded.get("Notch", 100 + SKIN_CACHE_TTL).await.is_none());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_lookups_are_not_repeated() {
+        let path = std::env::temp_dir().join(format!("ferrumc-skins-{}.json", Uuid::new_v4()));
+        let cache = SkinCache::load(&path).unwrap();
+
+        assert!(cache.start_lookup("Notch", 100).await);
+        // Still going, or it failed.
+        assert!(!cache.start_lookup("notch", 101).await);
+        assert!(cache.start_lookup("jeb_", 101).await);
+        assert!(cache.start_lookup("Notch", 100 + SKIN_RETRY_INTERVAL).await);
+
+        // Once it's cached, the next lookup is whenever that expires.
+        cache.insert("jeb_", textures(), 102).await.unwrap();
+        assert!(cache.start_lookup("jeb_", 103).await);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
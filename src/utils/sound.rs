@@ -0,0 +1,254 @@
+use crate::net::packets::outgoing::entity_sound_effect::EntitySoundEffect;
+use crate::net::packets::outgoing::sound_effect::{SoundEffect, SoundEvent};
+use crate::net::packets::ConnectionId;
+use crate::state::ServerState;
+use crate::utils::encoding::position::Position;
+use crate::world::dimensions::Dimension;
+
+/// How far away a sound at full volume can be heard, in blocks, same as vanilla. Louder sounds
+/// carry further.
+pub const SOUND_RANGE: f64 = 16.0;
+
+/// The sounds the server plays, so their identifiers aren't spelled out everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    BlockStoneBreak,
+    BlockStonePlace,
+    BlockWoodBreak,
+    BlockWoodPlace,
+    BlockGrassBreak,
+    BlockGrassPlace,
+    BlockGravelBreak,
+    BlockSandBreak,
+    BlockGlassBreak,
+    BlockWoolBreak,
+    EntityItemPickup,
+    EntityPlayerHurt,
+    EntityPlayerDeath,
+    EntityPlayerLevelUp,
+    EntityExperienceOrbPickup,
+    UiButtonClick,
+}
+
+impl Sound {
+    pub fn identifier(self) -> &'static str {
+        match self {
+            Sound::BlockStoneBreak => "minecraft:block.stone.break",
+            Sound::BlockStonePlace => "minecraft:block.stone.place",
+            Sound::BlockWoodBreak => "minecraft:block.wood.break",
+            Sound::BlockWoodPlace => "minecraft:block.wood.place",
+            Sound::BlockGrassBreak => "minecraft:block.grass.break",
+            Sound::BlockGrassPlace => "minecraft:block.grass.place",
+            Sound::BlockGravelBreak => "minecraft:block.gravel.break",
+            Sound::BlockSandBreak => "minecraft:block.sand.break",
+            Sound::BlockGlassBreak => "minecraft:block.glass.break",
+            Sound::BlockWoolBreak => "minecraft:block.wool.break",
+            Sound::EntityItemPickup => "minecraft:entity.item.pickup",
+            Sound::EntityPlayerHurt => "minecraft:entity.player.hurt",
+            Sound::EntityPlayerDeath => "minecraft:entity.player.death",
+            Sound::EntityPlayerLevelUp => "minecraft:entity.player.levelup",
+            Sound::EntityExperienceOrbPickup => "minecraft:entity.experience_orb.pickup",
+            Sound::UiButtonClick => "minecraft:ui.button.click",
+        }
+    }
+
+    /// The sound a block makes when it's broken, going by its name. Blocks that don't look like
+    /// anything else sound like stone.
+    pub fn block_break(block_name: &str) -> Sound {
+        let name = block_name.strip_prefix("minecraft:").unwrap_or(block_name);
+        if name.contains("glass") || name.contains("ice") {
+            Sound::BlockGlassBreak
+        } else if name.contains("wool") || name.contains("carpet") {
+            Sound::BlockWoolBreak
+        } else if [
+            "planks",
+            "log",
+            "wood",
+            "fence",
+            "door",
+            "chest",
+            "bookshelf",
+        ]
+        .iter()
+        .any(|wood| name.contains(wood))
+        {
+            Sound::BlockWoodBreak
+        } else if ["grass", "dirt", "leaves", "farmland", "podzol", "mycelium"]
+            .iter()
+            .any(|grass| name.contains(grass))
+        {
+            Sound::BlockGrassBreak
+        } else if name == "gravel" {
+            Sound::BlockGravelBreak
+        } else if name.ends_with("sand") || name.contains("concrete_powder") {
+            Sound::BlockSandBreak
+        } else {
+            Sound::BlockStoneBreak
+        }
+    }
+}
+
+impl From<Sound> for SoundEvent {
+    fn from(sound: Sound) -> Self {
+        SoundEvent::Named {
+            identifier: sound.identifier().to_string(),
+            fixed_range: None,
+        }
+    }
+}
+
+/// Which volume slider in the client's settings a sound is under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundCategory {
+    Master,
+    Music,
+    Records,
+    Weather,
+    Blocks,
+    Hostile,
+    Neutral,
+    Players,
+    Ambient,
+    Voice,
+}
+
+impl SoundCategory {
+    pub fn id(self) -> i32 {
+        self as i32
+    }
+}
+
+/// Whether a sound at `sound` can be heard at `listener`.
+pub fn is_audible(sound: (f64, f64, f64), listener: (f64, f64, f64), volume: f32) -> bool {
+    let range = SOUND_RANGE * (volume as f64).max(1.0);
+    let (dx, dy, dz) = (
+        sound.0 - listener.0,
+        sound.1 - listener.1,
+        sound.2 - listener.2,
+    );
+    dx * dx + dy * dy + dz * dz <= range * range
+}
+
+impl ServerState {
+    /// Plays a sound at `position` in `dimension` for every player close enough to hear it,
+    /// except `except`.
+    ///
+    /// Everyone gets the same seed, so they all hear the same variant of the sound. Players it
+    /// can't be sent to are skipped.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn play_sound_at(
+        &self,
+        dimension: &Dimension,
+        position: (f64, f64, f64),
+        sound: Sound,
+        category: SoundCategory,
+        volume: f32,
+        pitch: f32,
+        except: Option<ConnectionId>,
+    ) {
+        let seed = rand::random::<i64>();
+        let block = Position::new(
+            position.0.floor() as i32,
+            position.1.floor() as i16,
+            position.2.floor() as i32,
+        );
+        // Going by blocks is off by less than two blocks either way, so this has everyone that
+        // could hear it, and [is_audible] sorts out the rest.
+        let range = SOUND_RANGE * (volume as f64).max(1.0) + 2.0;
+        let listeners = dimension
+            .players
+            .players_in_radius(&block, range)
+            .into_iter()
+            .filter(|(id, listener)| {
+                Some(*id) != except && is_audible(position, block_center(listener), volume)
+            })
+            .map(|(id, _)| id);
+        self.send_to_players(listeners, || {
+            SoundEffect::new(sound.into(), category.id(), position, volume, pitch, seed)
+        })
+        .await;
+    }
+
+    /// Plays a sound that follows `entity_id` around for every player near it.
+    pub async fn play_sound_from_entity(
+        &self,
+        entity_id: usize,
+        sound: Sound,
+        category: SoundCategory,
+        volume: f32,
+        pitch: f32,
+    ) {
+        let Ok(position) = self.world.get_component::<Position>(entity_id).await else {
+            return;
+        };
+        let position = position.clone();
+        let Ok(network_id) = self.network_id(entity_id).await else {
+            return;
+        };
+        let dimension = self.dimension_of(entity_id).await;
+        let seed = rand::random::<i64>();
+        self.send_to_players_near(&dimension, &position, None, || {
+            EntitySoundEffect::new(
+                sound.into(),
+                category.id(),
+                network_id,
+                volume,
+                pitch,
+                seed,
+            )
+        })
+        .await;
+    }
+}
+
+/// The middle of the block at `position`, which is where players are as far as the server knows.
+pub fn block_center(position: &Position) -> (f64, f64, f64) {
+    (
+        position.x as f64 + 0.5,
+        position.y as f64 + 0.5,
+        position.z as f64 + 0.5,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_break_sounds() {
+        assert_eq!(
+            Sound::block_break("minecraft:stone"),
+            Sound::BlockStoneBreak
+        );
+        assert_eq!(
+            Sound::block_break("minecraft:oak_planks"),
+            Sound::BlockWoodBreak
+        );
+        assert_eq!(
+            Sound::block_break("minecraft:grass_block"),
+            Sound::BlockGrassBreak
+        );
+        assert_eq!(
+            Sound::block_break("minecraft:red_sand"),
+            Sound::BlockSandBreak
+        );
+        assert_eq!(
+            Sound::block_break("minecraft:sandstone"),
+            Sound::BlockStoneBreak
+        );
+        assert_eq!(
+            Sound::block_break("minecraft:white_stained_glass"),
+            Sound::BlockGlassBreak
+        );
+    }
+
+    #[test]
+    fn test_louder_sounds_carry_further() {
+        let origin = (0.0, 64.0, 0.0);
+        assert!(is_audible(origin, (16.0, 64.0, 0.0), 1.0));
+        assert!(!is_audible(origin, (16.0, 65.0, 0.0), 1.0));
+        // Quieter sounds can still be heard just as far, they're only quieter.
+        assert!(is_audible(origin, (16.0, 64.0, 0.0), 0.2));
+        assert!(is_audible(origin, (30.0, 64.0, 0.0), 2.0));
+    }
+}
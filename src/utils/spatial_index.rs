@@ -0,0 +1,384 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::RwLock;
+
+use crate::net::packets::ConnectionId;
+use crate::utils::encoding::position::Position;
+
+/// Where every player in the world is, bucketed by the chunk they're in, so finding the players
+/// near somewhere only has to look at the chunks around it instead of at every player.
+///
+/// Whatever moves a player has to tell the index with [SpatialIndex::update]. Players are added
+/// when they join the world and removed when they leave.
+#[derive(Default)]
+pub struct SpatialIndex {
+    inner: RwLock<Buckets>,
+}
+
+#[derive(Default)]
+struct Buckets {
+    chunks: HashMap<(i32, i32), HashSet<ConnectionId>>,
+    players: HashMap<ConnectionId, IndexedPlayer>,
+    /// How many players have each view distance, so the largest one is known without looking
+    /// at every player.
+    view_distances: BTreeMap<i32, usize>,
+}
+
+struct IndexedPlayer {
+    position: Position,
+    view_distance: i32,
+}
+
+fn chunk_of(position: &Position) -> (i32, i32) {
+    (position.x >> 4, position.z >> 4)
+}
+
+impl Buckets {
+    fn add_view_distance(&mut self, view_distance: i32) {
+        *self.view_distances.entry(view_distance).or_default() += 1;
+    }
+
+    fn remove_view_distance(&mut self, view_distance: i32) {
+        if let Some(count) = self.view_distances.get_mut(&view_distance) {
+            *count -= 1;
+            if *count == 0 {
+                self.view_distances.remove(&view_distance);
+            }
+        }
+    }
+
+    fn remove_from_chunk(&mut self, id: ConnectionId, chunk: (i32, i32)) {
+        if let Some(bucket) = self.chunks.get_mut(&chunk) {
+            bucket.remove(&id);
+            if bucket.is_empty() {
+                self.chunks.remove(&chunk);
+            }
+        }
+    }
+
+    /// Every player in a chunk at most `radius` chunks from `chunk` in either direction. Goes
+    /// through whichever is fewer, the chunks in that square or the chunks with players in them.
+    fn players_around(&self, chunk: (i32, i32), radius: i32) -> Vec<ConnectionId> {
+        let (chunk_x, chunk_z) = chunk;
+        let side = 2 * radius as usize + 1;
+        if side.saturating_mul(side) > self.chunks.len() {
+            return self
+                .chunks
+                .iter()
+                .filter(|((x, z), _)| (x - chunk_x).abs().max((z - chunk_z).abs()) <= radius)
+                .flat_map(|(_, bucket)| bucket.iter().copied())
+                .collect();
+        }
+
+        let mut players = Vec::new();
+        for x in chunk_x - radius..=chunk_x + radius {
+            for z in chunk_z - radius..=chunk_z + radius {
+                if let Some(bucket) = self.chunks.get(&(x, z)) {
+                    players.extend(bucket.iter().copied());
+                }
+            }
+        }
+        players
+    }
+}
+
+impl SpatialIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a player at `position`, who can see `view_distance` chunks around them. Replaces
+    /// whatever was in the index for them before.
+    pub fn insert(&self, id: ConnectionId, position: &Position, view_distance: i32) {
+        self.remove(id);
+        let mut buckets = self.inner.write().unwrap();
+        buckets
+            .chunks
+            .entry(chunk_of(position))
+            .or_default()
+            .insert(id);
+        buckets.add_view_distance(view_distance);
+        buckets.players.insert(
+            id,
+            IndexedPlayer {
+                position: position.clone(),
+                view_distance,
+            },
+        );
+    }
+
+    /// Moves a player to `position`, into a different chunk's bucket if they crossed into one.
+    /// Does nothing if they aren't in the index.
+    pub fn update(&self, id: ConnectionId, position: &Position) {
+        let mut buckets = self.inner.write().unwrap();
+        let Some(player) = buckets.players.get_mut(&id) else {
+            return;
+        };
+        let (old_chunk, new_chunk) = (chunk_of(&player.position), chunk_of(position));
+        player.position = position.clone();
+        if old_chunk != new_chunk {
+            buckets.remove_from_chunk(id, old_chunk);
+            buckets.chunks.entry(new_chunk).or_default().insert(id);
+        }
+    }
+
+    /// Changes how many chunks around them a player can see. Does nothing if they aren't in the
+    /// index.
+    pub fn set_view_distance(&self, id: ConnectionId, view_distance: i32) {
+        let mut buckets = self.inner.write().unwrap();
+        let Some(player) = buckets.players.get_mut(&id) else {
+            return;
+        };
+        let old = std::mem::replace(&mut player.view_distance, view_distance);
+        buckets.remove_view_distance(old);
+        buckets.add_view_distance(view_distance);
+    }
+
+    pub fn remove(&self, id: ConnectionId) {
+        let mut buckets = self.inner.write().unwrap();
+        let Some(player) = buckets.players.remove(&id) else {
+            return;
+        };
+        buckets.remove_from_chunk(id, chunk_of(&player.position));
+        buckets.remove_view_distance(player.view_distance);
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().players.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every player in the index.
+    pub fn players(&self) -> Vec<ConnectionId> {
+        self.inner.read().unwrap().players.keys().copied().collect()
+    }
+
+    pub fn position(&self, id: ConnectionId) -> Option<Position> {
+        let buckets = self.inner.read().unwrap();
+        buckets
+            .players
+            .get(&id)
+            .map(|player| player.position.clone())
+    }
+
+    /// Every player no more than `radius` blocks from `position`, with where they are.
+    pub fn players_in_radius(
+        &self,
+        position: &Position,
+        radius: f64,
+    ) -> Vec<(ConnectionId, Position)> {
+        if radius < 0.0 {
+            return Vec::new();
+        }
+        let buckets = self.inner.read().unwrap();
+        // Players any more chunks away than this are more than `radius` blocks away.
+        let chunk_radius = (radius / 16.0).ceil() as i32;
+        buckets
+            .players_around(chunk_of(position), chunk_radius)
+            .into_iter()
+            .filter_map(|id| {
+                let player = &buckets.players[&id];
+                let (dx, dy, dz) = (
+                    (player.position.x - position.x) as f64,
+                    (player.position.y - position.y) as f64,
+                    (player.position.z - position.z) as f64,
+                );
+                (dx * dx + dy * dy + dz * dz <= radius * radius)
+                    .then(|| (id, player.position.clone()))
+            })
+            .collect()
+    }
+
+    /// Every player that has the chunk within their view distance.
+    pub fn players_viewing_chunk(&self, chunk_x: i32, chunk_z: i32) -> Vec<ConnectionId> {
+        let buckets = self.inner.read().unwrap();
+        let Some(&max_view_distance) = buckets.view_distances.keys().next_back() else {
+            return Vec::new();
+        };
+        buckets
+            .players_around((chunk_x, chunk_z), max_view_distance)
+            .into_iter()
+            .filter(|id| {
+                let player = &buckets.players[id];
+                let (x, z) = chunk_of(&player.position);
+                (x - chunk_x).abs().max((z - chunk_z).abs()) <= player.view_distance
+            })
+            .collect()
+    }
+
+    /// Every other player in a chunk within `id`'s own view distance. Empty if they aren't in
+    /// the index.
+    pub fn players_in_view_of(&self, id: ConnectionId) -> Vec<ConnectionId> {
+        let buckets = self.inner.read().unwrap();
+        let Some(player) = buckets.players.get(&id) else {
+            return Vec::new();
+        };
+        buckets
+            .players_around(chunk_of(&player.position), player.view_distance)
+            .into_iter()
+            .filter(|&other| other != id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut ids: Vec<ConnectionId>) -> Vec<ConnectionId> {
+        ids.sort();
+        ids
+    }
+
+    fn ids(players: Vec<(ConnectionId, Position)>) -> Vec<ConnectionId> {
+        sorted(players.into_iter().map(|(id, _)| id).collect())
+    }
+
+    #[test]
+    fn test_radius_across_chunk_borders() {
+        let index = SpatialIndex::new();
+        // Either side of the border between chunks -1 and 0, and between 0 and 1.
+        index.insert(1, &Position::new(-1, 64, 0), 2);
+        index.insert(2, &Position::new(0, 64, 0), 2);
+        index.insert(3, &Position::new(15, 64, 15), 2);
+        index.insert(4, &Position::new(16, 64, 16), 2);
+        index.insert(5, &Position::new(-40, 64, 0), 2);
+
+        assert_eq!(
+            ids(index.players_in_radius(&Position::new(0, 64, 0), 1.0)),
+            vec![1, 2]
+        );
+        assert_eq!(
+            ids(index.players_in_radius(&Position::new(15, 64, 15), 1.5)),
+            vec![3, 4]
+        );
+        assert_eq!(
+            ids(index.players_in_radius(&Position::new(-1, 64, 0), 22.0)),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            ids(index.players_in_radius(&Position::new(-20, 64, 0), 20.0)),
+            vec![1, 2, 5]
+        );
+        // Height counts too.
+        assert!(index
+            .players_in_radius(&Position::new(0, 100, 0), 30.0)
+            .is_empty());
+        assert!(index
+            .players_in_radius(&Position::new(0, 64, 0), -1.0)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_moving_between_chunks() {
+        let index = SpatialIndex::new();
+        index.insert(1, &Position::new(15, 64, 0), 2);
+        assert_eq!(
+            ids(index.players_in_radius(&Position::new(15, 64, 0), 0.0)),
+            vec![1]
+        );
+
+        // Into the next chunk over, then back.
+        index.update(1, &Position::new(16, 64, 0));
+        assert!(index
+            .players_in_radius(&Position::new(15, 64, 0), 0.0)
+            .is_empty());
+        assert_eq!(index.position(1), Some(Position::new(16, 64, 0)));
+        assert_eq!(index.inner.read().unwrap().chunks.len(), 1);
+        index.update(1, &Position::new(15, 64, 0));
+        assert_eq!(index.players_viewing_chunk(0, 0), vec![1]);
+
+        // Players that aren't in the index stay out of it.
+        index.update(2, &Position::new(0, 64, 0));
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.players(), vec![1]);
+
+        index.remove(1);
+        assert!(index.is_empty());
+        assert!(index.inner.read().unwrap().chunks.is_empty());
+        assert!(index.players_viewing_chunk(0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_viewing_chunks() {
+        let index = SpatialIndex::new();
+        index.insert(1, &Position::new(0, 64, 0), 2);
+        index.insert(2, &Position::new(-17, 64, 0), 10);
+        index.insert(3, &Position::new(100, 64, 100), 2);
+
+        // Chunk 2 is the edge of player 1's view, and the edge of player 2's is chunk 8.
+        assert_eq!(sorted(index.players_viewing_chunk(2, 0)), vec![1, 2]);
+        assert_eq!(sorted(index.players_viewing_chunk(3, 0)), vec![2]);
+        assert_eq!(sorted(index.players_viewing_chunk(8, 4)), vec![2, 3]);
+        assert!(index.players_viewing_chunk(9, -9).is_empty());
+
+        // Player 2 sees player 3, who can't see back that far.
+        assert_eq!(sorted(index.players_in_view_of(2)), vec![1, 3]);
+        assert_eq!(index.players_in_view_of(1), vec![2]);
+        assert!(index.players_in_view_of(3).is_empty());
+        assert!(index.players_in_view_of(4).is_empty());
+
+        index.set_view_distance(2, 2);
+        assert!(index.players_viewing_chunk(3, 0).is_empty());
+        index.remove(2);
+        assert_eq!(
+            index.inner.read().unwrap().view_distances,
+            BTreeMap::from([(2, 2)])
+        );
+    }
+
+    #[test]
+    fn test_matches_a_scan() {
+        let index = SpatialIndex::new();
+        let mut positions = Vec::new();
+        for id in 0..300usize {
+            // Spread over a few chunks, including negative ones.
+            let position = Position::new(
+                (id * 37 % 101) as i32 - 50,
+                (id * 13 % 40) as i16 + 40,
+                (id * 53 % 97) as i32 - 48,
+            );
+            index.insert(id, &position, (id % 5) as i32 + 1);
+            positions.push(position);
+        }
+
+        for (center, radius) in [
+            (Position::new(0, 60, 0), 10.0),
+            (Position::new(-33, 50, 17), 25.5),
+            (Position::new(48, 79, -48), 40.0),
+        ] {
+            let scanned = positions
+                .iter()
+                .enumerate()
+                .filter(|(_, position)| {
+                    let (dx, dy, dz) = (
+                        (position.x - center.x) as f64,
+                        (position.y - center.y) as f64,
+                        (position.z - center.z) as f64,
+                    );
+                    dx * dx + dy * dy + dz * dz <= radius * radius
+                })
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>();
+            assert_eq!(ids(index.players_in_radius(&center, radius)), scanned);
+        }
+
+        for (chunk_x, chunk_z) in [(0, 0), (-4, 3), (5, -5), (9, 9)] {
+            let scanned = positions
+                .iter()
+                .enumerate()
+                .filter(|(id, position)| {
+                    let (x, z) = chunk_of(position);
+                    (x - chunk_x).abs().max((z - chunk_z).abs()) <= (id % 5) as i32 + 1
+                })
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                sorted(index.players_viewing_chunk(chunk_x, chunk_z)),
+                scanned
+            );
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+
+use crate::utils::prelude::*;
+
+/// A JSON chat component, as used for disconnect reasons and chat messages.
+///
+/// ```ignore
+/// let reason = TextComponent::new("Server is full").color("red").bold(true);
+/// let death = TextComponent::translate("death.attack.generic", ["Steve"]);
+/// ```
+///
+/// - `text`: The text shown, unless `translate` is set.
+/// - `translate`: A translation key the client looks up in its language, e.g.
+///   `"multiplayer.player.joined"`. Its `%s`s are filled in with `with`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextComponent {
+    pub text: String,
+    pub translate: Option<String>,
+    pub with: Vec<TextComponent>,
+    pub color: Option<String>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub extra: Vec<TextComponent>,
+}
+
+/// The colors the legacy `§0` to `§f` codes stand for, in order.
+const LEGACY_COLORS: [&str; 16] = [
+    "black",
+    "dark_blue",
+    "dark_green",
+    "dark_aqua",
+    "dark_red",
+    "dark_purple",
+    "gold",
+    "gray",
+    "dark_gray",
+    "blue",
+    "green",
+    "aqua",
+    "red",
+    "light_purple",
+    "yellow",
+    "white",
+];
+
+impl TextComponent {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// A component the client translates into its own language, with `with` filled in for the
+    /// key's `%s`s.
+    pub fn translate<T: Into<TextComponent>>(
+        key: impl Into<String>,
+        with: impl IntoIterator<Item = T>,
+    ) -> Self {
+        Self {
+            translate: Some(key.into()),
+            with: with.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Converts text with legacy `§` formatting codes, like the ones in the config, into a
+    /// component. Colors, bold, italic and `§r` are kept. Colors reset the formatting before them,
+    /// same as vanilla, and codes that can't be shown with a component are dropped.
+    pub fn from_legacy(text: &str) -> Self {
+        if !text.contains('§') {
+            return Self::new(text);
+        }
+        let mut root = Self::new("");
+        let mut current = Self::new("");
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '§' {
+                current.text.push(c);
+                continue;
+            }
+            let Some(code) = chars.next() else {
+                break;
+            };
+            let mut next = match code.to_ascii_lowercase() {
+                'l' => current.clone().bold(true),
+                'o' => current.clone().italic(true),
+                'r' => Self::default(),
+                code => match code.to_digit(16) {
+                    Some(color) => Self::new("").color(LEGACY_COLORS[color as usize]),
+                    // Obfuscated, strikethrough and underlined.
+                    None => current.clone(),
+                },
+            };
+            next.text = current.text.clone();
+            if next == current {
+                continue;
+            }
+            next.text.clear();
+            let finished = std::mem::replace(&mut current, next);
+            if !finished.text.is_empty() {
+                root.extra.push(finished);
+            }
+        }
+        if !current.text.is_empty() {
+            root.extra.push(current);
+        }
+        root
+    }
+
+    /// Sets the color, either a named color like `"red"` or a hex code like `"#FF0000"`.
+    pub fn color(mut self, color: impl Into<String>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.bold = Some(bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.italic = Some(italic);
+        self
+    }
+
+    /// Appends a child component, which inherits this component's style.
+    pub fn extra(mut self, component: impl Into<TextComponent>) -> Self {
+        self.extra.push(component.into());
+        self
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+}
+
+impl Serialize for TextComponent {
+    /// Vanilla reads a component with `text` in it as plain text, so it's left out of ones that
+    /// get translated.
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        match &self.translate {
+            Some(key) => {
+                map.serialize_entry("translate", key)?;
+                if !self.with.is_empty() {
+                    map.serialize_entry("with", &self.with)?;
+                }
+            }
+            None => map.serialize_entry("text", &self.text)?,
+        }
+        if let Some(color) = &self.color {
+            map.serialize_entry("color", color)?;
+        }
+        if let Some(bold) = self.bold {
+            map.serialize_entry("bold", &bold)?;
+        }
+        if let Some(italic) = self.italic {
+            map.serialize_entry("italic", &italic)?;
+        }
+        if !self.extra.is_empty() {
+            map.serialize_entry("extra", &self.extra)?;
+        }
+        map.end()
+    }
+}
+
+impl From<&str> for TextComponent {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<String> for TextComponent {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_text_skips_unset_fields() {
+        let json = TextComponent::new("Server is full").to_json().unwrap();
+        assert_eq!(json, r#"{"text":"Server is full"}"#);
+    }
+
+    #[test]
+    fn test_styled_text_with_extra() {
+        let json = TextComponent::new("Kicked: ")
+            .color("red")
+            .extra(TextComponent::new("spam").bold(true))
+            .to_json()
+            .unwrap();
+        assert_eq!(
+            json,
+            r#"{"text":"Kicked: ","color":"red","extra":[{"text":"spam","bold":true}]}"#
+        );
+    }
+
+    #[test]
+    fn test_translated_text_has_no_text_field() {
+        let json = TextComponent::translate("multiplayer.player.joined", ["Steve"])
+            .color("yellow")
+            .to_json()
+            .unwrap();
+        assert_eq!(
+            json,
+            r#"{"translate":"multiplayer.player.joined","with":[{"text":"Steve"}],"color":"yellow"}"#
+        );
+    }
+
+    #[test]
+    fn test_legacy_codes_become_components() {
+        assert_eq!(
+            TextComponent::from_legacy("no codes"),
+            TextComponent::new("no codes")
+        );
+
+        let json = TextComponent::from_legacy("§aGreen §lbold§r plain §6§ogold")
+            .to_json()
+            .unwrap();
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"text":"","extra":[{"text":"Green ","color":"green"},"#,
+                r#"{"text":"bold","color":"green","bold":true},{"text":" plain "},"#,
+                r#"{"text":"gold","color":"gold","italic":true}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_legacy_colors_reset_formatting() {
+        let component = TextComponent::from_legacy("§l§Cred§kstill red§");
+        assert_eq!(
+            component.extra,
+            vec![TextComponent::new("redstill red").color("red")]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Keeps track of how many ticks the server is managing to run, and how long they take, for
+/// showing players.
+#[derive(Default)]
+pub struct TickRate {
+    /// When each tick in the last second finished and how long it took, oldest first.
+    ticks: Mutex<VecDeque<(Instant, Duration)>>,
+}
+
+impl TickRate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a tick that just finished, after taking `duration`.
+    pub fn record(&self, duration: Duration) {
+        self.record_at(Instant::now(), duration);
+    }
+
+    fn record_at(&self, now: Instant, duration: Duration) {
+        let mut ticks = self.ticks.lock().unwrap();
+        ticks.push_back((now, duration));
+        while ticks
+            .front()
+            .is_some_and(|&(tick, _)| now.duration_since(tick) >= WINDOW)
+        {
+            ticks.pop_front();
+        }
+    }
+
+    /// How many ticks finished in the last second. 0 until the first one.
+    pub fn ticks_per_second(&self) -> f64 {
+        self.ticks_per_second_at(Instant::now())
+    }
+
+    fn ticks_per_second_at(&self, now: Instant) -> f64 {
+        let ticks = self.ticks.lock().unwrap();
+        ticks
+            .iter()
+            .filter(|&&(tick, _)| now.duration_since(tick) < WINDOW)
+            .count() as f64
+    }
+
+    /// How long the ticks in the last second took on average, in milliseconds. 0 until the
+    /// first one.
+    pub fn milliseconds_per_tick(&self) -> f64 {
+        self.milliseconds_per_tick_at(Instant::now())
+    }
+
+    fn milliseconds_per_tick_at(&self, now: Instant) -> f64 {
+        let ticks = self.ticks.lock().unwrap();
+        let recent = ticks
+            .iter()
+            .filter(|&&(tick, _)| now.duration_since(tick) < WINDOW)
+            .map(|&(_, duration)| duration)
+            .collect::<Vec<_>>();
+        if recent.is_empty() {
+            return 0.0;
+        }
+        let total = recent.iter().sum::<Duration>();
+        total.as_secs_f64() * 1000.0 / recent.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_the_last_second_counts() {
+        let tick_rate = TickRate::new();
+        let start = Instant::now();
+        for tick in 0..30 {
+            tick_rate.record_at(
+                start + Duration::from_millis(50 * tick),
+                Duration::from_millis(tick),
+            );
+        }
+        let last_tick = start + Duration::from_millis(50 * 29);
+        assert_eq!(tick_rate.ticks_per_second_at(last_tick), 20.0);
+        assert_eq!(
+            tick_rate.ticks_per_second_at(last_tick + Duration::from_millis(500)),
+            10.0
+        );
+        assert_eq!(
+            tick_rate.ticks_per_second_at(last_tick + Duration::from_secs(2)),
+            0.0
+        );
+
+        // Ticks 10 to 29 took 10 to 29 ms.
+        assert_eq!(tick_rate.milliseconds_per_tick_at(last_tick), 19.5);
+        assert_eq!(
+            tick_rate.milliseconds_per_tick_at(last_tick + Duration::from_secs(2)),
+            0.0
+        );
+    }
+}
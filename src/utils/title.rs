@@ -0,0 +1,90 @@
+use ferrumc_codec::enc::NetEncode;
+
+use crate::net::packets::outgoing::set_subtitle_text::SetSubtitleText;
+use crate::net::packets::outgoing::set_title_animation_times::SetTitleAnimationTimes;
+use crate::net::packets::outgoing::set_title_text::SetTitleText;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// A title to show in the middle of a player's screen, see
+/// [ConnectionExt::show_title](crate::net::ConnectionExt::show_title).
+///
+/// - `subtitle`: Shown under the title, if there is one.
+/// - `fade_in`, `stay` and `fade_out`: How long each part of showing the title takes, in ticks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Title {
+    pub title: TextComponent,
+    pub subtitle: Option<TextComponent>,
+    pub fade_in: i32,
+    pub stay: i32,
+    pub fade_out: i32,
+}
+
+impl Title {
+    /// A title with no subtitle and the same timings as vanilla.
+    pub fn new(title: impl Into<TextComponent>) -> Self {
+        Self {
+            title: title.into(),
+            subtitle: None,
+            fade_in: 10,
+            stay: 70,
+            fade_out: 20,
+        }
+    }
+
+    pub fn subtitle(mut self, subtitle: impl Into<TextComponent>) -> Self {
+        self.subtitle = Some(subtitle.into());
+        self
+    }
+
+    /// Encodes the packets that show the title, in the order the client needs them: the timings
+    /// and subtitle only apply to titles sent after them.
+    pub async fn encode(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        SetTitleAnimationTimes::new(self.fade_in, self.stay, self.fade_out)
+            .net_encode(&mut bytes)
+            .await?;
+        if let Some(subtitle) = &self.subtitle {
+            SetSubtitleText::new(subtitle.clone())?
+                .net_encode(&mut bytes)
+                .await?;
+        }
+        SetTitleText::new(self.title.clone())?
+            .net_encode(&mut bytes)
+            .await?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The ids of the packets in `bytes`. Only works for packets shorter than 128 bytes.
+    fn packet_ids(mut bytes: &[u8]) -> Vec<u8> {
+        let mut ids = Vec::new();
+        while let [length, id, ..] = bytes {
+            ids.push(*id);
+            bytes = &bytes[1 + *length as usize..];
+        }
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_timings_are_sent_before_the_title() {
+        let bytes = Title::new("Welcome")
+            .subtitle("to the server")
+            .encode()
+            .await
+            .unwrap();
+        assert_eq!(packet_ids(&bytes), vec![0x60, 0x5D, 0x5F]);
+
+        let bytes = Title::new("Welcome").encode().await.unwrap();
+        assert_eq!(packet_ids(&bytes), vec![0x60, 0x5F]);
+        // Length, id, then the fade in, stay and fade out as big endian ints.
+        assert_eq!(
+            bytes[..14],
+            [13, 0x60, 0, 0, 0, 10, 0, 0, 0, 70, 0, 0, 0, 20]
+        );
+    }
+}
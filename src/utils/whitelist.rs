@@ -0,0 +1,164 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::state::ServerState;
+use crate::utils::json_file::{read_json_file, write_json_file};
+use crate::utils::prelude::*;
+
+/// A player on the whitelist. Only the UUID is checked, the name is there so people editing the
+/// file can tell who's who.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub uuid: Uuid,
+    pub name: String,
+}
+
+/// The players allowed to join while `whitelist.enabled` is set, kept in a JSON file next to the
+/// config in the same format vanilla uses.
+///
+/// Every change is written to the file straight away, and the file can be edited by hand and
+/// [reloaded](PlayerWhitelist::reload) without a restart.
+pub struct PlayerWhitelist {
+    path: PathBuf,
+    entries: Mutex<Vec<WhitelistEntry>>,
+}
+
+impl PlayerWhitelist {
+    /// Loads the whitelist from `path`. Starts out empty if the file doesn't exist yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entries: Vec<WhitelistEntry> = read_json_file(&path)?;
+        debug!("Loaded {} whitelisted players", entries.len());
+
+        Ok(Self {
+            path,
+            entries: Mutex::new(entries),
+        })
+    }
+
+    pub async fn contains(&self, uuid: Uuid) -> bool {
+        self.entries
+            .lock()
+            .await
+            .iter()
+            .any(|entry| entry.uuid == uuid)
+    }
+
+    /// Returns `false` if the player was already whitelisted. Their name is updated either way.
+    pub async fn add(&self, uuid: Uuid, name: String) -> Result<bool> {
+        let mut entries = self.entries.lock().await;
+        let added = match entries.iter_mut().find(|entry| entry.uuid == uuid) {
+            Some(entry) => {
+                entry.name = name;
+                false
+            }
+            None => {
+                entries.push(WhitelistEntry { uuid, name });
+                true
+            }
+        };
+        write_json_file(&self.path, &*entries).await?;
+        Ok(added)
+    }
+
+    /// Returns `false` if the player wasn't whitelisted.
+    pub async fn remove(&self, uuid: Uuid) -> Result<bool> {
+        let mut entries = self.entries.lock().await;
+        let count = entries.len();
+        entries.retain(|entry| entry.uuid != uuid);
+        if entries.len() == count {
+            return Ok(false);
+        }
+        write_json_file(&self.path, &*entries).await?;
+        Ok(true)
+    }
+
+    /// Reads the file again, picking up changes made to it by hand. Returns how many players are
+    /// whitelisted now. If the file can't be read, the current whitelist is kept.
+    pub async fn reload(&self) -> Result<usize> {
+        let mut entries = self.entries.lock().await;
+        *entries = read_json_file(&self.path)?;
+        Ok(entries.len())
+    }
+}
+
+impl ServerState {
+    /// Lets a player join while the whitelist is on. Returns `false` if they already could.
+    pub async fn whitelist_add(&self, uuid: Uuid, name: String) -> Result<bool> {
+        info!("Adding {} ({}) to the whitelist", name, uuid);
+        self.whitelist.add(uuid, name).await
+    }
+
+    /// Takes a player off the whitelist. Players that are online stay online, this only stops
+    /// them from joining again. Returns `false` if they weren't whitelisted.
+    pub async fn whitelist_remove(&self, uuid: Uuid) -> Result<bool> {
+        info!("Removing {} from the whitelist", uuid);
+        self.whitelist.remove(uuid).await
+    }
+
+    /// Reloads the whitelist from its file. Returns how many players are on it.
+    pub async fn whitelist_reload(&self) -> Result<usize> {
+        let count = self.whitelist.reload().await?;
+        info!("Reloaded the whitelist, {} players are on it", count);
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("ferrumc-whitelist-{}.json", Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_whitelist_is_persisted() {
+        let path = temp_path();
+        let uuid = Uuid::new_v4();
+
+        let whitelist = PlayerWhitelist::load(&path).unwrap();
+        assert!(!whitelist.contains(uuid).await);
+        assert!(whitelist.add(uuid, "Notch".to_string()).await.unwrap());
+        assert!(!whitelist.add(uuid, "Notch".to_string()).await.unwrap());
+        assert!(whitelist.contains(uuid).await);
+
+        let reloaded = PlayerWhitelist::load(&path).unwrap();
+        assert!(reloaded.contains(uuid).await);
+        assert!(!reloaded.contains(Uuid::new_v4()).await);
+
+        assert!(reloaded.remove(uuid).await.unwrap());
+        assert!(!reloaded.remove(uuid).await.unwrap());
+        let reloaded = PlayerWhitelist::load(&path).unwrap();
+        assert!(!reloaded.contains(uuid).await);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reload_picks_up_edits() {
+        let path = temp_path();
+        let uuid = Uuid::new_v4();
+        let whitelist = PlayerWhitelist::load(&path).unwrap();
+
+        let entries = vec![WhitelistEntry {
+            uuid,
+            name: "jeb_".to_string(),
+        }];
+        std::fs::write(&path, serde_json::to_string(&entries).unwrap()).unwrap();
+        assert!(!whitelist.contains(uuid).await);
+        assert_eq!(whitelist.reload().await.unwrap(), 1);
+        assert!(whitelist.contains(uuid).await);
+
+        // A broken file keeps the old whitelist.
+        std::fs::write(&path, "not json").unwrap();
+        assert!(whitelist.reload().await.is_err());
+        assert!(whitelist.contains(uuid).await);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,91 @@
+use crate::net::packets::outgoing::open_screen::GENERIC_9X1;
+
+/// Slots in each row of a chest window.
+pub const SLOTS_PER_ROW: usize = 9;
+/// The most rows a chest window can have.
+pub const MAX_ROWS: u8 = 6;
+
+/// Hands out window ids for one connection, and remembers which window it has open.
+///
+/// Ids go from 1 to 255 and then start again at 1, since 0 is always the player's inventory.
+///
+/// - `last_id`: The id of the last window opened, or 0 if there hasn't been one.
+/// - `open`: The window the player has open right now, other than their inventory.
+#[derive(Debug, Default)]
+pub struct WindowIds {
+    pub last_id: u8,
+    pub open: Option<Window>,
+}
+
+impl WindowIds {
+    /// Opens a new window with the next id. Whatever was open before is replaced, same as the
+    /// client does.
+    pub fn open(&mut self, rows: u8) -> Window {
+        self.last_id = self.last_id % u8::MAX + 1;
+        let window = Window {
+            id: self.last_id,
+            rows,
+        };
+        self.open = Some(window);
+        window
+    }
+
+    /// Forgets the open window, if it's the one with this id.
+    pub fn close(&mut self, id: u8) {
+        if self.open.is_some_and(|window| window.id == id) {
+            self.open = None;
+        }
+    }
+}
+
+/// A chest window opened with
+/// [ConnectionExt::open_inventory](crate::net::ConnectionExt::open_inventory). Clicks in it come
+/// with its `id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    pub id: u8,
+    pub rows: u8,
+}
+
+impl Window {
+    /// How many slots the window has, not counting the player's inventory under it.
+    pub fn size(&self) -> usize {
+        self.rows as usize * SLOTS_PER_ROW
+    }
+
+    /// The window's id in the menu registry.
+    pub fn window_type(&self) -> i32 {
+        GENERIC_9X1 + self.rows as i32 - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_ids_wrap_around() {
+        let mut ids = WindowIds::default();
+        assert_eq!(ids.open(3).id, 1);
+        assert_eq!(ids.open(3).id, 2);
+
+        ids.last_id = 254;
+        assert_eq!(ids.open(1).id, 255);
+        // 0 is the player's inventory, so it's skipped.
+        assert_eq!(ids.open(1).id, 1);
+    }
+
+    #[test]
+    fn test_only_the_open_window_is_closed() {
+        let mut ids = WindowIds::default();
+        let first = ids.open(1);
+        let second = ids.open(6);
+        assert_eq!(second.size(), 54);
+        assert_eq!(second.window_type(), 5);
+
+        ids.close(first.id);
+        assert_eq!(ids.open, Some(second));
+        ids.close(second.id);
+        assert_eq!(ids.open, None);
+    }
+}
@@ -0,0 +1,336 @@
+use std::fs::File;
+use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+use byteorder::{BigEndian, ReadBytesExt};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use nbt_lib::NBTDeserializeBytes;
+
+use crate::utils::prelude::*;
+use crate::world::chunk_format::Chunk;
+
+/// Region files are split into sectors of this many bytes.
+const SECTOR_SIZE: u64 = 4096;
+/// Every region file holds 32x32 chunks.
+const REGION_WIDTH: i32 = 32;
+const CHUNKS_PER_REGION: usize = (REGION_WIDTH * REGION_WIDTH) as usize;
+/// The location table and the timestamp table, a sector each.
+const HEADER_SIZE: usize = 2 * SECTOR_SIZE as usize;
+
+const GZIP_COMPRESSION: u8 = 1;
+const ZLIB_COMPRESSION: u8 = 2;
+const NO_COMPRESSION: u8 = 3;
+/// Set on the compression type of chunks too big for their region file, which are kept in a
+/// `c.X.Z.mcc` file of their own instead.
+const EXTERNAL_CHUNK_FLAG: u8 = 0x80;
+
+/// Where a chunk is in its region file, in sectors from the start of the file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChunkLocation {
+    pub offset: u32,
+    pub sectors: u8,
+}
+
+impl ChunkLocation {
+    /// Whether the chunk hasn't been generated, so isn't in the file at all.
+    pub fn is_empty(&self) -> bool {
+        self.offset == 0 || self.sectors == 0
+    }
+}
+
+/// The tables at the start of a region file: where each of its chunks is, and when each was
+/// last saved, in seconds since the unix epoch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionHeader {
+    locations: Vec<ChunkLocation>,
+    timestamps: Vec<u32>,
+}
+
+impl RegionHeader {
+    /// Parses the first 8KiB of a region file.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(Error::Generic(format!(
+                "Region header is {} bytes, it should be {}",
+                bytes.len(),
+                HEADER_SIZE
+            )));
+        }
+        let mut cursor = Cursor::new(bytes);
+        let mut locations = Vec::with_capacity(CHUNKS_PER_REGION);
+        for _ in 0..CHUNKS_PER_REGION {
+            let location = cursor.read_u32::<BigEndian>()?;
+            locations.push(ChunkLocation {
+                offset: location >> 8,
+                sectors: location as u8,
+            });
+        }
+        let mut timestamps = Vec::with_capacity(CHUNKS_PER_REGION);
+        for _ in 0..CHUNKS_PER_REGION {
+            timestamps.push(cursor.read_u32::<BigEndian>()?);
+        }
+        Ok(Self {
+            locations,
+            timestamps,
+        })
+    }
+
+    /// Where the chunk at `chunk_x`, `chunk_z` is stored, if this is its region.
+    pub fn location(&self, chunk_x: i32, chunk_z: i32) -> ChunkLocation {
+        self.locations[header_index(chunk_x, chunk_z)]
+    }
+
+    /// When the chunk at `chunk_x`, `chunk_z` was last saved, if this is its region.
+    pub fn timestamp(&self, chunk_x: i32, chunk_z: i32) -> u32 {
+        self.timestamps[header_index(chunk_x, chunk_z)]
+    }
+}
+
+/// Where a chunk is in both of the header's tables.
+fn header_index(chunk_x: i32, chunk_z: i32) -> usize {
+    (chunk_x.rem_euclid(REGION_WIDTH) + chunk_z.rem_euclid(REGION_WIDTH) * REGION_WIDTH) as usize
+}
+
+/// A vanilla world on disk, read straight from its region files.
+///
+/// Chunks are converted to the network format as they're read, the same as imported ones, so
+/// they can be sent as they are. Only the overworld is read.
+#[derive(Debug, Clone)]
+pub struct AnvilWorld {
+    directory: PathBuf,
+}
+
+impl AnvilWorld {
+    /// The world in `directory`, which has the `region` folder in it. It doesn't have to exist.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// The region file the chunk at `chunk_x`, `chunk_z` is stored in.
+    pub fn region_path(&self, chunk_x: i32, chunk_z: i32) -> PathBuf {
+        self.directory.join("region").join(format!(
+            "r.{}.{}.mca",
+            chunk_x.div_euclid(REGION_WIDTH),
+            chunk_z.div_euclid(REGION_WIDTH)
+        ))
+    }
+
+    /// Reads the chunk at `chunk_x`, `chunk_z` without blocking the runtime. `None` if the world
+    /// doesn't have it.
+    pub async fn load_chunk(&self, chunk_x: i32, chunk_z: i32) -> Result<Option<Chunk>> {
+        let world = self.clone();
+        tokio::task::spawn_blocking(move || world.read_chunk(chunk_x, chunk_z))
+            .await
+            .map_err(|e| Error::Generic(format!("Chunk reading task failed: {}", e)))?
+    }
+
+    /// Reads the chunk at `chunk_x`, `chunk_z`. `None` if the world doesn't have it.
+    pub fn read_chunk(&self, chunk_x: i32, chunk_z: i32) -> Result<Option<Chunk>> {
+        let Some(nbt) = self.read_chunk_nbt(chunk_x, chunk_z)? else {
+            return Ok(None);
+        };
+        let mut chunk = Chunk::read_from_bytes(&mut Cursor::new(nbt)).map_err(|e| {
+            Error::InvalidChunk(chunk_x, chunk_z, format!("Could not read chunk: {}", e))
+        })?;
+        if (chunk.x_pos, chunk.z_pos) != (chunk_x, chunk_z) {
+            return Err(Error::InvalidChunk(
+                chunk_x,
+                chunk_z,
+                format!(
+                    "Region file has chunk {} {} where this one should be",
+                    chunk.x_pos, chunk.z_pos
+                ),
+            ));
+        }
+        chunk.convert_to_net_mode()?;
+        chunk.dimension = Some("overworld".to_string());
+        Ok(Some(chunk))
+    }
+
+    /// The chunk's uncompressed NBT, from its region file.
+    fn read_chunk_nbt(&self, chunk_x: i32, chunk_z: i32) -> Result<Option<Vec<u8>>> {
+        let path = self.region_path(chunk_x, chunk_z);
+        let mut file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut header = vec![0; HEADER_SIZE];
+        file.read_exact(&mut header)?;
+        let location = RegionHeader::parse(&header)?.location(chunk_x, chunk_z);
+        if location.is_empty() {
+            return Ok(None);
+        }
+
+        file.seek(SeekFrom::Start(location.offset as u64 * SECTOR_SIZE))?;
+        // The length counts the compression type, but not itself.
+        let length = file.read_u32::<BigEndian>()? as u64;
+        let compression = file.read_u8()?;
+        let available = location.sectors as u64 * SECTOR_SIZE - 5;
+        if length == 0 || length - 1 > available {
+            return Err(Error::InvalidChunk(
+                chunk_x,
+                chunk_z,
+                format!("Chunk is {} bytes long, but only has {}", length, available),
+            ));
+        }
+        if compression & EXTERNAL_CHUNK_FLAG != 0 {
+            return Err(Error::InvalidChunk(
+                chunk_x,
+                chunk_z,
+                format!("Chunk is stored outside {}", path.display()),
+            ));
+        }
+        let mut compressed = vec![0; length as usize - 1];
+        file.read_exact(&mut compressed)?;
+
+        let mut nbt = Vec::new();
+        match compression {
+            GZIP_COMPRESSION => {
+                GzDecoder::new(compressed.as_slice()).read_to_end(&mut nbt)?;
+            }
+            ZLIB_COMPRESSION => {
+                ZlibDecoder::new(compressed.as_slice()).read_to_end(&mut nbt)?;
+            }
+            NO_COMPRESSION => nbt = compressed,
+            other => {
+                return Err(Error::InvalidChunk(
+                    chunk_x,
+                    chunk_z,
+                    format!("Unknown compression type {}", other),
+                ))
+            }
+        }
+        Ok(Some(nbt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use ferrumc_codec::network_types::varint::VarInt;
+
+    use super::*;
+    use crate::world::chunk_format::Palette;
+
+    /// Has chunk -1 0 zlib compressed, and chunk -32 31 gzipped.
+    const TEST_WORLD: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/.etc/test_world");
+
+    fn block(name: &str, properties: &[(&str, &str)]) -> Palette {
+        Palette {
+            name: format!("minecraft:{}", name),
+            properties: (!properties.is_empty()).then(|| {
+                properties
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect::<BTreeMap<_, _>>()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_region_paths() {
+        let world = AnvilWorld::new("world");
+        let region = |x, z| world.region_path(x, z).file_name().unwrap().to_owned();
+        assert_eq!(region(-1, 0), "r.-1.0.mca");
+        assert_eq!(region(31, 32), "r.0.1.mca");
+        assert_eq!(region(-33, -32), "r.-2.-1.mca");
+    }
+
+    #[test]
+    fn test_header() {
+        let bytes = std::fs::read(AnvilWorld::new(TEST_WORLD).region_path(-1, 0)).unwrap();
+        let header = RegionHeader::parse(&bytes).unwrap();
+        assert_eq!(
+            header.location(-1, 0),
+            ChunkLocation {
+                offset: 2,
+                sectors: 1
+            }
+        );
+        assert_eq!(header.timestamp(-1, 0), 1_700_000_000);
+        assert_eq!(header.location(-32, 31).offset, 3);
+        assert!(header.location(-2, 0).is_empty());
+        assert!(RegionHeader::parse(&bytes[..100]).is_err());
+    }
+
+    #[test]
+    fn test_reading_blocks() {
+        let chunk = AnvilWorld::new(TEST_WORLD)
+            .read_chunk(-1, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(chunk.dimension.as_deref(), Some("overworld"));
+
+        // The bottom section is all bedrock, so it has a palette but no data.
+        assert_eq!(chunk.get_block(-8, -64, 8).unwrap(), block("bedrock", &[]));
+        assert_eq!(chunk.get_block(-1, -49, 15).unwrap(), block("bedrock", &[]));
+        assert_eq!(chunk.get_block(-16, -48, 0).unwrap(), block("stone", &[]));
+        assert_eq!(
+            chunk.get_block(-1, -33, 15).unwrap(),
+            block("grass_block", &[("snowy", "false")])
+        );
+        assert_eq!(
+            chunk.get_block(-5, -40, 7).unwrap(),
+            block("oak_log", &[("axis", "x")])
+        );
+        assert_eq!(chunk.get_block(-4, -40, 7).unwrap(), block("air", &[]));
+        assert_eq!(chunk.get_block(-8, -20, 8).unwrap(), block("air", &[]));
+
+        let sections = chunk.sections.as_ref().unwrap();
+        let non_air = |y: i8| {
+            let section = sections.iter().find(|section| section.y == y).unwrap();
+            section.block_states.as_ref().unwrap().non_air_blocks
+        };
+        assert_eq!(non_air(-4), Some(4096));
+        assert_eq!(non_air(-3), Some(4));
+        assert_eq!(non_air(-2), Some(0));
+        let biomes = sections[0].biomes.as_ref().unwrap();
+        assert_eq!(biomes.palette, vec!["minecraft:plains".to_string()]);
+        assert_eq!(biomes.net_palette, Some(vec![VarInt::from(39)]));
+
+        let heightmaps = chunk.heightmaps.as_ref().unwrap();
+        assert_eq!(heightmaps.motion_blocking.as_ref().unwrap().len(), 37);
+        let sign = &chunk.block_entities.as_ref().unwrap()[0];
+        assert_eq!((sign.x, sign.y, sign.z), (-2, -40, 3));
+        assert_eq!(sign.type_id(), Some(7));
+        assert_eq!(
+            sign.sign_text(true).unwrap().messages[0],
+            r#"{"text":"Hello"}"#
+        );
+        assert_eq!(
+            chunk.get_block(-2, -40, 3).unwrap(),
+            block("oak_sign", &[("rotation", "0"), ("waterlogged", "false")])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_loading_gzipped_chunk() {
+        let chunk = AnvilWorld::new(TEST_WORLD)
+            .load_chunk(-32, 31)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((chunk.x_pos, chunk.z_pos), (-32, 31));
+        assert_eq!(
+            chunk.get_block(-512, -60, 500).unwrap(),
+            block("deepslate", &[("axis", "y")])
+        );
+    }
+
+    #[test]
+    fn test_missing_chunks() {
+        let world = AnvilWorld::new(TEST_WORLD);
+        // Not generated, in a region file that exists.
+        assert!(world.read_chunk(-2, 0).unwrap().is_none());
+        // No region file at all.
+        assert!(world.read_chunk(100, 100).unwrap().is_none());
+        assert!(AnvilWorld::new("does_not_exist")
+            .read_chunk(0, 0)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::LazyLock;
+
+use ferrumc_codec::network_types::varint::VarInt;
+use hashbrown::HashMap;
+use serde_derive::Deserialize;
+
+use crate::world::chunk_format::Biomes;
+
+/// The registry codec sent with Login (play), which has every biome the client knows and its id.
+const REGISTRY_CODEC: &[u8] = include_bytes!("../../.etc/nbt_codec.nbt");
+
+/// What sections without biomes of their own are, and what biomes the client doesn't know are
+/// swapped for.
+pub const PLAINS: &str = "minecraft:plains";
+
+/// How many biomes there are along each side of a section. Each one covers 4x4x4 blocks.
+pub const BIOMES_PER_SIDE: usize = 4;
+/// How many biomes a section has.
+pub const BIOMES_PER_SECTION: usize = BIOMES_PER_SIDE.pow(3);
+
+static REGISTRY: LazyLock<BiomeRegistry> = LazyLock::new(BiomeRegistry::load);
+
+/// Only the biomes out of the registry codec.
+#[derive(Deserialize)]
+struct Codec {
+    #[serde(rename = "minecraft:worldgen/biome")]
+    biomes: CodecRegistry,
+}
+
+#[derive(Deserialize)]
+struct CodecRegistry {
+    value: Vec<CodecEntry>,
+}
+
+#[derive(Deserialize)]
+struct CodecEntry {
+    name: String,
+    id: i64,
+}
+
+/// Every biome's name, by the id the client knows it by.
+struct BiomeRegistry {
+    names: Vec<String>,
+    ids: HashMap<String, i32>,
+}
+
+impl BiomeRegistry {
+    fn load() -> Self {
+        let codec: Codec =
+            fastnbt::from_bytes(REGISTRY_CODEC).expect("The registry codec should be valid NBT");
+        let mut entries = codec.biomes.value;
+        entries.sort_unstable_by_key(|entry| entry.id);
+        assert!(
+            entries
+                .iter()
+                .enumerate()
+                .all(|(index, entry)| entry.id == index as i64),
+            "Biome ids in the registry codec should count up from 0"
+        );
+        let ids = entries
+            .iter()
+            .map(|entry| (entry.name.clone(), entry.id as i32))
+            .collect();
+        Self {
+            names: entries.into_iter().map(|entry| entry.name).collect(),
+            ids,
+        }
+    }
+}
+
+/// The id the client knows the biome called `name` by, or `None` if it isn't in the registry
+/// codec.
+pub fn biome_id(name: &str) -> Option<i32> {
+    REGISTRY.ids.get(name).copied()
+}
+
+/// The name of the biome with the id `id`.
+pub fn biome_name(id: i32) -> Option<&'static str> {
+    REGISTRY.names.get(usize::try_from(id).ok()?).map(String::as_str)
+}
+
+/// How many biomes the client knows.
+pub fn biome_count() -> usize {
+    REGISTRY.names.len()
+}
+
+/// The id of [PLAINS].
+pub fn plains_id() -> i32 {
+    biome_id(PLAINS).expect("Plains should be in the registry codec")
+}
+
+impl Biomes {
+    /// Biomes for a section that's all `name`. `None` if the client doesn't know it.
+    pub fn single(name: &str) -> Option<Self> {
+        let id = biome_id(name)?;
+        Some(Self {
+            palette: vec![name.to_string()],
+            data: None,
+            net_palette: Some(vec![VarInt::from(id)]),
+        })
+    }
+
+    /// Biomes for a section that's all plains.
+    pub fn plains() -> Self {
+        Self::single(PLAINS).expect("Plains should be in the registry codec")
+    }
+
+    /// Fills in the ids of the biomes in the palette, swapping any the client doesn't know, like
+    /// ones from mods or newer versions, for plains.
+    pub fn convert_to_net_mode(&mut self) {
+        self.net_palette = Some(
+            self.palette
+                .iter()
+                .map(|name| VarInt::from(biome_id(name).unwrap_or_else(plains_id)))
+                .collect(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        assert_eq!(biome_count(), 64);
+        assert_eq!(plains_id(), 39);
+        assert_eq!(biome_id("minecraft:badlands"), Some(0));
+        assert_eq!(biome_id("minecraft:ocean"), Some(35));
+        assert_eq!(biome_name(39), Some(PLAINS));
+        assert_eq!(biome_name(64), None);
+        assert_eq!(biome_id("minecraft:not_a_biome"), None);
+    }
+
+    #[test]
+    fn test_unknown_biomes_are_plains() {
+        let mut biomes = Biomes {
+            palette: vec![
+                "minecraft:desert".to_string(),
+                "somemod:crystal_caves".to_string(),
+            ],
+            data: Some(vec![0; 1]),
+            net_palette: None,
+        };
+        biomes.convert_to_net_mode();
+        assert_eq!(
+            biomes.net_palette,
+            Some(vec![VarInt::from(14), VarInt::from(39)])
+        );
+        assert!(Biomes::single("minecraft:not_a_biome").is_none());
+    }
+}
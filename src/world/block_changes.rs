@@ -0,0 +1,183 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+use tracing::warn;
+
+use crate::net::packets::outgoing::block_update::BlockUpdate;
+use crate::net::packets::outgoing::update_light::UpdateLight;
+use crate::net::packets::outgoing::update_section_blocks::UpdateSectionBlocks;
+use crate::state::ServerState;
+use crate::utils::encoding::position::Position;
+use crate::world::dimensions::Dimension;
+
+/// Sections with more changes than this in one tick get them all in one
+/// [UpdateSectionBlocks], instead of a [BlockUpdate] each.
+pub const MAX_SINGLE_BLOCK_UPDATES: usize = 4;
+
+/// The changes made to one section: each block's x, y and z inside it, and its new state id.
+pub type SectionChanges = HashMap<(u8, u8, u8), i32>;
+
+/// Collects the blocks changed during a tick, so players can be sent them all at once by
+/// [ServerState::flush_block_changes], grouped by section.
+///
+/// Only the last change to each block is kept, since that's all players need to see. Sections
+/// whose light changed are sent it once, however many changes there were.
+#[derive(Default)]
+pub struct BlockChangeBatcher {
+    changes: Mutex<HashMap<(i32, i32, i32), SectionChanges>>,
+    /// The y of each section whose light changed, by chunk.
+    relit: Mutex<HashMap<(i32, i32), BTreeSet<i8>>>,
+    /// Held while flushing, so changes to the same block can't be sent out of order.
+    flushing: tokio::sync::Mutex<()>,
+}
+
+impl BlockChangeBatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the block at `location` is now `block_id`.
+    pub fn record(&self, location: &Position, block_id: i32) {
+        let (x, y, z) = (location.x, location.y as i32, location.z);
+        let section = (x >> 4, y >> 4, z >> 4);
+        let block = ((x & 0xF) as u8, (y & 0xF) as u8, (z & 0xF) as u8);
+        self.changes
+            .lock()
+            .unwrap()
+            .entry(section)
+            .or_default()
+            .insert(block, block_id);
+    }
+
+    /// Takes every change recorded so far, by section.
+    pub fn take(&self) -> HashMap<(i32, i32, i32), SectionChanges> {
+        std::mem::take(&mut *self.changes.lock().unwrap())
+    }
+
+    /// Records that the light in the sections at the y in `sections` changed, in the chunk at
+    /// `chunk_x`, `chunk_z`.
+    pub fn record_light(&self, chunk_x: i32, chunk_z: i32, sections: &[i8]) {
+        self.relit
+            .lock()
+            .unwrap()
+            .entry((chunk_x, chunk_z))
+            .or_default()
+            .extend(sections);
+    }
+
+    /// Takes the y of every section whose light changed since this was last called, by chunk.
+    pub fn take_light(&self) -> HashMap<(i32, i32), BTreeSet<i8>> {
+        std::mem::take(&mut *self.relit.lock().unwrap())
+    }
+}
+
+impl ServerState {
+    /// Sends every block change recorded since the last flush, in every dimension, to the
+    /// players that can see it.
+    ///
+    /// Runs every tick, and before anything that needs players to have seen the changes first,
+    /// like acknowledging the block changes a client predicted.
+    pub async fn flush_block_changes(&self) {
+        for dimension in self.dimensions.iter() {
+            self.flush_dimension_block_changes(dimension).await;
+        }
+    }
+
+    async fn flush_dimension_block_changes(&self, dimension: &Dimension) {
+        let _flushing = dimension.block_changes.flushing.lock().await;
+        for (section, changes) in dimension.block_changes.take() {
+            let (section_x, section_y, section_z) = section;
+            let block_position = |(x, y, z): (u8, u8, u8)| {
+                Position::new(
+                    (section_x << 4) + x as i32,
+                    ((section_y << 4) + y as i32) as i16,
+                    (section_z << 4) + z as i32,
+                )
+            };
+
+            if changes.len() > MAX_SINGLE_BLOCK_UPDATES {
+                let blocks = changes.into_iter().collect::<Vec<_>>();
+                self.send_to_players_near(dimension, &block_position((0, 0, 0)), None, || {
+                    UpdateSectionBlocks::new(section, &blocks)
+                })
+                .await;
+            } else {
+                for (block, block_id) in changes {
+                    let location = block_position(block);
+                    self.send_to_players_near(dimension, &location, None, || {
+                        BlockUpdate::new(location.clone(), block_id)
+                    })
+                    .await;
+                }
+            }
+        }
+
+        // After the blocks, so the light isn't drawn on the blocks that were there before.
+        for ((chunk_x, chunk_z), relit) in dimension.block_changes.take_light() {
+            let chunk = match self
+                .database
+                .get_chunk(chunk_x, chunk_z, dimension.key.clone())
+                .await
+            {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!(
+                        "Failed to get chunk {} {} to send its light: {}",
+                        chunk_x, chunk_z, e
+                    );
+                    continue;
+                }
+            };
+            let Some(sections) = chunk.sections.as_deref() else {
+                continue;
+            };
+            let position = Position::new(chunk_x << 4, 0, chunk_z << 4);
+            self.send_to_players_near(dimension, &position, None, || {
+                UpdateLight::new(chunk_x, chunk_z, sections, &relit)
+            })
+            .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_are_grouped_by_section() {
+        let batcher = BlockChangeBatcher::new();
+        batcher.record(&Position::new(1, 64, 2), 1);
+        batcher.record(&Position::new(15, 79, 15), 2);
+        batcher.record(&Position::new(-1, -64, 16), 3);
+        // Only the last change to a block is kept.
+        batcher.record(&Position::new(1, 64, 2), 4);
+
+        let changes = batcher.take();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(
+            changes[&(0, 4, 0)],
+            HashMap::from([((1, 0, 2), 4), ((15, 15, 15), 2)])
+        );
+        assert_eq!(changes[&(-1, -4, 1)], HashMap::from([((15, 0, 0), 3)]));
+
+        assert!(batcher.take().is_empty());
+    }
+
+    #[test]
+    fn test_light_changes_are_sent_once_per_section() {
+        let batcher = BlockChangeBatcher::new();
+        batcher.record_light(0, 0, &[3, 4]);
+        batcher.record_light(-1, 2, &[-4]);
+        batcher.record_light(0, 0, &[4, 5]);
+        assert_eq!(
+            batcher.take_light(),
+            HashMap::from([
+                ((0, 0), BTreeSet::from([3, 4, 5])),
+                ((-1, 2), BTreeSet::from([-4]))
+            ])
+        );
+        assert!(batcher.take_light().is_empty());
+    }
+}
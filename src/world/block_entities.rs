@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+
+use nbt_lib::nbt_spec::serializer::impls::{NBTAnonymousType, NBTFieldType};
+use nbt_lib::nbt_spec::serializer::tag_types::{TAG_COMPOUND, TAG_INT, TAG_STRING};
+use nbt_lib::{NBTDeserialize, NBTError, NBTResult, NBTSerialize, NBTTag};
+
+use crate::net::packets::outgoing::block_entity_data::BlockEntityData;
+use crate::net::packets::ConnectionId;
+use crate::state::ServerState;
+use crate::utils::encoding::position::Position;
+use crate::utils::error::Error;
+use crate::world::chunk_format::{BlockEntity, Chunk, Palette};
+use crate::world::dimensions::Dimension;
+
+/// Every sign has this many lines on each side.
+pub const SIGN_LINES: usize = 4;
+
+/// The block entity type registry, in order, so a type's id is its index.
+const BLOCK_ENTITY_TYPES: [&str; 41] = [
+    "minecraft:furnace",
+    "minecraft:chest",
+    "minecraft:trapped_chest",
+    "minecraft:ender_chest",
+    "minecraft:jukebox",
+    "minecraft:dispenser",
+    "minecraft:dropper",
+    "minecraft:sign",
+    "minecraft:hanging_sign",
+    "minecraft:mob_spawner",
+    "minecraft:piston",
+    "minecraft:brewing_stand",
+    "minecraft:enchanting_table",
+    "minecraft:end_portal",
+    "minecraft:beacon",
+    "minecraft:skull",
+    "minecraft:daylight_detector",
+    "minecraft:hopper",
+    "minecraft:comparator",
+    "minecraft:banner",
+    "minecraft:structure_block",
+    "minecraft:end_gateway",
+    "minecraft:command_block",
+    "minecraft:shulker_box",
+    "minecraft:bed",
+    "minecraft:conduit",
+    "minecraft:barrel",
+    "minecraft:smoker",
+    "minecraft:blast_furnace",
+    "minecraft:lectern",
+    "minecraft:bell",
+    "minecraft:jigsaw",
+    "minecraft:campfire",
+    "minecraft:beehive",
+    "minecraft:sculk_sensor",
+    "minecraft:calibrated_sculk_sensor",
+    "minecraft:sculk_catalyst",
+    "minecraft:sculk_shrieker",
+    "minecraft:chiseled_bookshelf",
+    "minecraft:brushable_block",
+    "minecraft:decorated_pot",
+];
+
+/// Whether the block is any kind of sign, standing, on a wall or hanging.
+pub fn is_sign(block: &Palette) -> bool {
+    block.name.ends_with("_sign")
+}
+
+/// The text on one side of a sign.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignText {
+    /// The four lines, as JSON text components.
+    pub messages: Vec<String>,
+    pub color: String,
+    pub has_glowing_text: bool,
+}
+
+impl SignText {
+    /// One side of a sign, with the lines as JSON text components.
+    pub fn new(messages: Vec<String>) -> Self {
+        Self {
+            messages,
+            color: "black".to_string(),
+            has_glowing_text: false,
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(vec![r#"{"text":""}"#.to_string(); SIGN_LINES])
+    }
+
+    fn to_nbt(&self) -> NBTTag {
+        let mut compound = HashMap::new();
+        compound.insert(
+            "messages".to_string(),
+            NBTTag::List(self.messages.iter().cloned().map(NBTTag::String).collect()),
+        );
+        compound.insert("color".to_string(), NBTTag::String(self.color.clone()));
+        compound.insert(
+            "has_glowing_text".to_string(),
+            NBTTag::Byte(self.has_glowing_text as i8),
+        );
+        NBTTag::Compound(compound)
+    }
+
+    /// Anything missing or of the wrong type is left as it is on a blank sign.
+    fn from_nbt(tag: NBTTag) -> Self {
+        let mut text = Self::empty();
+        let NBTTag::Compound(mut compound) = tag else {
+            return text;
+        };
+        if let Some(NBTTag::List(messages)) = compound.remove("messages") {
+            for (line, message) in text.messages.iter_mut().zip(messages) {
+                if let NBTTag::String(message) = message {
+                    *line = message;
+                }
+            }
+        }
+        if let Some(NBTTag::String(color)) = compound.remove("color") {
+            text.color = color;
+        }
+        if let Some(NBTTag::Byte(glowing)) = compound.remove("has_glowing_text") {
+            text.has_glowing_text = glowing != 0;
+        }
+        text
+    }
+}
+
+impl BlockEntity {
+    /// A block entity of the kind `id` at `position`, holding the tags in `nbt`.
+    pub fn new(id: &str, position: &Position, nbt: HashMap<String, NBTTag>) -> Result<Self, Error> {
+        let mut block_entity = Self {
+            id: id.to_string(),
+            x: position.x,
+            y: position.y as i32,
+            z: position.z,
+            data: Vec::new(),
+        };
+        block_entity.set_nbt(nbt)?;
+        Ok(block_entity)
+    }
+
+    /// A blank sign for `block`, which has to be a sign, at `position`.
+    pub fn sign(position: &Position, block: &Palette) -> Result<Self, Error> {
+        let id = if block.name.contains("hanging_sign") {
+            "minecraft:hanging_sign"
+        } else {
+            "minecraft:sign"
+        };
+        let mut nbt = HashMap::new();
+        nbt.insert("front_text".to_string(), SignText::empty().to_nbt());
+        nbt.insert("back_text".to_string(), SignText::empty().to_nbt());
+        nbt.insert("is_waxed".to_string(), NBTTag::Byte(0));
+        Self::new(id, position, nbt)
+    }
+
+    pub fn position(&self) -> Position {
+        Position::new(self.x, self.y as i16, self.z)
+    }
+
+    /// The id clients know the block entity's type by, if it's one they can be sent.
+    pub fn type_id(&self) -> Option<i32> {
+        BLOCK_ENTITY_TYPES
+            .iter()
+            .position(|id| *id == self.id)
+            .map(|id| id as i32)
+    }
+
+    /// Everything stored in the block entity other than its id and position.
+    pub fn nbt(&self) -> Result<HashMap<String, NBTTag>, Error> {
+        match nbt_lib::read_tag(&mut Cursor::new(self.data.clone()))? {
+            NBTTag::Compound(compound) => Ok(compound),
+            tag => Err(NBTError::InvalidType("BlockEntity", tag.my_type()).into()),
+        }
+    }
+
+    /// Replaces everything stored in the block entity other than its id and position.
+    pub fn set_nbt(&mut self, nbt: HashMap<String, NBTTag>) -> Result<(), Error> {
+        let mut data = Vec::new();
+        NBTTag::Compound(nbt).nbt_serialize(&mut data)?;
+        self.data = data;
+        Ok(())
+    }
+
+    /// The text on the front or the back of a sign. Blank if it has none.
+    pub fn sign_text(&self, front: bool) -> Result<SignText, Error> {
+        let side = if front { "front_text" } else { "back_text" };
+        Ok(self
+            .nbt()?
+            .remove(side)
+            .map_or_else(SignText::empty, SignText::from_nbt))
+    }
+
+    pub fn set_sign_text(&mut self, front: bool, text: &SignText) -> Result<(), Error> {
+        let side = if front { "front_text" } else { "back_text" };
+        let mut nbt = self.nbt()?;
+        nbt.insert(side.to_string(), text.to_nbt());
+        self.set_nbt(nbt)
+    }
+
+    /// Waxed signs can't be edited.
+    pub fn is_waxed(&self) -> Result<bool, Error> {
+        Ok(matches!(self.nbt()?.get("is_waxed"), Some(NBTTag::Byte(waxed)) if *waxed != 0))
+    }
+
+    /// The block entity's data as clients expect it, a nameless root compound. The id and
+    /// position are left out, since they're sent alongside it.
+    pub fn to_network_nbt(&self) -> Result<Vec<u8>, Error> {
+        // TAG_Compound, then an empty name.
+        let mut nbt = vec![0x0A, 0x00, 0x00];
+        nbt.extend_from_slice(&self.data);
+        Ok(nbt)
+    }
+}
+
+/// Block entities in a chunk are compounds with their id and position next to whatever data
+/// their kind has.
+impl NBTDeserialize for BlockEntity {
+    fn read_from(nbt: NBTTag) -> NBTResult<Self> {
+        let mut compound = match nbt {
+            NBTTag::Compound(compound) => compound,
+            tag => return Err(NBTError::InvalidType("BlockEntity", tag.my_type())),
+        };
+        let mut field = |name: &str| {
+            compound
+                .remove(name)
+                .ok_or_else(|| NBTError::DeserializeError(format!("Field {} not found", name)))
+        };
+        let id = String::read_from(field("id")?)?;
+        let x = i32::read_from(field("x")?)?;
+        let y = i32::read_from(field("y")?)?;
+        let z = i32::read_from(field("z")?)?;
+
+        let mut data = Vec::new();
+        NBTTag::Compound(compound).nbt_serialize(&mut data)?;
+        Ok(Self { id, x, y, z, data })
+    }
+}
+
+impl NBTSerialize for BlockEntity {
+    fn nbt_serialize<W: Write>(&self, writer: &mut W) -> NBTResult<()> {
+        TAG_STRING.nbt_serialize(writer)?;
+        "id".nbt_serialize(writer)?;
+        self.id.nbt_serialize(writer)?;
+        for (name, value) in [("x", self.x), ("y", self.y), ("z", self.z)] {
+            TAG_INT.nbt_serialize(writer)?;
+            name.nbt_serialize(writer)?;
+            value.nbt_serialize(writer)?;
+        }
+        // Ends with the compound's TAG_End.
+        writer.write_all(&self.data)?;
+        Ok(())
+    }
+}
+
+impl NBTFieldType for BlockEntity {
+    fn tag_type(&self) -> u8 {
+        TAG_COMPOUND
+    }
+}
+
+impl NBTAnonymousType for BlockEntity {
+    fn tag_type() -> u8 {
+        TAG_COMPOUND
+    }
+}
+
+impl Chunk {
+    /// The block entity at the world coordinates `x`, `y`, `z`, if there is one.
+    pub fn get_block_entity(&self, x: i32, y: i32, z: i32) -> Option<&BlockEntity> {
+        self.block_entities
+            .iter()
+            .flatten()
+            .find(|entity| (entity.x, entity.y, entity.z) == (x, y, z))
+    }
+
+    /// Adds a block entity, replacing whatever block entity was at its position.
+    pub fn set_block_entity(&mut self, block_entity: BlockEntity) {
+        self.remove_block_entity(block_entity.x, block_entity.y, block_entity.z);
+        self.block_entities
+            .get_or_insert_with(Vec::new)
+            .push(block_entity);
+    }
+
+    pub fn remove_block_entity(&mut self, x: i32, y: i32, z: i32) -> Option<BlockEntity> {
+        let block_entities = self.block_entities.as_mut()?;
+        let index = block_entities
+            .iter()
+            .position(|entity| (entity.x, entity.y, entity.z) == (x, y, z))?;
+        Some(block_entities.remove(index))
+    }
+}
+
+impl ServerState {
+    /// The block entity at `location` in `dimension`, or `None` if there isn't one or its chunk
+    /// isn't loaded.
+    pub async fn get_block_entity(
+        &self,
+        dimension: &Dimension,
+        location: &Position,
+    ) -> Result<Option<BlockEntity>, Error> {
+        let (x, y, z) = (location.x, location.y as i32, location.z);
+        let Some(chunk) = self
+            .database
+            .get_chunk(x >> 4, z >> 4, dimension.key.clone())
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(chunk.get_block_entity(x, y, z).cloned())
+    }
+
+    /// Stores a block entity in `dimension`, and shows every player that can see it. Fails if
+    /// its chunk isn't loaded.
+    pub async fn set_block_entity(
+        &self,
+        dimension: &Dimension,
+        block_entity: BlockEntity,
+    ) -> Result<(), Error> {
+        let location = block_entity.position();
+        let (chunk_x, chunk_z) = (block_entity.x >> 4, block_entity.z >> 4);
+        // Same as for blocks, see [ServerState::set_block].
+        let lock = self
+            .database
+            .lock_chunk(chunk_x, chunk_z, &dimension.key)
+            .await;
+        let mut chunk = self
+            .database
+            .get_chunk(chunk_x, chunk_z, dimension.key.clone())
+            .await?
+            .ok_or(Error::ChunkNotFound(chunk_x, chunk_z))?;
+        let packet = BlockEntityData::new(&block_entity)?;
+        chunk.set_block_entity(block_entity);
+        self.database.update_chunk(chunk).await?;
+        drop(lock);
+
+        if let Some(packet) = packet {
+            self.send_to_players_near(dimension, &location, None, || packet.clone())
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Sends one player the block entity that's really at `location` in `dimension`, if there is
+    /// one, to undo a change their client made to it that we turned down.
+    pub async fn resend_block_entity(
+        &self,
+        dimension: &Dimension,
+        conn_id: ConnectionId,
+        location: &Position,
+    ) -> Result<(), Error> {
+        let Some(block_entity) = self.get_block_entity(dimension, location).await? else {
+            return Ok(());
+        };
+        let Some(packet) = BlockEntityData::new(&block_entity)? else {
+            return Ok(());
+        };
+        let conn = self.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(packet).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oak_sign() -> Palette {
+        Palette {
+            name: "minecraft:oak_sign".to_string(),
+            properties: None,
+        }
+    }
+
+    #[test]
+    fn test_signs() {
+        assert!(is_sign(&oak_sign()));
+        assert!(!is_sign(&Palette {
+            name: "minecraft:stone".to_string(),
+            properties: None,
+        }));
+
+        let mut sign = BlockEntity::sign(&Position::new(1, -2, 3), &oak_sign()).unwrap();
+        assert_eq!(sign.type_id(), Some(7));
+        assert_eq!((sign.x, sign.y, sign.z), (1, -2, 3));
+        assert!(!sign.is_waxed().unwrap());
+        assert_eq!(sign.sign_text(true).unwrap(), SignText::empty());
+
+        let mut text = SignText::new(vec![r#"{"text":"Hi"}"#.to_string(); SIGN_LINES]);
+        text.has_glowing_text = true;
+        sign.set_sign_text(false, &text).unwrap();
+        assert_eq!(sign.sign_text(false).unwrap(), text);
+        assert_eq!(sign.sign_text(true).unwrap(), SignText::empty());
+
+        let hanging = Palette {
+            name: "minecraft:oak_wall_hanging_sign".to_string(),
+            properties: None,
+        };
+        let hanging = BlockEntity::sign(&Position::new(0, 0, 0), &hanging).unwrap();
+        assert_eq!(hanging.type_id(), Some(8));
+    }
+
+    #[test]
+    fn test_type_ids() {
+        let chest = BlockEntity::new("minecraft:chest", &Position::new(0, 0, 0), HashMap::new());
+        assert_eq!(chest.unwrap().type_id(), Some(1));
+        let pot = BlockEntity::new(
+            "minecraft:decorated_pot",
+            &Position::new(0, 0, 0),
+            HashMap::new(),
+        );
+        assert_eq!(pot.unwrap().type_id(), Some(40));
+        let unknown = BlockEntity::new("mymod:machine", &Position::new(0, 0, 0), HashMap::new());
+        assert_eq!(unknown.unwrap().type_id(), None);
+    }
+
+    #[test]
+    fn test_nbt_round_trip() {
+        let mut nbt = HashMap::new();
+        nbt.insert("CustomName".to_string(), NBTTag::String("Loot".to_string()));
+        nbt.insert("Items".to_string(), NBTTag::List(Vec::new()));
+        let chest = BlockEntity::new("minecraft:chest", &Position::new(-5, 70, 12), nbt).unwrap();
+
+        let mut bytes = Vec::new();
+        chest.nbt_serialize(&mut bytes).unwrap();
+        let tag = nbt_lib::read_tag(&mut Cursor::new(bytes)).unwrap();
+        let read = BlockEntity::read_from(tag).unwrap();
+        assert_eq!(
+            (read.id.as_str(), read.x, read.y, read.z),
+            ("minecraft:chest", -5, 70, 12)
+        );
+        let mut nbt = read.nbt().unwrap();
+        assert!(matches!(nbt.remove("CustomName"), Some(NBTTag::String(name)) if name == "Loot"));
+        assert!(matches!(nbt.remove("Items"), Some(NBTTag::List(items)) if items.is_empty()));
+        assert!(nbt.is_empty());
+    }
+
+    #[test]
+    fn test_network_nbt() {
+        let sign = BlockEntity::sign(&Position::new(0, 0, 0), &oak_sign()).unwrap();
+        let nbt = sign.to_network_nbt().unwrap();
+        assert_eq!(nbt[..3], [0x0A, 0x00, 0x00]);
+        assert_eq!(*nbt.last().unwrap(), 0x00);
+
+        let tag = nbt_lib::read_tag(&mut std::io::Cursor::new(nbt)).unwrap();
+        let NBTTag::Compound(mut root) = tag else {
+            panic!("Expected a compound, got {:?}", tag);
+        };
+        let root = match root.remove("") {
+            Some(NBTTag::Compound(root)) => root,
+            _ => root,
+        };
+        assert!(matches!(root.get("is_waxed"), Some(NBTTag::Byte(0))));
+        let Some(NBTTag::Compound(back_text)) = root.get("back_text") else {
+            panic!("back_text is missing");
+        };
+        let Some(NBTTag::List(messages)) = back_text.get("messages") else {
+            panic!("messages are missing");
+        };
+        assert_eq!(messages.len(), SIGN_LINES);
+    }
+
+    #[test]
+    fn test_chunk_block_entities() {
+        let mut chunk = Chunk {
+            dimension: None,
+            status: "full".to_string(),
+            data_version: 3465,
+            heightmaps: None,
+            is_light_on: None,
+            inhabited_time: None,
+            y_pos: -4,
+            x_pos: 0,
+            z_pos: 0,
+            structures: None,
+            last_update: None,
+            sections: None,
+            block_entities: None,
+        };
+        assert!(chunk.get_block_entity(1, 2, 3).is_none());
+
+        let mut sign = BlockEntity::sign(&Position::new(1, 2, 3), &oak_sign()).unwrap();
+        chunk.set_block_entity(sign.clone());
+        let mut nbt = sign.nbt().unwrap();
+        nbt.insert("is_waxed".to_string(), NBTTag::Byte(1));
+        sign.set_nbt(nbt).unwrap();
+        chunk.set_block_entity(sign.clone());
+        assert_eq!(chunk.block_entities.as_ref().unwrap().len(), 1);
+        assert_eq!(chunk.get_block_entity(1, 2, 3), Some(&sign));
+        assert!(chunk.get_block_entity(1, 2, 3).unwrap().is_waxed().unwrap());
+
+        assert_eq!(chunk.remove_block_entity(1, 2, 3), Some(sign));
+        assert!(chunk.get_block_entity(1, 2, 3).is_none());
+    }
+}
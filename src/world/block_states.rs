@@ -0,0 +1,417 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Read;
+use std::sync::LazyLock;
+
+use hashbrown::HashMap;
+use serde_derive::Deserialize;
+
+use crate::world::chunk_format::Palette;
+
+/// Every vanilla block state by its network id, from the game's block report.
+const BLOCKSFILE: &[u8] = include_bytes!("../../.etc/blockmappings.bz2");
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::load);
+
+/// A block state in the mappings file. Each block has one state marked as its default, the one
+/// vanilla's block report marks.
+#[derive(Deserialize)]
+struct RawState {
+    name: String,
+    properties: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    default: bool,
+}
+
+/// A property and the values it can have, in the order they count up in state ids. Blocks with
+/// the same property share one, e.g. every stair's `facing`.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Property {
+    name: Box<str>,
+    values: Box<[Box<str>]>,
+}
+
+/// A block and the range of state ids its states take.
+///
+/// Vanilla numbers every combination of a block's properties in turn, with the last property
+/// counting up fastest, so a state's id is `first_state` plus each value's index times its
+/// property's stride.
+struct Block {
+    name: Box<str>,
+    first_state: u32,
+    default_state: u32,
+    /// Indices into [Registry::properties] and their strides, last counting up fastest.
+    properties: Box<[(u16, u32)]>,
+}
+
+struct Registry {
+    properties: Vec<Property>,
+    /// By first state id.
+    blocks: Vec<Block>,
+    /// Indices into `blocks`, by name.
+    by_name: Vec<u16>,
+    state_count: u32,
+}
+
+impl Registry {
+    fn load() -> Self {
+        let mut reader = bzip2::read::BzDecoder::new(BLOCKSFILE);
+        let mut json = String::new();
+        reader
+            .read_to_string(&mut json)
+            .expect("The block mappings should decompress");
+        let raw: HashMap<String, RawState> =
+            serde_json::from_str(&json).expect("The block mappings should be valid JSON");
+        let mut states = raw
+            .into_iter()
+            .map(|(id, state)| (id.parse::<u32>().expect("Block ids are numbers"), state))
+            .collect::<Vec<_>>();
+        states.sort_unstable_by_key(|(id, _)| *id);
+        Self::from_states(&states)
+    }
+
+    /// Builds the registry from every state, sorted by id. Every block's states have to be one
+    /// after another, numbered the way vanilla numbers them.
+    fn from_states(states: &[(u32, RawState)]) -> Self {
+        let mut registry = Registry {
+            properties: Vec::new(),
+            blocks: Vec::new(),
+            by_name: Vec::new(),
+            state_count: states.len() as u32,
+        };
+        let mut interned = HashMap::new();
+
+        let mut start = 0;
+        while start < states.len() {
+            let name = &states[start].1.name;
+            let end = states[start..]
+                .iter()
+                .position(|(_, state)| &state.name != name)
+                .map_or(states.len(), |len| start + len);
+            let block_states = &states[start..end];
+            let first_state = block_states[0].0;
+            let first_properties = block_states[0].1.properties.clone().unwrap_or_default();
+
+            let mut properties = Vec::new();
+            for property in first_properties.keys() {
+                let mut values = Vec::<Box<str>>::new();
+                for (_, state) in block_states {
+                    let value = &state.properties.as_ref().unwrap()[property];
+                    if !values.iter().any(|known| **known == **value) {
+                        values.push(value.as_str().into());
+                    }
+                }
+                // How many states go by before it first changes.
+                let stride = block_states
+                    .iter()
+                    .position(|(_, state)| {
+                        state.properties.as_ref().unwrap()[property] != first_properties[property]
+                    })
+                    .unwrap_or(1) as u32;
+                let property = Property {
+                    name: property.as_str().into(),
+                    values: values.into(),
+                };
+                let index = *interned.entry(property).or_insert_with_key(|property| {
+                    registry.properties.push(property.clone());
+                    (registry.properties.len() - 1) as u16
+                });
+                properties.push((index, stride));
+            }
+            properties.sort_by_key(|&(_, stride)| std::cmp::Reverse(stride));
+
+            let default_state = block_states
+                .iter()
+                .find(|(_, state)| state.default)
+                .map(|(id, _)| *id)
+                .unwrap_or_else(|| panic!("{} doesn't have a default state", name));
+            let block = Block {
+                name: name.as_str().into(),
+                first_state,
+                default_state,
+                properties: properties.into(),
+            };
+            for (id, state) in block_states {
+                let props = state.properties.clone().unwrap_or_default();
+                let props = props
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str()))
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    registry.state_of(&block, first_state, &props),
+                    Some(*id),
+                    "{} isn't numbered like vanilla numbers its states",
+                    name
+                );
+            }
+            registry.blocks.push(block);
+            start = end;
+        }
+
+        let mut by_name = (0..registry.blocks.len() as u16).collect::<Vec<_>>();
+        by_name.sort_unstable_by(|&a, &b| {
+            registry.blocks[a as usize]
+                .name
+                .cmp(&registry.blocks[b as usize].name)
+        });
+        registry.by_name = by_name;
+        registry
+    }
+
+    fn block(&self, id: u32) -> Option<&Block> {
+        if id >= self.state_count {
+            return None;
+        }
+        let index = self.blocks.partition_point(|block| block.first_state <= id);
+        self.blocks.get(index.checked_sub(1)?)
+    }
+
+    fn block_by_name(&self, name: &str) -> Option<&Block> {
+        let index = self
+            .by_name
+            .binary_search_by(|&index| (*self.blocks[index as usize].name).cmp(name))
+            .ok()?;
+        Some(&self.blocks[self.by_name[index] as usize])
+    }
+
+    /// The id of `block`'s state with the values in `props`, and the ones in `base` for the
+    /// properties left out. `None` if a property or value isn't one the block has.
+    fn state_of(&self, block: &Block, base: u32, props: &[(&str, &str)]) -> Option<u32> {
+        let mut id = block.first_state;
+        for &(index, stride) in block.properties.iter() {
+            let property = &self.properties[index as usize];
+            let count = property.values.len() as u32;
+            let value = match props.iter().find(|(name, _)| **name == *property.name) {
+                Some((_, value)) => property
+                    .values
+                    .iter()
+                    .position(|known| **known == **value)?
+                    as u32,
+                None => (base - block.first_state) / stride % count,
+            };
+            id += value * stride;
+        }
+        let known = props.iter().all(|(name, _)| {
+            block
+                .properties
+                .iter()
+                .any(|&(index, _)| *self.properties[index as usize].name == **name)
+        });
+        known.then_some(id)
+    }
+}
+
+/// A block state, as its network id.
+///
+/// Names and properties are kept once per block in a registry built from the block mappings,
+/// rather than for every state, so this is just a number and they're worked out from it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockState(u16);
+
+impl BlockState {
+    pub const AIR: BlockState = BlockState(0);
+
+    /// The state with the network id `id`, or `None` if there isn't one.
+    pub fn from_id(id: u32) -> Option<Self> {
+        REGISTRY.block(id).map(|_| Self(id as u16))
+    }
+
+    /// The state of the block called `name` with the properties in `props`. Properties that
+    /// are left out are the block's default. `None` if there's no such block, or it doesn't have
+    /// one of the properties or values.
+    pub fn from_name_and_props(name: &str, props: &[(&str, &str)]) -> Option<Self> {
+        let registry = &*REGISTRY;
+        let block = registry.block_by_name(name)?;
+        registry
+            .state_of(block, block.default_state, props)
+            .map(|id| Self(id as u16))
+    }
+
+    /// The state to place a block in when nothing decides otherwise, the same one vanilla
+    /// defaults to, e.g. stairs facing north on the bottom half and not waterlogged. `None` if
+    /// there's no block called `name`.
+    pub fn default_state(name: &str) -> Option<Self> {
+        REGISTRY
+            .block_by_name(name)
+            .map(|block| Self(block.default_state as u16))
+    }
+
+    /// How many block states there are. Their ids are every number below this.
+    pub fn count() -> u32 {
+        REGISTRY.state_count
+    }
+
+    /// The state a chunk palette entry is for.
+    pub fn from_palette(palette: &Palette) -> Option<Self> {
+        let props = palette
+            .properties
+            .iter()
+            .flatten()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect::<Vec<_>>();
+        Self::from_name_and_props(&palette.name, &props)
+    }
+
+    pub fn id(self) -> u32 {
+        self.0 as u32
+    }
+
+    fn block(self) -> &'static Block {
+        REGISTRY
+            .block(self.id())
+            .expect("Block states are only made from valid ids")
+    }
+
+    /// The block's name, with its namespace, e.g. `minecraft:oak_stairs`.
+    pub fn name(self) -> &'static str {
+        &self.block().name
+    }
+
+    /// The state's properties and their values, in the order vanilla lists them.
+    pub fn properties(self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        let registry = &*REGISTRY;
+        let block = self.block();
+        let offset = self.id() - block.first_state;
+        block.properties.iter().map(move |&(index, stride)| {
+            let property = &registry.properties[index as usize];
+            let value = offset / stride % property.values.len() as u32;
+            (&*property.name, &*property.values[value as usize])
+        })
+    }
+
+    /// The value of the property called `name`, or `None` if the block doesn't have it.
+    pub fn property(self, name: &str) -> Option<&'static str> {
+        self.properties()
+            .find(|(property, _)| *property == name)
+            .map(|(_, value)| value)
+    }
+
+    /// The same block, with `property` set to `value`. `None` if it doesn't have that property
+    /// or value.
+    pub fn with_property(self, property: &str, value: &str) -> Option<Self> {
+        let registry = &*REGISTRY;
+        let block = self.block();
+        registry
+            .state_of(block, self.id(), &[(property, value)])
+            .map(|id| Self(id as u16))
+    }
+
+    /// The chunk palette entry for this state.
+    pub fn to_palette(self) -> Palette {
+        let properties = self
+            .properties()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>();
+        Palette {
+            name: self.name().to_string(),
+            properties: (!properties.is_empty()).then_some(properties),
+        }
+    }
+}
+
+impl fmt::Debug for BlockState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+/// Like vanilla writes them in commands, e.g. `minecraft:oak_stairs[facing=east,half=top]`.
+impl fmt::Display for BlockState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())?;
+        let properties = self
+            .properties()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>();
+        if !properties.is_empty() {
+            write!(f, "[{}]", properties.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_ids() {
+        assert_eq!(BlockState::from_id(0), Some(BlockState::AIR));
+        assert_eq!(BlockState::AIR.name(), "minecraft:air");
+        assert_eq!(
+            BlockState::default_state("minecraft:air"),
+            Some(BlockState::AIR)
+        );
+        assert_eq!(
+            BlockState::default_state("minecraft:stone").unwrap().id(),
+            1
+        );
+        assert_eq!(
+            BlockState::from_name_and_props("minecraft:grass_block", &[("snowy", "false")])
+                .unwrap()
+                .id(),
+            9
+        );
+        assert_eq!(BlockState::from_id(REGISTRY.state_count), None);
+        assert_eq!(BlockState::default_state("minecraft:not_a_block"), None);
+    }
+
+    #[test]
+    fn test_stairs() {
+        let stairs = BlockState::from_name_and_props(
+            "minecraft:oak_stairs",
+            &[
+                ("facing", "north"),
+                ("half", "bottom"),
+                ("shape", "straight"),
+                ("waterlogged", "false"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(stairs.id(), 2885);
+        assert_eq!(stairs.property("half"), Some("bottom"));
+        assert_eq!(
+            stairs.to_string(),
+            "minecraft:oak_stairs[facing=north,half=bottom,shape=straight,waterlogged=false]"
+        );
+
+        let east = stairs.with_property("facing", "east").unwrap();
+        assert_eq!(east.id(), 2885 + 3 * 20);
+        assert_eq!(east.property("shape"), Some("straight"));
+        assert_eq!(stairs.with_property("facing", "up"), None);
+        assert_eq!(stairs.with_property("axis", "x"), None);
+        assert_eq!(
+            BlockState::from_name_and_props("minecraft:oak_stairs", &[("facing", "sideways")]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_default_states() {
+        let stairs = BlockState::default_state("minecraft:oak_stairs").unwrap();
+        assert_eq!(stairs.property("waterlogged"), Some("false"));
+        assert_eq!(stairs.property("half"), Some("bottom"));
+        assert_eq!(stairs.property("facing"), Some("north"));
+        assert_eq!(stairs.property("shape"), Some("straight"));
+
+        let slab = BlockState::default_state("minecraft:oak_slab").unwrap();
+        assert_eq!(slab.property("type"), Some("bottom"));
+        assert_eq!(slab.property("waterlogged"), Some("false"));
+        let fence = BlockState::default_state("minecraft:oak_fence").unwrap();
+        assert!(fence.properties().all(|(_, value)| value == "false"));
+        let log = BlockState::default_state("minecraft:oak_log").unwrap();
+        assert_eq!(log.property("axis"), Some("y"));
+
+        // Properties left out are the default's.
+        let top = BlockState::from_name_and_props("minecraft:oak_slab", &[("type", "top")]);
+        assert_eq!(top.unwrap().property("waterlogged"), Some("false"));
+    }
+
+    #[test]
+    fn test_every_state_round_trips() {
+        for id in 0..REGISTRY.state_count {
+            let state = BlockState::from_id(id).unwrap();
+            assert_eq!(BlockState::from_palette(&state.to_palette()), Some(state));
+        }
+    }
+}
@@ -1,8 +1,15 @@
 use tracing::debug;
 
-use crate::state::GlobalState;
+use crate::net::packets::outgoing::block_update::BlockUpdate;
+use crate::net::packets::ConnectionId;
+use crate::state::{GlobalState, ServerState};
 use crate::utils::binary_utils::read_n_bits_u16;
+use crate::utils::encoding::position::Position;
 use crate::utils::error::Error;
+use crate::world::chunk_format::{Chunk, Palette, Section};
+use crate::world::conversions::{block_from_id, block_id};
+use crate::world::dimensions::Dimension;
+use crate::world::lighting::opacity;
 
 pub async fn read_block(
     state: GlobalState,
@@ -110,6 +117,219 @@ pub async fn read_block(
     }
 }
 
+/// The lowest y blocks can be at in the overworld.
+pub const MIN_BUILD_HEIGHT: i32 = -64;
+/// The y above the highest blocks in the overworld.
+pub const MAX_BUILD_HEIGHT: i32 = 320;
+
+impl ServerState {
+    /// The block at `location` in `dimension`, or `None` if its chunk isn't loaded or it's
+    /// outside the world.
+    pub async fn get_block(
+        &self,
+        dimension: &Dimension,
+        location: &Position,
+    ) -> Result<Option<Palette>, Error> {
+        let (x, y, z) = (location.x, location.y as i32, location.z);
+        let block = self
+            .database
+            .read_chunk(x >> 4, z >> 4, &dimension.key, |chunk| {
+                chunk.get_block(x, y, z).ok()
+            })
+            .await?;
+        Ok(block.flatten())
+    }
+
+    /// Changes the block at `location` in `dimension`. Players that can see it are sent the
+    /// change, and the light of the sections whose light changed too, at the end of the tick, see
+    /// [ServerState::flush_block_changes]. Fails if its chunk isn't loaded.
+    ///
+    /// The chunk is locked while it's changed, so blocks set in it at the same time all stick.
+    pub async fn set_block(
+        &self,
+        dimension: &Dimension,
+        location: &Position,
+        block: Palette,
+    ) -> Result<(), Error> {
+        let (x, y, z) = (location.x, location.y as i32, location.z);
+        let (chunk_x, chunk_z) = (x >> 4, z >> 4);
+        let _lock = self
+            .database
+            .lock_chunk(chunk_x, chunk_z, &dimension.key)
+            .await;
+        let mut chunk = self
+            .database
+            .get_chunk(chunk_x, chunk_z, dimension.key.clone())
+            .await?
+            .ok_or(Error::ChunkNotFound(chunk_x, chunk_z))?;
+        let id = block_id(&block).unwrap_or(0);
+        let relit = chunk.set_block(x, y, z, block)?;
+        self.database.update_chunk(chunk).await?;
+
+        dimension.block_changes.record(location, id);
+        if !relit.is_empty() {
+            dimension
+                .block_changes
+                .record_light(chunk_x, chunk_z, &relit);
+        }
+        Ok(())
+    }
+
+    /// Sends one player the block that's really at `location` in `dimension`, to undo a change
+    /// their client made to it that we turned down.
+    pub async fn resend_block(
+        &self,
+        dimension: &Dimension,
+        conn_id: ConnectionId,
+        location: &Position,
+    ) -> Result<(), Error> {
+        let Some(block) = self.get_block(dimension, location).await? else {
+            return Ok(());
+        };
+        let conn = self.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(BlockUpdate::new(
+            location.clone(),
+            block_id(&block).unwrap_or(0),
+        ))
+        .await
+    }
+}
+
+/// The block everything is broken into.
+pub fn air() -> Palette {
+    Palette {
+        name: "minecraft:air".to_string(),
+        properties: None,
+    }
+}
+
+impl Chunk {
+    /// The block at the world coordinates `x`, `y`, `z`, which have to be inside this chunk.
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> Result<Palette, Error> {
+        let id = self.get_block_id(x, y, z)?;
+        block_from_id(id).ok_or(Error::InvalidChunk(
+            self.x_pos,
+            self.z_pos,
+            format!("Unknown block id {}", id),
+        ))
+    }
+
+    /// The id of the block at the world coordinates `x`, `y`, `z`, which have to be inside this
+    /// chunk. Only that block is read, so it's cheap enough to call for a few blocks at a time.
+    pub fn get_block_id(&self, x: i32, y: i32, z: i32) -> Result<i32, Error> {
+        let section = self.section_at(y)?;
+        section
+            .block_states
+            .as_ref()
+            .and_then(|block_states| block_states.get(block_index(x, y, z)))
+            .ok_or(Error::InvalidChunk(
+                self.x_pos,
+                self.z_pos,
+                "Section has broken block states".to_string(),
+            ))
+    }
+
+    /// Sets the block at the world coordinates `x`, `y`, `z`, which have to be inside this chunk.
+    ///
+    /// Both palettes, the block data, the non-air block count, the heightmaps and the sky light
+    /// are kept up to date, so the chunk can be sent to clients as it is. Changing it to a
+    /// different block removes its block entity.
+    ///
+    /// Returns the y of every section whose sky light changed.
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: Palette) -> Result<Vec<i8>, Error> {
+        let (chunk_x, chunk_z) = (self.x_pos, self.z_pos);
+        let id = block_id(&block).ok_or(Error::InvalidChunk(
+            chunk_x,
+            chunk_z,
+            format!("Block {} not found in block mappings", block.name),
+        ))?;
+
+        // Whatever was stored with the old block, e.g. a sign's text, goes with it.
+        if self
+            .get_block(x, y, z)
+            .map_or(true, |old| old.name != block.name)
+        {
+            self.remove_block_entity(x, y, z);
+        }
+
+        let section = self.section_at_mut(y)?;
+        if section.block_states.is_none() {
+            section.set_empty();
+        }
+        let block_states = section.block_states.as_mut().unwrap();
+        let mut container = block_states
+            .container()
+            .map_err(|e| Error::InvalidChunk(chunk_x, chunk_z, e.to_string()))?;
+        let old = container.set(block_index(x, y, z), id);
+        block_states.set_container(&container);
+        self.update_heightmaps(x, y, z)?;
+        if opacity(old) == opacity(id) {
+            return Ok(Vec::new());
+        }
+        self.update_sky_light(x, y, z, opacity(old))
+    }
+
+    fn section_at(&self, y: i32) -> Result<&Section, Error> {
+        let section_y = y.div_euclid(16);
+        self.sections
+            .iter()
+            .flatten()
+            .find(|section| section.y as i32 == section_y)
+            .ok_or(Error::InvalidChunk(
+                self.x_pos,
+                self.z_pos,
+                format!("No section at y {}", y),
+            ))
+    }
+
+    fn section_at_mut(&mut self, y: i32) -> Result<&mut Section, Error> {
+        let section_y = y.div_euclid(16);
+        let (chunk_x, chunk_z) = (self.x_pos, self.z_pos);
+        self.sections
+            .iter_mut()
+            .flatten()
+            .find(|section| section.y as i32 == section_y)
+            .ok_or(Error::InvalidChunk(
+                chunk_x,
+                chunk_z,
+                format!("No section at y {}", y),
+            ))
+    }
+}
+
+const BLOCKS_PER_SECTION: usize = 4096;
+/// Where a block is in its section's data, from its world coordinates.
+fn block_index(x: i32, y: i32, z: i32) -> usize {
+    (y.rem_euclid(16) * 256 + z.rem_euclid(16) * 16 + x.rem_euclid(16)) as usize
+}
+
+/// Reads the palette index of every block in a section. Indices don't span across longs, so
+/// any bits left over at the top of each long are padding.
+pub(crate) fn unpack_indices(data: &[i64], bits: usize) -> Vec<u16> {
+    let mut indices = vec![0; BLOCKS_PER_SECTION];
+    if bits == 0 {
+        return indices;
+    }
+    let per_long = 64 / bits;
+    let mask = (1u64 << bits) - 1;
+    for (i, index) in indices.iter_mut().enumerate() {
+        if let Some(&long) = data.get(i / per_long) {
+            *index = ((long as u64 >> ((i % per_long) * bits)) & mask) as u16;
+        }
+    }
+    indices
+}
+
+pub(crate) fn pack_indices(indices: &[u16], bits: usize) -> Vec<i64> {
+    let per_long = 64 / bits;
+    let mut data = vec![0i64; indices.len().div_ceil(per_long)];
+    for (i, &index) in indices.iter().enumerate() {
+        data[i / per_long] |= ((index as u64) << ((i % per_long) * bits)) as i64;
+    }
+    data
+}
+
 #[cfg(test)]
 mod tests {
     use tokio::net::TcpListener;
@@ -117,6 +337,71 @@ mod tests {
 
     use crate::utils::setup_logger;
     use crate::world::blocks::read_block;
+    use crate::world::chunk_format::{Chunk, Palette, Section};
+
+    use super::air;
+    use crate::world::conversions::default_block_state;
+
+    fn empty_chunk() -> Chunk {
+        let mut section = Section {
+            block_states: None,
+            biomes: None,
+            y: -1,
+            block_light: None,
+            sky_light: None,
+        };
+        section.set_empty();
+        Chunk {
+            dimension: Some("overworld".to_string()),
+            status: "full".to_string(),
+            data_version: 3465,
+            heightmaps: None,
+            is_light_on: None,
+            inhabited_time: None,
+            y_pos: -4,
+            x_pos: -1,
+            z_pos: 0,
+            structures: None,
+            last_update: None,
+            sections: Some(vec![section]),
+            block_entities: None,
+        }
+    }
+
+    #[test]
+    fn test_set_and_get_blocks() {
+        let stone = Palette {
+            name: "minecraft:stone".to_string(),
+            properties: None,
+        };
+        let mut chunk = empty_chunk();
+        assert_eq!(chunk.get_block(-3, -5, 2).unwrap(), air());
+
+        chunk.set_block(-3, -5, 2, stone.clone()).unwrap();
+        assert_eq!(chunk.get_block(-3, -5, 2).unwrap(), stone);
+        assert_eq!(chunk.get_block(-4, -5, 2).unwrap(), air());
+        let block_states = chunk.sections.as_ref().unwrap()[0]
+            .block_states
+            .clone()
+            .unwrap();
+        assert_eq!(block_states.non_air_blocks, Some(1));
+        assert_eq!(block_states.bits_per_block, Some(4));
+        assert_eq!(block_states.palette.unwrap(), vec![air(), stone]);
+
+        chunk.set_block(-3, -5, 2, air()).unwrap();
+        assert_eq!(chunk.get_block(-3, -5, 2).unwrap(), air());
+        assert!(chunk.get_block(-3, 20, 2).is_err());
+        assert!(chunk.set_block(-3, 20, 2, air()).is_err());
+    }
+
+    #[test]
+    fn test_default_block_states() {
+        assert_eq!(default_block_state("minecraft:air"), Some(air()));
+        let log = default_block_state("minecraft:oak_log").unwrap();
+        assert_eq!(log.name, "minecraft:oak_log");
+        assert!(log.properties.unwrap().contains_key("axis"));
+        assert!(default_block_state("minecraft:not_a_block").is_none());
+    }
 
     #[tokio::test]
     #[ignore]
@@ -124,7 +409,7 @@ mod tests {
         if setup_logger().is_ok() {
             warn!("Logger already set up");
         }
-        let state = crate::create_state(TcpListener::bind("0.0.0.0:0").await.unwrap())
+        let state = crate::create_state(vec![TcpListener::bind("0.0.0.0:0").await.unwrap()])
             .await
             .unwrap();
         info!(
@@ -0,0 +1,244 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::database::Database;
+use crate::net::packets::outgoing::set_border_center::SetBorderCenter;
+use crate::net::packets::outgoing::set_border_lerp_size::SetBorderLerpSize;
+use crate::net::packets::outgoing::set_border_size::SetBorderSize;
+use crate::net::packets::outgoing::set_border_warning_delay::SetBorderWarningDelay;
+use crate::net::packets::outgoing::set_border_warning_distance::SetBorderWarningDistance;
+use crate::state::ServerState;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+/// What the world info table saves the border under.
+const BORDER_KEY: &str = "border";
+
+/// The border is moving from `from` to [WorldBorder::diameter], over `duration` from `start`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Transition {
+    from: f64,
+    start: Instant,
+    duration: Duration,
+}
+
+/// The square players can't leave, centered on `center`.
+///
+/// - `diameter`: How wide the border is, or will be once it's done moving.
+/// - `warning_blocks`: How close to the border players have to be for their screen to go red.
+/// - `warning_time`: How many seconds before a shrinking border reaches players their screen
+///   goes red.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldBorder {
+    pub center: (f64, f64),
+    pub diameter: f64,
+    pub warning_blocks: i32,
+    pub warning_time: i32,
+    transition: Option<Transition>,
+}
+
+impl WorldBorder {
+    pub fn new(center: (f64, f64), diameter: f64, warning_blocks: i32, warning_time: i32) -> Self {
+        Self {
+            center,
+            diameter,
+            warning_blocks,
+            warning_time,
+            transition: None,
+        }
+    }
+
+    /// The border from the config's `world_border`, for worlds that don't have one saved yet.
+    pub fn from_config() -> Self {
+        let config = &get_global_config().world_border;
+        Self::new(
+            (config.center_x, config.center_z),
+            config.diameter,
+            config.warning_blocks,
+            config.warning_time,
+        )
+    }
+
+    /// How wide the border is at `now`, partway through moving if it still is.
+    pub fn diameter_at(&self, now: Instant) -> f64 {
+        let Some(transition) = self.transition else {
+            return self.diameter;
+        };
+        let elapsed = now.saturating_duration_since(transition.start);
+        if elapsed >= transition.duration {
+            return self.diameter;
+        }
+        let done = elapsed.as_secs_f64() / transition.duration.as_secs_f64();
+        transition.from + (self.diameter - transition.from) * done
+    }
+
+    /// How many milliseconds the border has left to move at `now`. 0 if it isn't moving.
+    pub fn remaining_ms(&self, now: Instant) -> i64 {
+        self.transition.map_or(0, |transition| {
+            let end = transition.start + transition.duration;
+            end.saturating_duration_since(now).as_millis() as i64
+        })
+    }
+
+    /// Moves the border to `diameter` over `transition_ms`, starting from wherever it is at
+    /// `now`. A transition of 0 or less sets it straight away.
+    pub fn resize_at(&mut self, diameter: f64, transition_ms: i64, now: Instant) {
+        self.transition = (transition_ms > 0).then(|| Transition {
+            from: self.diameter_at(now),
+            start: now,
+            duration: Duration::from_millis(transition_ms as u64),
+        });
+        self.diameter = diameter;
+    }
+
+    /// Only what the border is heading for is saved, since a transition doesn't carry on
+    /// after a restart.
+    fn to_bytes(self) -> Vec<u8> {
+        [
+            self.center.0.to_le_bytes().as_slice(),
+            &self.center.1.to_le_bytes(),
+            &self.diameter.to_le_bytes(),
+            &self.warning_blocks.to_le_bytes(),
+            &self.warning_time.to_le_bytes(),
+        ]
+        .concat()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let f64_at = |start: usize| -> Option<f64> {
+            Some(f64::from_le_bytes(
+                bytes.get(start..start + 8)?.try_into().ok()?,
+            ))
+        };
+        let i32_at = |start: usize| -> Option<i32> {
+            Some(i32::from_le_bytes(
+                bytes.get(start..start + 4)?.try_into().ok()?,
+            ))
+        };
+        Some(Self::new(
+            (f64_at(0)?, f64_at(8)?),
+            f64_at(16)?,
+            i32_at(24)?,
+            i32_at(28)?,
+        ))
+    }
+}
+
+/// [WorldBorder] that can be shared between tasks.
+#[derive(Debug)]
+pub struct SharedWorldBorder(Mutex<WorldBorder>);
+
+impl SharedWorldBorder {
+    pub fn new(border: WorldBorder) -> Self {
+        Self(Mutex::new(border))
+    }
+
+    pub fn get(&self) -> WorldBorder {
+        *self.0.lock().unwrap()
+    }
+
+    fn update<R>(&self, f: impl FnOnce(&mut WorldBorder) -> R) -> R {
+        f(&mut self.0.lock().unwrap())
+    }
+}
+
+impl ServerState {
+    /// Moves the world border to `center` and makes it `diameter` wide, growing or shrinking
+    /// smoothly over `transition_ms`. Everyone in the world is only sent what changed.
+    pub async fn set_border(&self, center: (f64, f64), diameter: f64, transition_ms: i64) {
+        let now = Instant::now();
+        let (moved, from) = self.border.update(|border| {
+            let moved = border.center != center;
+            let from = border.diameter_at(now);
+            border.center = center;
+            border.resize_at(diameter, transition_ms, now);
+            (moved, from)
+        });
+
+        if moved {
+            self.broadcast(|| SetBorderCenter::new(center.0, center.1))
+                .await;
+        }
+        if transition_ms > 0 {
+            self.broadcast(|| SetBorderLerpSize::new(from, diameter, transition_ms))
+                .await;
+        } else if from != diameter {
+            self.broadcast(|| SetBorderSize::new(diameter)).await;
+        }
+    }
+
+    /// Sets how close to the world border, in blocks, and how long before a shrinking border
+    /// reaches them, in seconds, players are warned.
+    pub async fn set_border_warning(&self, warning_blocks: i32, warning_time: i32) {
+        let old = self.border.update(|border| {
+            let old = *border;
+            border.warning_blocks = warning_blocks;
+            border.warning_time = warning_time;
+            old
+        });
+
+        if old.warning_blocks != warning_blocks {
+            self.broadcast(|| SetBorderWarningDistance::new(warning_blocks))
+                .await;
+        }
+        if old.warning_time != warning_time {
+            self.broadcast(|| SetBorderWarningDelay::new(warning_time))
+                .await;
+        }
+    }
+
+    /// Saves the world border with the world, so it's still there after a restart.
+    pub async fn save_border(&self) -> Result<()> {
+        self.database
+            .set_world_info(BORDER_KEY, self.border.get().to_bytes())
+            .await
+    }
+}
+
+/// Loads the world border saved with the world. New worlds use the one in the config.
+pub async fn load_border(database: &Database) -> Result<WorldBorder> {
+    let Some(bytes) = database.get_world_info(BORDER_KEY).await? else {
+        return Ok(WorldBorder::from_config());
+    };
+    WorldBorder::from_bytes(&bytes)
+        .ok_or_else(|| Error::DatabaseError("The saved world border is corrupted".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_border_moves_smoothly() {
+        let start = Instant::now();
+        let mut border = WorldBorder::new((0.0, 0.0), 100.0, 5, 15);
+        border.resize_at(200.0, 10_000, start);
+
+        assert_eq!(border.diameter_at(start), 100.0);
+        assert_eq!(border.diameter_at(start + Duration::from_secs(5)), 150.0);
+        assert_eq!(border.remaining_ms(start + Duration::from_secs(5)), 5000);
+        assert_eq!(border.diameter_at(start + Duration::from_secs(20)), 200.0);
+        assert_eq!(border.remaining_ms(start + Duration::from_secs(20)), 0);
+    }
+
+    #[test]
+    fn test_resizing_mid_transition_starts_from_where_it_is() {
+        let start = Instant::now();
+        let mut border = WorldBorder::new((0.0, 0.0), 100.0, 5, 15);
+        border.resize_at(200.0, 10_000, start);
+        let halfway = start + Duration::from_secs(5);
+        border.resize_at(50.0, 1000, halfway);
+        assert_eq!(border.diameter_at(halfway), 150.0);
+
+        border.resize_at(30.0, 0, halfway);
+        assert_eq!(border.diameter_at(halfway), 30.0);
+        assert_eq!(border.remaining_ms(halfway), 0);
+    }
+
+    #[test]
+    fn test_border_round_trips_through_bytes() {
+        let border = WorldBorder::new((12.5, -40.0), 1000.0, 8, 30);
+        assert_eq!(WorldBorder::from_bytes(&border.to_bytes()), Some(border));
+        assert_eq!(WorldBorder::from_bytes(&[1, 2, 3]), None);
+    }
+}
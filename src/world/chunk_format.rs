@@ -42,6 +42,7 @@ pub struct Chunk {
     #[nbt(rename = "LastUpdate")]
     pub last_update: Option<i64>,
     pub sections: Option<Vec<Section>>,
+    pub block_entities: Option<Vec<BlockEntity>>,
 }
 
 #[apply(ChunkDerives)]
@@ -111,6 +112,26 @@ pub struct Palette {
     pub properties: Option<BTreeMap<String, String>>,
 }
 
+/// Extra data for a block, e.g. a sign's text or a chest's items, kept separately from the block
+/// itself.
+///
+/// The NBT impls are written by hand in [crate::world::block_entities], since what's in `data`
+/// depends on the kind of block entity.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize, deepsize::DeepSizeOf,
+)]
+pub struct BlockEntity {
+    /// What kind of block entity it is, e.g. "minecraft:sign".
+    pub id: String,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    /// Everything else, as the payload of an NBT compound ending in `TAG_End`. Kept as it was
+    /// loaded, so kinds of block entity we don't know anything about aren't lost when the chunk
+    /// is saved again. See [BlockEntity::nbt].
+    pub data: Vec<u8>,
+}
+
 #[apply(ChunkDerives)]
 #[derive(deepsize::DeepSizeOf)]
 pub struct Properties {
@@ -131,5 +152,11 @@ pub struct Properties {
 #[apply(ChunkDerives)]
 #[derive(deepsize::DeepSizeOf)]
 pub struct Biomes {
+    // This is the palette for the section when stored on disk, as biome names
     pub palette: Vec<String>,
+    // One entry for each 4x4x4 cell, in y, then z, then x order. Missing if the palette only has
+    // one biome
+    pub data: Option<Vec<i64>>,
+    // This is the palette for the section when converted to network format
+    pub net_palette: Option<Vec<VarInt>>,
 }
@@ -0,0 +1,319 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
+
+use futures::future::{BoxFuture, FutureExt, Shared};
+use tokio::sync::{oneshot, Notify};
+
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+use crate::world::chunk_format::Chunk;
+use crate::world::dimensions::{storage_key, Dimension, OVERWORLD};
+
+/// How soon a chunk is needed. Chunks players are spawning in come first, then the rest, nearest
+/// to whoever asked for them first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChunkPriority {
+    Spawn,
+    /// How many chunks away from the player it is.
+    Distance(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChunkKey {
+    pub dimension: String,
+    pub x: i32,
+    pub z: i32,
+}
+
+impl ChunkKey {
+    pub fn new(dimension: &Dimension, x: i32, z: i32) -> Self {
+        Self {
+            dimension: dimension.key.clone(),
+            x,
+            z,
+        }
+    }
+
+    pub fn overworld(x: i32, z: i32) -> Self {
+        Self {
+            dimension: storage_key(OVERWORLD),
+            x,
+            z,
+        }
+    }
+}
+
+/// Errors are shared between everyone waiting for the chunk, so they're behind an [Arc] too.
+pub type ChunkResult = std::result::Result<Arc<Chunk>, Arc<Error>>;
+/// A chunk that's been asked for. Every clone resolves to the same chunk.
+pub type PendingChunk = Shared<BoxFuture<'static, ChunkResult>>;
+
+struct QueuedChunk {
+    priority: ChunkPriority,
+    /// Breaks ties, so chunks with the same priority are loaded in the order they were asked for.
+    sequence: u64,
+    key: ChunkKey,
+    sender: oneshot::Sender<ChunkResult>,
+}
+
+// The queue pops its greatest entry, which should be the most urgent one.
+impl Ord for QueuedChunk {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.priority, other.sequence).cmp(&(self.priority, self.sequence))
+    }
+}
+
+impl PartialOrd for QueuedChunk {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for QueuedChunk {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedChunk {}
+
+/// Loads and generates chunks in the background, most urgent first.
+///
+/// [ChunkService::request] queues a chunk and returns a future for it. Asking for a chunk that's
+/// already on its way gives the same future, so it's only loaded once. A fixed number of workers,
+/// run by [ChunkLoaderSystem](crate::net::systems::chunk_loader::ChunkLoaderSystem), take chunks
+/// off the queue and load them from the database, the world's region files or the generator, and
+/// save the ones that weren't in the database to it.
+///
+/// Dropping the future doesn't stop the chunk from being loaded, so a player leaving halfway
+/// through can't leave it half done.
+pub struct ChunkService {
+    queue: Mutex<BinaryHeap<QueuedChunk>>,
+    pending: Mutex<HashMap<ChunkKey, PendingChunk>>,
+    queued: Notify,
+    sequence: AtomicU64,
+    workers: usize,
+}
+
+impl ChunkService {
+    /// A service for `workers` workers to load chunks for. At least 1.
+    pub fn new(workers: usize) -> Self {
+        Self {
+            queue: Mutex::new(BinaryHeap::new()),
+            pending: Mutex::new(HashMap::new()),
+            queued: Notify::new(),
+            sequence: AtomicU64::new(0),
+            workers: workers.max(1),
+        }
+    }
+
+    /// How many chunks can be loaded at once.
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// How many chunks are waiting for a worker.
+    pub fn queued(&self) -> usize {
+        self.queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .len()
+    }
+
+    /// Asks for the chunk at `key`, which resolves once a worker has loaded it. If it's already
+    /// been asked for, it isn't queued again, and `priority` is ignored.
+    pub fn request(&self, key: ChunkKey, priority: ChunkPriority) -> PendingChunk {
+        let mut pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(chunk) = pending.get(&key) {
+            return chunk.clone();
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        let chunk = async move {
+            receiver.await.unwrap_or_else(|_| {
+                Err(Arc::new(Error::Generic(
+                    "The chunk was dropped before it was loaded".to_string(),
+                )))
+            })
+        }
+        .boxed()
+        .shared();
+        pending.insert(key.clone(), chunk.clone());
+        drop(pending);
+
+        self.queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(QueuedChunk {
+                priority,
+                sequence: self.sequence.fetch_add(1, AtomicOrdering::Relaxed),
+                key,
+                sender,
+            });
+        self.queued.notify_one();
+        chunk
+    }
+
+    /// Loads chunks off the queue, forever.
+    pub async fn run_worker(&self, state: &GlobalState) {
+        loop {
+            let queued = self.next().await;
+            let ChunkKey { dimension, x, z } = queued.key.clone();
+            // Pinned until it's handed out, so the cache can't drop it in between.
+            state.database.pin_chunk(x, z, &dimension);
+            let result = load_chunk(state, &queued.key)
+                .await
+                .map(Arc::new)
+                .map_err(Arc::new);
+            self.finish(queued, result);
+            state.database.unpin_chunk(x, z, &dimension);
+        }
+    }
+
+    /// Waits for the most urgent chunk in the queue.
+    async fn next(&self) -> QueuedChunk {
+        loop {
+            // Notifications that come while the queue's being checked aren't lost, they're kept
+            // for this.
+            let notified = self.queued.notified();
+            if let Some(queued) = self
+                .queue
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .pop()
+            {
+                return queued;
+            }
+            notified.await;
+        }
+    }
+
+    /// Hands the chunk to everyone waiting for it. Anyone asking for it after this gets it from
+    /// the database's cache instead.
+    fn finish(&self, queued: QueuedChunk, result: ChunkResult) {
+        // Nobody waiting for it anymore is fine, it's been saved either way.
+        let _ = queued.sender.send(result);
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&queued.key);
+    }
+}
+
+/// The chunk from the database, or else from the dimension's region files, or else generated.
+/// Chunks that weren't in the database are saved to it, so they're only read or generated once
+/// and changes to them stick.
+async fn load_chunk(state: &GlobalState, key: &ChunkKey) -> Result<Chunk> {
+    let (chunk_x, chunk_z) = (key.x, key.z);
+    if let Some(chunk) = state
+        .database
+        .get_chunk(chunk_x, chunk_z, key.dimension.clone())
+        .await?
+    {
+        return Ok(chunk);
+    }
+    let Some(dimension) = state.dimensions.by_key(&key.dimension) else {
+        return Err(Error::Generic(format!(
+            "There's no dimension to load chunks from called {}",
+            key.dimension
+        )));
+    };
+    let mut chunk = match dimension.anvil.load_chunk(chunk_x, chunk_z).await? {
+        Some(chunk) => chunk,
+        None => {
+            // Generating can take a while, so it's kept off the runtime's threads.
+            let generator = dimension.generator.clone();
+            tokio::task::spawn_blocking(move || generator.generate(chunk_x, chunk_z))
+                .await
+                .map_err(|e| Error::Generic(format!("Chunk generation task failed: {}", e)))?
+        }
+    };
+    // Region files and generators don't know which dimension they're making chunks for.
+    chunk.dimension = Some(key.dimension.clone());
+    state.database.insert_chunk(chunk.clone()).await?;
+    Ok(chunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::create_state;
+
+    #[test]
+    fn test_priorities() {
+        let service = ChunkService::new(1);
+        let _requests = [
+            service.request(ChunkKey::overworld(5, 5), ChunkPriority::Distance(5)),
+            service.request(ChunkKey::overworld(1, 0), ChunkPriority::Distance(1)),
+            service.request(ChunkKey::overworld(0, 0), ChunkPriority::Spawn),
+            service.request(ChunkKey::overworld(0, 1), ChunkPriority::Distance(1)),
+        ];
+
+        let order = (0..4)
+            .map(|_| {
+                let queued = service.queue.lock().unwrap().pop().unwrap();
+                (queued.key.x, queued.key.z)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![(0, 0), (1, 0), (0, 1), (5, 5)]);
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_shared() {
+        let service = ChunkService::new(1);
+        let first = service.request(ChunkKey::overworld(3, 4), ChunkPriority::Distance(2));
+        let second = service.request(ChunkKey::overworld(3, 4), ChunkPriority::Spawn);
+        assert!(first.ptr_eq(&second));
+        assert_eq!(service.queued(), 1);
+
+        // Whoever asked for it leaving doesn't stop it from being handed out.
+        drop(first);
+        let queued = service.next().await;
+        service.finish(queued, Err(Arc::new(Error::ChunkNotFound(3, 4))));
+        assert!(matches!(
+            second.await.unwrap_err().as_ref(),
+            Error::ChunkNotFound(3, 4)
+        ));
+        assert!(service.pending.lock().unwrap().is_empty());
+
+        // Once it's done, asking again queues it again.
+        let _again = service.request(ChunkKey::overworld(3, 4), ChunkPriority::Spawn);
+        assert_eq!(service.queued(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_workers_load_chunks() {
+        let state = create_state(vec![TcpListener::bind("127.0.0.1:0").await.unwrap()])
+            .await
+            .unwrap();
+        let worker_state = state.clone();
+        let worker = tokio::spawn(async move {
+            worker_state.chunks.run_worker(&worker_state).await;
+        });
+
+        let requests = (0..4)
+            .map(|x| {
+                state
+                    .chunks
+                    .request(ChunkKey::overworld(x, 9_000), ChunkPriority::Distance(0))
+            })
+            .collect::<Vec<_>>();
+        for (x, request) in requests.into_iter().enumerate() {
+            let chunk = request.await.unwrap();
+            assert_eq!((chunk.x_pos, chunk.z_pos), (x as i32, 9_000));
+        }
+        assert!(state
+            .database
+            .chunk_exists(0, 9_000, "overworld".to_string())
+            .await
+            .unwrap());
+        worker.abort();
+    }
+}
@@ -1,28 +1,29 @@
 use crate::utils::error::Error;
-use crate::world::chunk_format::{BlockStates, Chunk, Palette, Section};
+use crate::world::biomes::{biome_id, plains_id};
+use crate::world::block_states::BlockState;
+use crate::world::blocks::{air, unpack_indices};
+use crate::world::chunk_format::{Biomes, BlockStates, Chunk, Palette, Section};
+use crate::world::paletted_container::{read_palette_index, ContainerKind, PalettedContainer};
 use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::error::CodecError;
 use ferrumc_codec::network_types::varint::VarInt;
-use hashbrown::HashMap;
-use lazy_static::lazy_static;
-use std::io::Read;
 use tokio::io::AsyncWrite;
 use tracing::trace;
 
-const BLOCKSFILE: &[u8] = include_bytes!("../../.etc/blockmappings.bz2");
-
-lazy_static! {
-    static ref ID2BLOCK: HashMap<i32, Palette> = {
-        let mut bzipreader = bzip2::read::BzDecoder::new(BLOCKSFILE);
-        let mut output = String::new();
-        bzipreader.read_to_string(&mut output).unwrap();
-        let string_keys: HashMap<String, Palette> = serde_json::from_str(&output).unwrap();
-        string_keys
-            .iter()
-            .map(|(k, v)| (k.parse::<i32>().unwrap(), v.clone()))
-            .collect()
-    };
-    static ref BLOCK2ID: HashMap<Palette, i32> =
-        ID2BLOCK.iter().map(|(k, v)| (v.clone(), *k)).collect();
+/// The network id of a block state, or `None` if it isn't a vanilla block.
+pub fn block_id(block: &Palette) -> Option<i32> {
+    BlockState::from_palette(block).map(|state| state.id() as i32)
+}
+
+/// The block state with the network id `id`.
+pub fn block_from_id(id: i32) -> Option<Palette> {
+    BlockState::from_id(u32::try_from(id).ok()?).map(BlockState::to_palette)
+}
+
+/// The state to place a block in when nothing decides otherwise. `None` if there's no block
+/// called `name`. See [BlockState::default_state].
+pub fn default_block_state(name: &str) -> Option<Palette> {
+    BlockState::default_state(name).map(BlockState::to_palette)
 }
 
 impl Section {
@@ -38,7 +39,8 @@ impl Section {
 }
 
 impl Chunk {
-    /// Converts a chunk in the disk format to the network format
+    /// Converts a chunk in the disk format to the network format, and works out its heightmaps
+    /// and any sky light it's missing
     pub fn convert_to_net_mode(&mut self) -> Result<(), Error> {
         // This looks ugly, but it's the best way I could think of to do the error checking
         let sections = if let Some(c) = self.sections.as_mut() {
@@ -51,14 +53,16 @@ impl Chunk {
             ));
         };
         for section in sections {
+            if let Some(biomes) = section.biomes.as_mut() {
+                biomes.convert_to_net_mode();
+            }
             let mut set_empty = false;
             match section.block_states.as_mut() {
                 /*
                 If there are no block states, set the section to empty
                 This is mostly just if the section is empty or outside the world border
-                In network form this would also include sections that are only 1 type of block
-                But I'm not sure if this could also be the case for disk form.
-                TODO: Adapt this to work for sections that are possibly only 1 block that isn't air
+                Sections that are only 1 type of block still have block states, with just that block
+                in the palette.
                 */
                 None => {
                     trace!(
@@ -69,70 +73,56 @@ impl Chunk {
                     set_empty = true;
                 }
                 Some(block_states) => {
-                    // Set the max number of air blocks then decrease it every time we find and air
-                    // block in the data array. This will short-circuit if the section is malformed
-                    // but in that case we set it all to air anyway.
-                    let mut non_air_blocks = 4096i16;
-                    // This is just for readability
-                    let air_id = 0i32;
                     // If the palette is missing, we can't do anything and it's actually fucked
-                    if block_states.palette.is_none() {
+                    let Some(palette) = block_states.palette.as_ref() else {
                         return Err(Error::InvalidChunk(
                             self.x_pos,
                             self.z_pos,
                             "Palette is missing".to_string(),
                         ));
-                    }
-
-                    let palette = block_states.palette.as_mut().unwrap();
-
-                    // TODO: Adapt this for single block sections
-                    if block_states.data.is_some() {
-                        let bits_per_entry = (palette.len() as f32).log2().ceil() as i8;
-                        block_states.bits_per_block = Some(bits_per_entry.max(4));
-                    } else {
-                        trace!("No data found in section at {}", section.y);
-                        set_empty = true;
-                    }
-
-                    block_states.net_palette = Some(Vec::new());
+                    };
 
                     // Since the only difference (as far as I know) between the network and disk palettes
                     // is that the disk palette uses full block states and the network palette uses block IDs
-                    // we can actually just swap the block states for block IDs. We can't really do this
-                    // in place cos of type differences so we'll just make a new vec, iterate over the
-                    // block states and push the block IDs to the new vec, then clear the old one.
-                    for palette_entry in palette.iter() {
-                        if BLOCK2ID.contains_key(palette_entry) {
-                            if let Some(checked_palette) = block_states.net_palette.as_mut() {
-                                let block_id = *BLOCK2ID
-                                    .get(palette_entry)
-                                    .expect("Block not found in block mappings");
-                                // If the block is air, decrease the non-air blocks count
-                                if block_id == air_id {
-                                    non_air_blocks -= 1;
-                                }
-                                checked_palette.push(VarInt::from(block_id));
-                            } else {
-                                set_empty = true;
-                            }
-                        } else {
+                    // we can actually just swap the block states for block IDs.
+                    let mut net_palette = Vec::with_capacity(palette.len());
+                    for palette_entry in palette {
+                        let Some(block_id) = block_id(palette_entry) else {
                             return Err(Error::InvalidChunk(
                                 self.x_pos,
                                 self.z_pos,
                                 format!("Block {} not found in block mappings", palette_entry.name),
                             ));
-                        }
+                        };
+                        net_palette.push(VarInt::from(block_id));
                     }
-                    // This should never happen but if it does, we got some major problems to sort out
-                    if non_air_blocks < 0 {
-                        return Err(Error::InvalidChunk(
-                            self.x_pos,
-                            self.z_pos,
-                            "Too many air blocks. How the crispy kentucky fried fuck did this happen???".to_string(),
-                        ));
+
+                    match &block_states.data {
+                        Some(data) => {
+                            let bits = ((palette.len() as f32).log2().ceil() as usize).max(4);
+                            let non_air_blocks = unpack_indices(data, bits)
+                                .into_iter()
+                                .filter(|&index| {
+                                    net_palette
+                                        .get(index as usize)
+                                        .is_some_and(|id| id.get_val() != AIR_ID)
+                                })
+                                .count();
+                            block_states.bits_per_block = Some(bits as i8);
+                            block_states.non_air_blocks = Some(non_air_blocks as i16);
+                        }
+                        // Sections that are all one block don't have any data, just that block.
+                        None if net_palette.len() == 1 => {
+                            let all_air = net_palette[0].get_val() == AIR_ID;
+                            block_states.bits_per_block = Some(0);
+                            block_states.non_air_blocks = Some(if all_air { 0 } else { 4096 });
+                        }
+                        None => {
+                            trace!("No data found in section at {}", section.y);
+                            set_empty = true;
+                        }
                     }
-                    block_states.non_air_blocks = Some(non_air_blocks);
+                    block_states.net_palette = Some(net_palette);
                 }
             }
             if set_empty {
@@ -141,51 +131,195 @@ impl Chunk {
             }
         }
 
+        // Heightmaps on disk can be out of date, or missing from chunks from older versions.
+        self.heightmaps = Some(self.compute_heightmaps()?);
+        // Light from the world's files is kept, since it takes the chunks around it into
+        // account, which ours doesn't yet.
+        if self.missing_sky_light() {
+            self.compute_sky_light()?;
+        }
         Ok(())
     }
 }
 
+/// The network id of air, which doesn't count towards a section's non-air blocks.
+const AIR_ID: i32 = 0;
+
+impl BlockStates {
+    /// The blocks' network ids as a [PalettedContainer], which has to be converted to the
+    /// network format first.
+    pub fn container(&self) -> Result<PalettedContainer<i32>, Error> {
+        let palette = self.net_palette.as_ref().ok_or(Error::Generic(
+            "Block states haven't been converted to the network format".to_string(),
+        ))?;
+        PalettedContainer::from_parts(
+            ContainerKind::BlockStates,
+            palette.iter().map(VarInt::get_val).collect(),
+            self.data.clone(),
+        )
+    }
+
+    /// The id of the block at `index`, read straight out of the packed data instead of making a
+    /// [PalettedContainer] of the whole section. `None` if the block states are broken.
+    pub fn get(&self, index: usize) -> Option<i32> {
+        let palette = self.net_palette.as_ref()?;
+        let palette_index = read_palette_index(
+            ContainerKind::BlockStates,
+            palette.len(),
+            self.data.as_deref(),
+            index,
+        )?;
+        palette.get(palette_index).map(VarInt::get_val)
+    }
+
+    /// Replaces the blocks with the ones in `container`. Both palettes, the block data and the
+    /// non-air block count are kept up to date, so it can be sent to clients as it is or
+    /// converted again.
+    pub fn set_container(&mut self, container: &PalettedContainer<i32>) {
+        let palette = container.palette();
+        self.net_palette = Some(palette.iter().map(|&id| VarInt::from(id)).collect());
+        self.palette = Some(
+            palette
+                .iter()
+                .map(|&id| block_from_id(id).unwrap_or_else(air))
+                .collect(),
+        );
+        self.bits_per_block = Some(container.bits() as i8);
+        self.data = container.data().map(<[i64]>::to_vec);
+        self.non_air_blocks = Some(container.iter().filter(|&id| id != AIR_ID).count() as i16);
+    }
+}
+
+impl Biomes {
+    /// The biomes' ids as a [PalettedContainer]. Biomes the client doesn't know are plains, the
+    /// same as with [Biomes::convert_to_net_mode].
+    pub fn container(&self) -> Result<PalettedContainer<i32>, Error> {
+        let palette = match &self.net_palette {
+            Some(palette) => palette.iter().map(VarInt::get_val).collect(),
+            None => self
+                .palette
+                .iter()
+                .map(|name| biome_id(name).unwrap_or_else(plains_id))
+                .collect(),
+        };
+        PalettedContainer::from_parts(ContainerKind::Biomes, palette, self.data.clone())
+    }
+}
+
 impl NetEncode for Section {
+    /// Encodes the section the way the Chunk Data packet has it: the number of non-air blocks,
+    /// then the blocks and the biomes as paletted containers. A section without block states is
+    /// sent as air, and one without biomes as plains.
     async fn net_encode<W>(&self, writer: &mut W) -> ferrumc_codec::Result<()>
     where
         W: AsyncWrite + Unpin,
     {
-        if let Some(block_states) = &self.block_states {
-            // Non-air blocks
-            if let Some(non_air_blocks) = block_states.non_air_blocks {
-                (non_air_blocks as u16).net_encode(writer).await?;
-            } else {
-                // VarInt::from(0).net_encode(writer).await?;
-                0u16.net_encode(writer).await?;
-            }
+        let blocks = match &self.block_states {
+            Some(block_states) => block_states.container(),
+            None => Ok(PalettedContainer::new(ContainerKind::BlockStates, AIR_ID)),
+        }
+        .map_err(CodecError::from_external_error)?;
+        let biomes = match &self.biomes {
+            Some(biomes) => biomes.container(),
+            None => Ok(PalettedContainer::new(ContainerKind::Biomes, plains_id())),
+        }
+        .map_err(CodecError::from_external_error)?;
 
-            // Blocks
-            let bpe = block_states.bits_per_block.unwrap_or(15);
-            bpe.net_encode(writer).await?;
-
-            VarInt::from(block_states.net_palette.as_ref().unwrap().len() as i32)
-                .net_encode(writer)
-                .await?;
-            block_states
-                .net_palette
-                .as_ref()
-                .expect("Palette is missing")
-                .net_encode(writer)
-                .await?;
-
-            if let Some(data) = &block_states.data {
-                VarInt::from(data.len() as i32).net_encode(writer).await?;
-                for long in block_states.data.as_ref().unwrap() {
-                    long.net_encode(writer).await?;
-                }
-            } else {
-                VarInt::from(0).net_encode(writer).await?;
-            }
+        self.block_states
+            .as_ref()
+            .and_then(|block_states| block_states.non_air_blocks)
+            .unwrap_or(0)
+            .net_encode(writer)
+            .await?;
+        blocks.net_encode(writer).await?;
+        biomes.net_encode(writer).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::biomes::biome_name;
+    use crate::world::blocks::pack_indices;
+    use crate::world::paletted_container::bits_for;
+
+    /// Just the biomes out of an encoded section of air.
+    async fn encoded(biomes: Option<&Biomes>) -> Vec<u8> {
+        let section = Section {
+            block_states: None,
+            biomes: biomes.cloned(),
+            y: 0,
+            block_light: None,
+            sky_light: None,
+        };
+        let mut bytes = Vec::new();
+        section.net_encode(&mut bytes).await.unwrap();
+        bytes.split_off(5)
+    }
+
+    fn biomes(names: &[&str], indices: &[u16]) -> Biomes {
+        let mut biomes = Biomes {
+            palette: names.iter().map(|name| name.to_string()).collect(),
+            data: Some(pack_indices(indices, bits_for(names.len()))),
+            net_palette: None,
+        };
+        biomes.convert_to_net_mode();
+        biomes
+    }
+
+    #[tokio::test]
+    async fn test_encode_biomes() {
+        // No biomes, or just one, have no data.
+        assert_eq!(encoded(None).await, vec![0x00, 39, 0x00]);
+        let desert = Biomes::single("minecraft:desert").unwrap();
+        assert_eq!(encoded(Some(&desert)).await, vec![0x00, 14, 0x00]);
+
+        // Desert in the bottom half, plains on top, at a bit each.
+        let mut indices = vec![0; 32];
+        indices.extend([1; 32]);
+        let bytes = encoded(Some(&biomes(
+            &["minecraft:desert", "minecraft:plains"],
+            &indices,
+        )))
+        .await;
+        assert_eq!(bytes[..5], [0x01, 0x02, 14, 39, 0x01]);
+        assert_eq!(bytes[5..], 0xFFFF_FFFF_0000_0000u64.to_be_bytes());
+
+        // Too many to fit in 3 bits, so they're sent as ids, 6 bits each.
+        let names = (0..9).map(|id| biome_name(id).unwrap()).collect::<Vec<_>>();
+        let indices = (0..64).map(|i| i % 9).collect::<Vec<_>>();
+        let bytes = encoded(Some(&biomes(&names, &indices))).await;
+        assert_eq!(bytes[..2], [0x06, 0x07]);
+        assert_eq!(bytes.len(), 2 + 7 * 8);
+        let longs = bytes[2..]
+            .chunks(8)
+            .map(|long| i64::from_be_bytes(long.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(unpack_indices(&longs, 6)[..64], indices[..]);
+    }
 
-            /*// Biomes
-            // For now just write 3 0s
-            writer.write_all(&[0; 3]).await?;*/
+    #[test]
+    fn test_reading_one_block() {
+        let mut container = PalettedContainer::new(ContainerKind::BlockStates, 0);
+        // Enough blocks to need more bits than a long fits evenly.
+        for index in 0..40 {
+            container.set(index * 97, index as i32 + 1);
         }
-        Ok(())
+        let mut block_states = BlockStates {
+            non_air_blocks: None,
+            bits_per_block: None,
+            data: None,
+            palette: None,
+            net_palette: None,
+        };
+        assert_eq!(block_states.get(0), None);
+
+        block_states.set_container(&container);
+        for index in 0..ContainerKind::BlockStates.entries() {
+            assert_eq!(block_states.get(index), Some(container.get(index)));
+        }
+        // Data that's been cut short can't be read.
+        block_states.data.as_mut().unwrap().truncate(10);
+        assert_eq!(block_states.get(4095), None);
     }
 }
@@ -0,0 +1,299 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::database::Database;
+use crate::net::packets::ConnectionId;
+use crate::state::ServerState;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::config::ServerConfig;
+use crate::utils::prelude::*;
+use crate::utils::spatial_index::SpatialIndex;
+use crate::world::anvil::AnvilWorld;
+use crate::world::block_changes::BlockChangeBatcher;
+use crate::world::generation::{self, ChunkGenerator};
+use crate::world::time::{load_time, SharedWorldTime};
+use crate::world::weather::{load_weather, SharedWeather};
+
+pub const OVERWORLD: &str = "minecraft:overworld";
+pub const THE_NETHER: &str = "minecraft:the_nether";
+pub const THE_END: &str = "minecraft:the_end";
+/// The dimension types in the registry codec clients are sent when they join. Every dimension is
+/// drawn as one of them.
+///
+/// Every chunk is as tall as the overworld's, so the bundled codec gives the end and the nether
+/// the overworld's `min_y` and `height` too.
+pub const DIMENSION_TYPES: &[&str] = &[OVERWORLD, "minecraft:overworld_caves", THE_END, THE_NETHER];
+
+/// One of the world's dimensions, with everything that's kept apart for each of them: its chunks,
+/// how they're made, its time and weather, and the players in it.
+///
+/// Chunk and block functions take one to know which dimension's chunks they're after. Players
+/// are in the one their [CurrentDimension] says, see [ServerState::dimension_of].
+pub struct Dimension {
+    /// What the client calls it, like `minecraft:the_nether`.
+    pub name: String,
+    /// Which of [DIMENSION_TYPES] the client draws it as.
+    pub dimension_type: String,
+    /// What its chunks are saved under in the database, see [storage_key].
+    pub key: String,
+    /// Its part of the vanilla world in the config's `world` directory.
+    pub anvil: AnvilWorld,
+    /// Makes the chunks that aren't in the database or [Dimension::anvil].
+    pub generator: Arc<dyn ChunkGenerator>,
+    /// The dimension's time, moved on by [crate::net::systems::time_system::tick].
+    pub time: SharedWorldTime,
+    /// The dimension's weather, moved on by [crate::net::systems::weather_system::tick].
+    pub weather: SharedWeather,
+    /// Which chunk every player in the dimension is in, for finding the players near somewhere.
+    pub players: SpatialIndex,
+    /// Blocks changed since the last tick, see [ServerState::flush_block_changes].
+    pub block_changes: BlockChangeBatcher,
+}
+
+impl fmt::Debug for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dimension")
+            .field("name", &self.name)
+            .field("dimension_type", &self.dimension_type)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Dimension {
+    /// The dimension called `name`, with its vanilla chunks read from the world in `world`.
+    pub fn new(
+        name: &str,
+        dimension_type: &str,
+        world: &Path,
+        generator: Arc<dyn ChunkGenerator>,
+        time: SharedWorldTime,
+        weather: SharedWeather,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            dimension_type: dimension_type.to_string(),
+            key: storage_key(name),
+            anvil: AnvilWorld::new(anvil_directory(world, name)),
+            generator,
+            time,
+            weather,
+            players: SpatialIndex::new(),
+            block_changes: BlockChangeBatcher::new(),
+        }
+    }
+
+    pub fn is_overworld(&self) -> bool {
+        self.name == OVERWORLD
+    }
+
+    /// Whether it's drawn with a sky, which is where the weather comes from. The nether and the
+    /// end don't have one.
+    pub fn has_sky_light(&self) -> bool {
+        !matches!(self.dimension_type.as_str(), THE_NETHER | THE_END)
+    }
+}
+
+/// What a dimension's chunks are saved under in the database: its name, without the
+/// `minecraft:` in front, so the overworld's are where they were before there were other
+/// dimensions.
+pub fn storage_key(name: &str) -> String {
+    name.strip_prefix("minecraft:").unwrap_or(name).to_string()
+}
+
+/// Where vanilla keeps a dimension's region files in the world in `world`.
+pub fn anvil_directory(world: &Path, name: &str) -> PathBuf {
+    match name {
+        OVERWORLD => world.to_path_buf(),
+        THE_NETHER => world.join("DIM-1"),
+        THE_END => world.join("DIM1"),
+        _ => {
+            let (namespace, path) = name.split_once(':').unwrap_or(("minecraft", name));
+            world.join("dimensions").join(namespace).join(path)
+        }
+    }
+}
+
+/// Every dimension in the world, the overworld first.
+pub struct Dimensions {
+    dimensions: Vec<Arc<Dimension>>,
+}
+
+impl Dimensions {
+    /// The overworld and whatever else is in `dimensions`. Fails if two have the same name, or
+    /// one isn't drawn as any of the [DIMENSION_TYPES].
+    pub fn new(overworld: Dimension, dimensions: Vec<Dimension>) -> Result<Self> {
+        let mut names = HashSet::new();
+        for dimension in std::iter::once(&overworld).chain(&dimensions) {
+            if !names.insert(dimension.name.as_str()) {
+                return Err(Error::Generic(format!(
+                    "There's more than one dimension called {}",
+                    dimension.name
+                )));
+            }
+            if !DIMENSION_TYPES.contains(&dimension.dimension_type.as_str()) {
+                return Err(Error::Generic(format!(
+                    "Dimension {} has the type {}, which isn't one of {:?}",
+                    dimension.name, dimension.dimension_type, DIMENSION_TYPES
+                )));
+            }
+        }
+        Ok(Self {
+            dimensions: std::iter::once(overworld)
+                .chain(dimensions)
+                .map(Arc::new)
+                .collect(),
+        })
+    }
+
+    /// The dimensions in the config: the overworld from `world_generation`, and `dimensions`.
+    /// Each one's time and weather are loaded from `database`.
+    pub async fn load(config: &ServerConfig, database: &Database) -> Result<Self> {
+        let world = Path::new(&config.world);
+        let overworld = Dimension::new(
+            OVERWORLD,
+            OVERWORLD,
+            world,
+            generation::from_config(&config.world_generation)?,
+            SharedWorldTime::new(load_time(database, &storage_key(OVERWORLD)).await?),
+            SharedWeather::new(load_weather(database, &storage_key(OVERWORLD)).await?),
+        );
+        let mut dimensions = Vec::with_capacity(config.dimensions.len());
+        for dimension in &config.dimensions {
+            dimensions.push(Dimension::new(
+                &dimension.name,
+                &dimension.dimension_type,
+                world,
+                generation::from_config(&dimension.world_generation)?,
+                SharedWorldTime::new(load_time(database, &storage_key(&dimension.name)).await?),
+                SharedWeather::new(load_weather(database, &storage_key(&dimension.name)).await?),
+            ));
+        }
+        Self::new(overworld, dimensions)
+    }
+
+    /// Where players join for the first time.
+    pub fn overworld(&self) -> &Arc<Dimension> {
+        &self.dimensions[0]
+    }
+
+    /// The dimension called `name`, like `minecraft:the_nether`.
+    pub fn get(&self, name: &str) -> Option<&Arc<Dimension>> {
+        self.dimensions
+            .iter()
+            .find(|dimension| dimension.name == name)
+    }
+
+    /// The dimension whose chunks are saved under `key`, see [storage_key].
+    pub fn by_key(&self, key: &str) -> Option<&Arc<Dimension>> {
+        self.dimensions
+            .iter()
+            .find(|dimension| dimension.key == key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<Dimension>> {
+        self.dimensions.iter()
+    }
+
+    /// Every dimension's name, for the client to know which ones there are.
+    pub fn names(&self) -> Vec<String> {
+        self.dimensions
+            .iter()
+            .map(|dimension| dimension.name.clone())
+            .collect()
+    }
+}
+
+impl ServerState {
+    /// The dimension a player is in. Players that haven't joined the world yet are in the
+    /// overworld.
+    pub async fn dimension_of(&self, conn_id: ConnectionId) -> Arc<Dimension> {
+        match self.world.get_component::<CurrentDimension>(conn_id).await {
+            Ok(dimension) => dimension.0.clone(),
+            Err(_) => self.dimensions.overworld().clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::generation::superflat::SuperflatGenerator;
+
+    fn dimension(name: &str, dimension_type: &str) -> Dimension {
+        Dimension::new(
+            name,
+            dimension_type,
+            Path::new("world"),
+            Arc::new(SuperflatGenerator::from_layers("bedrock").unwrap()),
+            SharedWorldTime::default(),
+            SharedWeather::default(),
+        )
+    }
+
+    #[test]
+    fn test_storage_keys() {
+        // The overworld's chunks were saved under this before there were other dimensions.
+        assert_eq!(storage_key(OVERWORLD), "overworld");
+        assert_eq!(storage_key(THE_NETHER), "the_nether");
+        assert_eq!(storage_key("myplugin:mining"), "myplugin:mining");
+    }
+
+    #[test]
+    fn test_vanilla_region_directories() {
+        let world = Path::new("world");
+        assert_eq!(anvil_directory(world, OVERWORLD), world);
+        assert_eq!(anvil_directory(world, THE_NETHER), world.join("DIM-1"));
+        assert_eq!(anvil_directory(world, THE_END), world.join("DIM1"));
+        assert_eq!(
+            anvil_directory(world, "myplugin:mining"),
+            world.join("dimensions").join("myplugin").join("mining")
+        );
+    }
+
+    #[test]
+    fn test_looking_up_dimensions() {
+        let dimensions = Dimensions::new(
+            dimension(OVERWORLD, OVERWORLD),
+            vec![
+                dimension(THE_NETHER, THE_NETHER),
+                dimension("myplugin:mining", OVERWORLD),
+            ],
+        )
+        .unwrap();
+        assert!(dimensions.overworld().is_overworld());
+        assert_eq!(
+            dimensions.names(),
+            vec![OVERWORLD, THE_NETHER, "myplugin:mining"]
+        );
+        assert_eq!(dimensions.get(THE_NETHER).unwrap().key, "the_nether");
+        assert_eq!(
+            dimensions.by_key("myplugin:mining").unwrap().name,
+            "myplugin:mining"
+        );
+        assert!(dimensions.get(THE_END).is_none());
+    }
+
+    #[test]
+    fn test_sky_light() {
+        assert!(dimension(OVERWORLD, OVERWORLD).has_sky_light());
+        assert!(dimension("myplugin:caves", "minecraft:overworld_caves").has_sky_light());
+        assert!(!dimension(THE_NETHER, THE_NETHER).has_sky_light());
+        assert!(!dimension("myplugin:void", THE_END).has_sky_light());
+    }
+
+    #[test]
+    fn test_bad_dimensions_are_turned_down() {
+        let twice = Dimensions::new(
+            dimension(OVERWORLD, OVERWORLD),
+            vec![dimension(OVERWORLD, THE_END)],
+        );
+        assert!(twice.is_err());
+        let unknown_type = Dimensions::new(
+            dimension(OVERWORLD, OVERWORLD),
+            vec![dimension(THE_NETHER, "minecraft:the_moon")],
+        );
+        assert!(unknown_type.is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use crate::utils::config::WorldGeneration;
+use crate::utils::prelude::*;
+use crate::world::blocks::{pack_indices, MAX_BUILD_HEIGHT, MIN_BUILD_HEIGHT};
+use crate::world::chunk_format::{Biomes, BlockStates, Chunk, Palette, Section};
+use crate::world::generation::superflat::SuperflatGenerator;
+use crate::world::generation::terrain::NoiseGenerator;
+
+pub mod noise;
+pub mod superflat;
+pub mod terrain;
+
+/// Makes the chunks that aren't in the database or the world's region files.
+///
+/// Generating the same chunk twice has to give the same chunk, since any of them might be
+/// generated again if it's never saved.
+pub trait ChunkGenerator: Send + Sync {
+    /// The chunk at `x`, `z`, in the network format so it can be sent as it is.
+    fn generate(&self, x: i32, z: i32) -> Chunk;
+}
+
+/// The generator the config's `world_generation.generator` names.
+pub fn from_config(config: &WorldGeneration) -> Result<Arc<dyn ChunkGenerator>> {
+    match config.generator.as_str() {
+        "superflat" => Ok(Arc::new(
+            SuperflatGenerator::from_layers(&config.superflat_layers)?.with_biome(&config.biome)?,
+        )),
+        "default" => Ok(Arc::new(
+            NoiseGenerator::new(config.seed).with_biome(&config.biome)?,
+        )),
+        other => Err(Error::Generic(format!(
+            "Unknown world generator \"{}\"",
+            other
+        ))),
+    }
+}
+
+/// A chunk without any sections yet, for generators to fill in.
+pub fn new_chunk(x: i32, z: i32) -> Chunk {
+    Chunk {
+        dimension: Some("overworld".to_string()),
+        status: "minecraft:full".to_string(),
+        data_version: 3465,
+        heightmaps: None,
+        is_light_on: Some(1),
+        inhabited_time: Some(0),
+        y_pos: MIN_BUILD_HEIGHT >> 4,
+        x_pos: x,
+        z_pos: z,
+        structures: None,
+        last_update: Some(0),
+        sections: Some(Vec::new()),
+        block_entities: None,
+    }
+}
+
+/// The y of every section in the world, from the bottom up.
+pub fn section_ys() -> impl Iterator<Item = i8> {
+    (MIN_BUILD_HEIGHT >> 4) as i8..(MAX_BUILD_HEIGHT >> 4) as i8
+}
+
+/// A section at `y` in the disk format, with the block at each index in `indices` being the
+/// one at that index in `palette`. Blocks that aren't used are left out of its palette. Its
+/// biomes are `biomes`.
+pub fn new_section(y: i8, palette: &[Palette], indices: &[u16], biomes: &Biomes) -> Section {
+    let mut used = vec![None; palette.len()];
+    let mut section_palette = Vec::new();
+    let indices = indices
+        .iter()
+        .map(|&index| {
+            *used[index as usize].get_or_insert_with(|| {
+                section_palette.push(palette[index as usize].clone());
+                section_palette.len() as u16 - 1
+            })
+        })
+        .collect::<Vec<_>>();
+    // A section that's all one block is stored as just that block.
+    let data = match section_palette.len() {
+        1 => None,
+        len => Some(pack_indices(
+            &indices,
+            ((len as f32).log2().ceil() as usize).max(4),
+        )),
+    };
+    Section {
+        block_states: Some(BlockStates {
+            non_air_blocks: None,
+            bits_per_block: None,
+            data,
+            palette: Some(section_palette),
+            net_palette: None,
+        }),
+        biomes: Some(biomes.clone()),
+        y,
+        block_light: None,
+        sky_light: None,
+    }
+}
+
+/// The biomes for a generated section that's all the biome called `name`, checking the client
+/// knows it.
+pub fn single_biome(name: &str) -> Result<Biomes> {
+    Biomes::single(name).ok_or_else(|| Error::Generic(format!("Unknown biome \"{}\"", name)))
+}
@@ -0,0 +1,168 @@
+/// Gradient noise, Ken Perlin's improved version, shuffled by a seed.
+///
+/// Samples are smooth, roughly between -1 and 1, and 0 at whole coordinates.
+pub struct PerlinNoise {
+    permutation: [u8; 512],
+}
+
+impl PerlinNoise {
+    pub fn new(seed: u64) -> Self {
+        let mut shuffled: [u8; 256] = std::array::from_fn(|i| i as u8);
+        let mut state = seed;
+        for i in (1..shuffled.len()).rev() {
+            let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+            shuffled.swap(i, j);
+        }
+        Self {
+            permutation: std::array::from_fn(|i| shuffled[i & 255]),
+        }
+    }
+
+    pub fn sample_3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (xi, yi, zi) = (lattice(x), lattice(y), lattice(z));
+        let (x, y, z) = (x - x.floor(), y - y.floor(), z - z.floor());
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let p = &self.permutation;
+        let a = p[xi] as usize + yi;
+        let aa = p[a] as usize + zi;
+        let ab = p[a + 1] as usize + zi;
+        let b = p[xi + 1] as usize + yi;
+        let ba = p[b] as usize + zi;
+        let bb = p[b + 1] as usize + zi;
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(p[aa], x, y, z), grad(p[ba], x - 1.0, y, z)),
+                lerp(
+                    u,
+                    grad(p[ab], x, y - 1.0, z),
+                    grad(p[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(p[aa + 1], x, y, z - 1.0),
+                    grad(p[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    grad(p[ab + 1], x, y - 1.0, z - 1.0),
+                    grad(p[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+
+    pub fn sample_2d(&self, x: f64, z: f64) -> f64 {
+        // Off the lattice, so it isn't 0 along every whole z.
+        self.sample_3d(x, 0.5, z)
+    }
+}
+
+/// Several layers of [PerlinNoise], each twice as detailed and half as strong as the one
+/// before. Samples are roughly between -1 and 1.
+pub struct OctaveNoise {
+    octaves: Vec<PerlinNoise>,
+    frequency: f64,
+}
+
+impl OctaveNoise {
+    /// `frequency` is how many features the first octave has per block.
+    pub fn new(seed: u64, octaves: usize, frequency: f64) -> Self {
+        let mut state = seed;
+        Self {
+            octaves: (0..octaves.max(1))
+                .map(|_| PerlinNoise::new(splitmix64(&mut state)))
+                .collect(),
+            frequency,
+        }
+    }
+
+    pub fn sample_2d(&self, x: f64, z: f64) -> f64 {
+        self.sum(|octave, frequency| octave.sample_2d(x * frequency, z * frequency))
+    }
+
+    pub fn sample_3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.sum(|octave, frequency| octave.sample_3d(x * frequency, y * frequency, z * frequency))
+    }
+
+    fn sum(&self, sample: impl Fn(&PerlinNoise, f64) -> f64) -> f64 {
+        let (mut total, mut amplitude, mut frequency, mut max) = (0.0, 1.0, self.frequency, 0.0);
+        for octave in &self.octaves {
+            total += sample(octave, frequency) * amplitude;
+            max += amplitude;
+            amplitude /= 2.0;
+            frequency *= 2.0;
+        }
+        total / max
+    }
+}
+
+/// A fast, well mixed sequence of numbers from a seed, used to derive the seeds of every noise
+/// from the world's.
+pub fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Where `value` is in the permutation table, which repeats every 256.
+fn lattice(value: f64) -> usize {
+    (value.floor() as i64 & 255) as usize
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// The dot product of `x`, `y`, `z` with one of 12 gradients, picked by `hash`.
+fn grad(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        0..=3 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_is_seeded() {
+        let noise = OctaveNoise::new(42, 4, 1.0 / 64.0);
+        let again = OctaveNoise::new(42, 4, 1.0 / 64.0);
+        let other = OctaveNoise::new(43, 4, 1.0 / 64.0);
+        let mut differs = false;
+        for i in 0..100 {
+            let (x, z) = (i as f64 * 13.7, i as f64 * -7.3);
+            let sample = noise.sample_2d(x, z);
+            assert!((-1.1..=1.1).contains(&sample));
+            assert_eq!(sample, again.sample_2d(x, z));
+            differs |= sample != other.sample_2d(x, z);
+        }
+        assert!(differs);
+    }
+
+    #[test]
+    fn test_noise_is_smooth() {
+        let noise = PerlinNoise::new(7);
+        assert_eq!(noise.sample_3d(3.0, -2.0, 5.0), 0.0);
+        let step = noise.sample_2d(10.0, 10.0) - noise.sample_2d(10.001, 10.0);
+        assert!(step.abs() < 0.01);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::utils::prelude::*;
+use crate::world::blocks::{air, MAX_BUILD_HEIGHT, MIN_BUILD_HEIGHT};
+use crate::world::chunk_format::{Biomes, Chunk, Palette};
+use crate::world::conversions::default_block_state;
+use crate::world::generation::{new_chunk, new_section, section_ys, single_biome, ChunkGenerator};
+
+/// Every chunk is the same flat layers of blocks, from the bottom of the world up.
+pub struct SuperflatGenerator {
+    /// Already converted to the network format, so generating a chunk is just copying it.
+    template: Chunk,
+}
+
+impl SuperflatGenerator {
+    /// A generator from a list of layers like `bedrock,2xdirt,grass_block`, bottom first. A
+    /// number and an `x` in front of a block repeats it.
+    pub fn from_layers(layers: &str) -> Result<Self> {
+        Self::new(parse_layers(layers)?)
+    }
+
+    /// A generator with one layer for each block in `layers`, bottom first.
+    pub fn new(layers: Vec<Palette>) -> Result<Self> {
+        let height = MAX_BUILD_HEIGHT - MIN_BUILD_HEIGHT;
+        if layers.len() > height as usize {
+            return Err(Error::Generic(format!(
+                "Superflat worlds can only have {} layers, not {}",
+                height,
+                layers.len()
+            )));
+        }
+
+        let mut template = new_chunk(0, 0);
+        let sections = template.sections.get_or_insert_with(Vec::new);
+        for section_y in section_ys() {
+            let bottom = section_y as i32 * 16 - MIN_BUILD_HEIGHT;
+            let mut palette = vec![air()];
+            let mut indices = vec![0u16; 4096];
+            for y in 0..16 {
+                let Some(block) = layers.get((bottom + y) as usize) else {
+                    break;
+                };
+                let index = match palette.iter().position(|entry| entry == block) {
+                    Some(index) => index,
+                    None => {
+                        palette.push(block.clone());
+                        palette.len() - 1
+                    }
+                };
+                indices[y as usize * 256..(y as usize + 1) * 256].fill(index as u16);
+            }
+            sections.push(new_section(
+                section_y,
+                &palette,
+                &indices,
+                &Biomes::plains(),
+            ));
+        }
+        template.convert_to_net_mode()?;
+        Ok(Self { template })
+    }
+
+    /// The same layers, in the biome called `biome` rather than plains.
+    pub fn with_biome(mut self, biome: &str) -> Result<Self> {
+        let biomes = single_biome(biome)?;
+        for section in self.template.sections.iter_mut().flatten() {
+            section.biomes = Some(biomes.clone());
+        }
+        Ok(self)
+    }
+}
+
+impl ChunkGenerator for SuperflatGenerator {
+    fn generate(&self, x: i32, z: i32) -> Chunk {
+        let mut chunk = self.template.clone();
+        chunk.x_pos = x;
+        chunk.z_pos = z;
+        chunk
+    }
+}
+
+/// Reads a list of layers like `bedrock,2xdirt,grass_block`, bottom first. Blocks without a
+/// namespace are in `minecraft`, and are in their default state.
+pub fn parse_layers(layers: &str) -> Result<Vec<Palette>> {
+    let mut parsed = Vec::new();
+    for layer in layers
+        .split(',')
+        .map(str::trim)
+        .filter(|layer| !layer.is_empty())
+    {
+        let (count, name) = match layer.split_once('x') {
+            Some((count, name)) if count.parse::<usize>().is_ok() => {
+                (count.parse::<usize>().unwrap(), name.trim())
+            }
+            _ => (1, layer),
+        };
+        let name = if name.contains(':') {
+            name.to_string()
+        } else {
+            format!("minecraft:{}", name)
+        };
+        let block = default_block_state(&name).ok_or_else(|| {
+            Error::Generic(format!("Unknown block \"{}\" in superflat layers", name))
+        })?;
+        parsed.extend(std::iter::repeat_n(block, count));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::network_types::varint::VarInt;
+
+    use super::*;
+
+    fn block(name: &str) -> Palette {
+        default_block_state(&format!("minecraft:{}", name)).unwrap()
+    }
+
+    #[test]
+    fn test_parsing_layers() {
+        assert_eq!(
+            parse_layers("bedrock, 2xdirt,minecraft:grass_block").unwrap(),
+            vec![
+                block("bedrock"),
+                block("dirt"),
+                block("dirt"),
+                block("grass_block")
+            ]
+        );
+        assert_eq!(parse_layers("").unwrap(), vec![]);
+        assert!(parse_layers("bedrock,not_a_block").is_err());
+        assert!(SuperflatGenerator::from_layers("400xstone").is_err());
+    }
+
+    #[test]
+    fn test_generated_blocks() {
+        let generator = SuperflatGenerator::from_layers("bedrock,2xdirt,grass_block").unwrap();
+        let chunk = generator.generate(3, -7);
+        assert_eq!((chunk.x_pos, chunk.z_pos), (3, -7));
+        assert_eq!(chunk.get_block(48, -64, -112).unwrap(), block("bedrock"));
+        assert_eq!(chunk.get_block(63, -63, -97).unwrap(), block("dirt"));
+        assert_eq!(chunk.get_block(50, -62, -100).unwrap(), block("dirt"));
+        assert_eq!(
+            chunk.get_block(50, -61, -100).unwrap(),
+            block("grass_block")
+        );
+        assert_eq!(chunk.get_block(50, -60, -100).unwrap(), air());
+        assert_eq!(chunk.get_block(50, 100, -100).unwrap(), air());
+        assert_eq!(chunk.sections.as_ref().unwrap().len(), 24);
+
+        // 4 blocks above the bottom of the world, packed 7 to a long.
+        let heightmap = chunk.heightmaps.unwrap().motion_blocking.unwrap();
+        assert_eq!(heightmap.len(), 37);
+        assert_eq!(heightmap[0] & 0x1FF, 4);
+    }
+
+    #[test]
+    fn test_biome() {
+        let generator = SuperflatGenerator::from_layers("bedrock").unwrap();
+        let biomes = generator.generate(0, 0).sections.unwrap()[5]
+            .biomes
+            .clone()
+            .unwrap();
+        assert_eq!(biomes.palette, vec!["minecraft:plains".to_string()]);
+
+        let desert = generator.with_biome("minecraft:desert").unwrap();
+        for section in desert.generate(1, 1).sections.unwrap() {
+            let biomes = section.biomes.unwrap();
+            assert_eq!(biomes.net_palette, Some(vec![VarInt::from(14)]));
+            assert_eq!(biomes.data, None);
+        }
+        assert!(SuperflatGenerator::from_layers("bedrock")
+            .unwrap()
+            .with_biome("minecraft:nowhere")
+            .is_err());
+    }
+
+    #[test]
+    fn test_section_of_one_block() {
+        let generator = SuperflatGenerator::from_layers("16xstone,dirt").unwrap();
+        let chunk = generator.generate(0, 0);
+        let bottom = chunk.sections.as_ref().unwrap()[0]
+            .block_states
+            .clone()
+            .unwrap();
+        assert_eq!(bottom.data, None);
+        assert_eq!(bottom.non_air_blocks, Some(4096));
+        assert_eq!(chunk.get_block(5, -49, 5).unwrap(), block("stone"));
+        assert_eq!(chunk.get_block(5, -48, 5).unwrap(), block("dirt"));
+    }
+
+    #[test]
+    fn test_generating_is_deterministic() {
+        let generator =
+            SuperflatGenerator::from_layers("bedrock,3xstone,2xdirt,grass_block").unwrap();
+        for _ in 0..50 {
+            let x = rand::random::<i32>() % 1_000_000;
+            let z = rand::random::<i32>() % 1_000_000;
+            assert_eq!(generator.generate(x, z), generator.generate(x, z));
+        }
+    }
+}
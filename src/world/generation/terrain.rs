@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+
+use crate::utils::prelude::*;
+use crate::world::blocks::{air, MAX_BUILD_HEIGHT, MIN_BUILD_HEIGHT};
+use crate::world::chunk_format::{Biomes, Chunk, Palette};
+use crate::world::conversions::default_block_state;
+use crate::world::generation::noise::{splitmix64, OctaveNoise};
+use crate::world::generation::{new_chunk, new_section, section_ys, single_biome, ChunkGenerator};
+
+/// Everything below this y that isn't ground is water.
+pub const SEA_LEVEL: i32 = 63;
+/// How far above and below the terrain's height the 3D noise can move the ground, in blocks.
+const OVERHANG_RANGE: i32 = 8;
+/// How many blocks of dirt or sand there are under the top block.
+const SUBSURFACE_DEPTH: usize = 3;
+/// How high above sea level the ground can be and still be beach.
+const BEACH_HEIGHT: i32 = 2;
+/// From the continentalness, how high the terrain is: deep ocean out at sea, a coast around 0,
+/// then rising inland.
+const CONTINENTAL_HEIGHTS: [(f64, f64); 6] = [
+    (-1.0, 20.0),
+    (-0.35, 40.0),
+    (-0.1, 60.0),
+    (0.05, 66.0),
+    (0.4, 95.0),
+    (1.0, 150.0),
+];
+
+const AIR: u16 = 0;
+const STONE: u16 = 1;
+const DIRT: u16 = 2;
+const GRASS_BLOCK: u16 = 3;
+const SAND: u16 = 4;
+const WATER: u16 = 5;
+const BEDROCK: u16 = 6;
+
+/// Rolling overworld terrain from layered noise: oceans, beaches, plains and hills.
+///
+/// Nothing like vanilla's, but the same seed always gives the same world.
+///
+/// - `continentalness`: How far inland a column is, which decides its base height.
+/// - `erosion`: How flat the land around a column is.
+/// - `hills`: The bumps on top, scaled by the erosion.
+/// - `overhangs`: 3D noise near the surface, so cliffs aren't just columns.
+pub struct NoiseGenerator {
+    continentalness: OctaveNoise,
+    erosion: OctaveNoise,
+    hills: OctaveNoise,
+    overhangs: OctaveNoise,
+    /// Indexed by the constants above.
+    palette: Vec<Palette>,
+    biomes: Biomes,
+}
+
+impl NoiseGenerator {
+    pub fn new(seed: i64) -> Self {
+        let mut state = seed as u64;
+        let block = |name: &str| {
+            default_block_state(name)
+                .unwrap_or_else(|| panic!("{} isn't in the block mappings", name))
+        };
+        Self {
+            continentalness: OctaveNoise::new(splitmix64(&mut state), 5, 1.0 / 1024.0),
+            erosion: OctaveNoise::new(splitmix64(&mut state), 3, 1.0 / 512.0),
+            hills: OctaveNoise::new(splitmix64(&mut state), 4, 1.0 / 128.0),
+            overhangs: OctaveNoise::new(splitmix64(&mut state), 2, 1.0 / 24.0),
+            palette: vec![
+                air(),
+                block("minecraft:stone"),
+                block("minecraft:dirt"),
+                Palette {
+                    name: "minecraft:grass_block".to_string(),
+                    properties: Some(BTreeMap::from([("snowy".to_string(), "false".to_string())])),
+                },
+                block("minecraft:sand"),
+                // Only the source block, with a level of 0.
+                block("minecraft:water"),
+                block("minecraft:bedrock"),
+            ],
+            biomes: Biomes::plains(),
+        }
+    }
+
+    /// The same terrain, all in the biome called `biome` rather than plains.
+    pub fn with_biome(mut self, biome: &str) -> Result<Self> {
+        self.biomes = single_biome(biome)?;
+        Ok(self)
+    }
+
+    /// Roughly where the ground is in the column at `x`, `z`, before the overhangs.
+    fn height(&self, x: f64, z: f64) -> f64 {
+        // Octaves flatten the noise out, so it's stretched back to cover the whole range.
+        let continentalness = (self.continentalness.sample_2d(x, z) * 1.8).clamp(-1.0, 1.0);
+        let flatness = ((self.erosion.sample_2d(x, z) * 1.5 + 1.0) / 2.0).clamp(0.0, 1.0);
+        let amplitude = 3.0 + (1.0 - flatness) * 30.0;
+        spline(&CONTINENTAL_HEIGHTS, continentalness) + self.hills.sample_2d(x, z) * amplitude
+    }
+
+    /// The blocks in the column at `x`, `z`, from the bottom of the world up, as indices into
+    /// the palette.
+    fn column(&self, x: i32, z: i32) -> Vec<u16> {
+        let height = self.height(x as f64, z as f64);
+        let ground = height.floor() as i32;
+        let mut column = (MIN_BUILD_HEIGHT..MAX_BUILD_HEIGHT)
+            .map(|y| {
+                let solid = if y < ground - OVERHANG_RANGE {
+                    true
+                } else if y > ground + OVERHANG_RANGE {
+                    false
+                } else {
+                    let overhang = self.overhangs.sample_3d(x as f64, y as f64, z as f64);
+                    height - y as f64 + overhang * OVERHANG_RANGE as f64 > 0.0
+                };
+                match (solid, y < SEA_LEVEL) {
+                    (true, _) => STONE,
+                    (false, true) => WATER,
+                    (false, false) => AIR,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // From the top down, the first few blocks of every stretch of ground are its surface.
+        let mut depth = 0;
+        for (y, block) in column.iter_mut().enumerate().rev() {
+            if *block != STONE {
+                depth = 0;
+                continue;
+            }
+            let y = y as i32 + MIN_BUILD_HEIGHT;
+            let beach = y < SEA_LEVEL + BEACH_HEIGHT;
+            *block = match (depth, beach) {
+                (0, false) => GRASS_BLOCK,
+                (0..=SUBSURFACE_DEPTH, false) => DIRT,
+                (0..=SUBSURFACE_DEPTH, true) => SAND,
+                _ => STONE,
+            };
+            depth += 1;
+        }
+        column[0] = BEDROCK;
+        column
+    }
+}
+
+impl ChunkGenerator for NoiseGenerator {
+    fn generate(&self, chunk_x: i32, chunk_z: i32) -> Chunk {
+        let mut chunk = new_chunk(chunk_x, chunk_z);
+
+        // Columns in x then z order, the same as heightmaps.
+        let columns = (0..256)
+            .map(|i| self.column(chunk_x * 16 + i % 16, chunk_z * 16 + i / 16))
+            .collect::<Vec<_>>();
+
+        let sections = chunk.sections.get_or_insert_with(Vec::new);
+        for (i, section_y) in section_ys().enumerate() {
+            // Sections store blocks in y, then z, then x order.
+            let mut indices = vec![AIR; 4096];
+            for y in 0..16 {
+                for (column_index, column) in columns.iter().enumerate() {
+                    indices[y * 256 + column_index] = column[i * 16 + y];
+                }
+            }
+            sections.push(new_section(
+                section_y,
+                &self.palette,
+                &indices,
+                &self.biomes,
+            ));
+        }
+        chunk
+            .convert_to_net_mode()
+            .expect("Generated blocks are all in the block mappings");
+        chunk
+    }
+}
+
+/// Linearly interpolates between the `(input, output)` points, which are sorted by input.
+fn spline(points: &[(f64, f64)], input: f64) -> f64 {
+    let Some(index) = points.iter().position(|&(x, _)| x >= input) else {
+        return points[points.len() - 1].1;
+    };
+    if index == 0 {
+        return points[0].1;
+    }
+    let ((x0, y0), (x1, y1)) = (points[index - 1], points[index]);
+    y0 + (input - x0) / (x1 - x0) * (y1 - y0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spline() {
+        let points = [(0.0, 10.0), (1.0, 20.0), (2.0, 0.0)];
+        assert_eq!(spline(&points, -5.0), 10.0);
+        assert_eq!(spline(&points, 0.5), 15.0);
+        assert_eq!(spline(&points, 1.5), 10.0);
+        assert_eq!(spline(&points, 3.0), 0.0);
+    }
+
+    #[test]
+    fn test_generating_is_deterministic() {
+        let generator = NoiseGenerator::new(1234);
+        let again = NoiseGenerator::new(1234);
+        for (x, z) in [(0, 0), (-7, 3), (100, -250)] {
+            assert_eq!(generator.generate(x, z), again.generate(x, z));
+        }
+        assert_ne!(
+            generator.generate(0, 0),
+            NoiseGenerator::new(4321).generate(0, 0)
+        );
+    }
+
+    #[test]
+    fn test_terrain() {
+        let generator = NoiseGenerator::new(99);
+        let chunk = generator.generate(2, -3);
+        let block = |name: &str| default_block_state(name).unwrap();
+        for (x, z) in [(32, -48), (40, -40), (47, -33)] {
+            assert_eq!(
+                chunk.get_block(x, MIN_BUILD_HEIGHT, z).unwrap(),
+                block("minecraft:bedrock")
+            );
+            assert_eq!(
+                chunk.get_block(x, -30, z).unwrap(),
+                block("minecraft:stone")
+            );
+            assert_eq!(chunk.get_block(x, 300, z).unwrap(), air());
+
+            // Nothing at sea level is left empty, it's either ground or water.
+            assert_ne!(chunk.get_block(x, SEA_LEVEL - 1, z).unwrap(), air());
+        }
+    }
+
+    #[test]
+    fn test_heightmaps_match_blocks() {
+        let generator = NoiseGenerator::new(5);
+        let chunk = generator.generate(-1, 4);
+        let heightmap = chunk
+            .heightmaps
+            .as_ref()
+            .unwrap()
+            .motion_blocking
+            .clone()
+            .unwrap();
+        let heights = crate::world::blocks::unpack_indices(&heightmap, 9);
+        for (column, &height) in heights.iter().take(256).enumerate() {
+            let (x, z) = (-16 + column as i32 % 16, 64 + column as i32 / 16);
+            let top = height as i32 + MIN_BUILD_HEIGHT;
+            assert_ne!(chunk.get_block(x, top - 1, z).unwrap(), air());
+            assert_eq!(chunk.get_block(x, top, z).unwrap(), air());
+        }
+    }
+}
@@ -0,0 +1,147 @@
+/// How hard blocks are to break, by name, same as vanilla's hardness. Blocks with a hardness of 0
+/// break as soon as players start digging them, and ones with a hardness of -1 can only be broken
+/// in creative. Only those two kinds are in here so far, everything else takes some time to dig.
+const HARDNESS: &[(&str, f32)] = &[
+    ("minecraft:bedrock", -1.0),
+    ("minecraft:barrier", -1.0),
+    ("minecraft:light", -1.0),
+    ("minecraft:command_block", -1.0),
+    ("minecraft:chain_command_block", -1.0),
+    ("minecraft:repeating_command_block", -1.0),
+    ("minecraft:structure_block", -1.0),
+    ("minecraft:jigsaw", -1.0),
+    ("minecraft:end_portal", -1.0),
+    ("minecraft:end_portal_frame", -1.0),
+    ("minecraft:end_gateway", -1.0),
+    ("minecraft:nether_portal", -1.0),
+    ("minecraft:moving_piston", -1.0),
+    ("minecraft:grass", 0.0),
+    ("minecraft:fern", 0.0),
+    ("minecraft:dead_bush", 0.0),
+    ("minecraft:tall_grass", 0.0),
+    ("minecraft:large_fern", 0.0),
+    ("minecraft:dandelion", 0.0),
+    ("minecraft:poppy", 0.0),
+    ("minecraft:blue_orchid", 0.0),
+    ("minecraft:allium", 0.0),
+    ("minecraft:azure_bluet", 0.0),
+    ("minecraft:oxeye_daisy", 0.0),
+    ("minecraft:cornflower", 0.0),
+    ("minecraft:lily_of_the_valley", 0.0),
+    ("minecraft:wither_rose", 0.0),
+    ("minecraft:torchflower", 0.0),
+    ("minecraft:pink_petals", 0.0),
+    ("minecraft:sunflower", 0.0),
+    ("minecraft:lilac", 0.0),
+    ("minecraft:rose_bush", 0.0),
+    ("minecraft:peony", 0.0),
+    ("minecraft:pitcher_plant", 0.0),
+    ("minecraft:spore_blossom", 0.0),
+    ("minecraft:azalea", 0.0),
+    ("minecraft:flowering_azalea", 0.0),
+    ("minecraft:small_dripleaf", 0.0),
+    ("minecraft:hanging_roots", 0.0),
+    ("minecraft:crimson_roots", 0.0),
+    ("minecraft:warped_roots", 0.0),
+    ("minecraft:nether_sprouts", 0.0),
+    ("minecraft:sugar_cane", 0.0),
+    ("minecraft:kelp", 0.0),
+    ("minecraft:kelp_plant", 0.0),
+    ("minecraft:seagrass", 0.0),
+    ("minecraft:tall_seagrass", 0.0),
+    ("minecraft:lily_pad", 0.0),
+    ("minecraft:cave_vines", 0.0),
+    ("minecraft:cave_vines_plant", 0.0),
+    ("minecraft:weeping_vines", 0.0),
+    ("minecraft:weeping_vines_plant", 0.0),
+    ("minecraft:twisting_vines", 0.0),
+    ("minecraft:twisting_vines_plant", 0.0),
+    ("minecraft:wheat", 0.0),
+    ("minecraft:carrots", 0.0),
+    ("minecraft:potatoes", 0.0),
+    ("minecraft:beetroots", 0.0),
+    ("minecraft:torchflower_crop", 0.0),
+    ("minecraft:pitcher_crop", 0.0),
+    ("minecraft:melon_stem", 0.0),
+    ("minecraft:pumpkin_stem", 0.0),
+    ("minecraft:attached_melon_stem", 0.0),
+    ("minecraft:attached_pumpkin_stem", 0.0),
+    ("minecraft:nether_wart", 0.0),
+    ("minecraft:sweet_berry_bush", 0.0),
+    ("minecraft:redstone_wire", 0.0),
+    ("minecraft:repeater", 0.0),
+    ("minecraft:comparator", 0.0),
+    ("minecraft:tripwire", 0.0),
+    ("minecraft:tripwire_hook", 0.0),
+    ("minecraft:flower_pot", 0.0),
+    ("minecraft:end_rod", 0.0),
+    ("minecraft:scaffolding", 0.0),
+    ("minecraft:slime_block", 0.0),
+    ("minecraft:honey_block", 0.0),
+    ("minecraft:tnt", 0.0),
+    ("minecraft:frogspawn", 0.0),
+    ("minecraft:decorated_pot", 0.0),
+    ("minecraft:fire", 0.0),
+    ("minecraft:soul_fire", 0.0),
+    ("minecraft:structure_void", 0.0),
+];
+/// The ends of names of families of blocks that break straight away, e.g. every kind of sapling
+/// and torch.
+const INSTANT_SUFFIXES: &[&str] = &[
+    "_sapling",
+    "_tulip",
+    "_mushroom",
+    "_fungus",
+    "torch",
+    "_coral",
+    "_coral_fan",
+    "_coral_wall_fan",
+];
+
+/// How hard `block_name` is to break, or `None` if it isn't known, see [HARDNESS].
+pub fn hardness(block_name: &str) -> Option<f32> {
+    if let Some(&(_, hardness)) = HARDNESS.iter().find(|(name, _)| *name == block_name) {
+        return Some(hardness);
+    }
+    let instant = block_name.starts_with("minecraft:potted_")
+        || INSTANT_SUFFIXES
+            .iter()
+            .any(|suffix| block_name.ends_with(suffix));
+    instant.then_some(0.0)
+}
+
+/// Whether `block_name` breaks as soon as players start digging it.
+pub fn breaks_instantly(block_name: &str) -> bool {
+    hardness(block_name) == Some(0.0)
+}
+
+/// Whether `block_name` can only be broken in creative.
+pub fn is_unbreakable(block_name: &str) -> bool {
+    hardness(block_name).is_some_and(|hardness| hardness < 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hardness() {
+        for name in [
+            "minecraft:poppy",
+            "minecraft:torch",
+            "minecraft:redstone_wall_torch",
+            "minecraft:oak_sapling",
+            "minecraft:wheat",
+            "minecraft:potted_cactus",
+        ] {
+            assert!(breaks_instantly(name), "{name}");
+        }
+        assert!(is_unbreakable("minecraft:bedrock"));
+        assert!(!breaks_instantly("minecraft:bedrock"));
+        // Not every name ending the same way breaks straight away.
+        assert_eq!(hardness("minecraft:red_mushroom_block"), None);
+        assert_eq!(hardness("minecraft:tube_coral_block"), None);
+        assert!(!breaks_instantly("minecraft:stone"));
+        assert!(!is_unbreakable("minecraft:stone"));
+    }
+}
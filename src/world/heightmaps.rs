@@ -0,0 +1,437 @@
+use std::cmp::Reverse;
+
+use crate::utils::prelude::*;
+use crate::world::block_states::BlockState;
+use crate::world::blocks::{pack_indices, unpack_indices, MIN_BUILD_HEIGHT};
+use crate::world::chunk_format::{Chunk, Heightmaps, Section};
+use crate::world::paletted_container::PalettedContainer;
+
+/// How many bits each column's height takes up in a heightmap.
+pub const HEIGHTMAP_BITS: usize = 9;
+/// How many columns a chunk has, which are in x then z order in heightmaps.
+const COLUMNS: usize = 256;
+/// How many longs a heightmap takes, with 7 heights to a long.
+const HEIGHTMAP_LEN: usize = COLUMNS.div_ceil(64 / HEIGHTMAP_BITS);
+
+/// Blocks without anything to bump into, as far as heightmaps go. Most of them are plants,
+/// which rain falls through.
+const PASSABLE: &[&str] = &[
+    "minecraft:air",
+    "minecraft:cave_air",
+    "minecraft:void_air",
+    "minecraft:grass",
+    "minecraft:fern",
+    "minecraft:dead_bush",
+    "minecraft:tall_grass",
+    "minecraft:large_fern",
+    "minecraft:dandelion",
+    "minecraft:poppy",
+    "minecraft:blue_orchid",
+    "minecraft:allium",
+    "minecraft:azure_bluet",
+    "minecraft:oxeye_daisy",
+    "minecraft:cornflower",
+    "minecraft:lily_of_the_valley",
+    "minecraft:wither_rose",
+    "minecraft:torchflower",
+    "minecraft:pink_petals",
+    "minecraft:sunflower",
+    "minecraft:lilac",
+    "minecraft:rose_bush",
+    "minecraft:peony",
+    "minecraft:brown_mushroom",
+    "minecraft:red_mushroom",
+    "minecraft:crimson_fungus",
+    "minecraft:warped_fungus",
+    "minecraft:crimson_roots",
+    "minecraft:warped_roots",
+    "minecraft:nether_sprouts",
+    "minecraft:sugar_cane",
+    "minecraft:vine",
+    "minecraft:glow_lichen",
+    "minecraft:cobweb",
+    "minecraft:wheat",
+    "minecraft:carrots",
+    "minecraft:potatoes",
+    "minecraft:beetroots",
+    "minecraft:nether_wart",
+    "minecraft:sweet_berry_bush",
+    "minecraft:redstone_wire",
+    "minecraft:lever",
+    "minecraft:tripwire",
+    "minecraft:tripwire_hook",
+    "minecraft:fire",
+    "minecraft:soul_fire",
+    "minecraft:structure_void",
+];
+/// The ends of names of families of passable blocks, e.g. every kind of sapling.
+const PASSABLE_SUFFIXES: &[&str] = &[
+    "_sapling",
+    "_tulip",
+    "torch",
+    "_button",
+    "_pressure_plate",
+    "rail",
+    "_sign",
+    "_banner",
+];
+
+/// The heightmaps chunks keep and send to clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeightmapKind {
+    /// The highest block that's solid or has water or lava in it, which rain stops at.
+    MotionBlocking,
+    /// The highest block that isn't air.
+    WorldSurface,
+}
+
+impl HeightmapKind {
+    pub const ALL: [HeightmapKind; 2] =
+        [HeightmapKind::MotionBlocking, HeightmapKind::WorldSurface];
+
+    /// Whether the block with the network id `id` counts towards the heightmap.
+    pub fn counts(self, id: i32) -> bool {
+        let Some(state) = u32::try_from(id).ok().and_then(BlockState::from_id) else {
+            return false;
+        };
+        match self {
+            HeightmapKind::MotionBlocking => blocks_motion(state) || has_fluid(state),
+            HeightmapKind::WorldSurface => !is_air(state),
+        }
+    }
+
+    fn heightmap(self, heightmaps: &Heightmaps) -> Option<&Vec<i64>> {
+        match self {
+            HeightmapKind::MotionBlocking => heightmaps.motion_blocking.as_ref(),
+            HeightmapKind::WorldSurface => heightmaps.world_surface.as_ref(),
+        }
+    }
+
+    fn heightmap_mut(self, heightmaps: &mut Heightmaps) -> Option<&mut Vec<i64>> {
+        match self {
+            HeightmapKind::MotionBlocking => heightmaps.motion_blocking.as_mut(),
+            HeightmapKind::WorldSurface => heightmaps.world_surface.as_mut(),
+        }
+    }
+}
+
+fn is_air(state: BlockState) -> bool {
+    matches!(
+        state.name(),
+        "minecraft:air" | "minecraft:cave_air" | "minecraft:void_air"
+    )
+}
+
+/// Whether entities bump into the block. Worked out from its name, since the block mappings
+/// don't have collision shapes.
+pub(crate) fn blocks_motion(state: BlockState) -> bool {
+    let name = state.name();
+    !PASSABLE.contains(&name)
+        && !PASSABLE_SUFFIXES
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+}
+
+pub(crate) fn has_fluid(state: BlockState) -> bool {
+    matches!(state.name(), "minecraft:water" | "minecraft:lava")
+        || state.property("waterlogged") == Some("true")
+}
+
+/// Which column `x`, `z` is in a heightmap.
+fn column_index(x: i32, z: i32) -> usize {
+    (z.rem_euclid(16) * 16 + x.rem_euclid(16)) as usize
+}
+
+/// A heightmap's entry for a column whose highest counted block is at `y`.
+fn height_above(y: i32) -> u16 {
+    (y + 1 - MIN_BUILD_HEIGHT) as u16
+}
+
+fn read_height(heightmap: &[i64], column: usize) -> u16 {
+    let per_long = 64 / HEIGHTMAP_BITS;
+    let shift = (column % per_long) * HEIGHTMAP_BITS;
+    ((heightmap[column / per_long] as u64 >> shift) & ((1 << HEIGHTMAP_BITS) - 1)) as u16
+}
+
+fn write_height(heightmap: &mut [i64], column: usize, height: u16) {
+    let per_long = 64 / HEIGHTMAP_BITS;
+    let shift = (column % per_long) * HEIGHTMAP_BITS;
+    let long = heightmap[column / per_long] as u64 & !(((1 << HEIGHTMAP_BITS) - 1) << shift);
+    heightmap[column / per_long] = (long | (height as u64) << shift) as i64;
+}
+
+impl Chunk {
+    /// Works out both heightmaps from the blocks, which have to be in the network format. Each
+    /// column's height is the y above its highest block that counts, measured from the bottom
+    /// of the world, so columns without any are 0.
+    pub fn compute_heightmaps(&self) -> Result<Heightmaps> {
+        let mut heights = [[0u16; COLUMNS]; 2];
+        for section in self.sections_from_top() {
+            let Some(block_states) = &section.block_states else {
+                continue;
+            };
+            let container = self.container(block_states.container())?;
+            for (kind, heights) in HeightmapKind::ALL.into_iter().zip(heights.iter_mut()) {
+                let counts = counted(kind, &container);
+                for (column, height) in heights.iter_mut().enumerate() {
+                    if *height != 0 {
+                        continue;
+                    }
+                    if let Some(y) = highest(&container, &counts, column, 16) {
+                        *height = height_above(section.y as i32 * 16 + y as i32);
+                    }
+                }
+            }
+        }
+        Ok(Heightmaps {
+            motion_blocking: Some(pack_indices(&heights[0], HEIGHTMAP_BITS)),
+            world_surface: Some(pack_indices(&heights[1], HEIGHTMAP_BITS)),
+        })
+    }
+
+    /// Updates the heightmaps after the block at `x`, `y`, `z` changed. A block that counts can
+    /// only raise its column, but breaking the highest one means finding the next one down.
+    ///
+    /// Heightmaps that are missing or the wrong size are worked out again from scratch.
+    pub fn update_heightmaps(&mut self, x: i32, y: i32, z: i32) -> Result<()> {
+        let complete = self.heightmaps.as_ref().is_some_and(|heightmaps| {
+            HeightmapKind::ALL.into_iter().all(|kind| {
+                kind.heightmap(heightmaps)
+                    .is_some_and(|heightmap| heightmap.len() == HEIGHTMAP_LEN)
+            })
+        });
+        if !complete {
+            self.heightmaps = Some(self.compute_heightmaps()?);
+            return Ok(());
+        }
+
+        let column = column_index(x, z);
+        let id = self.block_id_at(x, y, z)?;
+        for kind in HeightmapKind::ALL {
+            let heightmaps = self.heightmaps.as_ref().unwrap();
+            let current = read_height(kind.heightmap(heightmaps).unwrap(), column);
+            let height = if kind.counts(id) {
+                current.max(height_above(y))
+            } else if current == height_above(y) {
+                self.column_height(kind, column, y - 1)?
+            } else {
+                continue;
+            };
+            let heightmaps = self.heightmaps.as_mut().unwrap();
+            write_height(kind.heightmap_mut(heightmaps).unwrap(), column, height);
+        }
+        Ok(())
+    }
+
+    /// The height of `column` in the heightmap, only looking at blocks at or below `top`.
+    fn column_height(&self, kind: HeightmapKind, column: usize, top: i32) -> Result<u16> {
+        for section in self.sections_from_top() {
+            let bottom = section.y as i32 * 16;
+            let Some(block_states) = section.block_states.as_ref().filter(|_| bottom <= top) else {
+                continue;
+            };
+            let container = self.container(block_states.container())?;
+            let below = (top - bottom + 1).min(16) as usize;
+            if let Some(y) = highest(&container, &counted(kind, &container), column, below) {
+                return Ok(height_above(bottom + y as i32));
+            }
+        }
+        Ok(0)
+    }
+
+    fn sections_from_top(&self) -> Vec<&Section> {
+        let mut sections = self.sections.iter().flatten().collect::<Vec<_>>();
+        sections.sort_unstable_by_key(|section| Reverse(section.y));
+        sections
+    }
+
+    fn block_id_at(&self, x: i32, y: i32, z: i32) -> Result<i32> {
+        let section_y = y.div_euclid(16);
+        let Some(block_states) = self
+            .sections
+            .iter()
+            .flatten()
+            .find(|section| section.y as i32 == section_y)
+            .and_then(|section| section.block_states.as_ref())
+        else {
+            return Ok(0);
+        };
+        let index = y.rem_euclid(16) as usize * COLUMNS + column_index(x, z);
+        Ok(self.container(block_states.container())?.get(index))
+    }
+
+    fn container(
+        &self,
+        container: Result<PalettedContainer<i32>>,
+    ) -> Result<PalettedContainer<i32>> {
+        container.map_err(|e| Error::InvalidChunk(self.x_pos, self.z_pos, e.to_string()))
+    }
+}
+
+/// Which of the container's palette entries count towards the heightmap.
+fn counted(kind: HeightmapKind, container: &PalettedContainer<i32>) -> Vec<bool> {
+    container
+        .palette()
+        .iter()
+        .map(|&id| kind.counts(id))
+        .collect()
+}
+
+/// The y in its section of the highest block in `column` that counts, out of the ones below
+/// `below`.
+fn highest(
+    container: &PalettedContainer<i32>,
+    counts: &[bool],
+    column: usize,
+    below: usize,
+) -> Option<usize> {
+    if !counts.contains(&true) {
+        return None;
+    }
+    (0..below)
+        .rev()
+        .find(|&y| counts[container.palette_index(y * COLUMNS + column)])
+}
+
+/// Every column's height in a heightmap, in x then z order.
+pub fn heights(heightmap: &[i64]) -> Vec<u16> {
+    unpack_indices(heightmap, HEIGHTMAP_BITS)
+        .into_iter()
+        .take(COLUMNS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::world::blocks::air;
+    use crate::world::conversions::default_block_state;
+    use crate::world::generation::superflat::SuperflatGenerator;
+    use crate::world::generation::terrain::NoiseGenerator;
+    use crate::world::generation::ChunkGenerator;
+
+    fn block(name: &str) -> crate::world::chunk_format::Palette {
+        default_block_state(name).unwrap()
+    }
+
+    fn height(chunk: &Chunk, kind: HeightmapKind, x: i32, z: i32) -> i32 {
+        let heightmap = kind.heightmap(chunk.heightmaps.as_ref().unwrap()).unwrap();
+        heights(heightmap)[column_index(x, z)] as i32 + MIN_BUILD_HEIGHT
+    }
+
+    #[test]
+    fn test_counted_blocks() {
+        let id = |name: &str| BlockState::default_state(name).unwrap().id() as i32;
+        let waterlogged = BlockState::from_name_and_props("minecraft:oak_slab", &[])
+            .unwrap()
+            .with_property("waterlogged", "true")
+            .unwrap()
+            .id() as i32;
+        for (name, motion_blocking, world_surface) in [
+            ("minecraft:air", false, false),
+            ("minecraft:stone", true, true),
+            ("minecraft:water", true, true),
+            ("minecraft:poppy", false, true),
+            ("minecraft:oak_sapling", false, true),
+            ("minecraft:wall_torch", false, true),
+            ("minecraft:oak_leaves", true, true),
+        ] {
+            assert_eq!(
+                HeightmapKind::MotionBlocking.counts(id(name)),
+                motion_blocking
+            );
+            assert_eq!(HeightmapKind::WorldSurface.counts(id(name)), world_surface);
+        }
+        assert!(HeightmapKind::MotionBlocking.counts(waterlogged));
+    }
+
+    #[test]
+    fn test_placing_and_breaking() {
+        let mut chunk = SuperflatGenerator::from_layers("bedrock,2xdirt,grass_block")
+            .unwrap()
+            .generate(0, 0);
+        let ground = MIN_BUILD_HEIGHT + 4;
+        assert_eq!(height(&chunk, HeightmapKind::MotionBlocking, 3, 5), ground);
+
+        // Flowers only count for the surface.
+        chunk
+            .set_block(3, ground, 5, block("minecraft:poppy"))
+            .unwrap();
+        assert_eq!(height(&chunk, HeightmapKind::MotionBlocking, 3, 5), ground);
+        assert_eq!(
+            height(&chunk, HeightmapKind::WorldSurface, 3, 5),
+            ground + 1
+        );
+
+        // Breaking the highest block of a column drops it to the next one down, even far below.
+        chunk
+            .set_block(3, 200, 5, block("minecraft:stone"))
+            .unwrap();
+        assert_eq!(height(&chunk, HeightmapKind::MotionBlocking, 3, 5), 201);
+        chunk.set_block(3, 200, 5, air()).unwrap();
+        assert_eq!(height(&chunk, HeightmapKind::MotionBlocking, 3, 5), ground);
+        assert_eq!(
+            height(&chunk, HeightmapKind::WorldSurface, 3, 5),
+            ground + 1
+        );
+
+        // Digging out the whole column leaves nothing.
+        for y in MIN_BUILD_HEIGHT..=ground {
+            chunk.set_block(3, y, 5, air()).unwrap();
+        }
+        assert_eq!(
+            height(&chunk, HeightmapKind::MotionBlocking, 3, 5),
+            MIN_BUILD_HEIGHT
+        );
+        assert_eq!(height(&chunk, HeightmapKind::MotionBlocking, 4, 5), ground);
+    }
+
+    #[test]
+    fn test_updates_match_recompute() {
+        let blocks = [
+            air(),
+            block("minecraft:stone"),
+            block("minecraft:water"),
+            block("minecraft:poppy"),
+            block("minecraft:oak_leaves"),
+            block("minecraft:torch"),
+        ];
+        for seed in 0..4u64 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut chunk = NoiseGenerator::new(seed as i64).generate(seed as i32, -3);
+            for _ in 0..250 {
+                // Mostly a few columns near the surface, so edits land on top of each other.
+                let x = rng.gen_range(0..4);
+                let z = rng.gen_range(0..4);
+                let y = match rng.gen_bool(0.8) {
+                    true => rng.gen_range(40..90),
+                    false => rng.gen_range(MIN_BUILD_HEIGHT..320),
+                };
+                let block = blocks[rng.gen_range(0..blocks.len())].clone();
+                chunk
+                    .set_block(seed as i32 * 16 + x, y, -48 + z, block)
+                    .unwrap();
+                assert_eq!(
+                    chunk.heightmaps.as_ref().unwrap(),
+                    &chunk.compute_heightmaps().unwrap(),
+                    "Seed {}",
+                    seed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_heightmaps() {
+        let chunk = NoiseGenerator::new(5).generate(-1, 4);
+        let heightmaps = chunk.heightmaps.as_ref().unwrap();
+        assert_eq!(heightmaps, &chunk.compute_heightmaps().unwrap());
+        assert_eq!(
+            heightmaps.motion_blocking.as_ref().unwrap().len(),
+            HEIGHTMAP_LEN
+        );
+    }
+}
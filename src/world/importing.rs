@@ -258,7 +258,7 @@ mod test {
         // set environment variable "FERRUMC_ROOT" to the root of the ferrumc project
         setup_logger()?;
         let listener = TcpListener::bind("0.0.0.0:0").await?;
-        let state = create_state(listener).await?;
+        let state = create_state(vec![listener]).await?;
 
         let chunk = state
             .database
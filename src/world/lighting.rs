@@ -0,0 +1,610 @@
+use std::cell::OnceCell;
+use std::collections::VecDeque;
+use std::sync::LazyLock;
+
+use crate::utils::prelude::*;
+use crate::world::block_states::BlockState;
+use crate::world::blocks::{MAX_BUILD_HEIGHT, MIN_BUILD_HEIGHT};
+use crate::world::chunk_format::{Chunk, Section};
+use crate::world::heightmaps::{blocks_motion, has_fluid};
+
+/// The brightest light can be, which is what the sky gives off.
+pub const MAX_LIGHT: u8 = 15;
+/// A nibble for each block in a section.
+const LIGHT_ARRAY_SIZE: usize = 2048;
+/// How many blocks tall a chunk is.
+const HEIGHT: usize = (MAX_BUILD_HEIGHT - MIN_BUILD_HEIGHT) as usize;
+/// How many blocks there are in each layer of a chunk.
+const LAYER: usize = 256;
+/// How many blocks a chunk has.
+const VOLUME: usize = HEIGHT * LAYER;
+
+/// Blocks light shines straight through, besides ones without anything to bump into.
+const CLEAR: &[&str] = &[
+    "minecraft:glass",
+    "minecraft:iron_bars",
+    "minecraft:chain",
+    "minecraft:ladder",
+    "minecraft:snow",
+    "minecraft:chest",
+    "minecraft:trapped_chest",
+    "minecraft:ender_chest",
+    "minecraft:scaffolding",
+    "minecraft:barrier",
+    "minecraft:light",
+    "minecraft:beacon",
+    "minecraft:conduit",
+    "minecraft:bell",
+    "minecraft:brewing_stand",
+    "minecraft:cauldron",
+    "minecraft:hopper",
+    "minecraft:lectern",
+    "minecraft:enchanting_table",
+    "minecraft:flower_pot",
+    "minecraft:end_rod",
+    "minecraft:lightning_rod",
+    "minecraft:campfire",
+    "minecraft:soul_campfire",
+    "minecraft:lantern",
+    "minecraft:soul_lantern",
+    "minecraft:cake",
+    "minecraft:daylight_detector",
+    "minecraft:repeater",
+    "minecraft:comparator",
+    "minecraft:anvil",
+    "minecraft:chipped_anvil",
+    "minecraft:damaged_anvil",
+];
+/// The ends of names of families of blocks light shines straight through, most of which don't
+/// fill their whole block.
+const CLEAR_SUFFIXES: &[&str] = &[
+    "_glass",
+    "_glass_pane",
+    "_slab",
+    "_stairs",
+    "_fence",
+    "_fence_gate",
+    "_wall",
+    "_door",
+    "_trapdoor",
+    "_carpet",
+    "_bed",
+    "candle",
+    "_head",
+    "_skull",
+];
+/// Blocks that dim light a little, like water does.
+const TRANSLUCENT: &[&str] = &[
+    "minecraft:ice",
+    "minecraft:frosted_ice",
+    "minecraft:slime_block",
+    "minecraft:honey_block",
+    "minecraft:cobweb",
+];
+
+/// Every block state's opacity, by network id.
+static OPACITY: LazyLock<Box<[u8]>> = LazyLock::new(|| {
+    (0..BlockState::count())
+        .map(|id| opacity_of(BlockState::from_id(id).unwrap()))
+        .collect()
+});
+
+/// The kinds of light each section keeps.
+///
+/// Only sky light is worked out for now. Block light, from blocks like torches, spreads the
+/// same way from different sources, so it'll go through [propagate] as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightKind {
+    Sky,
+    Block,
+}
+
+impl LightKind {
+    fn array(self, section: &Section) -> Option<&Vec<i8>> {
+        match self {
+            LightKind::Sky => section.sky_light.as_ref(),
+            LightKind::Block => section.block_light.as_ref(),
+        }
+    }
+
+    fn array_mut(self, section: &mut Section) -> &mut Option<Vec<i8>> {
+        match self {
+            LightKind::Sky => &mut section.sky_light,
+            LightKind::Block => &mut section.block_light,
+        }
+    }
+}
+
+/// How much the block with the network id `id` dims light passing through it. 0 is clear, and
+/// [MAX_LIGHT] lets none through at all.
+pub fn opacity(id: i32) -> u8 {
+    usize::try_from(id)
+        .ok()
+        .and_then(|id| OPACITY.get(id))
+        .copied()
+        .unwrap_or(MAX_LIGHT)
+}
+
+/// Worked out from the block's name, since the block mappings don't say how much light blocks
+/// let through.
+fn opacity_of(state: BlockState) -> u8 {
+    let name = state.name();
+    if name == "minecraft:tinted_glass" {
+        MAX_LIGHT
+    } else if has_fluid(state) || name.ends_with("_leaves") || TRANSLUCENT.contains(&name) {
+        1
+    } else if !blocks_motion(state)
+        || CLEAR.contains(&name)
+        || CLEAR_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+    {
+        0
+    } else {
+        MAX_LIGHT
+    }
+}
+
+/// Which of the 6 blocks touching the one at `index` are in the same chunk, for volumes in y,
+/// then z, then x order.
+fn neighbours(index: usize) -> impl Iterator<Item = usize> {
+    let (x, z, y) = (index % 16, index / 16 % 16, index / LAYER);
+    [
+        (x > 0).then(|| index - 1),
+        (x < 15).then(|| index + 1),
+        (z > 0).then(|| index - 16),
+        (z < 15).then(|| index + 16),
+        (y > 0).then(|| index - LAYER),
+        (y < HEIGHT - 1).then(|| index + LAYER),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// Spreads the light from every block in `queue` to the ones around it, losing a level for
+/// each block it passes, or more for blocks that dim it.
+fn propagate(levels: &mut [u8], opacity: impl Fn(usize) -> u8, mut queue: VecDeque<usize>) {
+    while let Some(index) = queue.pop_front() {
+        let level = levels[index];
+        if level <= 1 {
+            continue;
+        }
+        for neighbour in neighbours(index) {
+            let spread = level.saturating_sub(opacity(neighbour).max(1));
+            if spread > levels[neighbour] {
+                levels[neighbour] = spread;
+                queue.push_back(neighbour);
+            }
+        }
+    }
+}
+
+/// The sky light for a whole chunk, from every block's opacity.
+///
+/// Light comes straight down each column at full brightness until something dims it, then
+/// spreads out from there. Columns next to ones that are lit further down are lit from the side
+/// too, which is how light gets under overhangs.
+fn sky_light(opacity: &[u8]) -> Vec<u8> {
+    let mut levels = vec![0; VOLUME];
+    // The lowest block in each column the sky shines straight down to.
+    let mut bottoms = [HEIGHT; LAYER];
+    for (column, bottom) in bottoms.iter_mut().enumerate() {
+        while *bottom > 0 && opacity[(*bottom - 1) * LAYER + column] == 0 {
+            *bottom -= 1;
+            levels[*bottom * LAYER + column] = MAX_LIGHT;
+        }
+    }
+
+    let mut queue = VecDeque::new();
+    for column in 0..LAYER {
+        // Light only has to spread from the lowest block in a column, which lights whatever
+        // stopped it, and from the ones beside columns that aren't lit as far down.
+        let beside = neighbours(column)
+            .filter(|&neighbour| neighbour < LAYER)
+            .map(|neighbour| bottoms[neighbour])
+            .max()
+            .unwrap_or(0);
+        let top = beside.max(bottoms[column] + 1).min(HEIGHT);
+        queue.extend((bottoms[column]..top).map(|y| y * LAYER + column));
+    }
+    propagate(&mut levels, |index| opacity[index], queue);
+    levels
+}
+
+/// Fixes up the sky light in `levels` after the block at `index` changed from one that dimmed
+/// light by `old` to what `opacity` says now.
+///
+/// If it lets less light through, the light that came through it is taken away first. Then the
+/// sky shines down its column again, and the light around it spreads back in. Only the blocks
+/// whose light could have changed are visited, not the whole chunk.
+fn relight(levels: &mut [u8], opacity: impl Fn(usize) -> u8, index: usize, old: u8) {
+    let mut queue = VecDeque::new();
+    if opacity(index) > old {
+        darken(levels, index, &mut queue);
+    }
+
+    let column = index % LAYER;
+    for y in (0..HEIGHT).rev() {
+        let below = y * LAYER + column;
+        if opacity(below) != 0 {
+            break;
+        }
+        if levels[below] != MAX_LIGHT {
+            levels[below] = MAX_LIGHT;
+            queue.push_back(below);
+        }
+    }
+    queue.push_back(index);
+    queue.extend(neighbours(index));
+    propagate(levels, opacity, queue);
+}
+
+/// Takes away the light of the block at `index` and of everything lit through it, and queues
+/// the blocks around those that are still lit, to spread their light back in.
+fn darken(levels: &mut [u8], index: usize, queue: &mut VecDeque<usize>) {
+    let mut dark = VecDeque::from([(index, levels[index])]);
+    levels[index] = 0;
+    while let Some((index, level)) = dark.pop_front() {
+        for neighbour in neighbours(index) {
+            let neighbour_level = levels[neighbour];
+            if neighbour_level == 0 {
+                continue;
+            }
+            // Only the sky shining straight down keeps light at full brightness, so full
+            // brightness right below full brightness came through it too.
+            let lit_through =
+                neighbour_level < level || (level == MAX_LIGHT && neighbour + LAYER == index);
+            if lit_through {
+                levels[neighbour] = 0;
+                dark.push_back((neighbour, neighbour_level));
+            } else {
+                queue.push_back(neighbour);
+            }
+        }
+    }
+}
+
+/// A light array with two blocks' levels to a byte, the lower nibble first.
+fn pack_light(levels: &[u8]) -> Vec<i8> {
+    levels
+        .chunks(2)
+        .map(|pair| (pair[0] | pair[1] << 4) as i8)
+        .collect()
+}
+
+impl Chunk {
+    /// Works out the sky light of every section in the world, which have to be in the network
+    /// format, and gives back the y of the ones whose light changed. Light doesn't spread between
+    /// chunks yet, so the edges of caves can be darker than they should be.
+    pub fn compute_sky_light(&mut self) -> Result<Vec<i8>> {
+        let levels = sky_light(&self.opacities()?);
+        Ok(self.store_light(LightKind::Sky, &levels))
+    }
+
+    /// Works out the sky light again after the block at the world coordinates `x`, `y`, `z`
+    /// changed from one that dimmed light by `old_opacity`, and gives back the y of every section
+    /// whose light changed. Only the block's column and the light around it are gone over, so
+    /// it's much cheaper than [compute_sky_light](Chunk::compute_sky_light), which it falls back
+    /// to if the chunk isn't lit yet.
+    pub fn update_sky_light(&mut self, x: i32, y: i32, z: i32, old_opacity: u8) -> Result<Vec<i8>> {
+        if self.missing_sky_light() {
+            return self.compute_sky_light();
+        }
+        let mut levels = self.stored_light(LightKind::Sky);
+        let index = ((y - MIN_BUILD_HEIGHT) as usize) * LAYER
+            + z.rem_euclid(16) as usize * 16
+            + x.rem_euclid(16) as usize;
+        let opacities = LazyOpacities::new(self);
+        relight(
+            &mut levels,
+            |index| opacities.get(index),
+            index,
+            old_opacity,
+        );
+        if let Some(e) = opacities.error() {
+            return Err(Error::InvalidChunk(self.x_pos, self.z_pos, e));
+        }
+        Ok(self.store_light(LightKind::Sky, &levels))
+    }
+
+    /// Whether any section in the world is missing its sky light.
+    pub fn missing_sky_light(&self) -> bool {
+        self.sections
+            .iter()
+            .flatten()
+            .filter(|section| section_offset(section).is_some())
+            .any(|section| {
+                LightKind::Sky
+                    .array(section)
+                    .is_none_or(|light| light.len() != LIGHT_ARRAY_SIZE)
+            })
+    }
+
+    /// The light of the kind `kind` at the world coordinates `x`, `y`, `z`, which have to be
+    /// inside this chunk. `None` if its section doesn't have any.
+    pub fn light(&self, kind: LightKind, x: i32, y: i32, z: i32) -> Option<u8> {
+        let section_y = y.div_euclid(16);
+        let section = self
+            .sections
+            .iter()
+            .flatten()
+            .find(|section| section.y as i32 == section_y)?;
+        let index = (y.rem_euclid(16) * 256 + z.rem_euclid(16) * 16 + x.rem_euclid(16)) as usize;
+        let byte = *kind.array(section)?.get(index / 2)? as u8;
+        Some(if index.is_multiple_of(2) {
+            byte & 0xF
+        } else {
+            byte >> 4
+        })
+    }
+
+    /// Every block's opacity, for the whole height of the world.
+    fn opacities(&self) -> Result<Vec<u8>> {
+        let mut opacities = vec![0; VOLUME];
+        for section in self.sections.iter().flatten() {
+            let Some(offset) = section_offset(section) else {
+                continue;
+            };
+            let section_opacities = section_opacities(section)
+                .map_err(|e| Error::InvalidChunk(self.x_pos, self.z_pos, e))?;
+            if let Some(section_opacities) = section_opacities {
+                opacities[offset..offset + 4096].copy_from_slice(&section_opacities);
+            }
+        }
+        Ok(opacities)
+    }
+
+    /// Every block's light of the kind `kind`, for the whole height of the world, as each section
+    /// has it stored. Sections without any are dark.
+    fn stored_light(&self, kind: LightKind) -> Vec<u8> {
+        let mut levels = vec![0; VOLUME];
+        for section in self.sections.iter().flatten() {
+            let (Some(offset), Some(light)) = (section_offset(section), kind.array(section)) else {
+                continue;
+            };
+            for (pair, &byte) in levels[offset..offset + 4096].chunks_mut(2).zip(light) {
+                pair[0] = byte as u8 & 0xF;
+                pair[1] = byte as u8 >> 4;
+            }
+        }
+        levels
+    }
+
+    /// Stores `levels` in each section's light arrays. Returns the y of every section whose
+    /// light changed.
+    fn store_light(&mut self, kind: LightKind, levels: &[u8]) -> Vec<i8> {
+        let mut changed = Vec::new();
+        for section in self.sections.iter_mut().flatten() {
+            let Some(offset) = section_offset(section) else {
+                continue;
+            };
+            let levels = &levels[offset..offset + 4096];
+            let array = kind.array_mut(section);
+            let unchanged = array.as_ref().is_some_and(|light| {
+                light.len() == LIGHT_ARRAY_SIZE
+                    && light
+                        .iter()
+                        .zip(levels.chunks(2))
+                        .all(|(&byte, pair)| byte as u8 == pair[0] | pair[1] << 4)
+            });
+            if !unchanged {
+                *array = Some(pack_light(levels));
+                changed.push(section.y);
+            }
+        }
+        changed
+    }
+}
+
+/// The opacity of every block in `section`, in y, then z, then x order. `None` if there aren't
+/// any blocks, or they're all clear.
+fn section_opacities(section: &Section) -> std::result::Result<Option<Vec<u8>>, String> {
+    let Some(block_states) = &section.block_states else {
+        return Ok(None);
+    };
+    let container = block_states.container().map_err(|e| e.to_string())?;
+    let palette = container
+        .palette()
+        .iter()
+        .map(|&id| opacity(id))
+        .collect::<Vec<_>>();
+    if palette.iter().all(|&opacity| opacity == 0) {
+        return Ok(None);
+    }
+    Ok(Some(
+        (0..4096)
+            .map(|index| palette[container.palette_index(index)])
+            .collect(),
+    ))
+}
+
+/// Every block's opacity, like [Chunk::opacities], but only worked out for a section once light
+/// gets to it. Relighting around one block only reaches a few sections.
+struct LazyOpacities<'a> {
+    /// By where they are in a volume for the whole height of the world, from the bottom up.
+    sections: Vec<Option<&'a Section>>,
+    opacities: Vec<OnceCell<std::result::Result<Option<Vec<u8>>, String>>>,
+}
+
+impl<'a> LazyOpacities<'a> {
+    fn new(chunk: &'a Chunk) -> Self {
+        let mut sections = vec![None; HEIGHT / 16];
+        for section in chunk.sections.iter().flatten() {
+            if let Some(offset) = section_offset(section) {
+                sections[offset / 4096] = Some(section);
+            }
+        }
+        Self {
+            opacities: vec![OnceCell::new(); sections.len()],
+            sections,
+        }
+    }
+
+    /// Sections that couldn't be read are taken to let no light through, see
+    /// [LazyOpacities::error].
+    fn get(&self, index: usize) -> u8 {
+        let section = index / 4096;
+        let opacities = self.opacities[section]
+            .get_or_init(|| self.sections[section].map_or(Ok(None), section_opacities));
+        match opacities {
+            Ok(Some(opacities)) => opacities[index % 4096],
+            Ok(None) => 0,
+            Err(_) => MAX_LIGHT,
+        }
+    }
+
+    /// Why a section light got to couldn't be read, if one couldn't.
+    fn error(&self) -> Option<String> {
+        self.opacities
+            .iter()
+            .find_map(|opacities| opacities.get()?.clone().err())
+    }
+}
+
+/// Where a section starts in a volume for the whole height of the world, or `None` if it's
+/// outside the world.
+fn section_offset(section: &Section) -> Option<usize> {
+    let bottom = section.y as i32 * 16 - MIN_BUILD_HEIGHT;
+    usize::try_from(bottom)
+        .ok()
+        .filter(|&bottom| bottom < HEIGHT)
+        .map(|bottom| bottom * LAYER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::blocks::air;
+    use crate::world::conversions::default_block_state;
+    use crate::world::generation::superflat::SuperflatGenerator;
+    use crate::world::generation::ChunkGenerator;
+
+    fn flat_chunk() -> Chunk {
+        SuperflatGenerator::from_layers("bedrock,2xdirt,grass_block")
+            .unwrap()
+            .generate(0, 0)
+    }
+
+    fn sky(chunk: &Chunk, x: i32, y: i32, z: i32) -> u8 {
+        chunk.light(LightKind::Sky, x, y, z).unwrap()
+    }
+
+    /// The y just above the grass.
+    const GROUND: i32 = MIN_BUILD_HEIGHT + 4;
+
+    #[test]
+    fn test_opacity() {
+        let id = |name: &str| BlockState::default_state(name).unwrap().id() as i32;
+        assert_eq!(opacity(id("minecraft:air")), 0);
+        assert_eq!(opacity(id("minecraft:stone")), MAX_LIGHT);
+        assert_eq!(opacity(id("minecraft:glass")), 0);
+        assert_eq!(opacity(id("minecraft:oak_slab")), 0);
+        assert_eq!(opacity(id("minecraft:poppy")), 0);
+        assert_eq!(opacity(id("minecraft:water")), 1);
+        assert_eq!(opacity(id("minecraft:oak_leaves")), 1);
+        assert_eq!(opacity(id("minecraft:tinted_glass")), MAX_LIGHT);
+        assert_eq!(opacity(-1), MAX_LIGHT);
+    }
+
+    #[test]
+    fn test_open_sky() {
+        let chunk = flat_chunk();
+        assert!(!chunk.missing_sky_light());
+        assert_eq!(sky(&chunk, 3, 300, 5), 15);
+        assert_eq!(sky(&chunk, 3, GROUND, 5), 15);
+        assert_eq!(sky(&chunk, 3, GROUND - 1, 5), 0);
+        assert_eq!(sky(&chunk, 3, MIN_BUILD_HEIGHT, 5), 0);
+    }
+
+    #[test]
+    fn test_light_spreads_under_a_roof() {
+        let mut chunk = flat_chunk();
+        let stone = default_block_state("minecraft:stone").unwrap();
+        for x in 6..=10 {
+            for z in 6..=10 {
+                chunk.set_block(x, GROUND + 3, z, stone.clone()).unwrap();
+            }
+        }
+        // 3 blocks in from the edge of the roof.
+        assert_eq!(sky(&chunk, 8, GROUND + 2, 8), 12);
+        assert_eq!(sky(&chunk, 8, GROUND, 8), 12);
+        assert_eq!(sky(&chunk, 6, GROUND, 8), 14);
+        assert_eq!(sky(&chunk, 8, GROUND + 3, 8), 0);
+        assert_eq!(sky(&chunk, 8, GROUND + 4, 8), 15);
+
+        // A hole in the middle lets the sky straight in again.
+        chunk.set_block(8, GROUND + 3, 8, air()).unwrap();
+        assert_eq!(sky(&chunk, 8, GROUND, 8), 15);
+        assert_eq!(sky(&chunk, 7, GROUND, 8), 14);
+    }
+
+    #[test]
+    fn test_water_dims_light() {
+        let mut chunk = flat_chunk();
+        let water = default_block_state("minecraft:water").unwrap();
+        for y in GROUND..GROUND + 3 {
+            chunk.set_block(0, y, 0, water.clone()).unwrap();
+        }
+        // Only the surrounding air is brighter, and that's all that lights the water.
+        assert_eq!(sky(&chunk, 0, GROUND + 2, 0), 14);
+        assert_eq!(sky(&chunk, 0, GROUND, 0), 14);
+
+        // With stone all around, it gets darker further down.
+        let mut chunk = SuperflatGenerator::from_layers("bedrock,10xstone")
+            .unwrap()
+            .generate(0, 0);
+        let top = MIN_BUILD_HEIGHT + 11;
+        for y in top - 4..top {
+            chunk.set_block(0, y, 0, water.clone()).unwrap();
+        }
+        let light = (top - 4..top)
+            .map(|y| sky(&chunk, 0, y, 0))
+            .collect::<Vec<_>>();
+        assert_eq!(light, vec![11, 12, 13, 14]);
+    }
+
+    #[test]
+    fn test_relighting_matches_lighting_from_scratch() {
+        let mut chunk = SuperflatGenerator::from_layers("bedrock,10xstone")
+            .unwrap()
+            .generate(0, 0);
+        let blocks = ["stone", "air", "water", "glass", "oak_leaves", "air"]
+            .map(|name| default_block_state(&format!("minecraft:{}", name)).unwrap());
+        // Random enough, and the same every time.
+        let mut seed = 12345u32;
+        let mut next = |bound: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % bound
+        };
+        for _ in 0..150 {
+            let (x, z) = (next(6) as i32, next(6) as i32);
+            let y = MIN_BUILD_HEIGHT + 4 + next(16) as i32;
+            let block = blocks[next(blocks.len() as u32) as usize].clone();
+            chunk.set_block(x, y, z, block).unwrap();
+
+            let mut from_scratch = chunk.clone();
+            assert!(from_scratch.compute_sky_light().unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_setting_a_block_reports_the_sections_it_relit() {
+        let mut chunk = flat_chunk();
+        let stone = default_block_state("minecraft:stone").unwrap();
+        // Shades the column all the way down to the ground.
+        assert_eq!(
+            chunk.set_block(0, 5, 0, stone.clone()).unwrap(),
+            vec![-4, -3, -2, -1, 0]
+        );
+        assert!(chunk.set_block(0, 5, 0, stone).unwrap().is_empty());
+        // A hole in the ground that no light gets to.
+        assert!(chunk.set_block(8, GROUND - 3, 8, air()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_only_changed_sections_are_reported() {
+        let mut chunk = flat_chunk();
+        assert!(chunk.compute_sky_light().unwrap().is_empty());
+        chunk.sections.as_mut().unwrap()[10].sky_light = None;
+        assert!(chunk.missing_sky_light());
+        assert_eq!(chunk.compute_sky_light().unwrap(), vec![6]);
+    }
+}
@@ -1,7 +1,24 @@
+pub mod anvil;
+pub mod biomes;
+pub mod block_changes;
+pub mod border;
+pub mod block_entities;
+pub mod block_states;
 pub mod blocks;
 pub mod chunk_format;
+pub mod chunk_service;
 pub mod conversions;
+pub mod dimensions;
+pub mod generation;
+pub mod hardness;
+pub mod heightmaps;
 pub mod importing;
+pub mod lighting;
+pub mod paletted_container;
+pub mod physics;
+pub mod saving;
+pub mod time;
+pub mod weather;
 
 /// Since we don't know the exact amount of bytes, the first byte is the number of u8s in the last i64,
 /// so we know when to stop reading bytes from the last i64
@@ -0,0 +1,450 @@
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::io::AsyncWrite;
+
+use crate::utils::prelude::*;
+use crate::world::biomes::biome_count;
+use crate::world::block_states::BlockState;
+use crate::world::blocks::pack_indices;
+
+/// What a [PalettedContainer] holds, which decides how big it is and how it's sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    /// A section's 16x16x16 blocks.
+    BlockStates,
+    /// A section's 4x4x4 biomes.
+    Biomes,
+}
+
+impl ContainerKind {
+    /// How many entries the container has.
+    pub fn entries(self) -> usize {
+        match self {
+            ContainerKind::BlockStates => 4096,
+            ContainerKind::Biomes => 64,
+        }
+    }
+
+    /// The fewest bits an entry takes once there's more than one value.
+    fn min_bits(self) -> usize {
+        match self {
+            ContainerKind::BlockStates => 4,
+            ContainerKind::Biomes => 1,
+        }
+    }
+
+    /// Palettes that need more bits per entry than this are sent as global ids instead.
+    fn max_indirect_bits(self) -> usize {
+        match self {
+            ContainerKind::BlockStates => 8,
+            ContainerKind::Biomes => 3,
+        }
+    }
+
+    /// How many bits each entry takes when it's sent as its global id.
+    fn direct_bits(self) -> usize {
+        match self {
+            ContainerKind::BlockStates => 15,
+            ContainerKind::Biomes => bits_for(biome_count()),
+        }
+    }
+
+    /// How many bits an entry takes with `len` values in the palette. 0 means there's only one,
+    /// and no data at all.
+    fn bits_for_palette(self, len: usize) -> usize {
+        match len {
+            0 | 1 => 0,
+            len => bits_for(len).max(self.min_bits()),
+        }
+    }
+
+    /// How many longs the data takes with `bits` bits per entry. Entries don't span across
+    /// longs, so any bits left over at the top of each long are padding.
+    fn data_len(self, bits: usize) -> usize {
+        match bits {
+            0 => 0,
+            bits => self.entries().div_ceil(64 / bits),
+        }
+    }
+}
+
+/// Anything a [PalettedContainer] can hold, which has to have a global id to send it by.
+pub trait GlobalId: Copy + Eq {
+    fn global_id(self) -> i32;
+}
+
+/// Global ids as they are, e.g. the ids in a section's network palette.
+impl GlobalId for i32 {
+    fn global_id(self) -> i32 {
+        self
+    }
+}
+
+impl GlobalId for BlockState {
+    fn global_id(self) -> i32 {
+        self.id() as i32
+    }
+}
+
+/// A section's blocks or biomes, as a palette of the values in it and each entry's index into
+/// the palette, packed into longs.
+///
+/// The palette only ever grows, and the indices take more bits as it does. It's sent the way the
+/// Chunk Data packet has it:
+///
+/// - Single value: Only one value in the palette, and no data.
+/// - Indirect: The palette, then the indices, with at least 4 bits each for blocks.
+/// - Direct: With more than 8 bits for blocks or 3 for biomes, no palette, and every entry as
+///   its global id instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PalettedContainer<T> {
+    kind: ContainerKind,
+    palette: Vec<T>,
+    /// 0 while there's only one value in the palette.
+    bits: usize,
+    data: Vec<i64>,
+}
+
+impl<T: GlobalId> PalettedContainer<T> {
+    /// A container that's all `value`.
+    pub fn new(kind: ContainerKind, value: T) -> Self {
+        Self {
+            kind,
+            palette: vec![value],
+            bits: 0,
+            data: Vec::new(),
+        }
+    }
+
+    /// A container from a palette and the packed indices into it, the way sections store them.
+    /// `data` can only be left out if there's just one value in the palette.
+    pub fn from_parts(
+        kind: ContainerKind,
+        palette: Vec<T>,
+        data: Option<Vec<i64>>,
+    ) -> Result<Self> {
+        if palette.is_empty() {
+            return Err(Error::Generic(
+                "Paletted container has no palette".to_string(),
+            ));
+        }
+        let bits = kind.bits_for_palette(palette.len());
+        let data = match data {
+            _ if bits == 0 => Vec::new(),
+            Some(data) if data.len() == kind.data_len(bits) => data,
+            Some(data) => {
+                return Err(Error::Generic(format!(
+                    "Paletted container has {} longs of data, but a palette of {} needs {}",
+                    data.len(),
+                    palette.len(),
+                    kind.data_len(bits)
+                )))
+            }
+            None => {
+                return Err(Error::Generic(format!(
+                    "Paletted container with a palette of {} has no data",
+                    palette.len()
+                )))
+            }
+        };
+        let container = Self {
+            kind,
+            palette,
+            bits,
+            data,
+        };
+        if let Some(index) =
+            (0..kind.entries()).find(|&i| container.palette_index(i) >= container.palette.len())
+        {
+            return Err(Error::Generic(format!(
+                "Paletted container entry {} is outside its palette",
+                index
+            )));
+        }
+        Ok(container)
+    }
+
+    pub fn kind(&self) -> ContainerKind {
+        self.kind
+    }
+
+    pub fn palette(&self) -> &[T] {
+        &self.palette
+    }
+
+    /// How many bits each index takes, 0 if there's only one value.
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// The packed indices into the palette, or `None` if there's only one value.
+    pub fn data(&self) -> Option<&[i64]> {
+        (self.bits > 0).then_some(self.data.as_slice())
+    }
+
+    /// Whether it's sent as global ids rather than with its palette.
+    pub fn is_direct(&self) -> bool {
+        self.bits > self.kind.max_indirect_bits()
+    }
+
+    /// The value at `index`, where blocks are in y, then z, then x order.
+    pub fn get(&self, index: usize) -> T {
+        self.palette[self.palette_index(index)]
+    }
+
+    /// Sets the value at `index`, adding it to the palette and making room for it if it's
+    /// new. Returns the value that was there.
+    pub fn set(&mut self, index: usize, value: T) -> T {
+        assert!(
+            index < self.kind.entries(),
+            "Entry {} is out of range",
+            index
+        );
+        let palette_index = match self.palette.iter().position(|&known| known == value) {
+            Some(palette_index) => palette_index,
+            None => {
+                self.palette.push(value);
+                let bits = self.kind.bits_for_palette(self.palette.len());
+                if bits != self.bits {
+                    self.resize(bits);
+                }
+                self.palette.len() - 1
+            }
+        };
+        let old = self.get(index);
+        if self.bits > 0 {
+            let (long, shift) = self.position(index);
+            let mask = (1u64 << self.bits) - 1;
+            let packed = self.data[long] as u64 & !(mask << shift);
+            self.data[long] = (packed | (palette_index as u64) << shift) as i64;
+        }
+        old
+    }
+
+    /// Every value, in the same order as the indices.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        (0..self.kind.entries()).map(|index| self.get(index))
+    }
+
+    /// Where the value at `index` is in the palette.
+    pub fn palette_index(&self, index: usize) -> usize {
+        if self.bits == 0 {
+            return 0;
+        }
+        let (long, shift) = self.position(index);
+        ((self.data[long] as u64 >> shift) & ((1u64 << self.bits) - 1)) as usize
+    }
+
+    /// Which long the entry at `index` is in, and how far up it.
+    fn position(&self, index: usize) -> (usize, usize) {
+        let per_long = 64 / self.bits;
+        (index / per_long, (index % per_long) * self.bits)
+    }
+
+    /// Repacks every index with `bits` bits each.
+    fn resize(&mut self, bits: usize) {
+        let indices = (0..self.kind.entries())
+            .map(|index| self.palette_index(index) as u16)
+            .collect::<Vec<_>>();
+        self.bits = bits;
+        self.data = pack_indices(&indices, bits);
+    }
+}
+
+impl<T: GlobalId> NetEncode for PalettedContainer<T> {
+    async fn net_encode<W>(&self, writer: &mut W) -> ferrumc_codec::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        if self.bits == 0 {
+            0u8.net_encode(writer).await?;
+            VarInt::from(self.palette[0].global_id())
+                .net_encode(writer)
+                .await?;
+            return VarInt::from(0).net_encode(writer).await;
+        }
+
+        if !self.is_direct() {
+            (self.bits as u8).net_encode(writer).await?;
+            VarInt::from(self.palette.len() as i32)
+                .net_encode(writer)
+                .await?;
+            for value in &self.palette {
+                VarInt::from(value.global_id()).net_encode(writer).await?;
+            }
+            VarInt::from(self.data.len() as i32)
+                .net_encode(writer)
+                .await?;
+            return self.data.net_encode(writer).await;
+        }
+
+        let bits = self.kind.direct_bits();
+        let ids = self
+            .iter()
+            .map(|value| value.global_id() as u16)
+            .collect::<Vec<_>>();
+        let data = pack_indices(&ids, bits);
+        (bits as u8).net_encode(writer).await?;
+        VarInt::from(data.len() as i32).net_encode(writer).await?;
+        data.net_encode(writer).await
+    }
+}
+
+/// Where the entry at `index` is in the palette of a container stored as a palette of
+/// `palette_len` values and `data`, the way sections store them. Reads just that entry, without
+/// checking the rest like [PalettedContainer::from_parts] does. `None` if `data` is too short.
+pub fn read_palette_index(
+    kind: ContainerKind,
+    palette_len: usize,
+    data: Option<&[i64]>,
+    index: usize,
+) -> Option<usize> {
+    let bits = kind.bits_for_palette(palette_len);
+    if bits == 0 {
+        return Some(0);
+    }
+    let per_long = 64 / bits;
+    let long = *data?.get(index / per_long)? as u64;
+    Some(((long >> ((index % per_long) * bits)) & ((1u64 << bits) - 1)) as usize)
+}
+
+/// How many bits it takes to tell `len` values apart.
+pub fn bits_for(len: usize) -> usize {
+    (len as f32).log2().ceil() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::blocks::unpack_indices;
+
+    fn stone() -> BlockState {
+        BlockState::default_state("minecraft:stone").unwrap()
+    }
+
+    async fn encoded<T: GlobalId>(container: &PalettedContainer<T>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        container.net_encode(&mut bytes).await.unwrap();
+        bytes
+    }
+
+    fn longs(bytes: &[u8]) -> Vec<i64> {
+        bytes
+            .chunks(8)
+            .map(|long| i64::from_be_bytes(long.try_into().unwrap()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_single_value() {
+        let container = PalettedContainer::new(ContainerKind::BlockStates, stone());
+        assert!(container.iter().all(|block| block == stone()));
+        assert_eq!(container.data(), None);
+        assert_eq!(encoded(&container).await, vec![0x00, 0x01, 0x00]);
+
+        // Setting what's already there doesn't need any data.
+        let mut container = container;
+        assert_eq!(container.set(1234, stone()), stone());
+        assert_eq!(container.bits(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_indirect() {
+        let mut container = PalettedContainer::new(ContainerKind::BlockStates, BlockState::AIR);
+        assert_eq!(container.set(0, stone()), BlockState::AIR);
+        assert_eq!(container.bits(), 4);
+        assert_eq!(container.get(0), stone());
+        assert_eq!(container.get(1), BlockState::AIR);
+
+        let bytes = encoded(&container).await;
+        // 4 bits, air and stone, then 256 longs with stone first.
+        assert_eq!(bytes[..6], [0x04, 0x02, 0x00, 0x01, 0x80, 0x02]);
+        assert_eq!(bytes.len(), 6 + 256 * 8);
+        assert_eq!(longs(&bytes[6..])[0], 1);
+    }
+
+    #[tokio::test]
+    async fn test_seventeen_blocks_need_five_bits() {
+        let mut container = PalettedContainer::new(ContainerKind::BlockStates, 0i32);
+        for (index, id) in (1..=16).enumerate() {
+            container.set(index * 200, id);
+        }
+        assert_eq!(container.palette().len(), 17);
+        assert_eq!(container.bits(), 5);
+        for (index, id) in (1..=16).enumerate() {
+            assert_eq!(container.get(index * 200), id);
+            assert_eq!(container.get(index * 200 + 1), 0);
+        }
+
+        let bytes = encoded(&container).await;
+        // 12 entries to a long at 5 bits, so 342 longs.
+        assert_eq!(bytes[..2], [0x05, 17]);
+        assert_eq!(bytes[2..19], (0..17).collect::<Vec<u8>>()[..]);
+        assert_eq!(bytes[19..21], [0xD6, 0x02]);
+        let indices = unpack_indices(&longs(&bytes[21..]), 5);
+        assert_eq!(indices[200], 2);
+        assert_eq!(indices[3000], 16);
+    }
+
+    #[tokio::test]
+    async fn test_direct() {
+        // More blocks than fit in 8 bits, all different ids, so they're sent as global ids.
+        let mut container = PalettedContainer::new(ContainerKind::BlockStates, 0i32);
+        for index in 0..300 {
+            container.set(index, 1000 + index as i32);
+        }
+        assert_eq!(container.bits(), 9);
+        assert!(container.is_direct());
+
+        let bytes = encoded(&container).await;
+        // 4 entries to a long at 15 bits, so 1024 longs.
+        assert_eq!(bytes[..3], [15, 0x80, 0x08]);
+        let ids = unpack_indices(&longs(&bytes[3..]), 15);
+        assert_eq!(ids[0], 1000);
+        assert_eq!(ids[299], 1299);
+        assert_eq!(ids[300], 0);
+    }
+
+    #[tokio::test]
+    async fn test_biomes() {
+        let mut container = PalettedContainer::new(ContainerKind::Biomes, 39);
+        container.set(63, 14);
+        assert_eq!(container.bits(), 1);
+        let bytes = encoded(&container).await;
+        assert_eq!(bytes[..5], [0x01, 0x02, 39, 14, 0x01]);
+        assert_eq!(longs(&bytes[5..]), vec![i64::MIN]);
+
+        // Biomes go direct after 8.
+        for id in 0..8 {
+            container.set(id as usize, id);
+        }
+        assert_eq!(container.bits(), 4);
+        assert_eq!(encoded(&container).await[..2], [0x06, 0x07]);
+    }
+
+    #[test]
+    fn test_from_parts() {
+        let container = PalettedContainer::from_parts(
+            ContainerKind::Biomes,
+            vec![1, 2, 3],
+            Some(vec![0b10_01; 2]),
+        )
+        .unwrap();
+        assert_eq!(container.bits(), 2);
+        assert_eq!(container.get(0), 2);
+        assert_eq!(container.get(1), 3);
+        assert_eq!(container.get(2), 1);
+
+        // Too little data, or indices past the end of the palette.
+        assert!(
+            PalettedContainer::from_parts(ContainerKind::Biomes, vec![1, 2], Some(vec![])).is_err()
+        );
+        assert!(PalettedContainer::from_parts(
+            ContainerKind::Biomes,
+            vec![1, 2, 3],
+            Some(vec![-1; 2])
+        )
+        .is_err());
+        assert!(PalettedContainer::<i32>::from_parts(ContainerKind::Biomes, vec![], None).is_err());
+    }
+}
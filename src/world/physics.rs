@@ -0,0 +1,347 @@
+use std::ops::RangeInclusive;
+
+use crate::utils::components::physics_body::PhysicsBody;
+use crate::utils::components::velocity::Velocity;
+use crate::utils::encoding::position::Position;
+use crate::world::block_states::BlockState;
+use crate::world::chunk_format::Palette;
+use crate::world::heightmaps::blocks_motion;
+
+/// How much of their horizontal velocity entities on the ground keep every tick, on top of their
+/// drag. Vanilla's for every block but ice, slime and honey.
+pub const GROUND_FRICTION: f64 = 0.6;
+/// Velocities slower than this are stopped, so entities come to rest instead of creeping along.
+const MIN_VELOCITY: f64 = 0.003;
+/// How far a hitbox can be into a block without counting as in it, so rounding errors don't get
+/// entities stuck on blocks they're only touching.
+const EPSILON: f64 = 1e-7;
+
+/// Whether entities bump into `block`. They go through plants and the like, and into water and
+/// lava.
+pub fn is_solid(block: &Palette) -> bool {
+    BlockState::from_palette(block).is_some_and(|state| {
+        blocks_motion(state) && !matches!(state.name(), "minecraft:water" | "minecraft:lava")
+    })
+}
+
+/// A hitbox, from its lowest corner to its highest. Axes are in x, y, z order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl BoundingBox {
+    pub fn of(body: &PhysicsBody) -> Self {
+        let (x, y, z) = body.position;
+        let half_width = body.width / 2.0;
+        Self {
+            min: [x - half_width, y, z - half_width],
+            max: [x + half_width, y + body.height, z + half_width],
+        }
+    }
+
+    /// The whole of the block at `position`.
+    pub fn block(position: &Position) -> Self {
+        let (x, y, z) = (position.x as f64, position.y as f64, position.z as f64);
+        Self {
+            min: [x, y, z],
+            max: [x + 1.0, y + 1.0, z + 1.0],
+        }
+    }
+
+    /// The box grown to cover everywhere it goes while moving by `movement`.
+    pub fn expanded_by(mut self, (x, y, z): (f64, f64, f64)) -> Self {
+        for (axis, distance) in [x, y, z].into_iter().enumerate() {
+            if distance < 0.0 {
+                self.min[axis] += distance;
+            } else {
+                self.max[axis] += distance;
+            }
+        }
+        self
+    }
+
+    /// The box grown by `x`, `y` and `z` on both sides.
+    pub fn grown(mut self, (x, y, z): (f64, f64, f64)) -> Self {
+        for (axis, distance) in [x, y, z].into_iter().enumerate() {
+            self.min[axis] -= distance;
+            self.max[axis] += distance;
+        }
+        self
+    }
+
+    /// Whether the two boxes overlap. Boxes that are only touching don't.
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        (0..3).all(|axis| self.min[axis] < other.max[axis] && other.min[axis] < self.max[axis])
+    }
+
+    /// Every block the box is in, as x, y, z. Blocks it's only touching don't count.
+    pub fn blocks(&self) -> impl Iterator<Item = (i32, i32, i32)> {
+        let (ys, zs) = (self.blocks_along(1), self.blocks_along(2));
+        self.blocks_along(0).flat_map(move |x| {
+            let zs = zs.clone();
+            ys.clone()
+                .flat_map(move |y| zs.clone().map(move |z| (x, y, z)))
+        })
+    }
+
+    fn moved(mut self, axis: usize, distance: f64) -> Self {
+        self.min[axis] += distance;
+        self.max[axis] += distance;
+        self
+    }
+
+    /// The blocks the box is in along `axis`. Blocks it's only touching don't count.
+    fn blocks_along(&self, axis: usize) -> RangeInclusive<i32> {
+        let first = (self.min[axis] + EPSILON).floor() as i32;
+        let last = (self.max[axis] - EPSILON).ceil() as i32 - 1;
+        first..=last
+    }
+}
+
+/// How far a hitbox at `bounds` gets when it tries to move by `movement`, stopping against the
+/// first solid block in the way on each axis. `is_solid` says whether the block at `x`, `y`, `z`
+/// is, see [is_solid].
+///
+/// Up and down goes first, like vanilla, so a falling entity lands before it slides along the
+/// ground. Every block along the way is checked, so nothing falls through the floor however fast
+/// it's going.
+///
+/// Solid blocks are all taken to fill their whole block, since their shapes aren't known, so
+/// entities rest on top of slabs, stairs, fences and the like as if they were full cubes.
+pub fn collide(
+    bounds: BoundingBox,
+    movement: (f64, f64, f64),
+    is_solid: impl Fn(i32, i32, i32) -> bool,
+) -> (f64, f64, f64) {
+    let wanted = [movement.0, movement.1, movement.2];
+    let mut bounds = bounds;
+    let mut moved = [0.0; 3];
+    for axis in [1, 0, 2] {
+        moved[axis] = clip(&bounds, axis, wanted[axis], &is_solid);
+        bounds = bounds.moved(axis, moved[axis]);
+    }
+    (moved[0], moved[1], moved[2])
+}
+
+/// How far `bounds` can go along `axis`, up to `distance`, before it runs into a solid block.
+fn clip(
+    bounds: &BoundingBox,
+    axis: usize,
+    distance: f64,
+    is_solid: &impl Fn(i32, i32, i32) -> bool,
+) -> f64 {
+    let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+    // Whether any block in the layer the box would move into is solid.
+    let layer_is_solid = |layer: i32| {
+        bounds.blocks_along(a).any(|i| {
+            bounds.blocks_along(b).any(|j| {
+                let mut block = [0; 3];
+                block[axis] = layer;
+                block[a] = i;
+                block[b] = j;
+                is_solid(block[0], block[1], block[2])
+            })
+        })
+    };
+
+    if distance > 0.0 {
+        let edge = bounds.max[axis];
+        let first = (edge - EPSILON).ceil() as i32;
+        let end = (edge + distance).ceil() as i32;
+        match (first..end).find(|&layer| layer_is_solid(layer)) {
+            Some(layer) => (layer as f64 - edge).max(0.0),
+            None => distance,
+        }
+    } else if distance < 0.0 {
+        let edge = bounds.min[axis];
+        let first = (edge + EPSILON).floor() as i32 - 1;
+        let last = (edge + distance).floor() as i32;
+        match (last..=first).rev().find(|&layer| layer_is_solid(layer)) {
+            Some(layer) => ((layer + 1) as f64 - edge).min(0.0),
+            None => distance,
+        }
+    } else {
+        0.0
+    }
+}
+
+/// Moves `body` along by `velocity` for one tick: it falls, stops against whatever solid blocks
+/// are in the way, and slows down, faster when it's on the ground. See [collide] for `is_solid`.
+pub fn step(
+    body: &mut PhysicsBody,
+    velocity: &mut Velocity,
+    is_solid: impl Fn(i32, i32, i32) -> bool,
+) {
+    velocity.y -= body.gravity;
+    let wanted = (velocity.x, velocity.y, velocity.z);
+    let moved = collide(BoundingBox::of(body), wanted, is_solid);
+    body.position.0 += moved.0;
+    body.position.1 += moved.1;
+    body.position.2 += moved.2;
+    body.on_ground = wanted.1 < 0.0 && moved.1 != wanted.1;
+
+    // Whatever it bumped into stopped it on that axis.
+    if moved.0 != wanted.0 {
+        velocity.x = 0.0;
+    }
+    if moved.1 != wanted.1 {
+        velocity.y = 0.0;
+    }
+    if moved.2 != wanted.2 {
+        velocity.z = 0.0;
+    }
+
+    let horizontal_drag = match body.on_ground {
+        true => body.drag * GROUND_FRICTION,
+        false => body.drag,
+    };
+    velocity.x *= horizontal_drag;
+    velocity.y *= body.drag;
+    velocity.z *= horizontal_drag;
+    for axis in [&mut velocity.x, &mut velocity.y, &mut velocity.z] {
+        if axis.abs() < MIN_VELOCITY {
+            *axis = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} isn't {}",
+            actual,
+            expected
+        );
+    }
+
+    fn item_at(x: f64, y: f64, z: f64) -> BoundingBox {
+        BoundingBox::of(&PhysicsBody::item((x, y, z)))
+    }
+
+    fn only(block: (i32, i32, i32)) -> impl Fn(i32, i32, i32) -> bool {
+        move |x, y, z| (x, y, z) == block
+    }
+
+    #[test]
+    fn test_falling_stops_on_the_block_below() {
+        // 0.3 above the top of the block at y 64, trying to fall 0.5.
+        let (_, dy, _) = collide(item_at(0.5, 65.3, 0.5), (0.0, -0.5, 0.0), only((0, 64, 0)));
+        assert_close(dy, -0.3);
+        assert_eq!(65.3 + dy, 65.0);
+
+        // Falling as fast as it can still stops at the first solid block, not the one past it.
+        let (_, dy, _) = collide(item_at(0.5, 70.2, 0.5), (0.0, -3.9, 0.0), only((0, 66, 0)));
+        assert_eq!(70.2 + dy, 67.0);
+
+        // Already resting on it, it doesn't go anywhere.
+        let (_, dy, _) = collide(item_at(0.5, 65.0, 0.5), (0.0, -0.04, 0.0), only((0, 64, 0)));
+        assert_eq!(dy, 0.0);
+
+        // With nothing below, it falls the whole way.
+        let (_, dy, _) = collide(item_at(0.5, 65.0, 0.5), (0.0, -0.5, 0.0), only((0, 60, 0)));
+        assert_eq!(dy, -0.5);
+    }
+
+    #[test]
+    fn test_hanging_over_an_edge() {
+        let ledge = |x, y, _| x == 0 && y == 64;
+
+        // From 0.975 to 1.225, so still partly over the block at x 0.
+        let (_, dy, _) = collide(item_at(1.1, 65.0, 0.5), (0.0, -0.04, 0.0), ledge);
+        assert_eq!(dy, 0.0);
+        // From 1.075, entirely past it.
+        let (_, dy, _) = collide(item_at(1.2, 65.0, 0.5), (0.0, -0.04, 0.0), ledge);
+        assert_eq!(dy, -0.04);
+        // Touching the side of the block isn't standing on it.
+        let (_, dy, _) = collide(item_at(1.125, 65.0, 0.5), (0.0, -0.04, 0.0), ledge);
+        assert_eq!(dy, -0.04);
+    }
+
+    #[test]
+    fn test_walls_stop_sideways_movement() {
+        let wall = |x, y, _| x == 1 && y == 65;
+
+        // Its side is at 0.625, so it only gets 0.375 of the way.
+        let (dx, _, _) = collide(item_at(0.5, 65.0, 0.5), (1.0, 0.0, 0.0), wall);
+        assert_eq!(0.5 + dx, 0.875);
+        // Going the other way, or over the top of it, isn't stopped.
+        let (dx, _, _) = collide(item_at(0.5, 65.0, 0.5), (-1.0, 0.0, 0.0), wall);
+        assert_eq!(dx, -1.0);
+        let (dx, _, _) = collide(item_at(0.5, 66.0, 0.5), (1.0, 0.0, 0.0), wall);
+        assert_eq!(dx, 1.0);
+    }
+
+    #[test]
+    fn test_step() {
+        let floor = |_, y, _| y == 64;
+
+        // Sliding along the ground: gravity is cancelled out by the floor, and friction slows it
+        // to 0.1 * 0.98 * 0.6.
+        let mut body = PhysicsBody::item((0.5, 65.0, 0.5));
+        let mut velocity = Velocity::new(0.1, 0.0, 0.0);
+        step(&mut body, &mut velocity, floor);
+        assert_close(body.position.0, 0.6);
+        assert_eq!(body.position.1, 65.0);
+        assert!(body.on_ground);
+        assert_close(velocity.x, 0.0588);
+        assert_eq!(velocity.y, 0.0);
+
+        // Falling: 0.04 down, and then the drag takes it to 0.04 * 0.98.
+        let mut body = PhysicsBody::item((0.5, 70.0, 0.5));
+        let mut velocity = Velocity::default();
+        step(&mut body, &mut velocity, floor);
+        assert_close(body.position.1, 69.96);
+        assert!(!body.on_ground);
+        assert_close(velocity.y, -0.0392);
+
+        // Slower than 0.003 once friction is done with it, so it stops.
+        let mut body = PhysicsBody::item((0.5, 65.0, 0.5));
+        let mut velocity = Velocity::new(0.003, 0.0, 0.0);
+        step(&mut body, &mut velocity, floor);
+        assert!(velocity.is_zero());
+    }
+
+    #[test]
+    fn test_landing_stops_the_fall() {
+        let mut body = PhysicsBody::item((0.5, 65.3, 0.5));
+        let mut velocity = Velocity::new(0.0, -0.46, 0.0);
+        step(&mut body, &mut velocity, |_, y, _| y == 64);
+        assert_eq!(body.position.1, 65.0);
+        assert!(body.on_ground);
+        assert_eq!(velocity.y, 0.0);
+    }
+
+    #[test]
+    fn test_boxes_intersect() {
+        let item = item_at(0.5, 64.0, 0.5);
+        assert!(item.intersects(&item_at(0.7, 64.2, 0.5)));
+        // Side by side, touching.
+        assert!(!item.intersects(&item_at(0.75, 64.0, 0.5)));
+        assert!(item
+            .grown((0.1, 0.0, 0.0))
+            .intersects(&item_at(0.75, 64.0, 0.5)));
+        assert_eq!(
+            item.grown((1.0, 0.5, 1.0)),
+            BoundingBox {
+                min: [-0.625, 63.5, -0.625],
+                max: [1.625, 64.75, 1.625],
+            }
+        );
+    }
+
+    #[test]
+    fn test_fluids_and_plants_are_not_solid() {
+        let block = |name: &str| BlockState::default_state(name).unwrap().to_palette();
+        assert!(is_solid(&block("minecraft:stone")));
+        assert!(is_solid(&block("minecraft:oak_leaves")));
+        assert!(!is_solid(&block("minecraft:air")));
+        assert!(!is_solid(&block("minecraft:water")));
+        assert!(!is_solid(&block("minecraft:poppy")));
+    }
+}
@@ -0,0 +1,76 @@
+use tracing::debug;
+
+use crate::state::ServerState;
+use crate::utils::prelude::*;
+
+impl ServerState {
+    /// Saves everything about the world that's kept in memory: changed chunks, the time, the
+    /// weather, the border and the players that are online, then flushes the database to disk.
+    /// Run every `database.save_interval` seconds and on shutdown. Returns how many chunks were
+    /// saved.
+    pub async fn save_all(&self) -> Result<usize> {
+        let chunks = self.database.save_chunks().await?;
+        self.save_time().await?;
+        self.save_weather().await?;
+        self.save_border().await?;
+        let players = self.save_players().await?;
+        self.database.sync().await?;
+        debug!(
+            "Saved the world, with {} changed chunks and {} players",
+            chunks, players
+        );
+        Ok(chunks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use crate::create_state;
+    use crate::utils::encoding::position::Position;
+    use crate::world::conversions::default_block_state;
+
+    #[tokio::test]
+    async fn test_changes_survive_saving() {
+        let state = create_state(vec![TcpListener::bind("127.0.0.1:0").await.unwrap()])
+            .await
+            .unwrap();
+        let overworld = state.dimensions.overworld();
+        let (chunk_x, chunk_z) = (-4_321, 8_765);
+        let chunk = overworld.generator.generate(chunk_x, chunk_z);
+        state.database.insert_chunk(chunk).await.unwrap();
+
+        let location = Position {
+            x: chunk_x * 16 + 3,
+            z: chunk_z * 16 + 9,
+            y: 200,
+        };
+        let stone = default_block_state("minecraft:stone").unwrap();
+        state
+            .set_block(overworld, &location, stone.clone())
+            .await
+            .unwrap();
+        assert!(state.database.unsaved_chunks() >= 1);
+
+        // Until it's saved, the change can't be dropped from memory.
+        state
+            .database
+            .evict_chunk_from_cache(chunk_x, chunk_z, overworld.key.clone())
+            .await;
+        assert_eq!(
+            state.get_block(overworld, &location).await.unwrap(),
+            Some(stone.clone())
+        );
+
+        assert!(state.save_all().await.unwrap() >= 1);
+        state
+            .database
+            .evict_chunk_from_cache(chunk_x, chunk_z, overworld.key.clone())
+            .await;
+        assert_eq!(
+            state.get_block(overworld, &location).await.unwrap(),
+            Some(stone)
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use std::sync::Mutex;
+
+use crate::net::packets::outgoing::update_time::UpdateTime;
+use crate::net::packets::ConnectionId;
+use crate::state::ServerState;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+/// How many ticks a full day and night takes.
+pub const DAY_LENGTH: i64 = 24000;
+/// What the world info table saves the overworld's time under.
+const TIME_KEY: &str = "time";
+
+/// What the world info table saves the time of the dimension whose chunks are saved under `key`
+/// under. The overworld's is where it was before there were other dimensions.
+fn time_key(key: &str) -> String {
+    match key {
+        "overworld" => TIME_KEY.to_string(),
+        _ => format!("{}:{}", TIME_KEY, key),
+    }
+}
+
+/// The world's time, in ticks.
+///
+/// - `age`: How long the world has been running. Always goes up, even with the daylight cycle
+///   stopped.
+/// - `time_of_day`: Where the sun and moon are. Goes past [DAY_LENGTH] instead of wrapping, so
+///   the client can tell how many days have gone by, e.g. for the moon's phase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorldTime {
+    pub age: i64,
+    pub time_of_day: i64,
+}
+
+impl WorldTime {
+    /// Moves the time on by one tick.
+    pub fn tick(&mut self, daylight_cycle: bool) {
+        self.age += 1;
+        if daylight_cycle {
+            self.time_of_day += 1;
+        }
+    }
+
+    /// The time of day as the client expects it. It's negative if the daylight cycle is stopped,
+    /// which tells the client not to move the sun itself. Since -0 isn't negative, a stopped
+    /// cycle at exactly 0 is sent as -1.
+    pub fn time_of_day_for_client(&self, daylight_cycle: bool) -> i64 {
+        match (daylight_cycle, self.time_of_day) {
+            (true, time) => time,
+            (false, 0) => -1,
+            (false, time) => -time.abs(),
+        }
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        [self.age.to_le_bytes(), self.time_of_day.to_le_bytes()].concat()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let age = bytes.get(..8)?.try_into().ok()?;
+        let time_of_day = bytes.get(8..16)?.try_into().ok()?;
+        Some(Self {
+            age: i64::from_le_bytes(age),
+            time_of_day: i64::from_le_bytes(time_of_day),
+        })
+    }
+}
+
+/// [WorldTime] that can be shared between tasks.
+#[derive(Debug, Default)]
+pub struct SharedWorldTime(Mutex<WorldTime>);
+
+impl SharedWorldTime {
+    pub fn new(time: WorldTime) -> Self {
+        Self(Mutex::new(time))
+    }
+
+    pub fn get(&self) -> WorldTime {
+        *self.0.lock().unwrap()
+    }
+
+    /// Moves the time on by one tick, and returns what it is now.
+    pub fn tick(&self, daylight_cycle: bool) -> WorldTime {
+        let mut time = self.0.lock().unwrap();
+        time.tick(daylight_cycle);
+        *time
+    }
+
+    fn set_time_of_day(&self, time_of_day: i64) {
+        self.0.lock().unwrap().time_of_day = time_of_day;
+    }
+}
+
+fn update_time_packet(time: WorldTime) -> UpdateTime {
+    let daylight_cycle = get_global_config().game_rules.do_daylight_cycle;
+    UpdateTime::new(time.age, time.time_of_day_for_client(daylight_cycle))
+}
+
+impl ServerState {
+    /// Sets the time of day in every dimension, e.g. for `/time set`, and tells everyone straight
+    /// away. Negative times would look like a stopped cycle to the client, so they're set to 0.
+    pub async fn set_time(&self, time_of_day: i64) {
+        for dimension in self.dimensions.iter() {
+            dimension.time.set_time_of_day(time_of_day.max(0));
+        }
+        self.broadcast_time().await;
+    }
+
+    /// Sends everyone in the world the current time in the dimension they're in. Players it
+    /// can't be sent to are skipped.
+    pub async fn broadcast_time(&self) {
+        for dimension in self.dimensions.iter() {
+            let time = dimension.time.get();
+            self.send_to_dimension(dimension, || update_time_packet(time))
+                .await;
+        }
+    }
+
+    /// Sends one player the current time in the dimension they're in.
+    pub async fn send_time(&self, conn_id: ConnectionId) -> Result<()> {
+        let time = self.dimension_of(conn_id).await.time.get();
+        let conn = self.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(update_time_packet(time)).await
+    }
+
+    /// Saves every dimension's time with the world, so it carries on from there after a restart.
+    pub async fn save_time(&self) -> Result<()> {
+        for dimension in self.dimensions.iter() {
+            self.database
+                .set_world_info(&time_key(&dimension.key), dimension.time.get().to_bytes())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Loads the time saved with the world for the dimension whose chunks are saved under `key`. New
+/// worlds start at dawn.
+pub async fn load_time(database: &crate::database::Database, key: &str) -> Result<WorldTime> {
+    let Some(bytes) = database.get_world_info(&time_key(key)).await? else {
+        return Ok(WorldTime::default());
+    };
+    WorldTime::from_bytes(&bytes)
+        .ok_or_else(|| Error::DatabaseError("The saved world time is corrupted".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_a_stopped_cycle_only_ages_the_world() {
+        let mut time = WorldTime::default();
+        time.tick(true);
+        time.tick(false);
+        assert_eq!(
+            time,
+            WorldTime {
+                age: 2,
+                time_of_day: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_a_stopped_cycle_is_sent_negative() {
+        let time = WorldTime {
+            age: 10,
+            time_of_day: 6000,
+        };
+        assert_eq!(time.time_of_day_for_client(true), 6000);
+        assert_eq!(time.time_of_day_for_client(false), -6000);
+        assert_eq!(WorldTime::default().time_of_day_for_client(false), -1);
+    }
+
+    #[test]
+    fn test_time_round_trips_through_bytes() {
+        let time = WorldTime {
+            age: 123_456_789,
+            time_of_day: 30000,
+        };
+        assert_eq!(WorldTime::from_bytes(&time.to_bytes()), Some(time));
+        assert_eq!(WorldTime::from_bytes(&[1, 2, 3]), None);
+    }
+
+    #[test]
+    fn test_the_overworld_keeps_its_time_key() {
+        assert_eq!(time_key("overworld"), "time");
+        assert_eq!(time_key("the_nether"), "time:the_nether");
+    }
+}
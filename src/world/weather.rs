@@ -0,0 +1,417 @@
+use std::sync::{Arc, Mutex};
+
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::world_events::WeatherChangeEvent;
+use crate::net::packets::outgoing::game_event::{
+    GameEvent, BEGIN_RAINING, END_RAINING, RAIN_LEVEL_CHANGE, THUNDER_LEVEL_CHANGE,
+};
+use crate::net::packets::ConnectionId;
+use crate::state::ServerState;
+use crate::utils::prelude::*;
+use crate::world::dimensions::Dimension;
+
+/// What the world info table saves the overworld's weather under.
+const WEATHER_KEY: &str = "weather";
+/// How much rain and thunder fade in or out by each tick, same as vanilla.
+const FADE_PER_TICK: f32 = 0.01;
+/// How likely rain is to turn into a thunderstorm once it's run out, instead of clearing up.
+const THUNDER_CHANCE: f64 = 0.25;
+
+/// What the world info table saves the weather of the dimension whose chunks are saved under
+/// `key` under, like [crate::world::time]'s keys.
+fn weather_key(key: &str) -> String {
+    match key {
+        "overworld" => WEATHER_KEY.to_string(),
+        _ => format!("{}:{}", WEATHER_KEY, key),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Weather {
+    #[default]
+    Clear,
+    Rain,
+    /// Rain, with thunder.
+    Thunder,
+}
+
+impl Weather {
+    pub fn is_raining(self) -> bool {
+        self != Weather::Clear
+    }
+
+    /// How many ticks a spell of this weather lasts, picked at random from the same ranges as
+    /// vanilla's.
+    pub fn random_duration(self) -> u32 {
+        let (min, max) = match self {
+            Weather::Clear => (12000, 180000),
+            Weather::Rain => (12000, 24000),
+            Weather::Thunder => (3600, 15600),
+        };
+        min + rand::random::<u32>() % (max - min)
+    }
+
+    /// What this weather turns into once it runs out. `roll` is a random number from 0 to 1,
+    /// which decides whether rain turns into a thunderstorm, see [THUNDER_CHANCE].
+    fn next(self, roll: f64) -> Weather {
+        match self {
+            Weather::Clear => Weather::Rain,
+            Weather::Rain if roll < THUNDER_CHANCE => Weather::Thunder,
+            Weather::Rain | Weather::Thunder => Weather::Clear,
+        }
+    }
+
+    fn id(self) -> u8 {
+        match self {
+            Weather::Clear => 0,
+            Weather::Rain => 1,
+            Weather::Thunder => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Weather::Clear),
+            1 => Some(Weather::Rain),
+            2 => Some(Weather::Thunder),
+            _ => None,
+        }
+    }
+}
+
+/// A dimension's weather.
+///
+/// - `weather`: What it is now.
+/// - `remaining`: How many more ticks it lasts before it turns into the next, see
+///   [Weather::random_duration].
+/// - `rain_level`, `thunder_level`: How hard it's raining and thundering, from 0 to 1. They fade
+///   towards what `weather` calls for, so rain doesn't start or stop all at once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldWeather {
+    pub weather: Weather,
+    pub remaining: u32,
+    pub rain_level: f32,
+    pub thunder_level: f32,
+}
+
+impl Default for WorldWeather {
+    /// Clear skies for a random while, like new worlds start with.
+    fn default() -> Self {
+        Self::new(Weather::Clear, Weather::Clear.random_duration())
+    }
+}
+
+impl WorldWeather {
+    /// `weather` for `remaining` ticks, already at full strength.
+    pub fn new(weather: Weather, remaining: u32) -> Self {
+        let mut world_weather = Self {
+            weather,
+            remaining,
+            rain_level: 0.0,
+            thunder_level: 0.0,
+        };
+        (world_weather.rain_level, world_weather.thunder_level) = world_weather.target_levels();
+        world_weather
+    }
+
+    /// Moves the weather on by one tick. It only runs out and turns into the next weather while
+    /// `weather_cycle` is on, but rain and thunder always carry on fading.
+    pub fn tick(&mut self, weather_cycle: bool) {
+        if weather_cycle {
+            self.remaining = self.remaining.saturating_sub(1);
+            if self.remaining == 0 {
+                let next = self.weather.next(rand::random());
+                self.set(next, next.random_duration());
+            }
+        }
+        let (rain, thunder) = self.target_levels();
+        self.rain_level = fade(self.rain_level, rain);
+        self.thunder_level = fade(self.thunder_level, thunder);
+    }
+
+    /// Changes the weather to `weather` for `duration` ticks. It fades in like any other change.
+    pub fn set(&mut self, weather: Weather, duration: u32) {
+        self.weather = weather;
+        self.remaining = duration;
+    }
+
+    /// What `rain_level` and `thunder_level` fade towards.
+    fn target_levels(&self) -> (f32, f32) {
+        match self.weather {
+            Weather::Clear => (0.0, 0.0),
+            Weather::Rain => (1.0, 0.0),
+            Weather::Thunder => (1.0, 1.0),
+        }
+    }
+
+    /// The Game Events that tell a client that saw `self` it's now `after`.
+    fn changes_to(&self, after: &WorldWeather) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        if self.weather.is_raining() != after.weather.is_raining() {
+            events.push(match after.weather.is_raining() {
+                true => GameEvent::new(BEGIN_RAINING, 0.0),
+                false => GameEvent::new(END_RAINING, 0.0),
+            });
+        }
+        if self.rain_level != after.rain_level {
+            events.push(GameEvent::new(RAIN_LEVEL_CHANGE, after.rain_level));
+        }
+        if self.thunder_level != after.thunder_level {
+            events.push(GameEvent::new(THUNDER_LEVEL_CHANGE, after.thunder_level));
+        }
+        events
+    }
+
+    /// The Game Events that tell a client that's just come into the dimension what the weather
+    /// is. Clients start out with clear skies, so those need nothing.
+    fn current(&self) -> Vec<GameEvent> {
+        let clear = WorldWeather {
+            weather: Weather::Clear,
+            remaining: 0,
+            rain_level: 0.0,
+            thunder_level: 0.0,
+        };
+        clear.changes_to(self)
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = vec![self.weather.id()];
+        bytes.extend_from_slice(&self.remaining.to_le_bytes());
+        bytes.extend_from_slice(&self.rain_level.to_le_bytes());
+        bytes.extend_from_slice(&self.thunder_level.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let weather = Weather::from_id(*bytes.first()?)?;
+        let remaining = bytes.get(1..5)?.try_into().ok()?;
+        let rain_level = bytes.get(5..9)?.try_into().ok()?;
+        let thunder_level = bytes.get(9..13)?.try_into().ok()?;
+        Some(Self {
+            weather,
+            remaining: u32::from_le_bytes(remaining),
+            rain_level: f32::from_le_bytes(rain_level),
+            thunder_level: f32::from_le_bytes(thunder_level),
+        })
+    }
+}
+
+/// Moves `level` towards `target` by [FADE_PER_TICK], without going past it.
+fn fade(level: f32, target: f32) -> f32 {
+    match level < target {
+        true => (level + FADE_PER_TICK).min(target),
+        false => (level - FADE_PER_TICK).max(target),
+    }
+}
+
+/// [WorldWeather] that can be shared between tasks.
+#[derive(Debug, Default)]
+pub struct SharedWeather(Mutex<WorldWeather>);
+
+impl SharedWeather {
+    pub fn new(weather: WorldWeather) -> Self {
+        Self(Mutex::new(weather))
+    }
+
+    pub fn get(&self) -> WorldWeather {
+        *self.0.lock().unwrap()
+    }
+
+    /// Moves the weather on by one tick, and returns what it was before and what it is now.
+    pub fn tick(&self, weather_cycle: bool) -> (WorldWeather, WorldWeather) {
+        let mut weather = self.0.lock().unwrap();
+        let before = *weather;
+        weather.tick(weather_cycle);
+        (before, *weather)
+    }
+
+    /// Same as [WorldWeather::set], and returns what it was before and what it is now.
+    fn set(&self, weather: Weather, duration: u32) -> (WorldWeather, WorldWeather) {
+        let mut world_weather = self.0.lock().unwrap();
+        let before = *world_weather;
+        world_weather.set(weather, duration);
+        (before, *world_weather)
+    }
+}
+
+impl ServerState {
+    /// Changes the weather in `dimension` to `weather` for `duration` ticks, e.g. for `/weather`.
+    /// A `duration` of 0 picks one at random, like the weather cycle does. It fades in over the
+    /// next ticks, and a [WeatherChangeEvent] is dispatched.
+    pub async fn set_weather(
+        self: &Arc<Self>,
+        dimension: &Arc<Dimension>,
+        weather: Weather,
+        duration: u32,
+    ) {
+        let duration = match duration {
+            0 => weather.random_duration(),
+            duration => duration,
+        };
+        let (before, after) = dimension.weather.set(weather, duration);
+        self.broadcast_weather(dimension, &before, &after).await;
+        self.dispatch_event(WeatherChangeEvent::new(
+            dimension.clone(),
+            before.weather,
+            weather,
+            duration,
+        ))
+        .await;
+    }
+
+    /// Tells everyone in `dimension` about the weather going from `before` to `after`. Players
+    /// it can't be sent to are skipped.
+    pub async fn broadcast_weather(
+        &self,
+        dimension: &Dimension,
+        before: &WorldWeather,
+        after: &WorldWeather,
+    ) {
+        let events = before.changes_to(after);
+        if events.is_empty() {
+            return;
+        }
+        for event in &events {
+            self.send_to_dimension(dimension, || GameEvent::new(event.event, event.value))
+                .await;
+        }
+    }
+
+    /// Sends one player the current weather in the dimension they're in.
+    pub async fn send_weather(&self, conn_id: ConnectionId) -> Result<()> {
+        let weather = self.dimension_of(conn_id).await.weather.get();
+        self.send_weather_events(conn_id, &weather.current()).await
+    }
+
+    async fn send_weather_events(&self, conn_id: ConnectionId, events: &[GameEvent]) -> Result<()> {
+        let conn = self.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        for event in events {
+            conn.send_packet(GameEvent::new(event.event, event.value))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Saves every dimension's weather with the world, so it carries on after a restart.
+    pub async fn save_weather(&self) -> Result<()> {
+        for dimension in self.dimensions.iter() {
+            self.database
+                .set_world_info(
+                    &weather_key(&dimension.key),
+                    dimension.weather.get().to_bytes(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Loads the weather saved with the world for the dimension whose chunks are saved under `key`.
+/// New worlds start out clear.
+pub async fn load_weather(database: &crate::database::Database, key: &str) -> Result<WorldWeather> {
+    let Some(bytes) = database.get_world_info(&weather_key(key)).await? else {
+        return Ok(WorldWeather::default());
+    };
+    WorldWeather::from_bytes(&bytes)
+        .ok_or_else(|| Error::DatabaseError("The saved weather is corrupted".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weather_turns_into_the_next_when_it_runs_out() {
+        let mut weather = WorldWeather::new(Weather::Clear, 2);
+        weather.tick(true);
+        assert_eq!(weather.weather, Weather::Clear);
+        weather.tick(true);
+        assert_eq!(weather.weather, Weather::Rain);
+        assert!((12000..24000).contains(&weather.remaining));
+        // It fades in rather than starting all at once.
+        assert_eq!(weather.rain_level, FADE_PER_TICK);
+
+        assert_eq!(Weather::Rain.next(0.1), Weather::Thunder);
+        assert_eq!(Weather::Rain.next(0.9), Weather::Clear);
+        assert_eq!(Weather::Thunder.next(0.1), Weather::Clear);
+    }
+
+    #[test]
+    fn test_a_stopped_cycle_keeps_the_weather() {
+        let mut weather = WorldWeather::new(Weather::Thunder, 1);
+        weather.tick(false);
+        assert_eq!(weather.weather, Weather::Thunder);
+        assert_eq!(weather.remaining, 1);
+
+        // Clearing it up by hand still fades the rain out.
+        weather.set(Weather::Clear, 100);
+        for _ in 0..50 {
+            weather.tick(false);
+        }
+        assert!((weather.rain_level - 0.5).abs() < 1e-4);
+        for _ in 0..100 {
+            weather.tick(false);
+        }
+        assert_eq!(weather.rain_level, 0.0);
+        assert_eq!(weather.thunder_level, 0.0);
+    }
+
+    #[test]
+    fn test_durations_are_vanilla_like() {
+        for _ in 0..100 {
+            assert!((12000..180000).contains(&Weather::Clear.random_duration()));
+            assert!((3600..15600).contains(&Weather::Thunder.random_duration()));
+        }
+    }
+
+    #[test]
+    fn test_game_events_for_changes() {
+        let ids = |events: Vec<GameEvent>| -> Vec<(u8, f32)> {
+            events
+                .iter()
+                .map(|event| (event.event, event.value))
+                .collect()
+        };
+        let clear = WorldWeather::new(Weather::Clear, 100);
+        assert!(ids(clear.current()).is_empty());
+
+        let thunder = WorldWeather::new(Weather::Thunder, 100);
+        assert_eq!(
+            ids(thunder.current()),
+            vec![
+                (BEGIN_RAINING, 0.0),
+                (RAIN_LEVEL_CHANGE, 1.0),
+                (THUNDER_LEVEL_CHANGE, 1.0)
+            ]
+        );
+        let mut clearing = thunder;
+        clearing.set(Weather::Clear, 100);
+        assert_eq!(ids(thunder.changes_to(&clearing)), vec![(END_RAINING, 0.0)]);
+        clearing.tick(true);
+        let events = ids(thunder.changes_to(&clearing));
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[1].0, RAIN_LEVEL_CHANGE);
+        assert!((events[1].1 - 0.99).abs() < 1e-6);
+        assert_eq!(events[2].0, THUNDER_LEVEL_CHANGE);
+    }
+
+    #[test]
+    fn test_weather_round_trips_through_bytes() {
+        let weather = WorldWeather {
+            weather: Weather::Rain,
+            remaining: 12345,
+            rain_level: 0.5,
+            thunder_level: 0.0,
+        };
+        assert_eq!(WorldWeather::from_bytes(&weather.to_bytes()), Some(weather));
+        assert_eq!(WorldWeather::from_bytes(&[3; 13]), None);
+        assert_eq!(WorldWeather::from_bytes(&[1, 2, 3]), None);
+    }
+
+    #[test]
+    fn test_the_overworld_keeps_the_plain_key() {
+        assert_eq!(weather_key("overworld"), "weather");
+        assert_eq!(weather_key("the_nether"), "weather:the_nether");
+    }
+}